@@ -0,0 +1,114 @@
+use std::io::{self, Seek, SeekFrom, Write};
+
+// A writer for formats with a header describing what follows it (a record count, a root page)
+// The header can't be written until the body is, so its bytes are reserved as zeros up front and
+// written over in finish. That beats writing the body and then copying it all into a new file
+// behind the header, and a file that's never finished is easy to spot: its header is all zeros.
+//
+//   new:     [0 0 0 0 0 0 0 0]
+//   write:   [0 0 0 0 0 0 0 0][body ...................]
+//   finish:  [header.........][body ...................]
+pub struct HeaderWriter<W: Write + Seek> {
+    inner: W,
+    header_len: usize,
+    // bytes written so far, header included
+    position: u64,
+}
+
+impl<W: Write + Seek> HeaderWriter<W> {
+    // Writes header_len zeros, everything written after goes after them
+    pub fn new(mut inner: W, header_len: usize) -> io::Result<Self> {
+        inner.write_all(&vec![0; header_len])?;
+        Ok(HeaderWriter {
+            inner,
+            header_len,
+            position: header_len as u64,
+        })
+    }
+
+    // Where the next byte written will go, counting the header
+    pub fn position(&self) -> u64 {
+        self.position
+    }
+
+    // Writes the header over the reserved bytes and hands back the inner writer
+    // A header shorter than what was reserved leaves the rest as zeros, a longer one is an error
+    // (it would overwrite the start of the body).
+    pub fn finish(mut self, header: &[u8]) -> io::Result<W> {
+        if header.len() > self.header_len {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "a {} byte header doesn't fit in {} reserved bytes",
+                    header.len(),
+                    self.header_len
+                ),
+            ));
+        }
+        self.inner.seek(SeekFrom::Start(0))?;
+        self.inner.write_all(header)?;
+        self.inner.seek(SeekFrom::Start(self.position))?;
+        self.inner.flush()?;
+        Ok(self.inner)
+    }
+}
+
+impl<W: Write + Seek> Write for HeaderWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.position += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+#[cfg(test)]
+mod testing {
+    use std::fs::{self, File};
+    use std::io::{BufWriter, Cursor};
+
+    use super::*;
+    use crate::{read_u64_at, WriteBe};
+
+    #[test]
+    fn patches_the_header() {
+        let mut writer = HeaderWriter::new(Cursor::new(Vec::new()), 8).unwrap();
+        assert_eq!(writer.position(), 8);
+        writer.write_all(b"body").unwrap();
+        assert_eq!(writer.position(), 12);
+
+        let bytes = writer.finish(&4u64.to_be_bytes()).unwrap().into_inner();
+        assert_eq!(bytes, b"\0\0\0\0\0\0\0\x04body");
+    }
+
+    #[test]
+    fn short_headers_leave_zeros_and_long_ones_fail() {
+        let writer = HeaderWriter::new(Cursor::new(Vec::new()), 4).unwrap();
+        assert_eq!(writer.finish(&[7]).unwrap().into_inner(), [7, 0, 0, 0]);
+
+        let writer = HeaderWriter::new(Cursor::new(Vec::new()), 4).unwrap();
+        let err = writer.finish(&[1; 5]).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn through_a_buffered_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("records");
+
+        let file = BufWriter::new(File::create(&path).unwrap());
+        let mut writer = HeaderWriter::new(file, 8).unwrap();
+        for i in 0..1000u32 {
+            writer.write_be_u32(i).unwrap();
+        }
+        writer.finish(&1000u64.to_be_bytes()).unwrap();
+
+        let file = File::open(&path).unwrap();
+        assert_eq!(read_u64_at(&file, 0).unwrap(), 1000);
+        assert_eq!(fs::metadata(&path).unwrap().len(), 8 + 4000);
+        assert_eq!(crate::read_u32_at(&file, 8 + 4 * 999).unwrap(), 999);
+    }
+}
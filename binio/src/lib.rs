@@ -0,0 +1,7 @@
+mod header;
+mod read;
+mod write;
+
+pub use header::HeaderWriter;
+pub use read::{be_u16, be_u32, be_u64, read_u16_at, read_u32_at, read_u64_at, read_u8_at, ReadBe};
+pub use write::WriteBe;
@@ -0,0 +1,119 @@
+use std::io::{self, Read};
+use std::os::unix::fs::FileExt;
+
+// Big endian integers at a byte offset in a file, without moving a cursor
+// These are pread calls, so several threads can read the same File at once, and a lookup that
+// only needs a few bytes only reads a few bytes.
+pub fn read_u8_at(file: &impl FileExt, offset: u64) -> io::Result<u8> {
+    Ok(u8::from_be_bytes(read_array_at(file, offset)?))
+}
+
+pub fn read_u16_at(file: &impl FileExt, offset: u64) -> io::Result<u16> {
+    Ok(u16::from_be_bytes(read_array_at(file, offset)?))
+}
+
+pub fn read_u32_at(file: &impl FileExt, offset: u64) -> io::Result<u32> {
+    Ok(u32::from_be_bytes(read_array_at(file, offset)?))
+}
+
+pub fn read_u64_at(file: &impl FileExt, offset: u64) -> io::Result<u64> {
+    Ok(u64::from_be_bytes(read_array_at(file, offset)?))
+}
+
+fn read_array_at<const N: usize>(file: &impl FileExt, offset: u64) -> io::Result<[u8; N]> {
+    let mut buf = [0; N];
+    file.read_exact_at(&mut buf, offset)?;
+    Ok(buf)
+}
+
+// The same for bytes that are already in memory (a page, a memory map)
+// Panics if the integer runs past the end of bytes, like indexing does.
+pub fn be_u16(bytes: &[u8], at: usize) -> u16 {
+    u16::from_be_bytes(array(bytes, at))
+}
+
+pub fn be_u32(bytes: &[u8], at: usize) -> u32 {
+    u32::from_be_bytes(array(bytes, at))
+}
+
+pub fn be_u64(bytes: &[u8], at: usize) -> u64 {
+    u64::from_be_bytes(array(bytes, at))
+}
+
+fn array<const N: usize>(bytes: &[u8], at: usize) -> [u8; N] {
+    bytes[at..at + N].try_into().expect("slice is N long")
+}
+
+// Big endian integers from a stream, the other half of WriteBe
+pub trait ReadBe: Read {
+    fn read_be_u8(&mut self) -> io::Result<u8> {
+        Ok(u8::from_be_bytes(read_array(self)?))
+    }
+
+    fn read_be_u16(&mut self) -> io::Result<u16> {
+        Ok(u16::from_be_bytes(read_array(self)?))
+    }
+
+    fn read_be_u32(&mut self) -> io::Result<u32> {
+        Ok(u32::from_be_bytes(read_array(self)?))
+    }
+
+    fn read_be_u64(&mut self) -> io::Result<u64> {
+        Ok(u64::from_be_bytes(read_array(self)?))
+    }
+}
+
+impl<R: Read + ?Sized> ReadBe for R {}
+
+fn read_array<const N: usize, R: Read + ?Sized>(rdr: &mut R) -> io::Result<[u8; N]> {
+    let mut buf = [0; N];
+    rdr.read_exact(&mut buf)?;
+    Ok(buf)
+}
+
+#[cfg(test)]
+mod testing {
+    use std::fs;
+
+    use super::*;
+
+    #[test]
+    fn reads_at_offsets() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("ints");
+        fs::write(
+            &path,
+            [0xAB, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08],
+        )
+        .unwrap();
+        let file = fs::File::open(&path).unwrap();
+
+        assert_eq!(read_u8_at(&file, 0).unwrap(), 0xAB);
+        assert_eq!(read_u16_at(&file, 1).unwrap(), 0x0102);
+        assert_eq!(read_u32_at(&file, 1).unwrap(), 0x0102_0304);
+        assert_eq!(read_u64_at(&file, 1).unwrap(), 0x0102_0304_0506_0708);
+        let err = read_u64_at(&file, 2).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+
+        let bytes = fs::read(&path).unwrap();
+        assert_eq!(be_u16(&bytes, 7), 0x0708);
+        assert_eq!(be_u32(&bytes, 0), 0xAB01_0203);
+        assert_eq!(be_u64(&bytes, 1), 0x0102_0304_0506_0708);
+    }
+
+    #[test]
+    fn reads_from_streams() {
+        let mut rdr = &[0xAB, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09][..];
+        assert_eq!(rdr.read_be_u8().unwrap(), 0xAB);
+        assert_eq!(rdr.read_be_u16().unwrap(), 0x0102);
+        assert_eq!(rdr.read_be_u32().unwrap(), 0x0304_0506);
+        let err = rdr.read_be_u64().unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    #[should_panic]
+    fn slices_panic_past_the_end() {
+        be_u32(&[1, 2, 3], 0);
+    }
+}
@@ -0,0 +1,37 @@
+use std::io::{self, Write};
+
+// Big endian integer writes for anything Write, the other half of ReadBe and read_*_at
+pub trait WriteBe: Write {
+    fn write_be_u8(&mut self, value: u8) -> io::Result<()> {
+        self.write_all(&[value])
+    }
+
+    fn write_be_u16(&mut self, value: u16) -> io::Result<()> {
+        self.write_all(&value.to_be_bytes())
+    }
+
+    fn write_be_u32(&mut self, value: u32) -> io::Result<()> {
+        self.write_all(&value.to_be_bytes())
+    }
+
+    fn write_be_u64(&mut self, value: u64) -> io::Result<()> {
+        self.write_all(&value.to_be_bytes())
+    }
+}
+
+impl<W: Write + ?Sized> WriteBe for W {}
+
+#[cfg(test)]
+mod testing {
+    use super::*;
+
+    #[test]
+    fn writes_big_endian() {
+        let mut bytes = Vec::new();
+        bytes.write_be_u8(1).unwrap();
+        bytes.write_be_u16(0x0203).unwrap();
+        bytes.write_be_u32(0x0405_0607).unwrap();
+        bytes.write_be_u64(0x0809_0A0B_0C0D_0E0F).unwrap();
+        assert_eq!(bytes, (1..=15).collect::<Vec<u8>>());
+    }
+}
@@ -0,0 +1,134 @@
+use super::BloomFilter;
+
+impl BloomFilter {
+    // Checks many items at once, same answers as calling check on each
+    // With the nightly only `simd` feature every item's hashes are computed together, one seed
+    // per lane, and the words holding their bits are gathered in one go. Without it this is a
+    // plain loop over check.
+    pub fn check_batch<T: AsRef<[u8]>>(&self, items: &[T]) -> Vec<bool> {
+        items
+            .iter()
+            .map(|item| self.check_hashes(item.as_ref()))
+            .collect()
+    }
+
+    #[cfg(not(feature = "simd"))]
+    fn check_hashes(&self, item: &[u8]) -> bool {
+        self.check(item)
+    }
+
+    #[cfg(feature = "simd")]
+    fn check_hashes(&self, item: &[u8]) -> bool {
+        use simd::{murmur3_32_lanes, LANES};
+        use std::simd::prelude::*;
+
+        let size = Simd::splat(self.size as u64);
+        (0..self.hash_count).step_by(LANES).all(|first| {
+            let seeds = Simd::from_array(std::array::from_fn(|lane| (first + lane) as u32));
+            let active = Mask::<isize, LANES>::from_array(std::array::from_fn(|lane| {
+                first + lane < self.hash_count
+            }));
+
+            let idx = murmur3_32_lanes(item, seeds).cast::<u64>() % size;
+            let words = Simd::gather_select(
+                &self.bit_array,
+                active,
+                (idx / Simd::splat(64)).cast::<usize>(),
+                Simd::splat(u64::MAX),
+            );
+            let bits = (words >> (idx % Simd::splat(64))) & Simd::splat(1);
+            bits.simd_eq(Simd::splat(1)).all()
+        })
+    }
+}
+
+// murmur3_32 of the same bytes under LANES seeds at once
+// Every lane reads the same blocks, so the block mixing is done once and only the running hash
+// (which starts from the seed) is a vector.
+#[cfg(feature = "simd")]
+mod simd {
+    use std::simd::prelude::*;
+
+    pub const LANES: usize = 8;
+
+    const C1: u32 = 0xcc9e_2d51;
+    const C2: u32 = 0x1b87_3593;
+
+    fn mix_block(k: u32) -> u32 {
+        k.wrapping_mul(C1).rotate_left(15).wrapping_mul(C2)
+    }
+
+    fn rotate_left(h: Simd<u32, LANES>, n: u32) -> Simd<u32, LANES> {
+        (h << Simd::splat(n)) | (h >> Simd::splat(32 - n))
+    }
+
+    pub fn murmur3_32_lanes(bytes: &[u8], seeds: Simd<u32, LANES>) -> Simd<u32, LANES> {
+        let mut h = seeds;
+        let mut blocks = bytes.chunks_exact(4);
+        for block in blocks.by_ref() {
+            let k = mix_block(u32::from_le_bytes([block[0], block[1], block[2], block[3]]));
+            h = rotate_left(h ^ Simd::splat(k), 13) * Simd::splat(5) + Simd::splat(0xe654_6b64);
+        }
+
+        let tail = blocks.remainder();
+        if !tail.is_empty() {
+            let k = tail
+                .iter()
+                .rev()
+                .fold(0_u32, |k, byte| (k << 8) | *byte as u32);
+            h ^= Simd::splat(mix_block(k));
+        }
+
+        h ^= Simd::splat(bytes.len() as u32);
+        h ^= h >> Simd::splat(16);
+        h *= Simd::splat(0x85eb_ca6b);
+        h ^= h >> Simd::splat(13);
+        h *= Simd::splat(0xc2b2_ae35);
+        h ^ (h >> Simd::splat(16))
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::bloom::BloomFilter;
+
+        #[test]
+        fn lanes_match_scalar_murmur() {
+            let seeds = Simd::from_array(std::array::from_fn(|lane| lane as u32 * 7));
+            for len in 0..=13 {
+                let bytes: Vec<u8> = (0..len)
+                    .map(|b: u8| b.wrapping_mul(31).wrapping_add(5))
+                    .collect();
+                let hashes = murmur3_32_lanes(&bytes, seeds).to_array();
+                for (lane, seed) in seeds.to_array().into_iter().enumerate() {
+                    assert_eq!(hashes[lane], BloomFilter::hash(&bytes, seed));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bloom::words::get_words;
+
+    use super::*;
+
+    #[test]
+    fn batch_agrees_with_check() {
+        let words = get_words(4000);
+        // 20 hashes, so more than one chunk of lanes with a partial last one
+        for bloom in [
+            BloomFilter::new(0.05, 2000).unwrap(),
+            BloomFilter::with_size(10_007, 20).unwrap(),
+        ] {
+            let mut bloom = bloom;
+            for word in words.iter().take(2000) {
+                bloom.add_item(word);
+            }
+
+            let expected: Vec<bool> = words.iter().map(|word| bloom.check(word)).collect();
+            assert_eq!(bloom.check_batch(&words), expected);
+        }
+    }
+}
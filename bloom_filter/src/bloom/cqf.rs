@@ -0,0 +1,517 @@
+use std::collections::VecDeque;
+
+use murmur3::murmur3_x64_128;
+
+use super::{read_u64, BloomError, BloomFilter};
+
+// Max ratio of distinct fingerprints to canonical slots `new` sizes for
+const MAX_LOAD_FACTOR: f64 = 0.9;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct Slot {
+    remainder: u64,
+    count: u64,
+}
+
+// A counting quotient filter (Bender et al. / Pandey et al.)
+// Every item is hashed to a fingerprint of q + r bits. The high q bits (the quotient) pick the
+// item's canonical slot, the low r bits (the remainder) are what actually gets stored. Remainders
+// sharing a quotient are kept sorted in a contiguous "run", and runs are pushed right whenever
+// their canonical slot is taken, forming "clusters". Three metadata bits per slot are enough to
+// find any quotient's run again:
+//  occupied[i] => some stored fingerprint has quotient i
+//  continuation[i] => slot i continues the run started in a previous slot
+//  shifted[i] => the remainder in slot i isn't in its canonical slot
+// Unlike a bloom filter the fingerprints can be recovered, which is what makes counting,
+// deletion, merging and enumeration possible.
+// Note: the real CQF packs counters into remainder slots with a variable length encoding,
+// here every slot just carries its own counter.
+#[derive(Debug)]
+pub struct CountingQuotientFilter {
+    q_bits: u32,
+    r_bits: u32,
+    slots: Vec<Slot>,
+    occupied: Vec<bool>,
+    continuation: Vec<bool>,
+    shifted: Vec<bool>,
+    n_distinct: usize,
+    n_total: u64,
+}
+
+impl CountingQuotientFilter {
+    pub fn new(fp_rate: f64, n_items: usize) -> Result<Self, BloomError> {
+        BloomFilter::validate_params(fp_rate, n_items)?;
+        let q_bits = ((n_items as f64 / MAX_LOAD_FACTOR).log2().ceil() as u32).max(1);
+        let r_bits = ((1_f64 / fp_rate).log2().ceil() as u32).max(1);
+        Self::with_bits(q_bits, r_bits)
+    }
+
+    pub fn with_bits(q_bits: u32, r_bits: u32) -> Result<Self, BloomError> {
+        if q_bits == 0 || r_bits == 0 || q_bits + r_bits > 64 || q_bits >= usize::BITS {
+            return Err(BloomError::InvalidParams(format!(
+                "quotient ({q_bits} bits) and remainder ({r_bits} bits) must both be non-empty \
+                 and fit in 64 bits together"
+            )));
+        }
+
+        // the table doesn't wrap around, instead runs can spill into a few extra slots at the end
+        let n_canonical = 1_usize << q_bits;
+        let n_slots = n_canonical + 10 * (n_canonical as f64).sqrt().ceil() as usize;
+
+        Ok(CountingQuotientFilter {
+            q_bits,
+            r_bits,
+            slots: vec![Slot::default(); n_slots],
+            occupied: vec![false; n_slots],
+            continuation: vec![false; n_slots],
+            shifted: vec![false; n_slots],
+            n_distinct: 0,
+            n_total: 0,
+        })
+    }
+
+    // Number of distinct fingerprints stored
+    pub fn len(&self) -> usize {
+        self.n_distinct
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.n_distinct == 0
+    }
+
+    // Sum of all counts
+    pub fn total(&self) -> u64 {
+        self.n_total
+    }
+
+    // Fails with BloomError::Full if there's no room left, in which case the filter is unchanged
+    pub fn insert(&mut self, item: impl AsRef<[u8]>, count: u64) -> Result<(), BloomError> {
+        let fingerprint = self.fingerprint(item);
+        self.insert_fingerprint(fingerprint, count)
+    }
+
+    pub fn count(&self, item: impl AsRef<[u8]>) -> u64 {
+        self.count_fingerprint(self.fingerprint(item))
+    }
+
+    pub fn check(&self, item: impl AsRef<[u8]>) -> bool {
+        self.count(item) > 0
+    }
+
+    // Removes up to `count` occurences of the item, returning how many were actually removed
+    pub fn remove(&mut self, item: impl AsRef<[u8]>, count: u64) -> u64 {
+        let fingerprint = self.fingerprint(item);
+        self.remove_fingerprint(fingerprint, count)
+    }
+
+    // Adds all of other's counts to self
+    // Both filters must have been created with the same parameters.
+    // If self runs out of room part way through it's left with the counts merged so far.
+    pub fn merge(&mut self, other: &CountingQuotientFilter) -> Result<(), BloomError> {
+        if self.q_bits != other.q_bits || self.r_bits != other.r_bits {
+            return Err(BloomError::Incompatible(format!(
+                "{}+{} vs {}+{} fingerprint bits",
+                self.q_bits, self.r_bits, other.q_bits, other.r_bits
+            )));
+        }
+
+        other
+            .iter()
+            .try_for_each(|(fingerprint, count)| self.insert_fingerprint(fingerprint, count))
+    }
+
+    // Enumerates (fingerprint, count) pairs in ascending fingerprint order
+    pub fn iter(&self) -> impl Iterator<Item = (u64, u64)> + '_ {
+        let (entries, _) = self.decode(0, true);
+        entries
+            .into_iter()
+            .map(|(quotient, slot)| (self.join(quotient, slot.remainder), slot.count))
+    }
+
+    // Serialized layout, all little endian:
+    //   q_bits: u64 | r_bits: u64 | n_distinct: u64 | n_distinct * (fingerprint: u64 | count: u64)
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(24 + self.n_distinct * 16);
+        bytes.extend_from_slice(&(self.q_bits as u64).to_le_bytes());
+        bytes.extend_from_slice(&(self.r_bits as u64).to_le_bytes());
+        bytes.extend_from_slice(&(self.n_distinct as u64).to_le_bytes());
+        for (fingerprint, count) in self.iter() {
+            bytes.extend_from_slice(&fingerprint.to_le_bytes());
+            bytes.extend_from_slice(&count.to_le_bytes());
+        }
+        bytes
+    }
+
+    pub fn from_bytes(mut bytes: &[u8]) -> Result<Self, BloomError> {
+        let q_bits = read_u64(&mut bytes)?;
+        let r_bits = read_u64(&mut bytes)?;
+        let (q_bits, r_bits) = match (u32::try_from(q_bits), u32::try_from(r_bits)) {
+            (Ok(q_bits), Ok(r_bits)) => (q_bits, r_bits),
+            _ => return Err(BloomError::Corrupt("fingerprint size out of range".into())),
+        };
+        let mut cqf =
+            Self::with_bits(q_bits, r_bits).map_err(|e| BloomError::Corrupt(e.to_string()))?;
+
+        let n_distinct = read_u64(&mut bytes)?;
+        for _ in 0..n_distinct {
+            let fingerprint = read_u64(&mut bytes)?;
+            let count = read_u64(&mut bytes)?;
+            if fingerprint > Self::mask(q_bits + r_bits) || count == 0 {
+                return Err(BloomError::Corrupt(format!(
+                    "invalid entry ({fingerprint}, {count})"
+                )));
+            }
+            cqf.insert_fingerprint(fingerprint, count)?;
+        }
+
+        if !bytes.is_empty() || cqf.len() as u64 != n_distinct {
+            return Err(BloomError::Corrupt("entries don't match the header".into()));
+        }
+
+        Ok(cqf)
+    }
+
+    pub fn fingerprint(&self, item: impl AsRef<[u8]>) -> u64 {
+        // murmur3 reads through io::Read, which can't fail for a byte slice
+        let digest = murmur3_x64_128(&mut item.as_ref(), 0).unwrap_or_default() as u64;
+        digest & Self::mask(self.q_bits + self.r_bits)
+    }
+
+    pub fn insert_fingerprint(&mut self, fingerprint: u64, count: u64) -> Result<(), BloomError> {
+        if count == 0 {
+            return Ok(());
+        }
+
+        let (quotient, remainder) = self.split(fingerprint);
+        let (start, mut entries, end) = self.region(quotient);
+
+        let idx = entries.partition_point(|(q, slot)| (*q, slot.remainder) < (quotient, remainder));
+        match entries.get_mut(idx) {
+            Some((q, slot)) if *q == quotient && slot.remainder == remainder => {
+                slot.count = slot.count.saturating_add(count);
+            }
+            _ => {
+                let slot = Slot { remainder, count };
+                entries.insert(idx, (quotient, slot));
+                if Self::encoded_end(start, &entries) > self.slots.len() {
+                    return Err(BloomError::Full);
+                }
+                self.n_distinct += 1;
+            }
+        }
+
+        self.n_total = self.n_total.saturating_add(count);
+        self.encode(start, end, &entries);
+        Ok(())
+    }
+
+    pub fn count_fingerprint(&self, fingerprint: u64) -> u64 {
+        let (quotient, remainder) = self.split(fingerprint);
+        if !self.occupied[quotient] {
+            return 0;
+        }
+
+        // runs are sorted, so we can stop as soon as we've walked past the remainder
+        let mut s = self.run_start(quotient);
+        loop {
+            let slot = self.slots[s];
+            if slot.remainder >= remainder {
+                return if slot.remainder == remainder {
+                    slot.count
+                } else {
+                    0
+                };
+            }
+
+            s += 1;
+            if s == self.slots.len() || !self.continuation[s] {
+                return 0;
+            }
+        }
+    }
+
+    pub fn remove_fingerprint(&mut self, fingerprint: u64, count: u64) -> u64 {
+        let (quotient, remainder) = self.split(fingerprint);
+        if !self.occupied[quotient] {
+            return 0;
+        }
+
+        let (start, mut entries, end) = self.region(quotient);
+        let Some(idx) = entries
+            .iter()
+            .position(|(q, slot)| *q == quotient && slot.remainder == remainder)
+        else {
+            return 0;
+        };
+
+        let removed = count.min(entries[idx].1.count);
+        entries[idx].1.count -= removed;
+        if entries[idx].1.count == 0 {
+            entries.remove(idx);
+            self.n_distinct -= 1;
+        }
+
+        self.n_total -= removed;
+        self.encode(start, end, &entries);
+        removed
+    }
+
+    // Finds the slot where the run for an occupied quotient starts
+    fn run_start(&self, quotient: usize) -> usize {
+        // walk back to the start of the cluster, the first slot there holds an unshifted run
+        let mut b = quotient;
+        while self.shifted[b] {
+            b -= 1;
+        }
+
+        // then walk forward run by run until we reach the run belonging to quotient
+        let mut s = b;
+        while b != quotient {
+            s += 1;
+            while self.continuation[s] {
+                s += 1;
+            }
+
+            b += 1;
+            while !self.occupied[b] {
+                b += 1;
+            }
+        }
+
+        s
+    }
+
+    // Decodes the cluster(s) a fingerprint with the given quotient would live in, returning
+    // the first slot, the entries and the first empty slot after them.
+    fn region(&self, quotient: usize) -> (usize, Vec<(usize, Slot)>, usize) {
+        let mut start = quotient;
+        while self.shifted[start] {
+            start -= 1;
+        }
+
+        let (entries, end) = self.decode(start, false);
+        (start, entries, end)
+    }
+
+    // Decodes every stored (quotient, slot) from start up to the next empty slot, or up to the
+    // end of the table when skip_empty is set
+    // Start must be the beginning of a cluster.
+    fn decode(&self, start: usize, skip_empty: bool) -> (Vec<(usize, Slot)>, usize) {
+        let mut entries = vec![];
+        let mut quotients = VecDeque::new();
+        let mut quotient = 0;
+        let mut i = start;
+
+        while i < self.slots.len() {
+            if self.occupied[i] {
+                quotients.push_back(i);
+            }
+
+            if !self.continuation[i] {
+                match quotients.pop_front() {
+                    Some(q) => quotient = q,
+                    None if skip_empty => {
+                        i += 1;
+                        continue;
+                    }
+                    None => break,
+                }
+            }
+
+            entries.push((quotient, self.slots[i]));
+            i += 1;
+        }
+
+        (entries, i)
+    }
+
+    fn encoded_end(start: usize, entries: &[(usize, Slot)]) -> usize {
+        entries
+            .iter()
+            .fold(start, |pos, (quotient, _)| pos.max(*quotient) + 1)
+    }
+
+    // Clears slots [start, end) and lays entries back out from start
+    // Entries must be sorted by (quotient, remainder).
+    fn encode(&mut self, start: usize, end: usize, entries: &[(usize, Slot)]) {
+        for i in start..end {
+            self.slots[i] = Slot::default();
+            self.occupied[i] = false;
+            self.continuation[i] = false;
+            self.shifted[i] = false;
+        }
+
+        let mut pos = start;
+        let mut prev_quotient = None;
+        for (quotient, slot) in entries {
+            pos = pos.max(*quotient);
+            self.slots[pos] = *slot;
+            self.occupied[*quotient] = true;
+            self.shifted[pos] = pos != *quotient;
+            self.continuation[pos] = prev_quotient == Some(*quotient);
+            prev_quotient = Some(*quotient);
+            pos += 1;
+        }
+    }
+
+    fn split(&self, fingerprint: u64) -> (usize, u64) {
+        let quotient = (fingerprint >> self.r_bits) & Self::mask(self.q_bits);
+        (quotient as usize, fingerprint & Self::mask(self.r_bits))
+    }
+
+    fn join(&self, quotient: usize, remainder: u64) -> u64 {
+        ((quotient as u64) << self.r_bits) | remainder
+    }
+
+    fn mask(bits: u32) -> u64 {
+        u64::MAX >> (64 - bits)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use crate::bloom::words::get_words;
+    use rand::Rng;
+
+    use super::*;
+
+    #[test]
+    fn can_insert_count_and_remove() {
+        let mut cqf = CountingQuotientFilter::new(0.01, 100).unwrap();
+        assert!(cqf.insert("foo", 1).is_ok());
+        assert!(cqf.insert("foo", 2).is_ok());
+        assert!(cqf.insert("bar", 1).is_ok());
+        assert_eq!(cqf.count("foo"), 3);
+        assert_eq!(cqf.count("bar"), 1);
+        assert_eq!(cqf.len(), 2);
+        assert_eq!(cqf.total(), 4);
+
+        assert_eq!(cqf.remove("foo", 2), 2);
+        assert_eq!(cqf.count("foo"), 1);
+        assert_eq!(cqf.remove("foo", 5), 1);
+        assert!(!cqf.check("foo"));
+        assert!(cqf.check("bar"));
+        assert_eq!(cqf.len(), 1);
+        assert_eq!(cqf.total(), 1);
+    }
+
+    #[test]
+    fn has_no_false_negatives() {
+        let words = get_words(10000);
+        let mut cqf = CountingQuotientFilter::new(0.01, words.len()).unwrap();
+        for word in words.iter() {
+            assert!(cqf.insert(word, 1).is_ok());
+        }
+
+        for word in words.iter() {
+            assert!(cqf.count(word) >= 1);
+        }
+    }
+
+    #[test]
+    fn can_merge_filters() {
+        let mut a = CountingQuotientFilter::new(0.01, 100).unwrap();
+        let mut b = CountingQuotientFilter::new(0.01, 100).unwrap();
+        a.insert("foo", 1).unwrap();
+        b.insert("foo", 2).unwrap();
+        b.insert("bar", 1).unwrap();
+
+        assert!(a.merge(&b).is_ok());
+        assert_eq!(a.count("foo"), 3);
+        assert_eq!(a.count("bar"), 1);
+    }
+
+    #[test]
+    fn cannot_merge_incompatible_filters() {
+        let mut a = CountingQuotientFilter::with_bits(8, 8).unwrap();
+        let b = CountingQuotientFilter::with_bits(8, 9).unwrap();
+        assert!(matches!(a.merge(&b), Err(BloomError::Incompatible(_))));
+    }
+
+    #[test]
+    fn rejects_invalid_params() {
+        assert!(CountingQuotientFilter::new(0_f64, 10).is_err());
+        assert!(CountingQuotientFilter::new(0.01, 0).is_err());
+        assert!(CountingQuotientFilter::with_bits(0, 8).is_err());
+        assert!(CountingQuotientFilter::with_bits(32, 33).is_err());
+    }
+
+    #[test]
+    fn can_round_trip_through_bytes() {
+        let mut cqf = CountingQuotientFilter::new(0.01, 100).unwrap();
+        for (i, word) in get_words(100).into_iter().enumerate() {
+            cqf.insert(word, i as u64 + 1).unwrap();
+        }
+
+        let bytes = cqf.to_bytes();
+        let restored = CountingQuotientFilter::from_bytes(&bytes).unwrap();
+        assert_eq!(
+            restored.iter().collect::<Vec<_>>(),
+            cqf.iter().collect::<Vec<_>>()
+        );
+        assert_eq!(restored.total(), cqf.total());
+        assert!(CountingQuotientFilter::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+    }
+
+    #[test]
+    fn can_enumerate_fingerprints() {
+        let mut cqf = CountingQuotientFilter::with_bits(4, 4).unwrap();
+        cqf.insert_fingerprint(0x53, 2).unwrap();
+        cqf.insert_fingerprint(0x51, 1).unwrap();
+        cqf.insert_fingerprint(0x12, 7).unwrap();
+
+        let entries: Vec<_> = cqf.iter().collect();
+        assert_eq!(entries, vec![(0x12, 7), (0x51, 1), (0x53, 2)]);
+    }
+
+    #[test]
+    fn refuses_inserts_when_full() {
+        let mut cqf = CountingQuotientFilter::with_bits(1, 8).unwrap();
+        let capacity = cqf.slots.len() as u64;
+        for fingerprint in 0..capacity {
+            assert!(cqf.insert_fingerprint(fingerprint, 1).is_ok());
+        }
+
+        assert_eq!(cqf.insert_fingerprint(capacity, 1), Err(BloomError::Full));
+        assert_eq!(cqf.len() as u64, capacity);
+        assert!(cqf.insert_fingerprint(0, 1).is_ok());
+    }
+
+    #[test]
+    fn matches_exact_counts_under_random_operations() {
+        // small quotients force long clusters, so this exercises the shifting logic
+        let mut rng = rand::thread_rng();
+        let mut cqf = CountingQuotientFilter::with_bits(6, 4).unwrap();
+        let mut oracle: BTreeMap<u64, u64> = BTreeMap::new();
+
+        for _ in 0..20000 {
+            let fingerprint = rng.gen_range(0..1 << 10);
+            let count = rng.gen_range(1..4);
+            if rng.gen_bool(0.6) && oracle.len() < 50 {
+                assert!(cqf.insert_fingerprint(fingerprint, count).is_ok());
+                *oracle.entry(fingerprint).or_default() += count;
+            } else {
+                let expected = oracle.get(&fingerprint).map_or(0, |c| count.min(*c));
+                assert_eq!(cqf.remove_fingerprint(fingerprint, count), expected);
+                if let Some(c) = oracle.get_mut(&fingerprint) {
+                    *c -= expected;
+                    if *c == 0 {
+                        oracle.remove(&fingerprint);
+                    }
+                }
+            }
+
+            let fingerprint = rng.gen_range(0..1 << 10);
+            assert_eq!(
+                cqf.count_fingerprint(fingerprint),
+                oracle.get(&fingerprint).copied().unwrap_or(0)
+            );
+        }
+
+        let entries: Vec<_> = cqf.iter().collect();
+        let expected: Vec<_> = oracle.into_iter().collect();
+        assert_eq!(entries, expected);
+    }
+}
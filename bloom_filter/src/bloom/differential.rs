@@ -0,0 +1,262 @@
+// Differential tests of every filter against an exact set
+// Random insert/check/remove sequences are run through a filter and a HashMap of counts side by
+// side. A filter may say yes to things the oracle doesn't have (at roughly its fp rate) but must
+// never say no to anything the oracle has.
+use std::collections::{HashMap, HashSet};
+
+use proptest::prelude::*;
+
+use super::{
+    max_false_positives, max_fp_rate, BloomFilter, CountingQuotientFilter, PagedBloomFilter,
+    PartitionedMembership, StripedCountingFilter,
+};
+
+// The common surface of all the filters, removal is only called on filters that support it and
+// only for items the oracle says were inserted
+trait Membership {
+    fn insert(&mut self, item: &[u8]);
+    fn contains(&mut self, item: &[u8]) -> bool;
+    fn remove(&mut self, _item: &[u8]) {}
+    fn supports_remove(&self) -> bool {
+        false
+    }
+}
+
+impl Membership for BloomFilter {
+    fn insert(&mut self, item: &[u8]) {
+        self.add_item(item);
+    }
+
+    fn contains(&mut self, item: &[u8]) -> bool {
+        self.check(item)
+    }
+}
+
+// check_batch has its own (possibly simd) code path, so it gets its own variant
+struct Batched(BloomFilter);
+
+impl Membership for Batched {
+    fn insert(&mut self, item: &[u8]) {
+        self.0.add_item(item);
+    }
+
+    fn contains(&mut self, item: &[u8]) -> bool {
+        self.0.check_batch(&[item])[0]
+    }
+}
+
+impl Membership for StripedCountingFilter {
+    fn insert(&mut self, item: &[u8]) {
+        self.add_item(item);
+    }
+
+    fn contains(&mut self, item: &[u8]) -> bool {
+        self.check(item)
+    }
+
+    fn remove(&mut self, item: &[u8]) {
+        assert!(self.remove_item(item));
+    }
+
+    fn supports_remove(&self) -> bool {
+        true
+    }
+}
+
+impl Membership for CountingQuotientFilter {
+    fn insert(&mut self, item: &[u8]) {
+        CountingQuotientFilter::insert(self, item, 1).unwrap();
+    }
+
+    fn contains(&mut self, item: &[u8]) -> bool {
+        self.check(item)
+    }
+
+    fn remove(&mut self, item: &[u8]) {
+        assert_eq!(CountingQuotientFilter::remove(self, item, 1), 1);
+    }
+
+    fn supports_remove(&self) -> bool {
+        true
+    }
+}
+
+struct Paged {
+    filter: PagedBloomFilter,
+    // the file goes away with the dir
+    _dir: tempfile::TempDir,
+}
+
+impl Membership for Paged {
+    fn insert(&mut self, item: &[u8]) {
+        self.filter.add_item(item).unwrap();
+    }
+
+    fn contains(&mut self, item: &[u8]) -> bool {
+        self.filter.check(item).unwrap()
+    }
+}
+
+// Items go to one of two partitions by the parity of their bytes
+impl Membership for PartitionedMembership<u8> {
+    fn insert(&mut self, item: &[u8]) {
+        PartitionedMembership::insert(self, &partition_of(item), item).unwrap();
+    }
+
+    fn contains(&mut self, item: &[u8]) -> bool {
+        PartitionedMembership::contains(self, &partition_of(item), item)
+    }
+}
+
+fn partition_of(item: &[u8]) -> u8 {
+    item.iter().fold(0, |parity, b| parity ^ b) % 2
+}
+
+const VARIANTS: [&str; 6] = ["bloom", "batched", "striped", "cqf", "paged", "partitioned"];
+
+fn build(variant: &str, fp_rate: f64, n_items: usize) -> Box<dyn Membership> {
+    match variant {
+        "bloom" => Box::new(BloomFilter::new(fp_rate, n_items).unwrap()),
+        "batched" => Box::new(Batched(BloomFilter::new(fp_rate, n_items).unwrap())),
+        "striped" => Box::new(StripedCountingFilter::new(fp_rate, n_items, 4).unwrap()),
+        "cqf" => Box::new(CountingQuotientFilter::new(fp_rate, n_items).unwrap()),
+        "paged" => {
+            let dir = tempfile::tempdir().unwrap();
+            let path = dir.path().join("filter.bloom");
+            Box::new(Paged {
+                filter: PagedBloomFilter::create(path, fp_rate, n_items, 2).unwrap(),
+                _dir: dir,
+            })
+        }
+        "partitioned" => {
+            // enough bytes for the requested fp rate, split evenly between the two partitions
+            let bits = BloomFilter::get_size(fp_rate, n_items);
+            let per_partition = n_items.div_ceil(2);
+            Box::new(
+                PartitionedMembership::new(
+                    bits.div_ceil(8),
+                    [(0, per_partition), (1, per_partition)],
+                )
+                .unwrap(),
+            )
+        }
+        _ => unreachable!("unknown variant {variant}"),
+    }
+}
+
+// The most false positives a variant built by build(variant, fp_rate, inserted.len()) should
+// say yes to out of probes, going by the fp_rate it was asked for
+fn max_fps(variant: &str, fp_rate: f64, inserted: &HashSet<Vec<u8>>, probes: &[Vec<u8>]) -> f64 {
+    let n_items = inserted.len();
+    match variant {
+        // a probe is a false positive when it lands in an occupied slot with the same remainder,
+        // the remainder alone is enough bits for fp_rate and the load factor keeps it below that
+        "cqf" => max_false_positives(fp_rate, probes.len()),
+        // each partition is a bloom filter sized for half the items, holding however many items
+        // (and getting however many probes) actually went to it
+        "partitioned" => [0, 1]
+            .into_iter()
+            .map(|key| {
+                let in_partition = |item: &&Vec<u8>| partition_of(item) == key;
+                let n_inserted = inserted.iter().filter(in_partition).count();
+                let n_probes = probes.iter().filter(in_partition).count();
+                let max_rate = max_fp_rate(fp_rate, n_items.div_ceil(2), n_inserted);
+                max_false_positives(max_rate, n_probes)
+            })
+            .sum(),
+        _ => max_false_positives(max_fp_rate(fp_rate, n_items, n_items), probes.len()),
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Op {
+    Insert(Vec<u8>),
+    Check(Vec<u8>),
+    Remove(Vec<u8>),
+}
+
+// Items come from a tiny alphabet so sequences keep hitting the same items
+fn item() -> impl Strategy<Value = Vec<u8>> {
+    prop::collection::vec(0_u8..4, 0..6)
+}
+
+fn op() -> impl Strategy<Value = Op> {
+    prop_oneof![
+        3 => item().prop_map(Op::Insert),
+        3 => item().prop_map(Op::Check),
+        2 => item().prop_map(Op::Remove),
+    ]
+}
+
+proptest! {
+    #[test]
+    fn no_false_negatives(ops in prop::collection::vec(op(), 1..200)) {
+        for variant in VARIANTS {
+            let mut filter = build(variant, 0.01, 200);
+            let mut oracle: HashMap<Vec<u8>, usize> = HashMap::new();
+
+            for op in ops.iter() {
+                match op {
+                    Op::Insert(item) => {
+                        filter.insert(item);
+                        *oracle.entry(item.clone()).or_default() += 1;
+                    }
+                    Op::Check(item) => {
+                        if oracle.contains_key(item) {
+                            prop_assert!(filter.contains(item), "{variant} lost {item:?}");
+                        }
+                    }
+                    Op::Remove(item) => {
+                        if !filter.supports_remove() {
+                            continue;
+                        }
+                        if let Some(count) = oracle.get_mut(item) {
+                            filter.remove(item);
+                            *count -= 1;
+                            if *count == 0 {
+                                oracle.remove(item);
+                            }
+                        }
+                    }
+                }
+            }
+
+            for item in oracle.keys() {
+                prop_assert!(filter.contains(item), "{variant} lost {item:?}");
+            }
+        }
+    }
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(16))]
+
+    #[test]
+    fn fp_rate_stays_within_bounds(
+        fp_rate in 0.005_f64..0.1,
+        n_items in 100_usize..1000,
+        seed in any::<u64>(),
+    ) {
+        // inserted and probed items differ in their first byte, so they can never be equal
+        let inserted: HashSet<Vec<u8>> = (0..n_items as u64)
+            .map(|i| [&[0][..], &(seed ^ i).to_le_bytes()].concat())
+            .collect();
+        let probes: Vec<Vec<u8>> = (0..20_000_u64)
+            .map(|i| [&[1][..], &(seed ^ i).to_le_bytes()].concat())
+            .collect();
+
+        for variant in VARIANTS {
+            let mut filter = build(variant, fp_rate, n_items);
+            for item in inserted.iter() {
+                filter.insert(item);
+            }
+
+            let fps = probes.iter().filter(|item| filter.contains(item)).count();
+            let max_fps = max_fps(variant, fp_rate, &inserted, &probes);
+            prop_assert!(
+                fps as f64 <= max_fps,
+                "{variant}: {fps} false positives, at most {max_fps} expected at {fp_rate}"
+            );
+        }
+    }
+}
@@ -0,0 +1,38 @@
+use std::{error, fmt, io};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BloomError {
+    // Parameters that can't describe a usable filter (fp_rate outside (0, 1), zero items, ...)
+    InvalidParams(String),
+    // Two filters that don't map items to the same bits were combined
+    Incompatible(String),
+    // A fixed capacity filter has no room left
+    Full,
+    // A keyed filter was asked to add to a partition it doesn't have
+    UnknownPartition,
+    // Serialized bytes that don't describe a filter
+    Corrupt(String),
+    // Reading or writing a file backed filter failed
+    Io(String),
+}
+
+impl fmt::Display for BloomError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BloomError::InvalidParams(msg) => write!(f, "invalid filter parameters: {msg}"),
+            BloomError::Incompatible(msg) => write!(f, "filters aren't compatible ({msg})"),
+            BloomError::Full => write!(f, "filter is full"),
+            BloomError::UnknownPartition => write!(f, "no such partition"),
+            BloomError::Corrupt(msg) => write!(f, "corrupt filter bytes: {msg}"),
+            BloomError::Io(msg) => write!(f, "filter i/o failed: {msg}"),
+        }
+    }
+}
+
+impl error::Error for BloomError {}
+
+impl From<io::Error> for BloomError {
+    fn from(e: io::Error) -> Self {
+        BloomError::Io(e.to_string())
+    }
+}
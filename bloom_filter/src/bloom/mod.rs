@@ -1,26 +1,123 @@
+mod batch;
+mod cqf;
+#[cfg(test)]
+mod differential;
+mod error;
+mod paged;
+mod partitioned;
+mod striped;
+mod substring;
 mod words;
 
+pub use cqf::CountingQuotientFilter;
+pub use error::BloomError;
+pub use paged::PagedBloomFilter;
+pub use partitioned::{PartitionKey, PartitionedMembership};
+pub use striped::StripedCountingFilter;
+pub use substring::{SubstringFilter, WindowScanner};
+
 use murmur3::murmur3_32;
-use std::io::Result;
+use std::ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign};
+
+const WORD_BITS: usize = u64::BITS as usize;
 
-#[derive(Debug)]
-struct BloomFilter {
+// Bits are packed into u64 words so counting set bits is a popcount per word
+// Bits past `size` in the last word are always zero.
+#[derive(Debug, Clone)]
+pub struct BloomFilter {
     size: usize,
     hash_count: usize,
-    bit_array: Vec<bool>,
+    bit_array: Vec<u64>,
 }
 
 impl BloomFilter {
-    fn new(fp_rate: f64, n_items: usize) -> Self {
-        let size = Self::get_size(fp_rate, n_items);
-        let hash_count = Self::get_hash_count(size, n_items);
-        let bit_array = vec![false; size];
+    pub fn new(fp_rate: f64, n_items: usize) -> Result<Self, BloomError> {
+        let (size, hash_count) = Self::get_params(fp_rate, n_items)?;
+        Self::with_size(size, hash_count)
+    }
+
+    pub fn with_size(size: usize, hash_count: usize) -> Result<Self, BloomError> {
+        if size == 0 || hash_count == 0 {
+            return Err(BloomError::InvalidParams(format!(
+                "size ({size}) and hash count ({hash_count}) must be positive"
+            )));
+        }
+
+        let bit_array = vec![0; size.div_ceil(WORD_BITS)];
 
-        BloomFilter {
+        Ok(BloomFilter {
             size,
             hash_count,
             bit_array,
+        })
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    pub fn hash_count(&self) -> usize {
+        self.hash_count
+    }
+
+    // Serialized layout, all little endian:
+    //   size: u64 | hash_count: u64 | bit_array: [u64]
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(16 + self.bit_array.len() * 8);
+        bytes.extend_from_slice(&(self.size as u64).to_le_bytes());
+        bytes.extend_from_slice(&(self.hash_count as u64).to_le_bytes());
+        for word in self.bit_array.iter() {
+            bytes.extend_from_slice(&word.to_le_bytes());
+        }
+        bytes
+    }
+
+    pub fn from_bytes(mut bytes: &[u8]) -> Result<Self, BloomError> {
+        let size = read_u64(&mut bytes)? as usize;
+        let hash_count = read_u64(&mut bytes)? as usize;
+        // checked before allocating, so a garbage size can't ask for the moon
+        let expected = size.div_ceil(WORD_BITS).saturating_mul(8);
+        if bytes.len() != expected {
+            return Err(BloomError::Corrupt(format!(
+                "expected {expected} bytes of bits, got {}",
+                bytes.len()
+            )));
+        }
+        let mut bloom =
+            Self::with_size(size, hash_count).map_err(|e| BloomError::Corrupt(e.to_string()))?;
+        for word in bloom.bit_array.iter_mut() {
+            *word = read_u64(&mut bytes)?;
+        }
+
+        if !size.is_multiple_of(WORD_BITS)
+            && bloom.bit_array[size / WORD_BITS] >> (size % WORD_BITS) != 0
+        {
+            return Err(BloomError::Corrupt(
+                "bits set past the end of the filter".into(),
+            ));
+        }
+
+        Ok(bloom)
+    }
+
+    // Validates fp_rate and n_items and turns them into (size, hash_count)
+    fn get_params(fp_rate: f64, n_items: usize) -> Result<(usize, usize), BloomError> {
+        Self::validate_params(fp_rate, n_items)?;
+        let size = Self::get_size(fp_rate, n_items);
+        Ok((size, Self::get_hash_count(size, n_items)))
+    }
+
+    fn validate_params(fp_rate: f64, n_items: usize) -> Result<(), BloomError> {
+        if !(fp_rate > 0_f64 && fp_rate < 1_f64) {
+            return Err(BloomError::InvalidParams(format!(
+                "fp_rate must be between 0 and 1 (exclusive), got {fp_rate}"
+            )));
         }
+        if n_items == 0 {
+            return Err(BloomError::InvalidParams("n_items must be positive".into()));
+        }
+
+        Ok(())
     }
 
     fn get_size(fp_rate: f64, n_items: usize) -> usize {
@@ -31,26 +128,271 @@ impl BloomFilter {
         ((size as f64 / n_items as f64) * 2_f64.ln()).ceil() as usize
     }
 
-    fn add_item(&mut self, item: &str) {
+    // Items are anything that can be viewed as bytes (str, String, [u8], Vec<u8>, ...)
+    // The bytes are borrowed once and every hash reads the same slice, so neither adding nor
+    // checking allocates.
+    pub fn add_item(&mut self, item: impl AsRef<[u8]>) {
+        let item = item.as_ref();
         (0..self.hash_count).for_each(|i| {
-            let digest = Self::hash(&mut item.to_string(), i as u32).unwrap();
-            self.bit_array[digest as usize % self.size] = true;
+            let idx = self.index(item, i);
+            self.set_bit(idx);
         });
     }
 
-    fn check(&self, item: &str) -> bool {
-        for i in 0..self.hash_count {
-            let digest = Self::hash(&mut item.to_string(), i as u32).unwrap();
-            if !self.bit_array[digest as usize % self.size] {
-                return false;
+    pub fn check(&self, item: impl AsRef<[u8]>) -> bool {
+        let item = item.as_ref();
+        (0..self.hash_count).all(|i| self.get_bit(self.index(item, i)))
+    }
+
+    // Shrinks the filter by a power of two by repeatedly ORing the two halves of the bit array
+    // together. This works because for any size n divisible by 2: (h % n) % (n / 2) == h % (n / 2).
+    // Every halving roughly doubles the fraction of set bits, and the fp rate grows with it.
+    pub fn fold(&self, factor: usize) -> Result<BloomFilter, BloomError> {
+        if !factor.is_power_of_two() {
+            return Err(BloomError::InvalidParams(format!(
+                "fold factor must be a power of two, got {factor}"
+            )));
+        }
+        if !self.size.is_multiple_of(factor) {
+            return Err(BloomError::InvalidParams(format!(
+                "filter size {} can't be folded by {factor}",
+                self.size
+            )));
+        }
+
+        let mut folded = BloomFilter::with_size(self.size / factor, self.hash_count)?;
+        if folded.size.is_multiple_of(WORD_BITS) {
+            // every chunk starts on a word boundary so we can OR whole words
+            for chunk in self.bit_array.chunks(folded.bit_array.len()) {
+                for (word, other) in folded.bit_array.iter_mut().zip(chunk) {
+                    *word |= *other;
+                }
+            }
+        } else {
+            for idx in (0..self.size).filter(|idx| self.get_bit(*idx)) {
+                folded.set_bit(idx % folded.size);
             }
         }
 
-        true
+        Ok(folded)
+    }
+
+    // Fraction of bits that are set
+    pub fn fill_ratio(&self) -> f64 {
+        Self::count_ones(&self.bit_array) as f64 / self.size as f64
+    }
+
+    // Approximate number of distinct items added (Swamidass & Baldi):
+    //   n = -(m / k) * ln(1 - X / m)
+    // where m is the size, k the hash count and X the number of set bits
+    pub fn estimate_count(&self) -> f64 {
+        self.estimate_count_from_ones(Self::count_ones(&self.bit_array))
+    }
+
+    // Approximate size of the union of the two sets, estimated from the OR of both filters
+    pub fn estimate_union(&self, other: &BloomFilter) -> Result<f64, BloomError> {
+        self.check_compatible(other)?;
+        let ones = self
+            .bit_array
+            .iter()
+            .zip(other.bit_array.iter())
+            .map(|(a, b)| (a | b).count_ones() as usize)
+            .sum();
+        Ok(self.estimate_count_from_ones(ones))
+    }
+
+    // |A & B| = |A| + |B| - |A | B|
+    pub fn estimate_intersection(&self, other: &BloomFilter) -> Result<f64, BloomError> {
+        let union = self.estimate_union(other)?;
+        Ok((self.estimate_count() + other.estimate_count() - union).max(0_f64))
+    }
+
+    // |A & B| / |A | B|
+    pub fn estimate_jaccard(&self, other: &BloomFilter) -> Result<f64, BloomError> {
+        let union = self.estimate_union(other)?;
+        if union == 0_f64 {
+            return Ok(0_f64);
+        }
+
+        Ok(self.estimate_intersection(other)? / union)
+    }
+
+    // Filter containing the items of both filters
+    pub fn union(&self, other: &BloomFilter) -> Result<BloomFilter, BloomError> {
+        let mut union = self.clone();
+        union.combine_with(other, |a, b| a | b)?;
+        Ok(union)
+    }
+
+    // Filter containing the items that were added to both filters
+    // Note: the union is exactly the filter you'd get by adding both sets to one filter, the
+    // intersection may contain some extra bits (so a slightly higher fp rate) but never misses
+    // items that were added to both.
+    pub fn intersection(&self, other: &BloomFilter) -> Result<BloomFilter, BloomError> {
+        let mut intersection = self.clone();
+        intersection.combine_with(other, |a, b| a & b)?;
+        Ok(intersection)
+    }
+
+    fn estimate_count_from_ones(&self, ones: usize) -> f64 {
+        let m = self.size as f64;
+        let k = self.hash_count as f64;
+        -(m / k) * (1_f64 - ones as f64 / m).ln()
+    }
+
+    fn count_ones(words: &[u64]) -> usize {
+        words.iter().map(|word| word.count_ones() as usize).sum()
+    }
+
+    fn get_bit(&self, idx: usize) -> bool {
+        self.bit_array[idx / WORD_BITS] & (1 << (idx % WORD_BITS)) != 0
+    }
+
+    fn set_bit(&mut self, idx: usize) {
+        self.bit_array[idx / WORD_BITS] |= 1 << (idx % WORD_BITS);
+    }
+
+    // Filters can only be combined if their items hash to the same bits
+    fn check_compatible(&self, other: &BloomFilter) -> Result<(), BloomError> {
+        if self.size != other.size || self.hash_count != other.hash_count {
+            return Err(BloomError::Incompatible(format!(
+                "size {} vs {}, hash count {} vs {}",
+                self.size, other.size, self.hash_count, other.hash_count
+            )));
+        }
+
+        Ok(())
+    }
+
+    fn combine_with(
+        &mut self,
+        other: &BloomFilter,
+        op: impl Fn(u64, u64) -> u64,
+    ) -> Result<(), BloomError> {
+        self.check_compatible(other)?;
+        for (word, other) in self.bit_array.iter_mut().zip(other.bit_array.iter()) {
+            *word = op(*word, *other);
+        }
+
+        Ok(())
+    }
+
+    // Bit index of the i-th hash of item, each hash is just murmur3 with a different seed
+    fn index(&self, item: &[u8], i: usize) -> usize {
+        Self::hash(item, i as u32) as usize % self.size
+    }
+
+    fn hash(mut input: &[u8], seed: u32) -> u32 {
+        // murmur3 reads through io::Read, which can't fail for a byte slice
+        murmur3_32(&mut input, seed).unwrap_or_default()
+    }
+}
+
+// The highest fp rate a filter asked for fp_rate with sized_for items should end up with once it
+// holds n_items distinct items (more than it was sized for, in a partition that got more than its
+// share). It's worked out from the textbook size and hash count for fp_rate, not from a built
+// filter, so a filter that's sized wrong can't raise its own bar.
+// The *expected* rate is about fp_rate, but one filter's rate is fill_ratio^hash_count and how
+// full it gets depends on how many of its n_items * hash_count bits collide. With few items that
+// spreads a lot: 10 items at 0.0001 can be 8x over, 1000 items at 0.01 about 1.2x. The number of
+// set bits is close to normal, with the mean and variance of throwing balls into bins, so this
+// takes it 5 standard deviations high.
+#[cfg(test)]
+pub(crate) fn max_fp_rate(fp_rate: f64, sized_for: usize, n_items: usize) -> f64 {
+    let ln2 = 2_f64.ln();
+    let m = (-(sized_for as f64) * fp_rate.ln() / (ln2 * ln2)).ceil();
+    let hash_count = (m / sized_for as f64 * ln2).ceil() as i32;
+    let throws = n_items as i32 * hash_count;
+    let one_empty = (1_f64 - 1_f64 / m).powi(throws);
+    let two_empty = (1_f64 - 2_f64 / m).powi(throws);
+    let empty = m * one_empty;
+    let variance = empty + m * (m - 1_f64) * two_empty - empty * empty;
+    let set_bits = m - empty + 5_f64 * variance.max(0_f64).sqrt();
+    (set_bits / m).min(1_f64).powi(hash_count)
+}
+
+// The most false positives `probes` checks of items that aren't in a filter should turn up when
+// its fp rate is at most fp_rate. The count is binomial, and this is the Chernoff bound it only
+// goes over with probability 1e-6, which unlike mean + a few standard deviations still holds
+// when fp_rate * probes is tiny.
+#[cfg(test)]
+pub(crate) fn max_false_positives(fp_rate: f64, probes: usize) -> f64 {
+    let mean = fp_rate * probes as f64;
+    let log_odds = 1e6_f64.ln();
+    mean + (log_odds + (log_odds * log_odds + 8_f64 * mean * log_odds).sqrt()) / 2_f64
+}
+
+// Splits a little endian u64 off the front of bytes
+fn read_u64(bytes: &mut &[u8]) -> Result<u64, BloomError> {
+    let mut word = [0; 8];
+    word.copy_from_slice(read_slice(bytes, 8)?);
+    Ok(u64::from_le_bytes(word))
+}
+
+// Splits len bytes off the front of bytes
+fn read_slice<'a>(bytes: &mut &'a [u8], len: usize) -> Result<&'a [u8], BloomError> {
+    if bytes.len() < len {
+        return Err(BloomError::Corrupt(format!(
+            "expected {len} more bytes, got {}",
+            bytes.len()
+        )));
+    }
+
+    let (head, rest) = bytes.split_at(len);
+    *bytes = rest;
+    Ok(head)
+}
+
+// Operator versions of union and intersection
+// These can't return errors, so combining incompatible filters panics. Use union/intersection
+// when the filters might not match.
+impl BitOrAssign<&BloomFilter> for BloomFilter {
+    fn bitor_assign(&mut self, rhs: &BloomFilter) {
+        if let Err(e) = self.combine_with(rhs, |a, b| a | b) {
+            panic!("{e}");
+        }
+    }
+}
+
+impl BitAndAssign<&BloomFilter> for BloomFilter {
+    fn bitand_assign(&mut self, rhs: &BloomFilter) {
+        if let Err(e) = self.combine_with(rhs, |a, b| a & b) {
+            panic!("{e}");
+        }
+    }
+}
+
+impl BitOr<&BloomFilter> for BloomFilter {
+    type Output = BloomFilter;
+
+    fn bitor(mut self, rhs: &BloomFilter) -> Self::Output {
+        self |= rhs;
+        self
+    }
+}
+
+impl BitAnd<&BloomFilter> for BloomFilter {
+    type Output = BloomFilter;
+
+    fn bitand(mut self, rhs: &BloomFilter) -> Self::Output {
+        self &= rhs;
+        self
+    }
+}
+
+impl BitOr for &BloomFilter {
+    type Output = BloomFilter;
+
+    fn bitor(self, rhs: &BloomFilter) -> Self::Output {
+        self.clone() | rhs
     }
+}
+
+impl BitAnd for &BloomFilter {
+    type Output = BloomFilter;
 
-    fn hash(input: &mut str, seed: u32) -> Result<u32> {
-        murmur3_32(&mut input.as_bytes(), seed)
+    fn bitand(self, rhs: &BloomFilter) -> Self::Output {
+        self.clone() & rhs
     }
 }
 
@@ -63,13 +405,32 @@ mod tests {
 
     #[test]
     fn can_construct_bloom_filter() {
-        let _ = BloomFilter::new(0.05, 40);
+        let _ = BloomFilter::new(0.05, 40).unwrap();
+    }
+
+    #[test]
+    fn rejects_invalid_params() {
+        for (fp_rate, n_items) in [
+            (0_f64, 10),
+            (1_f64, 10),
+            (-0.5, 10),
+            (f64::NAN, 10),
+            (0.1, 0),
+        ] {
+            assert!(matches!(
+                BloomFilter::new(fp_rate, n_items),
+                Err(BloomError::InvalidParams(_))
+            ));
+        }
+
+        assert!(BloomFilter::with_size(0, 3).is_err());
+        assert!(BloomFilter::with_size(10, 0).is_err());
     }
 
     #[test]
     fn can_add_words_to_bloom_filter() {
         let included = get_words(10000);
-        let mut bloom = BloomFilter::new(0.05, included.len());
+        let mut bloom = BloomFilter::new(0.05, included.len()).unwrap();
         for word in included {
             bloom.add_item(word);
         }
@@ -79,7 +440,7 @@ mod tests {
     fn can_check_words_in_bloom_filter() {
         let mut included = get_words(10000);
         let excluded = included.split_off(5000);
-        let mut bloom = BloomFilter::new(0.05, included.len());
+        let mut bloom = BloomFilter::new(0.05, included.len()).unwrap();
 
         for word in included.iter() {
             bloom.add_item(word);
@@ -90,40 +451,279 @@ mod tests {
         }
     }
 
-    fn test_bloom(fp_rate: f64, n_included: usize, n_excluded: usize) -> f64 {
-        let mut included = get_words(n_included + n_excluded);
-        let excluded = included.split_off(n_included);
+    #[test]
+    fn can_round_trip_through_bytes() {
+        let mut bloom = BloomFilter::new(0.01, 100).unwrap();
+        for word in get_words(100) {
+            bloom.add_item(word);
+        }
+
+        let bytes = bloom.to_bytes();
+        let restored = BloomFilter::from_bytes(&bytes).unwrap();
+        assert_eq!(restored.size, bloom.size);
+        assert_eq!(restored.hash_count, bloom.hash_count);
+        assert_eq!(restored.bit_array, bloom.bit_array);
+    }
+
+    #[test]
+    fn rejects_corrupt_bytes() {
+        let bytes = BloomFilter::with_size(100, 3).unwrap().to_bytes();
+        assert!(matches!(
+            BloomFilter::from_bytes(&bytes[..bytes.len() - 1]),
+            Err(BloomError::Corrupt(_))
+        ));
+        assert!(matches!(
+            BloomFilter::from_bytes(&[0; 16]),
+            Err(BloomError::Corrupt(_))
+        ));
+        // a huge size with nothing behind it
+        assert!(matches!(
+            BloomFilter::from_bytes(&[0xFF; 24]),
+            Err(BloomError::Corrupt(_))
+        ));
+
+        // set a bit past size
+        let mut bytes = bytes;
+        let last = bytes.len() - 1;
+        bytes[last] = 0x80;
+        assert!(matches!(
+            BloomFilter::from_bytes(&bytes),
+            Err(BloomError::Corrupt(_))
+        ));
+    }
+
+    #[test]
+    fn str_and_byte_items_are_interchangeable() {
+        let mut bloom = BloomFilter::new(0.01, 10).unwrap();
+        bloom.add_item("foo");
+        bloom.add_item(String::from("bar"));
+        bloom.add_item(b"baz");
 
-        let mut bloom = BloomFilter::new(fp_rate, included.len());
+        assert!(bloom.check(b"foo"));
+        assert!(bloom.check("bar".as_bytes()));
+        assert!(bloom.check("baz"));
+    }
 
+    #[test]
+    fn folded_filter_keeps_all_items() {
+        let included = get_words(1000);
+        let mut bloom = BloomFilter::with_size(1 << 14, 7).unwrap();
         for word in included.iter() {
             bloom.add_item(word);
         }
 
-        let mut fp_count = 0;
-
-        for word in excluded.iter() {
-            let res = bloom.check(word);
-            if res && !included.contains(word) {
-                fp_count += 1;
+        for factor in [1, 2, 4, 8] {
+            let folded = bloom.fold(factor).unwrap();
+            assert_eq!(folded.size, bloom.size / factor);
+            for word in included.iter() {
+                assert!(folded.check(word));
             }
         }
+    }
+
+    #[test]
+    fn can_fold_sizes_that_arent_whole_words() {
+        let included = get_words(100);
+        let mut bloom = BloomFilter::with_size(200, 5).unwrap();
+        for word in included.iter() {
+            bloom.add_item(word);
+        }
+
+        let folded = bloom.fold(2).unwrap();
+        assert_eq!(folded.bit_array.len(), 2);
+        for word in included.iter() {
+            assert!(folded.check(word));
+        }
+    }
+
+    #[test]
+    fn fill_ratio_counts_set_bits() {
+        let mut bloom = BloomFilter::with_size(1000, 3).unwrap();
+        for word in get_words(100) {
+            bloom.add_item(word);
+        }
+
+        let set_bits = (0..bloom.size).filter(|idx| bloom.get_bit(*idx)).count();
+        assert!(set_bits > 0);
+        assert_eq!(bloom.fill_ratio(), set_bits as f64 / 1000_f64);
+    }
+
+    #[test]
+    fn folding_twice_is_folding_once_by_the_product() {
+        let mut bloom = BloomFilter::with_size(1 << 10, 3).unwrap();
+        for word in get_words(100) {
+            bloom.add_item(word);
+        }
 
-        // not sure if I've done something wrong but the fp_rate is sometimes a bit higher than
-        // fp_rate
-        (fp_count as f64 / n_excluded as f64) / fp_rate
+        let folded_twice = bloom.fold(2).unwrap().fold(4).unwrap();
+        assert_eq!(folded_twice.bit_array, bloom.fold(8).unwrap().bit_array);
+    }
+
+    #[test]
+    fn cannot_fold_by_non_power_of_two() {
+        let bloom = BloomFilter::with_size(12, 3).unwrap();
+        assert!(matches!(bloom.fold(3), Err(BloomError::InvalidParams(_))));
+        assert!(matches!(bloom.fold(0), Err(BloomError::InvalidParams(_))));
+    }
+
+    #[test]
+    fn cannot_fold_uneven_sizes() {
+        let bloom = BloomFilter::with_size(10, 3).unwrap();
+        assert!(matches!(bloom.fold(4), Err(BloomError::InvalidParams(_))));
+    }
+
+    #[test]
+    fn can_estimate_overlap_between_filters() {
+        let mut words = get_words(3000);
+        let only_b = words.split_off(2000);
+        let shared = words.split_off(1000);
+        let only_a = words;
+
+        let mut a = BloomFilter::new(0.01, 2000).unwrap();
+        let mut b = BloomFilter::new(0.01, 2000).unwrap();
+        for word in only_a.iter().chain(shared.iter()) {
+            a.add_item(word);
+        }
+        for word in only_b.iter().chain(shared.iter()) {
+            b.add_item(word);
+        }
+
+        assert!((a.estimate_count() - 2000_f64).abs() < 100_f64);
+        assert!((a.estimate_union(&b).unwrap() - 3000_f64).abs() < 150_f64);
+        assert!((a.estimate_intersection(&b).unwrap() - 1000_f64).abs() < 150_f64);
+        assert!((a.estimate_jaccard(&b).unwrap() - 1_f64 / 3_f64).abs() < 0.05);
+    }
+
+    #[test]
+    fn empty_filters_have_nothing_in_common() {
+        let a = BloomFilter::new(0.01, 100).unwrap();
+        let b = BloomFilter::new(0.01, 100).unwrap();
+        assert_eq!(a.fill_ratio(), 0_f64);
+        assert_eq!(a.estimate_intersection(&b), Ok(0_f64));
+        assert_eq!(a.estimate_jaccard(&b), Ok(0_f64));
+    }
+
+    #[test]
+    fn cannot_compare_incompatible_filters() {
+        let a = BloomFilter::new(0.01, 100).unwrap();
+        let b = BloomFilter::new(0.01, 200).unwrap();
+        assert!(matches!(
+            a.estimate_jaccard(&b),
+            Err(BloomError::Incompatible(_))
+        ));
+        assert!(matches!(a.union(&b), Err(BloomError::Incompatible(_))));
+        assert!(matches!(
+            a.intersection(&b),
+            Err(BloomError::Incompatible(_))
+        ));
+    }
+
+    #[test]
+    fn can_union_and_intersect_filters() {
+        let mut words = get_words(300);
+        let only_b = words.split_off(200);
+        let shared = words.split_off(100);
+        let only_a = words;
+
+        let mut a = BloomFilter::new(0.01, 300).unwrap();
+        let mut b = BloomFilter::new(0.01, 300).unwrap();
+        let mut expected_union = BloomFilter::new(0.01, 300).unwrap();
+        for word in only_a.iter().chain(shared.iter()) {
+            a.add_item(word);
+            expected_union.add_item(word);
+        }
+        for word in only_b.iter().chain(shared.iter()) {
+            b.add_item(word);
+            expected_union.add_item(word);
+        }
+
+        let union = &a | &b;
+        assert_eq!(union.bit_array, expected_union.bit_array);
+        assert_eq!(a.union(&b).unwrap().bit_array, union.bit_array);
+
+        let intersection = &a & &b;
+        for word in shared.iter() {
+            assert!(intersection.check(word));
+        }
+
+        let mut c = a.clone();
+        c |= &b;
+        assert_eq!(c.bit_array, union.bit_array);
+        c &= &a;
+        assert_eq!(c.bit_array, a.bit_array);
+        assert_eq!((a & &b).bit_array, intersection.bit_array);
+    }
+
+    #[test]
+    #[should_panic(expected = "aren't compatible")]
+    fn cannot_union_incompatible_filters() {
+        let a = BloomFilter::new(0.01, 100).unwrap();
+        let b = BloomFilter::new(0.02, 100).unwrap();
+        let _ = a | &b;
+    }
+
+    // Returns the false positives seen and the most there should be
+    // Returns the false positives seen and the most there should be
+    // The probes can't be in the word list (no word has a space in it), and there are enough of
+    // them that a filter hitting fp_rate turns up 100 or more false positives, so one that misses
+    // it by half fails.
+    fn test_bloom(fp_rate: f64, n_included: usize, n_excluded: usize) -> (usize, f64) {
+        let included = get_words(n_included);
+
+        let mut bloom = BloomFilter::new(fp_rate, included.len()).unwrap();
+
+        for word in included.iter() {
+            bloom.add_item(word);
+        }
+
+        let fp_count = (0..n_excluded)
+            .filter(|i| bloom.check(format!("not a word {i}")))
+            .count();
+
+        let max_rate = max_fp_rate(fp_rate, n_included, n_included);
+        (fp_count, max_false_positives(max_rate, n_excluded))
     }
 
     #[test]
     fn bloom_filter_checks_have_correct_fp_rate() {
         let mut rng = rand::thread_rng();
-        let max_fp_rate_ratio = 2_f64; // gotta be fairly lenient, bc either stats or I messed up...
 
         for _ in 0..100 {
             let fp_rate = rng.gen_range(0.0001..0.1);
             let n_included = rng.gen_range(10..1000);
-            let n_excluded = rng.gen_range(100..100000);
-            assert!(test_bloom(fp_rate, n_included, n_excluded) < max_fp_rate_ratio)
+            let n_excluded = (rng.gen_range(100_f64..200_f64) / fp_rate) as usize;
+            let (fp_count, max_count) = test_bloom(fp_rate, n_included, n_excluded);
+            assert!(
+                fp_count as f64 <= max_count,
+                "{fp_count} false positives in {n_excluded} for {n_included} items at {fp_rate}"
+            );
+        }
+    }
+
+    // m = -n ln(p) / ln(2)^2 bits and k = m / n * ln(2) hashes, each rounded up, which makes the
+    // expected fp rate (1 - e^(-kn/m))^k a few % over p at most
+    #[test]
+    fn params_follow_the_standard_formula() {
+        assert_eq!(BloomFilter::get_params(0.01, 100).unwrap(), (959, 7));
+        assert_eq!(BloomFilter::get_params(0.001, 1000).unwrap(), (14378, 10));
+
+        let ln2 = 2_f64.ln();
+        for fp_rate in [0.0001, 0.001, 0.005, 0.01, 0.05, 0.1, 0.3] {
+            for n_items in [1, 10, 100, 999, 100_000] {
+                let (size, hash_count) = BloomFilter::get_params(fp_rate, n_items).unwrap();
+                let (m, n) = (size as f64, n_items as f64);
+                let ideal_size = -n * fp_rate.ln() / (ln2 * ln2);
+                assert!(m >= ideal_size && m < ideal_size + 1_f64);
+                let ideal_hash_count = m / n * ln2;
+                let k = hash_count as f64;
+                assert!(k >= ideal_hash_count && k < ideal_hash_count + 1_f64);
+
+                let expected = (1_f64 - (-k * n / m).exp()).powf(k);
+                assert!(
+                    expected < 1.05 * fp_rate,
+                    "{size} bits and {hash_count} hashes for {n_items} at {fp_rate}: {expected}"
+                );
+            }
         }
     }
 }
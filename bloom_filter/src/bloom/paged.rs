@@ -0,0 +1,342 @@
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+use super::{read_u64, BloomError, BloomFilter, WORD_BITS};
+
+// 4 KiB of bits per page
+const PAGE_WORDS: usize = 512;
+// size: u64 | hash_count: u64, same as BloomFilter::to_bytes
+const HEADER_BYTES: u64 = 16;
+
+struct Page {
+    words: Vec<u64>,
+    dirty: bool,
+    last_used: u64,
+}
+
+// A bloom filter whose bit array lives in a file, with only a few pages of it in memory
+// The file has the same layout as BloomFilter::to_bytes, so a filter built in memory can be
+// written out and opened paged (and a small enough paged filter read back with from_bytes).
+// Pages are evicted least recently used first, dirty pages are written back on eviction, flush
+// and drop.
+pub struct PagedBloomFilter {
+    file: File,
+    size: usize,
+    hash_count: usize,
+    cache_pages: usize,
+    pages: HashMap<usize, Page>,
+    clock: u64,
+}
+
+impl PagedBloomFilter {
+    pub fn create(
+        path: impl AsRef<Path>,
+        fp_rate: f64,
+        n_items: usize,
+        cache_pages: usize,
+    ) -> Result<Self, BloomError> {
+        let (size, hash_count) = BloomFilter::get_params(fp_rate, n_items)?;
+        Self::create_with_size(path, size, hash_count, cache_pages)
+    }
+
+    // Creates (or truncates) the file, the bits are a sparse run of zeros on most filesystems
+    pub fn create_with_size(
+        path: impl AsRef<Path>,
+        size: usize,
+        hash_count: usize,
+        cache_pages: usize,
+    ) -> Result<Self, BloomError> {
+        if size == 0 || hash_count == 0 || cache_pages == 0 {
+            return Err(BloomError::InvalidParams(format!(
+                "size ({size}), hash count ({hash_count}) and cache pages ({cache_pages}) must be positive"
+            )));
+        }
+
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(path)?;
+        file.write_all(&(size as u64).to_le_bytes())?;
+        file.write_all(&(hash_count as u64).to_le_bytes())?;
+        file.set_len(HEADER_BYTES + size.div_ceil(WORD_BITS) as u64 * 8)?;
+
+        Ok(Self::with_file(file, size, hash_count, cache_pages))
+    }
+
+    pub fn open(path: impl AsRef<Path>, cache_pages: usize) -> Result<Self, BloomError> {
+        if cache_pages == 0 {
+            return Err(BloomError::InvalidParams(
+                "cache pages must be positive".into(),
+            ));
+        }
+
+        let mut file = OpenOptions::new().read(true).write(true).open(path)?;
+        let mut header = [0; HEADER_BYTES as usize];
+        file.read_exact(&mut header)?;
+        let mut header = &header[..];
+        let size = read_u64(&mut header)? as usize;
+        let hash_count = read_u64(&mut header)? as usize;
+        if size == 0 || hash_count == 0 {
+            return Err(BloomError::Corrupt(format!(
+                "size ({size}) and hash count ({hash_count}) must be positive"
+            )));
+        }
+
+        let expected = HEADER_BYTES + size.div_ceil(WORD_BITS) as u64 * 8;
+        let actual = file.metadata()?.len();
+        if actual != expected {
+            return Err(BloomError::Corrupt(format!(
+                "expected a {expected} byte file, got {actual}"
+            )));
+        }
+
+        Ok(Self::with_file(file, size, hash_count, cache_pages))
+    }
+
+    fn with_file(file: File, size: usize, hash_count: usize, cache_pages: usize) -> Self {
+        PagedBloomFilter {
+            file,
+            size,
+            hash_count,
+            cache_pages,
+            pages: HashMap::with_capacity(cache_pages),
+            clock: 0,
+        }
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    pub fn hash_count(&self) -> usize {
+        self.hash_count
+    }
+
+    pub fn add_item(&mut self, item: impl AsRef<[u8]>) -> Result<(), BloomError> {
+        let item = item.as_ref();
+        for i in 0..self.hash_count {
+            let idx = self.index(item, i);
+            let page = self.page(idx / WORD_BITS / PAGE_WORDS)?;
+            page.words[idx / WORD_BITS % PAGE_WORDS] |= 1 << (idx % WORD_BITS);
+            page.dirty = true;
+        }
+        Ok(())
+    }
+
+    // Takes &mut self because a check may have to page bits in
+    pub fn check(&mut self, item: impl AsRef<[u8]>) -> Result<bool, BloomError> {
+        let item = item.as_ref();
+        for i in 0..self.hash_count {
+            if !self.get_bit(self.index(item, i))? {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    // Checks many items while touching every page at most once
+    // All bit indices are computed up front and visited in page order, so each page the batch
+    // needs is read once, front to back, no matter how small the cache is.
+    pub fn check_batch<T: AsRef<[u8]>>(&mut self, items: &[T]) -> Result<Vec<bool>, BloomError> {
+        let mut lookups = Vec::with_capacity(items.len() * self.hash_count);
+        for (n, item) in items.iter().enumerate() {
+            let item = item.as_ref();
+            lookups.extend((0..self.hash_count).map(|i| (self.index(item, i), n)));
+        }
+        lookups.sort_unstable();
+
+        let mut found = vec![true; items.len()];
+        for (idx, n) in lookups {
+            if found[n] && !self.get_bit(idx)? {
+                found[n] = false;
+            }
+        }
+        Ok(found)
+    }
+
+    // Writes every dirty page back to the file
+    pub fn flush(&mut self) -> Result<(), BloomError> {
+        let mut dirty: Vec<usize> = self
+            .pages
+            .iter()
+            .filter(|(_, page)| page.dirty)
+            .map(|(n, _)| *n)
+            .collect();
+        dirty.sort_unstable();
+
+        for n in dirty {
+            if let Some(page) = self.pages.get(&n) {
+                Self::write_page(&mut self.file, n, &page.words)?;
+            }
+            if let Some(page) = self.pages.get_mut(&n) {
+                page.dirty = false;
+            }
+        }
+        self.file.flush()?;
+        Ok(())
+    }
+
+    fn get_bit(&mut self, idx: usize) -> Result<bool, BloomError> {
+        let page = self.page(idx / WORD_BITS / PAGE_WORDS)?;
+        Ok(page.words[idx / WORD_BITS % PAGE_WORDS] & (1 << (idx % WORD_BITS)) != 0)
+    }
+
+    fn index(&self, item: &[u8], i: usize) -> usize {
+        BloomFilter::hash(item, i as u32) as usize % self.size
+    }
+
+    // The cached page, reading it in (and evicting the least recently used page) if needed
+    fn page(&mut self, n: usize) -> Result<&mut Page, BloomError> {
+        if !self.pages.contains_key(&n) && self.pages.len() >= self.cache_pages {
+            self.evict()?;
+        }
+
+        self.clock += 1;
+        let page = match self.pages.entry(n) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => {
+                let n_words = PAGE_WORDS.min(self.size.div_ceil(WORD_BITS) - n * PAGE_WORDS);
+                entry.insert(Page {
+                    words: Self::read_page(&mut self.file, n, n_words)?,
+                    dirty: false,
+                    last_used: 0,
+                })
+            }
+        };
+        page.last_used = self.clock;
+        Ok(page)
+    }
+
+    fn evict(&mut self) -> Result<(), BloomError> {
+        let oldest = self
+            .pages
+            .iter()
+            .min_by_key(|(_, page)| page.last_used)
+            .map(|(n, _)| *n);
+
+        if let Some(n) = oldest {
+            let page = self.pages.remove(&n);
+            if let Some(page) = page.filter(|page| page.dirty) {
+                Self::write_page(&mut self.file, n, &page.words)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn page_offset(n: usize) -> u64 {
+        HEADER_BYTES + (n * PAGE_WORDS * 8) as u64
+    }
+
+    fn read_page(file: &mut File, n: usize, n_words: usize) -> Result<Vec<u64>, BloomError> {
+        let mut bytes = vec![0; n_words * 8];
+        file.seek(SeekFrom::Start(Self::page_offset(n)))?;
+        file.read_exact(&mut bytes)?;
+
+        let mut bytes = &bytes[..];
+        let mut words = Vec::with_capacity(bytes.len() / 8);
+        while !bytes.is_empty() {
+            words.push(read_u64(&mut bytes)?);
+        }
+        Ok(words)
+    }
+
+    fn write_page(file: &mut File, n: usize, words: &[u64]) -> Result<(), BloomError> {
+        let bytes: Vec<u8> = words.iter().flat_map(|word| word.to_le_bytes()).collect();
+        file.seek(SeekFrom::Start(Self::page_offset(n)))?;
+        file.write_all(&bytes)?;
+        Ok(())
+    }
+}
+
+// Dirty pages are written back on a best effort basis, call flush to see errors
+impl Drop for PagedBloomFilter {
+    fn drop(&mut self) {
+        let _ = self.flush();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use crate::bloom::words::get_words;
+
+    use super::*;
+
+    #[test]
+    fn agrees_with_in_memory_filter() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("words.bloom");
+        let words = get_words(5000);
+
+        let mut bloom = BloomFilter::new(0.01, words.len()).unwrap();
+        // a single page cache forces an eviction on nearly every hash
+        let mut paged = PagedBloomFilter::create(&path, 0.01, words.len(), 1).unwrap();
+        for word in words.iter().take(2500) {
+            bloom.add_item(word);
+            paged.add_item(word).unwrap();
+        }
+
+        for word in words.iter() {
+            assert_eq!(paged.check(word).unwrap(), bloom.check(word));
+        }
+        assert_eq!(
+            paged.check_batch(&words).unwrap(),
+            words
+                .iter()
+                .map(|word| bloom.check(word))
+                .collect::<Vec<_>>()
+        );
+
+        paged.flush().unwrap();
+        assert_eq!(fs::read(&path).unwrap(), bloom.to_bytes());
+    }
+
+    #[test]
+    fn can_reopen_a_filter() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("words.bloom");
+        let words = get_words(1000);
+
+        let mut bloom = BloomFilter::new(0.01, words.len()).unwrap();
+        for word in words.iter() {
+            bloom.add_item(word);
+        }
+        fs::write(&path, bloom.to_bytes()).unwrap();
+
+        {
+            let mut paged = PagedBloomFilter::open(&path, 2).unwrap();
+            assert_eq!(paged.size(), bloom.size());
+            assert!(paged.check_batch(&words).unwrap().into_iter().all(|x| x));
+            paged.add_item("not a word").unwrap();
+        }
+
+        let reopened = BloomFilter::from_bytes(&fs::read(&path).unwrap()).unwrap();
+        assert!(reopened.check("not a word"));
+    }
+
+    #[test]
+    fn rejects_bad_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("bad.bloom");
+
+        assert!(matches!(
+            PagedBloomFilter::open(&path, 1),
+            Err(BloomError::Io(_))
+        ));
+
+        let bytes = BloomFilter::new(0.01, 100).unwrap().to_bytes();
+        fs::write(&path, &bytes[..bytes.len() - 8]).unwrap();
+        assert!(matches!(
+            PagedBloomFilter::open(&path, 1),
+            Err(BloomError::Corrupt(_))
+        ));
+
+        assert!(PagedBloomFilter::create(&path, 0.01, 100, 0).is_err());
+    }
+}
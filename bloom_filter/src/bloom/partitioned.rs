@@ -0,0 +1,239 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use super::{read_slice, read_u64, BloomError, BloomFilter};
+
+// Keys a PartitionedMembership can be keyed by, they need a byte representation so the whole
+// thing can be serialized.
+pub trait PartitionKey: Eq + Hash + Sized {
+    fn to_key_bytes(&self) -> Vec<u8>;
+    fn from_key_bytes(bytes: &[u8]) -> Option<Self>;
+}
+
+impl PartitionKey for String {
+    fn to_key_bytes(&self) -> Vec<u8> {
+        self.as_bytes().to_vec()
+    }
+
+    fn from_key_bytes(bytes: &[u8]) -> Option<Self> {
+        String::from_utf8(bytes.to_vec()).ok()
+    }
+}
+
+impl PartitionKey for Vec<u8> {
+    fn to_key_bytes(&self) -> Vec<u8> {
+        self.clone()
+    }
+
+    fn from_key_bytes(bytes: &[u8]) -> Option<Self> {
+        Some(bytes.to_vec())
+    }
+}
+
+macro_rules! impl_partition_key_for_int {
+    ($($t:ty),*) => {$(
+        impl PartitionKey for $t {
+            fn to_key_bytes(&self) -> Vec<u8> {
+                self.to_le_bytes().to_vec()
+            }
+
+            fn from_key_bytes(bytes: &[u8]) -> Option<Self> {
+                Some(<$t>::from_le_bytes(bytes.try_into().ok()?))
+            }
+        }
+    )*};
+}
+
+impl_partition_key_for_int!(u8, u16, u32, u64, i32, i64);
+
+// One bloom filter per partition (chromosome, tenant, ...), carved out of a single memory budget
+// Every partition gets a share of the budget proportional to the number of items it expects, so
+// all partitions end up with the same bits per item and the same fp rate.
+#[derive(Debug, Clone)]
+pub struct PartitionedMembership<K: PartitionKey> {
+    filters: HashMap<K, BloomFilter>,
+}
+
+impl<K: PartitionKey> PartitionedMembership<K> {
+    // partitions are (key, expected number of items) pairs
+    pub fn new(
+        budget_bytes: usize,
+        partitions: impl IntoIterator<Item = (K, usize)>,
+    ) -> Result<Self, BloomError> {
+        let partitions: Vec<(K, usize)> = partitions.into_iter().collect();
+        if partitions.iter().any(|(_, n_items)| *n_items == 0) {
+            return Err(BloomError::InvalidParams(
+                "every partition must expect at least one item".into(),
+            ));
+        }
+
+        // anything under a bit per item is a filter that says yes to everything
+        let total_items: usize = partitions.iter().map(|(_, n_items)| n_items).sum();
+        let budget_bits = budget_bytes as f64 * 8_f64;
+        if budget_bits < total_items as f64 {
+            return Err(BloomError::InvalidParams(format!(
+                "a budget of {budget_bytes} bytes is too small for {total_items} items"
+            )));
+        }
+
+        let mut filters = HashMap::with_capacity(partitions.len());
+        for (key, n_items) in partitions {
+            let size = (budget_bits * n_items as f64 / total_items as f64).floor() as usize;
+            let hash_count = BloomFilter::get_hash_count(size, n_items);
+            filters.insert(key, BloomFilter::with_size(size, hash_count)?);
+        }
+
+        Ok(PartitionedMembership { filters })
+    }
+
+    pub fn insert(&mut self, key: &K, item: impl AsRef<[u8]>) -> Result<(), BloomError> {
+        let filter = self
+            .filters
+            .get_mut(key)
+            .ok_or(BloomError::UnknownPartition)?;
+        filter.add_item(item);
+        Ok(())
+    }
+
+    // Unknown partitions contain nothing
+    pub fn contains(&self, key: &K, item: impl AsRef<[u8]>) -> bool {
+        self.filters
+            .get(key)
+            .is_some_and(|filter| filter.check(item))
+    }
+
+    pub fn partitions(&self) -> impl Iterator<Item = &K> {
+        self.filters.keys()
+    }
+
+    pub fn partition(&self, key: &K) -> Option<&BloomFilter> {
+        self.filters.get(key)
+    }
+
+    pub fn memory_bytes(&self) -> usize {
+        self.filters
+            .values()
+            .map(|filter| filter.bit_array.len() * 8)
+            .sum()
+    }
+
+    // Serialized layout, all little endian:
+    //   n_partitions: u64 | n_partitions * (key_len: u64 | key | filter_len: u64 | filter)
+    // Partitions are written in key byte order so equal filters serialize to equal bytes.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut partitions: Vec<(Vec<u8>, &BloomFilter)> = self
+            .filters
+            .iter()
+            .map(|(key, filter)| (key.to_key_bytes(), filter))
+            .collect();
+        partitions.sort_by(|a, b| a.0.cmp(&b.0));
+
+        let mut bytes = vec![];
+        bytes.extend_from_slice(&(partitions.len() as u64).to_le_bytes());
+        for (key, filter) in partitions {
+            let filter = filter.to_bytes();
+            bytes.extend_from_slice(&(key.len() as u64).to_le_bytes());
+            bytes.extend_from_slice(&key);
+            bytes.extend_from_slice(&(filter.len() as u64).to_le_bytes());
+            bytes.extend_from_slice(&filter);
+        }
+        bytes
+    }
+
+    pub fn from_bytes(mut bytes: &[u8]) -> Result<Self, BloomError> {
+        let n_partitions = read_u64(&mut bytes)? as usize;
+        let mut filters = HashMap::new();
+        for _ in 0..n_partitions {
+            let key_len = read_u64(&mut bytes)? as usize;
+            let key = K::from_key_bytes(read_slice(&mut bytes, key_len)?)
+                .ok_or_else(|| BloomError::Corrupt("invalid partition key".into()))?;
+            let filter_len = read_u64(&mut bytes)? as usize;
+            let filter = BloomFilter::from_bytes(read_slice(&mut bytes, filter_len)?)?;
+            if filters.insert(key, filter).is_some() {
+                return Err(BloomError::Corrupt("duplicate partition key".into()));
+            }
+        }
+
+        if !bytes.is_empty() {
+            return Err(BloomError::Corrupt(format!(
+                "{} trailing bytes",
+                bytes.len()
+            )));
+        }
+
+        Ok(PartitionedMembership { filters })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bloom::words::get_words;
+
+    use super::*;
+
+    fn chroms() -> Vec<(String, usize)> {
+        vec![("1".into(), 2000), ("2".into(), 1000), ("X".into(), 500)]
+    }
+
+    #[test]
+    fn splits_budget_by_expected_items() {
+        let membership = PartitionedMembership::new(4096, chroms()).unwrap();
+        assert!(membership.memory_bytes() <= 4096 + 3 * 8);
+
+        let size = |key: &str| membership.partition(&key.to_string()).unwrap().size();
+        assert_eq!(size("1"), 2 * size("2"));
+        assert_eq!(size("2"), 2 * size("X"));
+    }
+
+    #[test]
+    fn can_insert_and_check_per_partition() {
+        let words = get_words(1000);
+        let mut membership = PartitionedMembership::new(4096, chroms()).unwrap();
+        for word in words.iter() {
+            membership.insert(&"2".to_string(), word).unwrap();
+        }
+
+        for word in words.iter() {
+            assert!(membership.contains(&"2".to_string(), word));
+        }
+        assert!(!membership.contains(&"MT".to_string(), words[0]));
+        assert_eq!(
+            membership.insert(&"MT".to_string(), "foo"),
+            Err(BloomError::UnknownPartition)
+        );
+    }
+
+    #[test]
+    fn works_with_integer_keys() {
+        let mut membership = PartitionedMembership::new(1024, [(1_u64, 100), (2, 100)]).unwrap();
+        membership.insert(&1, "foo").unwrap();
+        assert!(membership.contains(&1, "foo"));
+        assert!(!membership.contains(&2, "foo"));
+    }
+
+    #[test]
+    fn rejects_bad_budgets() {
+        assert!(PartitionedMembership::new(1, chroms()).is_err());
+        assert!(PartitionedMembership::new(4096, [("1".to_string(), 0)]).is_err());
+    }
+
+    #[test]
+    fn can_round_trip_through_bytes() {
+        let words = get_words(100);
+        let mut membership = PartitionedMembership::new(4096, chroms()).unwrap();
+        for (i, word) in words.iter().enumerate() {
+            let key = ["1", "2", "X"][i % 3].to_string();
+            membership.insert(&key, word).unwrap();
+        }
+
+        let bytes = membership.to_bytes();
+        let restored = PartitionedMembership::<String>::from_bytes(&bytes).unwrap();
+        assert_eq!(restored.to_bytes(), bytes);
+        for (i, word) in words.iter().enumerate() {
+            let key = ["1", "2", "X"][i % 3].to_string();
+            assert!(restored.contains(&key, word));
+        }
+
+        assert!(PartitionedMembership::<String>::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+    }
+}
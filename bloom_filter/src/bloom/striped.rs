@@ -0,0 +1,175 @@
+use std::mem;
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+use super::{BloomError, BloomFilter};
+
+// A counting bloom filter that can be shared between threads.
+// Counters are spread round-robin over `n_stripes` banks, each behind its own mutex, so two
+// threads only contend when their items hash into the same bank.
+#[derive(Debug)]
+pub struct StripedCountingFilter {
+    size: usize,
+    hash_count: usize,
+    stripes: Vec<Mutex<Vec<u8>>>,
+}
+
+impl StripedCountingFilter {
+    pub fn new(fp_rate: f64, n_items: usize, n_stripes: usize) -> Result<Self, BloomError> {
+        let (size, hash_count) = BloomFilter::get_params(fp_rate, n_items)?;
+        let n_stripes = n_stripes.clamp(1, size);
+        let stripe_len = size.div_ceil(n_stripes);
+        let stripes = (0..n_stripes)
+            .map(|_| Mutex::new(vec![0; stripe_len]))
+            .collect();
+
+        Ok(StripedCountingFilter {
+            size,
+            hash_count,
+            stripes,
+        })
+    }
+
+    pub fn add_item(&self, item: impl AsRef<[u8]>) {
+        self.with_counters(item.as_ref(), |counters| {
+            for counter in counters {
+                // a saturated counter is stuck for good, decrementing it could cause false negatives
+                **counter = counter.saturating_add(1);
+            }
+        });
+    }
+
+    // Returns false (and leaves the filter untouched) if the item is definitely not present.
+    pub fn remove_item(&self, item: impl AsRef<[u8]>) -> bool {
+        self.with_counters(item.as_ref(), |counters| {
+            if counters.iter().any(|c| **c == 0) {
+                return false;
+            }
+
+            for counter in counters {
+                if **counter != u8::MAX {
+                    **counter -= 1;
+                }
+            }
+
+            true
+        })
+    }
+
+    pub fn check(&self, item: impl AsRef<[u8]>) -> bool {
+        self.with_counters(item.as_ref(), |counters| counters.iter().all(|c| **c > 0))
+    }
+
+    // Locks every stripe the item hashes into and hands `f` the item's counters.
+    // Stripes are locked in ascending order so concurrent operations can't deadlock, and all of
+    // them are held for the duration of `f` so adds/removes of the same item are atomic.
+    fn with_counters<R>(&self, item: &[u8], f: impl FnOnce(&mut [&mut u8]) -> R) -> R {
+        let mut slots: Vec<(usize, usize)> = (0..self.hash_count)
+            .map(|i| {
+                let idx = BloomFilter::hash(item, i as u32) as usize % self.size;
+                (idx % self.stripes.len(), idx / self.stripes.len())
+            })
+            .collect();
+
+        let mut stripe_ids: Vec<usize> = slots.iter().map(|(stripe, _)| *stripe).collect();
+        stripe_ids.sort_unstable();
+        stripe_ids.dedup();
+
+        let mut guards: Vec<(usize, MutexGuard<Vec<u8>>)> = stripe_ids
+            .into_iter()
+            // a thread panicking mid-update can at worst leave some counters of one item bumped,
+            // which is no worse than a false positive, so poisoning is ignored
+            .map(|id| {
+                let guard = self.stripes[id].lock();
+                (id, guard.unwrap_or_else(PoisonError::into_inner))
+            })
+            .collect();
+
+        // several hashes may land on the same counter, it should only be touched once
+        slots.sort_unstable();
+        slots.dedup();
+
+        let mut counters: Vec<&mut u8> = Vec::with_capacity(slots.len());
+        let mut slots = slots.into_iter().peekable();
+        for (id, guard) in guards.iter_mut() {
+            let mut bank = guard.as_mut_slice();
+            let mut consumed = 0;
+            while let Some((_, offset)) = slots.next_if(|(stripe, _)| stripe == id) {
+                // split the bank so we can hold several disjoint &mut into it at once
+                let (_, rest) = mem::take(&mut bank).split_at_mut(offset - consumed);
+                if let Some((counter, rest)) = rest.split_first_mut() {
+                    counters.push(counter);
+                    bank = rest;
+                    consumed = offset + 1;
+                }
+            }
+        }
+
+        f(&mut counters)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+
+    use crate::bloom::words::get_words;
+
+    use super::*;
+
+    #[test]
+    fn can_add_check_and_remove_items() {
+        let filter = StripedCountingFilter::new(0.01, 100, 4).unwrap();
+        filter.add_item("foo");
+        assert!(filter.check("foo"));
+        assert!(filter.remove_item("foo"));
+        assert!(!filter.check("foo"));
+        assert!(!filter.remove_item("foo"));
+    }
+
+    #[test]
+    fn counts_duplicate_adds() {
+        let filter = StripedCountingFilter::new(0.01, 100, 4).unwrap();
+        filter.add_item("foo");
+        filter.add_item("foo");
+        assert!(filter.remove_item("foo"));
+        assert!(filter.check("foo"));
+        assert!(filter.remove_item("foo"));
+        assert!(!filter.check("foo"));
+    }
+
+    #[test]
+    fn works_with_more_stripes_than_counters() {
+        let filter = StripedCountingFilter::new(0.5, 1, 64).unwrap();
+        filter.add_item("foo");
+        assert!(filter.check("foo"));
+    }
+
+    #[test]
+    fn can_add_and_remove_from_many_threads() {
+        let words = get_words(8000);
+        let (kept, removed) = words.split_at(4000);
+        let filter = StripedCountingFilter::new(0.01, words.len(), 16).unwrap();
+
+        thread::scope(|s| {
+            for chunk in words.chunks(1000) {
+                let filter = &filter;
+                s.spawn(move || chunk.iter().for_each(|word| filter.add_item(word)));
+            }
+        });
+
+        thread::scope(|s| {
+            for chunk in removed.chunks(1000) {
+                let filter = &filter;
+                s.spawn(move || {
+                    chunk
+                        .iter()
+                        .for_each(|word| assert!(filter.remove_item(word)))
+                });
+            }
+        });
+
+        for word in kept {
+            assert!(filter.check(word));
+        }
+    }
+}
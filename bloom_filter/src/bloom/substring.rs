@@ -0,0 +1,213 @@
+use std::collections::VecDeque;
+
+use super::{BloomError, BloomFilter};
+
+// Multiplier of the rolling polynomial hash, arithmetic is mod 2^64
+const BASE: u64 = 0x0000_0100_0000_01b3;
+
+// Approximate substring matching on top of a bloom filter
+// Every window of `window` bytes of every added document goes into the filter (as its rolling
+// hash), so a query window is "in" the filter if it occurred somewhere in the documents. Rolling
+// the hash means each extra window costs O(1) instead of re-hashing `window` bytes.
+#[derive(Debug, Clone)]
+pub struct SubstringFilter {
+    window: usize,
+    bloom: BloomFilter,
+}
+
+impl SubstringFilter {
+    // n_windows is the expected number of distinct windows over all documents
+    pub fn new(window: usize, fp_rate: f64, n_windows: usize) -> Result<Self, BloomError> {
+        if window == 0 {
+            return Err(BloomError::InvalidParams("window must be positive".into()));
+        }
+
+        Ok(SubstringFilter {
+            window,
+            bloom: BloomFilter::new(fp_rate, n_windows)?,
+        })
+    }
+
+    pub fn window(&self) -> usize {
+        self.window
+    }
+
+    // Adds every window of the document, documents shorter than the window add nothing
+    pub fn add_document(&mut self, doc: impl AsRef<[u8]>) {
+        for (_, hash) in RollingHash::new(doc.as_ref(), self.window) {
+            self.bloom.add_item(hash.to_le_bytes());
+        }
+    }
+
+    // True if every window of the query was seen in some document
+    // Queries shorter than the window can't be answered and are never contained.
+    pub fn contains(&self, query: impl AsRef<[u8]>) -> bool {
+        let query = query.as_ref();
+        query.len() >= self.window
+            && RollingHash::new(query, self.window).all(|(_, hash)| self.check_hash(hash))
+    }
+
+    // Offsets of the windows of haystack that were (probably) seen in some document
+    pub fn find_windows<'a>(&'a self, haystack: &'a [u8]) -> impl Iterator<Item = usize> + 'a {
+        RollingHash::new(haystack, self.window)
+            .filter(|(_, hash)| self.check_hash(*hash))
+            .map(|(offset, _)| offset)
+    }
+
+    // Scanner for input that arrives a byte at a time
+    pub fn scanner(&self) -> WindowScanner<'_> {
+        WindowScanner {
+            filter: self,
+            buffer: VecDeque::with_capacity(self.window),
+            hash: 0,
+            high_power: high_power(self.window),
+        }
+    }
+
+    fn check_hash(&self, hash: u64) -> bool {
+        self.bloom.check(hash.to_le_bytes())
+    }
+}
+
+// Checks the last `window` bytes of a stream against a SubstringFilter as bytes are pushed
+pub struct WindowScanner<'a> {
+    filter: &'a SubstringFilter,
+    buffer: VecDeque<u8>,
+    hash: u64,
+    high_power: u64,
+}
+
+impl WindowScanner<'_> {
+    // Returns None until a full window has been pushed, then whether the window ending with
+    // this byte was seen in some document
+    pub fn push(&mut self, byte: u8) -> Option<bool> {
+        if self.buffer.len() == self.filter.window {
+            let oldest = self.buffer.pop_front().unwrap_or_default();
+            self.hash = self
+                .hash
+                .wrapping_sub((oldest as u64).wrapping_mul(self.high_power));
+        }
+        self.hash = self.hash.wrapping_mul(BASE).wrapping_add(byte as u64);
+        self.buffer.push_back(byte);
+
+        (self.buffer.len() == self.filter.window).then(|| self.filter.check_hash(self.hash))
+    }
+}
+
+// BASE^(window - 1), the weight of the oldest byte in a window
+fn high_power(window: usize) -> u64 {
+    (1..window).fold(1_u64, |power, _| power.wrapping_mul(BASE))
+}
+
+// Iterator over (offset, hash) of every window of a slice
+//   hash(s[i..i + w]) = s[i] * B^(w - 1) + s[i + 1] * B^(w - 2) + ... + s[i + w - 1]
+// Sliding one byte right subtracts the oldest byte's term, shifts everything up by one power and
+// adds the new byte.
+struct RollingHash<'a> {
+    bytes: &'a [u8],
+    window: usize,
+    offset: usize,
+    hash: u64,
+    high_power: u64,
+}
+
+impl<'a> RollingHash<'a> {
+    fn new(bytes: &'a [u8], window: usize) -> Self {
+        let hash = bytes.iter().take(window).fold(0_u64, |hash, b| {
+            hash.wrapping_mul(BASE).wrapping_add(*b as u64)
+        });
+
+        RollingHash {
+            bytes,
+            window,
+            offset: 0,
+            hash,
+            high_power: high_power(window),
+        }
+    }
+}
+
+impl Iterator for RollingHash<'_> {
+    type Item = (usize, u64);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.offset + self.window > self.bytes.len() {
+            return None;
+        }
+
+        let item = (self.offset, self.hash);
+        if let Some(next) = self.bytes.get(self.offset + self.window) {
+            let oldest = self.bytes[self.offset] as u64;
+            self.hash = self
+                .hash
+                .wrapping_sub(oldest.wrapping_mul(self.high_power))
+                .wrapping_mul(BASE)
+                .wrapping_add(*next as u64);
+        }
+        self.offset += 1;
+
+        Some(item)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ADAPTER: &str = "AGATCGGAAGAGCACACGTCTGAACTCCAGTCA";
+
+    #[test]
+    fn rolling_hash_matches_hashing_each_window() {
+        let bytes = b"the quick brown fox jumps over the lazy dog";
+        for window in [1, 3, 8, bytes.len()] {
+            let rolled: Vec<_> = RollingHash::new(bytes, window).collect();
+            assert_eq!(rolled.len(), bytes.len() - window + 1);
+            for (offset, hash) in rolled {
+                let (_, expected) = RollingHash::new(&bytes[offset..offset + window], window)
+                    .next()
+                    .unwrap();
+                assert_eq!(hash, expected);
+            }
+        }
+
+        assert_eq!(RollingHash::new(b"ab", 3).count(), 0);
+    }
+
+    #[test]
+    fn finds_adapter_windows_in_reads() {
+        let mut filter = SubstringFilter::new(12, 0.001, 100).unwrap();
+        filter.add_document(ADAPTER);
+
+        let read = format!("TTGACCATTAGG{}", &ADAPTER[..20]);
+        let hits: Vec<_> = filter.find_windows(read.as_bytes()).collect();
+        assert_eq!(hits, (12..=20).collect::<Vec<_>>());
+
+        assert!(filter.contains(&ADAPTER[5..30]));
+        assert!(!filter.contains("TTGACCATTAGGCC"));
+        assert!(!filter.contains("AGATCG"));
+    }
+
+    #[test]
+    fn scanner_agrees_with_find_windows() {
+        let mut filter = SubstringFilter::new(12, 0.001, 100).unwrap();
+        filter.add_document(ADAPTER);
+
+        let read = format!("TTGACCATTAGG{}CCGTA", &ADAPTER[..20]);
+        let mut scanner = filter.scanner();
+        let scanned: Vec<_> = read
+            .bytes()
+            .enumerate()
+            .filter_map(|(i, b)| scanner.push(b).map(|hit| (i, hit)))
+            .filter(|(_, hit)| *hit)
+            .map(|(i, _)| i + 1 - 12)
+            .collect();
+
+        let found: Vec<_> = filter.find_windows(read.as_bytes()).collect();
+        assert_eq!(scanned, found);
+    }
+
+    #[test]
+    fn rejects_empty_windows() {
+        assert!(SubstringFilter::new(0, 0.01, 100).is_err());
+    }
+}
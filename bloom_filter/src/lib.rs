@@ -1 +1,11 @@
+// The `simd` feature needs a nightly compiler for std::simd
+#![cfg_attr(feature = "simd", feature(portable_simd))]
+
 mod bloom;
+#[cfg(feature = "python")]
+mod python;
+
+pub use bloom::{
+    BloomError, BloomFilter, CountingQuotientFilter, PagedBloomFilter, PartitionKey,
+    PartitionedMembership, StripedCountingFilter, SubstringFilter, WindowScanner,
+};
@@ -0,0 +1,227 @@
+// Python bindings, built with `maturin build` (see pyproject.toml) or `--features python`
+// Items can be passed as str or bytes. Every type pickles through its to_bytes/from_bytes
+// representation.
+use std::borrow::Cow;
+
+use pyo3::exceptions::{PyIOError, PyKeyError, PyOverflowError, PyTypeError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::{PyBytes, PyString, PyTuple};
+
+use crate::{BloomError, BloomFilter, CountingQuotientFilter};
+
+impl From<BloomError> for PyErr {
+    fn from(e: BloomError) -> Self {
+        match e {
+            BloomError::Full => PyOverflowError::new_err(e.to_string()),
+            BloomError::UnknownPartition => PyKeyError::new_err(e.to_string()),
+            BloomError::Io(_) => PyIOError::new_err(e.to_string()),
+            _ => PyValueError::new_err(e.to_string()),
+        }
+    }
+}
+
+fn item_bytes<'a>(item: &'a Bound<'_, PyAny>) -> PyResult<Cow<'a, [u8]>> {
+    if let Ok(bytes) = item.downcast::<PyBytes>() {
+        Ok(Cow::Borrowed(bytes.as_bytes()))
+    } else if let Ok(string) = item.downcast::<PyString>() {
+        Ok(Cow::Owned(string.to_str()?.as_bytes().to_vec()))
+    } else {
+        Err(PyTypeError::new_err("items must be str or bytes"))
+    }
+}
+
+// pickle support: rebuild the object by calling cls.from_bytes(bytes)
+fn reduce<'py>(slf: &Bound<'py, PyAny>, bytes: &[u8]) -> PyResult<Bound<'py, PyTuple>> {
+    let py = slf.py();
+    let from_bytes = slf.get_type().getattr("from_bytes")?;
+    let args = PyTuple::new(py, [PyBytes::new(py, bytes)])?;
+    PyTuple::new(py, [from_bytes.into_any(), args.into_any()])
+}
+
+#[pyclass(name = "BloomFilter", module = "bloom_filter")]
+#[derive(Clone)]
+struct PyBloomFilter {
+    inner: BloomFilter,
+}
+
+#[pymethods]
+impl PyBloomFilter {
+    #[new]
+    fn new(fp_rate: f64, n_items: usize) -> PyResult<Self> {
+        Ok(PyBloomFilter {
+            inner: BloomFilter::new(fp_rate, n_items)?,
+        })
+    }
+
+    #[staticmethod]
+    fn with_size(size: usize, hash_count: usize) -> PyResult<Self> {
+        Ok(PyBloomFilter {
+            inner: BloomFilter::with_size(size, hash_count)?,
+        })
+    }
+
+    #[getter]
+    fn size(&self) -> usize {
+        self.inner.size()
+    }
+
+    #[getter]
+    fn hash_count(&self) -> usize {
+        self.inner.hash_count()
+    }
+
+    fn add(&mut self, item: &Bound<'_, PyAny>) -> PyResult<()> {
+        self.inner.add_item(item_bytes(item)?);
+        Ok(())
+    }
+
+    fn check(&self, item: &Bound<'_, PyAny>) -> PyResult<bool> {
+        Ok(self.inner.check(item_bytes(item)?))
+    }
+
+    fn __contains__(&self, item: &Bound<'_, PyAny>) -> PyResult<bool> {
+        self.check(item)
+    }
+
+    fn fill_ratio(&self) -> f64 {
+        self.inner.fill_ratio()
+    }
+
+    fn estimate_count(&self) -> f64 {
+        self.inner.estimate_count()
+    }
+
+    fn estimate_union(&self, other: &PyBloomFilter) -> PyResult<f64> {
+        Ok(self.inner.estimate_union(&other.inner)?)
+    }
+
+    fn estimate_intersection(&self, other: &PyBloomFilter) -> PyResult<f64> {
+        Ok(self.inner.estimate_intersection(&other.inner)?)
+    }
+
+    fn estimate_jaccard(&self, other: &PyBloomFilter) -> PyResult<f64> {
+        Ok(self.inner.estimate_jaccard(&other.inner)?)
+    }
+
+    fn fold(&self, factor: usize) -> PyResult<Self> {
+        Ok(PyBloomFilter {
+            inner: self.inner.fold(factor)?,
+        })
+    }
+
+    fn __or__(&self, other: &PyBloomFilter) -> PyResult<Self> {
+        Ok(PyBloomFilter {
+            inner: self.inner.union(&other.inner)?,
+        })
+    }
+
+    fn __and__(&self, other: &PyBloomFilter) -> PyResult<Self> {
+        Ok(PyBloomFilter {
+            inner: self.inner.intersection(&other.inner)?,
+        })
+    }
+
+    fn to_bytes<'py>(&self, py: Python<'py>) -> Bound<'py, PyBytes> {
+        PyBytes::new(py, &self.inner.to_bytes())
+    }
+
+    #[staticmethod]
+    fn from_bytes(bytes: &[u8]) -> PyResult<Self> {
+        Ok(PyBloomFilter {
+            inner: BloomFilter::from_bytes(bytes)?,
+        })
+    }
+
+    fn __reduce__<'py>(slf: &Bound<'py, Self>) -> PyResult<Bound<'py, PyTuple>> {
+        let bytes = slf.borrow().inner.to_bytes();
+        reduce(slf.as_any(), &bytes)
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "BloomFilter(size={}, hash_count={})",
+            self.inner.size(),
+            self.inner.hash_count()
+        )
+    }
+}
+
+#[pyclass(name = "CountingQuotientFilter", module = "bloom_filter")]
+struct PyCountingQuotientFilter {
+    inner: CountingQuotientFilter,
+}
+
+#[pymethods]
+impl PyCountingQuotientFilter {
+    #[new]
+    fn new(fp_rate: f64, n_items: usize) -> PyResult<Self> {
+        Ok(PyCountingQuotientFilter {
+            inner: CountingQuotientFilter::new(fp_rate, n_items)?,
+        })
+    }
+
+    #[staticmethod]
+    fn with_bits(q_bits: u32, r_bits: u32) -> PyResult<Self> {
+        Ok(PyCountingQuotientFilter {
+            inner: CountingQuotientFilter::with_bits(q_bits, r_bits)?,
+        })
+    }
+
+    #[pyo3(signature = (item, count = 1))]
+    fn insert(&mut self, item: &Bound<'_, PyAny>, count: u64) -> PyResult<()> {
+        Ok(self.inner.insert(item_bytes(item)?, count)?)
+    }
+
+    fn count(&self, item: &Bound<'_, PyAny>) -> PyResult<u64> {
+        Ok(self.inner.count(item_bytes(item)?))
+    }
+
+    #[pyo3(signature = (item, count = 1))]
+    fn remove(&mut self, item: &Bound<'_, PyAny>, count: u64) -> PyResult<u64> {
+        Ok(self.inner.remove(item_bytes(item)?, count))
+    }
+
+    fn __contains__(&self, item: &Bound<'_, PyAny>) -> PyResult<bool> {
+        Ok(self.inner.check(item_bytes(item)?))
+    }
+
+    fn __len__(&self) -> usize {
+        self.inner.len()
+    }
+
+    fn total(&self) -> u64 {
+        self.inner.total()
+    }
+
+    fn merge(&mut self, other: &PyCountingQuotientFilter) -> PyResult<()> {
+        Ok(self.inner.merge(&other.inner)?)
+    }
+
+    // (fingerprint, count) pairs in fingerprint order
+    fn items(&self) -> Vec<(u64, u64)> {
+        self.inner.iter().collect()
+    }
+
+    fn to_bytes<'py>(&self, py: Python<'py>) -> Bound<'py, PyBytes> {
+        PyBytes::new(py, &self.inner.to_bytes())
+    }
+
+    #[staticmethod]
+    fn from_bytes(bytes: &[u8]) -> PyResult<Self> {
+        Ok(PyCountingQuotientFilter {
+            inner: CountingQuotientFilter::from_bytes(bytes)?,
+        })
+    }
+
+    fn __reduce__<'py>(slf: &Bound<'py, Self>) -> PyResult<Bound<'py, PyTuple>> {
+        let bytes = slf.borrow().inner.to_bytes();
+        reduce(slf.as_any(), &bytes)
+    }
+}
+
+#[pymodule]
+fn bloom_filter(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PyBloomFilter>()?;
+    m.add_class::<PyCountingQuotientFilter>()?;
+    Ok(())
+}
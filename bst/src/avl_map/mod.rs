@@ -0,0 +1,505 @@
+use std::borrow::Borrow;
+use std::cmp::Ordering;
+use std::fmt;
+use std::iter::FusedIterator;
+use std::mem;
+use std::ops::{Bound, RangeBounds};
+
+// An AVL tree: a binary search tree that rebalances itself after every insert and remove
+// Every node remembers the height of its subtree, and the two subtrees of any node may differ in
+// height by at most 1. When an insert or remove breaks that, one or two rotations on the way back
+// up fix it. A rotation turns a node's child into its parent without changing the key order:
+//
+//   rotate_right:    d            b
+//                   / \          / \
+//                  b   e   =>   a   d
+//                 / \              / \
+//                a   c            c   e
+//
+// Keeping the heights that close means the tree is never more than ~1.44 * log2(n) tall, so
+// lookups stay O(log n) even for sorted inserts, which turn a plain BstMap into a linked list.
+//
+// Because the height is bounded, insert and remove can simply recurse: they're never more than
+// a few dozen calls deep
+pub struct AvlMap<K, V> {
+    root: Link<K, V>,
+    len: usize,
+}
+
+type Link<K, V> = Option<Box<Node<K, V>>>;
+
+struct Node<K, V> {
+    key: K,
+    value: V,
+    // height of the subtree rooted here, a leaf is 1
+    height: u8,
+    left: Link<K, V>,
+    right: Link<K, V>,
+}
+
+// In-order iteration with an explicit stack, like BstMapIter, optionally stopping at a key
+pub struct AvlMapIter<'a, K, V> {
+    stack: Vec<&'a Node<K, V>>,
+    // where a range stops, Unbounded for a plain iter()
+    end: Bound<&'a K>,
+    // how many entries are left at most; exact for a plain iter(), but a range can't know where
+    // it ends without walking there
+    remaining: usize,
+    exact: bool,
+}
+
+impl<K: Ord, V> AvlMap<K, V> {
+    pub fn new() -> Self {
+        AvlMap { root: None, len: 0 }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    // The number of nodes on the longest path from the root down, 0 for an empty map
+    pub fn height(&self) -> usize {
+        height(&self.root).into()
+    }
+
+    // Returns the old value if key was already in the map (the key itself isn't replaced)
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        let old = insert(&mut self.root, key, value);
+        if old.is_none() {
+            self.len += 1;
+        }
+        old
+    }
+
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let mut next = self.root.as_deref();
+        while let Some(node) = next {
+            next = match key.cmp(node.key.borrow()) {
+                Ordering::Less => node.left.as_deref(),
+                Ordering::Greater => node.right.as_deref(),
+                Ordering::Equal => return Some(&node.value),
+            };
+        }
+        None
+    }
+
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.get(key).is_some()
+    }
+
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let (_, value) = remove(&mut self.root, key)?;
+        self.len -= 1;
+        Some(value)
+    }
+
+    pub fn iter(&self) -> AvlMapIter<'_, K, V> {
+        AvlMapIter {
+            stack: self.seek(|_| true),
+            end: Bound::Unbounded,
+            remaining: self.len,
+            exact: true,
+        }
+    }
+
+    // The (key, value) pairs with keys inside range, in key order
+    // Finding the start is an O(log n) walk down the tree, after that it's the usual in-order walk
+    pub fn range<Q, R>(&self, range: R) -> AvlMapIter<'_, K, V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+        R: RangeBounds<Q>,
+    {
+        let stack = match range.start_bound() {
+            Bound::Unbounded => self.seek(|_| true),
+            Bound::Included(start) => self.seek(|k| k.borrow() >= start),
+            Bound::Excluded(start) => self.seek(|k| k.borrow() > start),
+        };
+        // the end bound has to borrow from the map (the iterator outlives range), so we find the
+        // first key past the end and stop when we get to it
+        let past_end = match range.end_bound() {
+            Bound::Unbounded => None,
+            Bound::Included(end) => self.seek(|k| k.borrow() > end).pop(),
+            Bound::Excluded(end) => self.seek(|k| k.borrow() >= end).pop(),
+        };
+        AvlMapIter {
+            stack,
+            end: past_end.map_or(Bound::Unbounded, |node| Bound::Excluded(&node.key)),
+            remaining: self.len,
+            exact: false,
+        }
+    }
+
+    // Walks down to the first key matching pred, which must be false for a prefix of the keys
+    // and true for the rest. Returns the path of nodes matching pred, the first such key on top:
+    // exactly the stack an in-order iterator starting at that key needs
+    fn seek(&self, pred: impl Fn(&K) -> bool) -> Vec<&Node<K, V>> {
+        let mut stack = Vec::new();
+        let mut next = self.root.as_deref();
+        while let Some(node) = next {
+            if pred(&node.key) {
+                stack.push(node);
+                next = node.left.as_deref();
+            } else {
+                next = node.right.as_deref();
+            }
+        }
+        stack
+    }
+
+    // Checks everything that makes this an AVL tree: keys in order, stored heights correct,
+    // sibling heights within 1 of each other and len matching the number of nodes
+    // It's O(n), meant for tests rather than for everyday use
+    pub fn check_invariants(&self) -> Result<(), String> {
+        let mut count = 0;
+        check(&self.root, None, None, &mut count)?;
+        if count != self.len {
+            return Err(format!(
+                "len is {} but the tree has {count} nodes",
+                self.len
+            ));
+        }
+        Ok(())
+    }
+}
+
+fn height<K, V>(link: &Link<K, V>) -> u8 {
+    link.as_ref().map_or(0, |node| node.height)
+}
+
+fn update_height<K, V>(node: &mut Node<K, V>) {
+    node.height = 1 + height(&node.left).max(height(&node.right));
+}
+
+// How much taller the left subtree is than the right one
+fn balance<K, V>(node: &Node<K, V>) -> i16 {
+    i16::from(height(&node.left)) - i16::from(height(&node.right))
+}
+
+// link's node's left child takes its place, see the picture at the top
+fn rotate_right<K, V>(link: &mut Link<K, V>) {
+    let mut node = link.take().expect("rotating a non-empty subtree");
+    let mut left = node.left.take().expect("rotate_right needs a left child");
+    node.left = left.right.take();
+    update_height(&mut node);
+    left.right = Some(node);
+    update_height(&mut left);
+    *link = Some(left);
+}
+
+fn rotate_left<K, V>(link: &mut Link<K, V>) {
+    let mut node = link.take().expect("rotating a non-empty subtree");
+    let mut right = node.right.take().expect("rotate_left needs a right child");
+    node.right = right.left.take();
+    update_height(&mut node);
+    right.left = Some(node);
+    update_height(&mut right);
+    *link = Some(right);
+}
+
+// Called on every node on the way back up from an insert or remove, once its children are
+// balanced. Its subtrees can now differ by 2, and there are two shapes that needs fixing:
+//  - the taller child leans the same way (left-left): one rotation the other way
+//  - the taller child leans the other way (left-right): first rotate the child so it leans the
+//    same way, then it's the first case
+fn rebalance<K, V>(link: &mut Link<K, V>) {
+    let Some(node) = link.as_mut() else {
+        return;
+    };
+    update_height(node);
+    match balance(node) {
+        2 => {
+            if balance(node.left.as_ref().expect("left is taller")) < 0 {
+                rotate_left(&mut node.left);
+            }
+            rotate_right(link);
+        }
+        -2 => {
+            if balance(node.right.as_ref().expect("right is taller")) > 0 {
+                rotate_right(&mut node.right);
+            }
+            rotate_left(link);
+        }
+        _ => {}
+    }
+}
+
+fn insert<K: Ord, V>(link: &mut Link<K, V>, key: K, value: V) -> Option<V> {
+    let Some(node) = link else {
+        *link = Some(Box::new(Node {
+            key,
+            value,
+            height: 1,
+            left: None,
+            right: None,
+        }));
+        return None;
+    };
+    let old = match key.cmp(&node.key) {
+        Ordering::Less => insert(&mut node.left, key, value),
+        Ordering::Greater => insert(&mut node.right, key, value),
+        // nothing changes shape, so nothing above needs rebalancing either
+        Ordering::Equal => return Some(mem::replace(&mut node.value, value)),
+    };
+    rebalance(link);
+    old
+}
+
+fn remove<K, V, Q>(link: &mut Link<K, V>, key: &Q) -> Option<(K, V)>
+where
+    K: Borrow<Q>,
+    Q: Ord + ?Sized,
+{
+    let node = link.as_mut()?;
+    let removed = match key.cmp(node.key.borrow()) {
+        Ordering::Less => remove(&mut node.left, key),
+        Ordering::Greater => remove(&mut node.right, key),
+        Ordering::Equal => {
+            // the same three cases as BstMap's unlink, the successor comes out of the right
+            // subtree through take_min, which rebalances on its way back up
+            let mut node = link.take().expect("matched Some above");
+            *link = match (node.left.take(), node.right.take()) {
+                (None, None) => None,
+                (Some(child), None) | (None, Some(child)) => Some(child),
+                (Some(left), Some(right)) => {
+                    let mut right = Some(right);
+                    let mut successor = take_min(&mut right);
+                    successor.left = Some(left);
+                    successor.right = right;
+                    Some(successor)
+                }
+            };
+            Some((node.key, node.value))
+        }
+    };
+    rebalance(link);
+    removed
+}
+
+// Takes the leftmost node out of a non-empty subtree, rebalancing every node above it
+fn take_min<K, V>(link: &mut Link<K, V>) -> Box<Node<K, V>> {
+    let node = link.as_mut().expect("take_min needs a non-empty subtree");
+    if node.left.is_some() {
+        let min = take_min(&mut node.left);
+        rebalance(link);
+        min
+    } else {
+        let mut min = link.take().expect("checked above");
+        *link = min.right.take();
+        min
+    }
+}
+
+// Every key in the subtree has to sit strictly between lo and hi (the keys of the ancestors we
+// went right and left at). Returns the subtree's real height
+fn check<K: Ord, V>(
+    link: &Link<K, V>,
+    lo: Option<&K>,
+    hi: Option<&K>,
+    count: &mut usize,
+) -> Result<u8, String> {
+    let Some(node) = link else {
+        return Ok(0);
+    };
+    *count += 1;
+    if lo.is_some_and(|lo| node.key <= *lo) || hi.is_some_and(|hi| node.key >= *hi) {
+        return Err(format!("node {count} is out of key order"));
+    }
+    let left = check(&node.left, lo, Some(&node.key), count)?;
+    let right = check(&node.right, Some(&node.key), hi, count)?;
+    if left.abs_diff(right) > 1 {
+        return Err(format!(
+            "subtree heights {left} and {right} differ by more than 1"
+        ));
+    }
+    let height = 1 + left.max(right);
+    if node.height != height {
+        return Err(format!("stored height {} should be {height}", node.height));
+    }
+    Ok(height)
+}
+
+impl<K: Ord, V> Default for AvlMap<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: Ord, V> Extend<(K, V)> for AvlMap<K, V> {
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        for (key, value) in iter {
+            self.insert(key, value);
+        }
+    }
+}
+
+impl<K: Ord, V> FromIterator<(K, V)> for AvlMap<K, V> {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut map = AvlMap::new();
+        map.extend(iter);
+        map
+    }
+}
+
+impl<K: Ord + fmt::Debug, V: fmt::Debug> fmt::Debug for AvlMap<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<'a, K: Ord, V> IntoIterator for &'a AvlMap<K, V> {
+    type Item = (&'a K, &'a V);
+    type IntoIter = AvlMapIter<'a, K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, K: Ord, V> Iterator for AvlMapIter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.stack.pop()?;
+        if let Bound::Excluded(end) = self.end {
+            if node.key >= *end {
+                self.stack.clear();
+                self.remaining = 0;
+                return None;
+            }
+        }
+        let mut next = node.right.as_deref();
+        while let Some(child) = next {
+            self.stack.push(child);
+            next = child.left.as_deref();
+        }
+        self.remaining -= 1;
+        Some((&node.key, &node.value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match (self.stack.is_empty(), self.exact) {
+            (true, _) => (0, Some(0)),
+            (false, true) => (self.remaining, Some(self.remaining)),
+            (false, false) => (0, Some(self.remaining)),
+        }
+    }
+}
+
+// Once the stack is empty it stays empty
+impl<K: Ord, V> FusedIterator for AvlMapIter<'_, K, V> {}
+
+#[cfg(test)]
+mod testing {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn can_insert_get_and_remove() {
+        let mut map = AvlMap::new();
+        assert_eq!(None, map.insert("b", 2));
+        assert_eq!(None, map.insert("a", 1));
+        assert_eq!(None, map.insert("c", 3));
+        assert_eq!(Some(2), map.insert("b", 20));
+        assert_eq!(3, map.len());
+        assert_eq!(Some(&20), map.get("b"));
+        assert_eq!(r#"{"a": 1, "b": 20, "c": 3}"#, format!("{map:?}"));
+
+        assert_eq!(Some(20), map.remove("b"));
+        assert_eq!(None, map.remove("b"));
+        assert!(!map.contains_key("b"));
+        assert_eq!(2, map.len());
+        map.check_invariants().unwrap();
+    }
+
+    #[test]
+    fn sorted_inserts_stay_balanced() {
+        let map: AvlMap<u32, ()> = (0..100_000).map(|k| (k, ())).collect();
+        map.check_invariants().unwrap();
+        // a perfectly balanced tree of 100k nodes is 17 tall, AVL promises < 1.44x that
+        assert!(map.height() <= 24, "height {}", map.height());
+
+        let mut map = map;
+        for k in (0..100_000).step_by(2) {
+            map.remove(&k);
+        }
+        map.check_invariants().unwrap();
+        assert!(map.height() <= 23);
+    }
+
+    #[test]
+    fn can_iterate_ranges() {
+        let map: AvlMap<u32, u32> = (0..50).map(|k| (k * 2, k)).collect();
+        let keys = |r: AvlMapIter<'_, u32, u32>| r.map(|(k, _)| *k).collect::<Vec<_>>();
+
+        assert_eq!(vec![10, 12, 14], keys(map.range(10..16)));
+        assert_eq!(vec![10, 12, 14, 16], keys(map.range(10..=16)));
+        assert_eq!(vec![12, 14, 16], keys(map.range(11..17)));
+        assert_eq!(vec![94, 96, 98], keys(map.range(93..)));
+        assert_eq!(vec![0, 2], keys(map.range(..=3)));
+        assert_eq!(
+            vec![12, 14],
+            keys(map.range((Bound::Excluded(10), Bound::Excluded(16))))
+        );
+        assert!(keys(map.range(200..)).is_empty());
+        assert!(keys(map.range(5..5)).is_empty());
+        assert_eq!(50, map.range::<u32, _>(..).count());
+        assert_eq!((50, Some(50)), map.iter().size_hint());
+    }
+
+    #[test]
+    fn invariant_checks_catch_broken_trees() {
+        let mut map: AvlMap<u32, ()> = (0..3).map(|k| (k, ())).collect();
+        map.root.as_mut().unwrap().height = 5;
+        assert!(map.check_invariants().is_err());
+
+        // the root (1) becomes bigger than its right child (2)
+        let mut map: AvlMap<u32, ()> = (0..3).map(|k| (k, ())).collect();
+        map.root.as_mut().unwrap().key = 7;
+        assert!(map.check_invariants().is_err());
+
+        let mut map: AvlMap<u32, ()> = (0..3).map(|k| (k, ())).collect();
+        map.len = 4;
+        assert!(map.check_invariants().is_err());
+    }
+
+    #[test]
+    fn matches_a_btree_map() {
+        let mut map = AvlMap::new();
+        let mut model = BTreeMap::new();
+        let mut x: u64 = 1;
+        for step in 0..5_000 {
+            x = x.wrapping_mul(6364136223846793005).wrapping_add(1);
+            let key = (x >> 33) % 500;
+            if step % 3 == 0 {
+                assert_eq!(model.remove(&key), map.remove(&key));
+            } else {
+                assert_eq!(model.insert(key, step), map.insert(key, step));
+            }
+            if step % 100 == 0 {
+                map.check_invariants().unwrap();
+            }
+        }
+        map.check_invariants().unwrap();
+        assert_eq!(model.len(), map.len());
+        assert!(model.iter().eq(map.iter()));
+        assert!(model.range(100..200).eq(map.range(100..200)));
+        assert!(model.range(..=250).eq(map.range(..=250)));
+    }
+}
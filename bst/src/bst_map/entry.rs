@@ -0,0 +1,182 @@
+use super::{unlink, Link, Node};
+
+// An entry is the result of looking a key up once: either the slot holding its node
+// (Occupied) or the empty slot where a node with that key belongs (Vacant)
+// Both keep a &mut to the slot, so inserting or removing through them is O(1) with no second
+// search. They also borrow the map's len, which is a different field than the root they borrowed
+// the slot from, so the borrow checker is happy with both at once
+pub enum Entry<'a, K, V> {
+    Occupied(OccupiedEntry<'a, K, V>),
+    Vacant(VacantEntry<'a, K, V>),
+}
+
+pub struct OccupiedEntry<'a, K, V> {
+    // always Some
+    link: &'a mut Link<K, V>,
+    len: &'a mut usize,
+}
+
+pub struct VacantEntry<'a, K, V> {
+    key: K,
+    // always None
+    link: &'a mut Link<K, V>,
+    len: &'a mut usize,
+}
+
+impl<'a, K: Ord, V> Entry<'a, K, V> {
+    pub fn key(&self) -> &K {
+        match self {
+            Entry::Occupied(entry) => entry.key(),
+            Entry::Vacant(entry) => entry.key(),
+        }
+    }
+
+    pub fn or_insert(self, default: V) -> &'a mut V {
+        self.or_insert_with(|| default)
+    }
+
+    pub fn or_insert_with(self, default: impl FnOnce() -> V) -> &'a mut V {
+        match self {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(default()),
+        }
+    }
+
+    pub fn or_default(self) -> &'a mut V
+    where
+        V: Default,
+    {
+        self.or_insert_with(V::default)
+    }
+
+    // Changes the value if there is one, does nothing to a vacant entry
+    pub fn and_modify(mut self, f: impl FnOnce(&mut V)) -> Self {
+        if let Entry::Occupied(entry) = &mut self {
+            f(entry.get_mut());
+        }
+        self
+    }
+}
+
+impl<'a, K: Ord, V> OccupiedEntry<'a, K, V> {
+    pub(super) fn new(link: &'a mut Link<K, V>, len: &'a mut usize) -> Self {
+        OccupiedEntry { link, len }
+    }
+
+    fn node(&self) -> &Node<K, V> {
+        self.link
+            .as_deref()
+            .expect("an occupied entry's slot holds a node")
+    }
+
+    fn node_mut(&mut self) -> &mut Node<K, V> {
+        self.link
+            .as_deref_mut()
+            .expect("an occupied entry's slot holds a node")
+    }
+
+    pub fn key(&self) -> &K {
+        &self.node().key
+    }
+
+    pub fn get(&self) -> &V {
+        &self.node().value
+    }
+
+    pub fn get_mut(&mut self) -> &mut V {
+        &mut self.node_mut().value
+    }
+
+    // Like get_mut, but the reference lives as long as the map borrow rather than the entry
+    pub fn into_mut(self) -> &'a mut V {
+        &mut self
+            .link
+            .as_deref_mut()
+            .expect("an occupied entry's slot holds a node")
+            .value
+    }
+
+    // Replaces the value and returns the old one
+    pub fn insert(&mut self, value: V) -> V {
+        std::mem::replace(self.get_mut(), value)
+    }
+
+    pub fn remove(self) -> V {
+        self.remove_entry().1
+    }
+
+    pub fn remove_entry(self) -> (K, V) {
+        *self.len -= 1;
+        let node = unlink(self.link);
+        (node.key, node.value)
+    }
+}
+
+impl<'a, K: Ord, V> VacantEntry<'a, K, V> {
+    pub(super) fn new(key: K, link: &'a mut Link<K, V>, len: &'a mut usize) -> Self {
+        VacantEntry { key, link, len }
+    }
+
+    pub fn key(&self) -> &K {
+        &self.key
+    }
+
+    pub fn into_key(self) -> K {
+        self.key
+    }
+
+    // A new node always goes in as a leaf, in the empty slot the search ended on
+    pub fn insert(self, value: V) -> &'a mut V {
+        *self.len += 1;
+        let node = self.link.insert(Box::new(Node {
+            key: self.key,
+            value,
+            left: None,
+            right: None,
+        }));
+        &mut node.value
+    }
+}
+
+#[cfg(test)]
+mod testing {
+    use crate::{BstMap, Entry};
+
+    #[test]
+    fn can_count_with_entries() {
+        let mut counts = BstMap::new();
+        for word in "the cat and the hat and the bat".split(' ') {
+            *counts.entry(word).or_insert(0) += 1;
+        }
+        assert_eq!(Some(&3), counts.get("the"));
+        assert_eq!(Some(&2), counts.get("and"));
+        assert_eq!(5, counts.len());
+    }
+
+    #[test]
+    fn can_use_occupied_and_vacant_entries() {
+        let mut map: BstMap<&str, i32> = BstMap::new();
+        match map.entry("a") {
+            Entry::Vacant(entry) => {
+                assert_eq!(&"a", entry.key());
+                *entry.insert(1) += 1;
+            }
+            Entry::Occupied(_) => panic!("map is empty"),
+        }
+        assert_eq!(Some(&2), map.get("a"));
+
+        map.entry("a").and_modify(|v| *v *= 10).or_insert(0);
+        map.entry("b").and_modify(|v| *v *= 10).or_insert(7);
+        assert_eq!(*map.entry("c").or_default(), 0);
+        assert_eq!(r#"{"a": 20, "b": 7, "c": 0}"#, format!("{map:?}"));
+
+        let Entry::Occupied(mut entry) = map.entry("b") else {
+            panic!("b was inserted above");
+        };
+        assert_eq!((&"b", &7), (entry.key(), entry.get()));
+        assert_eq!(7, entry.insert(8));
+        assert_eq!(("b", 8), entry.remove_entry());
+        assert_eq!(2, map.len());
+        assert!(!map.contains_key("b"));
+    }
+}
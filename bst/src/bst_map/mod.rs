@@ -0,0 +1,499 @@
+mod entry;
+
+use std::borrow::Borrow;
+use std::cmp::Ordering;
+use std::fmt;
+use std::iter::FusedIterator;
+
+pub use entry::{Entry, OccupiedEntry, VacantEntry};
+
+// A binary search tree map: every node has up to two children, everything in its left subtree
+// has a smaller key and everything in its right subtree a bigger one
+//
+//          5
+//        /   \
+//       2     8
+//      / \     \
+//     1   4     9
+//
+// Finding a key is a walk down from the root, going left or right at every node, so it takes as
+// many steps as the tree is tall. For keys inserted in random order that's O(log n), but nothing
+// here keeps the tree balanced: insert keys in sorted order and every node only has a right
+// child, which is just a linked list with extra steps (O(n) per lookup).
+//
+// Unlike the doubly linked list, every node has exactly one owner (its parent, or the map for
+// the root), so plain Box is enough and there's no unsafe code at all
+pub struct BstMap<K, V> {
+    root: Link<K, V>,
+    len: usize,
+}
+
+// The slot a node hangs from: the map's root or one of a node's children
+// Most of the map works on &mut Link rather than on nodes, because a slot is what you need to
+// put a new node in or take an old one out
+type Link<K, V> = Option<Box<Node<K, V>>>;
+
+struct Node<K, V> {
+    key: K,
+    value: V,
+    left: Link<K, V>,
+    right: Link<K, V>,
+}
+
+// In-order (sorted by key) iteration without recursion
+// stack holds the nodes we've gone left past but not visited yet, the top is the next one. After
+// visiting a node we push its right child and that child's whole left spine, which are the nodes
+// that come next. The stack is never deeper than the tree is tall
+pub struct BstMapIter<'a, K, V> {
+    stack: Vec<&'a Node<K, V>>,
+    remaining: usize,
+}
+
+// The same walk, but the map gave us its nodes, so we take the right child out of each node as
+// we visit it and the rest of the node is freed
+pub struct BstMapIntoIter<K, V> {
+    stack: Vec<Box<Node<K, V>>>,
+    remaining: usize,
+}
+
+impl<K: Ord, V> BstMap<K, V> {
+    pub fn new() -> Self {
+        BstMap { root: None, len: 0 }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    // Returns the old value if key was already in the map (the key itself isn't replaced)
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        match self.entry(key) {
+            Entry::Occupied(mut entry) => Some(entry.insert(value)),
+            Entry::Vacant(entry) => {
+                entry.insert(value);
+                None
+            }
+        }
+    }
+
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        // read only lookups don't need to hand out a slot, so they just follow the references
+        let mut next = self.root.as_deref();
+        while let Some(node) = next {
+            next = match key.cmp(node.key.borrow()) {
+                Ordering::Less => node.left.as_deref(),
+                Ordering::Greater => node.right.as_deref(),
+                Ordering::Equal => return Some(&node.value),
+            };
+        }
+        None
+    }
+
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        find_link(&mut self.root, key)
+            .as_mut()
+            .map(|node| &mut node.value)
+    }
+
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.get(key).is_some()
+    }
+
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.remove_entry(key).map(|(_, value)| value)
+    }
+
+    pub fn remove_entry<Q>(&mut self, key: &Q) -> Option<(K, V)>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let link = find_link(&mut self.root, key);
+        link.as_ref()?;
+        self.len -= 1;
+        let node = unlink(link);
+        Some((node.key, node.value))
+    }
+
+    // Looks key up once and hands back the slot it's in (or belongs in), so inserting or updating
+    // afterwards doesn't need a second walk down the tree
+    pub fn entry(&mut self, key: K) -> Entry<'_, K, V> {
+        let BstMap { root, len } = self;
+        let link = find_link(root, &key);
+        if link.is_some() {
+            Entry::Occupied(OccupiedEntry::new(link, len))
+        } else {
+            Entry::Vacant(VacantEntry::new(key, link, len))
+        }
+    }
+
+    pub fn first_key_value(&self) -> Option<(&K, &V)> {
+        let mut node = self.root.as_deref()?;
+        while let Some(left) = node.left.as_deref() {
+            node = left;
+        }
+        Some((&node.key, &node.value))
+    }
+
+    pub fn last_key_value(&self) -> Option<(&K, &V)> {
+        let mut node = self.root.as_deref()?;
+        while let Some(right) = node.right.as_deref() {
+            node = right;
+        }
+        Some((&node.key, &node.value))
+    }
+
+    pub fn iter(&self) -> BstMapIter<'_, K, V> {
+        let mut iter = BstMapIter {
+            stack: Vec::new(),
+            remaining: self.len,
+        };
+        iter.push_left_spine(self.root.as_deref());
+        iter
+    }
+
+    // The number of nodes on the longest path from the root down, 0 for an empty map
+    // Sorted inserts make this len(), random ones keep it around 2-3x log2(len)
+    pub fn height(&self) -> usize {
+        // breadth first, one level at a time, so a degenerate tree doesn't recurse len() deep
+        let mut level: Vec<&Node<K, V>> = self.root.as_deref().into_iter().collect();
+        let mut height = 0;
+        while !level.is_empty() {
+            height += 1;
+            level = level
+                .iter()
+                .flat_map(|node| [node.left.as_deref(), node.right.as_deref()])
+                .flatten()
+                .collect();
+        }
+        height
+    }
+}
+
+// Walks down from link to the slot where key is, or where it would go if it isn't in the tree
+// The loop looks at each node through a shared borrow and decides whether to stop before it
+// re-borrows the slot mutably to step into it. Holding a &mut node while deciding whether to
+// return link is fine, but it's a case the borrow checker can't (yet) see is fine
+fn find_link<'a, K, V, Q>(mut link: &'a mut Link<K, V>, key: &Q) -> &'a mut Link<K, V>
+where
+    K: Borrow<Q>,
+    Q: Ord + ?Sized,
+{
+    while let Some(node) = link.as_deref() {
+        let ordering = key.cmp(node.key.borrow());
+        if ordering == Ordering::Equal {
+            break;
+        }
+        let node = link.as_mut().expect("checked above");
+        link = match ordering {
+            Ordering::Less => &mut node.left,
+            _ => &mut node.right,
+        };
+    }
+    link
+}
+
+// Takes the node out of a non-empty slot and fills the slot with what's left of its subtree
+//  - no children: the slot is simply emptied
+//  - one child: the child moves up into the slot
+//  - two children: the smallest node of the right subtree (the next key in order) moves up into
+//    the slot and takes over both children. It can't have a left child, so taking it out of the
+//    right subtree is the easy one-child case again
+fn unlink<K, V>(link: &mut Link<K, V>) -> Box<Node<K, V>> {
+    let mut node = link.take().expect("unlink needs a non-empty slot");
+    *link = match (node.left.take(), node.right.take()) {
+        (None, None) => None,
+        (Some(child), None) | (None, Some(child)) => Some(child),
+        (Some(left), Some(right)) => {
+            let mut right = Some(right);
+            let mut successor = take_min(&mut right);
+            successor.left = Some(left);
+            successor.right = right;
+            Some(successor)
+        }
+    };
+    node
+}
+
+// Takes the leftmost node out of a non-empty subtree, its right child moves up into its slot
+fn take_min<K, V>(mut link: &mut Link<K, V>) -> Box<Node<K, V>> {
+    while link.as_ref().is_some_and(|node| node.left.is_some()) {
+        link = &mut link.as_mut().expect("checked above").left;
+    }
+    let mut min = link.take().expect("take_min needs a non-empty subtree");
+    *link = min.right.take();
+    min
+}
+
+// Dropping the root Box would drop its children, which drop theirs, and so on: one stack frame
+// per level, so a degenerate (sorted insert) tree with a lot of nodes overflows the stack
+// Instead we keep the nodes we still have to free on our own stack and detach each node's
+// children before it's dropped
+impl<K, V> Drop for BstMap<K, V> {
+    fn drop(&mut self) {
+        let mut stack: Vec<Box<Node<K, V>>> = self.root.take().into_iter().collect();
+        while let Some(mut node) = stack.pop() {
+            stack.extend(node.left.take());
+            stack.extend(node.right.take());
+        }
+    }
+}
+
+impl<K: Ord, V> Default for BstMap<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: Ord, V> Extend<(K, V)> for BstMap<K, V> {
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        for (key, value) in iter {
+            self.insert(key, value);
+        }
+    }
+}
+
+impl<K: Ord, V> FromIterator<(K, V)> for BstMap<K, V> {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut map = BstMap::new();
+        map.extend(iter);
+        map
+    }
+}
+
+impl<K: Ord + fmt::Debug, V: fmt::Debug> fmt::Debug for BstMap<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<'a, K: Ord, V> IntoIterator for &'a BstMap<K, V> {
+    type Item = (&'a K, &'a V);
+    type IntoIter = BstMapIter<'a, K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<K: Ord, V> IntoIterator for BstMap<K, V> {
+    type Item = (K, V);
+    type IntoIter = BstMapIntoIter<K, V>;
+
+    fn into_iter(mut self) -> Self::IntoIter {
+        let mut iter = BstMapIntoIter {
+            stack: Vec::new(),
+            remaining: self.len,
+        };
+        iter.push_left_spine(self.root.take());
+        iter
+    }
+}
+
+impl<'a, K, V> BstMapIter<'a, K, V> {
+    fn push_left_spine(&mut self, mut next: Option<&'a Node<K, V>>) {
+        while let Some(node) = next {
+            self.stack.push(node);
+            next = node.left.as_deref();
+        }
+    }
+}
+
+impl<'a, K, V> Iterator for BstMapIter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.stack.pop()?;
+        self.push_left_spine(node.right.as_deref());
+        self.remaining -= 1;
+        Some((&node.key, &node.value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<K, V> ExactSizeIterator for BstMapIter<'_, K, V> {}
+impl<K, V> FusedIterator for BstMapIter<'_, K, V> {}
+
+impl<K, V> BstMapIntoIter<K, V> {
+    fn push_left_spine(&mut self, mut next: Link<K, V>) {
+        while let Some(mut node) = next {
+            next = node.left.take();
+            self.stack.push(node);
+        }
+    }
+}
+
+impl<K, V> Iterator for BstMapIntoIter<K, V> {
+    type Item = (K, V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut node = self.stack.pop()?;
+        self.push_left_spine(node.right.take());
+        self.remaining -= 1;
+        Some((node.key, node.value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<K, V> ExactSizeIterator for BstMapIntoIter<K, V> {}
+impl<K, V> FusedIterator for BstMapIntoIter<K, V> {}
+
+// Anything left on the stack still owns its right subtree, so dropping a half used iterator
+// has the same deep recursion problem as dropping the map
+impl<K, V> Drop for BstMapIntoIter<K, V> {
+    fn drop(&mut self) {
+        while let Some(mut node) = self.stack.pop() {
+            self.stack.extend(node.left.take());
+            self.stack.extend(node.right.take());
+        }
+    }
+}
+
+#[cfg(test)]
+mod testing {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn can_insert_get_and_remove() {
+        let mut map = BstMap::new();
+        assert_eq!(None, map.insert(5, "five"));
+        assert_eq!(None, map.insert(2, "two"));
+        assert_eq!(None, map.insert(8, "eight"));
+        assert_eq!(Some("two"), map.insert(2, "TWO"));
+        assert_eq!(3, map.len());
+
+        assert_eq!(Some(&"TWO"), map.get(&2));
+        assert_eq!(None, map.get(&3));
+        *map.get_mut(&8).unwrap() = "EIGHT";
+        assert_eq!(r#"{2: "TWO", 5: "five", 8: "EIGHT"}"#, format!("{map:?}"));
+
+        // 5 is the root and has two children
+        assert_eq!(Some("five"), map.remove(&5));
+        assert_eq!(None, map.remove(&5));
+        assert!(!map.contains_key(&5));
+        assert_eq!(
+            vec![(&2, &"TWO"), (&8, &"EIGHT")],
+            map.iter().collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn can_look_up_borrowed_keys() {
+        let mut map: BstMap<String, i32> = BstMap::new();
+        map.insert("b".to_string(), 2);
+        map.insert("a".to_string(), 1);
+        assert_eq!(Some(&1), map.get("a"));
+        assert_eq!(Some(("b".to_string(), 2)), map.remove_entry("b"));
+    }
+
+    #[test]
+    fn removing_every_shape_of_node() {
+        //          5
+        //        /   \
+        //       2     8
+        //      / \   / \
+        //     1   4 6   9
+        //        /   \
+        //       3     7
+        let keys = [5, 2, 8, 1, 4, 6, 9, 3, 7];
+        for remove in keys {
+            let mut map: BstMap<i32, ()> = keys.iter().map(|&k| (k, ())).collect();
+            map.remove(&remove);
+            let expected: Vec<i32> = (1..=9).filter(|&k| k != remove).collect();
+            assert_eq!(expected, map.iter().map(|(k, _)| *k).collect::<Vec<_>>());
+            assert_eq!(8, map.len());
+        }
+    }
+
+    #[test]
+    fn iterates_in_key_order() {
+        let map: BstMap<i32, i32> = [4, 1, 3, 5, 2].into_iter().map(|k| (k, k * 10)).collect();
+        let mut iter = map.iter();
+        assert_eq!(5, iter.len());
+        assert_eq!(Some((&1, &10)), iter.next());
+        assert_eq!(4, iter.len());
+        assert_eq!(vec![2, 3, 4, 5], iter.map(|(k, _)| *k).collect::<Vec<_>>());
+        assert_eq!(
+            (Some((&1, &10)), Some((&5, &50))),
+            (map.first_key_value(), map.last_key_value())
+        );
+
+        let owned: Vec<(i32, i32)> = map.into_iter().collect();
+        assert_eq!(vec![(1, 10), (2, 20), (3, 30), (4, 40), (5, 50)], owned);
+    }
+
+    #[test]
+    fn sorted_inserts_make_a_tall_tree() {
+        let sorted: BstMap<u32, ()> = (0..1_000).map(|k| (k, ())).collect();
+        assert_eq!(1_000, sorted.height());
+
+        let mut x: u32 = 1;
+        let shuffled: BstMap<u32, ()> = (0..1_000)
+            .map(|_| {
+                x ^= x << 13;
+                x ^= x >> 17;
+                x ^= x << 5;
+                (x, ())
+            })
+            .collect();
+        assert!(shuffled.height() < 40);
+        assert_eq!(0, BstMap::<u32, ()>::new().height());
+    }
+
+    #[test]
+    fn can_drop_degenerate_trees() {
+        // every node is the right child of the one before, so a recursive drop would go 10k deep
+        let map: BstMap<u32, u32> = (0..10_000).map(|k| (k, k)).collect();
+        let mut iter = map.into_iter();
+        assert_eq!(Some((0, 0)), iter.next());
+        drop(iter);
+
+        let map: BstMap<u32, u32> = (0..10_000).rev().map(|k| (k, k)).collect();
+        drop(map);
+    }
+
+    #[test]
+    fn matches_a_btree_map() {
+        let mut map = BstMap::new();
+        let mut model = BTreeMap::new();
+        let mut x: u64 = 1;
+        for step in 0..5_000 {
+            x = x.wrapping_mul(6364136223846793005).wrapping_add(1);
+            let key = (x >> 33) % 300;
+            match step % 3 {
+                0 => assert_eq!(model.remove(&key), map.remove(&key)),
+                1 => assert_eq!(model.get(&key), map.get(&key)),
+                _ => assert_eq!(model.insert(key, step), map.insert(key, step)),
+            }
+        }
+        assert_eq!(model.len(), map.len());
+        assert!(model.iter().eq(map.iter()));
+    }
+}
@@ -0,0 +1,5 @@
+mod avl_map;
+mod bst_map;
+
+pub use avl_map::{AvlMap, AvlMapIter};
+pub use bst_map::{BstMap, BstMapIntoIter, BstMapIter, Entry, OccupiedEntry, VacantEntry};
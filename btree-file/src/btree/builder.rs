@@ -0,0 +1,205 @@
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use binio::HeaderWriter;
+
+use super::page::{capacities, Header, CHILD_SIZE, INTERNAL, LEAF, NODE_HEADER_SIZE, PAGE_SIZE};
+use super::BTreeError;
+
+// Writes a tree bottom up from records that arrive in key order
+// Leaves are packed full and streamed out as they fill, only the first key and page number of
+// each leaf stay in memory. finish then builds the internal levels from those, one level at a
+// time, until a single root is left and the header page (written as zeros up front) is filled in.
+// A builder dropped without finish leaves a file with no header, which BTreeFile::open rejects.
+pub struct BTreeBuilder {
+    file: HeaderWriter<BufWriter<File>>,
+    key_size: usize,
+    value_size: usize,
+    leaf_capacity: usize,
+    internal_capacity: usize,
+    leaf: Vec<u8>,
+    leaf_count: usize,
+    next_page: u64,
+    len: u64,
+    last_key: Vec<u8>,
+    // (first key, page) of every leaf written so far
+    leaves: Vec<(Vec<u8>, u64)>,
+}
+
+impl BTreeBuilder {
+    // Creates (or truncates) the file
+    pub fn create(
+        path: impl AsRef<Path>,
+        key_size: usize,
+        value_size: usize,
+    ) -> Result<Self, BTreeError> {
+        let (leaf_capacity, internal_capacity) = capacities(key_size, value_size)?;
+        let file = HeaderWriter::new(BufWriter::new(File::create(path)?), PAGE_SIZE)?;
+
+        Ok(BTreeBuilder {
+            file,
+            key_size,
+            value_size,
+            leaf_capacity,
+            internal_capacity,
+            leaf: vec![0; PAGE_SIZE],
+            leaf_count: 0,
+            next_page: 1,
+            len: 0,
+            last_key: Vec::with_capacity(key_size),
+            leaves: Vec::new(),
+        })
+    }
+
+    // Builds a whole tree from an iterator of sorted records
+    pub fn bulk_load<K, V>(
+        path: impl AsRef<Path>,
+        key_size: usize,
+        value_size: usize,
+        records: impl IntoIterator<Item = (K, V)>,
+    ) -> Result<u64, BTreeError>
+    where
+        K: AsRef<[u8]>,
+        V: AsRef<[u8]>,
+    {
+        let mut builder = Self::create(path, key_size, value_size)?;
+        for (key, value) in records {
+            builder.push(key.as_ref(), value.as_ref())?;
+        }
+        builder.finish()
+    }
+
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    // Keys must be strictly increasing (compared as bytes), so big endian integers sort numerically
+    pub fn push(&mut self, key: &[u8], value: &[u8]) -> Result<(), BTreeError> {
+        if key.len() != self.key_size || value.len() != self.value_size {
+            return Err(BTreeError::InvalidParams(format!(
+                "expected a {} byte key and {} byte value, got {} and {}",
+                self.key_size,
+                self.value_size,
+                key.len(),
+                value.len()
+            )));
+        }
+        if self.len > 0 && key <= &self.last_key[..] {
+            return Err(BTreeError::Unsorted(self.len));
+        }
+
+        // another record is coming, so a full leaf's successor is the very next page
+        if self.leaf_count == self.leaf_capacity {
+            let next = self.next_page + 1;
+            self.write_leaf(next)?;
+        }
+        if self.leaf_count == 0 {
+            self.leaves.push((key.to_vec(), self.next_page));
+        }
+
+        let at = NODE_HEADER_SIZE + self.leaf_count * (self.key_size + self.value_size);
+        self.leaf[at..at + self.key_size].copy_from_slice(key);
+        self.leaf[at + self.key_size..at + self.key_size + self.value_size].copy_from_slice(value);
+        self.leaf_count += 1;
+        self.len += 1;
+        self.last_key.clear();
+        self.last_key.extend_from_slice(key);
+        Ok(())
+    }
+
+    // Writes the last leaf, the internal levels and the header, returns the number of records
+    pub fn finish(self) -> Result<u64, BTreeError> {
+        self.finish_with_metadata(&[])
+    }
+
+    // The same with metadata for BTreeFile::metadata, which can be anything and any length
+    pub fn finish_with_metadata(mut self, metadata: &[u8]) -> Result<u64, BTreeError> {
+        if self.leaf_count > 0 {
+            self.write_leaf(0)?;
+        }
+
+        let first_leaf = self.leaves.first().map_or(0, |(_, page)| *page);
+        let mut level = std::mem::take(&mut self.leaves);
+        let mut height = u32::from(!level.is_empty());
+        while level.len() > 1 {
+            level = self.write_level(&level)?;
+            height += 1;
+        }
+
+        let metadata_page = if metadata.is_empty() {
+            0
+        } else {
+            self.file.write_all(metadata)?;
+            let pages = metadata.len().div_ceil(PAGE_SIZE);
+            let padding = pages * PAGE_SIZE - metadata.len();
+            self.file.write_all(&vec![0; padding])?;
+            self.next_page += pages as u64;
+            self.next_page - pages as u64
+        };
+
+        let header = Header {
+            key_size: self.key_size,
+            value_size: self.value_size,
+            len: self.len,
+            root: level.first().map_or(0, |(_, page)| *page),
+            height,
+            first_leaf,
+            metadata_page,
+            metadata_len: metadata.len() as u64,
+        };
+        let file = self.file.finish(&header.to_bytes())?;
+        let file = file.into_inner().map_err(|e| e.into_error())?;
+        file.sync_all()?;
+        Ok(self.len)
+    }
+
+    fn write_leaf(&mut self, next: u64) -> Result<(), BTreeError> {
+        self.leaf[0] = LEAF;
+        self.leaf[1..3].copy_from_slice(&(self.leaf_count as u16).to_be_bytes());
+        self.leaf[3..NODE_HEADER_SIZE].copy_from_slice(&next.to_be_bytes());
+        self.file.write_all(&self.leaf)?;
+        self.leaf.fill(0);
+        self.leaf_count = 0;
+        self.next_page += 1;
+        Ok(())
+    }
+
+    // Groups the nodes of one level under parents, returns the parents' (first key, page)
+    // Children are spread evenly rather than packing every parent but the last, which could leave
+    // the last one with a single child.
+    fn write_level(&mut self, level: &[(Vec<u8>, u64)]) -> Result<Vec<(Vec<u8>, u64)>, BTreeError> {
+        let fan_out = self.internal_capacity + 1;
+        let n_parents = level.len().div_ceil(fan_out);
+        let mut parents = Vec::with_capacity(n_parents);
+        let mut page = vec![0; PAGE_SIZE];
+
+        let mut start = 0;
+        for i in 0..n_parents {
+            let end = start + level.len() / n_parents + usize::from(i < level.len() % n_parents);
+            let children = &level[start..end];
+
+            page.fill(0);
+            page[0] = INTERNAL;
+            page[1..3].copy_from_slice(&(children.len() as u16 - 1).to_be_bytes());
+            page[3..NODE_HEADER_SIZE].copy_from_slice(&children[0].1.to_be_bytes());
+            let mut at = NODE_HEADER_SIZE;
+            for (key, child) in &children[1..] {
+                page[at..at + self.key_size].copy_from_slice(key);
+                page[at + self.key_size..at + self.key_size + CHILD_SIZE]
+                    .copy_from_slice(&child.to_be_bytes());
+                at += self.key_size + CHILD_SIZE;
+            }
+            self.file.write_all(&page)?;
+
+            parents.push((children[0].0.clone(), self.next_page));
+            self.next_page += 1;
+            start = end;
+        }
+        Ok(parents)
+    }
+}
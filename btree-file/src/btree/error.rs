@@ -0,0 +1,32 @@
+use std::{error, fmt, io};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BTreeError {
+    // Key/value sizes that can't be laid out in a page, or a record of the wrong size
+    InvalidParams(String),
+    // Bulk loading needs strictly increasing keys, this is the index of the first one that wasn't
+    Unsorted(u64),
+    // A file that doesn't describe a tree
+    Corrupt(String),
+    // Reading or writing the file failed
+    Io(String),
+}
+
+impl fmt::Display for BTreeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BTreeError::InvalidParams(msg) => write!(f, "invalid tree parameters: {msg}"),
+            BTreeError::Unsorted(n) => write!(f, "record {n} isn't greater than the one before it"),
+            BTreeError::Corrupt(msg) => write!(f, "corrupt tree file: {msg}"),
+            BTreeError::Io(msg) => write!(f, "tree i/o failed: {msg}"),
+        }
+    }
+}
+
+impl error::Error for BTreeError {}
+
+impl From<io::Error> for BTreeError {
+    fn from(e: io::Error) -> Self {
+        BTreeError::Io(e.to_string())
+    }
+}
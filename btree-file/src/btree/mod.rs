@@ -0,0 +1,545 @@
+mod builder;
+mod error;
+mod page;
+
+pub use builder::BTreeBuilder;
+pub use error::BTreeError;
+pub use page::MAGIC;
+
+use std::fs::File;
+use std::iter::FusedIterator;
+use std::ops::{Bound, RangeBounds};
+use std::path::Path;
+
+use binio::{be_u16, be_u64};
+use memmap2::Mmap;
+
+use page::{capacities, Header, CHILD_SIZE, INTERNAL, LEAF, NODE_HEADER_SIZE, PAGE_SIZE};
+
+// A read only B+tree of fixed size byte keys and values, written by BTreeBuilder
+// The whole file is memory mapped, so a lookup is a walk of `height` pages that the OS pages in
+// (and keeps cached) on demand, and results borrow straight from the map.
+// Lookups trust the page numbers they find, a damaged file can make them panic. verify checks
+// every page once up front for files that didn't come straight from a builder.
+pub struct BTreeFile {
+    map: Mmap,
+    header: Header,
+    page_count: u64,
+    leaf_capacity: usize,
+    internal_capacity: usize,
+}
+
+impl BTreeFile {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, BTreeError> {
+        let file = File::open(path)?;
+        // SAFETY: the map is read only, but another process truncating or rewriting the file while
+        // it's mapped is still undefined behaviour. Tree files are written once by a builder and
+        // only read after, same as the flat mapdbsnp index.
+        let map = unsafe { Mmap::map(&file)? };
+        if map.len() < PAGE_SIZE || map.len() % PAGE_SIZE != 0 {
+            return Err(BTreeError::Corrupt(format!(
+                "{} bytes isn't a whole number of {PAGE_SIZE} byte pages",
+                map.len()
+            )));
+        }
+
+        let header = Header::from_bytes(&map)?;
+        let (leaf_capacity, internal_capacity) = capacities(header.key_size, header.value_size)
+            .map_err(|e| BTreeError::Corrupt(e.to_string()))?;
+        let page_count = (map.len() / PAGE_SIZE) as u64;
+        let empty = header.len == 0;
+        if header.root >= page_count
+            || header.first_leaf >= page_count
+            || empty != (header.root == 0)
+            || empty != (header.height == 0)
+            || empty != (header.first_leaf == 0)
+            || (header.metadata_len > 0 && header.metadata_page == 0)
+            || header
+                .metadata_page
+                .checked_mul(PAGE_SIZE as u64)
+                .and_then(|start| start.checked_add(header.metadata_len))
+                .is_none_or(|end| end > map.len() as u64)
+        {
+            return Err(BTreeError::Corrupt(format!(
+                "header doesn't fit a {page_count} page file: {header:?}"
+            )));
+        }
+
+        Ok(BTreeFile {
+            map,
+            header,
+            page_count,
+            leaf_capacity,
+            internal_capacity,
+        })
+    }
+
+    pub fn len(&self) -> u64 {
+        self.header.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.header.len == 0
+    }
+
+    pub fn key_size(&self) -> usize {
+        self.header.key_size
+    }
+
+    pub fn value_size(&self) -> usize {
+        self.header.value_size
+    }
+
+    // What the builder was finished with, empty if it wasn't given any
+    pub fn metadata(&self) -> &[u8] {
+        let start = self.header.metadata_page as usize * PAGE_SIZE;
+        &self.map[start..start + self.header.metadata_len as usize]
+    }
+
+    // Pages on the way from the root to a leaf, 0 for an empty tree
+    pub fn height(&self) -> u32 {
+        self.header.height
+    }
+
+    pub fn get(&self, key: &[u8]) -> Option<&[u8]> {
+        if self.is_empty() || key.len() != self.header.key_size {
+            return None;
+        }
+
+        let leaf = self.page(self.find_leaf(key));
+        let idx = partition(node_count(leaf), |i| self.leaf_key(leaf, i) < key);
+        (idx < node_count(leaf) && self.leaf_key(leaf, idx) == key)
+            .then(|| self.leaf_record(leaf, idx).1)
+    }
+
+    pub fn contains_key(&self, key: &[u8]) -> bool {
+        self.get(key).is_some()
+    }
+
+    // Records with keys in the range, in key order
+    // Only the first leaf is found from the root, after that it's a walk along the leaf chain.
+    pub fn range<'k, R: RangeBounds<&'k [u8]>>(&self, range: R) -> BTreeRange<'_> {
+        let (page, idx) = if self.is_empty() {
+            (0, 0)
+        } else {
+            match range.start_bound() {
+                Bound::Unbounded => (self.header.first_leaf, 0),
+                Bound::Included(&start) => {
+                    let n = self.find_leaf(start);
+                    let leaf = self.page(n);
+                    (
+                        n,
+                        partition(node_count(leaf), |i| self.leaf_key(leaf, i) < start),
+                    )
+                }
+                Bound::Excluded(&start) => {
+                    let n = self.find_leaf(start);
+                    let leaf = self.page(n);
+                    (
+                        n,
+                        partition(node_count(leaf), |i| self.leaf_key(leaf, i) <= start),
+                    )
+                }
+            }
+        };
+
+        BTreeRange {
+            tree: self,
+            page,
+            idx,
+            end: range.end_bound().map(|end| end.to_vec()),
+        }
+    }
+
+    pub fn iter(&self) -> BTreeRange<'_> {
+        self.range(..)
+    }
+
+    // Checks every page: node kinds, counts, key order within and across pages, that the leaf
+    // chain visits the leaves in tree order, and that they hold `len` records between them
+    pub fn verify(&self) -> Result<(), BTreeError> {
+        let mut leaves = Vec::new();
+        if !self.is_empty() {
+            self.verify_node(
+                self.header.root,
+                self.header.height,
+                None,
+                None,
+                &mut leaves,
+            )?;
+        }
+
+        let mut n = self.header.first_leaf;
+        let mut len = 0;
+        for (i, &leaf) in leaves.iter().enumerate() {
+            if n != leaf {
+                return Err(BTreeError::Corrupt(format!(
+                    "leaf {i} is page {leaf} but the chain leads to page {n}"
+                )));
+            }
+            let page = self.page(n);
+            len += node_count(page) as u64;
+            n = be_u64(page, 3);
+        }
+        if n != 0 {
+            return Err(BTreeError::Corrupt(format!(
+                "leaf chain carries on to page {n}"
+            )));
+        }
+        if len != self.header.len {
+            return Err(BTreeError::Corrupt(format!(
+                "header says {} records, leaves hold {len}",
+                self.header.len
+            )));
+        }
+        Ok(())
+    }
+
+    // Keys in the subtree at `n` have to be in [lo, hi)
+    fn verify_node(
+        &self,
+        n: u64,
+        height: u32,
+        lo: Option<&[u8]>,
+        hi: Option<&[u8]>,
+        leaves: &mut Vec<u64>,
+    ) -> Result<(), BTreeError> {
+        if n == 0 || n >= self.page_count {
+            return Err(BTreeError::Corrupt(format!("no page {n}")));
+        }
+        let page = self.page(n);
+        let count = node_count(page);
+        let (kind, capacity) = if height == 1 {
+            (LEAF, self.leaf_capacity)
+        } else {
+            (INTERNAL, self.internal_capacity)
+        };
+        if page[0] != kind || count == 0 || count > capacity {
+            return Err(BTreeError::Corrupt(format!(
+                "page {n} should be a node of kind {kind} with 1 to {capacity} keys, found kind {} with {count}",
+                page[0]
+            )));
+        }
+
+        let key = |i| {
+            if height == 1 {
+                self.leaf_key(page, i)
+            } else {
+                self.internal_key(page, i)
+            }
+        };
+        for i in 0..count {
+            let in_order = (i == 0 || key(i - 1) < key(i))
+                && lo.is_none_or(|lo| lo <= key(i))
+                && hi.is_none_or(|hi| key(i) < hi);
+            if !in_order {
+                return Err(BTreeError::Corrupt(format!(
+                    "key {i} of page {n} is out of order"
+                )));
+            }
+        }
+
+        if height == 1 {
+            leaves.push(n);
+            return Ok(());
+        }
+        for i in 0..=count {
+            let lo = if i == 0 { lo } else { Some(key(i - 1)) };
+            let hi = if i == count { hi } else { Some(key(i)) };
+            self.verify_node(self.internal_child(page, i), height - 1, lo, hi, leaves)?;
+        }
+        Ok(())
+    }
+
+    // The only leaf that could hold `key`
+    fn find_leaf(&self, key: &[u8]) -> u64 {
+        let mut n = self.header.root;
+        for _ in 1..self.header.height {
+            let page = self.page(n);
+            let idx = partition(node_count(page), |i| self.internal_key(page, i) <= key);
+            n = self.internal_child(page, idx);
+        }
+        n
+    }
+
+    fn page(&self, n: u64) -> &[u8] {
+        let at = n as usize * PAGE_SIZE;
+        &self.map[at..at + PAGE_SIZE]
+    }
+
+    fn leaf_key<'a>(&self, page: &'a [u8], i: usize) -> &'a [u8] {
+        self.leaf_record(page, i).0
+    }
+
+    fn leaf_record<'a>(&self, page: &'a [u8], i: usize) -> (&'a [u8], &'a [u8]) {
+        let (key_size, value_size) = (self.header.key_size, self.header.value_size);
+        let at = NODE_HEADER_SIZE + i * (key_size + value_size);
+        page[at..at + key_size + value_size].split_at(key_size)
+    }
+
+    fn internal_key<'a>(&self, page: &'a [u8], i: usize) -> &'a [u8] {
+        let at = NODE_HEADER_SIZE + i * (self.header.key_size + CHILD_SIZE);
+        &page[at..at + self.header.key_size]
+    }
+
+    // Child i sits after key i - 1, child 0 is in the page header
+    fn internal_child(&self, page: &[u8], i: usize) -> u64 {
+        if i == 0 {
+            return be_u64(page, 3);
+        }
+        let at = NODE_HEADER_SIZE + (i - 1) * (self.header.key_size + CHILD_SIZE);
+        be_u64(page, at + self.header.key_size)
+    }
+}
+
+fn node_count(page: &[u8]) -> usize {
+    be_u16(page, 1) as usize
+}
+
+// The first index in 0..count that `pred` is false for, pred has to be true then false
+fn partition(count: usize, pred: impl Fn(usize) -> bool) -> usize {
+    let (mut lo, mut hi) = (0, count);
+    while lo < hi {
+        let mid = lo + (hi - lo) / 2;
+        if pred(mid) {
+            lo = mid + 1;
+        } else {
+            hi = mid;
+        }
+    }
+    lo
+}
+
+// (key, value) pairs borrowed from the map
+// A page of 0 means done, the header page is never a leaf.
+pub struct BTreeRange<'a> {
+    tree: &'a BTreeFile,
+    page: u64,
+    idx: usize,
+    end: Bound<Vec<u8>>,
+}
+
+impl<'a> Iterator for BTreeRange<'a> {
+    type Item = (&'a [u8], &'a [u8]);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.page != 0 {
+            let page = self.tree.page(self.page);
+            if self.idx == node_count(page) {
+                self.page = be_u64(page, 3);
+                self.idx = 0;
+                continue;
+            }
+
+            let (key, value) = self.tree.leaf_record(page, self.idx);
+            let in_range = match &self.end {
+                Bound::Unbounded => true,
+                Bound::Included(end) => key <= &end[..],
+                Bound::Excluded(end) => key < &end[..],
+            };
+            if !in_range {
+                self.page = 0;
+                return None;
+            }
+            self.idx += 1;
+            return Some((key, value));
+        }
+        None
+    }
+}
+
+// Once the leaf chain (or the range) has run out it stays out
+impl FusedIterator for BTreeRange<'_> {}
+
+#[cfg(test)]
+mod testing {
+    use std::collections::BTreeMap;
+    use std::fs::{self, OpenOptions};
+
+    use super::*;
+
+    // Long keys so a modest tree still needs internal levels, the number is at the end so byte
+    // order is numeric order
+    fn key(n: u32) -> Vec<u8> {
+        let mut key = vec![0; 60];
+        key.extend_from_slice(&n.to_be_bytes());
+        key
+    }
+
+    fn value(n: u32) -> [u8; 8] {
+        (u64::from(n) * 7).to_be_bytes()
+    }
+
+    #[test]
+    fn empty_tree() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("empty.btree");
+        let records: [([u8; 4], [u8; 4]); 0] = [];
+        assert_eq!(BTreeBuilder::bulk_load(&path, 4, 4, records).unwrap(), 0);
+
+        let tree = BTreeFile::open(&path).unwrap();
+        assert!(tree.is_empty());
+        assert_eq!(tree.height(), 0);
+        assert_eq!(tree.get(&[0; 4]), None);
+        assert_eq!(tree.iter().next(), None);
+        tree.verify().unwrap();
+    }
+
+    #[test]
+    fn single_leaf() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("small.btree");
+        let records = (1..=10u32).map(|n| (n.to_be_bytes(), value(n)));
+        BTreeBuilder::bulk_load(&path, 4, 8, records).unwrap();
+
+        let tree = BTreeFile::open(&path).unwrap();
+        assert_eq!((tree.len(), tree.height()), (10, 1));
+        assert_eq!(fs::metadata(&path).unwrap().len(), 2 * PAGE_SIZE as u64);
+        assert_eq!(tree.get(&3u32.to_be_bytes()), Some(&value(3)[..]));
+        assert_eq!(tree.get(&11u32.to_be_bytes()), None);
+        assert_eq!(tree.get(&[0; 3]), None);
+        let keys: Vec<_> = tree
+            .range(&4u32.to_be_bytes()[..]..&7u32.to_be_bytes()[..])
+            .map(|(key, _)| key)
+            .collect();
+        assert_eq!(keys, [4u32, 5, 6].map(u32::to_be_bytes));
+        tree.verify().unwrap();
+    }
+
+    #[test]
+    fn keeps_metadata_after_the_nodes() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("meta.btree");
+        let mut builder = BTreeBuilder::create(&path, 4, 8).unwrap();
+        for n in 1..=1000u32 {
+            builder.push(&n.to_be_bytes(), &value(n)).unwrap();
+        }
+        let metadata: Vec<u8> = (0..PAGE_SIZE + 10).map(|i| i as u8).collect();
+        builder.finish_with_metadata(&metadata).unwrap();
+
+        let tree = BTreeFile::open(&path).unwrap();
+        assert_eq!(tree.metadata(), metadata);
+        assert_eq!(tree.get(&1000u32.to_be_bytes()), Some(&value(1000)[..]));
+        tree.verify().unwrap();
+
+        // metadata that runs off the end of the file doesn't open
+        let len = fs::metadata(&path).unwrap().len();
+        OpenOptions::new()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_len(len - PAGE_SIZE as u64)
+            .unwrap();
+        assert!(matches!(
+            BTreeFile::open(&path),
+            Err(BTreeError::Corrupt(_))
+        ));
+    }
+
+    #[test]
+    fn agrees_with_btreemap() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("model.btree");
+        // every third number, so there are gaps to look up and to start ranges in
+        let model: BTreeMap<Vec<u8>, Vec<u8>> = (0..50_000u32)
+            .map(|n| (key(n * 3), value(n * 3).to_vec()))
+            .collect();
+        BTreeBuilder::bulk_load(&path, 64, 8, &model).unwrap();
+
+        let tree = BTreeFile::open(&path).unwrap();
+        tree.verify().unwrap();
+        assert_eq!(tree.len(), model.len() as u64);
+        assert_eq!(tree.height(), 3);
+        assert!(tree.iter().eq(model.iter().map(|(k, v)| (&k[..], &v[..]))));
+
+        let mut x: u64 = 42;
+        for _ in 0..2000 {
+            x = x.wrapping_mul(6364136223846793005).wrapping_add(1);
+            let a = key((x >> 33) as u32 % 160_000);
+            x = x.wrapping_mul(6364136223846793005).wrapping_add(1);
+            let b = key((x >> 33) as u32 % 160_000);
+            assert_eq!(tree.get(&a), model.get(&a).map(|v| &v[..]));
+
+            let (lo, hi) = if a <= b { (a, b) } else { (b, a) };
+            let bounds = match x % 4 {
+                0 => (Bound::Included(&lo[..]), Bound::Excluded(&hi[..])),
+                1 => (Bound::Excluded(&lo[..]), Bound::Included(&hi[..])),
+                2 => (Bound::Unbounded, Bound::Excluded(&hi[..])),
+                _ => (Bound::Included(&lo[..]), Bound::Unbounded),
+            };
+            let expected = model
+                .range::<[u8], _>(bounds)
+                .map(|(k, v)| (&k[..], &v[..]));
+            assert!(tree.range(bounds).take(500).eq(expected.take(500)));
+        }
+    }
+
+    #[test]
+    fn rejects_bad_records() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("bad.btree");
+
+        let mut builder = BTreeBuilder::create(&path, 4, 1).unwrap();
+        builder.push(&[0, 0, 0, 2], &[1]).unwrap();
+        assert_eq!(
+            builder.push(&[0, 0, 0, 2], &[1]),
+            Err(BTreeError::Unsorted(1))
+        );
+        assert_eq!(
+            builder.push(&[0, 0, 0, 1], &[1]),
+            Err(BTreeError::Unsorted(1))
+        );
+        assert!(matches!(
+            builder.push(&[0, 0, 3], &[1]),
+            Err(BTreeError::InvalidParams(_))
+        ));
+        assert!(matches!(
+            builder.push(&[0, 0, 0, 3], &[]),
+            Err(BTreeError::InvalidParams(_))
+        ));
+        builder.push(&[0, 0, 0, 3], &[1]).unwrap();
+        assert_eq!(builder.finish().unwrap(), 2);
+
+        assert!(BTreeBuilder::create(&path, 0, 4).is_err());
+        assert!(BTreeBuilder::create(&path, 4000, 4).is_err());
+    }
+
+    #[test]
+    fn rejects_bad_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("bad.btree");
+
+        assert!(matches!(BTreeFile::open(&path), Err(BTreeError::Io(_))));
+
+        // never finished, so the header page is still zeros
+        let mut builder = BTreeBuilder::create(&path, 4, 4).unwrap();
+        builder.push(&[1; 4], &[2; 4]).unwrap();
+        drop(builder);
+        assert!(matches!(
+            BTreeFile::open(&path),
+            Err(BTreeError::Corrupt(_))
+        ));
+
+        BTreeBuilder::bulk_load(
+            &path,
+            4,
+            4,
+            (0..1000u32).map(|n| (n.to_be_bytes(), n.to_be_bytes())),
+        )
+        .unwrap();
+        let mut bytes = fs::read(&path).unwrap();
+        fs::write(&path, &bytes[..bytes.len() - 1]).unwrap();
+        assert!(matches!(
+            BTreeFile::open(&path),
+            Err(BTreeError::Corrupt(_))
+        ));
+
+        // swap the first two keys of the first leaf
+        let first = PAGE_SIZE + NODE_HEADER_SIZE;
+        bytes[first + 3] = 1;
+        bytes[first + 8 + 3] = 0;
+        fs::write(&path, &bytes).unwrap();
+        let tree = BTreeFile::open(&path).unwrap();
+        assert!(matches!(tree.verify(), Err(BTreeError::Corrupt(_))));
+    }
+}
@@ -0,0 +1,92 @@
+use binio::{be_u32, be_u64};
+
+use super::BTreeError;
+
+// Every page is PAGE_SIZE bytes and every integer is big endian, same as the mapdbsnp format
+//
+//   page 0, header:  magic | key_size u32 | value_size u32 | len u64 | root u64 | height u32 | first_leaf u64
+//                    | metadata_page u64 | metadata_len u64
+//   leaf page:       LEAF u8 | count u16 | next u64 | count * (key | value)
+//   internal page:   INTERNAL u8 | count u16 | child_0 u64 | count * (key | child u64)
+//   metadata:        metadata_len bytes from the start of metadata_page, zero padded to a page
+//
+// An internal page with `count` keys has `count + 1` children, key i is the smallest key under
+// child i + 1. Leaves are written left to right so each one points at the next (0 ends the chain),
+// which is what range queries walk once they've found their first leaf.
+//
+//                    [ 40 | 70 ]
+//                   /     |     \
+//   [10 20 30] -> [40 50 60] -> [70 80]
+//
+// Metadata is whatever the caller wants kept with the tree, it comes after every node. Files from
+// before it have zeros there (the rest of the header page is padding), which reads as none.
+pub const PAGE_SIZE: usize = 4096;
+// The first bytes of every tree file, for telling them apart from other formats
+pub const MAGIC: &[u8; 8] = b"BTREEF01";
+pub const HEADER_SIZE: usize = 8 + 4 + 4 + 8 + 8 + 4 + 8 + 8 + 8;
+
+pub const LEAF: u8 = 1;
+pub const INTERNAL: u8 = 2;
+// kind u8 | count u16 | next (leaves) or child_0 (internal) u64
+pub const NODE_HEADER_SIZE: usize = 1 + 2 + 8;
+pub const CHILD_SIZE: usize = 8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Header {
+    pub key_size: usize,
+    pub value_size: usize,
+    pub len: u64,
+    // 0 when the tree is empty, the header page is never a node
+    pub root: u64,
+    // 1 when the root is a leaf
+    pub height: u32,
+    pub first_leaf: u64,
+    // 0 and 0 when there isn't any
+    pub metadata_page: u64,
+    pub metadata_len: u64,
+}
+
+impl Header {
+    pub fn to_bytes(self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(HEADER_SIZE);
+        bytes.extend_from_slice(MAGIC);
+        bytes.extend_from_slice(&(self.key_size as u32).to_be_bytes());
+        bytes.extend_from_slice(&(self.value_size as u32).to_be_bytes());
+        bytes.extend_from_slice(&self.len.to_be_bytes());
+        bytes.extend_from_slice(&self.root.to_be_bytes());
+        bytes.extend_from_slice(&self.height.to_be_bytes());
+        bytes.extend_from_slice(&self.first_leaf.to_be_bytes());
+        bytes.extend_from_slice(&self.metadata_page.to_be_bytes());
+        bytes.extend_from_slice(&self.metadata_len.to_be_bytes());
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, BTreeError> {
+        if bytes.len() < HEADER_SIZE || &bytes[..8] != MAGIC {
+            return Err(BTreeError::Corrupt("missing header".into()));
+        }
+        Ok(Header {
+            key_size: be_u32(bytes, 8) as usize,
+            value_size: be_u32(bytes, 12) as usize,
+            len: be_u64(bytes, 16),
+            root: be_u64(bytes, 24),
+            height: be_u32(bytes, 32),
+            first_leaf: be_u64(bytes, 36),
+            metadata_page: be_u64(bytes, 44),
+            metadata_len: be_u64(bytes, 52),
+        })
+    }
+}
+
+// How many records fit in a leaf and how many keys fit in an internal page
+// Both need to be at least 2 or the tree can't branch.
+pub fn capacities(key_size: usize, value_size: usize) -> Result<(usize, usize), BTreeError> {
+    let leaf = (PAGE_SIZE - NODE_HEADER_SIZE) / (key_size + value_size).max(1);
+    let internal = (PAGE_SIZE - NODE_HEADER_SIZE) / (key_size + CHILD_SIZE);
+    if key_size == 0 || leaf < 2 || internal < 2 {
+        return Err(BTreeError::InvalidParams(format!(
+            "{key_size} byte keys and {value_size} byte values don't fit two to a {PAGE_SIZE} byte page"
+        )));
+    }
+    Ok((leaf, internal))
+}
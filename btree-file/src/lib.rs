@@ -0,0 +1,3 @@
+mod btree;
+
+pub use btree::{BTreeBuilder, BTreeError, BTreeFile, BTreeRange, MAGIC};
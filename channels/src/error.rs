@@ -0,0 +1,76 @@
+use std::error::Error;
+use std::fmt;
+
+// send only fails one way: every Receiver is gone, so the value is handed back
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SendError<T>(pub T);
+
+// recv only fails one way: the channel is empty and every Sender is gone
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RecvError;
+
+// Why try_send didn't send, with the value it didn't send
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrySendError<T> {
+    // no room right now, but receivers are still around
+    Full(T),
+    // every Receiver is gone, so there never will be
+    Disconnected(T),
+}
+
+// Why try_recv didn't return a value
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TryRecvError {
+    // nothing to receive right now, but senders are still around
+    Empty,
+    // nothing to receive and every Sender is gone, so nothing ever will be
+    Disconnected,
+}
+
+impl<T> TrySendError<T> {
+    pub fn into_inner(self) -> T {
+        match self {
+            TrySendError::Full(value) | TrySendError::Disconnected(value) => value,
+        }
+    }
+}
+
+impl<T> fmt::Display for SendError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "sending on a channel with no receivers")
+    }
+}
+
+impl fmt::Display for RecvError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "receiving on an empty channel with no senders")
+    }
+}
+
+impl<T> fmt::Display for TrySendError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TrySendError::Full(_) => write!(f, "sending on a full channel"),
+            TrySendError::Disconnected(_) => write!(f, "sending on a channel with no receivers"),
+        }
+    }
+}
+
+impl fmt::Display for TryRecvError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TryRecvError::Empty => write!(f, "receiving on an empty channel"),
+            TryRecvError::Disconnected => {
+                write!(f, "receiving on an empty channel with no senders")
+            }
+        }
+    }
+}
+
+impl<T: fmt::Debug> Error for SendError<T> {}
+
+impl Error for RecvError {}
+
+impl<T: fmt::Debug> Error for TrySendError<T> {}
+
+impl Error for TryRecvError {}
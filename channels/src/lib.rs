@@ -0,0 +1,6 @@
+mod error;
+pub mod mpmc;
+pub mod spsc;
+mod sync;
+
+pub use error::{RecvError, SendError, TryRecvError, TrySendError};
@@ -0,0 +1,353 @@
+// A bounded multi-producer multi-consumer channel: a queue behind a mutex, and two condition
+// variables to sleep on when it's full or empty
+//
+// A condition variable is a place for threads to wait until some condition on the locked state
+// might have changed. The pattern is always the same:
+//
+//   lock
+//   while the condition isn't true: wait (unlocks, sleeps, locks again before returning)
+//   act on the state
+//   unlock, notify whoever might be waiting for what we just changed
+//
+// wait unlocking and sleeping as one step is what makes it work: nobody can change the state and
+// notify in between us checking and us sleeping, which would leave us asleep with the condition
+// true. Wakeups can also be spurious, hence while rather than if.
+//
+// Here the conditions are "there's room" for senders (not_full) and "there's something" for
+// receivers (not_empty). A send notifies one receiver and a receive notifies one sender, each
+// change only lets one waiter make progress. Disconnecting is the exception, when the last of the
+// senders (or receivers) goes every thread waiting on the other side is woken to find out.
+//
+// The capacity bounds how far senders can get ahead of receivers, so a slow consumer slows its
+// producers down (backpressure) rather than letting the queue grow without limit.
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::mem;
+
+use crate::sync::{lock, wait, Arc, Condvar, Mutex};
+use crate::{RecvError, SendError, TryRecvError, TrySendError};
+
+struct State<T> {
+    queue: VecDeque<T>,
+    senders: usize,
+    receivers: usize,
+}
+
+struct Shared<T> {
+    state: Mutex<State<T>>,
+    capacity: usize,
+    not_full: Condvar,
+    not_empty: Condvar,
+}
+
+pub struct Sender<T> {
+    shared: Arc<Shared<T>>,
+}
+
+pub struct Receiver<T> {
+    shared: Arc<Shared<T>>,
+}
+
+// Creates a channel with room for capacity values, clone either end for more threads
+// Panics if capacity is 0.
+pub fn bounded<T>(capacity: usize) -> (Sender<T>, Receiver<T>) {
+    assert!(capacity > 0, "a channel needs room for at least one value");
+    let shared = Arc::new(Shared {
+        state: Mutex::new(State {
+            queue: VecDeque::with_capacity(capacity),
+            senders: 1,
+            receivers: 1,
+        }),
+        capacity,
+        not_full: Condvar::new(),
+        not_empty: Condvar::new(),
+    });
+    (
+        Sender {
+            shared: Arc::clone(&shared),
+        },
+        Receiver { shared },
+    )
+}
+
+impl<T> Shared<T> {
+    fn len(&self) -> usize {
+        lock(&self.state).queue.len()
+    }
+}
+
+impl<T> Sender<T> {
+    // Waits for room and queues value, or hands it back once every Receiver is gone
+    pub fn send(&self, value: T) -> Result<(), SendError<T>> {
+        let mut state = lock(&self.shared.state);
+        loop {
+            if state.receivers == 0 {
+                return Err(SendError(value));
+            }
+            if state.queue.len() < self.shared.capacity {
+                break;
+            }
+            state = wait(&self.shared.not_full, state);
+        }
+        state.queue.push_back(value);
+        drop(state);
+        self.shared.not_empty.notify_one();
+        Ok(())
+    }
+
+    pub fn try_send(&self, value: T) -> Result<(), TrySendError<T>> {
+        let mut state = lock(&self.shared.state);
+        if state.receivers == 0 {
+            return Err(TrySendError::Disconnected(value));
+        }
+        if state.queue.len() == self.shared.capacity {
+            return Err(TrySendError::Full(value));
+        }
+        state.queue.push_back(value);
+        drop(state);
+        self.shared.not_empty.notify_one();
+        Ok(())
+    }
+
+    // Values waiting to be received, other threads may be changing it as you look
+    pub fn len(&self) -> usize {
+        self.shared.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.shared.capacity
+    }
+}
+
+impl<T> Receiver<T> {
+    // Waits for a value, fails once the channel is empty and every Sender is gone
+    pub fn recv(&self) -> Result<T, RecvError> {
+        let mut state = lock(&self.shared.state);
+        let value = loop {
+            if let Some(value) = state.queue.pop_front() {
+                break value;
+            }
+            if state.senders == 0 {
+                return Err(RecvError);
+            }
+            state = wait(&self.shared.not_empty, state);
+        };
+        drop(state);
+        self.shared.not_full.notify_one();
+        Ok(value)
+    }
+
+    pub fn try_recv(&self) -> Result<T, TryRecvError> {
+        let mut state = lock(&self.shared.state);
+        match state.queue.pop_front() {
+            Some(value) => {
+                drop(state);
+                self.shared.not_full.notify_one();
+                Ok(value)
+            }
+            None if state.senders == 0 => Err(TryRecvError::Disconnected),
+            None => Err(TryRecvError::Empty),
+        }
+    }
+
+    // Iterates over values until every Sender is gone
+    pub fn iter(&self) -> impl Iterator<Item = T> + '_ {
+        std::iter::from_fn(move || self.recv().ok())
+    }
+
+    pub fn len(&self) -> usize {
+        self.shared.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.shared.capacity
+    }
+}
+
+impl<T> Clone for Sender<T> {
+    fn clone(&self) -> Self {
+        lock(&self.shared.state).senders += 1;
+        Sender {
+            shared: Arc::clone(&self.shared),
+        }
+    }
+}
+
+impl<T> Clone for Receiver<T> {
+    fn clone(&self) -> Self {
+        lock(&self.shared.state).receivers += 1;
+        Receiver {
+            shared: Arc::clone(&self.shared),
+        }
+    }
+}
+
+impl<T> Drop for Sender<T> {
+    fn drop(&mut self) {
+        let mut state = lock(&self.shared.state);
+        state.senders -= 1;
+        if state.senders == 0 {
+            drop(state);
+            // receivers waiting for values that'll never come
+            self.shared.not_empty.notify_all();
+        }
+    }
+}
+
+impl<T> Drop for Receiver<T> {
+    fn drop(&mut self) {
+        let mut state = lock(&self.shared.state);
+        state.receivers -= 1;
+        if state.receivers == 0 {
+            // nobody can receive what's left, drop it now rather than with the last Sender (and
+            // outside the lock, T's drop could be slow or even panic)
+            let unreceived = mem::take(&mut state.queue);
+            drop(state);
+            drop(unreceived);
+            // senders waiting for room that'll never be needed
+            self.shared.not_full.notify_all();
+        }
+    }
+}
+
+impl<T> fmt::Debug for Sender<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Sender").finish_non_exhaustive()
+    }
+}
+
+impl<T> fmt::Debug for Receiver<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Receiver").finish_non_exhaustive()
+    }
+}
+
+#[cfg(all(test, not(loom)))]
+mod testing {
+    use std::rc::Rc;
+    use std::thread;
+
+    use super::*;
+
+    #[test]
+    fn try_send_and_try_recv() {
+        let (tx, rx) = bounded(2);
+        assert_eq!(rx.try_recv(), Err(TryRecvError::Empty));
+        tx.try_send(1).unwrap();
+        tx.try_send(2).unwrap();
+        assert_eq!(tx.try_send(3), Err(TrySendError::Full(3)));
+        assert_eq!(rx.try_recv(), Ok(1));
+        tx.try_send(3).unwrap();
+        assert_eq!(rx.len(), 2);
+
+        // values sent before the last Sender left can still be received
+        drop(tx);
+        assert_eq!(rx.iter().collect::<Vec<_>>(), [2, 3]);
+        assert_eq!(rx.try_recv(), Err(TryRecvError::Disconnected));
+        assert_eq!(rx.recv(), Err(RecvError));
+    }
+
+    #[test]
+    fn dropping_the_receivers_disconnects_and_frees() {
+        let counter = Rc::new(());
+        let (tx, rx) = bounded(4);
+        let rx2 = rx.clone();
+        tx.send(Rc::clone(&counter)).unwrap();
+        drop(rx);
+        tx.send(Rc::clone(&counter)).unwrap();
+        drop(rx2);
+        assert_eq!(Rc::strong_count(&counter), 1);
+        assert!(matches!(
+            tx.try_send(Rc::clone(&counter)),
+            Err(TrySendError::Disconnected(_))
+        ));
+        assert!(tx.send(Rc::clone(&counter)).is_err());
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn many_senders_many_receivers() {
+        const SENDERS: u64 = 4;
+        const RECEIVERS: usize = 3;
+        const PER_SENDER: u64 = 10_000;
+        let (tx, rx) = bounded(8);
+
+        let mut received: Vec<u64> = thread::scope(|s| {
+            for t in 0..SENDERS {
+                let tx = tx.clone();
+                s.spawn(move || {
+                    for i in 0..PER_SENDER {
+                        tx.send(t * PER_SENDER + i).unwrap();
+                    }
+                });
+            }
+            // the receivers only finish once every Sender, this one included, is gone
+            drop(tx);
+            let receivers: Vec<_> = (0..RECEIVERS)
+                .map(|_| {
+                    let rx = rx.clone();
+                    s.spawn(move || {
+                        let values: Vec<u64> = rx.iter().collect();
+                        // each sender's values come out in the order they went in
+                        for t in 0..SENDERS {
+                            let own = values.iter().filter(|&&v| v / PER_SENDER == t);
+                            assert!(own.clone().zip(own.skip(1)).all(|(a, b)| a < b));
+                        }
+                        values
+                    })
+                })
+                .collect();
+            receivers
+                .into_iter()
+                .flat_map(|r| r.join().unwrap())
+                .collect()
+        });
+
+        // everything arrived, exactly once
+        received.sort_unstable();
+        assert!(received.into_iter().eq(0..SENDERS * PER_SENDER));
+    }
+}
+
+// Model checked with loom, which runs the closure under every interleaving of the threads:
+//  RUSTFLAGS="--cfg loom" cargo test --release --lib mpmc
+#[cfg(all(test, loom))]
+mod loom_testing {
+    use super::*;
+
+    #[test]
+    fn blocked_senders_all_get_through() {
+        loom::model(|| {
+            // room for one, so whichever sender comes second has to wait for the receiver
+            let (tx, rx) = bounded(1);
+            let tx2 = tx.clone();
+            let a = loom::thread::spawn(move || tx.send(1).unwrap());
+            let b = loom::thread::spawn(move || tx2.send(2).unwrap());
+            let mut received = vec![rx.recv().unwrap(), rx.recv().unwrap()];
+            a.join().unwrap();
+            b.join().unwrap();
+            received.sort_unstable();
+            assert_eq!(received, [1, 2]);
+            assert_eq!(rx.try_recv(), Err(TryRecvError::Disconnected));
+        });
+    }
+
+    #[test]
+    fn last_sender_leaving_wakes_the_receiver() {
+        loom::model(|| {
+            let (tx, rx) = bounded::<i32>(1);
+            let t = loom::thread::spawn(move || rx.recv());
+            drop(tx);
+            assert_eq!(t.join().unwrap(), Err(RecvError));
+        });
+    }
+}
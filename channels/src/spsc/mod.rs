@@ -0,0 +1,399 @@
+// A bounded channel for exactly one sender and one receiver, lock-free whenever neither side has
+// to wait
+//
+// With only one thread at each end the queue itself needs no lock, it's a Lamport ring buffer
+// (see ringbuf's spsc): head and tail are counters that only go up, the receiver is the only
+// one that moves head and the sender the only one that moves tail, and each publishes its
+// counter with a Release store once it's done with the slot. try_send and try_recv are a couple
+// of atomic loads and a store.
+//
+// Blocking is the slow path. A side that finds the queue full (or empty) spins for a moment
+// first, the other thread is often about to get there, and only then sleeps on a condvar. To
+// sleep it locks the mutex, raises its waiting flag, checks one last time and waits. The other
+// side looks at the flag after every send or receive and only touches the mutex if it's up, so
+// two threads that keep up with each other never lock anything. The two fences are what keep a
+// wakeup from getting lost between "check" and "wait":
+//
+//   sleeper                          other side
+//   waiting = true                   tail += 1 (or head)
+//   fence(SeqCst)                    fence(SeqCst)
+//   check tail (or head)             check waiting
+//
+// Two SeqCst fences can't both be ordered first, so at least one side sees the other's write:
+// either the sleeper sees the change and doesn't sleep, or the other side sees the flag and
+// locks the mutex to notify, which it can only get once the sleeper is inside wait.
+
+use std::fmt;
+use std::mem::MaybeUninit;
+
+use crate::sync::{
+    fence, hint, lock, wait, Arc, AtomicBool, AtomicUsize, Condvar, Mutex, Ordering, UnsafeCell,
+};
+use crate::{RecvError, SendError, TryRecvError, TrySendError};
+
+// Tries before going to sleep
+const SPINS: usize = if cfg!(loom) { 1 } else { 100 };
+
+struct Shared<T> {
+    // a power of two long, so slots can be found with a mask and the counters can wrap round
+    // usize::MAX without skipping (a length that doesn't divide 2^64 would)
+    buf: Box<[UnsafeCell<MaybeUninit<T>>]>,
+    capacity: usize,
+    // the next slot to receive from, only the receiver moves it
+    head: AtomicUsize,
+    // the next slot to send to, only the sender moves it
+    tail: AtomicUsize,
+    // either end has been dropped
+    closed: AtomicBool,
+    // the slow path
+    lock: Mutex<()>,
+    receiver_waiting: AtomicBool,
+    sender_waiting: AtomicBool,
+    not_empty: Condvar,
+    not_full: Condvar,
+}
+
+pub struct Sender<T> {
+    shared: Arc<Shared<T>>,
+}
+
+pub struct Receiver<T> {
+    shared: Arc<Shared<T>>,
+}
+
+// Creates a channel with room for capacity values
+// Neither end is Clone, and sending and receiving take &mut, so there's only ever one thread at
+// each end. Panics if capacity is 0.
+pub fn bounded<T>(capacity: usize) -> (Sender<T>, Receiver<T>) {
+    assert!(capacity > 0, "a channel needs room for at least one value");
+    let shared = Arc::new(Shared {
+        buf: (0..capacity.next_power_of_two())
+            .map(|_| UnsafeCell::new(MaybeUninit::uninit()))
+            .collect(),
+        capacity,
+        head: AtomicUsize::new(0),
+        tail: AtomicUsize::new(0),
+        closed: AtomicBool::new(false),
+        lock: Mutex::new(()),
+        receiver_waiting: AtomicBool::new(false),
+        sender_waiting: AtomicBool::new(false),
+        not_empty: Condvar::new(),
+        not_full: Condvar::new(),
+    });
+    (
+        Sender {
+            shared: Arc::clone(&shared),
+        },
+        Receiver { shared },
+    )
+}
+
+impl<T> Shared<T> {
+    fn slot(&self, counter: usize) -> &UnsafeCell<MaybeUninit<T>> {
+        &self.buf[counter & (self.buf.len() - 1)]
+    }
+
+    fn len(&self) -> usize {
+        let tail = self.tail.load(Ordering::Acquire);
+        let head = self.head.load(Ordering::Acquire);
+        tail.wrapping_sub(head)
+    }
+
+    // Sleeps on condvar until ready() is true, with waiting raised for as long as that takes
+    fn sleep_until(&self, waiting: &AtomicBool, condvar: &Condvar, ready: impl Fn() -> bool) {
+        let mut guard = lock(&self.lock);
+        waiting.store(true, Ordering::Relaxed);
+        fence(Ordering::SeqCst);
+        while !ready() {
+            guard = wait(condvar, guard);
+        }
+        waiting.store(false, Ordering::Relaxed);
+    }
+
+    // Wakes the other side if it's asleep (or on its way to sleep) waiting for what we just did
+    fn wake(&self, waiting: &AtomicBool, condvar: &Condvar) {
+        fence(Ordering::SeqCst);
+        if waiting.load(Ordering::Relaxed) {
+            drop(lock(&self.lock));
+            condvar.notify_one();
+        }
+    }
+
+    fn close(&self) {
+        self.closed.store(true, Ordering::Release);
+        self.wake(&self.receiver_waiting, &self.not_empty);
+        self.wake(&self.sender_waiting, &self.not_full);
+    }
+}
+
+impl<T> Sender<T> {
+    pub fn try_send(&mut self, value: T) -> Result<(), TrySendError<T>> {
+        let shared = &*self.shared;
+        if shared.closed.load(Ordering::Acquire) {
+            return Err(TrySendError::Disconnected(value));
+        }
+        // Relaxed: nobody else writes tail
+        let tail = shared.tail.load(Ordering::Relaxed);
+        // Acquire pairs with the receive's Release, the receiver is done with the slots before head
+        let head = shared.head.load(Ordering::Acquire);
+        if tail.wrapping_sub(head) == shared.capacity {
+            return Err(TrySendError::Full(value));
+        }
+
+        // SAFETY: the slot is outside head..tail, so the receiver won't touch it until we move
+        // tail past it below
+        shared
+            .slot(tail)
+            .with_mut(|slot| unsafe { (*slot).write(value) });
+        // Release: the write above is visible to anyone who sees the new tail
+        shared.tail.store(tail.wrapping_add(1), Ordering::Release);
+        shared.wake(&shared.receiver_waiting, &shared.not_empty);
+        Ok(())
+    }
+
+    // Waits for room and sends value, or hands it back if the Receiver is gone
+    pub fn send(&mut self, mut value: T) -> Result<(), SendError<T>> {
+        loop {
+            for _ in 0..SPINS {
+                match self.try_send(value) {
+                    Ok(()) => return Ok(()),
+                    Err(TrySendError::Disconnected(back)) => return Err(SendError(back)),
+                    Err(TrySendError::Full(back)) => value = back,
+                }
+                hint::spin_loop();
+            }
+            let shared = &*self.shared;
+            shared.sleep_until(&shared.sender_waiting, &shared.not_full, || {
+                shared.len() < shared.capacity || shared.closed.load(Ordering::Acquire)
+            });
+        }
+    }
+
+    // Values waiting to be received, the receiver may be taking them as you look
+    pub fn len(&self) -> usize {
+        self.shared.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.shared.capacity
+    }
+}
+
+impl<T> Receiver<T> {
+    pub fn try_recv(&mut self) -> Result<T, TryRecvError> {
+        let shared = &*self.shared;
+        // closed before tail: the sender sends before it closes, so once we've seen it closed
+        // we'll see everything it sent
+        let closed = shared.closed.load(Ordering::Acquire);
+        // Relaxed: nobody else writes head
+        let head = shared.head.load(Ordering::Relaxed);
+        // Acquire pairs with the send's Release, every slot before tail has been written
+        let tail = shared.tail.load(Ordering::Acquire);
+        if head == tail {
+            return Err(match closed {
+                true => TryRecvError::Disconnected,
+                false => TryRecvError::Empty,
+            });
+        }
+
+        // SAFETY: the slot is inside head..tail so it holds a value, and the sender won't touch
+        // it until we move head past it below
+        let value = shared
+            .slot(head)
+            .with_mut(|slot| unsafe { (*slot).assume_init_read() });
+        // Release: we've finished reading the slot before the sender can see it's free
+        shared.head.store(head.wrapping_add(1), Ordering::Release);
+        shared.wake(&shared.sender_waiting, &shared.not_full);
+        Ok(value)
+    }
+
+    // Waits for a value, fails once the channel is empty and the Sender is gone
+    pub fn recv(&mut self) -> Result<T, RecvError> {
+        loop {
+            for _ in 0..SPINS {
+                match self.try_recv() {
+                    Ok(value) => return Ok(value),
+                    Err(TryRecvError::Disconnected) => return Err(RecvError),
+                    Err(TryRecvError::Empty) => hint::spin_loop(),
+                }
+            }
+            let shared = &*self.shared;
+            shared.sleep_until(&shared.receiver_waiting, &shared.not_empty, || {
+                shared.len() > 0 || shared.closed.load(Ordering::Acquire)
+            });
+        }
+    }
+
+    // Iterates over values until the Sender is gone
+    pub fn iter(&mut self) -> impl Iterator<Item = T> + '_ {
+        std::iter::from_fn(move || self.recv().ok())
+    }
+
+    pub fn len(&self) -> usize {
+        self.shared.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.shared.capacity
+    }
+}
+
+impl<T> Drop for Sender<T> {
+    fn drop(&mut self) {
+        self.shared.close();
+    }
+}
+
+impl<T> Drop for Receiver<T> {
+    fn drop(&mut self) {
+        self.shared.close();
+    }
+}
+
+// Once both ends are gone nobody else can be using the buffer, so whatever was never received
+// gets dropped here
+impl<T> Drop for Shared<T> {
+    fn drop(&mut self) {
+        let head = self.head.load(Ordering::Relaxed);
+        let tail = self.tail.load(Ordering::Relaxed);
+        let mut i = head;
+        while i != tail {
+            // SAFETY: head..tail are the slots holding values
+            self.slot(i)
+                .with_mut(|slot| unsafe { (*slot).assume_init_drop() });
+            i = i.wrapping_add(1);
+        }
+    }
+}
+
+// The UnsafeCells stop the compiler from working this out for itself
+// Values cross from the sender's thread to the receiver's, so T: Send is all that's needed
+unsafe impl<T: Send> Sync for Shared<T> {}
+
+impl<T> fmt::Debug for Sender<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Sender").finish_non_exhaustive()
+    }
+}
+
+impl<T> fmt::Debug for Receiver<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Receiver").finish_non_exhaustive()
+    }
+}
+
+#[cfg(all(test, not(loom)))]
+mod testing {
+    use std::rc::Rc;
+    use std::thread;
+
+    use super::*;
+
+    #[test]
+    fn capacity_isnt_rounded_up() {
+        let (mut tx, mut rx) = bounded(3);
+        for i in 0..3 {
+            tx.try_send(i).unwrap();
+        }
+        assert_eq!(tx.try_send(3), Err(TrySendError::Full(3)));
+        assert_eq!(rx.try_recv(), Ok(0));
+        tx.try_send(3).unwrap();
+        drop(tx);
+        assert_eq!(rx.iter().collect::<Vec<_>>(), [1, 2, 3]);
+        assert_eq!(rx.try_recv(), Err(TryRecvError::Disconnected));
+    }
+
+    #[test]
+    fn counters_can_wrap() {
+        let (mut tx, mut rx) = bounded(3);
+        // start both counters just short of usize::MAX
+        let start = usize::MAX - 4;
+        tx.shared.head.store(start, Ordering::Relaxed);
+        tx.shared.tail.store(start, Ordering::Relaxed);
+        for i in 0..100 {
+            tx.try_send(i).unwrap();
+            tx.try_send(i).unwrap();
+            assert_eq!(rx.try_recv(), Ok(i));
+            assert_eq!(rx.try_recv(), Ok(i));
+        }
+        assert!(rx.is_empty());
+    }
+
+    #[test]
+    fn unreceived_values_are_dropped() {
+        let counter = Rc::new(());
+        let (mut tx, mut rx) = bounded(8);
+        for _ in 0..5 {
+            tx.try_send(Rc::clone(&counter)).unwrap();
+        }
+        drop(rx.try_recv());
+        drop(rx);
+        assert!(matches!(
+            tx.try_send(Rc::clone(&counter)),
+            Err(TrySendError::Disconnected(_))
+        ));
+        assert_eq!(Rc::strong_count(&counter), 1 + 4);
+        drop(tx);
+        assert_eq!(Rc::strong_count(&counter), 1);
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn blocking_across_threads() {
+        const COUNT: u64 = 200_000;
+        let (mut tx, mut rx) = bounded(16);
+        let sender = thread::spawn(move || {
+            for i in 0..COUNT {
+                tx.send(i).unwrap();
+            }
+        });
+        // everything arrives, once, in order, and then the channel reports the sender gone
+        assert!(rx.iter().eq(0..COUNT));
+        sender.join().unwrap();
+    }
+}
+
+// Model checked with loom, which runs the closure under every interleaving of the threads (and
+// every weak memory behaviour the orderings allow):
+//  RUSTFLAGS="--cfg loom" cargo test --release --lib spsc
+#[cfg(all(test, loom))]
+mod loom_testing {
+    use super::*;
+
+    #[test]
+    fn blocking_send_and_recv_dont_lose_wakeups() {
+        loom::model(|| {
+            let (mut tx, mut rx) = bounded(1);
+            let sender = loom::thread::spawn(move || {
+                tx.send(1).unwrap();
+                tx.send(2).unwrap();
+            });
+            assert_eq!(rx.recv(), Ok(1));
+            assert_eq!(rx.recv(), Ok(2));
+            assert_eq!(rx.recv(), Err(RecvError));
+            sender.join().unwrap();
+        });
+    }
+
+    #[test]
+    fn dropping_the_receiver_wakes_a_blocked_sender() {
+        loom::model(|| {
+            let (mut tx, rx) = bounded(1);
+            let sender = loom::thread::spawn(move || {
+                let _ = tx.send(1);
+                tx.send(2)
+            });
+            drop(rx);
+            // the second send either found it gone straight away or was woken to find out
+            assert_eq!(sender.join().unwrap(), Err(SendError(2)));
+        });
+    }
+}
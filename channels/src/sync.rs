@@ -0,0 +1,50 @@
+// The channels' locks and atomics, swapped for loom's model-checked versions when building with
+// --cfg loom
+
+use std::sync::{LockResult, PoisonError};
+
+#[cfg(loom)]
+pub(crate) use loom::{
+    cell::UnsafeCell,
+    hint,
+    sync::atomic::{fence, AtomicBool, AtomicUsize, Ordering},
+    sync::{Arc, Condvar, Mutex, MutexGuard},
+};
+
+#[cfg(not(loom))]
+pub(crate) use std::{
+    hint,
+    sync::atomic::{fence, AtomicBool, AtomicUsize, Ordering},
+    sync::{Arc, Condvar, Mutex, MutexGuard},
+};
+
+// std's UnsafeCell with loom's interface, where every access goes through a closure so loom can
+// check it doesn't race
+#[cfg(not(loom))]
+pub(crate) struct UnsafeCell<T>(std::cell::UnsafeCell<T>);
+
+#[cfg(not(loom))]
+impl<T> UnsafeCell<T> {
+    pub(crate) fn new(value: T) -> Self {
+        UnsafeCell(std::cell::UnsafeCell::new(value))
+    }
+
+    pub(crate) fn with_mut<R>(&self, f: impl FnOnce(*mut T) -> R) -> R {
+        f(self.0.get())
+    }
+}
+
+// A panic while holding one of these locks can't leave the channel half changed (the only user
+// code that runs under them is T's drop, after the queue is already consistent), so a poisoned
+// lock is as good as any other
+pub(crate) fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    ignore_poison(mutex.lock())
+}
+
+pub(crate) fn wait<'a, T>(condvar: &Condvar, guard: MutexGuard<'a, T>) -> MutexGuard<'a, T> {
+    ignore_poison(condvar.wait(guard))
+}
+
+fn ignore_poison<T>(result: LockResult<T>) -> T {
+    result.unwrap_or_else(PoisonError::into_inner)
+}
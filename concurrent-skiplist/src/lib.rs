@@ -0,0 +1,3 @@
+mod skiplist;
+
+pub use skiplist::{Range, SkipMap};
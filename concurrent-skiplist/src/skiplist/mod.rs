@@ -0,0 +1,541 @@
+// An ordered map that any number of threads can use at once, a skip list with a lock per node
+//
+// A skip list is a sorted linked list with express lanes. Every node is on level 0, about half
+// of them are also on level 1, a quarter on level 2, and so on, so a search can skip ahead on
+// the top level and drop down a level whenever the next step would overshoot:
+//
+//   level 2   head ---------------------> 30 --------------------> nil
+//   level 1   head --------> 10 -------> 30 -------> 50 ---------> nil
+//   level 0   head -> 5 ---> 10 -> 20 -> 30 -> 40 -> 50 -> 60 ---> nil
+//
+// That's O(log n) expected, like a balanced tree, but every change is a few pointer swaps in
+// one neighbourhood with no rebalancing, which is what makes it easy to share between threads.
+//
+// This is the "lazy" skip list (Herlihy, Lev, Luchangco and Shavit):
+//  - searches take no node locks at all, only each link's lock for as long as it takes to read it
+//  - insert finds the node before the new key on each level (its preds), locks them, checks
+//    nothing changed between the search and the locking, and links the new node in bottom up.
+//    It's only in the map once `linked` is set, after every level is done.
+//  - remove marks the node first (that's the moment it's gone), then locks its preds, checks
+//    them the same way and unlinks it top down
+//  - a check that fails just means another thread got there first, so it searches again
+// Locks are always taken in descending key order (a node's level 0 pred has the biggest key of
+// its preds), so two threads can never each hold a lock the other one wants.
+//
+// Removed nodes can still be in the middle of another thread's search, so nodes are reference
+// counted: a search holds an Arc to where it is, and a removed node goes once the last search
+// has moved off it. Its links are left alone, so that search can carry on along them.
+
+mod sync;
+
+use std::fmt;
+use std::mem;
+use std::ops::{Bound, RangeBounds};
+use std::sync::{LockResult, PoisonError};
+
+use sync::{
+    thread, Arc, AtomicBool, AtomicU64, AtomicUsize, Mutex, MutexGuard, Ordering, RwLock,
+    RwLockReadGuard, RwLockWriteGuard,
+};
+
+// Enough for 2^16 keys at full speed, more still works with longer searches. loom explores every
+// interleaving of every lock, so under it the list stays short.
+const MAX_HEIGHT: usize = if cfg!(loom) { 2 } else { 16 };
+
+type Link<K, V> = RwLock<Option<Arc<Node<K, V>>>>;
+
+struct Node<K, V> {
+    // None for the head, which comes before every key
+    entry: Option<(K, RwLock<V>)>,
+    // one per level the node is on
+    next: Box<[Link<K, V>]>,
+    // held while linking in after this node, unlinking the node after it, or marking it
+    lock: Mutex<()>,
+    // removed, whether or not it's unlinked yet
+    marked: AtomicBool,
+    // linked in on every level, until then it isn't in the map
+    linked: AtomicBool,
+}
+
+impl<K, V> Node<K, V> {
+    fn new(entry: Option<(K, V)>, next: Vec<Option<Arc<Self>>>) -> Arc<Self> {
+        Arc::new(Node {
+            entry: entry.map(|(key, value)| (key, RwLock::new(value))),
+            next: next.into_iter().map(RwLock::new).collect(),
+            lock: Mutex::new(()),
+            marked: AtomicBool::new(false),
+            linked: AtomicBool::new(false),
+        })
+    }
+
+    fn key(&self) -> &K {
+        &self.entry.as_ref().expect("the head has no key").0
+    }
+
+    fn value(&self) -> &RwLock<V> {
+        &self.entry.as_ref().expect("the head has no value").1
+    }
+
+    fn height(&self) -> usize {
+        self.next.len()
+    }
+
+    fn next(&self, level: usize) -> Option<Arc<Self>> {
+        read(&self.next[level]).clone()
+    }
+
+    // In the map, as far as readers are concerned
+    fn is_live(&self) -> bool {
+        self.linked.load(Ordering::Acquire) && !self.marked.load(Ordering::Acquire)
+    }
+}
+
+// Where a key is or would go: the last node before it and the one after that on every level
+struct Position<K, V> {
+    preds: Vec<Arc<Node<K, V>>>,
+    succs: Vec<Option<Arc<Node<K, V>>>>,
+    // the highest level the key's own node was found on
+    found: Option<usize>,
+}
+
+pub struct SkipMap<K, V> {
+    head: Arc<Node<K, V>>,
+    len: AtomicUsize,
+    // for picking heights, a counter run through a mixer
+    seed: AtomicU64,
+}
+
+impl<K: Ord + Clone, V: Clone> SkipMap<K, V> {
+    pub fn new() -> Self {
+        SkipMap {
+            head: Node::new(None, vec![None; MAX_HEIGHT]),
+            len: AtomicUsize::new(0),
+            seed: AtomicU64::new(0),
+        }
+    }
+
+    // Keys in the map, other threads may be changing it as you look
+    pub fn len(&self) -> usize {
+        self.len.load(Ordering::Relaxed)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn get(&self, key: &K) -> Option<V> {
+        let mut pred = Arc::clone(&self.head);
+        for level in (0..MAX_HEIGHT).rev() {
+            while let Some(node) = pred.next(level) {
+                match node.key().cmp(key) {
+                    std::cmp::Ordering::Less => pred = node,
+                    std::cmp::Ordering::Equal => {
+                        return node.is_live().then(|| read(node.value()).clone());
+                    }
+                    std::cmp::Ordering::Greater => break,
+                }
+            }
+        }
+        None
+    }
+
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.get(key).is_some()
+    }
+
+    // Adds or replaces a value, returning the one it replaced
+    pub fn insert(&self, key: K, value: V) -> Option<V> {
+        let height = self.random_height();
+        loop {
+            let pos = self.find(&key);
+            if let Some(level) = pos.found {
+                let node = pos.succs[level].as_ref().expect("found on this level");
+                // another insert is still linking it in, it'll be there in a moment
+                while !node.linked.load(Ordering::Acquire) && !node.marked.load(Ordering::Acquire) {
+                    thread::yield_now();
+                }
+                // remove marks with the value locked, so this can't land in a removed node
+                let mut current = write(node.value());
+                if !node.marked.load(Ordering::Acquire) {
+                    return Some(mem::replace(&mut *current, value));
+                }
+                // being removed, try again once it's gone
+                drop(current);
+                thread::yield_now();
+                continue;
+            }
+
+            let guards = lock_preds(&pos.preds[..height]);
+            let valid = (0..height).all(|level| {
+                let (pred, succ) = (&pos.preds[level], &pos.succs[level]);
+                !pred.marked.load(Ordering::Acquire)
+                    && succ
+                        .as_ref()
+                        .is_none_or(|succ| !succ.marked.load(Ordering::Acquire))
+                    && same(&read(&pred.next[level]), succ)
+            });
+            if !valid {
+                drop(guards);
+                thread::yield_now();
+                continue;
+            }
+
+            let node = Node::new(Some((key, value)), pos.succs[..height].to_vec());
+            for (level, pred) in pos.preds[..height].iter().enumerate() {
+                *write(&pred.next[level]) = Some(Arc::clone(&node));
+            }
+            node.linked.store(true, Ordering::Release);
+            self.len.fetch_add(1, Ordering::Relaxed);
+            return None;
+        }
+    }
+
+    pub fn remove(&self, key: &K) -> Option<V> {
+        // once marked it's ours to unlink, however many searches that takes
+        let mut victim: Option<(Arc<Node<K, V>>, V)> = None;
+        loop {
+            let pos = self.find(key);
+            let node = match &victim {
+                Some((node, _)) => Arc::clone(node),
+                None => {
+                    let level = pos.found?;
+                    let node = Arc::clone(pos.succs[level].as_ref().expect("found on this level"));
+                    // half inserted (not in the map yet) or found below its top level (being
+                    // unlinked from the top down by someone else)
+                    if !node.linked.load(Ordering::Acquire) || node.height() != level + 1 {
+                        return None;
+                    }
+                    // with the value locked too, the value it goes with is the last one written
+                    let guard = lock(&node.lock);
+                    let value = read(node.value());
+                    if node.marked.swap(true, Ordering::AcqRel) {
+                        return None;
+                    }
+                    victim = Some((Arc::clone(&node), value.clone()));
+                    drop(value);
+                    drop(guard);
+                    node
+                }
+            };
+
+            let height = node.height();
+            let guards = lock_preds(&pos.preds[..height]);
+            let valid = (0..height).all(|level| {
+                let pred = &pos.preds[level];
+                !pred.marked.load(Ordering::Acquire)
+                    && same(&read(&pred.next[level]), &Some(Arc::clone(&node)))
+            });
+            if !valid {
+                drop(guards);
+                thread::yield_now();
+                continue;
+            }
+
+            // nobody links in after a marked node, so its own links are settled
+            for level in (0..height).rev() {
+                *write(&pos.preds[level].next[level]) = node.next(level);
+            }
+            self.len.fetch_sub(1, Ordering::Relaxed);
+            return victim.map(|(_, value)| value);
+        }
+    }
+
+    // Entries in the range, in key order
+    // The scan doesn't stop the world: an entry added or removed while it runs may or may not be
+    // seen, but every entry that's there the whole time is, once each.
+    pub fn range<R: RangeBounds<K>>(&self, range: R) -> Range<K, V> {
+        let mut pred = Arc::clone(&self.head);
+        for level in (0..MAX_HEIGHT).rev() {
+            while let Some(node) = pred.next(level) {
+                let before = match range.start_bound() {
+                    Bound::Included(start) => node.key() < start,
+                    Bound::Excluded(start) => node.key() <= start,
+                    Bound::Unbounded => false,
+                };
+                if !before {
+                    break;
+                }
+                pred = node;
+            }
+        }
+        Range {
+            next: pred.next(0),
+            end: range.end_bound().cloned(),
+        }
+    }
+
+    pub fn iter(&self) -> Range<K, V> {
+        self.range(..)
+    }
+
+    // Every level's preds and succs for key, and whether it's there
+    fn find(&self, key: &K) -> Position<K, V> {
+        let mut preds = Vec::with_capacity(MAX_HEIGHT);
+        let mut succs = Vec::with_capacity(MAX_HEIGHT);
+        let mut found = None;
+        let mut pred = Arc::clone(&self.head);
+        for level in (0..MAX_HEIGHT).rev() {
+            let mut succ = pred.next(level);
+            while let Some(node) = succ.as_ref().filter(|node| node.key() < key) {
+                pred = Arc::clone(node);
+                succ = pred.next(level);
+            }
+            if found.is_none() && succ.as_ref().is_some_and(|node| node.key() == key) {
+                found = Some(level);
+            }
+            preds.push(Arc::clone(&pred));
+            succs.push(succ);
+        }
+        preds.reverse();
+        succs.reverse();
+        Position {
+            preds,
+            succs,
+            found,
+        }
+    }
+
+    // 1 half the time, 2 a quarter of the time, ...
+    fn random_height(&self) -> usize {
+        // splitmix64
+        let mut x = self
+            .seed
+            .fetch_add(0x9E37_79B9_7F4A_7C15, Ordering::Relaxed);
+        x = (x ^ (x >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        x = (x ^ (x >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        x ^= x >> 31;
+        1 + (x.trailing_ones() as usize).min(MAX_HEIGHT - 1)
+    }
+}
+
+impl<K: Ord + Clone, V: Clone> Default for SkipMap<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: Ord + Clone + fmt::Debug, V: Clone + fmt::Debug> fmt::Debug for SkipMap<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+// Left to themselves, dropping the first node would drop the second through its link, and so on
+// down the list, one stack frame per node. Cutting every link first drops them one at a time.
+impl<K, V> Drop for SkipMap<K, V> {
+    fn drop(&mut self) {
+        let mut next = write(&self.head.next[0]).take();
+        for link in self.head.next.iter() {
+            write(link).take();
+        }
+        while let Some(node) = next {
+            next = write(&node.next[0]).take();
+            for link in node.next.iter() {
+                write(link).take();
+            }
+        }
+    }
+}
+
+// Cloned entries from SkipMap::range
+pub struct Range<K, V> {
+    next: Option<Arc<Node<K, V>>>,
+    end: Bound<K>,
+}
+
+impl<K: Ord + Clone, V: Clone> Iterator for Range<K, V> {
+    type Item = (K, V);
+
+    fn next(&mut self) -> Option<(K, V)> {
+        loop {
+            let node = self.next.take()?;
+            let in_range = match &self.end {
+                Bound::Included(end) => node.key() <= end,
+                Bound::Excluded(end) => node.key() < end,
+                Bound::Unbounded => true,
+            };
+            if !in_range {
+                return None;
+            }
+            self.next = node.next(0);
+            if node.is_live() {
+                return Some((node.key().clone(), read(node.value()).clone()));
+            }
+        }
+    }
+}
+
+// Locks each distinct pred once, lowest level (biggest key) first
+fn lock_preds<K, V>(preds: &[Arc<Node<K, V>>]) -> Vec<MutexGuard<'_, ()>> {
+    let mut guards = Vec::with_capacity(preds.len());
+    for (level, pred) in preds.iter().enumerate() {
+        if level == 0 || !Arc::ptr_eq(pred, &preds[level - 1]) {
+            guards.push(lock(&pred.lock));
+        }
+    }
+    guards
+}
+
+fn same<K, V>(a: &Option<Arc<Node<K, V>>>, b: &Option<Arc<Node<K, V>>>) -> bool {
+    match (a, b) {
+        (Some(a), Some(b)) => Arc::ptr_eq(a, b),
+        (None, None) => true,
+        _ => false,
+    }
+}
+
+// Nothing panics while holding a lock except a user's Clone, and that leaves nothing half done,
+// so a poisoned lock is as good as any other
+fn ignore_poison<T>(result: LockResult<T>) -> T {
+    result.unwrap_or_else(PoisonError::into_inner)
+}
+
+fn lock(mutex: &Mutex<()>) -> MutexGuard<'_, ()> {
+    ignore_poison(mutex.lock())
+}
+
+fn read<T>(lock: &RwLock<T>) -> RwLockReadGuard<'_, T> {
+    ignore_poison(lock.read())
+}
+
+fn write<T>(lock: &RwLock<T>) -> RwLockWriteGuard<'_, T> {
+    ignore_poison(lock.write())
+}
+
+#[cfg(all(test, not(loom)))]
+mod testing {
+    use std::collections::BTreeMap;
+    use std::thread;
+
+    use super::*;
+
+    #[test]
+    fn agrees_with_btreemap() {
+        let map = SkipMap::new();
+        let mut model = BTreeMap::new();
+        let mut x: u64 = 3;
+        for _ in 0..20_000 {
+            x = x.wrapping_mul(6364136223846793005).wrapping_add(1);
+            let key = (x >> 33) % 500;
+            match (x >> 20) % 4 {
+                0 | 1 => assert_eq!(map.insert(key, x), model.insert(key, x)),
+                2 => assert_eq!(map.remove(&key), model.remove(&key)),
+                _ => assert_eq!(map.get(&key), model.get(&key).copied()),
+            }
+            assert_eq!(map.len(), model.len());
+        }
+
+        assert!(map.iter().eq(model.clone()));
+        assert!(map
+            .range(100..=200)
+            .eq(model.range(100..=200).map(|(k, v)| (*k, *v))));
+        let bounds = (Bound::Excluded(250), Bound::Unbounded);
+        assert!(map
+            .range(bounds)
+            .eq(model.range(bounds).map(|(k, v)| (*k, *v))));
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn threads_share_the_map() {
+        const THREADS: u64 = 4;
+        const PER_THREAD: u64 = 5_000;
+        let map = SkipMap::new();
+
+        thread::scope(|s| {
+            for t in 0..THREADS {
+                let map = &map;
+                s.spawn(move || {
+                    // interleaved keys, so every thread is working in the same neighbourhoods
+                    for i in 0..PER_THREAD {
+                        map.insert(i * THREADS + t, t);
+                    }
+                    for i in (1..PER_THREAD).step_by(2) {
+                        assert_eq!(map.remove(&(i * THREADS + t)), Some(t));
+                    }
+                });
+            }
+            // a scan part way through only ever sees keys in order
+            s.spawn(|| {
+                for _ in 0..20 {
+                    let keys: Vec<u64> = map.iter().map(|(key, _)| key).collect();
+                    assert!(keys.windows(2).all(|pair| pair[0] < pair[1]));
+                }
+            });
+        });
+
+        let expected: Vec<(u64, u64)> = (0..PER_THREAD)
+            .step_by(2)
+            .flat_map(|i| (0..THREADS).map(move |t| (i * THREADS + t, t)))
+            .collect();
+        assert_eq!(map.len(), expected.len());
+        assert!(map.iter().eq(expected));
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn long_lists_drop_without_recursing() {
+        let map = SkipMap::new();
+        for i in 0..200_000u32 {
+            map.insert(i, ());
+        }
+        drop(map);
+    }
+}
+
+// Model checked with loom, which runs the closure under every interleaving of the threads:
+//  LOOM_MAX_PREEMPTIONS=4 RUSTFLAGS="--cfg loom" cargo test --release --lib skiplist
+// The bound matters: a thread that loses a race searches again, and loom would otherwise keep
+// finding new ways to make it lose.
+#[cfg(all(test, loom))]
+mod loom_testing {
+    use super::*;
+
+    #[test]
+    fn concurrent_inserts_all_land() {
+        loom::model(|| {
+            let map = Arc::new(SkipMap::new());
+            map.insert(2, 'b');
+            let other = Arc::clone(&map);
+            let t = loom::thread::spawn(move || {
+                other.insert(1, 'a');
+            });
+            map.insert(3, 'c');
+            t.join().unwrap();
+            assert!(map.iter().eq([(1, 'a'), (2, 'b'), (3, 'c')]));
+            assert_eq!(map.len(), 3);
+        });
+    }
+
+    #[test]
+    fn insert_next_to_a_removal_isnt_lost() {
+        loom::model(|| {
+            // 3's pred on level 0 is the node being removed
+            let map = Arc::new(SkipMap::new());
+            map.insert(2, ());
+            let other = Arc::clone(&map);
+            let t = loom::thread::spawn(move || other.remove(&2));
+            map.insert(3, ());
+            assert_eq!(t.join().unwrap(), Some(()));
+            assert!(map.iter().eq([(3, ())]));
+        });
+    }
+
+    #[test]
+    fn insert_and_remove_of_one_key() {
+        loom::model(|| {
+            let map = Arc::new(SkipMap::new());
+            map.insert(1, 0);
+            let other = Arc::clone(&map);
+            let t = loom::thread::spawn(move || other.remove(&1));
+            let replaced = map.insert(1, 10);
+            let removed = t.join().unwrap();
+            // either order is fine, but it has to be one of them
+            match (removed, replaced) {
+                (Some(0), None) => assert_eq!(map.get(&1), Some(10)),
+                (Some(10), Some(0)) => assert_eq!(map.get(&1), None),
+                other => panic!("no order of the two gives {other:?}"),
+            }
+        });
+    }
+}
@@ -0,0 +1,16 @@
+// The map's locks and atomics, swapped for loom's model-checked versions when building with
+// --cfg loom
+
+#[cfg(loom)]
+pub(super) use loom::{
+    sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
+    sync::{Arc, Mutex, MutexGuard, RwLock, RwLockReadGuard, RwLockWriteGuard},
+    thread,
+};
+
+#[cfg(not(loom))]
+pub(super) use std::{
+    sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
+    sync::{Arc, Mutex, MutexGuard, RwLock, RwLockReadGuard, RwLockWriteGuard},
+    thread,
+};
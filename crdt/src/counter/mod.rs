@@ -0,0 +1,109 @@
+use std::collections::BTreeMap;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::Merge;
+
+// A counter that only goes up
+//
+// A single shared number can't be merged: if A says 5 and B says 7, was that two increments on
+// top of A's, or B's own count all along? So every replica keeps its own count, and only ever
+// increments its own entry. The value is the sum, and merging takes the larger count for each
+// replica, which is whichever has seen more of that replica's increments:
+//
+//   A: {A: 3, B: 1}     merge     B: {A: 2, B: 4}     =     {A: 3, B: 4}   value 7
+//
+// R is whatever identifies a replica, a node name or a number, and must be unique to it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct GCounter<R: Ord> {
+    counts: BTreeMap<R, u64>,
+}
+
+impl<R: Ord + Clone> GCounter<R> {
+    pub fn new() -> Self {
+        GCounter {
+            counts: BTreeMap::new(),
+        }
+    }
+
+    // Counts up by `by` on behalf of replica, which should be the one calling
+    pub fn increment(&mut self, replica: &R, by: u64) {
+        match self.counts.get_mut(replica) {
+            Some(count) => *count += by,
+            None => {
+                self.counts.insert(replica.clone(), by);
+            }
+        }
+    }
+
+    pub fn value(&self) -> u64 {
+        self.counts.values().sum()
+    }
+
+    // How much one replica has counted, as far as this copy knows
+    pub fn count(&self, replica: &R) -> u64 {
+        self.counts.get(replica).copied().unwrap_or(0)
+    }
+}
+
+impl<R: Ord + Clone> Default for GCounter<R> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<R: Ord + Clone> Merge for GCounter<R> {
+    fn merge(&mut self, other: &Self) {
+        for (replica, &theirs) in &other.counts {
+            let ours = self.counts.entry(replica.clone()).or_insert(0);
+            *ours = (*ours).max(theirs);
+        }
+    }
+}
+
+// A counter that goes up and down
+// Decrementing can't be done to a GCounter (merge would take the old, larger count and undo it),
+// so this is two of them, one counting up and one counting down, and the value is the
+// difference. Each half only ever grows, so each merges as before.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct PNCounter<R: Ord> {
+    up: GCounter<R>,
+    down: GCounter<R>,
+}
+
+impl<R: Ord + Clone> PNCounter<R> {
+    pub fn new() -> Self {
+        PNCounter {
+            up: GCounter::new(),
+            down: GCounter::new(),
+        }
+    }
+
+    pub fn increment(&mut self, replica: &R, by: u64) {
+        self.up.increment(replica, by);
+    }
+
+    pub fn decrement(&mut self, replica: &R, by: u64) {
+        self.down.increment(replica, by);
+    }
+
+    pub fn value(&self) -> i128 {
+        i128::from(self.up.value()) - i128::from(self.down.value())
+    }
+}
+
+impl<R: Ord + Clone> Default for PNCounter<R> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<R: Ord + Clone> Merge for PNCounter<R> {
+    fn merge(&mut self, other: &Self) {
+        self.up.merge(&other.up);
+        self.down.merge(&other.down);
+    }
+}
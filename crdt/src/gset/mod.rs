@@ -0,0 +1,57 @@
+use std::collections::BTreeSet;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::Merge;
+
+// A set that only grows: add anywhere, merge is union
+// Removing isn't possible, a replica that hadn't seen the removal would put the value back on
+// the next merge. (A 2P-Set adds a second GSet of removed values, at the price of never being
+// able to add one back.)
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct GSet<T: Ord> {
+    values: BTreeSet<T>,
+}
+
+impl<T: Ord + Clone> GSet<T> {
+    pub fn new() -> Self {
+        GSet {
+            values: BTreeSet::new(),
+        }
+    }
+
+    // true if it wasn't there already
+    pub fn insert(&mut self, value: T) -> bool {
+        self.values.insert(value)
+    }
+
+    pub fn contains(&self, value: &T) -> bool {
+        self.values.contains(value)
+    }
+
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.values.iter()
+    }
+}
+
+impl<T: Ord + Clone> Default for GSet<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Ord + Clone> Merge for GSet<T> {
+    fn merge(&mut self, other: &Self) {
+        self.values.extend(other.values.iter().cloned());
+    }
+}
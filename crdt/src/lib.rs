@@ -0,0 +1,9 @@
+mod counter;
+mod gset;
+mod lww;
+mod merge;
+
+pub use counter::{GCounter, PNCounter};
+pub use gset::GSet;
+pub use lww::LwwRegister;
+pub use merge::Merge;
@@ -0,0 +1,69 @@
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::Merge;
+
+// A single value where the last write wins
+//
+// Every write is stamped with a timestamp and the replica that made it, and merge keeps the
+// write with the bigger (timestamp, replica). Two writes with the same timestamp are put in
+// order by replica, so every copy picks the same one rather than each keeping its own.
+//
+//   A sets "x" at (5, A)    B sets "y" at (5, B)    merged either way: "y", (5, B) > (5, A)
+//
+// Timestamps come from the caller, and "last" is only as good as they are: wall clocks drift,
+// so a replica with a fast clock wins writes it made earlier. Hybrid logical clocks are the usual
+// fix. A replica must never stamp two different values with the same timestamp; if it does, the
+// bigger value wins, which keeps merge well defined but is as arbitrary as it sounds.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct LwwRegister<T, R> {
+    value: T,
+    timestamp: u64,
+    replica: R,
+}
+
+impl<T: Ord + Clone, R: Ord + Clone> LwwRegister<T, R> {
+    pub fn new(value: T, timestamp: u64, replica: R) -> Self {
+        LwwRegister {
+            value,
+            timestamp,
+            replica,
+        }
+    }
+
+    pub fn value(&self) -> &T {
+        &self.value
+    }
+
+    pub fn timestamp(&self) -> u64 {
+        self.timestamp
+    }
+
+    pub fn replica(&self) -> &R {
+        &self.replica
+    }
+
+    // Writes value, unless what's here is newer already
+    // Returns whether it was written.
+    pub fn set(&mut self, value: T, timestamp: u64, replica: R) -> bool {
+        let write = LwwRegister::new(value, timestamp, replica);
+        let newer = write.key() > self.key();
+        if newer {
+            *self = write;
+        }
+        newer
+    }
+
+    fn key(&self) -> (u64, &R, &T) {
+        (self.timestamp, &self.replica, &self.value)
+    }
+}
+
+impl<T: Ord + Clone, R: Ord + Clone> Merge for LwwRegister<T, R> {
+    fn merge(&mut self, other: &Self) {
+        if other.key() > self.key() {
+            self.clone_from(other);
+        }
+    }
+}
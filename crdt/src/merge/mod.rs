@@ -0,0 +1,26 @@
+#[cfg(test)]
+mod properties;
+
+// Conflict-free replicated data types: values that several replicas change independently, with
+// no coordination, and that still end up the same everywhere once they've seen each other's
+// changes
+//
+// These are the state-based kind (CvRDTs). Each replica changes its own copy, and now and then
+// sends the whole state to others, who merge it into theirs. Messages can arrive late, twice, or
+// in any order, so merge has to be
+//  - commutative:  a.merge(b) == b.merge(a)             order of arrival doesn't matter
+//  - associative:  (a.merge(b)).merge(c) == a.merge(b.merge(c))   nor does grouping
+//  - idempotent:   a.merge(a) == a                      nor does hearing the same thing twice
+// which makes merge the least upper bound of a join semilattice, and every state a replica can
+// reach only ever moves up it. Two replicas that have seen the same set of updates, by whatever
+// route, are in the same state.
+//
+//   replica A:  inc ----> {A: 1} ---- merge B ----> {A: 1, B: 2}
+//                                 \               /
+//   replica B:  inc inc -> {B: 2} ---- merge A --'  (same state)
+//
+// The catch is that every operation has to be expressible that way, which is why there's no
+// plain "decrement" on a GCounter or "remove" on a GSet: those would move a state down.
+pub trait Merge {
+    fn merge(&mut self, other: &Self);
+}
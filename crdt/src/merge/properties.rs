@@ -0,0 +1,149 @@
+// Property tests for the merge laws: for any three states of the same type, merge has to be
+// commutative, associative and idempotent. States are built by running random operations from a
+// few replicas, with small ranges so that replicas and values collide often.
+use std::fmt::Debug;
+
+use proptest::prelude::*;
+use proptest::test_runner::TestCaseError;
+
+use crate::{GCounter, GSet, LwwRegister, Merge, PNCounter};
+
+fn merged<C: Merge + Clone>(a: &C, b: &C) -> C {
+    let mut merged = a.clone();
+    merged.merge(b);
+    merged
+}
+
+fn check_laws<C: Merge + Clone + PartialEq + Debug>(a: C, b: C, c: C) -> Result<(), TestCaseError> {
+    prop_assert_eq!(merged(&a, &b), merged(&b, &a), "commutative");
+    prop_assert_eq!(
+        merged(&merged(&a, &b), &c),
+        merged(&a, &merged(&b, &c)),
+        "associative"
+    );
+    prop_assert_eq!(merged(&a, &a), a.clone(), "idempotent");
+    // and merging only ever moves a state up: what it's merged with is absorbed
+    let ab = merged(&a, &b);
+    prop_assert_eq!(merged(&ab, &b), ab.clone(), "absorbs");
+    Ok(())
+}
+
+fn gcounter() -> impl Strategy<Value = GCounter<u8>> {
+    prop::collection::vec((0_u8..4, 0_u64..100), 0..20).prop_map(|ops| {
+        let mut counter = GCounter::new();
+        for (replica, by) in ops {
+            counter.increment(&replica, by);
+        }
+        counter
+    })
+}
+
+fn pncounter() -> impl Strategy<Value = PNCounter<u8>> {
+    prop::collection::vec((0_u8..4, -100_i64..100), 0..20).prop_map(|ops| {
+        let mut counter = PNCounter::new();
+        for (replica, by) in ops {
+            match by >= 0 {
+                true => counter.increment(&replica, by.unsigned_abs()),
+                false => counter.decrement(&replica, by.unsigned_abs()),
+            }
+        }
+        counter
+    })
+}
+
+fn gset() -> impl Strategy<Value = GSet<u16>> {
+    prop::collection::vec(0_u16..50, 0..30).prop_map(|values| {
+        let mut set = GSet::new();
+        for value in values {
+            set.insert(value);
+        }
+        set
+    })
+}
+
+fn lww() -> impl Strategy<Value = LwwRegister<u8, u8>> {
+    prop::collection::vec((0_u8..5, 0_u64..8, 0_u8..3), 1..10).prop_map(|writes| {
+        let (value, timestamp, replica) = writes[0];
+        let mut register = LwwRegister::new(value, timestamp, replica);
+        for &(value, timestamp, replica) in &writes[1..] {
+            register.set(value, timestamp, replica);
+        }
+        register
+    })
+}
+
+proptest! {
+    #[test]
+    fn gcounter_laws(a in gcounter(), b in gcounter(), c in gcounter()) {
+        check_laws(a, b, c)?;
+    }
+
+    #[test]
+    fn pncounter_laws(a in pncounter(), b in pncounter(), c in pncounter()) {
+        check_laws(a, b, c)?;
+    }
+
+    #[test]
+    fn gset_laws(a in gset(), b in gset(), c in gset()) {
+        check_laws(a, b, c)?;
+    }
+
+    #[test]
+    fn lww_laws(a in lww(), b in lww(), c in lww()) {
+        check_laws(a, b, c)?;
+    }
+
+    // Replicas that each count on their own converge on the total of everything counted
+    #[test]
+    fn counters_converge_on_the_total(
+        ops in prop::collection::vec((0_usize..3, -50_i64..50), 0..40),
+    ) {
+        let mut replicas: Vec<PNCounter<usize>> = vec![PNCounter::new(); 3];
+        for &(replica, by) in &ops {
+            match by >= 0 {
+                true => replicas[replica].increment(&replica, by.unsigned_abs()),
+                false => replicas[replica].decrement(&replica, by.unsigned_abs()),
+            }
+        }
+        let all = replicas.iter().fold(PNCounter::new(), |acc, r| merged(&acc, r));
+        let total: i64 = ops.iter().map(|&(_, by)| by).sum();
+        prop_assert_eq!(all.value(), i128::from(total));
+    }
+}
+
+#[test]
+fn last_writer_wins_and_ties_go_to_the_bigger_replica() {
+    let mut a = LwwRegister::new("x", 5, 'a');
+    let b = LwwRegister::new("y", 5, 'b');
+    assert!(!a.set("old", 4, 'z'));
+    a.merge(&b);
+    assert_eq!((*a.value(), a.timestamp(), *a.replica()), ("y", 5, 'b'));
+    assert!(a.set("z", 6, 'a'));
+    assert_eq!(*a.value(), "z");
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn states_round_trip_through_json() {
+    let mut counter = PNCounter::new();
+    counter.increment(&"a".to_string(), 5);
+    counter.decrement(&"b".to_string(), 7);
+    let json = serde_json::to_string(&counter).unwrap();
+    assert_eq!(
+        json,
+        r#"{"up":{"counts":{"a":5}},"down":{"counts":{"b":7}}}"#
+    );
+    let back: PNCounter<String> = serde_json::from_str(&json).unwrap();
+    assert_eq!(back, counter);
+    assert_eq!(back.value(), -2);
+
+    let set: GSet<u32> = serde_json::from_str(r#"{"values":[3,1,2]}"#).unwrap();
+    assert_eq!(set.iter().copied().collect::<Vec<_>>(), [1, 2, 3]);
+
+    let register = LwwRegister::new(vec![1, 2], 9, 3_u8);
+    let json = serde_json::to_string(&register).unwrap();
+    assert_eq!(
+        serde_json::from_str::<LwwRegister<Vec<i32>, u8>>(&json).unwrap(),
+        register
+    );
+}
@@ -0,0 +1,3 @@
+mod sorter;
+
+pub use sorter::{Codec, ExternalSorter, Sorted};
@@ -0,0 +1,126 @@
+use std::cmp::Ordering;
+use std::fs::File;
+use std::io::{self, BufReader};
+
+use tempfile::TempPath;
+
+use super::Codec;
+
+// k-way merge of sorted runs
+// The head of every run sits in a binary min-heap, ordered by compare and then by run number so
+// equal records come out of earlier runs first. BinaryHeap wants Ord, which a closure can't give
+// it, so the heap is done by hand.
+// The runs' files are opened here and deleted when the merge is dropped.
+pub(super) struct Merge<T, C, F> {
+    runs: Vec<BufReader<File>>,
+    _paths: Vec<TempPath>,
+    // (head record, run it came from)
+    heap: Vec<(T, usize)>,
+    // the run the last record came from, refilled on the next call so a read error comes after
+    // the record before it rather than instead of it
+    refill: Option<usize>,
+    codec: C,
+    compare: F,
+    failed: bool,
+}
+
+impl<T, C, F> Merge<T, C, F>
+where
+    C: Codec<T>,
+    F: FnMut(&T, &T) -> Ordering,
+{
+    pub(super) fn new(paths: Vec<TempPath>, codec: C, compare: F) -> io::Result<Self> {
+        let runs = paths
+            .iter()
+            .map(|path| File::open(path).map(BufReader::new))
+            .collect::<io::Result<_>>()?;
+        let mut merge = Merge {
+            runs,
+            _paths: paths,
+            heap: Vec::new(),
+            refill: None,
+            codec,
+            compare,
+            failed: false,
+        };
+        for run in 0..merge.runs.len() {
+            if let Some(item) = merge.codec.read(&mut merge.runs[run])? {
+                merge.heap.push((item, run));
+                merge.sift_up(merge.heap.len() - 1);
+            }
+        }
+        Ok(merge)
+    }
+
+    fn less(&mut self, a: usize, b: usize) -> bool {
+        let ((a, run_a), (b, run_b)) = (&self.heap[a], &self.heap[b]);
+        match (self.compare)(a, b) {
+            Ordering::Equal => run_a < run_b,
+            ordering => ordering == Ordering::Less,
+        }
+    }
+
+    fn sift_up(&mut self, mut i: usize) {
+        while i > 0 {
+            let parent = (i - 1) / 2;
+            if !self.less(i, parent) {
+                break;
+            }
+            self.heap.swap(i, parent);
+            i = parent;
+        }
+    }
+
+    fn sift_down(&mut self, mut i: usize) {
+        loop {
+            let (left, right) = (2 * i + 1, 2 * i + 2);
+            let mut smallest = i;
+            if left < self.heap.len() && self.less(left, smallest) {
+                smallest = left;
+            }
+            if right < self.heap.len() && self.less(right, smallest) {
+                smallest = right;
+            }
+            if smallest == i {
+                break;
+            }
+            self.heap.swap(i, smallest);
+            i = smallest;
+        }
+    }
+}
+
+impl<T, C, F> Iterator for Merge<T, C, F>
+where
+    C: Codec<T>,
+    F: FnMut(&T, &T) -> Ordering,
+{
+    type Item = io::Result<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+        if let Some(run) = self.refill.take() {
+            match self.codec.read(&mut self.runs[run]) {
+                Ok(Some(item)) => {
+                    self.heap.push((item, run));
+                    self.sift_up(self.heap.len() - 1);
+                }
+                Ok(None) => {}
+                Err(e) => {
+                    self.failed = true;
+                    return Some(Err(e));
+                }
+            }
+        }
+        if self.heap.is_empty() {
+            return None;
+        }
+
+        let (item, run) = self.heap.swap_remove(0);
+        self.sift_down(0);
+        self.refill = Some(run);
+        Some(Ok(item))
+    }
+}
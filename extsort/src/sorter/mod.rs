@@ -0,0 +1,345 @@
+// Sorting more records than fit in memory (external merge sort)
+//
+// Records are pushed into a buffer of run_len records. Each time it fills it's sorted and written
+// out to a temporary file, a "run". finish then merges the runs, reading one record at a time
+// from each and always taking the smallest head:
+//
+//   input:   9 4 7 1 | 8 2 6 3 | 5
+//   runs:    1 4 7 9 | 2 3 6 8 | 5          (sorted in memory, spilled to disk)
+//   merge:   heads 1 2 5 -> take 1, refill from run 0 -> heads 4 2 5 -> take 2 ...
+//
+// So memory holds at most run_len records while pushing, and one record per run while merging.
+// A run is closed once it's written and only opened again to be merged, so a big input's
+// thousand runs aren't a thousand open files. Merging k runs keeps k files open though, so with
+// more than merge_width runs some are first merged into longer runs, merge_width at a time,
+// until few enough are left. Everything that fits in one run is never written out at all.
+//
+// Records go to disk through a Codec, which is how this knows nothing about what's being sorted.
+// The sort is stable: runs hold consecutive stretches of the input, ties within a run keep their
+// order (slice::sort_by is stable) and ties between runs go to the earlier run.
+
+mod merge;
+
+use std::cmp::Ordering;
+use std::io::{self, BufRead, BufWriter, Write};
+use std::path::PathBuf;
+use std::vec;
+
+use merge::Merge;
+use tempfile::{NamedTempFile, TempPath};
+
+// How records are written to and read back from runs
+// read gets the bytes write wrote, in the same order, and returns None only at the very end of
+// a run. Anything else that goes wrong (a run that ends half way through a record) is an error.
+pub trait Codec<T> {
+    fn write<W: Write>(&self, item: &T, out: &mut W) -> io::Result<()>;
+    fn read<R: BufRead>(&self, input: &mut R) -> io::Result<Option<T>>;
+}
+
+impl<T, C: Codec<T>> Codec<T> for &C {
+    fn write<W: Write>(&self, item: &T, out: &mut W) -> io::Result<()> {
+        (**self).write(item, out)
+    }
+
+    fn read<R: BufRead>(&self, input: &mut R) -> io::Result<Option<T>> {
+        (**self).read(input)
+    }
+}
+
+pub struct ExternalSorter<T, C, F> {
+    codec: C,
+    compare: F,
+    run_len: usize,
+    merge_width: usize,
+    // None is the system's temp directory
+    temp_dir: Option<PathBuf>,
+    buffer: Vec<T>,
+    // spilled runs, closed until they're merged and deleted when they're dropped
+    runs: Vec<TempPath>,
+}
+
+impl<T, C, F> ExternalSorter<T, C, F>
+where
+    C: Codec<T>,
+    F: FnMut(&T, &T) -> Ordering,
+{
+    pub const DEFAULT_RUN_LEN: usize = 1 << 20;
+    pub const DEFAULT_MERGE_WIDTH: usize = 64;
+
+    // Sorts by compare, ExternalSorter::new(codec, u64::cmp) or |a, b| a.key.cmp(&b.key)
+    pub fn new(codec: C, compare: F) -> Self {
+        ExternalSorter {
+            codec,
+            compare,
+            run_len: Self::DEFAULT_RUN_LEN,
+            merge_width: Self::DEFAULT_MERGE_WIDTH,
+            temp_dir: None,
+            buffer: Vec::new(),
+            runs: Vec::new(),
+        }
+    }
+
+    // How many records to hold in memory before spilling a run
+    pub fn run_len(mut self, run_len: usize) -> Self {
+        assert!(run_len > 0, "runs need at least one record");
+        self.run_len = run_len;
+        self
+    }
+
+    // How many runs to merge at once, which is how many files are open at once
+    pub fn merge_width(mut self, merge_width: usize) -> Self {
+        assert!(
+            merge_width >= 2,
+            "merging needs at least two runs at a time"
+        );
+        self.merge_width = merge_width;
+        self
+    }
+
+    // Where to put runs, each is deleted once it's been merged (or the sorter's dropped)
+    pub fn temp_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.temp_dir = Some(dir.into());
+        self
+    }
+
+    // Runs spilled so far
+    pub fn run_count(&self) -> usize {
+        self.runs.len()
+    }
+
+    pub fn push(&mut self, item: T) -> io::Result<()> {
+        if self.buffer.len() == self.run_len {
+            self.spill()?;
+        }
+        self.buffer.push(item);
+        Ok(())
+    }
+
+    // Pushes everything and finishes
+    pub fn sort(mut self, items: impl IntoIterator<Item = T>) -> io::Result<Sorted<T, C, F>> {
+        for item in items {
+            self.push(item)?;
+        }
+        self.finish()
+    }
+
+    // Everything pushed, in order
+    pub fn finish(mut self) -> io::Result<Sorted<T, C, F>> {
+        if self.runs.is_empty() {
+            self.buffer.sort_by(&mut self.compare);
+            return Ok(Sorted {
+                inner: Inner::Memory(std::mem::take(&mut self.buffer).into_iter()),
+            });
+        }
+        if !self.buffer.is_empty() {
+            self.spill()?;
+        }
+
+        // each pass merges consecutive groups, so runs that were next to each other in the input
+        // stay next to each other, which stability needs
+        while self.runs.len() > self.merge_width {
+            let mut runs = std::mem::take(&mut self.runs).into_iter().peekable();
+            while runs.peek().is_some() {
+                let group: Vec<TempPath> = runs.by_ref().take(self.merge_width).collect();
+                let run = if group.len() == 1 {
+                    group.into_iter().next().unwrap()
+                } else {
+                    let merged = Merge::new(group, &self.codec, &mut self.compare)?;
+                    write_run(self.temp_dir.as_ref(), &self.codec, merged)?
+                };
+                self.runs.push(run);
+            }
+        }
+
+        let runs = std::mem::take(&mut self.runs);
+        Ok(Sorted {
+            inner: Inner::Merge(Merge::new(runs, self.codec, self.compare)?),
+        })
+    }
+
+    fn spill(&mut self) -> io::Result<()> {
+        self.buffer.sort_by(&mut self.compare);
+        let items = self.buffer.drain(..).map(Ok);
+        let run = write_run(self.temp_dir.as_ref(), &self.codec, items)?;
+        self.runs.push(run);
+        Ok(())
+    }
+}
+
+// Writes a run and closes it, what's kept is its path
+fn write_run<T>(
+    dir: Option<&PathBuf>,
+    codec: &impl Codec<T>,
+    items: impl Iterator<Item = io::Result<T>>,
+) -> io::Result<TempPath> {
+    let (file, path) = match dir {
+        Some(dir) => NamedTempFile::new_in(dir)?,
+        None => NamedTempFile::new()?,
+    }
+    .into_parts();
+    let mut out = BufWriter::new(file);
+    for item in items {
+        codec.write(&item?, &mut out)?;
+    }
+    out.flush()?;
+    Ok(path)
+}
+
+// The sorted records, read back from the runs as they're needed
+// Reading a run can fail, so items are io::Result. After an error there are no more items.
+pub struct Sorted<T, C, F> {
+    inner: Inner<T, C, F>,
+}
+
+enum Inner<T, C, F> {
+    Memory(vec::IntoIter<T>),
+    Merge(Merge<T, C, F>),
+}
+
+impl<T, C, F> Iterator for Sorted<T, C, F>
+where
+    C: Codec<T>,
+    F: FnMut(&T, &T) -> Ordering,
+{
+    type Item = io::Result<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match &mut self.inner {
+            Inner::Memory(items) => items.next().map(Ok),
+            Inner::Merge(merge) => merge.next(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod testing {
+    use binio::{ReadBe, WriteBe};
+
+    use super::*;
+
+    // (key, position in the input), sorted by key only so stability shows
+    struct PairCodec;
+
+    impl Codec<(u32, u32)> for PairCodec {
+        fn write<W: Write>(&self, item: &(u32, u32), out: &mut W) -> io::Result<()> {
+            out.write_be_u32(item.0)?;
+            out.write_be_u32(item.1)
+        }
+
+        fn read<R: BufRead>(&self, input: &mut R) -> io::Result<Option<(u32, u32)>> {
+            if input.fill_buf()?.is_empty() {
+                return Ok(None);
+            }
+            Ok(Some((input.read_be_u32()?, input.read_be_u32()?)))
+        }
+    }
+
+    fn random_pairs(seed: u64, n: usize, keys: u64) -> Vec<(u32, u32)> {
+        let mut x = seed;
+        (0..n as u32)
+            .map(|i| {
+                x = x.wrapping_mul(6364136223846793005).wrapping_add(1);
+                (((x >> 33) % keys) as u32, i)
+            })
+            .collect()
+    }
+
+    fn by_key(a: &(u32, u32), b: &(u32, u32)) -> Ordering {
+        a.0.cmp(&b.0)
+    }
+
+    #[test]
+    fn agrees_with_a_stable_sort() {
+        for (n, run_len, merge_width) in [
+            (0, 4, 2),
+            (3, 4, 2),
+            (4, 4, 2),
+            (5, 4, 2),
+            (1000, 7, 2),
+            (1000, 10, 3),
+            (5000, 64, 64),
+            (3000, 1, 64),
+        ] {
+            let input = random_pairs(n as u64 + 1, n, 50);
+            let mut expected = input.clone();
+            expected.sort_by(by_key);
+
+            let sorted: Vec<_> = ExternalSorter::new(PairCodec, by_key)
+                .run_len(run_len)
+                .merge_width(merge_width)
+                .sort(input)
+                .unwrap()
+                .collect::<io::Result<_>>()
+                .unwrap();
+            assert_eq!(sorted, expected, "{n} records, runs of {run_len}");
+        }
+    }
+
+    #[test]
+    fn small_inputs_stay_in_memory() {
+        let mut sorter = ExternalSorter::new(PairCodec, by_key).run_len(10);
+        for pair in random_pairs(1, 10, 5) {
+            sorter.push(pair).unwrap();
+        }
+        assert_eq!(sorter.run_count(), 0);
+        sorter.push((0, 10)).unwrap();
+        assert_eq!(sorter.run_count(), 1);
+
+        let dir = tempfile::tempdir().unwrap();
+        let sorted = ExternalSorter::new(PairCodec, |a: &(u32, u32), b: &(u32, u32)| b.cmp(a))
+            .temp_dir(dir.path())
+            .run_len(2)
+            .sort([(1, 0), (3, 0), (2, 0)])
+            .unwrap();
+        assert_eq!(
+            sorted.map(Result::unwrap).collect::<Vec<_>>(),
+            [(3, 0), (2, 0), (1, 0)]
+        );
+    }
+
+    // Only a run being written or merged is open, a spilled one is just a file name
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn spilled_runs_are_closed() {
+        let open_files = || std::fs::read_dir("/proc/self/fd").unwrap().count();
+        let before = open_files();
+        let mut sorter = ExternalSorter::new(PairCodec, by_key).run_len(1);
+        for pair in random_pairs(2, 2000, 100) {
+            sorter.push(pair).unwrap();
+        }
+        assert_eq!(sorter.run_count(), 1999);
+        // other tests running alongside can open a few files of their own
+        assert!(open_files() < before + 100);
+        assert_eq!(sorter.finish().unwrap().count(), 2000);
+    }
+
+    // Reads back one byte short of a record, like a run that was cut off
+    struct Truncating;
+
+    impl Codec<u32> for Truncating {
+        fn write<W: Write>(&self, item: &u32, out: &mut W) -> io::Result<()> {
+            let bytes = item.to_be_bytes();
+            out.write_all(if *item == 7 { &bytes[..3] } else { &bytes })
+        }
+
+        fn read<R: BufRead>(&self, input: &mut R) -> io::Result<Option<u32>> {
+            if input.fill_buf()?.is_empty() {
+                return Ok(None);
+            }
+            input.read_be_u32().map(Some)
+        }
+    }
+
+    #[test]
+    fn read_errors_end_the_iterator() {
+        let mut sorted = ExternalSorter::new(Truncating, u32::cmp)
+            .run_len(2)
+            .sort([1, 7, 3, 2])
+            .unwrap();
+        // runs [1, 7] and [2, 3], 7 is cut short
+        assert_eq!(sorted.next().unwrap().unwrap(), 1);
+        let err = sorted.next().unwrap().unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+        assert!(sorted.next().is_none());
+    }
+}
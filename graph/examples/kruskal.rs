@@ -0,0 +1,54 @@
+// Minimum spanning tree with Kruskal's algorithm
+//  cargo run --example kruskal
+// Take edges cheapest first and keep every one that joins two pieces that aren't connected yet.
+// "Are these already connected" is exactly what DisjointSet answers, and keeping an edge is a
+// union. Once there's one piece left (n - 1 edges kept) it's a spanning tree, and no other one
+// is cheaper: any cheaper edge we skipped would have closed a cycle.
+
+use graph::{DisjointSet, Graph};
+
+fn main() {
+    // road distances in km, a single edge per road (the direction doesn't matter here)
+    let mut graph = Graph::new();
+    let cities = [
+        "Aberdeen",
+        "Dundee",
+        "Edinburgh",
+        "Glasgow",
+        "Inverness",
+        "Perth",
+        "Stirling",
+    ];
+    let ids: Vec<_> = cities.iter().map(|&city| graph.add_node(city)).collect();
+    for (a, b, km) in [
+        (0, 1, 107),
+        (0, 4, 168),
+        (1, 2, 96),
+        (1, 5, 35),
+        (2, 3, 76),
+        (2, 5, 70),
+        (2, 6, 59),
+        (3, 6, 42),
+        (4, 5, 180),
+        (5, 6, 53),
+        (3, 4, 270),
+    ] {
+        graph.add_edge(ids[a], ids[b], km);
+    }
+
+    let mut edges: Vec<_> = graph.edge_ids().collect();
+    edges.sort_by_key(|&edge| graph.edge(edge).2);
+
+    let mut pieces = DisjointSet::new(graph.node_count());
+    let mut total = 0;
+    println!("roads to keep:");
+    for edge in edges {
+        let (a, b, &km) = graph.edge(edge);
+        if pieces.union(a.index(), b.index()) {
+            println!("  {:<10} - {:<10} {km:>4} km", graph.node(a), graph.node(b));
+            total += km;
+        }
+    }
+    assert_eq!(pieces.set_count(), 1, "the road map isn't connected");
+    println!("total: {total} km");
+}
@@ -0,0 +1,165 @@
+// A partition of 0..n into disjoint sets, with union and "which set is x in" both close to O(1)
+// Every set is a tree stored as parent links, and the root is the name of the set:
+//
+//   parent: [0, 0, 1, 3, 3]        0     3
+//                                  |     |
+//                                  1     4
+//                                  |
+//                                  2
+//
+// find walks up to the root. union hangs one root under the other, the shorter tree under the
+// taller (union by rank) so trees stay shallow. find also points every node it walked past
+// straight at the root (path compression) so the next walk is one step. Together they make any
+// sequence of operations take O(α(n)) each, where α is the inverse Ackermann function and is
+// under 5 for any n that fits in memory.
+#[derive(Debug, Clone)]
+pub struct DisjointSet {
+    parent: Vec<usize>,
+    // an upper bound on the tree's height, only meaningful for roots. It's never lowered when
+    // path compression flattens a tree, which is fine, it only has to be a bound
+    rank: Vec<u8>,
+    sets: usize,
+}
+
+impl DisjointSet {
+    // Every element starts in a set of its own
+    pub fn new(len: usize) -> Self {
+        DisjointSet {
+            parent: (0..len).collect(),
+            rank: vec![0; len],
+            sets: len,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.parent.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.parent.is_empty()
+    }
+
+    pub fn set_count(&self) -> usize {
+        self.sets
+    }
+
+    // Adds a new element in a set of its own, and returns it
+    pub fn push(&mut self) -> usize {
+        self.parent.push(self.parent.len());
+        self.rank.push(0);
+        self.sets += 1;
+        self.parent.len() - 1
+    }
+
+    // The root of x's set, every element of a set has the same one
+    // Takes &mut self because it compresses the path it walks.
+    pub fn find(&mut self, x: usize) -> usize {
+        let mut root = x;
+        while self.parent[root] != root {
+            root = self.parent[root];
+        }
+        // second pass: point everything on the way at the root
+        let mut x = x;
+        while self.parent[x] != root {
+            let next = self.parent[x];
+            self.parent[x] = root;
+            x = next;
+        }
+        root
+    }
+
+    // Joins the sets of a and b, false if they were already the same set
+    pub fn union(&mut self, a: usize, b: usize) -> bool {
+        let (a, b) = (self.find(a), self.find(b));
+        if a == b {
+            return false;
+        }
+
+        let (short, tall) = if self.rank[a] < self.rank[b] {
+            (a, b)
+        } else {
+            (b, a)
+        };
+        self.parent[short] = tall;
+        // two trees of the same height make one a level taller
+        if self.rank[short] == self.rank[tall] {
+            self.rank[tall] += 1;
+        }
+        self.sets -= 1;
+        true
+    }
+
+    pub fn same_set(&mut self, a: usize, b: usize) -> bool {
+        self.find(a) == self.find(b)
+    }
+}
+
+#[cfg(test)]
+mod testing {
+    use super::*;
+
+    #[test]
+    fn union_and_find() {
+        let mut sets = DisjointSet::new(6);
+        assert_eq!((sets.len(), sets.set_count()), (6, 6));
+        assert!(sets.union(0, 1));
+        assert!(sets.union(2, 3));
+        assert!(sets.union(1, 3));
+        assert!(!sets.union(0, 2));
+        assert_eq!(sets.set_count(), 3);
+
+        assert!(sets.same_set(0, 3));
+        assert!(!sets.same_set(0, 4));
+        let new = sets.push();
+        assert_eq!((new, sets.set_count()), (6, 4));
+        assert!(sets.union(new, 5));
+        assert!(sets.same_set(6, 5));
+        assert!(DisjointSet::new(0).is_empty());
+    }
+
+    #[test]
+    fn agrees_with_relabelling() {
+        // the slow way: a label per element, relabel a whole set on union
+        let n = 500;
+        let mut sets = DisjointSet::new(n);
+        let mut labels: Vec<usize> = (0..n).collect();
+
+        let mut x: u64 = 11;
+        let mut next = || {
+            x = x.wrapping_mul(6364136223846793005).wrapping_add(1);
+            (x >> 33) as usize % n
+        };
+        for _ in 0..2000 {
+            let (a, b) = (next(), next());
+            let (la, lb) = (labels[a], labels[b]);
+            assert_eq!(sets.union(a, b), la != lb);
+            for label in labels.iter_mut().filter(|label| **label == lb) {
+                *label = la;
+            }
+
+            let (c, d) = (next(), next());
+            assert_eq!(sets.same_set(c, d), labels[c] == labels[d]);
+        }
+
+        let mut distinct = labels.clone();
+        distinct.sort_unstable();
+        distinct.dedup();
+        assert_eq!(sets.set_count(), distinct.len());
+    }
+
+    #[test]
+    fn trees_stay_shallow() {
+        // union by rank alone keeps every tree O(log n) tall, even before any compression
+        let n = 1 << 16;
+        let mut sets = DisjointSet::new(n);
+        let mut width = 1;
+        while width < n {
+            for i in (0..n).step_by(2 * width) {
+                sets.union(i, i + width);
+            }
+            width *= 2;
+        }
+        assert_eq!(sets.set_count(), 1);
+        assert!(sets.rank.iter().all(|&rank| rank <= 16));
+    }
+}
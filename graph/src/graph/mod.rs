@@ -0,0 +1,219 @@
+mod shortest;
+mod topo;
+mod traverse;
+
+use std::fmt;
+use std::iter::FusedIterator;
+
+pub use shortest::{Path, ShortestPaths};
+pub use topo::Cycle;
+pub use traverse::{Bfs, Dfs};
+
+// A directed graph stored as adjacency lists
+//
+//   nodes:     0:"a"  1:"b"  2:"c"
+//   edges:     0: a -> b   1: a -> c   2: b -> c
+//   outgoing:  a: [0, 1]   b: [2]   c: []
+//
+// Nodes and edges live in Vecs and are named by their index, wrapped in NodeId/EdgeId so the two
+// can't be mixed up. Every node keeps the ids of the edges leaving it, so walking a node's
+// neighbours is a slice walk rather than a search through every edge.
+//
+// Nothing is ever removed, which is what keeps the ids valid for the life of the graph (removing
+// node 1 would either leave a hole or renumber everything after it).
+pub struct Graph<N, E> {
+    nodes: Vec<N>,
+    edges: Vec<Edge<E>>,
+    outgoing: Vec<Vec<EdgeId>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct NodeId(usize);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct EdgeId(usize);
+
+struct Edge<E> {
+    from: NodeId,
+    to: NodeId,
+    weight: E,
+}
+
+impl NodeId {
+    pub fn index(self) -> usize {
+        self.0
+    }
+}
+
+impl EdgeId {
+    pub fn index(self) -> usize {
+        self.0
+    }
+}
+
+impl<N, E> Graph<N, E> {
+    pub fn new() -> Self {
+        Graph {
+            nodes: Vec::new(),
+            edges: Vec::new(),
+            outgoing: Vec::new(),
+        }
+    }
+
+    pub fn node_count(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn edge_count(&self) -> usize {
+        self.edges.len()
+    }
+
+    pub fn add_node(&mut self, node: N) -> NodeId {
+        self.nodes.push(node);
+        self.outgoing.push(Vec::new());
+        NodeId(self.nodes.len() - 1)
+    }
+
+    // Parallel edges and self loops are both allowed
+    // Panics if either node isn't in the graph.
+    pub fn add_edge(&mut self, from: NodeId, to: NodeId, weight: E) -> EdgeId {
+        assert!(
+            from.0 < self.nodes.len() && to.0 < self.nodes.len(),
+            "no such node"
+        );
+        let id = EdgeId(self.edges.len());
+        self.edges.push(Edge { from, to, weight });
+        self.outgoing[from.0].push(id);
+        id
+    }
+
+    pub fn node(&self, id: NodeId) -> &N {
+        &self.nodes[id.0]
+    }
+
+    pub fn node_mut(&mut self, id: NodeId) -> &mut N {
+        &mut self.nodes[id.0]
+    }
+
+    // (from, to, weight)
+    pub fn edge(&self, id: EdgeId) -> (NodeId, NodeId, &E) {
+        let edge = &self.edges[id.0];
+        (edge.from, edge.to, &edge.weight)
+    }
+
+    pub fn edge_mut(&mut self, id: EdgeId) -> &mut E {
+        &mut self.edges[id.0].weight
+    }
+
+    pub fn node_ids(&self) -> impl ExactSizeIterator<Item = NodeId> + FusedIterator {
+        (0..self.nodes.len()).map(NodeId)
+    }
+
+    pub fn edge_ids(&self) -> impl ExactSizeIterator<Item = EdgeId> + FusedIterator {
+        (0..self.edges.len()).map(EdgeId)
+    }
+
+    // Edges leaving `id` in the order they were added, as (edge, to, weight)
+    pub fn edges_from(
+        &self,
+        id: NodeId,
+    ) -> impl DoubleEndedIterator<Item = (EdgeId, NodeId, &E)> + ExactSizeIterator + FusedIterator
+    {
+        self.outgoing[id.0].iter().map(|&edge| {
+            let (_, to, weight) = self.edge(edge);
+            (edge, to, weight)
+        })
+    }
+
+    // Nodes `id` has an edge to, once per edge
+    pub fn neighbors(
+        &self,
+        id: NodeId,
+    ) -> impl DoubleEndedIterator<Item = NodeId> + ExactSizeIterator + FusedIterator + '_ {
+        self.outgoing[id.0]
+            .iter()
+            .map(|&edge| self.edges[edge.0].to)
+    }
+
+    pub fn find_edge(&self, from: NodeId, to: NodeId) -> Option<EdgeId> {
+        self.outgoing[from.0]
+            .iter()
+            .copied()
+            .find(|edge| self.edges[edge.0].to == to)
+    }
+}
+
+impl<N, E> Default for Graph<N, E> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<N: fmt::Debug, E: fmt::Debug> fmt::Debug for Graph<N, E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let edges = self.edges.iter().map(|edge| {
+            (
+                &self.nodes[edge.from.0],
+                &self.nodes[edge.to.0],
+                &edge.weight,
+            )
+        });
+        f.debug_struct("Graph")
+            .field("nodes", &self.nodes)
+            .field("edges", &edges.collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod testing {
+    use super::*;
+
+    #[test]
+    fn nodes_and_edges() {
+        let mut graph = Graph::new();
+        let a = graph.add_node("a");
+        let b = graph.add_node("b");
+        let c = graph.add_node("c");
+        let ab = graph.add_edge(a, b, 1);
+        graph.add_edge(a, c, 2);
+        graph.add_edge(b, c, 3);
+        graph.add_edge(c, c, 4);
+
+        assert_eq!((graph.node_count(), graph.edge_count()), (3, 4));
+        assert_eq!(graph.neighbors(a).collect::<Vec<_>>(), [b, c]);
+        assert_eq!(graph.neighbors(c).collect::<Vec<_>>(), [c]);
+        assert_eq!(graph.edge(ab), (a, b, &1));
+        assert_eq!(
+            graph.find_edge(b, c).map(|edge| graph.edge(edge).2),
+            Some(&3)
+        );
+        assert_eq!(graph.find_edge(c, a), None);
+
+        *graph.node_mut(b) = "B";
+        *graph.edge_mut(ab) += 10;
+        assert_eq!(graph.node(b), &"B");
+        assert_eq!(
+            graph
+                .edges_from(a)
+                .map(|(_, to, w)| (to, *w))
+                .collect::<Vec<_>>(),
+            [(b, 11), (c, 2)]
+        );
+        assert_eq!(
+            format!("{graph:?}"),
+            r#"Graph { nodes: ["a", "B", "c"], edges: [("a", "B", 11), ("a", "c", 2), ("B", "c", 3), ("c", "c", 4)] }"#
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "no such node")]
+    fn edges_need_both_nodes() {
+        let mut graph = Graph::new();
+        let a = graph.add_node(());
+        let mut other: Graph<(), ()> = Graph::new();
+        other.add_node(());
+        let b = other.add_node(());
+        graph.add_edge(a, b, ());
+    }
+}
@@ -0,0 +1,309 @@
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::ops::Add;
+
+use super::{Graph, NodeId};
+
+// A route through the graph and what it costs, nodes runs from start to goal inclusive
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Path<W> {
+    pub cost: W,
+    pub nodes: Vec<NodeId>,
+}
+
+// Every shortest path out of one start node, from dijkstra
+// prev is the node each one was reached from on its shortest path, so a path is read backwards
+// from its end to the start and then flipped
+#[derive(Debug, Clone)]
+pub struct ShortestPaths<W> {
+    start: NodeId,
+    dist: Vec<Option<W>>,
+    prev: Vec<Option<NodeId>>,
+}
+
+impl<W: Copy> ShortestPaths<W> {
+    pub fn start(&self) -> NodeId {
+        self.start
+    }
+
+    // None if node can't be reached from the start
+    pub fn distance(&self, node: NodeId) -> Option<W> {
+        self.dist[node.0]
+    }
+
+    pub fn path_to(&self, node: NodeId) -> Option<Path<W>> {
+        let cost = self.dist[node.0]?;
+        let mut nodes = vec![node];
+        while let Some(prev) = self.prev[nodes[nodes.len() - 1].0] {
+            nodes.push(prev);
+        }
+        nodes.reverse();
+        Some(Path { cost, nodes })
+    }
+}
+
+// Edge weights are the edges' own values, anything that can be added, compared and has a zero
+// (Default). That rules out f64, which isn't Ord (NaN), use integers or wrap floats in a type
+// that orders them. Weights can't be negative, the searches panic if they find one.
+impl<N, E> Graph<N, E>
+where
+    E: Copy + Ord + Add<Output = E> + Default,
+{
+    // Shortest paths from start to everything reachable (Dijkstra's algorithm)
+    // Nodes come off a min-heap in order of distance from the start. With no negative weights,
+    // nothing found later can lead back to an already popped node more cheaply, so its distance
+    // is final the moment it's popped. A node gets pushed again every time a shorter way to it
+    // turns up, instead of updating its old entry in place (BinaryHeap can't), and the stale
+    // entries are skipped as they're popped.
+    pub fn dijkstra(&self, start: NodeId) -> ShortestPaths<E> {
+        self.search(start, None, |_| E::default())
+    }
+
+    // Dijkstra, stopping as soon as goal is reached
+    pub fn shortest_path(&self, start: NodeId, goal: NodeId) -> Option<Path<E>> {
+        self.search(start, Some(goal), |_| E::default())
+            .path_to(goal)
+    }
+
+    // Dijkstra aimed at goal: the heap is ordered by distance so far plus heuristic(node), an
+    // estimate of what's left
+    // The heuristic must never overestimate (straight line distance on a map, Manhattan distance
+    // on a grid) or the path found may not be the shortest. The better the estimate the fewer
+    // nodes get looked at. One that always says zero is just Dijkstra.
+    pub fn astar(
+        &self,
+        start: NodeId,
+        goal: NodeId,
+        heuristic: impl Fn(NodeId) -> E,
+    ) -> Option<Path<E>> {
+        self.search(start, Some(goal), heuristic).path_to(goal)
+    }
+
+    fn search(
+        &self,
+        start: NodeId,
+        goal: Option<NodeId>,
+        heuristic: impl Fn(NodeId) -> E,
+    ) -> ShortestPaths<E> {
+        assert!(start.0 < self.node_count(), "no such node");
+        let mut dist = vec![None; self.node_count()];
+        let mut prev = vec![None; self.node_count()];
+        // Reverse turns the max-heap into a min-heap, (estimate, distance, node)
+        let mut heap = BinaryHeap::new();
+
+        dist[start.0] = Some(E::default());
+        heap.push(Reverse((heuristic(start), E::default(), start)));
+        while let Some(Reverse((_, d, node))) = heap.pop() {
+            if Some(node) == goal {
+                break;
+            }
+            // a shorter way here was found after this entry was pushed
+            if dist[node.0].is_some_and(|best| d > best) {
+                continue;
+            }
+
+            for (_, to, &weight) in self.edges_from(node) {
+                assert!(weight >= E::default(), "negative edge weight");
+                let next = d + weight;
+                if dist[to.0].is_none_or(|best| next < best) {
+                    dist[to.0] = Some(next);
+                    prev[to.0] = Some(node);
+                    heap.push(Reverse((next + heuristic(to), next, to)));
+                }
+            }
+        }
+
+        ShortestPaths { start, dist, prev }
+    }
+}
+
+#[cfg(test)]
+mod testing {
+    use super::*;
+
+    //        7       9
+    //   a ------ b ------ d
+    //    \      / \       |
+    //   14\  2 /   \ 10   | 6
+    //      \  /     \     |
+    //       c ------ e ---+
+    //          11       (e -> d)
+    fn example() -> (Graph<char, u32>, Vec<NodeId>) {
+        let mut graph = Graph::new();
+        let ids: Vec<_> = "abcde".chars().map(|c| graph.add_node(c)).collect();
+        for (from, to, weight) in [
+            (0, 1, 7),
+            (0, 2, 14),
+            (1, 2, 2),
+            (1, 3, 9),
+            (1, 4, 10),
+            (2, 4, 11),
+            (4, 3, 6),
+        ] {
+            graph.add_edge(ids[from], ids[to], weight);
+        }
+        (graph, ids)
+    }
+
+    fn names(graph: &Graph<char, u32>, path: &Path<u32>) -> String {
+        path.nodes.iter().map(|&id| *graph.node(id)).collect()
+    }
+
+    #[test]
+    fn dijkstra_finds_shortest_paths() {
+        let (graph, ids) = example();
+        let paths = graph.dijkstra(ids[0]);
+        assert_eq!(
+            ids.iter().map(|&id| paths.distance(id)).collect::<Vec<_>>(),
+            [Some(0), Some(7), Some(9), Some(16), Some(17)]
+        );
+        assert_eq!(names(&graph, &paths.path_to(ids[2]).unwrap()), "abc");
+        assert_eq!(names(&graph, &paths.path_to(ids[4]).unwrap()), "abe");
+        assert_eq!(paths.path_to(ids[0]).unwrap().nodes, [ids[0]]);
+
+        let from_e = graph.dijkstra(ids[4]);
+        assert_eq!(from_e.distance(ids[3]), Some(6));
+        assert_eq!(from_e.distance(ids[0]), None);
+        assert_eq!(graph.shortest_path(ids[4], ids[0]), None);
+
+        let path = graph.shortest_path(ids[0], ids[3]).unwrap();
+        assert_eq!((path.cost, names(&graph, &path)), (16, "abd".into()));
+    }
+
+    #[test]
+    #[should_panic(expected = "negative edge weight")]
+    fn rejects_negative_weights() {
+        let mut graph = Graph::new();
+        let a = graph.add_node(());
+        let b = graph.add_node(());
+        graph.add_edge(a, b, -1i32);
+        graph.dijkstra(a);
+    }
+
+    // Bellman-Ford: relax every edge n - 1 times
+    fn brute_force(graph: &Graph<usize, u32>, start: NodeId) -> Vec<Option<u32>> {
+        let mut dist = vec![None; graph.node_count()];
+        dist[start.index()] = Some(0);
+        for _ in 1..graph.node_count() {
+            for edge in graph.edge_ids() {
+                let (from, to, &weight) = graph.edge(edge);
+                if let Some(d) = dist[from.index()] {
+                    if dist[to.index()].is_none_or(|best| d + weight < best) {
+                        dist[to.index()] = Some(d + weight);
+                    }
+                }
+            }
+        }
+        dist
+    }
+
+    #[test]
+    fn agrees_with_bellman_ford() {
+        let mut x: u64 = 3;
+        let mut next = |n: u64| {
+            x = x.wrapping_mul(6364136223846793005).wrapping_add(1);
+            (x >> 33) % n
+        };
+        for _ in 0..100 {
+            let n = 1 + next(40) as usize;
+            let mut graph = Graph::new();
+            let ids: Vec<_> = (0..n).map(|n| graph.add_node(n)).collect();
+            for _ in 0..next(4 * n as u64) {
+                let (from, to) = (next(n as u64) as usize, next(n as u64) as usize);
+                graph.add_edge(ids[from], ids[to], next(20) as u32);
+            }
+
+            let paths = graph.dijkstra(ids[0]);
+            let expected = brute_force(&graph, ids[0]);
+            for &id in &ids {
+                assert_eq!(paths.distance(id), expected[id.index()]);
+                // and the path really costs what it says
+                if let Some(path) = paths.path_to(id) {
+                    let cost: u32 = path
+                        .nodes
+                        .windows(2)
+                        .map(|pair| {
+                            graph
+                                .edges_from(pair[0])
+                                .filter(|&(_, to, _)| to == pair[1])
+                                .map(|(_, _, &weight)| weight)
+                                .min()
+                                .unwrap()
+                        })
+                        .sum();
+                    assert_eq!(cost, path.cost);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn astar_on_a_grid() {
+        // '#' is a wall, moving to a neighbouring square costs 1
+        let grid = [
+            "..........",
+            ".########.",
+            ".#......#.",
+            ".#.####.#.",
+            ".#.#..#.#.",
+            ".#.#.##.#.",
+            ".#.#....#.",
+            ".#.######.",
+            ".#........",
+            ".#########",
+        ];
+        let (rows, cols) = (grid.len(), grid[0].len());
+        let mut graph = Graph::new();
+        let ids: Vec<_> = (0..rows * cols)
+            .map(|i| graph.add_node((i / cols, i % cols)))
+            .collect();
+        let open = |r: usize, c: usize| grid[r].as_bytes()[c] == b'.';
+        for r in 0..rows {
+            for c in 0..cols {
+                let neighbours = [
+                    (r + 1, c),
+                    (r, c + 1),
+                    (r.wrapping_sub(1), c),
+                    (r, c.wrapping_sub(1)),
+                ];
+                for (nr, nc) in neighbours {
+                    if open(r, c) && nr < rows && nc < cols && open(nr, nc) {
+                        graph.add_edge(ids[r * cols + c], ids[nr * cols + nc], 1u32);
+                    }
+                }
+            }
+        }
+
+        let graph = &graph;
+        let start = ids[0];
+        let manhattan = |goal: NodeId| {
+            let (gr, gc) = *graph.node(goal);
+            move |id: NodeId| {
+                let (r, c) = *graph.node(id);
+                (r.abs_diff(gr) + c.abs_diff(gc)) as u32
+            }
+        };
+        for goal in graph.node_ids() {
+            let dijkstra = graph.shortest_path(start, goal);
+            let astar = graph.astar(start, goal, manhattan(goal));
+            assert_eq!(
+                dijkstra.as_ref().map(|path| path.cost),
+                astar.as_ref().map(|path| path.cost)
+            );
+            if let Some(path) = astar {
+                assert_eq!(path.nodes.len() as u32, path.cost + 1);
+                assert_eq!(
+                    (path.nodes[0], path.nodes[path.nodes.len() - 1]),
+                    (start, goal)
+                );
+            }
+        }
+
+        // into the middle of the spiral, the long way round
+        let centre = ids[4 * cols + 4];
+        assert_eq!(
+            graph.astar(start, centre, manhattan(centre)).unwrap().cost,
+            44
+        );
+    }
+}
@@ -0,0 +1,205 @@
+use std::collections::VecDeque;
+use std::{error, fmt};
+
+use super::{Graph, NodeId};
+
+// The nodes of a cycle in edge order, each has an edge to the next and the last to the first
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cycle(pub Vec<NodeId>);
+
+impl fmt::Display for Cycle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "graph has a cycle through {} node(s)", self.0.len())
+    }
+}
+
+impl error::Error for Cycle {}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Mark {
+    Unvisited,
+    // on the current path, finding an edge back to one of these closes a cycle
+    OnPath,
+    Done,
+}
+
+impl<N, E> Graph<N, E> {
+    // An order where every edge goes from an earlier node to a later one (Kahn's algorithm)
+    // Nodes nothing points to can go first. Taking one out of the graph lowers the in-degree of
+    // everything it points to, and whatever drops to zero is ready to go next:
+    //
+    //   in-degree   a:0 b:1 c:2        a -> b -> c
+    //   take a      b:0 c:1            a ------> c
+    //   take b      c:0
+    //   take c                         order: a b c
+    //
+    // Nodes on a cycle never get to zero, so if the order comes up short there's a cycle, and
+    // find_cycle digs one out for the error. Ready nodes are taken in id order, so the result is
+    // the same every time for the same graph.
+    pub fn topological_sort(&self) -> Result<Vec<NodeId>, Cycle> {
+        let mut in_degree = vec![0usize; self.node_count()];
+        for edge in &self.edges {
+            in_degree[edge.to.0] += 1;
+        }
+
+        let mut ready: VecDeque<NodeId> = self
+            .node_ids()
+            .filter(|node| in_degree[node.0] == 0)
+            .collect();
+        let mut order = Vec::with_capacity(self.node_count());
+        while let Some(node) = ready.pop_front() {
+            order.push(node);
+            for next in self.neighbors(node) {
+                in_degree[next.0] -= 1;
+                if in_degree[next.0] == 0 {
+                    ready.push_back(next);
+                }
+            }
+        }
+
+        if order.len() < self.node_count() {
+            return Err(self.find_cycle().expect("nodes left over means a cycle"));
+        }
+        Ok(order)
+    }
+
+    pub fn is_cyclic(&self) -> bool {
+        self.find_cycle().is_some()
+    }
+
+    // Depth first from every node not visited yet, keeping the current path
+    // An edge back to a node on the path closes a cycle: the path from that node on. The search
+    // keeps its own stack of (node, next edge to try) so a long chain can't overflow the real one.
+    pub fn find_cycle(&self) -> Option<Cycle> {
+        let mut marks = vec![Mark::Unvisited; self.node_count()];
+        let mut path: Vec<(NodeId, usize)> = Vec::new();
+
+        for start in self.node_ids() {
+            if marks[start.0] != Mark::Unvisited {
+                continue;
+            }
+            marks[start.0] = Mark::OnPath;
+            path.push((start, 0));
+
+            while let Some((node, next_edge)) = path.last_mut() {
+                let Some(&edge) = self.outgoing[node.0].get(*next_edge) else {
+                    marks[node.0] = Mark::Done;
+                    path.pop();
+                    continue;
+                };
+                *next_edge += 1;
+
+                let to = self.edges[edge.0].to;
+                match marks[to.0] {
+                    Mark::Unvisited => {
+                        marks[to.0] = Mark::OnPath;
+                        path.push((to, 0));
+                    }
+                    Mark::OnPath => {
+                        let from = path.iter().position(|&(node, _)| node == to).unwrap();
+                        return Some(Cycle(path[from..].iter().map(|&(node, _)| node).collect()));
+                    }
+                    Mark::Done => {}
+                }
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod testing {
+    use super::*;
+
+    fn build(n: usize, edges: &[(usize, usize)]) -> (Graph<usize, ()>, Vec<NodeId>) {
+        let mut graph = Graph::new();
+        let ids: Vec<_> = (0..n).map(|n| graph.add_node(n)).collect();
+        for &(from, to) in edges {
+            graph.add_edge(ids[from], ids[to], ());
+        }
+        (graph, ids)
+    }
+
+    // every edge has to point forwards in the order
+    fn is_topological(graph: &Graph<usize, ()>, order: &[NodeId]) -> bool {
+        let mut position = vec![usize::MAX; graph.node_count()];
+        for (i, node) in order.iter().enumerate() {
+            position[node.index()] = i;
+        }
+        order.len() == graph.node_count()
+            && graph.edge_ids().all(|edge| {
+                let (from, to, _) = graph.edge(edge);
+                position[from.index()] < position[to.index()]
+            })
+    }
+
+    // consecutive nodes (and last to first) are joined by edges
+    fn is_cycle(graph: &Graph<usize, ()>, cycle: &Cycle) -> bool {
+        let nodes = &cycle.0;
+        !nodes.is_empty()
+            && (0..nodes.len()).all(|i| {
+                graph
+                    .find_edge(nodes[i], nodes[(i + 1) % nodes.len()])
+                    .is_some()
+            })
+    }
+
+    #[test]
+    fn sorts_a_dag() {
+        let (graph, ids) = build(6, &[(0, 1), (0, 2), (1, 3), (2, 3), (4, 5), (5, 3)]);
+        let order = graph.topological_sort().unwrap();
+        assert!(is_topological(&graph, &order));
+        assert_eq!(order, [0, 4, 1, 2, 5, 3].map(|n| ids[n]));
+        assert!(!graph.is_cyclic());
+        assert_eq!(Graph::<(), ()>::new().topological_sort(), Ok(vec![]));
+    }
+
+    #[test]
+    fn finds_cycles() {
+        let (graph, ids) = build(5, &[(0, 1), (1, 2), (2, 3), (3, 1), (3, 4)]);
+        let cycle = graph.topological_sort().unwrap_err();
+        assert!(is_cycle(&graph, &cycle));
+        assert_eq!(cycle, Cycle(vec![ids[1], ids[2], ids[3]]));
+        assert_eq!(cycle.to_string(), "graph has a cycle through 3 node(s)");
+
+        let (graph, ids) = build(2, &[(0, 1), (1, 1)]);
+        assert_eq!(graph.find_cycle(), Some(Cycle(vec![ids[1]])));
+    }
+
+    #[test]
+    fn random_graphs() {
+        let mut x: u64 = 7;
+        let mut next = |n: usize| {
+            x = x.wrapping_mul(6364136223846793005).wrapping_add(1);
+            (x >> 33) as usize % n
+        };
+        for round in 0..200 {
+            let n = 2 + next(30);
+            let edges: Vec<_> = (0..next(3 * n))
+                .map(|_| (next(n), next(n)))
+                // even rounds only keep edges from lower to higher ids, so they stay acyclic
+                .filter(|(a, b)| round % 2 == 1 || a < b)
+                .collect();
+            let (graph, _) = build(n, &edges);
+
+            match graph.topological_sort() {
+                Ok(order) => {
+                    assert!(is_topological(&graph, &order));
+                    assert!(graph.find_cycle().is_none());
+                }
+                Err(cycle) => assert!(is_cycle(&graph, &cycle)),
+            }
+            if round % 2 == 0 {
+                assert!(!graph.is_cyclic());
+            }
+        }
+    }
+
+    #[test]
+    fn long_chains_dont_overflow_the_stack() {
+        let n = 200_000;
+        let edges: Vec<_> = (0..n - 1).map(|i| (i, i + 1)).chain([(n - 1, 0)]).collect();
+        let (graph, _) = build(n, &edges);
+        assert_eq!(graph.find_cycle().unwrap().0.len(), n);
+    }
+}
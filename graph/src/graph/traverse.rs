@@ -0,0 +1,163 @@
+use std::collections::VecDeque;
+use std::iter::FusedIterator;
+
+use super::{Graph, NodeId};
+
+// Breadth first: everything one edge from the start, then everything two edges away, ...
+// queue holds nodes that have been found but not visited. A node is marked seen as it's queued,
+// not as it's visited, so a node with several edges into it is only queued once
+pub struct Bfs<'a, N, E> {
+    graph: &'a Graph<N, E>,
+    queue: VecDeque<NodeId>,
+    seen: Vec<bool>,
+    unseen: usize,
+}
+
+// Depth first, in preorder: follows the first edge out of a node as far as it goes before
+// backing up to try the second one
+// stack holds nodes still to try, the top is the next one. Nodes can be pushed more than once
+// (once per edge into them) and are only marked seen when they're popped, which is what makes
+// this a real depth first order rather than a breadth first search with a stack
+pub struct Dfs<'a, N, E> {
+    graph: &'a Graph<N, E>,
+    stack: Vec<NodeId>,
+    seen: Vec<bool>,
+    unseen: usize,
+}
+
+impl<N, E> Graph<N, E> {
+    // Nodes reachable from start (start first), nearest first
+    pub fn bfs(&self, start: NodeId) -> Bfs<'_, N, E> {
+        assert!(start.0 < self.node_count(), "no such node");
+        let mut seen = vec![false; self.node_count()];
+        seen[start.0] = true;
+        Bfs {
+            graph: self,
+            queue: VecDeque::from([start]),
+            seen,
+            unseen: self.node_count() - 1,
+        }
+    }
+
+    // Nodes reachable from start (start first), in depth first preorder
+    pub fn dfs(&self, start: NodeId) -> Dfs<'_, N, E> {
+        assert!(start.0 < self.node_count(), "no such node");
+        Dfs {
+            graph: self,
+            stack: vec![start],
+            seen: vec![false; self.node_count()],
+            unseen: self.node_count(),
+        }
+    }
+}
+
+impl<N, E> Iterator for Bfs<'_, N, E> {
+    type Item = NodeId;
+
+    fn next(&mut self) -> Option<NodeId> {
+        let node = self.queue.pop_front()?;
+        for next in self.graph.neighbors(node) {
+            if !self.seen[next.0] {
+                self.seen[next.0] = true;
+                self.unseen -= 1;
+                self.queue.push_back(next);
+            }
+        }
+        Some(node)
+    }
+
+    // Everything queued will be visited, and at most everything not found yet after that
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.queue.len(), Some(self.queue.len() + self.unseen))
+    }
+}
+
+// Once the queue is empty nothing can be added to it
+impl<N, E> FusedIterator for Bfs<'_, N, E> {}
+
+impl<N, E> Iterator for Dfs<'_, N, E> {
+    type Item = NodeId;
+
+    fn next(&mut self) -> Option<NodeId> {
+        while let Some(node) = self.stack.pop() {
+            if self.seen[node.0] {
+                continue;
+            }
+            self.seen[node.0] = true;
+            self.unseen -= 1;
+            // reversed so the first edge's node is on top and gets followed first
+            let neighbors = self.graph.neighbors(node).rev();
+            self.stack
+                .extend(neighbors.filter(|next| !self.seen[next.0]));
+            return Some(node);
+        }
+        None
+    }
+
+    // Anything on the stack might have been seen already, so only the upper bound is known
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some(self.unseen))
+    }
+}
+
+// Once the stack is empty nothing can be added to it
+impl<N, E> FusedIterator for Dfs<'_, N, E> {}
+
+#[cfg(test)]
+mod testing {
+    use super::*;
+
+    //   0 -> 1 -> 3 -> 5
+    //   |         ^
+    //   v         |
+    //   2 ------> 4      6 (unreachable)
+    fn example() -> (Graph<usize, ()>, Vec<NodeId>) {
+        let mut graph = Graph::new();
+        let ids: Vec<_> = (0..7).map(|n| graph.add_node(n)).collect();
+        for (from, to) in [(0, 1), (0, 2), (1, 3), (2, 4), (4, 3), (3, 5)] {
+            graph.add_edge(ids[from], ids[to], ());
+        }
+        (graph, ids)
+    }
+
+    fn values(graph: &Graph<usize, ()>, ids: impl Iterator<Item = NodeId>) -> Vec<usize> {
+        ids.map(|id| *graph.node(id)).collect()
+    }
+
+    #[test]
+    fn bfs_goes_level_by_level() {
+        let (graph, ids) = example();
+        assert_eq!(values(&graph, graph.bfs(ids[0])), [0, 1, 2, 3, 4, 5]);
+        assert_eq!(values(&graph, graph.bfs(ids[2])), [2, 4, 3, 5]);
+        assert_eq!(values(&graph, graph.bfs(ids[6])), [6]);
+
+        let mut bfs = graph.bfs(ids[0]);
+        assert_eq!(bfs.size_hint(), (1, Some(7)));
+        bfs.by_ref().for_each(drop);
+        assert_eq!(bfs.size_hint(), (0, Some(1)));
+        assert_eq!(bfs.next(), None);
+    }
+
+    #[test]
+    fn dfs_follows_the_first_edge_first() {
+        let (graph, ids) = example();
+        assert_eq!(values(&graph, graph.dfs(ids[0])), [0, 1, 3, 5, 2, 4]);
+        assert_eq!(values(&graph, graph.dfs(ids[4])), [4, 3, 5]);
+
+        let mut dfs = graph.dfs(ids[6]);
+        assert_eq!(dfs.next(), Some(ids[6]));
+        assert_eq!(dfs.next(), None);
+        assert_eq!(dfs.next(), None);
+    }
+
+    #[test]
+    fn cycles_are_only_visited_once() {
+        let mut graph = Graph::new();
+        let ids: Vec<_> = (0..4).map(|n| graph.add_node(n)).collect();
+        for (from, to) in [(0, 1), (1, 2), (2, 0), (2, 3), (3, 3), (1, 2)] {
+            graph.add_edge(ids[from], ids[to], ());
+        }
+        assert_eq!(values(&graph, graph.bfs(ids[1])), [1, 2, 0, 3]);
+        assert_eq!(values(&graph, graph.dfs(ids[1])), [1, 2, 0, 3]);
+    }
+}
@@ -0,0 +1,5 @@
+mod disjoint_set;
+mod graph;
+
+pub use disjoint_set::DisjointSet;
+pub use graph::{Bfs, Cycle, Dfs, EdgeId, Graph, NodeId, Path, ShortestPaths};
@@ -0,0 +1,3 @@
+mod open_map;
+
+pub use open_map::{OpenHashMap, OpenHashMapIter};
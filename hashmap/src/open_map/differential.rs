@@ -0,0 +1,50 @@
+// Differential tests against std's HashMap
+// Random insert/get/remove sequences are run through both maps side by side and every answer
+// has to match. Keys come from a small range so sequences keep hitting the same keys, and the
+// tombstones pile up
+use std::collections::HashMap;
+use std::hash::{BuildHasherDefault, DefaultHasher};
+
+use proptest::prelude::*;
+
+use super::OpenHashMap;
+
+#[derive(Debug, Clone)]
+enum Op {
+    Insert(u8, u32),
+    Get(u8),
+    Remove(u8),
+}
+
+fn op() -> impl Strategy<Value = Op> {
+    prop_oneof![
+        3 => (0_u8..64, any::<u32>()).prop_map(|(k, v)| Op::Insert(k, v)),
+        2 => (0_u8..64).prop_map(Op::Get),
+        2 => (0_u8..64).prop_map(Op::Remove),
+    ]
+}
+
+proptest! {
+    #[test]
+    fn matches_std_hash_map(ops in prop::collection::vec(op(), 1..500)) {
+        // a fixed hasher so a failing case replays the same way
+        let mut map: OpenHashMap<u8, u32, BuildHasherDefault<DefaultHasher>> =
+            OpenHashMap::default();
+        let mut oracle: HashMap<u8, u32> = HashMap::new();
+
+        for op in ops {
+            match op {
+                Op::Insert(k, v) => prop_assert_eq!(oracle.insert(k, v), map.insert(k, v)),
+                Op::Get(k) => prop_assert_eq!(oracle.get(&k), map.get(&k)),
+                Op::Remove(k) => prop_assert_eq!(oracle.remove(&k), map.remove(&k)),
+            }
+            prop_assert_eq!(oracle.len(), map.len());
+        }
+
+        let mut entries: Vec<(u8, u32)> = map.iter().map(|(k, v)| (*k, *v)).collect();
+        entries.sort();
+        let mut expected: Vec<(u8, u32)> = oracle.into_iter().collect();
+        expected.sort();
+        prop_assert_eq!(expected, entries);
+    }
+}
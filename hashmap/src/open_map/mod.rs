@@ -0,0 +1,410 @@
+#[cfg(test)]
+mod differential;
+
+use std::borrow::Borrow;
+use std::collections::hash_map::RandomState;
+use std::fmt;
+use std::hash::{BuildHasher, Hash};
+use std::iter::FusedIterator;
+use std::mem;
+
+// A hash map with open addressing: every entry lives directly in one big Vec of slots, there
+// are no per-bucket lists. A key's hash picks the slot to start at, and if that's taken by
+// another key we keep probing other slots until we find it (or an empty slot, which means it
+// isn't there).
+//
+// Probing is quadratic, in triangular steps: the i-th probe is i*(i+1)/2 slots past the start.
+// Keys that hash close to each other spread out quickly instead of piling into one long run of
+// full slots (which is what probing one slot at a time does). With a power of two number of
+// slots, triangular steps visit every slot before repeating, so a probe always finds an empty
+// slot as long as there is one.
+//
+// Removing a key can't just empty its slot: another key may have probed past it on the way to
+// its own slot, and an empty slot would cut that key's probe sequence short. So removed slots
+// become tombstones, which lookups step over and inserts can reuse. Tombstones still lengthen
+// probes, so they count towards the load factor, and growing (or rehashing at the same size)
+// clears them all out.
+//
+// The hasher is a BuildHasher type parameter, like std's HashMap: RandomState by default, but
+// anything that turns keys into u64s can be plugged in
+pub struct OpenHashMap<K, V, S = RandomState> {
+    slots: Vec<Slot<K, V>>,
+    len: usize,
+    tombstones: usize,
+    hasher: S,
+}
+
+enum Slot<K, V> {
+    Empty,
+    Tombstone,
+    // the full hash is kept so growing doesn't have to rehash every key, and most mismatches
+    // are caught by comparing hashes before comparing keys
+    Full { hash: u64, key: K, value: V },
+}
+
+pub struct OpenHashMapIter<'a, K, V> {
+    slots: std::slice::Iter<'a, Slot<K, V>>,
+    remaining: usize,
+}
+
+// Grow once full slots and tombstones take up more than 3/4 of the table, quadratic probing
+// slows down sharply past that
+const MAX_LOAD_NUMERATOR: usize = 3;
+const MAX_LOAD_DENOMINATOR: usize = 4;
+const MIN_CAPACITY: usize = 8;
+
+impl<K: Hash + Eq, V> OpenHashMap<K, V, RandomState> {
+    pub fn new() -> Self {
+        Self::with_hasher(RandomState::new())
+    }
+}
+
+impl<K: Hash + Eq, V, S: BuildHasher> OpenHashMap<K, V, S> {
+    // No slots are allocated until the first insert
+    pub fn with_hasher(hasher: S) -> Self {
+        OpenHashMap {
+            slots: Vec::new(),
+            len: 0,
+            tombstones: 0,
+            hasher,
+        }
+    }
+
+    pub fn with_capacity_and_hasher(capacity: usize, hasher: S) -> Self {
+        let mut map = Self::with_hasher(hasher);
+        if capacity > 0 {
+            map.resize(slots_for(capacity));
+        }
+        map
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    // How many entries fit before the next resize
+    pub fn capacity(&self) -> usize {
+        self.slots.len() * MAX_LOAD_NUMERATOR / MAX_LOAD_DENOMINATOR
+    }
+
+    // Returns the old value if key was already in the map (the key itself isn't replaced)
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        let hash = self.hasher.hash_one(&key);
+        if let Ok(index) = self.find(hash, &key) {
+            let Slot::Full { value: old, .. } = &mut self.slots[index] else {
+                unreachable!("find only returns Ok for full slots");
+            };
+            return Some(mem::replace(old, value));
+        }
+
+        // make room first, then probe again: resizing moves everything
+        self.reserve_one();
+        let index = self
+            .find(hash, &key)
+            .expect_err("key was not in the map a moment ago");
+        if matches!(self.slots[index], Slot::Tombstone) {
+            self.tombstones -= 1;
+        }
+        self.slots[index] = Slot::Full { hash, key, value };
+        self.len += 1;
+        None
+    }
+
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let index = self.find(self.hasher.hash_one(key), key).ok()?;
+        match &self.slots[index] {
+            Slot::Full { value, .. } => Some(value),
+            _ => unreachable!("find only returns Ok for full slots"),
+        }
+    }
+
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let index = self.find(self.hasher.hash_one(key), key).ok()?;
+        match &mut self.slots[index] {
+            Slot::Full { value, .. } => Some(value),
+            _ => unreachable!("find only returns Ok for full slots"),
+        }
+    }
+
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.get(key).is_some()
+    }
+
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let index = self.find(self.hasher.hash_one(key), key).ok()?;
+        match mem::replace(&mut self.slots[index], Slot::Tombstone) {
+            Slot::Full { value, .. } => {
+                self.len -= 1;
+                self.tombstones += 1;
+                Some(value)
+            }
+            _ => unreachable!("find only returns Ok for full slots"),
+        }
+    }
+
+    // Removes everything but keeps the allocated slots
+    pub fn clear(&mut self) {
+        self.slots.fill_with(|| Slot::Empty);
+        self.len = 0;
+        self.tombstones = 0;
+    }
+
+    pub fn iter(&self) -> OpenHashMapIter<'_, K, V> {
+        OpenHashMapIter {
+            slots: self.slots.iter(),
+            remaining: self.len,
+        }
+    }
+
+    // Probes for key: Ok(slot) if it's there, otherwise Err(slot) with the slot an insert should
+    // use, which is the first tombstone we passed or else the empty slot that ended the probe
+    // There's always at least one empty slot (see reserve_one), so the loop always ends. An
+    // empty table has no slots at all, so there's nothing to find
+    fn find<Q>(&self, hash: u64, key: &Q) -> Result<usize, usize>
+    where
+        K: Borrow<Q>,
+        Q: Eq + ?Sized,
+    {
+        if self.slots.is_empty() {
+            return Err(0);
+        }
+        let mask = self.slots.len() - 1;
+        let mut index = hash as usize & mask;
+        let mut first_tombstone = None;
+        for step in 1.. {
+            match &self.slots[index] {
+                Slot::Empty => return Err(first_tombstone.unwrap_or(index)),
+                Slot::Tombstone => {
+                    first_tombstone.get_or_insert(index);
+                }
+                Slot::Full {
+                    hash: h, key: k, ..
+                } => {
+                    if *h == hash && k.borrow() == key {
+                        return Ok(index);
+                    }
+                }
+            }
+            // moving on by 1, 2, 3, ... slots is the same as probing at i*(i+1)/2
+            index = (index + step) & mask;
+        }
+        unreachable!("a table with an empty slot always ends the probe")
+    }
+
+    // Makes sure one more entry fits without going over the maximum load
+    // When tombstones are most of the load a rehash at the same size is enough to make room
+    fn reserve_one(&mut self) {
+        let used = self.len + self.tombstones + 1;
+        if used * MAX_LOAD_DENOMINATOR <= self.slots.len() * MAX_LOAD_NUMERATOR {
+            return;
+        }
+        self.resize(slots_for(self.len + 1).max(self.slots.len()));
+    }
+
+    // Moves every entry into a new table with `slots` slots, which drops all the tombstones
+    // The stored hashes mean keys don't need to be hashed again
+    fn resize(&mut self, slots: usize) {
+        let old = mem::replace(&mut self.slots, Vec::with_capacity(slots));
+        self.slots.resize_with(slots, || Slot::Empty);
+        self.tombstones = 0;
+        let mask = slots - 1;
+        for slot in old {
+            let Slot::Full { hash, key, value } = slot else {
+                continue;
+            };
+            // every key is unique, so only an empty slot needs finding
+            let mut index = hash as usize & mask;
+            let mut step = 1;
+            while !matches!(self.slots[index], Slot::Empty) {
+                index = (index + step) & mask;
+                step += 1;
+            }
+            self.slots[index] = Slot::Full { hash, key, value };
+        }
+    }
+}
+
+// The smallest power of two number of slots that holds entries under the maximum load
+fn slots_for(entries: usize) -> usize {
+    (entries * MAX_LOAD_DENOMINATOR)
+        .div_ceil(MAX_LOAD_NUMERATOR)
+        .next_power_of_two()
+        .max(MIN_CAPACITY)
+}
+
+impl<K: Hash + Eq, V, S: BuildHasher + Default> Default for OpenHashMap<K, V, S> {
+    fn default() -> Self {
+        Self::with_hasher(S::default())
+    }
+}
+
+impl<K: Hash + Eq, V, S: BuildHasher> Extend<(K, V)> for OpenHashMap<K, V, S> {
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        for (key, value) in iter {
+            self.insert(key, value);
+        }
+    }
+}
+
+impl<K: Hash + Eq, V, S: BuildHasher + Default> FromIterator<(K, V)> for OpenHashMap<K, V, S> {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut map = Self::default();
+        map.extend(iter);
+        map
+    }
+}
+
+impl<K: Hash + Eq + fmt::Debug, V: fmt::Debug, S: BuildHasher> fmt::Debug for OpenHashMap<K, V, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<'a, K: Hash + Eq, V, S: BuildHasher> IntoIterator for &'a OpenHashMap<K, V, S> {
+    type Item = (&'a K, &'a V);
+    type IntoIter = OpenHashMapIter<'a, K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+// Entries come out in slot order, which depends on the hashes: effectively random
+impl<'a, K, V> Iterator for OpenHashMapIter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        for slot in self.slots.by_ref() {
+            if let Slot::Full { key, value, .. } = slot {
+                self.remaining -= 1;
+                return Some((key, value));
+            }
+        }
+        None
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<K, V> ExactSizeIterator for OpenHashMapIter<'_, K, V> {}
+impl<K, V> FusedIterator for OpenHashMapIter<'_, K, V> {}
+
+#[cfg(test)]
+mod testing {
+    use super::*;
+    use std::hash::{BuildHasherDefault, Hasher};
+
+    // Every key hashes to the same value, so every key collides and probes the same sequence
+    #[derive(Default)]
+    struct ConstHasher;
+
+    impl Hasher for ConstHasher {
+        fn finish(&self) -> u64 {
+            42
+        }
+
+        fn write(&mut self, _bytes: &[u8]) {}
+    }
+
+    #[test]
+    fn can_insert_get_and_remove() {
+        let mut map = OpenHashMap::new();
+        assert_eq!(None, map.insert("a", 1));
+        assert_eq!(None, map.insert("b", 2));
+        assert_eq!(Some(1), map.insert("a", 10));
+        assert_eq!(2, map.len());
+
+        assert_eq!(Some(&10), map.get("a"));
+        *map.get_mut("b").unwrap() += 1;
+        assert_eq!(Some(&3), map.get("b"));
+        assert_eq!(None, map.get("c"));
+
+        assert_eq!(Some(10), map.remove("a"));
+        assert_eq!(None, map.remove("a"));
+        assert!(!map.contains_key("a"));
+        assert_eq!(r#"{"b": 3}"#, format!("{map:?}"));
+    }
+
+    #[test]
+    fn grows_past_the_load_factor() {
+        let mut map = OpenHashMap::new();
+        assert_eq!(0, map.capacity());
+        for i in 0..1_000 {
+            map.insert(i, i * 2);
+            assert!(map.len() <= map.capacity());
+        }
+        // 1024 slots only hold 768, so it took 2048
+        assert_eq!(1_536, map.capacity());
+        assert!((0..1_000).all(|i| map.get(&i) == Some(&(i * 2))));
+        assert_eq!(1_000, map.iter().len());
+    }
+
+    #[test]
+    fn tombstones_are_reused_and_cleared() {
+        let mut map = OpenHashMap::with_capacity_and_hasher(6, RandomState::new());
+        let slots = map.slots.len();
+        // churning through keys leaves tombstones behind, but the table never has to grow since
+        // there are never more than a few live entries
+        for i in 0..1_000 {
+            map.insert(i, ());
+            if i >= 3 {
+                map.remove(&(i - 3));
+            }
+            assert!(map.slots.iter().any(|slot| matches!(slot, Slot::Empty)));
+        }
+        assert_eq!(3, map.len());
+        assert_eq!(slots, map.slots.len());
+        assert!(map.tombstones < slots);
+    }
+
+    #[test]
+    fn works_with_a_terrible_hasher() {
+        let mut map: OpenHashMap<u32, u32, BuildHasherDefault<ConstHasher>> =
+            OpenHashMap::default();
+        for i in 0..100 {
+            map.insert(i, i);
+        }
+        for i in (0..100).step_by(2) {
+            map.remove(&i);
+        }
+        // lookups have to probe past every tombstone to find the odd keys
+        assert!((0..100).all(|i| map.get(&i) == (i % 2 == 1).then_some(&i)));
+        map.clear();
+        assert!(map.is_empty() && map.get(&1).is_none());
+    }
+
+    #[test]
+    fn triangular_probes_visit_every_slot() {
+        for slots in [8, 64, 1024] {
+            let mut seen = vec![false; slots];
+            let mut index = 5;
+            for step in 1..=slots {
+                seen[index] = true;
+                index = (index + step) & (slots - 1);
+            }
+            assert!(seen.iter().all(|&s| s));
+        }
+    }
+}
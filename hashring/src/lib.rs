@@ -0,0 +1,3 @@
+mod ring;
+
+pub use ring::{HashRing, Move, Rebalance, DEFAULT_VNODES};
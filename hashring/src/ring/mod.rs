@@ -0,0 +1,165 @@
+#[cfg(test)]
+mod properties;
+
+mod rebalance;
+
+pub use rebalance::{Move, Rebalance};
+
+// Consistent hashing: nodes and keys are hashed onto the same circle of u64 positions, and a key
+// belongs to the first node at or after its position, going clockwise
+//
+//           0
+//       n3     n1          k1 -> n1
+//    k3          k1        k2 -> n2
+//       n2     k2          k3 -> n3 (wrapping past the top to the first node after it)
+//
+// Adding a node only takes keys from the nodes just clockwise of where it lands, and removing one
+// only hands its keys to its neighbours. Compare hash(key) % n, where going from n to n + 1 nodes
+// moves almost every key.
+//
+// One position per node would give lopsided arcs, so every node gets `vnodes` positions per unit
+// of weight (virtual nodes) and owns the arc before each. Lots of small arcs average out: with
+// ~160 per node the busiest node gets ~10-20% more than its share, not double. A node with
+// weight 2 gets twice the points, and about twice the keys.
+//
+// Positions come from a fixed hash (FNV-1a with a murmur finalizer) rather than std's
+// DefaultHasher, which can change between Rust releases: every process that builds a ring from
+// the same members has to agree on where keys go.
+#[derive(Debug, Clone)]
+pub struct HashRing<N> {
+    vnodes: u32,
+    // (node, weight), weights are never 0
+    members: Vec<(N, u32)>,
+    // (position, index into members), sorted
+    points: Vec<(u64, usize)>,
+}
+
+pub const DEFAULT_VNODES: u32 = 160;
+
+impl<N: AsRef<[u8]> + Clone + Eq> HashRing<N> {
+    pub fn new() -> Self {
+        Self::with_vnodes(DEFAULT_VNODES)
+    }
+
+    // Panics if vnodes is 0, there'd be nowhere on the ring for any node
+    pub fn with_vnodes(vnodes: u32) -> Self {
+        assert!(vnodes > 0, "a ring needs at least one point per node");
+        HashRing {
+            vnodes,
+            members: Vec::new(),
+            points: Vec::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.members.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.members.is_empty()
+    }
+
+    pub fn members(&self) -> impl Iterator<Item = (&N, u32)> {
+        self.members.iter().map(|(node, weight)| (node, *weight))
+    }
+
+    pub fn weight(&self, node: &N) -> Option<u32> {
+        self.members
+            .iter()
+            .find(|(member, _)| member == node)
+            .map(|&(_, weight)| weight)
+    }
+
+    // Adds a node, or changes its weight if it's already here (0 removes it)
+    // Returns the parts of the ring that changed hands.
+    pub fn add(&mut self, node: N, weight: u32) -> Rebalance<N> {
+        let before = self.clone();
+        match self.members.iter_mut().find(|(member, _)| *member == node) {
+            Some(member) => member.1 = weight,
+            None => self.members.push((node, weight)),
+        }
+        self.members.retain(|&(_, weight)| weight > 0);
+        self.place();
+        Rebalance::between(&before, self)
+    }
+
+    pub fn remove(&mut self, node: &N) -> Rebalance<N> {
+        self.add(node.clone(), 0)
+    }
+
+    // The node a key belongs to, None if the ring is empty
+    pub fn get_node(&self, key: impl AsRef<[u8]>) -> Option<&N> {
+        self.owner(position(key.as_ref()))
+    }
+
+    // Each member's share of the ring, the fraction of all keys it can expect to get
+    pub fn shares(&self) -> Vec<(&N, f64)> {
+        let mut owned = vec![0u128; self.members.len()];
+        for (i, &(end, member)) in self.points.iter().enumerate() {
+            let start = self.points[(i + self.points.len() - 1) % self.points.len()].0;
+            owned[member] += arc_len(start, end);
+        }
+        self.members
+            .iter()
+            .zip(owned)
+            .map(|((node, _), owned)| (node, owned as f64 / RING_SIZE as f64))
+            .collect()
+    }
+
+    // The node owning a position: the first point at or after it, wrapping around
+    fn owner(&self, pos: u64) -> Option<&N> {
+        let idx = self.points.partition_point(|&(point, _)| point < pos);
+        let &(_, member) = self.points.get(idx).or(self.points.first())?;
+        Some(&self.members[member].0)
+    }
+
+    // Rebuilds the points from the members
+    // Two points can land on the same position, the tie goes to the smaller node name so the
+    // order members joined in doesn't matter.
+    fn place(&mut self) {
+        self.points.clear();
+        for (idx, (node, weight)) in self.members.iter().enumerate() {
+            let count = u64::from(self.vnodes) * u64::from(*weight);
+            for i in 0..count {
+                let label = [node.as_ref(), b"#", &i.to_be_bytes()].concat();
+                self.points.push((position(&label), idx));
+            }
+        }
+        let members = &self.members;
+        self.points.sort_unstable_by(|a, b| {
+            a.0.cmp(&b.0)
+                .then_with(|| members[a.1].0.as_ref().cmp(members[b.1].0.as_ref()))
+        });
+    }
+}
+
+impl<N: AsRef<[u8]> + Clone + Eq> Default for HashRing<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// 2^64, the number of positions on the ring
+const RING_SIZE: u128 = 1 << 64;
+
+// Positions in (start, end], going clockwise; start == end is the whole ring
+fn arc_len(start: u64, end: u64) -> u128 {
+    match end.wrapping_sub(start) {
+        0 => RING_SIZE,
+        len => u128::from(len),
+    }
+}
+
+fn position(bytes: &[u8]) -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for &byte in bytes {
+        hash = (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3);
+    }
+    // FNV alone leaves similar inputs ("node#1", "node#2") at similar positions, this mixes
+    // every input bit into every output bit
+    hash ^= hash >> 33;
+    hash = hash.wrapping_mul(0xff51_afd7_ed55_8ccd);
+    hash ^= hash >> 33;
+    hash = hash.wrapping_mul(0xc4ce_b9fe_1a85_ec53);
+    hash ^ hash >> 33
+}
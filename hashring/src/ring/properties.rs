@@ -0,0 +1,131 @@
+// Property tests: however the members are named and weighted, the ring has to spread keys in
+// proportion to weight, and joins and leaves have to move only the keys they must (and report
+// exactly those)
+use proptest::prelude::*;
+
+use super::HashRing;
+
+fn ring_of(members: &[(String, u32)]) -> HashRing<String> {
+    let mut ring = HashRing::new();
+    for (node, weight) in members {
+        ring.add(node.clone(), *weight);
+    }
+    ring
+}
+
+fn members() -> impl Strategy<Value = Vec<(String, u32)>> {
+    prop::collection::hash_set("[a-z]{1,8}", 2..12).prop_flat_map(|names| {
+        let names: Vec<String> = names.into_iter().collect();
+        let weights = prop::collection::vec(1_u32..4, names.len());
+        (Just(names), weights).prop_map(|(names, weights)| names.into_iter().zip(weights).collect())
+    })
+}
+
+fn keys(seed: u64) -> impl Iterator<Item = String> {
+    (0..2000).map(move |i| format!("key-{seed}-{i}"))
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(64))]
+
+    #[test]
+    fn shares_follow_weights(members in members()) {
+        let ring = ring_of(&members);
+        let total: u32 = members.iter().map(|(_, weight)| weight).sum();
+        let shares = ring.shares();
+        prop_assert!((shares.iter().map(|(_, share)| share).sum::<f64>() - 1.0).abs() < 1e-9);
+
+        // 160 points per unit of weight put a share within ~8% of its target (one standard
+        // deviation), this allows five
+        for (node, share) in shares {
+            let weight = ring.weight(node).unwrap();
+            let expected = f64::from(weight) / f64::from(total);
+            prop_assert!(
+                (share / expected - 1.0).abs() < 0.4,
+                "{node} with weight {weight} has {share}, expected {expected}"
+            );
+        }
+    }
+
+    #[test]
+    fn joins_only_move_keys_to_the_new_node(
+        members in members(),
+        weight in 1_u32..4,
+        seed in any::<u64>(),
+    ) {
+        let mut ring = ring_of(&members);
+        let before: Vec<(String, String)> =
+            keys(seed).map(|key| (ring.get_node(&key).unwrap().clone(), key)).collect();
+
+        let new = "NEW".to_string();
+        let rebalance = ring.add(new.clone(), weight);
+        let share = ring.shares().into_iter().find(|(node, _)| **node == new).unwrap().1;
+        prop_assert!((rebalance.fraction() - share).abs() < 1e-9);
+
+        for (old_owner, key) in before {
+            let owner = ring.get_node(&key).unwrap();
+            prop_assert!(*owner == old_owner || *owner == new);
+            let expected = (*owner != old_owner).then(|| (Some(old_owner), Some(new.clone())));
+            let found = rebalance.get_move(&key).map(|m| (m.from.clone(), m.to.clone()));
+            prop_assert_eq!(found, expected);
+        }
+    }
+
+    #[test]
+    fn leaves_only_move_the_leaving_nodes_keys(members in members(), seed in any::<u64>()) {
+        let mut ring = ring_of(&members);
+        let gone = members[0].0.clone();
+        let share = ring.shares().into_iter().find(|(node, _)| **node == gone).unwrap().1;
+        let before: Vec<(String, String)> =
+            keys(seed).map(|key| (ring.get_node(&key).unwrap().clone(), key)).collect();
+
+        let rebalance = ring.remove(&gone);
+        prop_assert!((rebalance.fraction() - share).abs() < 1e-9);
+        prop_assert_eq!(ring.weight(&gone), None);
+
+        for (old_owner, key) in before {
+            let owner = ring.get_node(&key).unwrap();
+            prop_assert_eq!(old_owner == gone, *owner != old_owner);
+            prop_assert_eq!(old_owner == gone, rebalance.get_move(&key).is_some());
+            if let Some(m) = rebalance.get_move(&key) {
+                prop_assert_eq!(m.from.as_ref(), Some(&gone));
+                prop_assert_eq!(m.to.as_ref(), Some(owner));
+            }
+        }
+    }
+
+    #[test]
+    fn join_order_doesnt_matter(members in members(), seed in any::<u64>()) {
+        let forward = ring_of(&members);
+        let mut reversed = members.clone();
+        reversed.reverse();
+        let backward = ring_of(&reversed);
+        for key in keys(seed) {
+            prop_assert_eq!(forward.get_node(&key), backward.get_node(&key));
+        }
+    }
+}
+
+#[test]
+fn empty_and_single_node_rings() {
+    let mut ring: HashRing<&str> = HashRing::new();
+    assert_eq!(ring.get_node("a"), None);
+    assert!(ring.shares().is_empty());
+
+    // the first node takes the whole ring, from nobody
+    let rebalance = ring.add("only", 2);
+    assert_eq!(rebalance.fraction(), 1.0);
+    assert!(rebalance.moves().iter().all(|m| m.from.is_none()));
+    assert_eq!(ring.get_node("a"), Some(&"only"));
+    assert_eq!(ring.shares(), [(&"only", 1.0)]);
+
+    // reweighting a lone node moves nothing
+    assert!(ring.add("only", 3).is_empty());
+    assert_eq!(ring.weight(&"only"), Some(3));
+
+    let rebalance = ring.remove(&"only");
+    assert_eq!(rebalance.fraction(), 1.0);
+    assert!(rebalance.get_move("a").is_some_and(|m| m.to.is_none()));
+    assert!(ring.is_empty());
+    assert_eq!(ring.members().count(), 0);
+}
@@ -0,0 +1,100 @@
+use super::{arc_len, position, HashRing, RING_SIZE};
+
+// An arc of the ring whose keys belong to a different node than they did
+// The arc is the positions in (start, end], wrapping past u64::MAX when start >= end. from is
+// None when the ring was empty before, to is None when it's empty after.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Move<N> {
+    pub start: u64,
+    pub end: u64,
+    pub from: Option<N>,
+    pub to: Option<N>,
+}
+
+impl<N> Move<N> {
+    fn contains(&self, pos: u64) -> bool {
+        if self.start < self.end {
+            self.start < pos && pos <= self.end
+        } else {
+            pos > self.start || pos <= self.end
+        }
+    }
+}
+
+// What changed hands between two versions of a ring, what a node joining or leaving means for
+// the data already stored
+// Every point of either ring is a boundary, and between two neighbouring boundaries neither ring
+// changes owner, so comparing the owners at each boundary finds every arc that moved:
+//
+//   before   ----a----|--------b--------|----
+//   after    ----a----|--c--|-----b-----|----
+//   moves              (b -> c)
+//
+// Neighbouring arcs with the same from and to are joined into one move.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rebalance<N> {
+    // in order around the ring
+    moves: Vec<Move<N>>,
+}
+
+impl<N: AsRef<[u8]> + Clone + Eq> Rebalance<N> {
+    pub fn between(before: &HashRing<N>, after: &HashRing<N>) -> Self {
+        let mut boundaries: Vec<u64> = before
+            .points
+            .iter()
+            .chain(&after.points)
+            .map(|&(pos, _)| pos)
+            .collect();
+        boundaries.sort_unstable();
+        boundaries.dedup();
+
+        let mut moves: Vec<Move<N>> = Vec::new();
+        for (i, &end) in boundaries.iter().enumerate() {
+            let start = boundaries[(i + boundaries.len() - 1) % boundaries.len()];
+            let from = before.owner(end);
+            let to = after.owner(end);
+            if from == to {
+                continue;
+            }
+            match moves.last_mut() {
+                Some(last)
+                    if last.end == start
+                        && last.from.as_ref() == from
+                        && last.to.as_ref() == to =>
+                {
+                    last.end = end;
+                }
+                _ => moves.push(Move {
+                    start,
+                    end,
+                    from: from.cloned(),
+                    to: to.cloned(),
+                }),
+            }
+        }
+        Rebalance { moves }
+    }
+
+    pub fn moves(&self) -> &[Move<N>] {
+        &self.moves
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.moves.is_empty()
+    }
+
+    // The fraction of all keys that have to move
+    pub fn fraction(&self) -> f64 {
+        let moved: u128 = self.moves.iter().map(|m| arc_len(m.start, m.end)).sum();
+        moved as f64 / RING_SIZE as f64
+    }
+
+    // The move a key is part of, None if it stays where it was
+    pub fn get_move(&self, key: impl AsRef<[u8]>) -> Option<&Move<N>> {
+        let pos = position(key.as_ref());
+        let idx = self.moves.partition_point(|m| m.end < pos);
+        // past the last end only the first move can wrap around to cover it
+        let candidate = self.moves.get(idx).or(self.moves.first())?;
+        candidate.contains(pos).then_some(candidate)
+    }
+}
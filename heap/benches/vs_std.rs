@@ -0,0 +1,134 @@
+// Compares DaryHeap at a few widths with std::collections::BinaryHeap
+//  cargo bench --bench vs_std
+// DaryHeap<_, 2> is the same algorithm as BinaryHeap, so those two should be close (std's is
+// tuned with a "hole" instead of swaps). Wider heaps trade shorter sift ups for more comparisons
+// per level on the way down.
+
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion};
+use heap::{DaryHeap, IndexedHeap};
+
+const SIZES: [usize; 2] = [1_000, 100_000];
+
+fn values(n: usize) -> Vec<u64> {
+    let mut x: u64 = 1;
+    (0..n)
+        .map(|_| {
+            x = x.wrapping_mul(6364136223846793005).wrapping_add(1);
+            x >> 16
+        })
+        .collect()
+}
+
+// Push everything then pop everything
+fn push_pop(c: &mut Criterion) {
+    let mut group = c.benchmark_group("push_pop");
+    for n in SIZES {
+        let input = values(n);
+        macro_rules! bench {
+            ($name:literal, $heap:ty) => {
+                group.bench_with_input(BenchmarkId::new($name, n), &input, |b, input| {
+                    b.iter(|| {
+                        let mut heap = <$heap>::new();
+                        for &value in input {
+                            heap.push(value);
+                        }
+                        while let Some(value) = heap.pop() {
+                            black_box(value);
+                        }
+                    })
+                });
+            };
+        }
+        bench!("BinaryHeap", BinaryHeap<u64>);
+        bench!("DaryHeap<2>", DaryHeap<u64, 2>);
+        bench!("DaryHeap<4>", DaryHeap<u64, 4>);
+        bench!("DaryHeap<8>", DaryHeap<u64, 8>);
+    }
+    group.finish();
+}
+
+// Building a heap from a Vec in one go
+fn heapify(c: &mut Criterion) {
+    let mut group = c.benchmark_group("heapify");
+    for n in SIZES {
+        let input = values(n);
+        macro_rules! bench {
+            ($name:literal, $heap:ty) => {
+                group.bench_with_input(BenchmarkId::new($name, n), &input, |b, input| {
+                    b.iter_batched(
+                        || input.clone(),
+                        |input| black_box(<$heap>::from(input)),
+                        BatchSize::LargeInput,
+                    )
+                });
+            };
+        }
+        bench!("BinaryHeap", BinaryHeap<u64>);
+        bench!("DaryHeap<2>", DaryHeap<u64, 2>);
+        bench!("DaryHeap<4>", DaryHeap<u64, 4>);
+    }
+    group.finish();
+}
+
+// The Dijkstra pattern: every value gets lowered a few times before it's popped, either in
+// place with decrease_key or by pushing it again and skipping the stale copies
+fn decrease_key(c: &mut Criterion) {
+    let mut group = c.benchmark_group("decrease_key");
+    for n in SIZES {
+        let input = values(n);
+        group.bench_with_input(
+            BenchmarkId::new("BinaryHeap repush", n),
+            &input,
+            |b, input| {
+                b.iter(|| {
+                    let mut best = input.clone();
+                    let mut heap: BinaryHeap<_> = input
+                        .iter()
+                        .enumerate()
+                        .map(|(i, &v)| Reverse((v, i)))
+                        .collect();
+                    for round in 1..=3 {
+                        for (i, value) in best.iter_mut().enumerate().step_by(round + 1) {
+                            *value /= 2;
+                            heap.push(Reverse((*value, i)));
+                        }
+                    }
+                    while let Some(Reverse((value, i))) = heap.pop() {
+                        if value == best[i] {
+                            black_box(i);
+                        }
+                    }
+                })
+            },
+        );
+        macro_rules! bench {
+            ($name:literal, $d:literal) => {
+                group.bench_with_input(BenchmarkId::new($name, n), &input, |b, input| {
+                    b.iter(|| {
+                        let mut best = input.clone();
+                        let mut heap = IndexedHeap::<_, $d>::new();
+                        let handles: Vec<_> = input.iter().map(|&v| heap.push(v)).collect();
+                        for round in 1..=3 {
+                            for (i, value) in best.iter_mut().enumerate().step_by(round + 1) {
+                                *value /= 2;
+                                heap.decrease_key(handles[i], *value);
+                            }
+                        }
+                        while let Some((handle, _)) = heap.pop() {
+                            black_box(handle);
+                        }
+                    })
+                });
+            };
+        }
+        bench!("IndexedHeap<2>", 2);
+        bench!("IndexedHeap<4>", 4);
+    }
+    group.finish();
+}
+
+criterion_group!(benches, push_pop, heapify, decrease_key);
+criterion_main!(benches);
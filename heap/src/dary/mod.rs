@@ -0,0 +1,316 @@
+use std::fmt;
+use std::iter::FusedIterator;
+use std::mem;
+
+// A max-heap where every node has D children instead of 2, kept in a Vec
+//
+//   D = 3:                 9                 [9, 7, 8, 4, 2, 6, 5, 1, 3, 0]
+//                      /   |   \
+//                    7     8     4           children of i: D*i + 1 ..= D*i + D
+//                  / | \  / | \               parent of i:   (i - 1) / D
+//                 2  6 5 1  3 0
+//
+// Every node is at least as big as its children, so the biggest value is always at the root.
+// push puts the new value at the end and swaps it up past smaller parents (sift up). pop swaps
+// the root with the last value, takes it off the end, and swaps the new root down past bigger
+// children (sift down).
+//
+// A wider heap is shorter (log_D n levels), so sift up does fewer swaps, but sift down compares
+// D children per level to find the biggest. D = 2 is std's BinaryHeap. 4 tends to win when pushes
+// (and decrease_key in IndexedHeap) outnumber pops, and the D children of a node sit next to each
+// other in memory, so scanning them is cheap.
+#[derive(Clone)]
+pub struct DaryHeap<T, const D: usize = 2> {
+    data: Vec<T>,
+}
+
+pub(crate) fn parent<const D: usize>(i: usize) -> usize {
+    (i - 1) / D
+}
+
+pub(crate) fn first_child<const D: usize>(i: usize) -> usize {
+    D * i + 1
+}
+
+impl<T: Ord, const D: usize> DaryHeap<T, D> {
+    pub fn new() -> Self {
+        const { assert!(D >= 2, "a heap needs at least two children per node") };
+        DaryHeap { data: Vec::new() }
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        let mut heap = Self::new();
+        heap.data.reserve(capacity);
+        heap
+    }
+
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.data.clear();
+    }
+
+    // The biggest value
+    pub fn peek(&self) -> Option<&T> {
+        self.data.first()
+    }
+
+    pub fn push(&mut self, value: T) {
+        self.data.push(value);
+        self.sift_up(self.data.len() - 1);
+    }
+
+    // Takes out the biggest value
+    pub fn pop(&mut self) -> Option<T> {
+        let last = self.data.pop()?;
+        if self.data.is_empty() {
+            return Some(last);
+        }
+        let top = mem::replace(&mut self.data[0], last);
+        self.sift_down(0, self.data.len());
+        Some(top)
+    }
+
+    // Replaces the biggest value, cheaper than a pop then a push because it only sifts once
+    pub fn push_pop(&mut self, value: T) -> T {
+        match self.data.first() {
+            Some(top) if *top > value => {
+                let top = mem::replace(&mut self.data[0], value);
+                self.sift_down(0, self.data.len());
+                top
+            }
+            _ => value,
+        }
+    }
+
+    // Every value, in no particular order
+    pub fn iter(&self) -> std::slice::Iter<'_, T> {
+        self.data.iter()
+    }
+
+    pub fn into_vec(self) -> Vec<T> {
+        self.data
+    }
+
+    // Sorted smallest first, in place (heapsort): the biggest value is swapped to the end of the
+    // Vec and the heap shrinks by one in front of it, until there's no heap left
+    pub fn into_sorted_vec(mut self) -> Vec<T> {
+        let mut end = self.data.len();
+        while end > 1 {
+            end -= 1;
+            self.data.swap(0, end);
+            self.sift_down(0, end);
+        }
+        self.data
+    }
+
+    // Takes every value out biggest first
+    pub fn drain_sorted(&mut self) -> impl Iterator<Item = T> + '_ {
+        std::iter::from_fn(|| self.pop())
+    }
+
+    fn sift_up(&mut self, mut i: usize) {
+        while i > 0 {
+            let up = parent::<D>(i);
+            if self.data[i] <= self.data[up] {
+                break;
+            }
+            self.data.swap(i, up);
+            i = up;
+        }
+    }
+
+    // Only data[..end] is the heap
+    fn sift_down(&mut self, mut i: usize, end: usize) {
+        loop {
+            let first = first_child::<D>(i);
+            if first >= end {
+                break;
+            }
+            let mut biggest = first;
+            for child in first + 1..(first + D).min(end) {
+                if self.data[child] > self.data[biggest] {
+                    biggest = child;
+                }
+            }
+            if self.data[biggest] <= self.data[i] {
+                break;
+            }
+            self.data.swap(i, biggest);
+            i = biggest;
+        }
+    }
+
+    // Floyd's heapify: sift down every node that has children, last first. Most nodes are near
+    // the bottom and only move a level or two, which makes it O(n) rather than n pushes' O(n log n)
+    fn rebuild(&mut self) {
+        let len = self.data.len();
+        if len < 2 {
+            return;
+        }
+        for i in (0..=parent::<D>(len - 1)).rev() {
+            self.sift_down(i, len);
+        }
+    }
+}
+
+impl<T: Ord, const D: usize> Default for DaryHeap<T, D> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Ord, const D: usize> From<Vec<T>> for DaryHeap<T, D> {
+    fn from(data: Vec<T>) -> Self {
+        let mut heap = Self::new();
+        heap.data = data;
+        heap.rebuild();
+        heap
+    }
+}
+
+impl<T: Ord, const D: usize> FromIterator<T> for DaryHeap<T, D> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        Self::from(iter.into_iter().collect::<Vec<_>>())
+    }
+}
+
+impl<T: Ord, const D: usize> Extend<T> for DaryHeap<T, D> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for value in iter {
+            self.push(value);
+        }
+    }
+}
+
+impl<T: fmt::Debug, const D: usize> fmt::Debug for DaryHeap<T, D> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.data.iter()).finish()
+    }
+}
+
+// Values in heap (Vec) order, like BinaryHeap's IntoIter
+pub struct DaryHeapIntoIter<T> {
+    inner: std::vec::IntoIter<T>,
+}
+
+impl<T, const D: usize> IntoIterator for DaryHeap<T, D> {
+    type Item = T;
+    type IntoIter = DaryHeapIntoIter<T>;
+
+    fn into_iter(self) -> DaryHeapIntoIter<T> {
+        DaryHeapIntoIter {
+            inner: self.data.into_iter(),
+        }
+    }
+}
+
+impl<T> Iterator for DaryHeapIntoIter<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.inner.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<T> ExactSizeIterator for DaryHeapIntoIter<T> {}
+
+// vec::IntoIter stays empty once it's empty
+impl<T> FusedIterator for DaryHeapIntoIter<T> {}
+
+#[cfg(test)]
+mod testing {
+    use std::collections::BinaryHeap;
+
+    use super::*;
+
+    fn is_heap<T: Ord, const D: usize>(heap: &DaryHeap<T, D>) -> bool {
+        (1..heap.data.len()).all(|i| heap.data[i] <= heap.data[parent::<D>(i)])
+    }
+
+    fn agrees_with_std<const D: usize>() {
+        let mut heap = DaryHeap::<u32, D>::new();
+        let mut model = BinaryHeap::new();
+        let mut x: u64 = D as u64;
+        for _ in 0..20_000 {
+            x = x.wrapping_mul(6364136223846793005).wrapping_add(1);
+            let value = (x >> 33) as u32 % 1000;
+            match x % 5 {
+                0..=2 => {
+                    heap.push(value);
+                    model.push(value);
+                }
+                3 => assert_eq!(heap.pop(), model.pop()),
+                _ => {
+                    let expected = match model.peek() {
+                        Some(&top) if top > value => {
+                            model.pop();
+                            model.push(value);
+                            top
+                        }
+                        _ => value,
+                    };
+                    assert_eq!(heap.push_pop(value), expected);
+                }
+            }
+            assert_eq!(heap.peek(), model.peek());
+            assert_eq!(heap.len(), model.len());
+        }
+        assert!(is_heap(&heap));
+        assert_eq!(heap.into_sorted_vec(), model.into_sorted_vec());
+    }
+
+    #[test]
+    fn binary_heap() {
+        agrees_with_std::<2>();
+    }
+
+    #[test]
+    fn wider_heaps() {
+        agrees_with_std::<3>();
+        agrees_with_std::<4>();
+        agrees_with_std::<8>();
+    }
+
+    #[test]
+    fn heapify() {
+        let values: Vec<i32> = (0..1000).map(|i| (i * 7919) % 1009 - 500).collect();
+        let heap: DaryHeap<_, 4> = values.iter().copied().collect();
+        assert!(is_heap(&heap));
+        assert_eq!(heap.peek(), values.iter().max());
+
+        let mut sorted = values.clone();
+        sorted.sort_unstable();
+        assert_eq!(heap.clone().into_sorted_vec(), sorted);
+
+        let mut heap = heap;
+        let drained: Vec<_> = heap.drain_sorted().collect();
+        assert!(heap.is_empty());
+        assert!(drained.iter().eq(sorted.iter().rev()));
+    }
+
+    #[test]
+    fn small_heaps() {
+        let mut heap: DaryHeap<&str> = DaryHeap::default();
+        assert_eq!((heap.pop(), heap.peek()), (None, None));
+        assert_eq!(heap.push_pop("a"), "a");
+        heap.extend(["b", "c", "a"]);
+        assert_eq!(format!("{heap:?}"), r#"["c", "b", "a"]"#);
+        assert_eq!(heap.push_pop("b"), "c");
+        assert_eq!(heap.iter().len(), 3);
+        let mut values: Vec<_> = heap.into_iter().collect();
+        values.sort_unstable();
+        assert_eq!(values, ["a", "b", "b"]);
+        assert!(DaryHeap::<u8, 3>::from(vec![]).into_sorted_vec().is_empty());
+    }
+}
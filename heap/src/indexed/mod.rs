@@ -0,0 +1,341 @@
+use std::fmt;
+
+use crate::dary::{first_child, parent};
+
+// Names a value pushed onto an IndexedHeap, for finding it again once it's moved
+// Slots are reused after a value leaves the heap, the generation is what tells a handle to the
+// old value apart from one to the new
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Handle {
+    slot: usize,
+    generation: u32,
+}
+
+struct Slot<T> {
+    // None once the value has left the heap, until the slot is reused
+    value: Option<T>,
+    // where in `heap` the value is, kept up to date by every swap
+    pos: usize,
+    generation: u32,
+}
+
+// A D-ary min-heap whose values can be changed after they're pushed
+// Values don't move around in memory: they sit in slots and the heap itself is a Vec of slot
+// numbers. Each slot remembers where it is in the heap, so a Handle (slot number) goes straight to
+// the right spot, instead of a search through the whole heap.
+//
+//   heap:  [2, 0, 1]               slots: 0: (value 5, pos 1)
+//                                         1: (value 9, pos 2)
+//                                         2: (value 3, pos 0)
+//
+// This is the heap Dijkstra's algorithm is usually written with: when a shorter way to a node
+// turns up its distance is decreased in place, where with std's BinaryHeap the node is pushed a
+// second time and the stale entry skipped when it's popped (see graph's dijkstra).
+pub struct IndexedHeap<T, const D: usize = 2> {
+    heap: Vec<usize>,
+    slots: Vec<Slot<T>>,
+    free: Vec<usize>,
+}
+
+impl<T: Ord, const D: usize> IndexedHeap<T, D> {
+    pub fn new() -> Self {
+        const { assert!(D >= 2, "a heap needs at least two children per node") };
+        IndexedHeap {
+            heap: Vec::new(),
+            slots: Vec::new(),
+            free: Vec::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.heap.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.heap.is_empty()
+    }
+
+    pub fn push(&mut self, value: T) -> Handle {
+        let pos = self.heap.len();
+        let slot = match self.free.pop() {
+            Some(slot) => {
+                let entry = &mut self.slots[slot];
+                entry.value = Some(value);
+                entry.pos = pos;
+                slot
+            }
+            None => {
+                self.slots.push(Slot {
+                    value: Some(value),
+                    pos,
+                    generation: 0,
+                });
+                self.slots.len() - 1
+            }
+        };
+        self.heap.push(slot);
+        self.sift_up(pos);
+        self.handle(slot)
+    }
+
+    // The smallest value
+    pub fn peek(&self) -> Option<(Handle, &T)> {
+        let &slot = self.heap.first()?;
+        Some((self.handle(slot), self.value(0)))
+    }
+
+    // Takes out the smallest value, its handle goes stale
+    pub fn pop(&mut self) -> Option<(Handle, T)> {
+        let &slot = self.heap.first()?;
+        let handle = self.handle(slot);
+        self.remove(handle).map(|value| (handle, value))
+    }
+
+    // None if the value has already left the heap
+    pub fn get(&self, handle: Handle) -> Option<&T> {
+        self.live(handle)?;
+        self.slots[handle.slot].value.as_ref()
+    }
+
+    pub fn contains(&self, handle: Handle) -> bool {
+        self.live(handle).is_some()
+    }
+
+    // Lowers a value in place, it can only move up
+    // Panics if the handle is stale or value is bigger than what's there, use update for that.
+    pub fn decrease_key(&mut self, handle: Handle, value: T) {
+        let pos = self.live(handle).expect("stale heap handle");
+        let old = self.slots[handle.slot].value.as_mut().unwrap();
+        assert!(value <= *old, "decrease_key can't make a value bigger");
+        *old = value;
+        self.sift_up(pos);
+    }
+
+    // Changes a value either way, returns the old one (None for a stale handle)
+    pub fn update(&mut self, handle: Handle, value: T) -> Option<T> {
+        let pos = self.live(handle)?;
+        let old = self.slots[handle.slot].value.replace(value);
+        self.sift_up(pos);
+        self.sift_down(self.slots[handle.slot].pos);
+        old
+    }
+
+    // Takes any value out of the heap
+    // The last value in the heap fills the hole and is sifted whichever way it needs to go.
+    pub fn remove(&mut self, handle: Handle) -> Option<T> {
+        let pos = self.live(handle)?;
+        let last = self.heap.len() - 1;
+        self.swap(pos, last);
+        self.heap.pop();
+
+        let slot = &mut self.slots[handle.slot];
+        let value = slot.value.take();
+        slot.generation = slot.generation.wrapping_add(1);
+        self.free.push(handle.slot);
+
+        if pos < last {
+            let moved = self.heap[pos];
+            self.sift_up(pos);
+            self.sift_down(self.slots[moved].pos);
+        }
+        value
+    }
+
+    pub fn clear(&mut self) {
+        self.heap.clear();
+        self.slots.clear();
+        self.free.clear();
+    }
+
+    fn handle(&self, slot: usize) -> Handle {
+        Handle {
+            slot,
+            generation: self.slots[slot].generation,
+        }
+    }
+
+    // Where the handle's value is in the heap, None if it's stale
+    fn live(&self, handle: Handle) -> Option<usize> {
+        let slot = self.slots.get(handle.slot)?;
+        (slot.generation == handle.generation && slot.value.is_some()).then_some(slot.pos)
+    }
+
+    fn value(&self, pos: usize) -> &T {
+        self.slots[self.heap[pos]]
+            .value
+            .as_ref()
+            .expect("slots in the heap hold values")
+    }
+
+    fn swap(&mut self, a: usize, b: usize) {
+        self.heap.swap(a, b);
+        self.slots[self.heap[a]].pos = a;
+        self.slots[self.heap[b]].pos = b;
+    }
+
+    fn sift_up(&mut self, mut i: usize) {
+        while i > 0 {
+            let up = parent::<D>(i);
+            if self.value(i) >= self.value(up) {
+                break;
+            }
+            self.swap(i, up);
+            i = up;
+        }
+    }
+
+    fn sift_down(&mut self, mut i: usize) {
+        let end = self.heap.len();
+        loop {
+            let first = first_child::<D>(i);
+            if first >= end {
+                break;
+            }
+            let mut smallest = first;
+            for child in first + 1..(first + D).min(end) {
+                if self.value(child) < self.value(smallest) {
+                    smallest = child;
+                }
+            }
+            if self.value(smallest) >= self.value(i) {
+                break;
+            }
+            self.swap(i, smallest);
+            i = smallest;
+        }
+    }
+}
+
+impl<T: Ord, const D: usize> Default for IndexedHeap<T, D> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: fmt::Debug, const D: usize> fmt::Debug for IndexedHeap<T, D> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list()
+            .entries(self.heap.iter().map(|&slot| &self.slots[slot].value))
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod testing {
+    use super::*;
+
+    fn check<T: Ord, const D: usize>(heap: &IndexedHeap<T, D>) {
+        for (pos, &slot) in heap.heap.iter().enumerate() {
+            assert_eq!(heap.slots[slot].pos, pos);
+            if pos > 0 {
+                assert!(heap.value(pos) >= heap.value(parent::<D>(pos)));
+            }
+        }
+        assert_eq!(heap.heap.len() + heap.free.len(), heap.slots.len());
+    }
+
+    fn agrees_with_scanning<const D: usize>() {
+        let mut heap = IndexedHeap::<u32, D>::new();
+        // (handle, value) of everything in the heap, the smallest found by looking at them all
+        let mut model: Vec<(Handle, u32)> = Vec::new();
+        let mut stale = Vec::new();
+
+        let mut x: u64 = 99 + D as u64;
+        let mut next = |n: u64| {
+            x = x.wrapping_mul(6364136223846793005).wrapping_add(1);
+            (x >> 33) % n
+        };
+        for _ in 0..10_000 {
+            match next(6) {
+                0 | 1 => {
+                    let value = next(1000) as u32;
+                    model.push((heap.push(value), value));
+                }
+                2 => {
+                    let popped = heap.pop();
+                    let min = model.iter().map(|&(_, value)| value).min();
+                    assert_eq!(popped.map(|(_, value)| value), min);
+                    if let Some((handle, _)) = popped {
+                        model.retain(|&(h, _)| h != handle);
+                        stale.push(handle);
+                    }
+                }
+                3 if !model.is_empty() => {
+                    let i = next(model.len() as u64) as usize;
+                    let (handle, value) = model[i];
+                    let lower = value - next(u64::from(value) + 1) as u32;
+                    heap.decrease_key(handle, lower);
+                    model[i].1 = lower;
+                }
+                4 if !model.is_empty() => {
+                    let i = next(model.len() as u64) as usize;
+                    let value = next(1000) as u32;
+                    assert_eq!(heap.update(model[i].0, value), Some(model[i].1));
+                    model[i].1 = value;
+                }
+                5 if !model.is_empty() => {
+                    let i = next(model.len() as u64) as usize;
+                    let (handle, value) = model.swap_remove(i);
+                    assert_eq!(heap.remove(handle), Some(value));
+                    stale.push(handle);
+                }
+                _ => {}
+            }
+
+            assert_eq!(heap.len(), model.len());
+            assert_eq!(
+                heap.peek().map(|(_, &value)| value),
+                model.iter().map(|&(_, value)| value).min()
+            );
+        }
+        check(&heap);
+        for &(handle, value) in &model {
+            assert_eq!(heap.get(handle), Some(&value));
+        }
+        for &handle in &stale {
+            assert!(!heap.contains(handle));
+            assert_eq!(heap.remove(handle), None);
+        }
+    }
+
+    #[test]
+    fn binary_heap() {
+        agrees_with_scanning::<2>();
+    }
+
+    #[test]
+    fn wider_heaps() {
+        agrees_with_scanning::<3>();
+        agrees_with_scanning::<4>();
+    }
+
+    #[test]
+    fn handles_go_stale() {
+        let mut heap: IndexedHeap<&str> = IndexedHeap::new();
+        let b = heap.push("b");
+        let a = heap.push("a");
+        assert_eq!(heap.peek(), Some((a, &"a")));
+        assert_eq!(format!("{heap:?}"), r#"[Some("a"), Some("b")]"#);
+
+        assert_eq!(heap.pop(), Some((a, "a")));
+        // the freed slot is reused, the old handle doesn't see the new value
+        let c = heap.push("c");
+        assert_ne!(a, c);
+        assert_eq!(heap.get(a), None);
+        assert_eq!(heap.get(c), Some(&"c"));
+        assert_eq!(heap.update(a, "z"), None);
+
+        heap.decrease_key(c, "a");
+        assert_eq!(heap.pop(), Some((c, "a")));
+        assert_eq!(heap.pop(), Some((b, "b")));
+        assert_eq!(heap.pop(), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "can't make a value bigger")]
+    fn decrease_key_only_decreases() {
+        let mut heap: IndexedHeap<u8> = IndexedHeap::new();
+        let handle = heap.push(1);
+        heap.decrease_key(handle, 2);
+    }
+}
@@ -0,0 +1,5 @@
+mod dary;
+mod indexed;
+
+pub use dary::{DaryHeap, DaryHeapIntoIter};
+pub use indexed::{Handle, IndexedHeap};
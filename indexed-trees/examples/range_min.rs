@@ -0,0 +1,33 @@
+// Range minimums over a changing array with a lazy segment tree
+//  cargo run --example range_min
+// Hourly temperatures from a sensor over two days. "How cold did it get between these hours" is a
+// range min. Finding out the sensor read 2 degrees high for a stretch is a range add, and
+// replacing a stretch of bad readings with a fixed value is a range assign. Neither visits every
+// hour in the stretch, and queries afterwards see them straight away.
+
+use indexed_trees::{Add, Assign, Min, SegmentTree};
+
+fn main() {
+    let readings: Vec<i64> = (0..48)
+        .map(|hour: i64| {
+            // colder at night, a little warmer on the second day
+            let hour_of_day = hour % 24;
+            let night = (hour_of_day - 14).abs();
+            18 - night / 2 + hour / 24
+        })
+        .collect();
+
+    let mut offsets = SegmentTree::<Min, Add>::new(&readings);
+    println!("coldest on day 1: {}", offsets.query(0..24));
+    println!("coldest on day 2: {}", offsets.query(24..48));
+
+    // the sensor read 2 high from 20:00 on day 1 to 06:00 on day 2
+    offsets.update(20..30, Add(-2));
+    println!("coldest night, corrected: {}", offsets.query(18..32));
+
+    // hours 40 to 44 were garbage, replace them with the reading just before
+    let mut patched = SegmentTree::<Min, Assign>::new(&readings);
+    patched.update(40..45, Assign(readings[39]));
+    println!("coldest on day 2, patched: {}", patched.query(24..48));
+    println!("coldest overall, patched: {}", patched.query(..));
+}
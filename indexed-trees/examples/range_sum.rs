@@ -0,0 +1,36 @@
+// Range sums over a changing array, with a Fenwick tree and with a segment tree
+//  cargo run --example range_sum
+// A shop's takings per day for a month. Refunds change single days, which is all a Fenwick tree
+// needs. A price change over a run of days changes every day in it, which a Fenwick tree would
+// have to do one day at a time; the segment tree does it as one range update.
+
+use indexed_trees::{Add, Fenwick, SegmentTree, Sum};
+
+fn main() {
+    let takings: Vec<i64> = (0..30).map(|day| 200 + (day * 37) % 90).collect();
+    let mut fenwick = Fenwick::from_values(&takings);
+    let mut segments = SegmentTree::<Sum, Add>::new(&takings);
+
+    println!("week 1: {}", fenwick.range_sum(0..7));
+    println!("week 2: {}", fenwick.range_sum(7..14));
+
+    // a refund of 45 on day 9
+    fenwick.add(9, -45);
+    segments.update(9..=9, Add(-45));
+    println!("week 2 after the refund: {}", fenwick.range_sum(7..14));
+
+    // a surcharge of 10 a day from day 10 to 20, then what did weeks 2 and 3 bring in
+    segments.update(10..=20, Add(10));
+    println!(
+        "weeks 2 and 3 with the surcharge: {}",
+        segments.query(7..21)
+    );
+    println!("the month: {}", segments.query(..));
+
+    // the day the month's first 3000 was reached
+    let day = fenwick.find_prefix(3000);
+    println!(
+        "3000 reached on day {day}, with {} taken by then",
+        fenwick.prefix_sum(day + 1)
+    );
+}
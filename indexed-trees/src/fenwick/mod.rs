@@ -0,0 +1,123 @@
+#[cfg(test)]
+mod properties;
+
+use std::ops::{Add, RangeBounds, Sub};
+
+use crate::bounds::bounds;
+
+// A Fenwick tree (binary indexed tree): prefix sums of an array that's being changed, both in
+// O(log n), in an array the same size as the values
+//
+// Keeping the prefix sums themselves makes a query O(1) but an update O(n), every sum after the
+// change moves. Keeping the values makes it the other way round. A Fenwick tree keeps sums of
+// blocks whose sizes come from the binary of the (1-based) index: slot i holds the sum of the
+// lowbit(i) values ending at i, where lowbit(i) is i's lowest set bit.
+//
+//   slot i     1   2   3   4   5   6   7   8
+//   lowbit     1   2   1   4   1   2   1   8
+//   covers     1  1-2  3  1-4  5  5-6  7  1-8
+//
+// A prefix sum up to i adds the slot at i, then strips i's lowest bit and goes again:
+// 7 = 0b111 -> 7 (7), 6 (5-6), 4 (1-4). At most one slot per bit.
+// Adding to value i changes every slot covering it, found by adding the lowest bit instead:
+// 3 = 0b11 -> 3, 4, 8.
+//
+// Sums need subtraction for ranges (sum(a..b) = prefix(b) - prefix(a)), so this is for groups,
+// like integers under +. Min doesn't have an inverse, for that see SegmentTree.
+#[derive(Debug, Clone)]
+pub struct Fenwick<T> {
+    // slot i + 1 of the picture above is tree[i]
+    tree: Vec<T>,
+}
+
+fn lowbit(i: usize) -> usize {
+    i & i.wrapping_neg()
+}
+
+impl<T: Copy + Default + Add<Output = T> + Sub<Output = T>> Fenwick<T> {
+    // len zeros (T::default())
+    pub fn new(len: usize) -> Self {
+        Fenwick {
+            tree: vec![T::default(); len],
+        }
+    }
+
+    // In O(n): each slot passes its finished sum up to the next slot covering it
+    pub fn from_values(values: &[T]) -> Self {
+        let mut tree = values.to_vec();
+        for i in 1..=tree.len() {
+            let parent = i + lowbit(i);
+            if parent <= tree.len() {
+                tree[parent - 1] = tree[parent - 1] + tree[i - 1];
+            }
+        }
+        Fenwick { tree }
+    }
+
+    pub fn len(&self) -> usize {
+        self.tree.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tree.is_empty()
+    }
+
+    pub fn add(&mut self, index: usize, delta: T) {
+        assert!(index < self.len(), "index {index} is past the end");
+        let mut i = index + 1;
+        while i <= self.len() {
+            self.tree[i - 1] = self.tree[i - 1] + delta;
+            i += lowbit(i);
+        }
+    }
+
+    // The sum of the first `end` values
+    pub fn prefix_sum(&self, end: usize) -> T {
+        assert!(end <= self.len(), "prefix end {end} is past the end");
+        let mut sum = T::default();
+        let mut i = end;
+        while i > 0 {
+            sum = sum + self.tree[i - 1];
+            i -= lowbit(i);
+        }
+        sum
+    }
+
+    pub fn range_sum(&self, range: impl RangeBounds<usize>) -> T {
+        let range = bounds(range, self.len());
+        self.prefix_sum(range.end) - self.prefix_sum(range.start)
+    }
+
+    pub fn get(&self, index: usize) -> T {
+        self.range_sum(index..=index)
+    }
+
+    pub fn set(&mut self, index: usize, value: T) {
+        let delta = value - self.get(index);
+        self.add(index, delta);
+    }
+}
+
+impl<T: Copy + Default + Add<Output = T> + Sub<Output = T> + PartialOrd> Fenwick<T> {
+    // The first index whose prefix sum, including itself, reaches target, len if none does
+    // The values have to be non-negative, so the prefix sums only go up. Then this is a search
+    // down the tree rather than a binary search over prefix_sum calls: try the biggest block
+    // first and keep it if the sum is still short, O(log n) in all. With values as weights, it
+    // picks index i with probability weight(i) / total for a target uniform in [1, total].
+    pub fn find_prefix(&self, target: T) -> usize {
+        let mut pos = 0;
+        let mut remaining = target;
+        let mut step = match self.len() {
+            0 => 0,
+            len => 1 << len.ilog2(),
+        };
+        while step > 0 {
+            if pos + step <= self.len() && self.tree[pos + step - 1] < remaining {
+                pos += step;
+                remaining = remaining - self.tree[pos - 1];
+            }
+            step >>= 1;
+        }
+        pos
+    }
+}
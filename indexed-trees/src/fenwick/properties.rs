@@ -0,0 +1,75 @@
+// Property tests against a plain Vec: after any sequence of adds and sets, every sum the tree
+// gives has to be the one adding up the values would
+use proptest::prelude::*;
+
+use super::Fenwick;
+
+#[derive(Debug, Clone)]
+enum Op {
+    Add(usize, i64),
+    Set(usize, i64),
+}
+
+fn values_and_ops() -> impl Strategy<Value = (Vec<i64>, Vec<Op>)> {
+    prop::collection::vec(-1000_i64..1000, 1..100).prop_flat_map(|values| {
+        let len = values.len();
+        let op = prop_oneof![
+            (0..len, -1000_i64..1000).prop_map(|(i, delta)| Op::Add(i, delta)),
+            (0..len, -1000_i64..1000).prop_map(|(i, value)| Op::Set(i, value)),
+        ];
+        (Just(values), prop::collection::vec(op, 0..50))
+    })
+}
+
+proptest! {
+    #[test]
+    fn sums_match_brute_force((mut values, ops) in values_and_ops()) {
+        let mut tree = Fenwick::from_values(&values);
+        for op in ops {
+            match op {
+                Op::Add(i, delta) => {
+                    tree.add(i, delta);
+                    values[i] += delta;
+                }
+                Op::Set(i, value) => {
+                    tree.set(i, value);
+                    values[i] = value;
+                }
+            }
+        }
+
+        for start in 0..=values.len() {
+            prop_assert_eq!(tree.prefix_sum(start), values[..start].iter().sum::<i64>());
+            for end in start..=values.len() {
+                prop_assert_eq!(tree.range_sum(start..end), values[start..end].iter().sum::<i64>());
+            }
+        }
+    }
+
+    #[test]
+    fn find_prefix_matches_a_scan(
+        weights in prop::collection::vec(0_u64..20, 0..100),
+        target in 0_u64..1000,
+    ) {
+        let tree = Fenwick::from_values(&weights);
+        let mut running = 0;
+        let expected = weights
+            .iter()
+            .position(|&w| {
+                running += w;
+                running >= target
+            })
+            .unwrap_or(weights.len());
+        prop_assert_eq!(tree.find_prefix(target), expected);
+    }
+}
+
+#[test]
+fn building_matches_adding_one_at_a_time() {
+    let values: Vec<i64> = (1..=37).map(|i| i * i - 50).collect();
+    let mut added = Fenwick::new(values.len());
+    for (i, &value) in values.iter().enumerate() {
+        added.add(i, value);
+    }
+    assert_eq!(added.tree, Fenwick::from_values(&values).tree);
+}
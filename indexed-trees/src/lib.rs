@@ -0,0 +1,8 @@
+mod bounds;
+mod fenwick;
+mod monoid;
+mod segment_tree;
+
+pub use fenwick::Fenwick;
+pub use monoid::{Action, Add, Assign, Max, Min, Monoid, Sum};
+pub use segment_tree::SegmentTree;
@@ -0,0 +1,143 @@
+// What a segment tree needs to know about the values it summarises, and the changes it applies
+//
+// A monoid is a way to combine two values that's associative, (a + b) + c == a + (b + c), with
+// an identity that changes nothing when combined, like 0 for + or i64::MAX for min.
+// Associative is what lets a tree keep one summary per node: the summary of a range is the
+// combination of the summaries of any pieces it's cut into, however it's cut. The identity is
+// the summary of nothing, for empty ranges.
+//
+// An action is an update to every value in a range, "add 5 to all of these". A lazy segment tree
+// never visits all of them: it changes the summary of each node covering the range and leaves a
+// note for the node's children to catch up on later. For that an action has to be able to
+//  - change a summary directly, knowing only how many values are under it (apply)
+//  - fold two notes into one, when a second update comes before the first was passed on (compose)
+// Adding 5 to each of 10 values adds 50 to their sum, but only 5 to their min, so whether an
+// action works with a monoid depends on both.
+
+pub trait Monoid {
+    type Value: Clone;
+
+    fn identity() -> Self::Value;
+    fn combine(a: &Self::Value, b: &Self::Value) -> Self::Value;
+}
+
+pub trait Action<M: Monoid>: Clone {
+    // The summary of len values after this is applied to each of them
+    fn apply(&self, summary: &M::Value, len: usize) -> M::Value;
+    // One action doing earlier then self
+    fn compose(&self, earlier: &Self) -> Self;
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Sum;
+
+#[derive(Debug, Clone, Copy)]
+pub struct Min;
+
+#[derive(Debug, Clone, Copy)]
+pub struct Max;
+
+impl Monoid for Sum {
+    type Value = i64;
+
+    fn identity() -> i64 {
+        0
+    }
+
+    fn combine(a: &i64, b: &i64) -> i64 {
+        a + b
+    }
+}
+
+impl Monoid for Min {
+    type Value = i64;
+
+    fn identity() -> i64 {
+        i64::MAX
+    }
+
+    fn combine(a: &i64, b: &i64) -> i64 {
+        *a.min(b)
+    }
+}
+
+impl Monoid for Max {
+    type Value = i64;
+
+    fn identity() -> i64 {
+        i64::MIN
+    }
+
+    fn combine(a: &i64, b: &i64) -> i64 {
+        *a.max(b)
+    }
+}
+
+// Adds to every value in the range
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Add(pub i64);
+
+// Sets every value in the range
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Assign(pub i64);
+
+impl Action<Sum> for Add {
+    fn apply(&self, sum: &i64, len: usize) -> i64 {
+        sum + self.0 * len as i64
+    }
+
+    fn compose(&self, earlier: &Self) -> Self {
+        Add(self.0 + earlier.0)
+    }
+}
+
+impl Action<Min> for Add {
+    fn apply(&self, min: &i64, _: usize) -> i64 {
+        min + self.0
+    }
+
+    fn compose(&self, earlier: &Self) -> Self {
+        Add(self.0 + earlier.0)
+    }
+}
+
+impl Action<Max> for Add {
+    fn apply(&self, max: &i64, _: usize) -> i64 {
+        max + self.0
+    }
+
+    fn compose(&self, earlier: &Self) -> Self {
+        Add(self.0 + earlier.0)
+    }
+}
+
+// For assignments the later one wins outright
+impl Action<Sum> for Assign {
+    fn apply(&self, _: &i64, len: usize) -> i64 {
+        self.0 * len as i64
+    }
+
+    fn compose(&self, _: &Self) -> Self {
+        *self
+    }
+}
+
+impl Action<Min> for Assign {
+    fn apply(&self, _: &i64, _: usize) -> i64 {
+        self.0
+    }
+
+    fn compose(&self, _: &Self) -> Self {
+        *self
+    }
+}
+
+impl Action<Max> for Assign {
+    fn apply(&self, _: &i64, _: usize) -> i64 {
+        self.0
+    }
+
+    fn compose(&self, _: &Self) -> Self {
+        *self
+    }
+}
@@ -0,0 +1,185 @@
+#[cfg(test)]
+mod properties;
+
+use std::ops::RangeBounds;
+
+use crate::bounds::bounds;
+use crate::{Action, Monoid};
+
+// A segment tree with lazy propagation: combine any range of values, or apply an update to every
+// value in a range, both in O(log n)
+//
+// Every node summarises a range of the values, the root all of them and each child half its
+// parent's. Any range is covered by O(log n) nodes (at most two per level):
+//
+//                        0..8
+//               0..4              4..8
+//           0..2    2..4      4..6    6..8
+//          0   1   2   3     4   5   6   7
+//
+//   query 1..7 = combine(1, 2..4, 4..6, 6)
+//
+// An update to a range changes the summaries of the nodes covering it, and the nodes above them,
+// and stops there. Each covering node keeps the update as pending for its children, and it's
+// only passed down to them when an update later on needs to go through the node (push). Queries
+// don't push: they carry the pending updates they've passed on the way down and apply them to
+// the summary where they stop, so a query only needs &self.
+pub struct SegmentTree<M: Monoid, A> {
+    len: usize,
+    // node 1 is the root, node i's children are 2i and 2i + 1. A node's summary already has its
+    // own pending update in it, but not the ones still pending above it.
+    summary: Vec<M::Value>,
+    pending: Vec<Option<A>>,
+}
+
+impl<M: Monoid, A: Action<M>> SegmentTree<M, A> {
+    pub fn new(values: &[M::Value]) -> Self {
+        // a tree over n values has fewer than 4n nodes, whatever n is
+        let size = 4 * values.len().max(1);
+        let mut tree = SegmentTree {
+            len: values.len(),
+            summary: vec![M::identity(); size],
+            pending: vec![None; size],
+        };
+        if !values.is_empty() {
+            tree.build(1, 0, values.len(), values);
+        }
+        tree
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    // The values in range combined, identity if it's empty
+    pub fn query(&self, range: impl RangeBounds<usize>) -> M::Value {
+        let range = bounds(range, self.len);
+        if range.is_empty() {
+            return M::identity();
+        }
+        self.query_node(1, 0, self.len, range.start, range.end, None)
+    }
+
+    pub fn get(&self, index: usize) -> M::Value {
+        assert!(index < self.len, "index {index} is past the end");
+        self.query(index..=index)
+    }
+
+    // Applies the action to every value in range
+    pub fn update(&mut self, range: impl RangeBounds<usize>, action: A) {
+        let range = bounds(range, self.len);
+        if !range.is_empty() {
+            self.update_node(1, 0, self.len, range.start, range.end, &action);
+        }
+    }
+
+    pub fn set(&mut self, index: usize, value: M::Value) {
+        assert!(index < self.len, "index {index} is past the end");
+        self.set_node(1, 0, self.len, index, value);
+    }
+
+    fn build(&mut self, node: usize, lo: usize, hi: usize, values: &[M::Value]) {
+        if hi - lo == 1 {
+            self.summary[node] = values[lo].clone();
+            return;
+        }
+        let mid = lo + (hi - lo) / 2;
+        self.build(2 * node, lo, mid, values);
+        self.build(2 * node + 1, mid, hi, values);
+        self.pull(node);
+    }
+
+    // node covers lo..hi, and above is everything pending on the way down to it, newest last
+    fn query_node(
+        &self,
+        node: usize,
+        lo: usize,
+        hi: usize,
+        start: usize,
+        end: usize,
+        above: Option<&A>,
+    ) -> M::Value {
+        if end <= lo || hi <= start {
+            return M::identity();
+        }
+        if start <= lo && hi <= end {
+            return match above {
+                Some(action) => action.apply(&self.summary[node], hi - lo),
+                None => self.summary[node].clone(),
+            };
+        }
+        let combined = match (above, &self.pending[node]) {
+            (Some(above), Some(own)) => Some(above.compose(own)),
+            (above, own) => above.or(own.as_ref()).cloned(),
+        };
+        let mid = lo + (hi - lo) / 2;
+        let left = self.query_node(2 * node, lo, mid, start, end, combined.as_ref());
+        let right = self.query_node(2 * node + 1, mid, hi, start, end, combined.as_ref());
+        M::combine(&left, &right)
+    }
+
+    fn update_node(
+        &mut self,
+        node: usize,
+        lo: usize,
+        hi: usize,
+        start: usize,
+        end: usize,
+        action: &A,
+    ) {
+        if end <= lo || hi <= start {
+            return;
+        }
+        if start <= lo && hi <= end {
+            self.apply(node, hi - lo, action);
+            return;
+        }
+        self.push(node, lo, hi);
+        let mid = lo + (hi - lo) / 2;
+        self.update_node(2 * node, lo, mid, start, end, action);
+        self.update_node(2 * node + 1, mid, hi, start, end, action);
+        self.pull(node);
+    }
+
+    fn set_node(&mut self, node: usize, lo: usize, hi: usize, index: usize, value: M::Value) {
+        if hi - lo == 1 {
+            self.summary[node] = value;
+            return;
+        }
+        self.push(node, lo, hi);
+        let mid = lo + (hi - lo) / 2;
+        match index < mid {
+            true => self.set_node(2 * node, lo, mid, index, value),
+            false => self.set_node(2 * node + 1, mid, hi, index, value),
+        }
+        self.pull(node);
+    }
+
+    // Applies an action to a whole node, and remembers it for the children
+    fn apply(&mut self, node: usize, len: usize, action: &A) {
+        self.summary[node] = action.apply(&self.summary[node], len);
+        if len > 1 {
+            self.pending[node] = Some(match &self.pending[node] {
+                Some(earlier) => action.compose(earlier),
+                None => action.clone(),
+            });
+        }
+    }
+
+    // Hands a node's pending action on to its children
+    fn push(&mut self, node: usize, lo: usize, hi: usize) {
+        if let Some(action) = self.pending[node].take() {
+            let mid = lo + (hi - lo) / 2;
+            self.apply(2 * node, mid - lo, &action);
+            self.apply(2 * node + 1, hi - mid, &action);
+        }
+    }
+
+    fn pull(&mut self, node: usize) {
+        self.summary[node] = M::combine(&self.summary[2 * node], &self.summary[2 * node + 1]);
+    }
+}
@@ -0,0 +1,115 @@
+// Property tests against a plain Vec: every pairing of monoid and action the crate has, run
+// through random updates, point sets and queries, has to agree with doing each one value by value
+use proptest::prelude::*;
+use proptest::test_runner::TestCaseError;
+
+use super::SegmentTree;
+use crate::{Action, Add, Assign, Max, Min, Monoid, Sum};
+
+#[derive(Debug, Clone)]
+enum Op {
+    Update(usize, usize, i64),
+    Set(usize, i64),
+    Query(usize, usize),
+}
+
+fn values_and_ops() -> impl Strategy<Value = (Vec<i64>, Vec<Op>)> {
+    prop::collection::vec(-1000_i64..1000, 1..80).prop_flat_map(|values| {
+        let len = values.len();
+        let range = (0..=len, 0..=len).prop_map(|(a, b)| (a.min(b), a.max(b)));
+        let op = prop_oneof![
+            (range.clone(), -100_i64..100).prop_map(|((a, b), x)| Op::Update(a, b, x)),
+            (0..len, -1000_i64..1000).prop_map(|(i, x)| Op::Set(i, x)),
+            range.prop_map(|(a, b)| Op::Query(a, b)),
+        ];
+        (Just(values), prop::collection::vec(op, 0..100))
+    })
+}
+
+// Runs the ops through a tree and a Vec, action(x) builds the tree's action and change(v, x) does
+// the same thing to a single value
+fn check<M, A>(
+    mut values: Vec<i64>,
+    ops: Vec<Op>,
+    action: impl Fn(i64) -> A,
+    change: impl Fn(&mut i64, i64),
+) -> Result<(), TestCaseError>
+where
+    M: Monoid<Value = i64>,
+    A: Action<M>,
+{
+    let mut tree = SegmentTree::<M, A>::new(&values);
+    let brute = |values: &[i64]| {
+        values
+            .iter()
+            .fold(M::identity(), |acc, v| M::combine(&acc, v))
+    };
+    for op in ops {
+        match op {
+            Op::Update(start, end, x) => {
+                tree.update(start..end, action(x));
+                values[start..end].iter_mut().for_each(|v| change(v, x));
+            }
+            Op::Set(i, x) => {
+                tree.set(i, x);
+                values[i] = x;
+            }
+            Op::Query(start, end) => {
+                prop_assert_eq!(tree.query(start..end), brute(&values[start..end]));
+            }
+        }
+    }
+    for (i, &value) in values.iter().enumerate() {
+        prop_assert_eq!(tree.get(i), value);
+    }
+    prop_assert_eq!(tree.query(..), brute(&values));
+    Ok(())
+}
+
+fn add(v: &mut i64, x: i64) {
+    *v += x;
+}
+
+fn assign(v: &mut i64, x: i64) {
+    *v = x;
+}
+
+proptest! {
+    #[test]
+    fn sum_with_add((values, ops) in values_and_ops()) {
+        check::<Sum, _>(values, ops, Add, add)?;
+    }
+
+    #[test]
+    fn sum_with_assign((values, ops) in values_and_ops()) {
+        check::<Sum, _>(values, ops, Assign, assign)?;
+    }
+
+    #[test]
+    fn min_with_add((values, ops) in values_and_ops()) {
+        check::<Min, _>(values, ops, Add, add)?;
+    }
+
+    #[test]
+    fn min_with_assign((values, ops) in values_and_ops()) {
+        check::<Min, _>(values, ops, Assign, assign)?;
+    }
+
+    #[test]
+    fn max_with_add((values, ops) in values_and_ops()) {
+        check::<Max, _>(values, ops, Add, add)?;
+    }
+}
+
+#[test]
+fn empty_trees_and_ranges() {
+    let mut tree = SegmentTree::<Min, Add>::new(&[]);
+    assert!(tree.is_empty());
+    assert_eq!(tree.query(..), i64::MAX);
+    tree.update(.., Add(3));
+
+    let mut tree = SegmentTree::<Sum, Add>::new(&[1, 2, 3]);
+    assert_eq!(tree.query(1..1), 0);
+    tree.update(2..2, Add(10));
+    assert_eq!(tree.query(..), 6);
+}
@@ -0,0 +1,197 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::{error, fmt};
+
+use crate::IntervalTree;
+
+// A stretch of one chromosome, 0-based and half-open like the tree's intervals
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Region {
+    pub chrom: String,
+    pub start: u64,
+    pub end: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RegionError {
+    // No chromosome before the colon
+    MissingChrom,
+    // A start or end that isn't a number
+    BadPosition(String),
+    // Positions count from 1, and start can't come after end
+    BadRange(u64, u64),
+}
+
+impl fmt::Display for RegionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RegionError::MissingChrom => write!(f, "region has no chromosome"),
+            RegionError::BadPosition(pos) => write!(f, "{pos:?} isn't a position"),
+            RegionError::BadRange(start, end) => write!(f, "{start}-{end} isn't a range"),
+        }
+    }
+}
+
+impl error::Error for RegionError {}
+
+// Parses samtools style regions, which count from 1 and include both ends:
+//  "chr2"               the whole chromosome
+//  "chr2:1000"          position 1000 to the end
+//  "chr2:1000-2000"     positions 1000 to 2000, 0-based [999, 2000)
+// Thousands separators ("1,000,000") are allowed, as in samtools.
+impl FromStr for Region {
+    type Err = RegionError;
+
+    fn from_str(s: &str) -> Result<Self, RegionError> {
+        let (chrom, range) = match s.rsplit_once(':') {
+            Some((chrom, range)) => (chrom, Some(range)),
+            None => (s, None),
+        };
+        if chrom.is_empty() {
+            return Err(RegionError::MissingChrom);
+        }
+
+        let position = |pos: &str| {
+            pos.replace(',', "")
+                .parse::<u64>()
+                .map_err(|_| RegionError::BadPosition(pos.into()))
+        };
+        let (start, end) = match range.map(|range| range.split_once('-')) {
+            None => (1, u64::MAX),
+            Some(None) => (position(range.unwrap())?, u64::MAX),
+            Some(Some((start, end))) => (position(start)?, position(end)?),
+        };
+        if start == 0 || start > end {
+            return Err(RegionError::BadRange(start, end));
+        }
+
+        Ok(Region {
+            chrom: chrom.into(),
+            start: start - 1,
+            end,
+        })
+    }
+}
+
+impl fmt::Display for Region {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.end {
+            u64::MAX if self.start == 0 => write!(f, "{}", self.chrom),
+            u64::MAX => write!(f, "{}:{}", self.chrom, self.start + 1),
+            end => write!(f, "{}:{}-{}", self.chrom, self.start + 1, end),
+        }
+    }
+}
+
+// An IntervalTree per chromosome
+#[derive(Debug)]
+pub struct GenomeIntervals<V> {
+    chroms: HashMap<String, IntervalTree<V>>,
+    len: usize,
+}
+
+impl<V> GenomeIntervals<V> {
+    pub fn new() -> Self {
+        GenomeIntervals {
+            chroms: HashMap::new(),
+            len: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    // [start, end), 0-based
+    pub fn insert(&mut self, chrom: &str, start: u64, end: u64, value: V) {
+        match self.chroms.get_mut(chrom) {
+            Some(tree) => tree.insert(start, end, value),
+            None => {
+                let mut tree = IntervalTree::new();
+                tree.insert(start, end, value);
+                self.chroms.insert(chrom.into(), tree);
+            }
+        }
+        self.len += 1;
+    }
+
+    pub fn insert_region(&mut self, region: &Region, value: V) {
+        self.insert(&region.chrom, region.start, region.end, value);
+    }
+
+    // Intervals on the region's chromosome that overlap it, in start order
+    pub fn query_overlapping(&self, region: &Region) -> impl Iterator<Item = (u64, u64, &V)> {
+        self.chroms
+            .get(&region.chrom)
+            .map(|tree| tree.query_overlapping(region.start, region.end))
+            .into_iter()
+            .flatten()
+    }
+
+    // Whether any interval covers a 0-based position
+    pub fn contains(&self, chrom: &str, pos: u64) -> bool {
+        self.chroms
+            .get(chrom)
+            .is_some_and(|tree| tree.overlaps(pos, pos + 1))
+    }
+
+    pub fn chrom(&self, chrom: &str) -> Option<&IntervalTree<V>> {
+        self.chroms.get(chrom)
+    }
+}
+
+impl<V> Default for GenomeIntervals<V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod testing {
+    use super::*;
+
+    #[test]
+    fn parses_regions() {
+        let region: Region = "chr2:1,000-2000".parse().unwrap();
+        assert_eq!(
+            (region.chrom.as_str(), region.start, region.end),
+            ("chr2", 999, 2000)
+        );
+        assert_eq!(region.to_string(), "chr2:1000-2000");
+
+        assert_eq!("X".parse::<Region>().unwrap().to_string(), "X");
+        assert_eq!("X:5".parse::<Region>().unwrap().to_string(), "X:5");
+        assert_eq!(":5-6".parse::<Region>(), Err(RegionError::MissingChrom));
+        assert_eq!("1:0-6".parse::<Region>(), Err(RegionError::BadRange(0, 6)));
+        assert_eq!("1:7-6".parse::<Region>(), Err(RegionError::BadRange(7, 6)));
+        assert_eq!(
+            "1:a-6".parse::<Region>(),
+            Err(RegionError::BadPosition("a".into()))
+        );
+    }
+
+    #[test]
+    fn queries_by_chromosome() {
+        let mut genes = GenomeIntervals::new();
+        genes.insert("1", 11_873, 14_409, "DDX11L1");
+        genes.insert("1", 14_403, 29_570, "WASH7P");
+        genes.insert("17", 43_044_294, 43_125_483, "BRCA1");
+        assert_eq!(genes.len(), 3);
+
+        let hits: Vec<_> = genes
+            .query_overlapping(&"1:14000-15000".parse().unwrap())
+            .map(|(_, _, &name)| name)
+            .collect();
+        assert_eq!(hits, ["DDX11L1", "WASH7P"]);
+        assert_eq!(genes.query_overlapping(&"2".parse().unwrap()).count(), 0);
+
+        assert!(genes.contains("17", 43_044_294));
+        assert!(!genes.contains("17", 43_125_483));
+        assert!(!genes.contains("X", 0));
+        assert_eq!(genes.chrom("1").map(IntervalTree::len), Some(2));
+    }
+}
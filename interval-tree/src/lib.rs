@@ -0,0 +1,5 @@
+mod genome;
+mod tree;
+
+pub use genome::{GenomeIntervals, Region, RegionError};
+pub use tree::{IntervalTree, Overlapping};
@@ -0,0 +1,367 @@
+use std::cmp::Ordering;
+use std::fmt;
+use std::iter::FusedIterator;
+
+// Intervals on one sequence, answering "which intervals overlap this range" without a scan
+// It's an AVL tree (see bst's AvlMap) ordered by start, where every node also remembers the
+// biggest end anywhere in its subtree:
+//
+//                [15, 20) max 40
+//               /               \
+//     [5, 10) max 12      [30, 40) max 40
+//          \
+//        [8, 12) max 12
+//
+// A query for [s, e) can skip any subtree whose max end is <= s (everything in it ends before
+// the range starts), and the right subtree of any node starting at or after e (everything there
+// starts after the range ends). What's left is the path down to the hits plus the hits
+// themselves, O(log n) per interval reported at worst and much closer to O(log n + k) when the
+// hits are next to each other in start order, which is the usual case for genomic regions.
+//
+// Intervals are half-open, [start, end) contains start but not end, and an interval with
+// start == end is empty and overlaps nothing. That goes for queries too, [5, 5) finds nothing
+// even inside [3, 10).
+pub struct IntervalTree<V> {
+    root: Link<V>,
+    len: usize,
+}
+
+type Link<V> = Option<Box<Node<V>>>;
+
+struct Node<V> {
+    start: u64,
+    end: u64,
+    value: V,
+    // the biggest end in the subtree rooted here
+    max_end: u64,
+    height: u8,
+    left: Link<V>,
+    right: Link<V>,
+}
+
+// The query walk with an explicit stack, like AvlMapIter
+// The stack holds subtrees still to look at. Hits come out in start order because a node's left
+// subtree is pushed last (so looked at first) and the node itself goes on the stack again as a
+// "visit me" marker between its two subtrees.
+pub struct Overlapping<'a, V> {
+    stack: Vec<Visit<'a, V>>,
+    start: u64,
+    end: u64,
+}
+
+enum Visit<'a, V> {
+    Subtree(&'a Node<V>),
+    Node(&'a Node<V>),
+}
+
+impl<V> IntervalTree<V> {
+    pub fn new() -> Self {
+        IntervalTree { root: None, len: 0 }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    // Overlapping and duplicate intervals are all kept
+    // Panics if start > end.
+    pub fn insert(&mut self, start: u64, end: u64, value: V) {
+        assert!(start <= end, "interval starts after it ends");
+        insert(&mut self.root, start, end, value);
+        self.len += 1;
+    }
+
+    // Every interval sharing at least one position with [start, end), in start order, as
+    // (start, end, value). An empty (or backwards) range has no positions, so it finds nothing.
+    pub fn query_overlapping(&self, start: u64, end: u64) -> Overlapping<'_, V> {
+        Overlapping {
+            stack: self
+                .root
+                .as_deref()
+                .filter(|_| start < end)
+                .map(Visit::Subtree)
+                .into_iter()
+                .collect(),
+            start,
+            end,
+        }
+    }
+
+    pub fn overlaps(&self, start: u64, end: u64) -> bool {
+        self.query_overlapping(start, end).next().is_some()
+    }
+
+    // Every interval, in start order (empty ones overlap nothing, so they're left out)
+    pub fn iter(&self) -> Overlapping<'_, V> {
+        self.query_overlapping(0, u64::MAX)
+    }
+
+    // Checks the order, balance and max ends of every node, for tests
+    pub fn check_invariants(&self) -> Result<(), String> {
+        let mut count = 0;
+        check(&self.root, 0, u64::MAX, &mut count)?;
+        if count != self.len {
+            return Err(format!("len is {} but there are {count} nodes", self.len));
+        }
+        Ok(())
+    }
+}
+
+impl<V> Default for IntervalTree<V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<V: fmt::Debug> fmt::Debug for IntervalTree<V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list()
+            .entries(self.iter().map(|(start, end, value)| (start..end, value)))
+            .finish()
+    }
+}
+
+impl<'a, V> Iterator for Overlapping<'a, V> {
+    type Item = (u64, u64, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some(visit) = self.stack.pop() {
+            match visit {
+                Visit::Node(node) => {
+                    if node.start < node.end && node.end > self.start {
+                        return Some((node.start, node.end, &node.value));
+                    }
+                }
+                Visit::Subtree(node) => {
+                    // nothing in here ends after the range starts
+                    if node.max_end <= self.start {
+                        continue;
+                    }
+                    // this node and everything right of it start too late
+                    if node.start < self.end {
+                        if let Some(right) = &node.right {
+                            self.stack.push(Visit::Subtree(right));
+                        }
+                        self.stack.push(Visit::Node(node));
+                    }
+                    if let Some(left) = &node.left {
+                        self.stack.push(Visit::Subtree(left));
+                    }
+                }
+            }
+        }
+        None
+    }
+}
+
+// Once the stack is empty it stays empty
+impl<V> FusedIterator for Overlapping<'_, V> {}
+
+fn height<V>(link: &Link<V>) -> u8 {
+    link.as_ref().map_or(0, |node| node.height)
+}
+
+fn max_end<V>(link: &Link<V>) -> u64 {
+    link.as_ref().map_or(0, |node| node.max_end)
+}
+
+// Height and max end both only depend on the node and its children, so they're fixed up
+// together whenever a node's children change
+fn update<V>(node: &mut Node<V>) {
+    node.height = 1 + height(&node.left).max(height(&node.right));
+    node.max_end = node.end.max(max_end(&node.left)).max(max_end(&node.right));
+}
+
+fn balance<V>(node: &Node<V>) -> i16 {
+    i16::from(height(&node.left)) - i16::from(height(&node.right))
+}
+
+fn rotate_right<V>(link: &mut Link<V>) {
+    let mut node = link.take().expect("rotating a non-empty subtree");
+    let mut left = node.left.take().expect("rotate_right needs a left child");
+    node.left = left.right.take();
+    update(&mut node);
+    left.right = Some(node);
+    update(&mut left);
+    *link = Some(left);
+}
+
+fn rotate_left<V>(link: &mut Link<V>) {
+    let mut node = link.take().expect("rotating a non-empty subtree");
+    let mut right = node.right.take().expect("rotate_left needs a right child");
+    node.right = right.left.take();
+    update(&mut node);
+    right.left = Some(node);
+    update(&mut right);
+    *link = Some(right);
+}
+
+// Same as AvlMap's rebalance, the rotations keep max_end right through update
+fn rebalance<V>(link: &mut Link<V>) {
+    let Some(node) = link.as_mut() else {
+        return;
+    };
+    update(node);
+    match balance(node) {
+        2 => {
+            if balance(node.left.as_ref().expect("left is taller")) < 0 {
+                rotate_left(&mut node.left);
+            }
+            rotate_right(link);
+        }
+        -2 => {
+            if balance(node.right.as_ref().expect("right is taller")) > 0 {
+                rotate_right(&mut node.right);
+            }
+            rotate_left(link);
+        }
+        _ => {}
+    }
+}
+
+fn insert<V>(link: &mut Link<V>, start: u64, end: u64, value: V) {
+    let Some(node) = link else {
+        *link = Some(Box::new(Node {
+            start,
+            end,
+            value,
+            max_end: end,
+            height: 1,
+            left: None,
+            right: None,
+        }));
+        return;
+    };
+    // equal starts go right, so intervals with the same start come out in insertion order
+    match start.cmp(&node.start) {
+        Ordering::Less => insert(&mut node.left, start, end, value),
+        Ordering::Equal | Ordering::Greater => insert(&mut node.right, start, end, value),
+    }
+    rebalance(link);
+}
+
+// Every start in the subtree has to be between lo and hi (inclusive, equal starts can end up on
+// either side after a rotation). Returns the subtree's (height, max end)
+fn check<V>(link: &Link<V>, lo: u64, hi: u64, count: &mut usize) -> Result<(u8, u64), String> {
+    let Some(node) = link else {
+        return Ok((0, 0));
+    };
+    *count += 1;
+    if node.start < lo || node.start > hi {
+        return Err(format!("node {count} is out of start order"));
+    }
+    let (left_height, left_max) = check(&node.left, lo, node.start, count)?;
+    let (right_height, right_max) = check(&node.right, node.start, hi, count)?;
+    if left_height.abs_diff(right_height) > 1 {
+        return Err(format!(
+            "subtree heights {left_height} and {right_height} differ by more than 1"
+        ));
+    }
+    let max = node.end.max(left_max).max(right_max);
+    if node.height != 1 + left_height.max(right_height) || node.max_end != max {
+        return Err(format!("node {count} has a stale height or max end"));
+    }
+    Ok((node.height, max))
+}
+
+#[cfg(test)]
+mod testing {
+    use super::*;
+
+    fn query(tree: &IntervalTree<usize>, start: u64, end: u64) -> Vec<usize> {
+        tree.query_overlapping(start, end)
+            .map(|(_, _, &v)| v)
+            .collect()
+    }
+
+    #[test]
+    fn finds_overlaps() {
+        let mut tree = IntervalTree::new();
+        for (i, (start, end)) in [(15, 20), (5, 10), (30, 40), (8, 12), (18, 18)]
+            .into_iter()
+            .enumerate()
+        {
+            tree.insert(start, end, i);
+        }
+        tree.check_invariants().unwrap();
+
+        assert_eq!(query(&tree, 9, 16), [1, 3, 0]);
+        // half-open: [5, 10) and [10, 15) don't share a position
+        assert_eq!(query(&tree, 10, 15), [3]);
+        assert_eq!(query(&tree, 20, 30), Vec::<usize>::new());
+        // the empty interval [18, 18) overlaps nothing
+        assert_eq!(query(&tree, 17, 19), [0]);
+        assert_eq!(query(&tree, 0, 100), [1, 3, 0, 2]);
+        assert!(tree.overlaps(39, 1000));
+        assert!(!tree.overlaps(40, 1000));
+        assert_eq!(tree.len(), 5);
+
+        let mut small = IntervalTree::new();
+        small.insert(1, 2, 'a');
+        small.insert(0, 5, 'b');
+        assert_eq!(format!("{small:?}"), "[(0..5, 'b'), (1..2, 'a')]");
+    }
+
+    #[test]
+    fn agrees_with_scanning() {
+        let mut tree = IntervalTree::new();
+        let mut intervals = Vec::new();
+        let mut x: u64 = 17;
+        let mut next = |n: u64| {
+            x = x.wrapping_mul(6364136223846793005).wrapping_add(1);
+            (x >> 33) % n
+        };
+        for i in 0..5000 {
+            let start = next(100_000);
+            let end = start + next(500);
+            tree.insert(start, end, i);
+            intervals.push((start, end, i));
+        }
+        tree.check_invariants().unwrap();
+        assert_eq!(
+            tree.iter().count(),
+            intervals.iter().filter(|&&(s, e, _)| s < e).count()
+        );
+
+        for _ in 0..500 {
+            let start = next(100_000);
+            let end = start + next(2000);
+            let mut got = query(&tree, start, end);
+            got.sort_unstable();
+            let expected: Vec<_> = intervals
+                .iter()
+                .filter(|&&(s, e, _)| s < e && start < end && s < end && e > start)
+                .map(|&(_, _, i)| i)
+                .collect();
+            assert_eq!(got, expected);
+        }
+    }
+
+    #[test]
+    fn empty_queries_find_nothing() {
+        let mut tree = IntervalTree::new();
+        tree.insert(3, 10, 0);
+        tree.insert(5, 5, 1);
+
+        assert_eq!(query(&tree, 5, 5), Vec::<usize>::new());
+        assert_eq!(query(&tree, 3, 3), Vec::<usize>::new());
+        assert_eq!(query(&tree, 8, 4), Vec::<usize>::new());
+        assert!(!tree.overlaps(5, 5));
+        assert_eq!(query(&tree, 5, 6), [0]);
+    }
+
+    #[test]
+    fn stays_balanced_for_sorted_inserts() {
+        let mut tree = IntervalTree::new();
+        for i in 0..100_000 {
+            tree.insert(i, i + 10, ());
+        }
+        tree.check_invariants().unwrap();
+        assert!(height(&tree.root) <= 24);
+        assert_eq!(tree.query_overlapping(50_000, 50_001).count(), 10);
+    }
+}
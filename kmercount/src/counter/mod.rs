@@ -0,0 +1,247 @@
+use bloom_filter::{BloomError, CountingQuotientFilter};
+use heap::DaryHeap;
+use std::cmp::Reverse;
+
+use crate::{Kmers, MAX_K};
+
+// Distinct k-mers per canonical slot before the filter is doubled, the same limit the CQF's own
+// `new` sizes for. Past it clusters get long and every insert walks further.
+const MAX_LOAD_FACTOR: f64 = 0.9;
+const INITIAL_Q_BITS: u32 = 16;
+
+// Odd multipliers for the fingerprint mix, and their inverses mod 2^64
+const MIX_A: u64 = 0x9E37_79B9_7F4A_7C15;
+const MIX_B: u64 = 0xC2B2_AE3D_27D4_EB4F;
+const UNMIX_A: u64 = inverse(MIX_A);
+const UNMIX_B: u64 = inverse(MIX_B);
+
+// Counts canonical k-mers exactly in a counting quotient filter
+// A CQF stores fingerprints of q + r bits, and a k-mer is only 2k bits, so with q + r = 2k the
+// "fingerprint" can be the k-mer itself: nothing collides and iter hands back every k-mer with
+// its count. Packed k-mers are far from uniform though (poly-A alone would pile onto quotient 0),
+// so they're mixed first with a function that can be undone
+//
+//   x * A mod 2^2k,  x ^ x >> k,  x * B mod 2^2k
+//
+// Multiplying by an odd number is a bijection mod a power of two, and xoring in the top half
+// shifted down can be undone by doing it again, so every step runs backwards. This is how
+// Squeakr's exact mode works.
+//
+// The filter starts small and doubles when it gets full, moving a bit from the remainder to the
+// quotient so fingerprints stay 2k bits, and everything is reinserted.
+pub struct KmerCounter {
+    k: usize,
+    cqf: CountingQuotientFilter,
+    q_bits: u32,
+}
+
+impl KmerCounter {
+    pub fn new(k: usize) -> Result<Self, BloomError> {
+        if !(1..=MAX_K).contains(&k) {
+            return Err(BloomError::InvalidParams(format!(
+                "k must be 1 to {MAX_K}, not {k}"
+            )));
+        }
+        let bits = 2 * k as u32;
+        let q_bits = INITIAL_Q_BITS.min(bits - 1);
+        Ok(KmerCounter {
+            k,
+            cqf: CountingQuotientFilter::with_bits(q_bits, bits - q_bits)?,
+            q_bits,
+        })
+    }
+
+    pub fn k(&self) -> usize {
+        self.k
+    }
+
+    // Distinct canonical k-mers
+    pub fn len(&self) -> usize {
+        self.cqf.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cqf.is_empty()
+    }
+
+    // Every k-mer counted, repeats included
+    pub fn total(&self) -> u64 {
+        self.cqf.total()
+    }
+
+    // Counts every k-mer of seq
+    pub fn add_sequence(&mut self, seq: &[u8]) -> Result<(), BloomError> {
+        for kmer in Kmers::new(seq, self.k) {
+            self.insert(kmer)?;
+        }
+        Ok(())
+    }
+
+    // Counts one packed canonical k-mer
+    pub fn insert(&mut self, kmer: u64) -> Result<(), BloomError> {
+        let bits = 2 * self.k as u32;
+        if self.cqf.len() as f64 >= MAX_LOAD_FACTOR * (1u64 << self.q_bits) as f64
+            && self.q_bits + 1 < bits
+        {
+            self.grow()?;
+        }
+        self.cqf.insert_fingerprint(self.mix(kmer), 1)
+    }
+
+    pub fn count(&self, kmer: u64) -> u64 {
+        self.cqf.count_fingerprint(self.mix(kmer))
+    }
+
+    // (k-mer, count) pairs, in no useful order
+    pub fn iter(&self) -> impl Iterator<Item = (u64, u64)> + '_ {
+        self.cqf
+            .iter()
+            .map(|(fingerprint, count)| (self.unmix(fingerprint), count))
+    }
+
+    // How many distinct k-mers were seen each number of times, by ascending count
+    // The same thing `jellyfish histo` prints: lots of k-mers seen once (mostly sequencing
+    // errors), then a peak around the sequencing depth.
+    pub fn histogram(&self) -> Vec<(u64, u64)> {
+        let mut counts: Vec<u64> = self.cqf.iter().map(|(_, count)| count).collect();
+        counts.sort_unstable();
+        let mut histogram: Vec<(u64, u64)> = Vec::new();
+        for count in counts {
+            match histogram.last_mut() {
+                Some((last, kmers)) if *last == count => *kmers += 1,
+                _ => histogram.push((count, 1)),
+            }
+        }
+        histogram
+    }
+
+    // The n most common k-mers, most common first, ties by k-mer
+    // A min-heap of the best n so far: anything beating its smallest replaces it.
+    pub fn top(&self, n: usize) -> Vec<(u64, u64)> {
+        if n == 0 {
+            return Vec::new();
+        }
+        let mut best = DaryHeap::<_, 4>::with_capacity(n);
+        for (kmer, count) in self.iter() {
+            let entry = Reverse((count, Reverse(kmer)));
+            if best.len() < n {
+                best.push(entry);
+            } else if best.peek().is_some_and(|smallest| entry < *smallest) {
+                best.push_pop(entry);
+            }
+        }
+        best.into_sorted_vec()
+            .into_iter()
+            .map(|Reverse((count, Reverse(kmer)))| (kmer, count))
+            .collect()
+    }
+
+    fn grow(&mut self) -> Result<(), BloomError> {
+        let bits = 2 * self.k as u32;
+        let q_bits = self.q_bits + 1;
+        let mut bigger = CountingQuotientFilter::with_bits(q_bits, bits - q_bits)?;
+        self.cqf
+            .iter()
+            .try_for_each(|(fingerprint, count)| bigger.insert_fingerprint(fingerprint, count))?;
+        self.cqf = bigger;
+        self.q_bits = q_bits;
+        Ok(())
+    }
+
+    fn mask(&self) -> u64 {
+        u64::MAX >> (64 - 2 * self.k)
+    }
+
+    fn mix(&self, kmer: u64) -> u64 {
+        let mut x = kmer.wrapping_mul(MIX_A) & self.mask();
+        x ^= x >> self.k;
+        x.wrapping_mul(MIX_B) & self.mask()
+    }
+
+    fn unmix(&self, fingerprint: u64) -> u64 {
+        let mut x = fingerprint.wrapping_mul(UNMIX_B) & self.mask();
+        x ^= x >> self.k;
+        x.wrapping_mul(UNMIX_A) & self.mask()
+    }
+}
+
+// Newton's method for the inverse of an odd number mod 2^64, each step doubles the correct bits
+// (an odd a is its own inverse mod 8, so 3 bits to start, 6 steps gets past 64)
+const fn inverse(a: u64) -> u64 {
+    let mut x = a;
+    let mut i = 0;
+    while i < 6 {
+        x = x.wrapping_mul(2u64.wrapping_sub(a.wrapping_mul(x)));
+        i += 1;
+    }
+    x
+}
+
+#[cfg(test)]
+mod testing {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    #[test]
+    fn mixing_round_trips() {
+        assert_eq!(MIX_A.wrapping_mul(UNMIX_A), 1);
+        assert_eq!(MIX_B.wrapping_mul(UNMIX_B), 1);
+        for k in [1, 2, 11, 31, 32] {
+            let counter = KmerCounter::new(k).unwrap();
+            let mut x: u64 = 1;
+            for _ in 0..1000 {
+                x = x.wrapping_mul(6364136223846793005).wrapping_add(1);
+                let kmer = x & counter.mask();
+                assert_eq!(counter.unmix(counter.mix(kmer)), kmer);
+            }
+        }
+        assert!(KmerCounter::new(0).is_err() && KmerCounter::new(33).is_err());
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn matches_a_hashmap() {
+        // random bases, the odd N and a repeat every so often, enough to make the filter grow
+        let mut x: u64 = 1;
+        let mut seq: Vec<u8> = (0..300_000)
+            .map(|_| {
+                x = x.wrapping_mul(6364136223846793005).wrapping_add(1);
+                match (x >> 33) % 64 {
+                    0 => b'N',
+                    r => b"ACGT"[r as usize % 4],
+                }
+            })
+            .collect();
+        for i in (0..seq.len() - 50).step_by(997) {
+            seq[i..i + 30].copy_from_slice(b"GATTACAGATTACAGATTACAGATTACAGA");
+        }
+
+        for k in [5, 21] {
+            let mut counter = KmerCounter::new(k).unwrap();
+            counter.add_sequence(&seq).unwrap();
+            let mut expected: HashMap<u64, u64> = HashMap::new();
+            for kmer in Kmers::new(&seq, k) {
+                *expected.entry(kmer).or_default() += 1;
+            }
+
+            assert_eq!(counter.len(), expected.len());
+            assert_eq!(counter.total(), expected.values().sum::<u64>());
+            let found: HashMap<u64, u64> = counter.iter().collect();
+            assert_eq!(found, expected);
+
+            let histogram = counter.histogram();
+            assert_eq!(
+                histogram.iter().map(|(_, kmers)| kmers).sum::<u64>(),
+                expected.len() as u64
+            );
+
+            let mut by_count: Vec<(u64, u64)> = expected.into_iter().collect();
+            by_count.sort_by_key(|&(kmer, count)| (Reverse(count), kmer));
+            by_count.truncate(5);
+            assert_eq!(counter.top(5), by_count);
+        }
+        let counter = KmerCounter::new(21).unwrap();
+        assert!(counter.top(3).is_empty());
+    }
+}
@@ -0,0 +1,112 @@
+// k-mers are packed two bits a base, first base highest, so a u64 holds up to 32 of them
+//   A = 00, C = 01, G = 10, T = 11        "GAT" = 10 00 11 = 35
+// With that order the complement of a base is 3 minus it.
+pub const MAX_K: usize = 32;
+
+fn encode_base(base: u8) -> Option<u64> {
+    match base {
+        b'A' | b'a' => Some(0),
+        b'C' | b'c' => Some(1),
+        b'G' | b'g' => Some(2),
+        b'T' | b't' => Some(3),
+        _ => None,
+    }
+}
+
+pub fn decode(code: u64, k: usize) -> String {
+    (0..k)
+        .rev()
+        .map(|i| b"ACGT"[(code >> (2 * i) & 3) as usize] as char)
+        .collect()
+}
+
+// The canonical k-mers of a sequence, left to right
+// A read can come from either strand, so a k-mer and its reverse complement are counted as one:
+// whichever packs to the smaller number. Both are rolled along together, a base at a time
+//
+//   forward:  shift left, new base in at the bottom          ACG -> CGT
+//   reverse:  shift right, new base's complement at the top  CGT -> ACG
+//
+// so each k-mer costs a few shifts instead of k. Anything that isn't ACGT (N mostly) starts
+// again after it, no k-mer spans one.
+pub struct Kmers<'a> {
+    seq: std::slice::Iter<'a, u8>,
+    k: usize,
+    mask: u64,
+    forward: u64,
+    reverse: u64,
+    // bases since the last non-ACGT, only counts up to k
+    filled: usize,
+}
+
+impl<'a> Kmers<'a> {
+    // k must be 1..=MAX_K
+    pub fn new(seq: &'a [u8], k: usize) -> Self {
+        assert!((1..=MAX_K).contains(&k), "k must be 1 to {MAX_K}, not {k}");
+        Kmers {
+            seq: seq.iter(),
+            k,
+            mask: u64::MAX >> (64 - 2 * k),
+            forward: 0,
+            reverse: 0,
+            filled: 0,
+        }
+    }
+}
+
+impl Iterator for Kmers<'_> {
+    type Item = u64;
+
+    fn next(&mut self) -> Option<u64> {
+        for &base in self.seq.by_ref() {
+            let Some(code) = encode_base(base) else {
+                self.filled = 0;
+                continue;
+            };
+            self.forward = (self.forward << 2 | code) & self.mask;
+            self.reverse = self.reverse >> 2 | (3 - code) << (2 * (self.k - 1));
+            self.filled = (self.filled + 1).min(self.k);
+            if self.filled == self.k {
+                return Some(self.forward.min(self.reverse));
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod testing {
+    use super::*;
+
+    fn reverse_complement(kmer: &str) -> String {
+        kmer.chars()
+            .rev()
+            .map(|c| match c {
+                'A' => 'T',
+                'C' => 'G',
+                'G' => 'C',
+                _ => 'A',
+            })
+            .collect()
+    }
+
+    #[test]
+    fn canonical_kmers_match_the_slow_way() {
+        let seq = "ACGTTGCANNGATTACAGATTACAtgcaTTTTTTTTTTTTTTTTTTTTTTTTTTTTTTTTTA";
+        for k in [1, 3, 5, 31, 32] {
+            let expected: Vec<String> = seq
+                .to_ascii_uppercase()
+                .split('N')
+                .flat_map(|part| {
+                    (0..(part.len() + 1).saturating_sub(k)).map(move |i| &part[i..i + k])
+                })
+                .map(|kmer| kmer.to_string().min(reverse_complement(kmer)))
+                .collect();
+            let found: Vec<String> = Kmers::new(seq.as_bytes(), k)
+                .map(|code| decode(code, k))
+                .collect();
+            assert_eq!(found, expected, "k = {k}");
+        }
+        assert_eq!(decode(35, 3), "GAT");
+    }
+}
@@ -0,0 +1,7 @@
+mod counter;
+mod kmer;
+mod seq;
+
+pub use counter::KmerCounter;
+pub use kmer::{decode, Kmers, MAX_K};
+pub use seq::{SeqReader, SeqRecord};
@@ -0,0 +1,131 @@
+// Counts canonical k-mers in FASTA or FASTQ files
+//
+//   kmercount reads.fq [more.fa]... [-k 21] [--top 10] [--histogram hist.tsv]
+//
+// Reads stream through a SeqReader into a KmerCounter (a counting quotient filter from
+// bloom_filter, used exactly), then the most common k-mers go to stdout as `KMER<TAB>COUNT` and
+// the histogram, `COUNT<TAB>KMERS` for how many distinct k-mers were seen that many times, to its
+// own file. "-" reads stdin. Errors and exit codes come from report, like lr's.
+
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use bloom_filter::BloomError;
+use clap::{ArgAction, Parser};
+use kmercount::{decode, KmerCounter, SeqReader, SeqRecord, MAX_K};
+use report::{Error, ErrorKind, Location, Reporter, Result, ResultExt};
+
+#[derive(Debug, Parser)]
+#[command(
+    name = "kmercount",
+    version,
+    about = "Count canonical k-mers in FASTA or FASTQ"
+)]
+struct Cli {
+    #[arg(required = true, help = "FASTA or FASTQ files, - for stdin")]
+    inputs: Vec<PathBuf>,
+    #[arg(short, default_value_t = 21, help = "k-mer length, up to 32")]
+    k: usize,
+    #[arg(
+        long,
+        default_value_t = 10,
+        help = "how many of the most common k-mers to print"
+    )]
+    top: usize,
+    #[arg(long, value_name = "PATH", help = "write the count histogram here")]
+    histogram: Option<PathBuf>,
+    #[arg(short, long, action = ArgAction::Count, help = "-v for info, -vv for debug")]
+    verbose: u8,
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    init_logging(cli.verbose);
+    Reporter::new("kmercount").exit(run(cli))
+}
+
+fn run(cli: Cli) -> Result<()> {
+    if !(1..=MAX_K).contains(&cli.k) {
+        return Err(Error::usage(format!(
+            "k must be 1 to {MAX_K}, not {}",
+            cli.k
+        )));
+    }
+    let mut counter = KmerCounter::new(cli.k).map_err(bloom_error)?;
+
+    for path in &cli.inputs {
+        log::info!("counting {}", path.display());
+        if path == Path::new("-") {
+            count_file(&mut counter, io::stdin().lock(), path)?;
+        } else {
+            let file = File::open(path).in_file(path)?;
+            count_file(&mut counter, BufReader::new(file), path)?;
+        }
+    }
+    log::info!("{} k-mers, {} distinct", counter.total(), counter.len());
+
+    if let Some(path) = &cli.histogram {
+        let mut out = BufWriter::new(File::create(path).in_file(path)?);
+        for (count, kmers) in counter.histogram() {
+            writeln!(out, "{count}\t{kmers}").in_file(path)?;
+        }
+        out.flush().in_file(path)?;
+    }
+
+    let mut out = io::stdout().lock();
+    for (kmer, count) in counter.top(cli.top) {
+        writeln!(out, "{}\t{count}", decode(kmer, cli.k))?;
+    }
+    Ok(())
+}
+
+fn count_file(counter: &mut KmerCounter, rdr: impl BufRead, path: &Path) -> Result<()> {
+    let mut reader = SeqReader::new(rdr);
+    let mut record = SeqRecord::default();
+    while reader
+        .read(&mut record)
+        .at(Location::file(path).line(reader.line()))?
+    {
+        counter.add_sequence(&record.seq).map_err(bloom_error)?;
+    }
+    Ok(())
+}
+
+// The counter only gives up when every possible k-mer is already in it, so none of these should
+// happen past the k check
+fn bloom_error(e: BloomError) -> Error {
+    let kind = match e {
+        BloomError::InvalidParams(_) => ErrorKind::Usage,
+        _ => ErrorKind::Internal,
+    };
+    Error::new(kind, e.to_string())
+}
+
+fn init_logging(verbose: u8) {
+    let level = match verbose {
+        0 => "warn",
+        1 => "info",
+        _ => "debug",
+    };
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or(level))
+        .format_timestamp(None)
+        .init();
+}
+
+#[cfg(test)]
+mod testing {
+    use clap::CommandFactory;
+
+    use super::*;
+
+    #[test]
+    fn cli_is_well_formed() {
+        Cli::command().debug_assert();
+        let cli = Cli::parse_from(["kmercount", "a.fq", "-", "-k", "31", "--top", "3"]);
+        assert_eq!(cli.inputs.len(), 2);
+        assert_eq!((cli.k, cli.top), (31, 3));
+        assert!(cli.histogram.is_none());
+    }
+}
@@ -0,0 +1,175 @@
+use std::io::{self, BufRead};
+
+// One read or contig, the name without its '>' or '@' and the bases with line breaks removed
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SeqRecord {
+    pub name: String,
+    pub seq: Vec<u8>,
+}
+
+// Streams records from FASTA or FASTQ, told apart by the first byte of the file
+//
+//   >chr1 some description        @read1
+//   ACGTACGTAC                    ACGTNACGTA
+//   GTACGT                        +
+//   >chr2                         IIIII#IIII
+//   ...                           @read2 ...
+//
+// FASTA sequences can be wrapped over any number of lines, FASTQ ones have to be on one (every
+// current sequencer writes them that way). Qualities are checked for length and then dropped.
+// Records are read into a SeqRecord the caller keeps, so a whole run needs one allocation.
+pub struct SeqReader<R> {
+    inner: R,
+    format: Option<Format>,
+    line: u64,
+    buf: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
+    Fasta,
+    Fastq,
+}
+
+impl<R: BufRead> SeqReader<R> {
+    pub fn new(inner: R) -> Self {
+        SeqReader {
+            inner,
+            format: None,
+            line: 0,
+            buf: String::new(),
+        }
+    }
+
+    // The 1-based number of the last line read, which is where an error was found
+    pub fn line(&self) -> u64 {
+        self.line.max(1)
+    }
+
+    // Reads the next record into record, false at the end of the input
+    // A malformed file is an InvalidData error, see line for where.
+    pub fn read(&mut self, record: &mut SeqRecord) -> io::Result<bool> {
+        record.name.clear();
+        record.seq.clear();
+        let format = match self.format {
+            Some(format) => format,
+            None => match self.peek()? {
+                None => return Ok(false),
+                Some(b'>') => *self.format.insert(Format::Fasta),
+                Some(b'@') => *self.format.insert(Format::Fastq),
+                Some(_) => return Err(self.invalid("expected '>' or '@', not FASTA or FASTQ")),
+            },
+        };
+        match format {
+            Format::Fasta => self.read_fasta(record),
+            Format::Fastq => self.read_fastq(record),
+        }
+    }
+
+    fn read_fasta(&mut self, record: &mut SeqRecord) -> io::Result<bool> {
+        if self.peek()?.is_none() || !self.next_line()? {
+            return Ok(false);
+        }
+        let Some(name) = self.buf.strip_prefix('>') else {
+            return Err(self.invalid("expected a '>' header"));
+        };
+        record.name.push_str(name);
+        // sequence lines until the next header
+        while !matches!(self.peek()?, None | Some(b'>')) {
+            self.next_line()?;
+            record.seq.extend_from_slice(self.buf.as_bytes());
+        }
+        Ok(true)
+    }
+
+    fn read_fastq(&mut self, record: &mut SeqRecord) -> io::Result<bool> {
+        if self.peek()?.is_none() || !self.next_line()? {
+            return Ok(false);
+        }
+        let Some(name) = self.buf.strip_prefix('@') else {
+            return Err(self.invalid("expected an '@' header"));
+        };
+        record.name.push_str(name);
+        if !self.next_line()? {
+            return Err(self.invalid("missing the sequence"));
+        }
+        record.seq.extend_from_slice(self.buf.as_bytes());
+        if !self.next_line()? || !self.buf.starts_with('+') {
+            return Err(self.invalid("expected a '+' line"));
+        }
+        if !self.next_line()? || self.buf.len() != record.seq.len() {
+            return Err(self.invalid("quality isn't the same length as the sequence"));
+        }
+        Ok(true)
+    }
+
+    // The next byte without consuming it, skipping blank lines
+    fn peek(&mut self) -> io::Result<Option<u8>> {
+        loop {
+            match self.inner.fill_buf()?.first() {
+                Some(b'\n') => {
+                    self.inner.consume(1);
+                    self.line += 1;
+                }
+                Some(b'\r') => self.inner.consume(1),
+                byte => return Ok(byte.copied()),
+            }
+        }
+    }
+
+    fn next_line(&mut self) -> io::Result<bool> {
+        self.buf.clear();
+        if self.inner.read_line(&mut self.buf)? == 0 {
+            return Ok(false);
+        }
+        self.line += 1;
+        let len = self.buf.trim_end_matches(['\n', '\r']).len();
+        self.buf.truncate(len);
+        Ok(true)
+    }
+
+    fn invalid(&self, message: &str) -> io::Error {
+        io::Error::new(io::ErrorKind::InvalidData, message)
+    }
+}
+
+#[cfg(test)]
+mod testing {
+    use super::*;
+
+    fn read_all(text: &str) -> Result<Vec<SeqRecord>, (io::Error, u64)> {
+        let mut reader = SeqReader::new(text.as_bytes());
+        let mut records = Vec::new();
+        let mut record = SeqRecord::default();
+        while reader.read(&mut record).map_err(|e| (e, reader.line()))? {
+            records.push(record.clone());
+        }
+        Ok(records)
+    }
+
+    #[test]
+    fn fasta_and_fastq() {
+        let fasta = read_all(">chr1 first\nACGT\nAC\n\n>chr2\r\nGG\r\n").unwrap();
+        assert_eq!(fasta.len(), 2);
+        assert_eq!(fasta[0].name, "chr1 first");
+        assert_eq!(fasta[0].seq, b"ACGTAC");
+        assert_eq!(fasta[1].seq, b"GG");
+
+        let fastq = read_all("@r1\nACGTN\n+\nIIII#\n@r2\nTT\n+r2\nII\n").unwrap();
+        assert_eq!(fastq.len(), 2);
+        assert_eq!(fastq[0].seq, b"ACGTN");
+        assert_eq!(fastq[1].name, "r2");
+
+        assert!(read_all("").unwrap().is_empty());
+    }
+
+    #[test]
+    fn malformed_input() {
+        let (err, line) = read_all("ACGT\n").unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(line, 1);
+        let (_, line) = read_all("@r1\nAC\n+\nII\n\n@r2\nACGT\n+\nIII\n").unwrap_err();
+        assert_eq!(line, 9);
+        assert!(read_all("@r1\nACGT\n").is_err());
+    }
+}
@@ -0,0 +1,220 @@
+use std::io::BufRead;
+
+use crate::ChainError;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Strand {
+    Forward,
+    Reverse,
+}
+
+// One alignment from a UCSC chain file, the format liftOver's hg19ToHg38.over.chain and friends
+// come in
+//
+//   chain 4900 chr1 249250621 + 10000 10300 chr1 248956422 + 10500 10810 1
+//   100 0 10          aligned 100, then a gap of 0 on the source and 10 on the target
+//   200               the last block has no gaps after it
+//   (blank line)
+//
+// The header is score, then name, size, strand, start and end on each side, then an id. UCSC
+// calls the sides t and q; here they're the source build positions are lifted from and the target
+// build they're lifted to. Coordinates are 0-based and half-open, and when the target strand is
+// '-' the target's are counted from the end of the reverse strand.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Chain {
+    pub score: u64,
+    pub source: String,
+    pub source_size: u64,
+    pub target: String,
+    pub target_size: u64,
+    pub target_strand: Strand,
+    pub blocks: Vec<Block>,
+}
+
+// An ungapped stretch where source [source_start, source_start + len) lines up with target
+// [target_start, target_start + len), the target on the chain's target strand
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Block {
+    pub source_start: u64,
+    pub target_start: u64,
+    pub len: u64,
+}
+
+// Reads chains one at a time, stopping after the first error
+// Comment lines ('#') and blank lines between chains are skipped.
+pub struct ChainReader<R> {
+    inner: R,
+    line: u64,
+    buf: String,
+    done: bool,
+}
+
+impl<R: BufRead> ChainReader<R> {
+    pub fn new(inner: R) -> Self {
+        ChainReader {
+            inner,
+            line: 0,
+            buf: String::new(),
+            done: false,
+        }
+    }
+
+    fn read_chain(&mut self) -> Result<Option<Chain>, ChainError> {
+        // the header, past any blank lines and comments
+        loop {
+            if !self.next_line()? {
+                return Ok(None);
+            }
+            if !self.buf.is_empty() && !self.buf.starts_with('#') {
+                break;
+            }
+        }
+        let header: Vec<&str> = self.buf.split_whitespace().collect();
+        let ["chain", score, source, source_size, "+", source_start, source_end, target, target_size, strand, target_start, target_end, ..] =
+            header[..]
+        else {
+            return Err(self.error("expected a chain header"));
+        };
+        let target_strand = match strand {
+            "+" => Strand::Forward,
+            "-" => Strand::Reverse,
+            _ => return Err(self.error(&format!("{strand:?} isn't a strand"))),
+        };
+        let mut chain = Chain {
+            score: self.number(score)?,
+            source: source.to_string(),
+            source_size: self.number(source_size)?,
+            target: target.to_string(),
+            target_size: self.number(target_size)?,
+            target_strand,
+            blocks: Vec::new(),
+        };
+        let source_range = (self.number(source_start)?, self.number(source_end)?);
+        let target_range = (self.number(target_start)?, self.number(target_end)?);
+
+        // blocks, up to one with no gaps after it
+        let (mut source_pos, mut target_pos) = (source_range.0, target_range.0);
+        loop {
+            if !self.next_line()? {
+                return Err(self.error("chain ends without a last block"));
+            }
+            let fields: Vec<&str> = self.buf.split_whitespace().collect();
+            let (len, gaps) = match fields[..] {
+                [len] => (self.number(len)?, None),
+                [len, source_gap, target_gap] => (
+                    self.number(len)?,
+                    Some((self.number(source_gap)?, self.number(target_gap)?)),
+                ),
+                _ => return Err(self.error("expected `size dt dq` or a last `size`")),
+            };
+            chain.blocks.push(Block {
+                source_start: source_pos,
+                target_start: target_pos,
+                len,
+            });
+            source_pos += len;
+            target_pos += len;
+            let Some((source_gap, target_gap)) = gaps else {
+                break;
+            };
+            source_pos += source_gap;
+            target_pos += target_gap;
+        }
+
+        if (source_pos, target_pos) != (source_range.1, target_range.1)
+            || source_pos > chain.source_size
+            || target_pos > chain.target_size
+        {
+            return Err(self.error("blocks don't add up to the header's ranges"));
+        }
+        Ok(Some(chain))
+    }
+
+    fn next_line(&mut self) -> Result<bool, ChainError> {
+        self.buf.clear();
+        if self.inner.read_line(&mut self.buf)? == 0 {
+            return Ok(false);
+        }
+        self.line += 1;
+        let len = self.buf.trim_end().len();
+        self.buf.truncate(len);
+        Ok(true)
+    }
+
+    fn number(&self, field: &str) -> Result<u64, ChainError> {
+        field
+            .parse()
+            .map_err(|_| self.error(&format!("{field:?} isn't a number")))
+    }
+
+    fn error(&self, message: &str) -> ChainError {
+        ChainError::Parse {
+            line: self.line.max(1),
+            message: message.into(),
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for ChainReader<R> {
+    type Item = Result<Chain, ChainError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let next = self.read_chain().transpose();
+        self.done = !matches!(next, Some(Ok(_)));
+        next
+    }
+}
+
+#[cfg(test)]
+mod testing {
+    use super::*;
+
+    #[test]
+    fn reads_chains_and_checks_them() {
+        let text = "# a comment\n\
+            chain 4900 chr1 1000 + 100 420 chr1 1100 + 150 460 1\n\
+            100 0 10\n\
+            200 20 0\n\
+            0\n\
+            \n\
+            chain 50 chr2 500 + 0 50 chr5 900 - 800 850 2\n\
+            50\n";
+        let chains: Vec<Chain> = ChainReader::new(text.as_bytes())
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(chains.len(), 2);
+        assert_eq!(chains[0].score, 4900);
+        assert_eq!(
+            chains[0].blocks[..2],
+            [
+                Block {
+                    source_start: 100,
+                    target_start: 150,
+                    len: 100
+                },
+                Block {
+                    source_start: 200,
+                    target_start: 260,
+                    len: 200
+                },
+            ]
+        );
+        assert_eq!(chains[1].target, "chr5");
+        assert_eq!(chains[1].target_strand, Strand::Reverse);
+
+        let short = text.replace("200 20 0", "199 20 0");
+        let err = ChainReader::new(short.as_bytes())
+            .find_map(Result::err)
+            .unwrap();
+        assert_eq!(err.line(), Some(5));
+
+        let err = ChainReader::new("chain 1 chr1 x + 0 1 chr1 1 + 0 1\n1\n".as_bytes())
+            .next()
+            .unwrap()
+            .unwrap_err();
+        assert_eq!(err.line(), Some(1));
+    }
+}
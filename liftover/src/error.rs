@@ -0,0 +1,42 @@
+use std::{error, fmt, io};
+
+#[derive(Debug)]
+pub enum ChainError {
+    // Reading the chain file failed
+    Io(io::Error),
+    // A header or block line that doesn't parse or doesn't add up, at a 1-based line
+    Parse { line: u64, message: String },
+}
+
+impl ChainError {
+    pub fn line(&self) -> Option<u64> {
+        match self {
+            ChainError::Io(_) => None,
+            ChainError::Parse { line, .. } => Some(*line),
+        }
+    }
+}
+
+impl fmt::Display for ChainError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ChainError::Io(e) => write!(f, "{e}"),
+            ChainError::Parse { line, message } => write!(f, "bad chain on line {line}: {message}"),
+        }
+    }
+}
+
+impl error::Error for ChainError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            ChainError::Io(e) => Some(e),
+            ChainError::Parse { .. } => None,
+        }
+    }
+}
+
+impl From<io::Error> for ChainError {
+    fn from(e: io::Error) -> Self {
+        ChainError::Io(e)
+    }
+}
@@ -0,0 +1,7 @@
+mod chain;
+mod error;
+mod lift;
+
+pub use chain::{Block, Chain, ChainReader, Strand};
+pub use error::ChainError;
+pub use lift::LiftOver;
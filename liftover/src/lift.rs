@@ -0,0 +1,144 @@
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+
+use interval_tree::GenomeIntervals;
+
+use crate::{Chain, ChainError, ChainReader, Strand};
+
+// Which chain a block came from, and where its target side starts
+struct Lift {
+    chain: usize,
+    target_start: u64,
+}
+
+// Where a chain goes, without its blocks (those are in the tree)
+struct Target {
+    name: String,
+    size: u64,
+    strand: Strand,
+    score: u64,
+}
+
+// Lifts positions from one build to another with a chain file
+// Every aligned block goes into an interval tree on its source chromosome, so a lookup is one walk
+// down that chromosome's tree to the blocks covering the position, then an offset into the block:
+//
+//   source  ....[=====block=====]....          pos = block start + 7
+//                      ^
+//   target  ......[=====block=====]..          lifted = target start + 7
+//
+// A position in a gap between blocks (deleted or rearranged in the target build) doesn't lift. If
+// several chains cover it, the best scoring one wins, which is what liftOver does for
+// -minMatch=1 on a single base.
+pub struct LiftOver {
+    blocks: GenomeIntervals<Lift>,
+    targets: Vec<Target>,
+}
+
+impl LiftOver {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, ChainError> {
+        Self::from_reader(BufReader::new(File::open(path)?))
+    }
+
+    // Chain files are usually distributed gzipped, they need unpacking first
+    pub fn from_reader(rdr: impl BufRead) -> Result<Self, ChainError> {
+        let mut liftover = LiftOver {
+            blocks: GenomeIntervals::new(),
+            targets: Vec::new(),
+        };
+        for chain in ChainReader::new(rdr) {
+            liftover.add_chain(chain?);
+        }
+        Ok(liftover)
+    }
+
+    pub fn add_chain(&mut self, chain: Chain) {
+        let idx = self.targets.len();
+        for block in &chain.blocks {
+            let lift = Lift {
+                chain: idx,
+                target_start: block.target_start,
+            };
+            let source_end = block.source_start + block.len;
+            self.blocks
+                .insert(&chain.source, block.source_start, source_end, lift);
+        }
+        self.targets.push(Target {
+            name: chain.target,
+            size: chain.target_size,
+            strand: chain.target_strand,
+            score: chain.score,
+        });
+    }
+
+    // How many chains have been added
+    pub fn len(&self) -> usize {
+        self.targets.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.targets.is_empty()
+    }
+
+    // Whether any chain starts from this chromosome, for guessing at "chr1" vs "1"
+    pub fn has_chrom(&self, chrom: &str) -> bool {
+        self.blocks.chrom(chrom).is_some()
+    }
+
+    // A 0-based source position's chromosome and 0-based position in the target build
+    // Positions on a reverse strand chain are turned back into forward strand ones.
+    pub fn lift(&self, chrom: &str, pos: u64) -> Option<(&str, u64)> {
+        let (block_start, _, lift) = self
+            .blocks
+            .chrom(chrom)?
+            .query_overlapping(pos, pos + 1)
+            .max_by_key(|(_, _, lift)| self.targets[lift.chain].score)?;
+        let target = &self.targets[lift.chain];
+        let offset = lift.target_start + (pos - block_start);
+        let pos = match target.strand {
+            Strand::Forward => offset,
+            Strand::Reverse => target.size - 1 - offset,
+        };
+        Some((&target.name, pos))
+    }
+}
+
+#[cfg(test)]
+mod testing {
+    use super::*;
+
+    const CHAINS: &str = "chain 1000 chr1 1000 + 100 420 chr1 1100 + 150 460 1\n\
+        100 0 10\n\
+        200 20 0\n\
+        0\n\
+        \n\
+        chain 50 chr2 500 + 0 50 chr5 900 - 800 850 2\n\
+        50\n\
+        \n\
+        chain 10 chr1 1000 + 390 400 chr9 100 + 0 10 3\n\
+        10\n";
+
+    #[test]
+    fn lifts_through_blocks_and_gaps() {
+        let liftover = LiftOver::from_reader(CHAINS.as_bytes()).unwrap();
+        assert_eq!(liftover.len(), 3);
+        assert!(liftover.has_chrom("chr1") && !liftover.has_chrom("1"));
+
+        // first block, [100, 200) -> [150, 250)
+        assert_eq!(liftover.lift("chr1", 100), Some(("chr1", 150)));
+        assert_eq!(liftover.lift("chr1", 199), Some(("chr1", 249)));
+        // second block, [200, 400) -> [260, 460), after the target's 10 base gap
+        assert_eq!(liftover.lift("chr1", 200), Some(("chr1", 260)));
+        // covered by the third chain too, the better scoring first one wins
+        assert_eq!(liftover.lift("chr1", 395), Some(("chr1", 455)));
+        // the source gap [400, 420) and outside any chain
+        assert_eq!(liftover.lift("chr1", 405), None);
+        assert_eq!(liftover.lift("chr1", 99), None);
+        assert_eq!(liftover.lift("chr3", 10), None);
+
+        // reverse strand: [0, 50) -> reverse [800, 850) -> forward [50, 100) backwards
+        assert_eq!(liftover.lift("chr2", 0), Some(("chr5", 99)));
+        assert_eq!(liftover.lift("chr2", 49), Some(("chr5", 50)));
+    }
+}
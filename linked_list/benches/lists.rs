@@ -0,0 +1,129 @@
+// Compares the pointer-linked LinkedList with the index-linked SlabList and the chunked
+// UnrolledList
+//  cargo bench --bench lists
+// Every LinkedList node is its own heap allocation, wherever the allocator put it, while SlabList
+// nodes sit side by side in one Vec and freed slots are recycled without the allocator.
+// Note: a SlabList slot is bigger than a LinkedList node (two links, a generation and the enum
+// tag against one pointer), so for small values walking it can still come out slower
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use linked_list::{LinkedList, SlabList, UnrolledList};
+
+const SIZES: [usize; 2] = [1_000, 100_000];
+
+fn build(c: &mut Criterion) {
+    let mut group = c.benchmark_group("push_back");
+    for n in SIZES {
+        group.bench_with_input(BenchmarkId::new("LinkedList", n), &n, |b, &n| {
+            b.iter(|| (0..n).collect::<LinkedList<usize>>())
+        });
+        group.bench_with_input(BenchmarkId::new("SlabList", n), &n, |b, &n| {
+            b.iter(|| (0..n).collect::<SlabList<usize>>())
+        });
+    }
+    group.finish();
+}
+
+fn traverse(c: &mut Criterion) {
+    let mut group = c.benchmark_group("sum");
+    for n in SIZES {
+        // Push onto both ends in turn and then remove half the values, so neither list is simply
+        // laid out in order: the LinkedList's nodes end up scattered between freed allocations
+        // and the SlabList's between free slots
+        let mut ll = LinkedList::new();
+        let mut slab = SlabList::new();
+        let mut removed = Vec::new();
+        for i in 0..2 * n {
+            let key = if i % 2 == 0 {
+                ll.push_back(i);
+                slab.push_back(i)
+            } else {
+                ll.push_front(i);
+                slab.push_front(i)
+            };
+            if i % 4 >= 2 {
+                removed.push(key);
+            }
+        }
+        ll.retain(|v| v % 4 < 2);
+        for key in removed {
+            slab.remove(key);
+        }
+
+        group.bench_with_input(BenchmarkId::new("LinkedList", n), &ll, |b, ll| {
+            b.iter(|| black_box(ll).iter().sum::<usize>())
+        });
+        group.bench_with_input(BenchmarkId::new("SlabList", n), &slab, |b, slab| {
+            b.iter(|| black_box(slab).iter().sum::<usize>())
+        });
+        // An UnrolledList's layout doesn't depend on its history: values always sit in chunks
+        let unrolled: UnrolledList<usize> = ll.iter().copied().collect();
+        group.bench_with_input(
+            BenchmarkId::new("UnrolledList", n),
+            &unrolled,
+            |b, unrolled| b.iter(|| black_box(unrolled).iter().sum::<usize>()),
+        );
+    }
+    group.finish();
+}
+
+// A queue workload: values are constantly pushed on one end and popped off the other
+// SlabList recycles its freed slots, LinkedList goes to the allocator every time
+fn churn(c: &mut Criterion) {
+    let mut group = c.benchmark_group("queue_churn");
+    let n = 10_000;
+    group.bench_function("LinkedList", |b| {
+        let mut ll: LinkedList<usize> = (0..n).collect();
+        b.iter(|| {
+            for i in 0..n {
+                ll.pop_front();
+                ll.push_back(i);
+            }
+        })
+    });
+    group.bench_function("SlabList", |b| {
+        let mut slab: SlabList<usize> = (0..n).collect();
+        b.iter(|| {
+            for i in 0..n {
+                slab.pop_front();
+                slab.push_back(i);
+            }
+        })
+    });
+    group.finish();
+}
+
+// Reaching the middle of the list: LinkedList steps through every node on the way, UnrolledList
+// skips a whole chunk per step
+fn middle(c: &mut Criterion) {
+    let mut group = c.benchmark_group("middle");
+    let n = 100_000;
+    let ll: LinkedList<usize> = (0..n).collect();
+    let unrolled: UnrolledList<usize> = (0..n).collect();
+    group.bench_function("LinkedList/get", |b| {
+        b.iter(|| black_box(&ll).get(n / 2).copied())
+    });
+    group.bench_function("UnrolledList/get", |b| {
+        b.iter(|| black_box(&unrolled).get(n / 2).copied())
+    });
+
+    // insert and remove again so every iteration starts from the same list
+    let mut ll = ll;
+    let mut unrolled = unrolled;
+    group.bench_function("LinkedList/insert_remove", |b| {
+        b.iter(|| {
+            let _ = ll.insert(n / 2, 0);
+            ll.remove(n / 2)
+        })
+    });
+    group.bench_function("UnrolledList/insert_remove", |b| {
+        b.iter(|| {
+            let _ = unrolled.insert(n / 2, 0);
+            unrolled.remove(n / 2)
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, build, traverse, churn, middle);
+criterion_main!(benches);
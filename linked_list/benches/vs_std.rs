@@ -0,0 +1,146 @@
+// Compares this crate's lists with std::collections::LinkedList and VecDeque
+//  cargo bench --bench vs_std
+// std's LinkedList is the same design as our DoublyLinkedList, so the two should be close.
+// VecDeque is the one to beat: it's a ring buffer in a single allocation, so it's what you should
+// reach for unless you really need what only a linked list can do (stable node addresses, O(1)
+// splicing, editing at a cursor).
+
+use std::collections::{LinkedList as StdLinkedList, VecDeque};
+
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion};
+use linked_list::{DoublyLinkedList, LinkedList, UnrolledList};
+
+const SIZES: [usize; 2] = [1_000, 100_000];
+
+// Fill up at the back and empty out at the front
+fn push_pop(c: &mut Criterion) {
+    let mut group = c.benchmark_group("push_pop");
+    for n in SIZES {
+        macro_rules! bench {
+            ($name:literal, $list:ty) => {
+                group.bench_with_input(BenchmarkId::new($name, n), &n, |b, &n| {
+                    b.iter(|| {
+                        let mut list = <$list>::new();
+                        for i in 0..n {
+                            list.push_back(i);
+                        }
+                        while let Some(value) = list.pop_front() {
+                            black_box(value);
+                        }
+                    })
+                });
+            };
+        }
+        bench!("LinkedList", LinkedList<usize>);
+        bench!("DoublyLinkedList", DoublyLinkedList<usize>);
+        bench!("UnrolledList", UnrolledList<usize>);
+        bench!("std::LinkedList", StdLinkedList<usize>);
+        bench!("VecDeque", VecDeque<usize>);
+    }
+    group.finish();
+}
+
+fn iterate(c: &mut Criterion) {
+    let mut group = c.benchmark_group("iterate");
+    for n in SIZES {
+        macro_rules! bench {
+            ($name:literal, $list:ty) => {
+                let list: $list = (0..n).collect();
+                group.bench_with_input(BenchmarkId::new($name, n), &list, |b, list| {
+                    b.iter(|| black_box(list).iter().sum::<usize>())
+                });
+            };
+        }
+        bench!("LinkedList", LinkedList<usize>);
+        bench!("DoublyLinkedList", DoublyLinkedList<usize>);
+        bench!("UnrolledList", UnrolledList<usize>);
+        bench!("std::LinkedList", StdLinkedList<usize>);
+        bench!("VecDeque", VecDeque<usize>);
+    }
+    group.finish();
+}
+
+// Removes half of the values, each from a pseudo random position
+// Every list has to walk to the position first, so this is O(n) per removal for the linked ones
+// and an O(n) shift for VecDeque; what differs is how fast each one gets there
+fn remove_random(c: &mut Criterion) {
+    let mut group = c.benchmark_group("remove_random");
+    let n = 10_000;
+    // the same positions for every list
+    let mut x: u64 = 1;
+    let positions: Vec<usize> = (0..n / 2)
+        .map(|removed| {
+            x = x
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            (x >> 33) as usize % (n - removed)
+        })
+        .collect();
+
+    group.bench_function("LinkedList", |b| {
+        b.iter_batched_ref(
+            || (0..n).collect::<LinkedList<usize>>(),
+            |list| {
+                positions.iter().for_each(|&at| {
+                    black_box(list.remove(at));
+                })
+            },
+            BatchSize::SmallInput,
+        )
+    });
+    group.bench_function("DoublyLinkedList", |b| {
+        b.iter_batched_ref(
+            || (0..n).collect::<DoublyLinkedList<usize>>(),
+            |list| {
+                for &at in &positions {
+                    let mut cursor = list.cursor_front_mut();
+                    for _ in 0..at {
+                        cursor.move_next();
+                    }
+                    black_box(cursor.remove_current());
+                }
+            },
+            BatchSize::SmallInput,
+        )
+    });
+    group.bench_function("UnrolledList", |b| {
+        b.iter_batched_ref(
+            || (0..n).collect::<UnrolledList<usize>>(),
+            |list| {
+                positions.iter().for_each(|&at| {
+                    black_box(list.remove(at));
+                })
+            },
+            BatchSize::SmallInput,
+        )
+    });
+    // std's LinkedList::remove is still unstable, so split, pop and stitch back together
+    group.bench_function("std::LinkedList", |b| {
+        b.iter_batched_ref(
+            || (0..n).collect::<StdLinkedList<usize>>(),
+            |list| {
+                for &at in &positions {
+                    let mut rest = list.split_off(at);
+                    black_box(rest.pop_front());
+                    list.append(&mut rest);
+                }
+            },
+            BatchSize::SmallInput,
+        )
+    });
+    group.bench_function("VecDeque", |b| {
+        b.iter_batched_ref(
+            || (0..n).collect::<VecDeque<usize>>(),
+            |list| {
+                positions.iter().for_each(|&at| {
+                    black_box(list.remove(at));
+                })
+            },
+            BatchSize::SmallInput,
+        )
+    });
+    group.finish();
+}
+
+criterion_group!(benches, push_pop, iterate, remove_random);
+criterion_main!(benches);
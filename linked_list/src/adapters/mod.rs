@@ -0,0 +1,238 @@
+use crate::{DoublyLinkedList, DoublyLinkedListIterRef, LinkedList, LinkedListIterRef};
+
+// Thin wrappers that only expose the operations that make sense for how the collection is used
+// A Stack can't be popped from the bottom and a Queue can't jump the line, so the type says what
+// the code means, and every operation that's left is O(1):
+//  - Stack: push and pop at the front of a singly linked list
+//  - Queue: enqueue at the back and dequeue from the front of a singly linked list, which is O(1)
+//    at both ends since LinkedList keeps a tail pointer
+//  - Deque: both ends of a doubly linked list
+// All three iterate in the order values would come out: top first for a Stack, front first for
+// the others
+
+#[derive(Default, Debug, Clone, PartialEq)]
+pub struct Stack<T> {
+    list: LinkedList<T>,
+}
+
+impl<T> Stack<T> {
+    pub fn new() -> Self {
+        Stack {
+            list: LinkedList::new(),
+        }
+    }
+
+    pub fn push(&mut self, value: T) {
+        self.list.push_front(value);
+    }
+
+    pub fn pop(&mut self) -> Option<T> {
+        self.list.pop_front()
+    }
+
+    // The value pop would return, without popping it
+    pub fn peek(&self) -> Option<&T> {
+        self.list.front()
+    }
+
+    pub fn peek_mut(&mut self) -> Option<&mut T> {
+        self.list.front_mut()
+    }
+
+    pub fn len(&self) -> usize {
+        self.list.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.list.is_empty()
+    }
+
+    pub fn iter(&self) -> LinkedListIterRef<'_, T> {
+        self.list.iter()
+    }
+}
+
+// Pushes the values in order, so the last one ends up on top
+impl<T> Extend<T> for Stack<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for value in iter {
+            self.push(value);
+        }
+    }
+}
+
+impl<T> FromIterator<T> for Stack<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut stack = Stack::new();
+        stack.extend(iter);
+        stack
+    }
+}
+
+#[derive(Default, Debug, Clone, PartialEq)]
+pub struct Queue<T> {
+    list: LinkedList<T>,
+}
+
+impl<T> Queue<T> {
+    pub fn new() -> Self {
+        Queue {
+            list: LinkedList::new(),
+        }
+    }
+
+    pub fn enqueue(&mut self, value: T) {
+        self.list.push_back(value);
+    }
+
+    pub fn dequeue(&mut self) -> Option<T> {
+        self.list.pop_front()
+    }
+
+    // The value dequeue would return, without dequeuing it
+    pub fn peek(&self) -> Option<&T> {
+        self.list.front()
+    }
+
+    pub fn peek_mut(&mut self) -> Option<&mut T> {
+        self.list.front_mut()
+    }
+
+    pub fn len(&self) -> usize {
+        self.list.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.list.is_empty()
+    }
+
+    pub fn iter(&self) -> LinkedListIterRef<'_, T> {
+        self.list.iter()
+    }
+}
+
+impl<T> Extend<T> for Queue<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        self.list.extend(iter);
+    }
+}
+
+impl<T> FromIterator<T> for Queue<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        Queue {
+            list: iter.into_iter().collect(),
+        }
+    }
+}
+
+#[derive(Default, Debug, Clone, PartialEq)]
+pub struct Deque<T> {
+    list: DoublyLinkedList<T>,
+}
+
+impl<T> Deque<T> {
+    pub fn new() -> Self {
+        Deque {
+            list: DoublyLinkedList::new(),
+        }
+    }
+
+    pub fn push_front(&mut self, value: T) {
+        self.list.push_front(value);
+    }
+
+    pub fn push_back(&mut self, value: T) {
+        self.list.push_back(value);
+    }
+
+    pub fn pop_front(&mut self) -> Option<T> {
+        self.list.pop_front()
+    }
+
+    pub fn pop_back(&mut self) -> Option<T> {
+        self.list.pop_back()
+    }
+
+    pub fn front(&self) -> Option<&T> {
+        self.list.front()
+    }
+
+    pub fn back(&self) -> Option<&T> {
+        self.list.back()
+    }
+
+    pub fn len(&self) -> usize {
+        self.list.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.list.is_empty()
+    }
+
+    pub fn iter(&self) -> DoublyLinkedListIterRef<'_, T> {
+        self.list.iter()
+    }
+}
+
+impl<T> Extend<T> for Deque<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        self.list.extend(iter);
+    }
+}
+
+impl<T> FromIterator<T> for Deque<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        Deque {
+            list: iter.into_iter().collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod testing {
+    use super::*;
+
+    #[test]
+    fn stack_is_last_in_first_out() {
+        let mut stack: Stack<i32> = (1..=3).collect();
+        assert_eq!(Some(&3), stack.peek());
+        stack.push(4);
+        *stack.peek_mut().unwrap() *= 10;
+        assert_eq!(vec![&40, &3, &2, &1], stack.iter().collect::<Vec<_>>());
+        assert_eq!(Some(40), stack.pop());
+        assert_eq!(Some(3), stack.pop());
+        assert_eq!(2, stack.len());
+        stack.pop();
+        stack.pop();
+        assert!(stack.is_empty() && stack.pop().is_none());
+    }
+
+    #[test]
+    fn queue_is_first_in_first_out() {
+        let mut queue: Queue<i32> = (1..=3).collect();
+        assert_eq!(Some(&1), queue.peek());
+        queue.enqueue(4);
+        assert_eq!(vec![&1, &2, &3, &4], queue.iter().collect::<Vec<_>>());
+        assert_eq!(Some(1), queue.dequeue());
+        assert_eq!(Some(2), queue.dequeue());
+        queue.extend([5, 6]);
+        assert_eq!(
+            vec![3, 4, 5, 6],
+            std::iter::from_fn(|| queue.dequeue()).collect::<Vec<_>>()
+        );
+        assert!(queue.is_empty() && queue.peek().is_none());
+    }
+
+    #[test]
+    fn deque_works_at_both_ends() {
+        let mut deque: Deque<i32> = (2..=3).collect();
+        deque.push_front(1);
+        deque.push_back(4);
+        assert_eq!((Some(&1), Some(&4)), (deque.front(), deque.back()));
+        assert_eq!(4, deque.len());
+        assert_eq!(Some(4), deque.pop_back());
+        assert_eq!(Some(1), deque.pop_front());
+        assert_eq!(vec![&2, &3], deque.iter().collect::<Vec<_>>());
+        assert_eq!(deque.clone(), [2, 3].into_iter().collect());
+    }
+}
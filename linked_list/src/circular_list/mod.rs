@@ -0,0 +1,482 @@
+use alloc::boxed::Box;
+use core::fmt;
+use core::iter::FusedIterator;
+use core::marker::PhantomData;
+use core::ptr::NonNull;
+
+// A circular singly linked list: the last node links back to the first instead of to nothing
+//
+//   tail -> [c] -> [a] -> [b] -> [c] -> [a] -> ...
+//
+// We only keep a pointer to the tail, the head is always tail.next. That one pointer is enough
+// for O(1) push_front, push_back and pop_front, and rotating the whole list by one step is just
+// moving the tail along.
+// Every node is pointed at by the node before it, including the last one by the first, so the
+// ownership story is the same as in DoublyLinkedList: raw pointers, owned by the list as a whole.
+pub struct CircularList<T> {
+    tail: Option<NonNull<Node<T>>>,
+    len: usize,
+    _owns: PhantomData<Box<Node<T>>>,
+}
+
+// Unlike LinkedList there's no Option here: in a circle every node has a next, even if it's
+// itself (a list of one)
+struct Node<T> {
+    value: T,
+    next: NonNull<Node<T>>,
+}
+
+// Walking a circle never reaches an end, so the iterators count down from len() instead
+pub struct CircularListIterRef<'a, T> {
+    next_node: Option<NonNull<Node<T>>>,
+    remaining: usize,
+    _list: PhantomData<&'a T>,
+}
+
+pub struct CircularListIterMut<'a, T> {
+    next_node: Option<NonNull<Node<T>>>,
+    remaining: usize,
+    _list: PhantomData<&'a mut T>,
+}
+
+pub struct CircularListIter<T> {
+    list: CircularList<T>,
+}
+
+// A cursor that goes round and round: moving past the last value lands back on the first
+// It remembers the node *before* the current one, which is what makes removing the current
+// value O(1) in a singly linked list
+// This is the shape of a round-robin scheduler: look at current(), move_next() to give the next
+// one a turn, remove_current() when one is done
+pub struct CircularCursorMut<'a, T> {
+    prev: Option<NonNull<Node<T>>>,
+    list: &'a mut CircularList<T>,
+}
+
+impl<T> CircularList<T> {
+    pub fn new() -> Self {
+        CircularList {
+            tail: None,
+            len: 0,
+            _owns: PhantomData,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    // The new node goes between the tail and the head, so it's both "after the last" and "before
+    // the first". push_front and push_back only differ in whether the tail then moves onto it
+    pub fn push_front(&mut self, value: T) {
+        self.link_after_tail(value);
+    }
+
+    pub fn push_back(&mut self, value: T) {
+        self.tail = Some(self.link_after_tail(value));
+    }
+
+    pub fn pop_front(&mut self) -> Option<T> {
+        let tail = self.tail?;
+        // SAFETY: tail and its next (the head) are live nodes of this list
+        unsafe { Some(self.unlink_after(tail)) }
+    }
+
+    pub fn front(&self) -> Option<&T> {
+        // SAFETY: the nodes live as long as the list, and &self stops anyone changing them
+        self.tail
+            .map(|tail| unsafe { &(*(*tail.as_ptr()).next.as_ptr()).value })
+    }
+
+    pub fn back(&self) -> Option<&T> {
+        // SAFETY: as in front()
+        self.tail.map(|tail| unsafe { &(*tail.as_ptr()).value })
+    }
+
+    // Turns the circle one step: the front value becomes the back one
+    pub fn rotate(&mut self) {
+        self.rotate_by(1);
+    }
+
+    // Turns the circle n steps, O(n % len) since going all the way round changes nothing
+    pub fn rotate_by(&mut self, n: usize) {
+        if self.len == 0 {
+            return;
+        }
+        for _ in 0..n % self.len {
+            // SAFETY: tail is a live node of this list
+            self.tail = self.tail.map(|tail| unsafe { (*tail.as_ptr()).next });
+        }
+    }
+
+    pub fn iter(&self) -> CircularListIterRef<'_, T> {
+        CircularListIterRef {
+            next_node: self.head(),
+            remaining: self.len,
+            _list: PhantomData,
+        }
+    }
+
+    pub fn iter_mut(&mut self) -> CircularListIterMut<'_, T> {
+        CircularListIterMut {
+            next_node: self.head(),
+            remaining: self.len,
+            _list: PhantomData,
+        }
+    }
+
+    // Starts on the front value (or nowhere, if the list is empty)
+    pub fn cursor_mut(&mut self) -> CircularCursorMut<'_, T> {
+        CircularCursorMut {
+            prev: self.tail,
+            list: self,
+        }
+    }
+
+    fn head(&self) -> Option<NonNull<Node<T>>> {
+        // SAFETY: tail is a live node of this list
+        self.tail.map(|tail| unsafe { (*tail.as_ptr()).next })
+    }
+
+    // Links a new node in straight after the tail (so before the head) and returns it
+    fn link_after_tail(&mut self, value: T) -> NonNull<Node<T>> {
+        match self.tail {
+            // SAFETY: tail is a live node of this list
+            Some(tail) => unsafe { self.link_after(tail, value) },
+            None => {
+                // a list of one is a node whose next is itself, which we can only set once the
+                // node exists
+                let node = NonNull::from(Box::leak(Box::new(Node {
+                    value,
+                    next: NonNull::dangling(),
+                })));
+                // SAFETY: node was just allocated and nothing else points at it
+                unsafe { (*node.as_ptr()).next = node };
+                self.tail = Some(node);
+                self.len += 1;
+                node
+            }
+        }
+    }
+
+    // SAFETY: prev must be a live node of this list
+    unsafe fn link_after(&mut self, prev: NonNull<Node<T>>, value: T) -> NonNull<Node<T>> {
+        let node = NonNull::from(Box::leak(Box::new(Node {
+            value,
+            next: (*prev.as_ptr()).next,
+        })));
+        (*prev.as_ptr()).next = node;
+        self.len += 1;
+        node
+    }
+
+    // Unlinks and frees the node after prev, returning its value
+    // SAFETY: prev must be a live node of this list (so the list isn't empty)
+    unsafe fn unlink_after(&mut self, prev: NonNull<Node<T>>) -> T {
+        let node = (*prev.as_ptr()).next;
+        if node == prev {
+            // prev's next is itself: it was the only node
+            self.tail = None;
+        } else {
+            (*prev.as_ptr()).next = (*node.as_ptr()).next;
+            if Some(node) == self.tail {
+                self.tail = Some(prev);
+            }
+        }
+        self.len -= 1;
+        Box::from_raw(node.as_ptr()).value
+    }
+}
+
+impl<'a, T> CircularCursorMut<'a, T> {
+    pub fn current(&mut self) -> Option<&mut T> {
+        // SAFETY: prev and its next are live nodes, and the cursor borrows the list mutably
+        self.prev
+            .map(|prev| unsafe { &mut (*(*prev.as_ptr()).next.as_ptr()).value })
+    }
+
+    // Moves on to the next value, wrapping from the back of the list to the front
+    pub fn move_next(&mut self) {
+        // SAFETY: prev is a live node of the list
+        self.prev = self.prev.map(|prev| unsafe { (*prev.as_ptr()).next });
+    }
+
+    // Removes the current value and moves on to the one after it
+    pub fn remove_current(&mut self) -> Option<T> {
+        let prev = self.prev?;
+        // SAFETY: prev is a live node of the list
+        let value = unsafe { self.list.unlink_after(prev) };
+        if self.list.is_empty() {
+            self.prev = None;
+        }
+        Some(value)
+    }
+
+    // Inserts a value just before the current one, the cursor stays where it is
+    // Before the front value means it becomes the new front
+    pub fn insert_before(&mut self, value: T) {
+        self.prev = Some(match self.prev {
+            // SAFETY: prev is a live node of the list
+            Some(prev) => unsafe { self.list.link_after(prev, value) },
+            None => self.list.link_after_tail(value),
+        });
+    }
+
+    // Inserts a value just after the current one, the cursor stays where it is
+    // After the back value means it becomes the new back
+    pub fn insert_after(&mut self, value: T) {
+        let Some(prev) = self.prev else {
+            self.list.push_back(value);
+            self.prev = self.list.tail;
+            return;
+        };
+        // SAFETY: prev and its next are live nodes of the list
+        unsafe {
+            let current = (*prev.as_ptr()).next;
+            let node = self.list.link_after(current, value);
+            if Some(current) == self.list.tail {
+                self.list.tail = Some(node);
+            }
+        }
+    }
+}
+
+impl<T> Default for CircularList<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Drop for CircularList<T> {
+    fn drop(&mut self) {
+        while self.pop_front().is_some() {}
+    }
+}
+
+// See DoublyLinkedList, closing the ring doesn't share a node with anything outside the list
+unsafe impl<T: Send> Send for CircularList<T> {}
+unsafe impl<T: Sync> Sync for CircularList<T> {}
+
+impl<T> Extend<T> for CircularList<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for value in iter {
+            self.push_back(value);
+        }
+    }
+}
+
+impl<T> FromIterator<T> for CircularList<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut list = CircularList::new();
+        list.extend(iter);
+        list
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for CircularList<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<T> IntoIterator for CircularList<T> {
+    type Item = T;
+    type IntoIter = CircularListIter<T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        CircularListIter { list: self }
+    }
+}
+
+impl<'a, T> IntoIterator for &'a CircularList<T> {
+    type Item = &'a T;
+    type IntoIter = CircularListIterRef<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, T> Iterator for CircularListIterRef<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.next_node.map(|node| {
+            // SAFETY: the list is borrowed for 'a, so the node stays alive and unchanged for 'a
+            let node = unsafe { &*node.as_ptr() };
+            self.next_node = Some(node.next);
+            self.remaining -= 1;
+            &node.value
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<T> ExactSizeIterator for CircularListIterRef<'_, T> {}
+impl<T> FusedIterator for CircularListIterRef<'_, T> {}
+
+impl<'a, T> Iterator for CircularListIterMut<'a, T> {
+    type Item = &'a mut T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.next_node.map(|node| {
+            // SAFETY: counting down from len() means we visit each node once, so no two &mut T
+            // we hand out point at the same value
+            let node = unsafe { &mut *node.as_ptr() };
+            self.next_node = Some(node.next);
+            self.remaining -= 1;
+            &mut node.value
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<T> ExactSizeIterator for CircularListIterMut<'_, T> {}
+impl<T> FusedIterator for CircularListIterMut<'_, T> {}
+
+impl<T> Iterator for CircularListIter<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.list.pop_front()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.list.len, Some(self.list.len))
+    }
+}
+
+impl<T> ExactSizeIterator for CircularListIter<T> {}
+impl<T> FusedIterator for CircularListIter<T> {}
+
+#[cfg(test)]
+mod testing {
+    use super::*;
+
+    #[test]
+    fn can_push_and_pop() {
+        let mut list = CircularList::new();
+        list.push_back(1);
+        list.push_back(2);
+        list.push_front(0);
+        assert_eq!(vec![0, 1, 2], list.iter().copied().collect::<Vec<_>>());
+        assert_eq!((Some(&0), Some(&2)), (list.front(), list.back()));
+
+        assert_eq!(Some(0), list.pop_front());
+        assert_eq!(Some(1), list.pop_front());
+        assert_eq!((Some(&2), Some(&2)), (list.front(), list.back()));
+        assert_eq!(Some(2), list.pop_front());
+        assert_eq!(None, list.pop_front());
+        assert!(list.is_empty());
+    }
+
+    #[test]
+    fn can_rotate() {
+        let mut list: CircularList<i32> = (0..4).collect();
+        list.rotate();
+        assert_eq!(vec![1, 2, 3, 0], list.iter().copied().collect::<Vec<_>>());
+        list.rotate_by(6);
+        assert_eq!(vec![3, 0, 1, 2], list.iter().copied().collect::<Vec<_>>());
+        assert_eq!(Some(&2), list.back());
+
+        for v in list.iter_mut() {
+            *v *= 10;
+        }
+        assert_eq!("[30, 0, 10, 20]", format!("{list:?}"));
+
+        let mut empty: CircularList<i32> = CircularList::new();
+        empty.rotate_by(3);
+        assert!(empty.iter().next().is_none());
+    }
+
+    #[test]
+    fn cursor_wraps_around() {
+        let mut list: CircularList<i32> = (0..3).collect();
+        let mut cursor = list.cursor_mut();
+        let mut seen = Vec::new();
+        for _ in 0..7 {
+            seen.push(*cursor.current().unwrap());
+            cursor.move_next();
+        }
+        assert_eq!(vec![0, 1, 2, 0, 1, 2, 0], seen);
+    }
+
+    #[test]
+    fn can_run_a_round_robin() {
+        // (name, work left): every turn does one unit of work, finished jobs leave the ring
+        let mut jobs: CircularList<(char, u32)> =
+            [('a', 1), ('b', 3), ('c', 2)].into_iter().collect();
+        let mut order = Vec::new();
+        let mut cursor = jobs.cursor_mut();
+        while let Some((name, left)) = cursor.current() {
+            order.push(*name);
+            *left -= 1;
+            if *left == 0 {
+                cursor.remove_current();
+            } else {
+                cursor.move_next();
+            }
+        }
+        assert_eq!(vec!['a', 'b', 'c', 'b', 'c', 'b'], order);
+        assert!(jobs.is_empty());
+    }
+
+    #[test]
+    fn cursor_can_insert_on_both_sides() {
+        let mut list: CircularList<i32> = CircularList::new();
+        let mut cursor = list.cursor_mut();
+        cursor.insert_after(1);
+        assert_eq!(Some(&mut 1), cursor.current());
+        cursor.insert_before(0);
+        cursor.insert_after(2);
+        assert_eq!(Some(&mut 1), cursor.current());
+        assert_eq!(vec![0, 1, 2], list.iter().copied().collect::<Vec<_>>());
+        assert_eq!((Some(&0), Some(&2)), (list.front(), list.back()));
+
+        // on the back value, "after" becomes the new back
+        let mut cursor = list.cursor_mut();
+        cursor.move_next();
+        cursor.move_next();
+        cursor.insert_after(3);
+        // on the front value, "before" becomes the new front
+        cursor.move_next();
+        cursor.move_next();
+        cursor.insert_before(-1);
+        assert_eq!(Some(&mut 0), cursor.current());
+        assert_eq!(
+            vec![-1, 0, 1, 2, 3],
+            list.iter().copied().collect::<Vec<_>>()
+        );
+        assert_eq!((Some(&-1), Some(&3)), (list.front(), list.back()));
+    }
+
+    #[test]
+    fn removing_the_back_moves_the_tail() {
+        let mut list: CircularList<i32> = (0..3).collect();
+        let mut cursor = list.cursor_mut();
+        cursor.move_next();
+        cursor.move_next();
+        assert_eq!(Some(2), cursor.remove_current());
+        // the cursor moved on to the next value, which wraps to the front
+        assert_eq!(Some(&mut 0), cursor.current());
+        assert_eq!(Some(&1), list.back());
+        list.push_back(5);
+        assert_eq!(vec![0, 1, 5], list.into_iter().collect::<Vec<_>>());
+    }
+}
@@ -0,0 +1,409 @@
+use alloc::boxed::Box;
+use core::mem;
+use core::ptr::NonNull;
+
+use super::{DoublyLinkedList, Node};
+
+// A cursor is a position in the list that can move in either direction
+// Besides pointing at a node a cursor can point at the "ghost" position that sits between the
+// tail and the head: moving next from the tail or prev from the head lands on the ghost, and
+// moving again wraps around to the other end. This is the same model as the (nightly) cursors on
+// std::collections::LinkedList.
+// index is the position of the current node, the ghost counts as index len
+pub struct Cursor<'a, T> {
+    current: Option<NonNull<Node<T>>>,
+    index: usize,
+    list: &'a DoublyLinkedList<T>,
+}
+
+// A cursor that can also edit the list around it
+// Every edit is O(1) since we already hold a pointer to the node we're editing next to
+pub struct CursorMut<'a, T> {
+    current: Option<NonNull<Node<T>>>,
+    index: usize,
+    list: &'a mut DoublyLinkedList<T>,
+}
+
+impl<T> DoublyLinkedList<T> {
+    // Cursors start on the first (or last) node, or on the ghost if the list is empty
+    pub fn cursor_front(&self) -> Cursor<'_, T> {
+        Cursor {
+            current: self.head,
+            index: 0,
+            list: self,
+        }
+    }
+
+    pub fn cursor_back(&self) -> Cursor<'_, T> {
+        Cursor {
+            current: self.tail,
+            index: self.len.saturating_sub(1),
+            list: self,
+        }
+    }
+
+    pub fn cursor_front_mut(&mut self) -> CursorMut<'_, T> {
+        CursorMut {
+            current: self.head,
+            index: 0,
+            list: self,
+        }
+    }
+
+    pub fn cursor_back_mut(&mut self) -> CursorMut<'_, T> {
+        CursorMut {
+            current: self.tail,
+            index: self.len.saturating_sub(1),
+            list: self,
+        }
+    }
+}
+
+// The movement logic is the same for both cursors, so it lives in free functions over the parts
+// of the cursor that change
+// SAFETY (for all of these): every pointer handed in points at a live node of `list`
+fn move_next<T>(
+    current: &mut Option<NonNull<Node<T>>>,
+    index: &mut usize,
+    list: &DoublyLinkedList<T>,
+) {
+    match *current {
+        // from the ghost we wrap around to the head
+        None => {
+            *current = list.head;
+            *index = 0;
+        }
+        Some(node) => {
+            *current = unsafe { (*node.as_ptr()).next };
+            *index += 1;
+        }
+    }
+}
+
+fn move_prev<T>(
+    current: &mut Option<NonNull<Node<T>>>,
+    index: &mut usize,
+    list: &DoublyLinkedList<T>,
+) {
+    match *current {
+        // from the ghost we wrap around to the tail
+        None => {
+            *current = list.tail;
+            *index = list.len.saturating_sub(1);
+        }
+        Some(node) => {
+            *current = unsafe { (*node.as_ptr()).prev };
+            // stepping back off the head lands on the ghost, whose index is len
+            *index = match *current {
+                Some(_) => *index - 1,
+                None => list.len,
+            };
+        }
+    }
+}
+
+fn peek_next<T>(
+    current: Option<NonNull<Node<T>>>,
+    list: &DoublyLinkedList<T>,
+) -> Option<NonNull<Node<T>>> {
+    match current {
+        None => list.head,
+        Some(node) => unsafe { (*node.as_ptr()).next },
+    }
+}
+
+fn peek_prev<T>(
+    current: Option<NonNull<Node<T>>>,
+    list: &DoublyLinkedList<T>,
+) -> Option<NonNull<Node<T>>> {
+    match current {
+        None => list.tail,
+        Some(node) => unsafe { (*node.as_ptr()).prev },
+    }
+}
+
+impl<'a, T> Cursor<'a, T> {
+    // None when the cursor is on the ghost
+    pub fn index(&self) -> Option<usize> {
+        self.current.map(|_| self.index)
+    }
+
+    pub fn move_next(&mut self) {
+        move_next(&mut self.current, &mut self.index, self.list);
+    }
+
+    pub fn move_prev(&mut self) {
+        move_prev(&mut self.current, &mut self.index, self.list);
+    }
+
+    // A shared cursor only hands out shared references, so they can live as long as the list
+    // borrow ('a) rather than just as long as the cursor
+    pub fn current(&self) -> Option<&'a T> {
+        // SAFETY: the list is borrowed for 'a and can't change in the meantime
+        self.current.map(|node| unsafe { &(*node.as_ptr()).value })
+    }
+
+    pub fn peek_next(&self) -> Option<&'a T> {
+        peek_next(self.current, self.list).map(|node| unsafe { &(*node.as_ptr()).value })
+    }
+
+    pub fn peek_prev(&self) -> Option<&'a T> {
+        peek_prev(self.current, self.list).map(|node| unsafe { &(*node.as_ptr()).value })
+    }
+}
+
+impl<T> CursorMut<'_, T> {
+    pub fn index(&self) -> Option<usize> {
+        self.current.map(|_| self.index)
+    }
+
+    pub fn move_next(&mut self) {
+        move_next(&mut self.current, &mut self.index, self.list);
+    }
+
+    pub fn move_prev(&mut self) {
+        move_prev(&mut self.current, &mut self.index, self.list);
+    }
+
+    // Unlike Cursor, these borrow the cursor itself: a &mut T must not outlive the cursor or we
+    // could use the cursor to remove the node while still holding a reference into it
+    pub fn current(&mut self) -> Option<&mut T> {
+        // SAFETY: we hold the only (mutable) borrow of the list through the cursor
+        self.current
+            .map(|node| unsafe { &mut (*node.as_ptr()).value })
+    }
+
+    pub fn peek_next(&mut self) -> Option<&mut T> {
+        peek_next(self.current, self.list).map(|node| unsafe { &mut (*node.as_ptr()).value })
+    }
+
+    pub fn peek_prev(&mut self) -> Option<&mut T> {
+        peek_prev(self.current, self.list).map(|node| unsafe { &mut (*node.as_ptr()).value })
+    }
+
+    // A read only view of the same position
+    pub fn as_cursor(&self) -> Cursor<'_, T> {
+        Cursor {
+            current: self.current,
+            index: self.index,
+            list: self.list,
+        }
+    }
+
+    // Inserts a value before the current node, or at the back of the list when on the ghost
+    // The cursor stays on the same node, which is now one further from the head
+    pub fn insert_before(&mut self, value: T) {
+        match self.current {
+            None => self.list.push_back(value),
+            Some(current) => {
+                // SAFETY: current is a live node of the list and new is freshly allocated
+                unsafe {
+                    let prev = (*current.as_ptr()).prev;
+                    let new = DoublyLinkedList::alloc(Node {
+                        value,
+                        prev,
+                        next: Some(current),
+                    });
+                    (*current.as_ptr()).prev = Some(new);
+                    match prev {
+                        Some(prev) => (*prev.as_ptr()).next = Some(new),
+                        None => self.list.head = Some(new),
+                    }
+                }
+                self.list.len += 1;
+            }
+        }
+        self.index += 1;
+    }
+
+    // Inserts a value after the current node, or at the front of the list when on the ghost
+    pub fn insert_after(&mut self, value: T) {
+        match self.current {
+            None => {
+                self.list.push_front(value);
+                // the ghost's index is len, which just grew
+                self.index += 1;
+            }
+            Some(current) => {
+                // SAFETY: current is a live node of the list and new is freshly allocated
+                unsafe {
+                    let next = (*current.as_ptr()).next;
+                    let new = DoublyLinkedList::alloc(Node {
+                        value,
+                        prev: Some(current),
+                        next,
+                    });
+                    (*current.as_ptr()).next = Some(new);
+                    match next {
+                        Some(next) => (*next.as_ptr()).prev = Some(new),
+                        None => self.list.tail = Some(new),
+                    }
+                }
+                self.list.len += 1;
+            }
+        }
+    }
+
+    // Unlinks the current node and returns its value, the cursor moves on to the next node
+    // Does nothing (and returns None) on the ghost
+    pub fn remove_current(&mut self) -> Option<T> {
+        let current = self.current?;
+        // SAFETY: current is a live node of the list, after relinking its neighbours nothing points
+        // at it anymore so we can take back ownership and free it
+        let node = unsafe { Box::from_raw(current.as_ptr()) };
+        match node.prev {
+            Some(prev) => unsafe { (*prev.as_ptr()).next = node.next },
+            None => self.list.head = node.next,
+        }
+        match node.next {
+            Some(next) => unsafe { (*next.as_ptr()).prev = node.prev },
+            None => self.list.tail = node.prev,
+        }
+        self.list.len -= 1;
+        // the next node slides into our index, and if there is none the ghost's index (len) is
+        // already our old index
+        self.current = node.next;
+        Some(node.value)
+    }
+
+    // Splits the list after the current node, returning everything after it as a new list
+    // On the ghost the whole list is returned and this one is left empty
+    pub fn split_after(&mut self) -> DoublyLinkedList<T> {
+        let Some(current) = self.current else {
+            self.index = 0;
+            return mem::take(self.list);
+        };
+
+        // SAFETY: current and its next are live nodes of the list
+        let next = unsafe { (*current.as_ptr()).next.take() };
+        let split_len = self.list.len - self.index - 1;
+        let mut split = DoublyLinkedList::new();
+        if let Some(next) = next {
+            unsafe { (*next.as_ptr()).prev = None };
+            split.head = Some(next);
+            split.tail = self.list.tail;
+            split.len = split_len;
+        }
+
+        self.list.tail = Some(current);
+        self.list.len = self.index + 1;
+        split
+    }
+}
+
+#[cfg(test)]
+mod testing {
+    use super::*;
+
+    fn list_of<T>(values: impl IntoIterator<Item = T>) -> DoublyLinkedList<T> {
+        let mut list = DoublyLinkedList::new();
+        for value in values {
+            list.push_back(value);
+        }
+        list
+    }
+
+    #[test]
+    fn cursor_walks_through_the_ghost() {
+        let list = list_of([0, 1, 2]);
+        let mut cursor = list.cursor_front();
+        assert_eq!((Some(0), Some(&0)), (cursor.index(), cursor.current()));
+        // the head's prev is the ghost, which has no value
+        assert_eq!(None, cursor.peek_prev());
+        assert_eq!(Some(&1), cursor.peek_next());
+
+        cursor.move_next();
+        cursor.move_next();
+        assert_eq!((Some(2), Some(&2)), (cursor.index(), cursor.current()));
+
+        cursor.move_next();
+        assert_eq!((None, None), (cursor.index(), cursor.current()));
+        assert_eq!(Some(&0), cursor.peek_next());
+        assert_eq!(Some(&2), cursor.peek_prev());
+
+        cursor.move_next();
+        assert_eq!((Some(0), Some(&0)), (cursor.index(), cursor.current()));
+
+        cursor.move_prev();
+        assert_eq!(None, cursor.index());
+        cursor.move_prev();
+        assert_eq!((Some(2), Some(&2)), (cursor.index(), cursor.current()));
+
+        let empty: DoublyLinkedList<i32> = DoublyLinkedList::new();
+        let mut cursor = empty.cursor_back();
+        assert_eq!(None, cursor.current());
+        cursor.move_next();
+        assert_eq!(None, cursor.current());
+    }
+
+    #[test]
+    fn cursor_mut_can_insert_around_current() {
+        let mut list = list_of([1, 3]);
+        let mut cursor = list.cursor_front_mut();
+        cursor.insert_before(0);
+        assert_eq!((Some(1), Some(&mut 1)), (cursor.index(), cursor.current()));
+        cursor.insert_after(2);
+        cursor.move_next();
+        assert_eq!((Some(2), Some(&mut 2)), (cursor.index(), cursor.current()));
+
+        // on the ghost, before means the back and after means the front
+        cursor.move_next();
+        cursor.move_next();
+        assert_eq!(None, cursor.index());
+        cursor.insert_before(4);
+        cursor.insert_after(-1);
+        assert_eq!(None, cursor.index());
+        cursor.move_prev();
+        assert_eq!((Some(5), Some(&mut 4)), (cursor.index(), cursor.current()));
+
+        assert_eq!(
+            vec![-1, 0, 1, 2, 3, 4],
+            list.into_iter().collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn cursor_mut_can_remove_current() {
+        let mut list = list_of([0, 1, 2, 3]);
+        let mut cursor = list.cursor_front_mut();
+        cursor.move_next();
+        assert_eq!(Some(1), cursor.remove_current());
+        assert_eq!((Some(1), Some(&mut 2)), (cursor.index(), cursor.current()));
+
+        cursor.move_next();
+        assert_eq!(Some(3), cursor.remove_current());
+        assert_eq!(None, cursor.index());
+        assert_eq!(None, cursor.remove_current());
+
+        cursor.move_next();
+        assert_eq!(Some(0), cursor.remove_current());
+        assert_eq!(Some(2), cursor.remove_current());
+        assert_eq!(None, cursor.current());
+        assert!(list.is_empty());
+        assert_eq!(None, list.front());
+        assert_eq!(None, list.back());
+    }
+
+    #[test]
+    fn cursor_mut_can_split_after() {
+        let mut list = list_of([0, 1, 2, 3, 4]);
+        let mut cursor = list.cursor_front_mut();
+        cursor.move_next();
+        let mut tail = cursor.split_after();
+        assert_eq!(Some(1), cursor.index());
+        assert_eq!(vec![&0, &1], list.iter().collect::<Vec<_>>());
+        assert_eq!(vec![&1, &0], list.iter().rev().collect::<Vec<_>>());
+        assert_eq!(3, tail.len());
+        assert_eq!(vec![&4, &3, &2], tail.iter().rev().collect::<Vec<_>>());
+
+        // splitting after the tail gives an empty list
+        let mut cursor = tail.cursor_back_mut();
+        assert!(cursor.split_after().is_empty());
+
+        // splitting on the ghost takes everything
+        cursor.move_next();
+        let all = cursor.split_after();
+        assert_eq!(vec![2, 3, 4], all.into_iter().collect::<Vec<_>>());
+        assert!(tail.is_empty());
+    }
+}
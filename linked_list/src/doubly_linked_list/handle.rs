@@ -0,0 +1,74 @@
+use alloc::boxed::Box;
+use core::ptr::NonNull;
+
+use super::{DoublyLinkedList, Node};
+
+// Crate-internal access to single nodes, for structures that keep their own index of where each
+// value lives (LruCache keeps a HashMap of them) and need O(1) access without walking the list
+// A handle is just the node's address, it's only meaningful together with the list it came from
+pub(crate) struct NodeHandle<T>(NonNull<Node<T>>);
+
+impl<T> Clone for NodeHandle<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for NodeHandle<T> {}
+
+// A handle can't be used without the list, so it's only as shareable as the list is
+unsafe impl<T: Send> Send for NodeHandle<T> {}
+unsafe impl<T: Sync> Sync for NodeHandle<T> {}
+
+// Every method here is unsafe for the same reason: the handle must have come from this list, and
+// the node it points at must not have been removed since
+impl<T> DoublyLinkedList<T> {
+    pub(crate) fn push_front_handle(&mut self, value: T) -> NodeHandle<T> {
+        self.push_front(value);
+        NodeHandle(self.head.expect("we just pushed a node"))
+    }
+
+    pub(crate) unsafe fn get_by_handle(&self, handle: NodeHandle<T>) -> &T {
+        &(*handle.0.as_ptr()).value
+    }
+
+    pub(crate) unsafe fn get_mut_by_handle(&mut self, handle: NodeHandle<T>) -> &mut T {
+        &mut (*handle.0.as_ptr()).value
+    }
+
+    // Relinks the node at the front of the list, nothing is allocated or freed
+    pub(crate) unsafe fn move_to_front(&mut self, handle: NodeHandle<T>) {
+        if self.head == Some(handle.0) {
+            return;
+        }
+        self.unlink(handle.0);
+        let node = handle.0.as_ptr();
+        (*node).prev = None;
+        (*node).next = self.head;
+        match self.head {
+            Some(head) => (*head.as_ptr()).prev = Some(handle.0),
+            None => self.tail = Some(handle.0),
+        }
+        self.head = Some(handle.0);
+        self.len += 1;
+    }
+
+    pub(crate) unsafe fn remove_by_handle(&mut self, handle: NodeHandle<T>) -> T {
+        self.unlink(handle.0);
+        Box::from_raw(handle.0.as_ptr()).value
+    }
+
+    // Takes node out of the chain (fixing up its neighbours, head and tail) without freeing it
+    unsafe fn unlink(&mut self, node: NonNull<Node<T>>) {
+        let Node { prev, next, .. } = *node.as_ptr();
+        match prev {
+            Some(prev) => (*prev.as_ptr()).next = next,
+            None => self.head = next,
+        }
+        match next {
+            Some(next) => (*next.as_ptr()).prev = prev,
+            None => self.tail = prev,
+        }
+        self.len -= 1;
+    }
+}
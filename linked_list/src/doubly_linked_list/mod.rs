@@ -0,0 +1,481 @@
+mod cursor;
+// node handles are only used by LruCache, which needs std for its HashMap
+#[cfg(feature = "std")]
+mod handle;
+mod visit;
+
+use alloc::boxed::Box;
+use core::cmp::Ordering;
+use core::fmt;
+use core::hash::{Hash, Hasher};
+use core::iter::FusedIterator;
+use core::marker::PhantomData;
+use core::ptr::NonNull;
+
+pub use cursor::{Cursor, CursorMut};
+#[cfg(feature = "std")]
+pub(crate) use handle::NodeHandle;
+pub use visit::Visit;
+
+// A doubly linked list: every node knows both its next and its previous node
+// With Box every node can only have one owner, but here each node is pointed at from two sides
+// (its prev's next and its next's prev), so the links are raw pointers and the list as a whole
+// owns all of the nodes. This is the same approach std::collections::LinkedList takes.
+// NonNull is a raw pointer that's promised to never be null, so Option<NonNull<_>> is still just
+// one pointer wide (None is stored as null).
+pub struct DoublyLinkedList<T> {
+    head: Option<NonNull<Node<T>>>,
+    tail: Option<NonNull<Node<T>>>,
+    len: usize,
+    // Raw pointers don't tell the compiler that we own the nodes, PhantomData does
+    // It makes the list behave (for drop checking and variance) as if it held Box<Node<T>>
+    _owns: PhantomData<Box<Node<T>>>,
+}
+
+struct Node<T> {
+    value: T,
+    prev: Option<NonNull<Node<T>>>,
+    next: Option<NonNull<Node<T>>>,
+}
+
+// Iterators hold raw pointers to the nodes that are left at each end, plus how many values are
+// left between them, so walking from the front and from the back can't cross over
+pub struct DoublyLinkedListIterRef<'a, T> {
+    front: Option<NonNull<Node<T>>>,
+    back: Option<NonNull<Node<T>>>,
+    remaining: usize,
+    // the iterator borrows the list for 'a, PhantomData carries that lifetime
+    _list: PhantomData<&'a Node<T>>,
+}
+
+pub struct DoublyLinkedListIterMut<'a, T> {
+    front: Option<NonNull<Node<T>>>,
+    back: Option<NonNull<Node<T>>>,
+    remaining: usize,
+    _list: PhantomData<&'a mut Node<T>>,
+}
+
+pub struct DoublyLinkedListIter<T> {
+    list: DoublyLinkedList<T>,
+}
+
+impl<T> DoublyLinkedList<T> {
+    pub fn new() -> Self {
+        DoublyLinkedList {
+            head: None,
+            tail: None,
+            len: 0,
+            _owns: PhantomData,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    // All four push/pop operations are O(1) since we know where both ends are
+
+    pub fn push_front(&mut self, value: T) {
+        let node = Self::alloc(Node {
+            value,
+            prev: None,
+            next: self.head,
+        });
+        match self.head {
+            // SAFETY: head points at a live node owned by this list
+            Some(head) => unsafe { (*head.as_ptr()).prev = Some(node) },
+            None => self.tail = Some(node),
+        }
+        self.head = Some(node);
+        self.len += 1;
+    }
+
+    pub fn push_back(&mut self, value: T) {
+        let node = Self::alloc(Node {
+            value,
+            prev: self.tail,
+            next: None,
+        });
+        match self.tail {
+            // SAFETY: tail points at a live node owned by this list
+            Some(tail) => unsafe { (*tail.as_ptr()).next = Some(node) },
+            None => self.head = Some(node),
+        }
+        self.tail = Some(node);
+        self.len += 1;
+    }
+
+    pub fn pop_front(&mut self) -> Option<T> {
+        self.head.map(|head| {
+            // SAFETY: head was allocated by alloc() and is unlinked before it's freed
+            let node = unsafe { Box::from_raw(head.as_ptr()) };
+            self.head = node.next;
+            match self.head {
+                Some(new_head) => unsafe { (*new_head.as_ptr()).prev = None },
+                None => self.tail = None,
+            }
+            self.len -= 1;
+            node.value
+        })
+    }
+
+    pub fn pop_back(&mut self) -> Option<T> {
+        self.tail.map(|tail| {
+            // SAFETY: tail was allocated by alloc() and is unlinked before it's freed
+            let node = unsafe { Box::from_raw(tail.as_ptr()) };
+            self.tail = node.prev;
+            match self.tail {
+                Some(new_tail) => unsafe { (*new_tail.as_ptr()).next = None },
+                None => self.head = None,
+            }
+            self.len -= 1;
+            node.value
+        })
+    }
+
+    pub fn front(&self) -> Option<&T> {
+        // SAFETY: the node lives as long as the list, and &self stops anyone from changing it
+        self.head.map(|node| unsafe { &(*node.as_ptr()).value })
+    }
+
+    pub fn back(&self) -> Option<&T> {
+        // SAFETY: as in front()
+        self.tail.map(|node| unsafe { &(*node.as_ptr()).value })
+    }
+
+    pub fn iter(&self) -> DoublyLinkedListIterRef<'_, T> {
+        DoublyLinkedListIterRef {
+            front: self.head,
+            back: self.tail,
+            remaining: self.len,
+            _list: PhantomData,
+        }
+    }
+
+    pub fn iter_mut(&mut self) -> DoublyLinkedListIterMut<'_, T> {
+        DoublyLinkedListIterMut {
+            front: self.head,
+            back: self.tail,
+            remaining: self.len,
+            _list: PhantomData,
+        }
+    }
+
+    // Nodes are allocated as Boxes and turned into raw pointers, the list is then responsible for
+    // turning them back into Boxes (with Box::from_raw) exactly once to free them
+    fn alloc(node: Node<T>) -> NonNull<Node<T>> {
+        NonNull::from(Box::leak(Box::new(node)))
+    }
+}
+
+impl<T> Default for DoublyLinkedList<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// The compiler doesn't know how to free raw pointers, so without this every node would leak
+// Popping until empty frees each node in a loop, so long lists don't recurse either
+impl<T> Drop for DoublyLinkedList<T> {
+    fn drop(&mut self) {
+        while self.pop_front().is_some() {}
+    }
+}
+
+// Raw pointers make a type neither Send nor Sync by default
+// Our list owns its nodes outright (no sharing), so it's as thread safe as the T's it holds
+unsafe impl<T: Send> Send for DoublyLinkedList<T> {}
+unsafe impl<T: Sync> Sync for DoublyLinkedList<T> {}
+
+impl<T> Extend<T> for DoublyLinkedList<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for value in iter {
+            self.push_back(value);
+        }
+    }
+}
+
+impl<T> FromIterator<T> for DoublyLinkedList<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut list = DoublyLinkedList::new();
+        list.extend(iter);
+        list
+    }
+}
+
+// The standard traits, element by element just like LinkedList
+// Deriving them wouldn't even work here, the fields are raw pointers
+impl<T: fmt::Debug> fmt::Debug for DoublyLinkedList<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<T: Clone> Clone for DoublyLinkedList<T> {
+    fn clone(&self) -> Self {
+        self.iter().cloned().collect()
+    }
+}
+
+impl<T: PartialEq> PartialEq for DoublyLinkedList<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.len == other.len && self.iter().eq(other.iter())
+    }
+}
+
+impl<T: Eq> Eq for DoublyLinkedList<T> {}
+
+impl<T: PartialOrd> PartialOrd for DoublyLinkedList<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        self.iter().partial_cmp(other.iter())
+    }
+}
+
+impl<T: Ord> Ord for DoublyLinkedList<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.iter().cmp(other.iter())
+    }
+}
+
+impl<T: Hash> Hash for DoublyLinkedList<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.len.hash(state);
+        for value in self.iter() {
+            value.hash(state);
+        }
+    }
+}
+
+impl<T> IntoIterator for DoublyLinkedList<T> {
+    type Item = T;
+    type IntoIter = DoublyLinkedListIter<T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        DoublyLinkedListIter { list: self }
+    }
+}
+
+impl<'a, T> IntoIterator for &'a DoublyLinkedList<T> {
+    type Item = &'a T;
+    type IntoIter = DoublyLinkedListIterRef<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a mut DoublyLinkedList<T> {
+    type Item = &'a mut T;
+    type IntoIter = DoublyLinkedListIterMut<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+impl<'a, T> Iterator for DoublyLinkedListIterRef<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.front.map(|node| {
+            // SAFETY: the list is borrowed for 'a so the node stays alive and unchanged
+            let node = unsafe { &*node.as_ptr() };
+            self.front = node.next;
+            self.remaining -= 1;
+            &node.value
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+// DoubleEndedIterator adds next_back(), which is what .rev() is built on
+impl<T> DoubleEndedIterator for DoublyLinkedListIterRef<'_, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.back.map(|node| {
+            // SAFETY: as in next()
+            let node = unsafe { &*node.as_ptr() };
+            self.back = node.prev;
+            self.remaining -= 1;
+            &node.value
+        })
+    }
+}
+
+impl<T> ExactSizeIterator for DoublyLinkedListIterRef<'_, T> {}
+impl<T> FusedIterator for DoublyLinkedListIterRef<'_, T> {}
+
+impl<'a, T> Iterator for DoublyLinkedListIterMut<'a, T> {
+    type Item = &'a mut T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.front.map(|node| {
+            // SAFETY: the list is mutably borrowed for 'a, and remaining makes sure each node is
+            // handed out once, so no two &mut T alias
+            let node = unsafe { &mut *node.as_ptr() };
+            self.front = node.next;
+            self.remaining -= 1;
+            &mut node.value
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<T> DoubleEndedIterator for DoublyLinkedListIterMut<'_, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.back.map(|node| {
+            // SAFETY: as in next()
+            let node = unsafe { &mut *node.as_ptr() };
+            self.back = node.prev;
+            self.remaining -= 1;
+            &mut node.value
+        })
+    }
+}
+
+impl<T> ExactSizeIterator for DoublyLinkedListIterMut<'_, T> {}
+impl<T> FusedIterator for DoublyLinkedListIterMut<'_, T> {}
+
+impl<T> Iterator for DoublyLinkedListIter<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.list.pop_front()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.list.len, Some(self.list.len))
+    }
+}
+
+impl<T> DoubleEndedIterator for DoublyLinkedListIter<T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.list.pop_back()
+    }
+}
+
+impl<T> ExactSizeIterator for DoublyLinkedListIter<T> {}
+impl<T> FusedIterator for DoublyLinkedListIter<T> {}
+
+#[cfg(test)]
+mod testing {
+    use super::*;
+
+    fn list_of<T>(values: impl IntoIterator<Item = T>) -> DoublyLinkedList<T> {
+        let mut list = DoublyLinkedList::new();
+        for value in values {
+            list.push_back(value);
+        }
+        list
+    }
+
+    #[test]
+    fn can_push_and_pop_both_ends() {
+        let mut list = DoublyLinkedList::new();
+        list.push_back(1);
+        list.push_front(0);
+        list.push_back(2);
+        assert_eq!(3, list.len());
+        assert_eq!(Some(&0), list.front());
+        assert_eq!(Some(&2), list.back());
+
+        assert_eq!(Some(2), list.pop_back());
+        assert_eq!(Some(0), list.pop_front());
+        assert_eq!(Some(1), list.pop_back());
+        assert_eq!(None, list.pop_back());
+        assert_eq!(None, list.pop_front());
+        assert!(list.is_empty());
+
+        list.push_front(3);
+        assert_eq!(Some(&3), list.front());
+        assert_eq!(Some(&3), list.back());
+    }
+
+    #[test]
+    fn can_iter_both_ways() {
+        let list = list_of([0, 1, 2, 3]);
+        assert_eq!(vec![&0, &1, &2, &3], list.iter().collect::<Vec<_>>());
+        assert_eq!(vec![&3, &2, &1, &0], list.iter().rev().collect::<Vec<_>>());
+
+        // the two ends meet in the middle without handing anything out twice
+        let mut iter = list.iter();
+        assert_eq!(Some(&0), iter.next());
+        assert_eq!(Some(&3), iter.next_back());
+        assert_eq!(2, iter.len());
+        assert_eq!(Some(&1), iter.next());
+        assert_eq!(Some(&2), iter.next_back());
+        assert_eq!(None, iter.next());
+        assert_eq!(None, iter.next_back());
+    }
+
+    #[test]
+    fn can_iter_mut_both_ways() {
+        let mut list = list_of([0, 1, 2]);
+        for (i, value) in list.iter_mut().rev().enumerate() {
+            *value += i * 10;
+        }
+        assert_eq!(vec![20, 11, 2], list.into_iter().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn can_into_iter_both_ways() {
+        let list = list_of([String::from("a"), String::from("b"), String::from("c")]);
+        let mut iter = list.into_iter();
+        assert_eq!(Some("c".to_string()), iter.next_back());
+        assert_eq!(
+            vec!["a".to_string(), "b".to_string()],
+            iter.collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn drops_every_value() {
+        use std::rc::Rc;
+
+        let counter = Rc::new(());
+        let mut list = list_of((0..10).map(|_| Rc::clone(&counter)));
+        list.pop_front();
+        list.pop_back();
+        assert_eq!(9, Rc::strong_count(&counter));
+        drop(list);
+        assert_eq!(1, Rc::strong_count(&counter));
+    }
+
+    #[test]
+    fn standard_traits_work_element_wise() {
+        use std::collections::HashSet;
+
+        let list: DoublyLinkedList<i32> = (0..3).collect();
+        assert_eq!("[0, 1, 2]", format!("{list:?}"));
+
+        let mut copy = list.clone();
+        assert_eq!(list, copy);
+        copy.pop_back();
+        assert_ne!(list, copy);
+        assert!(copy < list);
+
+        let set: HashSet<_> = [list.clone(), list.clone(), copy].into_iter().collect();
+        assert_eq!(2, set.len());
+    }
+}
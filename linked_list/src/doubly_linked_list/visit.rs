@@ -0,0 +1,168 @@
+use super::{CursorMut, DoublyLinkedList};
+
+// visit_mut walks the list front to back like iter_mut, but the callback gets a Visit instead of
+// a plain &mut T, which also lets it edit the list around the value it's looking at
+// It's a CursorMut underneath: the callback does its edits, then visit_mut moves the cursor on
+// to the next value that was in the list before the visit started, so values inserted during the
+// walk are never visited themselves (otherwise inserting on every visit would never end)
+pub struct Visit<'c, 'a, T> {
+    cursor: &'c mut CursorMut<'a, T>,
+    removed: bool,
+    inserted: usize,
+    stopped: bool,
+}
+
+impl<T> DoublyLinkedList<T> {
+    pub fn visit_mut(&mut self, mut f: impl FnMut(&mut Visit<'_, '_, T>)) {
+        let mut cursor = self.cursor_front_mut();
+        while cursor.index().is_some() {
+            let mut visit = Visit {
+                cursor: &mut cursor,
+                removed: false,
+                inserted: 0,
+                stopped: false,
+            };
+            f(&mut visit);
+            let Visit {
+                removed,
+                inserted,
+                stopped,
+                ..
+            } = visit;
+            if stopped {
+                break;
+            }
+            // remove_current already moved the cursor past the removed node, and anything
+            // inserted in its place went in before the cursor
+            if !removed {
+                for _ in 0..=inserted {
+                    cursor.move_next();
+                }
+            }
+        }
+    }
+}
+
+impl<T> Visit<'_, '_, T> {
+    // Panics if the value has already been removed
+    pub fn value(&mut self) -> &mut T {
+        assert!(!self.removed, "value() called after remove()");
+        self.cursor.current().expect("a visit is always on a node")
+    }
+
+    // Takes the value out of the list
+    // Panics if it has already been removed
+    pub fn remove(&mut self) -> T {
+        assert!(!self.removed, "remove() called twice on the same visit");
+        self.removed = true;
+        self.cursor
+            .remove_current()
+            .expect("a visit is always on a node")
+    }
+
+    // Inserts a value after the current one (or where it was, if it's been removed)
+    // Several inserts in one visit keep the order they were made in. After a remove that's free
+    // since the cursor already sits after the gap, otherwise we step over the values inserted so
+    // far and back, so k inserts in one visit cost O(k^2) steps
+    pub fn insert_after(&mut self, value: T) {
+        if self.removed {
+            self.cursor.insert_before(value);
+        } else {
+            for _ in 0..self.inserted {
+                self.cursor.move_next();
+            }
+            self.cursor.insert_after(value);
+            for _ in 0..self.inserted {
+                self.cursor.move_prev();
+            }
+        }
+        self.inserted += 1;
+    }
+
+    // Ends the walk once this visit is over, the rest of the list isn't visited
+    pub fn stop(&mut self) {
+        self.stopped = true;
+    }
+}
+
+#[cfg(test)]
+mod testing {
+    use super::*;
+
+    fn values<T: Clone>(list: &DoublyLinkedList<T>) -> Vec<T> {
+        list.iter().cloned().collect()
+    }
+
+    #[test]
+    fn can_edit_values_in_place() {
+        let mut list: DoublyLinkedList<i32> = (1..=4).collect();
+        list.visit_mut(|v| *v.value() *= 10);
+        assert_eq!(vec![10, 20, 30, 40], values(&list));
+    }
+
+    #[test]
+    fn can_remove_while_visiting() {
+        let mut list: DoublyLinkedList<i32> = (1..=6).collect();
+        let mut removed = Vec::new();
+        list.visit_mut(|v| {
+            if *v.value() % 2 == 0 {
+                removed.push(v.remove());
+            }
+        });
+        assert_eq!(vec![1, 3, 5], values(&list));
+        assert_eq!(vec![2, 4, 6], removed);
+        assert_eq!((Some(&1), Some(&5)), (list.front(), list.back()));
+    }
+
+    #[test]
+    fn inserted_values_are_not_visited() {
+        let mut list: DoublyLinkedList<i32> = (1..=3).collect();
+        let mut visited = Vec::new();
+        list.visit_mut(|v| {
+            let value = *v.value();
+            visited.push(value);
+            v.insert_after(value * 10);
+            v.insert_after(value * 100);
+        });
+        assert_eq!(vec![1, 2, 3], visited);
+        assert_eq!(vec![1, 10, 100, 2, 20, 200, 3, 30, 300], values(&list));
+    }
+
+    #[test]
+    fn can_replace_a_value_with_several() {
+        let mut list: DoublyLinkedList<&str> = ["a", "bc", "d"].into_iter().collect();
+        list.visit_mut(|v| {
+            if v.value().len() > 1 {
+                let value = v.remove();
+                v.insert_after(&value[..1]);
+                v.insert_after(&value[1..]);
+            }
+        });
+        assert_eq!(vec!["a", "b", "c", "d"], values(&list));
+        assert_eq!(4, list.len());
+
+        // replacing the last value has to fix up the tail
+        let mut list: DoublyLinkedList<i32> = (1..=2).collect();
+        list.visit_mut(|v| {
+            if *v.value() == 2 {
+                v.remove();
+                v.insert_after(3);
+            }
+        });
+        list.push_back(4);
+        assert_eq!(vec![1, 3, 4], values(&list));
+    }
+
+    #[test]
+    fn can_stop_early() {
+        let mut list: DoublyLinkedList<i32> = (1..=5).collect();
+        list.visit_mut(|v| {
+            if *v.value() == 3 {
+                v.stop();
+            } else {
+                *v.value() = 0;
+            }
+        });
+        assert_eq!(vec![0, 0, 3, 4, 5], values(&list));
+    }
+}
@@ -0,0 +1,395 @@
+use core::cell::Cell;
+use core::fmt;
+use core::iter::FusedIterator;
+use core::marker::PhantomData;
+use core::ptr::NonNull;
+
+// An intrusive linked list: instead of the list allocating nodes that hold values, the values
+// themselves carry the links (a ListLink field) and the list just threads through them
+//
+//   struct Task {
+//       name: String,
+//       link: ListLink<Task>,
+//   }
+//
+// This is how kernels and allocators keep lists: the list never allocates, a value can be
+// unlinked in O(1) given only a reference to it, and the value can live wherever its owner wants
+// (on the stack, in a Vec, in a static...).
+//
+// Here the list borrows its values for 'a, so the borrow checker makes sure every value outlives
+// the list and can't be moved or dropped while it's linked. The links are Cells, because all we
+// ever have is a shared reference to a value and relinking still has to change its link.
+pub struct IntrusiveList<'a, A: IntrusiveAdapter> {
+    head: Option<NonNull<A::Value>>,
+    tail: Option<NonNull<A::Value>>,
+    len: usize,
+    _values: PhantomData<&'a A::Value>,
+}
+
+// The field a value embeds to be linked into an IntrusiveList
+// The links point at whole values (not at other ListLinks), so getting from a link back to the
+// value that holds it never needs pointer arithmetic
+pub struct ListLink<T> {
+    prev: Cell<Option<NonNull<T>>>,
+    next: Cell<Option<NonNull<T>>>,
+    // stops one value being linked into two lists (or one list twice) through the same link
+    linked: Cell<bool>,
+}
+
+/// Tells an IntrusiveList where in a value its ListLink is
+///
+/// # Safety
+///
+/// link() must always return the same ListLink for the same value, and that ListLink must not be
+/// used by any other adapter. The list relies on that to keep its links consistent, so getting it
+/// wrong is undefined behaviour, which is why this trait is unsafe to implement.
+/// The intrusive_adapter! macro writes a correct implementation for you.
+pub unsafe trait IntrusiveAdapter {
+    type Value;
+
+    fn link(value: &Self::Value) -> &ListLink<Self::Value>;
+}
+
+pub struct IntrusiveListIter<'a, A: IntrusiveAdapter> {
+    front: Option<NonNull<A::Value>>,
+    back: Option<NonNull<A::Value>>,
+    remaining: usize,
+    _values: PhantomData<&'a A::Value>,
+}
+
+// intrusive_adapter!(pub TaskAdapter = Task { link }) declares a TaskAdapter type that links
+// Tasks through their `link` field
+// The generated link() can only ever return that one field, which is what makes it sound
+#[macro_export]
+macro_rules! intrusive_adapter {
+    ($vis:vis $adapter:ident = $value:ty { $field:ident }) => {
+        $vis struct $adapter;
+
+        // SAFETY: link() always returns the same field of the value
+        unsafe impl $crate::IntrusiveAdapter for $adapter {
+            type Value = $value;
+
+            fn link(value: &$value) -> &$crate::ListLink<$value> {
+                &value.$field
+            }
+        }
+    };
+}
+
+impl<T> ListLink<T> {
+    pub const fn new() -> Self {
+        ListLink {
+            prev: Cell::new(None),
+            next: Cell::new(None),
+            linked: Cell::new(false),
+        }
+    }
+
+    pub fn is_linked(&self) -> bool {
+        self.linked.get()
+    }
+}
+
+impl<T> Default for ListLink<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Links are plumbing, printing their pointers wouldn't tell anyone anything useful
+impl<T> fmt::Debug for ListLink<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ListLink")
+            .field("linked", &self.is_linked())
+            .finish()
+    }
+}
+
+impl<'a, A: IntrusiveAdapter> IntrusiveList<'a, A> {
+    pub fn new() -> Self {
+        IntrusiveList {
+            head: None,
+            tail: None,
+            len: 0,
+            _values: PhantomData,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    // Panics if value is already linked into a list
+    pub fn push_front(&mut self, value: &'a A::Value) {
+        let link = Self::claim(value);
+        let node = NonNull::from(value);
+        link.next.set(self.head);
+        match self.head {
+            Some(head) => Self::link_of(head).prev.set(Some(node)),
+            None => self.tail = Some(node),
+        }
+        self.head = Some(node);
+        self.len += 1;
+    }
+
+    // Panics if value is already linked into a list
+    pub fn push_back(&mut self, value: &'a A::Value) {
+        let link = Self::claim(value);
+        let node = NonNull::from(value);
+        link.prev.set(self.tail);
+        match self.tail {
+            Some(tail) => Self::link_of(tail).next.set(Some(node)),
+            None => self.head = Some(node),
+        }
+        self.tail = Some(node);
+        self.len += 1;
+    }
+
+    // Popping hands back the reference we were given, the value itself never went anywhere
+    pub fn pop_front(&mut self) -> Option<&'a A::Value> {
+        self.head.map(|node| self.unlink(node))
+    }
+
+    pub fn pop_back(&mut self) -> Option<&'a A::Value> {
+        self.tail.map(|node| self.unlink(node))
+    }
+
+    pub fn front(&self) -> Option<&'a A::Value> {
+        self.head.map(Self::value)
+    }
+
+    pub fn back(&self) -> Option<&'a A::Value> {
+        self.tail.map(Self::value)
+    }
+
+    // Keeps only the values f returns true for
+    // Unlinking a value is O(1) since it knows its own neighbours
+    pub fn retain(&mut self, mut f: impl FnMut(&A::Value) -> bool) {
+        let mut current = self.head;
+        while let Some(node) = current {
+            current = Self::link_of(node).next.get();
+            if !f(Self::value(node)) {
+                self.unlink(node);
+            }
+        }
+    }
+
+    pub fn iter(&self) -> IntrusiveListIter<'a, A> {
+        IntrusiveListIter {
+            front: self.head,
+            back: self.tail,
+            remaining: self.len,
+            _values: PhantomData,
+        }
+    }
+
+    // Unlinks every value so they can go into another list
+    pub fn clear(&mut self) {
+        while self.pop_front().is_some() {}
+    }
+
+    fn claim(value: &A::Value) -> &ListLink<A::Value> {
+        let link = A::link(value);
+        assert!(!link.is_linked(), "value is already linked into a list");
+        link.linked.set(true);
+        link
+    }
+
+    // Every node in the list came from a &'a A::Value, so it's valid (and unmoved) for 'a
+    fn value(node: NonNull<A::Value>) -> &'a A::Value {
+        // SAFETY: see above, and we only ever read through it (the links are Cells)
+        unsafe { node.as_ref() }
+    }
+
+    fn link_of(node: NonNull<A::Value>) -> &'a ListLink<A::Value> {
+        A::link(Self::value(node))
+    }
+
+    // node must be linked into this list
+    fn unlink(&mut self, node: NonNull<A::Value>) -> &'a A::Value {
+        let link = Self::link_of(node);
+        let (prev, next) = (link.prev.take(), link.next.take());
+        match prev {
+            Some(prev) => Self::link_of(prev).next.set(next),
+            None => self.head = next,
+        }
+        match next {
+            Some(next) => Self::link_of(next).prev.set(prev),
+            None => self.tail = prev,
+        }
+        link.linked.set(false);
+        self.len -= 1;
+        Self::value(node)
+    }
+}
+
+impl<A: IntrusiveAdapter> Default for IntrusiveList<'_, A> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// The values outlive the list, so when it goes away they have to be told they're free again
+// (otherwise they could never be linked into another list)
+impl<A: IntrusiveAdapter> Drop for IntrusiveList<'_, A> {
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
+
+impl<A: IntrusiveAdapter> fmt::Debug for IntrusiveList<'_, A>
+where
+    A::Value: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<'a, A: IntrusiveAdapter> IntoIterator for &IntrusiveList<'a, A> {
+    type Item = &'a A::Value;
+    type IntoIter = IntrusiveListIter<'a, A>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, A: IntrusiveAdapter> Iterator for IntrusiveListIter<'a, A> {
+    type Item = &'a A::Value;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.front.map(|node| {
+            self.front = IntrusiveList::<'a, A>::link_of(node).next.get();
+            self.remaining -= 1;
+            IntrusiveList::<'a, A>::value(node)
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<'a, A: IntrusiveAdapter> DoubleEndedIterator for IntrusiveListIter<'a, A> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.back.map(|node| {
+            self.back = IntrusiveList::<'a, A>::link_of(node).prev.get();
+            self.remaining -= 1;
+            IntrusiveList::<'a, A>::value(node)
+        })
+    }
+}
+
+impl<A: IntrusiveAdapter> ExactSizeIterator for IntrusiveListIter<'_, A> {}
+impl<A: IntrusiveAdapter> FusedIterator for IntrusiveListIter<'_, A> {}
+
+#[cfg(test)]
+mod testing {
+    use super::*;
+
+    #[derive(Debug)]
+    struct Task {
+        id: u32,
+        link: ListLink<Task>,
+        // a value can sit in several lists at once, as long as each one has its own link
+        ready: ListLink<Task>,
+    }
+
+    impl Task {
+        fn new(id: u32) -> Self {
+            Task {
+                id,
+                link: ListLink::new(),
+                ready: ListLink::new(),
+            }
+        }
+    }
+
+    intrusive_adapter!(TaskAdapter = Task { link });
+    intrusive_adapter!(ReadyAdapter = Task { ready });
+
+    fn ids<A: IntrusiveAdapter<Value = Task>>(list: &IntrusiveList<'_, A>) -> Vec<u32> {
+        list.iter().map(|task| task.id).collect()
+    }
+
+    #[test]
+    fn can_push_and_pop_both_ends() {
+        let tasks: Vec<Task> = (0..3).map(Task::new).collect();
+        let mut list: IntrusiveList<TaskAdapter> = IntrusiveList::new();
+        list.push_back(&tasks[1]);
+        list.push_back(&tasks[2]);
+        list.push_front(&tasks[0]);
+        assert_eq!(vec![0, 1, 2], ids(&list));
+        assert_eq!(
+            vec![2, 1, 0],
+            list.iter().rev().map(|t| t.id).collect::<Vec<_>>()
+        );
+        assert!(tasks.iter().all(|t| t.link.is_linked()));
+
+        assert_eq!(Some(2), list.pop_back().map(|t| t.id));
+        assert_eq!(Some(0), list.pop_front().map(|t| t.id));
+        assert!(!tasks[0].link.is_linked());
+        assert_eq!(
+            (Some(1), Some(1)),
+            (list.front().map(|t| t.id), list.back().map(|t| t.id))
+        );
+        assert_eq!(1, list.len());
+    }
+
+    #[test]
+    fn values_can_be_in_one_list_per_link() {
+        let tasks: Vec<Task> = (0..4).map(Task::new).collect();
+        let mut all: IntrusiveList<TaskAdapter> = IntrusiveList::new();
+        let mut ready: IntrusiveList<ReadyAdapter> = IntrusiveList::new();
+        for task in &tasks {
+            all.push_back(task);
+            if task.id % 2 == 1 {
+                ready.push_front(task);
+            }
+        }
+        assert_eq!(vec![0, 1, 2, 3], ids(&all));
+        assert_eq!(vec![3, 1], ids(&ready));
+
+        all.retain(|task| task.id != 1);
+        assert_eq!(vec![0, 2, 3], ids(&all));
+        assert_eq!(vec![3, 1], ids(&ready));
+        assert!(!tasks[1].link.is_linked() && tasks[1].ready.is_linked());
+    }
+
+    #[test]
+    #[should_panic(expected = "already linked")]
+    fn cannot_link_a_value_twice() {
+        let task = Task::new(0);
+        let mut a: IntrusiveList<TaskAdapter> = IntrusiveList::new();
+        let mut b: IntrusiveList<TaskAdapter> = IntrusiveList::new();
+        a.push_back(&task);
+        b.push_back(&task);
+    }
+
+    #[test]
+    fn dropping_the_list_frees_its_values() {
+        let tasks: Vec<Task> = (0..3).map(Task::new).collect();
+        {
+            let mut list: IntrusiveList<TaskAdapter> = IntrusiveList::new();
+            for task in &tasks {
+                list.push_back(task);
+            }
+        }
+        assert!(tasks.iter().all(|t| !t.link.is_linked()));
+
+        let mut again: IntrusiveList<TaskAdapter> = IntrusiveList::new();
+        again.push_back(&tasks[2]);
+        assert_eq!(vec![2], ids(&again));
+    }
+}
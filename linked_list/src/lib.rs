@@ -1,2 +1,47 @@
+// Everything except LruCache (HashMap) and mpsc (threads) only needs an allocator, so without the
+// default std feature the crate builds as no_std + alloc, e.g. for embedded targets or wasm
+// Tests always have std
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+extern crate alloc;
+
+mod adapters;
+mod circular_list;
+mod doubly_linked_list;
+mod intrusive_list;
 mod linked_list;
+#[cfg(feature = "std")]
+mod lru_cache;
+mod macros;
+#[cfg(feature = "std")]
+pub mod mpsc;
+mod persistent_list;
+mod rc_doubly_linked_list;
+mod skip_list;
+mod slab_list;
+#[cfg(test)]
+mod soundness;
+mod unrolled_list;
 
+pub use adapters::{Deque, Queue, Stack};
+pub use circular_list::{
+    CircularCursorMut, CircularList, CircularListIter, CircularListIterMut, CircularListIterRef,
+};
+pub use doubly_linked_list::{
+    Cursor, CursorMut, DoublyLinkedList, DoublyLinkedListIter, DoublyLinkedListIterMut,
+    DoublyLinkedListIterRef, Visit,
+};
+pub use intrusive_list::{IntrusiveAdapter, IntrusiveList, IntrusiveListIter, ListLink};
+pub use linked_list::{
+    LinkedList, LinkedListChunks, LinkedListDrain, LinkedListExtractIf, LinkedListIter,
+    LinkedListIterMut, LinkedListIterRef, LinkedListPairs,
+};
+#[cfg(feature = "std")]
+pub use lru_cache::LruCache;
+pub use persistent_list::{PersistentList, PersistentListIterRef};
+pub use rc_doubly_linked_list::{
+    RcDoublyLinkedList, RcDoublyLinkedListIter, RcDoublyLinkedListValues,
+};
+pub use skip_list::{SkipList, SkipListIter};
+pub use slab_list::{SlabKey, SlabList, SlabListIter, SlabListIterRef};
+pub use unrolled_list::{UnrolledList, UnrolledListIter, UnrolledListIterRef};
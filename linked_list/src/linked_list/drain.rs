@@ -0,0 +1,163 @@
+use alloc::boxed::Box;
+use core::iter::FusedIterator;
+use core::ptr::NonNull;
+
+use super::{LinkedList, Node};
+
+// drain() is into_iter() for a list we only have a &mut to
+// It hands out every value by value and leaves the list empty (but still usable) afterwards
+pub struct LinkedListDrain<'a, T> {
+    list: &'a mut LinkedList<T>,
+}
+
+// extract_if() walks the list once, removing and handing out only the values pred matches
+// prev/next remember where we are in the list between calls to next()
+pub struct LinkedListExtractIf<'a, T, F> {
+    list: &'a mut LinkedList<T>,
+    // the last node we've kept, None while we're still at the front
+    prev: Option<NonNull<Node<T>>>,
+    // the next node to look at
+    next: Option<NonNull<Node<T>>>,
+    pred: F,
+}
+
+impl<T> LinkedList<T> {
+    pub fn drain(&mut self) -> LinkedListDrain<'_, T> {
+        LinkedListDrain { list: self }
+    }
+
+    // pred gets a &mut so it can change values on the way past, like retain_mut
+    // Values pred rejects stay linked in their original order. If the iterator is dropped before
+    // it's finished, everything it hasn't looked at yet stays in the list too
+    pub fn extract_if<F>(&mut self, pred: F) -> LinkedListExtractIf<'_, T, F>
+    where
+        F: FnMut(&mut T) -> bool,
+    {
+        LinkedListExtractIf {
+            prev: None,
+            next: self.head,
+            list: self,
+            pred,
+        }
+    }
+}
+
+impl<T> Iterator for LinkedListDrain<'_, T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.list.pop_front()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.list.len, Some(self.list.len))
+    }
+}
+
+impl<T> ExactSizeIterator for LinkedListDrain<'_, T> {}
+impl<T> FusedIterator for LinkedListDrain<'_, T> {}
+
+// Whatever the caller didn't take still gets removed, drain() always leaves the list empty
+impl<T> Drop for LinkedListDrain<'_, T> {
+    fn drop(&mut self) {
+        while self.list.pop_front().is_some() {}
+    }
+}
+
+impl<T, F> Iterator for LinkedListExtractIf<'_, T, F>
+where
+    F: FnMut(&mut T) -> bool,
+{
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some(node) = self.next {
+            // SAFETY: node is a live node of the list, which we're borrowing mutably
+            let node_ref = unsafe { &mut *node.as_ptr() };
+            self.next = node_ref.next;
+            if !(self.pred)(&mut node_ref.value) {
+                self.prev = Some(node);
+                continue;
+            }
+
+            match self.prev {
+                // SAFETY: prev is a live node of the list
+                Some(prev) => unsafe { (*prev.as_ptr()).next = self.next },
+                None => self.list.head = self.next,
+            }
+            if self.next.is_none() {
+                self.list.tail = self.prev;
+            }
+            self.list.len -= 1;
+            // SAFETY: node is no longer reachable from the list, so this is the only free
+            let node = unsafe { Box::from_raw(node.as_ptr()) };
+            return Some(node.value);
+        }
+        None
+    }
+
+    // Anything from none to all of the values we haven't looked at yet could match
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some(self.list.len))
+    }
+}
+
+impl<T, F> FusedIterator for LinkedListExtractIf<'_, T, F> where F: FnMut(&mut T) -> bool {}
+
+#[cfg(test)]
+mod testing {
+    use super::*;
+
+    #[test]
+    fn can_drain() {
+        let mut ll: LinkedList<i32> = (0..4).collect();
+        let drained: Vec<_> = ll.drain().collect();
+        assert_eq!(vec![0, 1, 2, 3], drained);
+        assert!(ll.is_empty());
+
+        // the list is still usable afterwards
+        ll.push_back(7);
+        assert_eq!(Some(&7), ll.back());
+    }
+
+    #[test]
+    fn dropping_a_drain_empties_the_list() {
+        let mut ll: LinkedList<i32> = (0..4).collect();
+        let mut drain = ll.drain();
+        assert_eq!(4, drain.len());
+        assert_eq!(Some(0), drain.next());
+        drop(drain);
+        assert!(ll.is_empty());
+        assert_eq!((None, None), (ll.front(), ll.back()));
+    }
+
+    #[test]
+    fn can_extract_if() {
+        let mut ll: LinkedList<i32> = (0..10).collect();
+        let evens: Vec<_> = ll.extract_if(|v| *v % 2 == 0).collect();
+        assert_eq!(vec![0, 2, 4, 6, 8], evens);
+        assert_eq!(vec![1, 3, 5, 7, 9], ll.iter().copied().collect::<Vec<_>>());
+        assert_eq!(5, ll.len());
+
+        // extracting the last value moves the tail back
+        let nines: Vec<_> = ll.extract_if(|v| *v == 9).collect();
+        assert_eq!(vec![9], nines);
+        assert_eq!(Some(&7), ll.back());
+        ll.push_back(11);
+        assert_eq!(vec![1, 3, 5, 7, 11], Vec::from(ll));
+    }
+
+    #[test]
+    fn dropping_extract_if_keeps_unvisited_values() {
+        let mut ll: LinkedList<i32> = (0..6).collect();
+        {
+            let mut big = ll.extract_if(|v| {
+                *v *= 10;
+                *v >= 20
+            });
+            assert_eq!(Some(20), big.next());
+        }
+        // 0 and 1 were visited (and changed) and kept, 3.. were never looked at
+        assert_eq!(vec![0, 10, 3, 4, 5], Vec::from(ll));
+    }
+}
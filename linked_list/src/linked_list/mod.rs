@@ -1,34 +1,599 @@
+mod drain;
+#[cfg(feature = "serde")]
+mod serde_impls;
+mod sort;
+mod windows;
+
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+use core::fmt;
+use core::hash::{Hash, Hasher};
+use core::iter::FusedIterator;
+use core::marker::PhantomData;
+use core::mem;
+use core::ops::{Index, IndexMut};
+use core::ptr::NonNull;
+
+pub use drain::{LinkedListDrain, LinkedListExtractIf};
+pub use windows::{LinkedListChunks, LinkedListPairs};
+
 // Our base LinkedList representation
-// We can make value generic later
+// T is the type of the values stored in the list, every node in a list holds the same T
+// The list knows where its first and last nodes are
+// When head is None the list is empty, so an empty list doesn't need a dummy node
+// len is kept up to date by everything that adds or removes nodes, so it never has to be counted
+//
+// Why raw pointers instead of Box?
+// With Box every node is owned by the node before it, which is lovely right up until we want a
+// tail pointer: the last node would be owned by its predecessor's Box *and* pointed at from the
+// list. Box promises it's the only way to reach its contents, so reaching the last node through
+// a second pointer is undefined behaviour even if it looks like it works.
+// So the links are raw pointers and the list as a whole owns every node instead. Nodes are still
+// allocated with Box::new and freed by turning the pointer back into a Box (see alloc/pop_front).
+// NonNull is a raw pointer that's promised to never be null, so Option<NonNull<_>> is still just
+// one pointer wide (None is stored as null).
+pub struct LinkedList<T> {
+    head: Option<NonNull<Node<T>>>,
+    tail: Option<NonNull<Node<T>>>,
+    len: usize,
+    // Raw pointers don't tell the compiler that we own the nodes, PhantomData does
+    // It makes the list behave (for drop checking and variance) as if it held Box<Node<T>>
+    _owns: PhantomData<Box<Node<T>>>,
+}
+
+// A single link in the chain
 // When next is None we're at the end of the list
-// In order to create recursive types we have to store fixed sized types on the stack
-// We use a Box to create a fixed size pointer on the heap pointing to the heap
-struct LinkedList {
-    value: i32,
-    next: Option<Box<LinkedList>>,
+struct Node<T> {
+    value: T,
+    next: Option<NonNull<Node<T>>>,
 }
 
 // This wrapper type lets us implement an unconsumed .iter() method on LinkedList
-// Note: because next_node contains a reference, we have to have an explicit lifetime annotation
-struct LinkedListIterRef<'a> {
-    next_node: Option<&'a LinkedList>,
+// Note: the iterator borrows the list, but a raw pointer doesn't carry a lifetime, so PhantomData
+// holds the 'a for us (and stops the list being changed while we iterate)
+// remaining lets the iterator report its exact length without walking the rest of the list
+pub struct LinkedListIterRef<'a, T> {
+    next_node: Option<NonNull<Node<T>>>,
+    remaining: usize,
+    _list: PhantomData<&'a T>,
+}
+
+// The mutable version of LinkedListIterRef, it hands out &mut T so values can be changed in place
+pub struct LinkedListIterMut<'a, T> {
+    next_node: Option<NonNull<Node<T>>>,
+    remaining: usize,
+    _list: PhantomData<&'a mut T>,
 }
 
 // This wrapper type lets us implement IntoIterator on our LinkedList
 // This lets us use for loops on LinkedList
 // Note: .into_iter() *consumes* underlying types
-struct LinkedListIter {
-    next_node: Option<LinkedList>,
+// Owning the list means iterating is just popping off the front until it's empty
+pub struct LinkedListIter<T> {
+    list: LinkedList<T>,
 }
 
 // .iter() is not part of a standard rust trait
 // it's a common and idiomatic "inherent impl" on many collection types
-impl LinkedList {
+// Note: generic impls declare their type parameters up front with impl<T>
+impl<T> LinkedList<T> {
+    pub fn new() -> Self {
+        LinkedList {
+            head: None,
+            tail: None,
+            len: 0,
+            _owns: PhantomData,
+        }
+    }
+
+    // O(1) since we track the length as we go
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    // O(1): the new node just points at the old head
+    pub fn push_front(&mut self, value: T) {
+        let node = Self::alloc(value, self.head);
+        // the first node of an empty list is also its last
+        if self.tail.is_none() {
+            self.tail = Some(node);
+        }
+        self.head = Some(node);
+        self.len += 1;
+    }
+
+    // O(1): the second node (if any) becomes the head
+    pub fn pop_front(&mut self) -> Option<T> {
+        self.head.map(|head| {
+            // SAFETY: head was made by alloc() and we unlink it right away, so turning it back into
+            // a Box (which frees it when it goes out of scope) happens exactly once
+            let node = unsafe { Box::from_raw(head.as_ptr()) };
+            self.head = node.next;
+            if self.head.is_none() {
+                self.tail = None;
+            }
+            self.len -= 1;
+            node.value
+        })
+    }
+
+    // O(1): we know where the last node is, so we just hang a new one off it
+    pub fn push_back(&mut self, value: T) {
+        self.push_node_back(Self::alloc(value, None));
+    }
+
+    // O(n): knowing the last node doesn't help here, we need the one *before* it to unlink it,
+    // and the only way to find that in a singly linked list is to walk from the front
+    pub fn pop_back(&mut self) -> Option<T> {
+        match self.len {
+            0 => None,
+            1 => self.pop_front(),
+            len => {
+                let new_tail = self.node_at(len - 2);
+                // SAFETY: new_tail and its next (the old tail) are live nodes of this list, and
+                // after this the old tail isn't linked from anywhere so we can free it
+                let node = unsafe {
+                    let old_tail = (*new_tail.as_ptr()).next.take()?;
+                    Box::from_raw(old_tail.as_ptr())
+                };
+                self.tail = Some(new_tail);
+                self.len -= 1;
+                Some(node.value)
+            }
+        }
+    }
+
+    // Moves every node of other onto the end of this list in O(1), leaving other empty
+    // Nothing is copied or reallocated, the last node of self just gets linked to other's head
+    pub fn append(&mut self, other: &mut LinkedList<T>) {
+        match self.tail {
+            None => mem::swap(self, other),
+            Some(tail) => {
+                if let Some(other_head) = other.head.take() {
+                    // SAFETY: tail is a live node of self, and other_head now belongs to self too
+                    unsafe { (*tail.as_ptr()).next = Some(other_head) };
+                    self.tail = other.tail.take();
+                    self.len += mem::take(&mut other.len);
+                }
+            }
+        }
+    }
+
+    // The opposite of append: cuts the list in two after the first `at` values and returns the
+    // second half as a new list. Like append no values move, only the links at the cut change
+    // O(at), since we have to walk to the cut. Panics if at > len(), like Vec::split_off
+    pub fn split_off(&mut self, at: usize) -> LinkedList<T> {
+        let len = self.len;
+        assert!(
+            at <= len,
+            "split index {at} out of bounds for a list of length {len}"
+        );
+
+        if at == 0 {
+            return mem::take(self);
+        }
+
+        let mut back = LinkedList::new();
+        let last = self.node_at(at - 1);
+        // SAFETY: last is a live node of self, everything after it now belongs to back
+        back.head = unsafe { (*last.as_ptr()).next.take() };
+        if back.head.is_some() {
+            back.tail = self.tail;
+            back.len = len - at;
+            self.tail = Some(last);
+            self.len = at;
+        }
+        back
+    }
+
+    // Keeps the first len values and drops the rest, does nothing if the list isn't longer than len
+    // The cut off nodes are split into a list of their own and dropped as a whole, so they're
+    // freed by Drop's pop_front loop and a long tail doesn't need a deep stack
+    pub fn truncate(&mut self, len: usize) {
+        if len < self.len {
+            drop(self.split_off(len));
+        }
+    }
+
+    // Removes the last n values and returns them as a new list, in order
+    // If n >= len() the whole list is taken and this one is left empty
+    // Still O(len - n), since the cut is found by walking from the front
+    pub fn take_last(&mut self, n: usize) -> LinkedList<T> {
+        self.split_off(self.len.saturating_sub(n))
+    }
+
+    // Like split_off, but the cut goes before the first value pred matches
+    // If nothing matches self is left alone and the returned list is empty
+    pub fn split_when(&mut self, pred: impl FnMut(&T) -> bool) -> LinkedList<T> {
+        match self.position(pred) {
+            Some(at) => self.split_off(at),
+            None => LinkedList::new(),
+        }
+    }
+
+    // Rotates the list so the value at index n becomes the front, the first n values move to the
+    // back in their original order. n wraps around, so rotating by len() (or any multiple of it)
+    // does nothing
+    // O(n) to walk to the pivot, then it's just three links: the old tail points at the old head,
+    // the node before the pivot becomes the tail and the pivot becomes the head
+    pub fn rotate_left(&mut self, n: usize) {
+        if self.len == 0 {
+            return;
+        }
+        let n = n % self.len;
+        if n == 0 {
+            return;
+        }
+
+        let new_tail = self.node_at(n - 1);
+        // SAFETY: new_tail and the old tail are live nodes of this list, and since 0 < n < len
+        // they're different nodes with new_tail before the old tail
+        unsafe {
+            let new_head = (*new_tail.as_ptr()).next.take();
+            if let Some(old_tail) = self.tail {
+                (*old_tail.as_ptr()).next = self.head;
+            }
+            self.head = new_head;
+        }
+        self.tail = Some(new_tail);
+    }
+
+    // The other direction: the last n values move to the front
+    // Note: a singly linked list can only walk forwards, so this walks len - n nodes
+    pub fn rotate_right(&mut self, n: usize) {
+        if self.len == 0 {
+            return;
+        }
+        self.rotate_left(self.len - n % self.len);
+    }
+
+    // Splits the list into (the values pred matches, the values it doesn't), both in their
+    // original order
+    // The nodes themselves move into the two lists, so nothing is allocated or freed
+    pub fn partition(mut self, mut pred: impl FnMut(&T) -> bool) -> (LinkedList<T>, LinkedList<T>) {
+        let mut matched = LinkedList::new();
+        let mut unmatched = LinkedList::new();
+
+        // The chain is taken out of self first, every node then belongs either to the chain we're
+        // still walking or to one of the two new lists, never both
+        let mut current = self.head.take();
+        self.tail = None;
+        self.len = 0;
+
+        while let Some(node) = current {
+            // SAFETY: node is the first node of the chain we own, nothing else points at it once
+            // we've taken its next
+            let matches = unsafe {
+                current = (*node.as_ptr()).next.take();
+                pred(&(*node.as_ptr()).value)
+            };
+            if matches {
+                matched.push_node_back(node);
+            } else {
+                unmatched.push_node_back(node);
+            }
+        }
+        (matched, unmatched)
+    }
+
+    // Keeps only the values f returns true for, in their original order, in a single pass
+    // Kept nodes are left exactly where they are, only the removed ones are unlinked and freed
+    pub fn retain(&mut self, mut f: impl FnMut(&T) -> bool) {
+        self.retain_mut(|value| f(value));
+    }
+
+    // Same as retain, but f can change the values it looks at (whether it keeps them or not)
+    pub fn retain_mut(&mut self, mut f: impl FnMut(&mut T) -> bool) {
+        // prev is the last node we've kept so far, None while we're still at the front
+        let mut prev: Option<NonNull<Node<T>>> = None;
+        let mut current = self.head;
+
+        while let Some(node) = current {
+            // SAFETY: node is a live node of this list and nothing else is borrowing it
+            let node_ref = unsafe { &mut *node.as_ptr() };
+            current = node_ref.next;
+            if f(&mut node_ref.value) {
+                prev = Some(node);
+                continue;
+            }
+
+            // The list is relinked around the node before it's freed, so even if f panics on a
+            // later value the list is still a valid chain
+            match prev {
+                // SAFETY: prev is a live node of this list
+                Some(prev) => unsafe { (*prev.as_ptr()).next = current },
+                None => self.head = current,
+            }
+            if current.is_none() {
+                self.tail = prev;
+            }
+            self.len -= 1;
+            // SAFETY: node is no longer reachable from the list, so this is the only free
+            drop(unsafe { Box::from_raw(node.as_ptr()) });
+        }
+    }
+
+    // The read API
+    // Note: get/get_mut are O(index), a linked list has to walk to find things
+    // front and back are O(1) since we keep pointers to both ends
+
+    pub fn front(&self) -> Option<&T> {
+        // SAFETY: the node lives as long as the list, and &self stops anyone from changing it
+        self.head.map(|node| unsafe { &(*node.as_ptr()).value })
+    }
+
+    pub fn front_mut(&mut self) -> Option<&mut T> {
+        // SAFETY: &mut self means we're the only ones looking at the list
+        self.head.map(|node| unsafe { &mut (*node.as_ptr()).value })
+    }
+
+    pub fn back(&self) -> Option<&T> {
+        // SAFETY: as in front()
+        self.tail.map(|node| unsafe { &(*node.as_ptr()).value })
+    }
+
+    pub fn back_mut(&mut self) -> Option<&mut T> {
+        // SAFETY: as in front_mut()
+        self.tail.map(|node| unsafe { &mut (*node.as_ptr()).value })
+    }
+
+    pub fn get(&self, index: usize) -> Option<&T> {
+        self.iter().nth(index)
+    }
+
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut T> {
+        self.iter_mut().nth(index)
+    }
+
+    // Searching, all O(n) and all stop at the first match
+    // They're thin wrappers over the iterator methods of the same names, just so common questions
+    // about a list don't need an .iter() every time
+
+    pub fn contains(&self, value: &T) -> bool
+    where
+        T: PartialEq,
+    {
+        self.iter().any(|v| v == value)
+    }
+
+    pub fn find(&self, mut pred: impl FnMut(&T) -> bool) -> Option<&T> {
+        self.iter().find(|v| pred(v))
+    }
+
+    pub fn position(&self, pred: impl FnMut(&T) -> bool) -> Option<usize> {
+        self.iter().position(pred)
+    }
+
+    // Inserts value so that it ends up at position index, shifting everything after it along
+    // index == len() is allowed and appends to the end
+    // Out of bounds we hand the value back in the Err instead of dropping it
+    pub fn insert(&mut self, index: usize, value: T) -> Result<(), T> {
+        if index > self.len {
+            return Err(value);
+        }
+
+        if index == 0 {
+            self.push_front(value);
+        } else if index == self.len {
+            self.push_back(value);
+        } else {
+            // somewhere in the middle: the new node goes between prev and prev's next
+            let prev = self.node_at(index - 1);
+            // SAFETY: prev is a live node of this list
+            unsafe {
+                let node = Self::alloc(value, (*prev.as_ptr()).next);
+                (*prev.as_ptr()).next = Some(node);
+            }
+            self.len += 1;
+        }
+        Ok(())
+    }
+
+    // Removes and returns the value at position index, None if there isn't one
+    pub fn remove(&mut self, index: usize) -> Option<T> {
+        if index >= self.len {
+            return None;
+        }
+        if index == 0 {
+            return self.pop_front();
+        }
+
+        let prev = self.node_at(index - 1);
+        // SAFETY: prev and its next are live nodes of this list, once the removed node's next takes
+        // its place in the chain nothing points at it anymore and we can free it
+        let node = unsafe {
+            let removed = (*prev.as_ptr()).next?;
+            let node = Box::from_raw(removed.as_ptr());
+            (*prev.as_ptr()).next = node.next;
+            node
+        };
+        if node.next.is_none() {
+            self.tail = Some(prev);
+        }
+        self.len -= 1;
+        Some(node.value)
+    }
+
     // Note: iter() takes a *reference* to a LinkedList
     // This is the fundamental distinction from .into_iter() that lets us iter over references
-    fn iter(&self) -> LinkedListIterRef {
+    // The '_ says the returned iterator borrows from self
+    pub fn iter(&self) -> LinkedListIterRef<'_, T> {
         LinkedListIterRef {
-            next_node: Some(self),
+            next_node: self.head,
+            remaining: self.len,
+            _list: PhantomData,
+        }
+    }
+
+    // Same idea as iter() but with a mutable borrow of the list
+    // While the iterator lives nobody else can look at the list, which is what makes it safe to
+    // hand out a &mut T for every node
+    pub fn iter_mut(&mut self) -> LinkedListIterMut<'_, T> {
+        LinkedListIterMut {
+            next_node: self.head,
+            remaining: self.len,
+            _list: PhantomData,
+        }
+    }
+
+    // Nodes are allocated as Boxes and turned into raw pointers, the list is then responsible for
+    // turning them back into Boxes (with Box::from_raw) exactly once to free them
+    fn alloc(value: T, next: Option<NonNull<Node<T>>>) -> NonNull<Node<T>> {
+        NonNull::from(Box::leak(Box::new(Node { value, next })))
+    }
+
+    // Links an existing node (with no next) onto the end of the list, the list now owns it
+    fn push_node_back(&mut self, node: NonNull<Node<T>>) {
+        match self.tail {
+            // SAFETY: tail points at a live node owned by this list
+            Some(tail) => unsafe { (*tail.as_ptr()).next = Some(node) },
+            None => self.head = Some(node),
+        }
+        self.tail = Some(node);
+        self.len += 1;
+    }
+
+    // The node at position index, O(index)
+    // Callers make sure index < len, so every node we step through has a next
+    fn node_at(&self, index: usize) -> NonNull<Node<T>> {
+        let mut node = self.head.expect("index is within bounds");
+        for _ in 0..index {
+            // SAFETY: node is a live node of this list
+            node = unsafe { (*node.as_ptr()).next }.expect("index is within bounds");
+        }
+        node
+    }
+}
+
+// The same pop-until-empty loop as DoublyLinkedList's Drop, for the same reasons
+impl<T> Drop for LinkedList<T> {
+    fn drop(&mut self) {
+        while self.pop_front().is_some() {}
+    }
+}
+
+// See DoublyLinkedList, the nodes here are just as unshared
+unsafe impl<T: Send> Send for LinkedList<T> {}
+unsafe impl<T: Sync> Sync for LinkedList<T> {}
+
+// Default is the standard trait for "give me an empty one", clippy expects it alongside new()
+impl<T> Default for LinkedList<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Extend appends everything an iterator yields to the end of the list
+// push_back is O(1), so extending by n values is O(n)
+impl<T> Extend<T> for LinkedList<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for value in iter {
+            self.push_back(value);
+        }
+    }
+}
+
+// FromIterator is what makes .collect::<LinkedList<_>>() work
+impl<T> FromIterator<T> for LinkedList<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut ll = LinkedList::new();
+        ll.extend(iter);
+        ll
+    }
+}
+
+// From gives us the conversion, and Into comes for free in the other direction
+// (LinkedList::from(vec) and vec.into() do the same thing)
+impl<T> From<Vec<T>> for LinkedList<T> {
+    fn from(values: Vec<T>) -> Self {
+        values.into_iter().collect()
+    }
+}
+
+// We can't write impl Into<Vec<T>> for LinkedList<T> directly in terms of From on our own type,
+// but we're allowed to implement From<LinkedList<T>> for Vec<T> since LinkedList is ours
+impl<T> From<LinkedList<T>> for Vec<T> {
+    fn from(ll: LinkedList<T>) -> Self {
+        // into_iter() is an ExactSizeIterator, so collect() allocates the Vec once
+        ll.into_iter().collect()
+    }
+}
+
+impl<T: Clone> LinkedList<T> {
+    // Copies (clones) every value out of a slice, the slice itself is left alone
+    pub fn from_slice(values: &[T]) -> Self {
+        values.iter().cloned().collect()
+    }
+}
+
+// Index lets us write ll[i] just like with a Vec or slice
+// Unlike a Vec this is O(i), and just like a Vec it panics when i is out of bounds
+impl<T> Index<usize> for LinkedList<T> {
+    type Output = T;
+
+    fn index(&self, index: usize) -> &Self::Output {
+        let len = self.len;
+        self.get(index)
+            .unwrap_or_else(|| panic!("index {index} out of bounds for a list of length {len}"))
+    }
+}
+
+// IndexMut is the ll[i] = x version, it builds on Index (Output comes from there)
+impl<T> IndexMut<usize> for LinkedList<T> {
+    fn index_mut(&mut self, index: usize) -> &mut Self::Output {
+        let len = self.len;
+        self.get_mut(index)
+            .unwrap_or_else(|| panic!("index {index} out of bounds for a list of length {len}"))
+    }
+}
+
+// The standard traits, all implemented element by element
+// We can't #[derive] these: derive would compare/print/hash the nodes and their links, where we
+// only care about the values (and Clone needs to rebuild the chain, not share it)
+impl<T: fmt::Debug> fmt::Debug for LinkedList<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<T: Clone> Clone for LinkedList<T> {
+    fn clone(&self) -> Self {
+        self.iter().cloned().collect()
+    }
+}
+
+impl<T: PartialEq> PartialEq for LinkedList<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.len == other.len && self.iter().eq(other.iter())
+    }
+}
+
+impl<T: Eq> Eq for LinkedList<T> {}
+
+impl<T: PartialOrd> PartialOrd for LinkedList<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        self.iter().partial_cmp(other.iter())
+    }
+}
+
+impl<T: Ord> Ord for LinkedList<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.iter().cmp(other.iter())
+    }
+}
+
+// The length goes in first so that e.g. [[1], [2, 3]] and [[1, 2], [3]] hash differently when
+// lists are nested
+impl<T: Hash> Hash for LinkedList<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.len.hash(state);
+        for value in self.iter() {
+            value.hash(state);
         }
     }
 }
@@ -37,79 +602,105 @@ impl LinkedList {
 // It has two associated types:
 //  Item => the type returned by the iterator
 //  IntoIter => the wrapper type implementing Iterator<Item = Self::Item>
-impl IntoIterator for LinkedList {
-    type Item = i32;
-    type IntoIter = LinkedListIter;
+impl<T> IntoIterator for LinkedList<T> {
+    type Item = T;
+    type IntoIter = LinkedListIter<T>;
 
     fn into_iter(self) -> Self::IntoIter {
-        LinkedListIter {
-            next_node: Some(self),
-        }
+        LinkedListIter { list: self }
     }
 }
 
 // This is the meat of our unconsumed iteration logic
 // Because we're iterating over references we need explicit lifetype annotations
-impl<'a> Iterator for LinkedListIterRef<'a> {
-    type Item = &'a i32;
+impl<'a, T> Iterator for LinkedListIterRef<'a, T> {
+    type Item = &'a T;
 
     fn next(&mut self) -> Option<Self::Item> {
         // Note: .map() is a very common idiom to work with Option types when:
         //  - when Option::Some, you need to operate on the value inside of option and get an Option
         //  - when Option::None, you need to get a None
         self.next_node.map(|node| {
-            self.next_node = node.next.as_deref();
-            // even though node is a &LinkedList, node.value is still an i32 (it's copied!)
+            // SAFETY: the list is borrowed for 'a, so the node stays alive and unchanged for 'a
+            let node = unsafe { &*node.as_ptr() };
+            self.next_node = node.next;
+            self.remaining -= 1;
+            // node is a &'a Node<T>, so borrowing its value gives us a &'a T
             &node.value
         })
     }
+
+    // size_hint is how adapters like collect() know how much to allocate up front
+    // It returns (lower bound, upper bound), and we know both exactly
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
 }
 
-impl Iterator for LinkedListIter {
-    type Item = i32;
+// ExactSizeIterator is a marker that size_hint is exact, it gives us .len() on the iterator
+// The default implementation of len() just reads size_hint, so there's nothing to write here
+impl<T> ExactSizeIterator for LinkedListIterRef<'_, T> {}
+impl<T> FusedIterator for LinkedListIterRef<'_, T> {}
+
+impl<'a, T> Iterator for LinkedListIterMut<'a, T> {
+    type Item = &'a mut T;
 
     fn next(&mut self) -> Option<Self::Item> {
-        // We have to move the underlying LinkedList from next_node into node
-        // We have to use take() here since next_node owns the LinkedList value
-        // .take() is a common tool for getting an owned option from a shared reference
-        // next_node is a shared reference to a LinkedList behind &mut self
-        self.next_node.take().map(|node| {
-            // The commented line below wouldn't work
-            // as_deref() returns a reference to the underling LinkedList but does not move
-            // ownership out of the Box - the Box still lives!
-            // Since the Box still lives and owns the LinkedList it's illegal to try to move
-            // *ll_ref to self.next_node
-            // self.next_node = node.next.as_deref().map(|ll_ref| *ll_ref);
-            self.next_node = node.next.map(|bx| *bx); // this works since bx owns the LinkedList
-            node.value
+        self.next_node.map(|node| {
+            // SAFETY: the list is mutably borrowed for 'a and we move past every node as soon as
+            // we hand out its value, so no two &mut T we return ever point at the same value
+            let node = unsafe { &mut *node.as_ptr() };
+            self.next_node = node.next;
+            self.remaining -= 1;
+            &mut node.value
         })
     }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<T> ExactSizeIterator for LinkedListIterMut<'_, T> {}
+impl<T> FusedIterator for LinkedListIterMut<'_, T> {}
+
+impl<T> Iterator for LinkedListIter<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // pop_front already does all the work of moving the value out of the first node
+        self.list.pop_front()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.list.len, Some(self.list.len))
+    }
 }
 
+impl<T> ExactSizeIterator for LinkedListIter<T> {}
+impl<T> FusedIterator for LinkedListIter<T> {}
+
 #[cfg(test)]
 mod testing {
     use super::*;
 
+    fn list_of<T>(values: impl IntoIterator<Item = T>) -> LinkedList<T> {
+        let mut ll = LinkedList::new();
+        for value in values {
+            ll.push_back(value);
+        }
+        ll
+    }
+
     #[test]
     fn can_construct_linked_list() {
-        let _ = LinkedList {
-            value: 0,
-            next: Some(Box::new(LinkedList {
-                value: 1,
-                next: None,
-            })),
-        };
+        let ll: LinkedList<i32> = LinkedList::new();
+        assert!(ll.iter().next().is_none());
     }
 
     #[test]
     fn can_into_iter_list() {
-        let ll = LinkedList {
-            value: 0,
-            next: Some(Box::new(LinkedList {
-                value: 1,
-                next: None,
-            })),
-        };
+        let ll = list_of([0, 1]);
 
         let v: Vec<i32> = ll.into_iter().collect();
         assert_eq!(vec![0, 1], v);
@@ -117,17 +708,450 @@ mod testing {
 
     #[test]
     fn can_iter_list() {
-        let ll = LinkedList {
-            value: 0,
-            next: Some(Box::new(LinkedList {
-                value: 1,
-                next: None,
-            })),
-        };
+        let ll = list_of([0, 1]);
 
         let v1: Vec<&i32> = ll.iter().collect();
         let v2: Vec<&i32> = ll.iter().collect();
         assert_eq!(vec![&0, &1], v1);
         assert_eq!(vec![&0, &1], v2);
     }
+
+    #[test]
+    fn iterators_know_their_length_and_stay_done() {
+        let mut ll = list_of([0, 1, 2]);
+        let mut iter = ll.iter();
+        iter.next();
+        assert_eq!(2, iter.len());
+        assert_eq!(2, iter.by_ref().count());
+        assert_eq!((None, None), (iter.next(), iter.next()));
+
+        assert_eq!(3, ll.iter_mut().len());
+        let mut owned = ll.into_iter();
+        assert_eq!((3, Some(3)), owned.size_hint());
+        owned.by_ref().for_each(drop);
+        assert_eq!(None, owned.next());
+    }
+
+    #[test]
+    fn can_hold_non_copy_values() {
+        let ll = list_of([String::from("zero"), String::from("one")]);
+
+        let lens: Vec<usize> = ll.iter().map(|s| s.len()).collect();
+        assert_eq!(vec![4, 3], lens);
+        let v: Vec<String> = ll.into_iter().collect();
+        assert_eq!(vec!["zero", "one"], v);
+    }
+
+    #[test]
+    fn can_push_and_pop_front() {
+        let mut ll = LinkedList::new();
+        ll.push_front(1);
+        ll.push_front(0);
+        assert_eq!(vec![&0, &1], ll.iter().collect::<Vec<_>>());
+
+        assert_eq!(Some(0), ll.pop_front());
+        assert_eq!(Some(1), ll.pop_front());
+        assert_eq!(None, ll.pop_front());
+    }
+
+    #[test]
+    fn can_push_and_pop_back() {
+        let mut ll = LinkedList::new();
+        ll.push_back(0);
+        ll.push_back(1);
+        ll.push_front(-1);
+        assert_eq!(vec![&-1, &0, &1], ll.iter().collect::<Vec<_>>());
+
+        assert_eq!(Some(1), ll.pop_back());
+        assert_eq!(Some(0), ll.pop_back());
+        assert_eq!(Some(-1), ll.pop_back());
+        assert_eq!(None, ll.pop_back());
+
+        // the list is still usable once it's been emptied
+        ll.push_back(2);
+        assert_eq!(Some(2), ll.pop_front());
+    }
+
+    #[test]
+    fn tracks_length() {
+        let mut ll = LinkedList::new();
+        assert_eq!(0, ll.len());
+        assert!(ll.is_empty());
+
+        ll.push_back(1);
+        ll.push_front(0);
+        ll.push_back(2);
+        assert_eq!(3, ll.len());
+        assert!(!ll.is_empty());
+
+        ll.pop_back();
+        ll.pop_front();
+        assert_eq!(1, ll.len());
+        ll.pop_back();
+        ll.pop_back();
+        assert_eq!(0, ll.len());
+    }
+
+    #[test]
+    fn iterators_know_their_length() {
+        let ll = list_of([0, 1, 2]);
+
+        let mut iter = ll.iter();
+        assert_eq!(3, iter.len());
+        iter.next();
+        assert_eq!((2, Some(2)), iter.size_hint());
+
+        let mut into_iter = ll.into_iter();
+        into_iter.next();
+        into_iter.next();
+        assert_eq!(1, into_iter.len());
+        into_iter.next();
+        assert_eq!(0, into_iter.len());
+    }
+
+    #[test]
+    fn can_iter_mut_list() {
+        let mut ll = list_of([0, 1, 2]);
+
+        for value in ll.iter_mut() {
+            *value *= 10;
+        }
+        assert_eq!(vec![&0, &10, &20], ll.iter().collect::<Vec<_>>());
+
+        let mut iter = ll.iter_mut();
+        assert_eq!(3, iter.len());
+        if let Some(first) = iter.next() {
+            *first = -1;
+        }
+        assert_eq!(2, iter.len());
+        assert_eq!(Some(-1), ll.pop_front());
+    }
+
+    #[test]
+    fn can_insert_at_index() {
+        let mut ll = LinkedList::new();
+        assert_eq!(Ok(()), ll.insert(0, 1));
+        assert_eq!(Ok(()), ll.insert(0, 0));
+        assert_eq!(Ok(()), ll.insert(2, 3));
+        assert_eq!(Ok(()), ll.insert(2, 2));
+        assert_eq!(Err(5), ll.insert(5, 5));
+        assert_eq!(4, ll.len());
+        assert_eq!(vec![0, 1, 2, 3], ll.into_iter().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn can_remove_at_index() {
+        let mut ll = list_of([0, 1, 2, 3]);
+        assert_eq!(None, ll.remove(4));
+        assert_eq!(Some(2), ll.remove(2));
+        assert_eq!(Some(3), ll.remove(2));
+        assert_eq!(Some(0), ll.remove(0));
+        assert_eq!(1, ll.len());
+        assert_eq!(vec![1], ll.into_iter().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn can_read_by_position() {
+        let mut ll = list_of([0, 1, 2]);
+        assert_eq!(Some(&0), ll.front());
+        assert_eq!(Some(&2), ll.back());
+        assert_eq!(Some(&1), ll.get(1));
+        assert_eq!(None, ll.get(3));
+        assert_eq!(1, ll[1]);
+
+        *ll.front_mut().unwrap() = 10;
+        *ll.back_mut().unwrap() = 12;
+        *ll.get_mut(1).unwrap() = 11;
+        assert_eq!(vec![&10, &11, &12], ll.iter().collect::<Vec<_>>());
+
+        ll[1] += 10;
+        assert_eq!(21, ll[1]);
+
+        let empty: LinkedList<i32> = LinkedList::new();
+        assert_eq!(None, empty.front());
+        assert_eq!(None, empty.back());
+    }
+
+    #[test]
+    #[should_panic(expected = "index 3 out of bounds")]
+    fn indexing_out_of_bounds_panics() {
+        let ll = list_of([0, 1, 2]);
+        let _ = ll[3];
+    }
+
+    #[test]
+    fn can_convert_to_and_from_vecs() {
+        let ll = LinkedList::from(vec![0, 1, 2]);
+        assert_eq!(3, ll.len());
+        let v: Vec<i32> = ll.into();
+        assert_eq!(vec![0, 1, 2], v);
+
+        let words = ["a".to_string(), "b".to_string()];
+        let ll = LinkedList::from_slice(&words);
+        assert_eq!(vec![&words[0], &words[1]], ll.iter().collect::<Vec<_>>());
+
+        let mut ll: LinkedList<i32> = (0..3).collect();
+        ll.extend(vec![3, 4]);
+        ll.extend(Vec::new());
+        assert_eq!(5, ll.len());
+        assert_eq!(Vec::from(ll), vec![0, 1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn standard_traits_work_element_wise() {
+        use std::collections::HashMap;
+
+        let ll: LinkedList<i32> = (0..3).collect();
+        assert_eq!("[0, 1, 2]", format!("{ll:?}"));
+
+        let mut copy = ll.clone();
+        assert_eq!(ll, copy);
+        copy.push_back(3);
+        assert_ne!(ll, copy);
+        assert!(ll < copy);
+        assert_eq!(3, ll.len());
+
+        let mut counts = HashMap::new();
+        *counts.entry(ll.clone()).or_insert(0) += 1;
+        *counts.entry((0..3).collect::<LinkedList<_>>()).or_insert(0) += 1;
+        assert_eq!(Some(&2), counts.get(&ll));
+
+        assert_eq!(LinkedList::<i32>::new(), LinkedList::default());
+    }
+
+    #[test]
+    // a million nodes takes far too long under Miri, the soundness tests cover Drop there
+    #[cfg_attr(miri, ignore)]
+    fn can_drop_long_lists() {
+        let ll: LinkedList<usize> = (0..1_000_000).collect();
+        assert_eq!(1_000_000, ll.len());
+        drop(ll);
+    }
+
+    #[test]
+    fn can_append_lists() {
+        let mut ll = list_of(0..3);
+        let mut other = list_of(3..6);
+        ll.append(&mut other);
+        assert_eq!(
+            vec![0, 1, 2, 3, 4, 5],
+            ll.iter().copied().collect::<Vec<_>>()
+        );
+        assert_eq!(6, ll.len());
+        assert!(other.is_empty());
+        assert_eq!(None, other.front());
+
+        // the tail has to follow the appended nodes
+        assert_eq!(Some(&5), ll.back());
+        ll.push_back(6);
+        assert_eq!(Some(6), ll.pop_back());
+        assert_eq!(Some(5), ll.remove(5));
+        assert_eq!(Some(&4), ll.back());
+
+        // other is still a perfectly usable list afterwards
+        other.push_back(7);
+        ll.append(&mut other);
+        assert_eq!(vec![0, 1, 2, 3, 4, 7], Vec::from(ll));
+    }
+
+    #[test]
+    fn can_append_to_and_from_empty_lists() {
+        let mut ll: LinkedList<i32> = LinkedList::new();
+        let mut other = list_of(0..2);
+        ll.append(&mut other);
+        assert_eq!(vec![0, 1], ll.iter().copied().collect::<Vec<_>>());
+        assert!(other.is_empty());
+
+        ll.append(&mut other);
+        assert_eq!(2, ll.len());
+        assert_eq!(Some(&1), ll.back());
+    }
+
+    #[test]
+    fn can_split_off() {
+        let mut ll = list_of(0..5);
+        let back = ll.split_off(2);
+        assert_eq!(vec![0, 1], ll.iter().copied().collect::<Vec<_>>());
+        assert_eq!(vec![2, 3, 4], back.iter().copied().collect::<Vec<_>>());
+        assert_eq!((Some(&1), Some(&4)), (ll.back(), back.back()));
+        assert_eq!((2, 3), (ll.len(), back.len()));
+
+        // splitting at either end leaves one side empty
+        let mut everything = ll.split_off(0);
+        assert!(ll.is_empty());
+        assert_eq!(None, ll.back());
+        assert!(everything.split_off(2).is_empty());
+        assert_eq!(vec![0, 1], Vec::from(everything));
+
+        // the halves are independent lists that can be stitched back together
+        let mut front = list_of(0..2);
+        front.append(&mut list_of(2..5));
+        assert_eq!(vec![0, 1, 2, 3, 4], Vec::from(front));
+    }
+
+    #[test]
+    fn can_truncate() {
+        let mut ll = list_of(0..5);
+        ll.truncate(10);
+        assert_eq!(5, ll.len());
+        ll.truncate(2);
+        assert_eq!((2, Some(&1)), (ll.len(), ll.back()));
+        ll.push_back(9);
+        assert_eq!(vec![0, 1, 9], ll.iter().copied().collect::<Vec<_>>());
+        ll.truncate(0);
+        assert!(ll.is_empty() && ll.back().is_none());
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn can_truncate_long_lists() {
+        let mut ll = list_of(0..1_000_000);
+        ll.truncate(1);
+        assert_eq!(vec![0], Vec::from(ll));
+    }
+
+    #[test]
+    fn can_take_last() {
+        let mut ll = list_of(0..5);
+        let last = ll.take_last(2);
+        assert_eq!(vec![3, 4], Vec::from(last));
+        assert_eq!((3, Some(&2)), (ll.len(), ll.back()));
+        assert!(ll.take_last(0).is_empty());
+        assert_eq!(vec![0, 1, 2], Vec::from(ll.take_last(7)));
+        assert!(ll.is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "split index 4 out of bounds")]
+    fn split_off_out_of_bounds_panics() {
+        list_of(0..3).split_off(4);
+    }
+
+    #[test]
+    fn can_split_when() {
+        let mut ll = list_of([1, 3, 5, 6, 7, 8]);
+        let evens_onwards = ll.split_when(|v| v % 2 == 0);
+        assert_eq!(vec![1, 3, 5], ll.iter().copied().collect::<Vec<_>>());
+        assert_eq!(vec![6, 7, 8], Vec::from(evens_onwards));
+
+        assert!(ll.split_when(|v| *v > 10).is_empty());
+        assert_eq!(3, ll.len());
+    }
+
+    #[test]
+    fn can_retain() {
+        let mut ll = list_of(0..10);
+        ll.retain(|v| v % 3 == 0);
+        assert_eq!(vec![0, 3, 6, 9], ll.iter().copied().collect::<Vec<_>>());
+        assert_eq!((4, Some(&9)), (ll.len(), ll.back()));
+
+        // dropping the last node moves the tail back
+        ll.retain(|v| *v < 5);
+        assert_eq!(Some(&3), ll.back());
+        ll.push_back(4);
+        assert_eq!(vec![0, 3, 4], ll.iter().copied().collect::<Vec<_>>());
+
+        ll.retain(|_| false);
+        assert!(ll.is_empty());
+        assert_eq!((None, None), (ll.front(), ll.back()));
+    }
+
+    #[test]
+    fn can_retain_mut() {
+        let mut ll = list_of(1..=6);
+        ll.retain_mut(|v| {
+            *v *= 10;
+            *v > 20
+        });
+        assert_eq!(vec![30, 40, 50, 60], Vec::from(ll));
+    }
+
+    #[test]
+    fn retain_drops_removed_values() {
+        use std::rc::Rc;
+
+        let tracker = Rc::new(());
+        let mut ll = list_of((0..4).map(|_| Rc::clone(&tracker)));
+        let mut keep = [true, false, false, true].into_iter();
+        ll.retain(|_| keep.next().unwrap());
+        assert_eq!(2, ll.len());
+        assert_eq!(3, Rc::strong_count(&tracker));
+    }
+
+    #[test]
+    fn can_search() {
+        let ll = list_of(["apple", "banana", "cherry"].map(String::from));
+        assert!(ll.contains(&"banana".to_string()));
+        assert!(!ll.contains(&"durian".to_string()));
+
+        assert_eq!(Some(&"banana".to_string()), ll.find(|s| s.starts_with('b')));
+        assert_eq!(None, ll.find(|s| s.is_empty()));
+
+        assert_eq!(Some(2), ll.position(|s| s.len() == 6 && s.ends_with('y')));
+        assert_eq!(None, ll.position(|s| s.len() > 6));
+
+        let empty: LinkedList<i32> = LinkedList::new();
+        assert!(!empty.contains(&0));
+        assert_eq!(None, empty.position(|_| true));
+    }
+
+    #[test]
+    fn can_rotate() {
+        let mut ll = list_of(0..5);
+        ll.rotate_left(2);
+        assert_eq!(vec![2, 3, 4, 0, 1], ll.iter().copied().collect::<Vec<_>>());
+        assert_eq!((Some(&2), Some(&1)), (ll.front(), ll.back()));
+
+        ll.rotate_right(2);
+        assert_eq!(vec![0, 1, 2, 3, 4], ll.iter().copied().collect::<Vec<_>>());
+
+        // rotations wrap around
+        ll.rotate_left(5);
+        assert_eq!(vec![0, 1, 2, 3, 4], ll.iter().copied().collect::<Vec<_>>());
+        ll.rotate_right(11);
+        assert_eq!(vec![4, 0, 1, 2, 3], ll.iter().copied().collect::<Vec<_>>());
+        ll.push_back(5);
+        assert_eq!(vec![4, 0, 1, 2, 3, 5], Vec::from(ll));
+
+        let mut empty: LinkedList<i32> = LinkedList::new();
+        empty.rotate_left(3);
+        empty.rotate_right(3);
+        assert!(empty.is_empty());
+    }
+
+    #[test]
+    fn rotate_matches_vec_rotate() {
+        for n in 0..12 {
+            let mut ll = list_of(0..7);
+            let mut v: Vec<i32> = (0..7).collect();
+            ll.rotate_left(n);
+            v.rotate_left(n % 7);
+            assert_eq!(v, ll.iter().copied().collect::<Vec<_>>());
+            ll.rotate_right(n);
+            v.rotate_right(n % 7);
+            assert_eq!(v, Vec::from(ll));
+        }
+    }
+
+    #[test]
+    fn can_partition() {
+        let (evens, odds) = list_of(0..10).partition(|v| v % 2 == 0);
+        assert_eq!(
+            vec![0, 2, 4, 6, 8],
+            evens.iter().copied().collect::<Vec<_>>()
+        );
+        assert_eq!(
+            vec![1, 3, 5, 7, 9],
+            odds.iter().copied().collect::<Vec<_>>()
+        );
+        assert_eq!((5, 5), (evens.len(), odds.len()));
+        assert_eq!((Some(&8), Some(&9)), (evens.back(), odds.back()));
+
+        let (all, none) = list_of(["a", "b"]).partition(|_| true);
+        assert_eq!(vec!["a", "b"], Vec::from(all));
+        assert!(none.is_empty());
+
+        let (none, all) = LinkedList::<i32>::new().partition(|_| true);
+        assert!(none.is_empty() && all.is_empty());
+    }
 }
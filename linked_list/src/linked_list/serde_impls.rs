@@ -0,0 +1,79 @@
+use core::fmt;
+use core::marker::PhantomData;
+
+use serde::de::{Deserialize, Deserializer, SeqAccess, Visitor};
+use serde::ser::{Serialize, SerializeSeq, Serializer};
+
+use super::LinkedList;
+
+// A list serializes as a plain sequence of its values, exactly like a Vec would
+// That means a LinkedList and a Vec holding the same values are interchangeable on the wire
+impl<T: Serialize> Serialize for LinkedList<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut seq = serializer.serialize_seq(Some(self.len))?;
+        for value in self.iter() {
+            seq.serialize_element(value)?;
+        }
+        seq.end()
+    }
+}
+
+// Deserializing goes through a Visitor: serde calls back into it with whatever the input holds,
+// and we only know how to handle sequences
+// The PhantomData is there because the visitor has to mention T without holding one
+struct LinkedListVisitor<T>(PhantomData<T>);
+
+impl<'de, T: Deserialize<'de>> Visitor<'de> for LinkedListVisitor<T> {
+    type Value = LinkedList<T>;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("a sequence")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut ll = LinkedList::new();
+        // push_back is O(1), so values can go straight on in the order they arrive
+        while let Some(value) = seq.next_element()? {
+            ll.push_back(value);
+        }
+        Ok(ll)
+    }
+}
+
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for LinkedList<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_seq(LinkedListVisitor(PhantomData))
+    }
+}
+
+#[cfg(test)]
+mod testing {
+    use super::*;
+    use crate::list;
+
+    #[test]
+    fn can_round_trip_through_json() {
+        let ll = list![1, 2, 3];
+        let json = serde_json::to_string(&ll).unwrap();
+        assert_eq!("[1,2,3]", json);
+        assert_eq!(ll, serde_json::from_str::<LinkedList<i32>>(&json).unwrap());
+
+        let empty: LinkedList<String> = serde_json::from_str("[]").unwrap();
+        assert!(empty.is_empty());
+    }
+
+    #[test]
+    fn can_nest_in_other_values() {
+        let nested = vec![list![String::from("a")], list![], list![String::from("b")]];
+        let json = serde_json::to_string(&nested).unwrap();
+        assert_eq!(r#"[["a"],[],["b"]]"#, json);
+        let back: Vec<LinkedList<String>> = serde_json::from_str(&json).unwrap();
+        assert_eq!(nested, back);
+    }
+
+    #[test]
+    fn rejects_non_sequences() {
+        let err = serde_json::from_str::<LinkedList<i32>>("{\"a\": 1}").unwrap_err();
+        assert!(err.to_string().contains("expected a sequence"), "{err}");
+    }
+}
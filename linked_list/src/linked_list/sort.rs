@@ -0,0 +1,328 @@
+use core::cmp::Ordering;
+use core::ptr::NonNull;
+
+use super::{LinkedList, Node};
+
+// Merge sort is the natural sort for linked lists:
+//  - splitting a chain in two and merging two chains only needs relinking, no values are moved
+//    and no extra buffer is needed
+//  - it only ever walks the chains front to back, which is all a singly linked list can do
+// It's also stable: equal values keep their relative order.
+impl<T> LinkedList<T> {
+    pub fn sort(&mut self)
+    where
+        T: Ord,
+    {
+        self.sort_by(T::cmp);
+    }
+
+    // cmp is FnMut (not Fn) so comparators can keep state, e.g. count how often they're called
+    pub fn sort_by(&mut self, mut cmp: impl FnMut(&T, &T) -> Ordering) {
+        if self.len <= 1 {
+            return;
+        }
+
+        // The chain is taken out of the list while we sort it, so if cmp panics halfway through
+        // the list is simply empty (and the nodes leak) instead of pointing into a half-merged mess
+        let len = self.len;
+        let head = merge_sort(self.detach(), len, &mut cmp);
+
+        self.head = head;
+        self.len = len;
+        // merging relinks everything, so the old last node could have ended up anywhere
+        self.tail = Some(self.node_at(len - 1));
+    }
+
+    pub fn sort_by_key<K: Ord>(&mut self, mut key: impl FnMut(&T) -> K) {
+        self.sort_by(|a, b| key(a).cmp(&key(b)));
+    }
+
+    // Merges two already sorted lists into one sorted list in O(n + m)
+    // This is the merge step of merge sort on its own: the nodes of both lists are relinked into
+    // one chain, no values are moved and nothing is allocated
+    // On ties the values from a go first
+    pub fn merge_sorted(a: LinkedList<T>, b: LinkedList<T>) -> LinkedList<T>
+    where
+        T: Ord,
+    {
+        Self::merge_sorted_by(a, b, T::cmp)
+    }
+
+    // If a or b isn't sorted by cmp the result is still every value of both, just not sorted
+    pub fn merge_sorted_by(
+        mut a: LinkedList<T>,
+        mut b: LinkedList<T>,
+        mut cmp: impl FnMut(&T, &T) -> Ordering,
+    ) -> LinkedList<T> {
+        // The last node of the merged chain is whichever of the two last nodes sorts last
+        // (b's on a tie, since a's values go first)
+        let tail = match (a.back(), b.back()) {
+            (Some(x), Some(y)) if cmp(y, x) == Ordering::Less => a.tail,
+            (Some(_), Some(_)) | (None, _) => b.tail,
+            (Some(_), None) => a.tail,
+        };
+
+        let mut merged = LinkedList::new();
+        merged.len = a.len + b.len;
+        // Both lists are emptied before we merge, so if cmp panics they won't free nodes that
+        // are now linked into the other chain (they just leak instead)
+        let (a_head, b_head) = (a.detach(), b.detach());
+        merged.head = merge_links(a_head, b_head, &mut cmp);
+        merged.tail = tail;
+        merged
+    }
+
+    // Inserts value after every value that's <= it, so a sorted list stays sorted and equal values
+    // come out in the order they went in. Together with pop_front that makes the list a simple
+    // priority queue (smallest first).
+    // Every insert walks the list to find its spot, so it's O(n). That's fine for short lists or
+    // values that mostly arrive in order (appending at the back is O(1)), but for anything big a
+    // BinaryHeap (O(log n) push and pop) or a BTreeSet (O(log n) insert, ordered iteration) is the
+    // better choice
+    pub fn insert_sorted(&mut self, value: T)
+    where
+        T: Ord,
+    {
+        self.insert_sorted_by(value, T::cmp);
+    }
+
+    pub fn insert_sorted_by(&mut self, value: T, mut cmp: impl FnMut(&T, &T) -> Ordering) {
+        match self.back() {
+            Some(back) if cmp(back, &value) == Ordering::Greater => {}
+            // empty, or value goes after everything: no need to walk
+            _ => return self.push_back(value),
+        }
+
+        // find the last node that doesn't sort after value, the new node goes right after it
+        // The walk always stops before the tail, which we know sorts after value
+        let mut prev: Link<T> = None;
+        let mut next = self.head;
+        while let Some(node) = next {
+            // SAFETY: node is a live node of this list
+            let node_ref = unsafe { &*node.as_ptr() };
+            if cmp(&node_ref.value, &value) == Ordering::Greater {
+                break;
+            }
+            prev = Some(node);
+            next = node_ref.next;
+        }
+
+        match prev {
+            None => self.push_front(value),
+            // SAFETY: prev is a live node of this list
+            Some(prev) => unsafe {
+                let node = Self::alloc(value, (*prev.as_ptr()).next);
+                (*prev.as_ptr()).next = Some(node);
+                self.len += 1;
+            },
+        }
+    }
+
+    pub fn is_sorted(&self) -> bool
+    where
+        T: PartialOrd,
+    {
+        self.is_sorted_by(|a, b| a <= b)
+    }
+
+    // cmp(a, b) says whether a may come before b, like slice::is_sorted_by
+    pub fn is_sorted_by(&self, mut cmp: impl FnMut(&T, &T) -> bool) -> bool {
+        self.iter().is_sorted_by(|a, b| cmp(a, b))
+    }
+
+    // Takes the whole chain out of the list, leaving it empty, and returns the chain's head
+    fn detach(&mut self) -> Link<T> {
+        self.tail = None;
+        self.len = 0;
+        self.head.take()
+    }
+}
+
+// A chain is a run of linked nodes, identified by the link to its first node
+type Link<T> = Option<NonNull<Node<T>>>;
+
+// Sorts a chain of len nodes by splitting it in half, sorting the halves and merging them
+// Recursion only goes log2(len) deep since the halves shrink every time
+fn merge_sort<T>(head: Link<T>, len: usize, cmp: &mut impl FnMut(&T, &T) -> Ordering) -> Link<T> {
+    if len <= 1 {
+        return head;
+    }
+
+    let mid = len / 2;
+    let back = split_link(head, mid);
+    let front = merge_sort(head, mid, cmp);
+    let back = merge_sort(back, len - mid, cmp);
+    merge_links(front, back, cmp)
+}
+
+// Cuts a chain after `at` nodes (at > 0) and returns everything after the cut
+// The chain must have at least `at` nodes
+pub(super) fn split_link<T>(head: Link<T>, at: usize) -> Link<T> {
+    let mut node = head.expect("chain has at least `at` nodes");
+    for _ in 1..at {
+        // SAFETY: every node in a chain is live and owned by whoever owns the chain
+        node = unsafe { (*node.as_ptr()).next }.expect("chain has at least `at` nodes");
+    }
+    // SAFETY: as above
+    unsafe { (*node.as_ptr()).next.take() }
+}
+
+// Merges two sorted chains into one sorted chain by relinking their nodes
+// On ties the node from `a` goes first, which is what makes merge sort stable
+pub(super) fn merge_links<T>(
+    mut a: Link<T>,
+    mut b: Link<T>,
+    cmp: &mut impl FnMut(&T, &T) -> Ordering,
+) -> Link<T> {
+    let mut head = None;
+    // tail is the last node of the merged chain so far, where the next node gets hung off
+    let mut tail: Link<T> = None;
+    // once either chain runs out the rest of the other one is already sorted
+    // SAFETY (for the whole loop): every node we touch belongs to exactly one of a, b or the
+    // merged chain, and all of them are live
+    while let (Some(x), Some(y)) = (a, b) {
+        let from = unsafe {
+            if cmp(&(*y.as_ptr()).value, &(*x.as_ptr()).value) == Ordering::Less {
+                &mut b
+            } else {
+                &mut a
+            }
+        };
+
+        // unhook the front node of whichever chain it came from and hang it off the tail
+        let node = from.expect("matched Some above");
+        unsafe {
+            *from = (*node.as_ptr()).next.take();
+            match tail {
+                Some(t) => (*t.as_ptr()).next = Some(node),
+                None => head = Some(node),
+            }
+        }
+        tail = Some(node);
+    }
+
+    let rest = a.or(b);
+    match tail {
+        // SAFETY: tail is the last node of the merged chain
+        Some(t) => unsafe { (*t.as_ptr()).next = rest },
+        None => head = rest,
+    }
+    head
+}
+
+#[cfg(test)]
+mod testing {
+    use super::*;
+    use crate::list;
+
+    #[test]
+    fn can_sort() {
+        let mut ll: LinkedList<i32> = list![5, 3, 8, 1, 9, 2, 7, 3];
+        ll.sort();
+        assert_eq!(vec![1, 2, 3, 3, 5, 7, 8, 9], Vec::from(ll));
+
+        let mut empty: LinkedList<i32> = LinkedList::new();
+        empty.sort();
+        assert!(empty.is_empty());
+
+        let mut one: LinkedList<i32> = list![1];
+        one.sort();
+        assert_eq!(vec![1], Vec::from(one));
+    }
+
+    #[test]
+    fn sort_is_stable() {
+        let mut ll: LinkedList<(i32, char)> =
+            list![(2, 'a'), (1, 'b'), (2, 'c'), (1, 'd'), (0, 'e')];
+        ll.sort_by_key(|(n, _)| *n);
+        assert_eq!(
+            vec![(0, 'e'), (1, 'b'), (1, 'd'), (2, 'a'), (2, 'c')],
+            Vec::from(ll)
+        );
+    }
+
+    #[test]
+    fn sort_by_matches_std_sort() {
+        let values: Vec<u32> = (0..1000_u32)
+            .map(|i| i.wrapping_mul(2654435761) % 97)
+            .collect();
+        let mut ll = LinkedList::from(values.clone());
+        ll.sort_by(|a, b| b.cmp(a));
+
+        let mut expected = values;
+        expected.sort_by(|a, b| b.cmp(a));
+        assert_eq!(1000, ll.len());
+        assert_eq!(expected, Vec::from(ll));
+    }
+
+    #[test]
+    fn insert_sorted_keeps_the_list_sorted() {
+        let mut ll = LinkedList::new();
+        for value in [5, 1, 4, 1, 9, 2, 6, 5, 3] {
+            ll.insert_sorted(value);
+            assert!(ll.is_sorted());
+        }
+        assert_eq!(Some(&9), ll.back());
+        ll.push_back(10);
+        assert_eq!(vec![1, 1, 2, 3, 4, 5, 5, 6, 9, 10], Vec::from(ll));
+    }
+
+    #[test]
+    fn insert_sorted_puts_equal_values_last() {
+        let mut ll: LinkedList<(i32, char)> = list![(1, 'a'), (2, 'a'), (3, 'a')];
+        ll.insert_sorted_by((2, 'b'), |x, y| x.0.cmp(&y.0));
+        ll.insert_sorted_by((0, 'b'), |x, y| x.0.cmp(&y.0));
+        ll.insert_sorted_by((3, 'b'), |x, y| x.0.cmp(&y.0));
+        assert_eq!(
+            vec![(0, 'b'), (1, 'a'), (2, 'a'), (2, 'b'), (3, 'a'), (3, 'b')],
+            Vec::from(ll)
+        );
+    }
+
+    #[test]
+    fn can_check_if_sorted() {
+        assert!(LinkedList::<i32>::new().is_sorted());
+        assert!(list![1].is_sorted());
+        assert!(list![1, 1, 2].is_sorted());
+        assert!(!list![1, 3, 2].is_sorted());
+        assert!(list![3, 2, 2].is_sorted_by(|a, b| a >= b));
+        // NaN isn't <= anything, so it can never be sorted
+        assert!(!list![1.0, f64::NAN].is_sorted());
+    }
+
+    #[test]
+    fn can_merge_sorted_lists() {
+        let a: LinkedList<i32> = list![1, 4, 6, 9];
+        let b: LinkedList<i32> = list![0, 2, 4, 5, 10, 11];
+        let mut merged = LinkedList::merge_sorted(a, b);
+        assert_eq!(10, merged.len());
+        assert_eq!(Some(&11), merged.back());
+        merged.push_back(12);
+        assert_eq!(vec![0, 1, 2, 4, 4, 5, 6, 9, 10, 11, 12], Vec::from(merged));
+
+        // the tail comes from a when a has the largest value
+        let mut merged = LinkedList::merge_sorted(list![3, 8], list![1]);
+        assert_eq!(Some(&8), merged.back());
+        merged.push_back(9);
+        assert_eq!(vec![1, 3, 8, 9], Vec::from(merged));
+
+        let merged = LinkedList::merge_sorted(LinkedList::new(), list![1, 2]);
+        assert_eq!(vec![1, 2], Vec::from(merged));
+        let merged = LinkedList::merge_sorted(list![1, 2], LinkedList::new());
+        assert_eq!((2, Some(&2)), (merged.len(), merged.back()));
+        assert!(LinkedList::<i32>::merge_sorted(LinkedList::new(), LinkedList::new()).is_empty());
+    }
+
+    #[test]
+    fn merge_sorted_prefers_the_first_list_on_ties() {
+        let a: LinkedList<(i32, char)> = list![(1, 'a'), (2, 'a')];
+        let b: LinkedList<(i32, char)> = list![(1, 'b'), (2, 'b')];
+        let mut merged = LinkedList::merge_sorted_by(a, b, |x, y| x.0.cmp(&y.0));
+        assert_eq!(Some(&(2, 'b')), merged.back());
+        merged.push_back((3, 'c'));
+        assert_eq!(
+            vec![(1, 'a'), (1, 'b'), (2, 'a'), (2, 'b'), (3, 'c')],
+            Vec::from(merged)
+        );
+    }
+}
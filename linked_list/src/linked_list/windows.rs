@@ -0,0 +1,120 @@
+use alloc::vec::Vec;
+use core::iter::FusedIterator;
+
+use super::{LinkedList, LinkedListIterRef};
+
+// Borrowing iterators that look at more than one value at a time
+// A slice can hand out &[T] windows because its values sit next to each other in memory. A list's
+// values don't, so instead these hold on to references to consecutive values: both are built on
+// top of iter(), and everything they return borrows from the list for 'a, not from the iterator
+
+// Every overlapping pair of neighbours: [1, 2, 3] gives (1, 2) and (2, 3)
+pub struct LinkedListPairs<'a, T> {
+    prev: Option<&'a T>,
+    iter: LinkedListIterRef<'a, T>,
+}
+
+// The values in groups of size, the last group is shorter if len isn't a multiple of size
+pub struct LinkedListChunks<'a, T> {
+    size: usize,
+    iter: LinkedListIterRef<'a, T>,
+}
+
+impl<T> LinkedList<T> {
+    pub fn pairs(&self) -> LinkedListPairs<'_, T> {
+        let mut iter = self.iter();
+        LinkedListPairs {
+            prev: iter.next(),
+            iter,
+        }
+    }
+
+    // Panics if size is 0, like slice::chunks
+    pub fn chunks(&self, size: usize) -> LinkedListChunks<'_, T> {
+        assert!(size != 0, "chunk size must be non-zero");
+        LinkedListChunks {
+            size,
+            iter: self.iter(),
+        }
+    }
+}
+
+impl<'a, T> Iterator for LinkedListPairs<'a, T> {
+    type Item = (&'a T, &'a T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let next = self.iter.next()?;
+        // the second value of this pair is the first value of the next one
+        let prev = self.prev.replace(next)?;
+        Some((prev, next))
+    }
+
+    // one pair for every value after the first
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<T> ExactSizeIterator for LinkedListPairs<'_, T> {}
+impl<T> FusedIterator for LinkedListPairs<'_, T> {}
+
+impl<'a, T> Iterator for LinkedListChunks<'a, T> {
+    type Item = Vec<&'a T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let chunk: Vec<&T> = self.iter.by_ref().take(self.size).collect();
+        (!chunk.is_empty()).then_some(chunk)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let chunks = self.iter.len().div_ceil(self.size);
+        (chunks, Some(chunks))
+    }
+}
+
+impl<T> ExactSizeIterator for LinkedListChunks<'_, T> {}
+impl<T> FusedIterator for LinkedListChunks<'_, T> {}
+
+#[cfg(test)]
+mod testing {
+    use crate::list;
+    use crate::LinkedList;
+
+    #[test]
+    fn can_iterate_pairs() {
+        let ll: LinkedList<i32> = list![1, 2, 3, 4];
+        let pairs = ll.pairs();
+        assert_eq!(3, pairs.len());
+        assert_eq!(
+            vec![(&1, &2), (&2, &3), (&3, &4)],
+            pairs.collect::<Vec<_>>()
+        );
+        // the pairs borrow the list, not the iterator
+        let (first, _) = ll.pairs().next().unwrap();
+        assert_eq!(&1, first);
+
+        assert_eq!(0, list![1].pairs().count());
+        assert_eq!(0, LinkedList::<i32>::new().pairs().len());
+    }
+
+    #[test]
+    fn can_iterate_chunks() {
+        let ll: LinkedList<i32> = (1..=7).collect();
+        let chunks = ll.chunks(3);
+        assert_eq!(3, chunks.len());
+        assert_eq!(
+            vec![vec![&1, &2, &3], vec![&4, &5, &6], vec![&7]],
+            chunks.collect::<Vec<_>>()
+        );
+        assert_eq!(7, ll.chunks(1).len());
+        assert_eq!(1, ll.chunks(100).len());
+        assert_eq!(None, LinkedList::<i32>::new().chunks(2).next());
+    }
+
+    #[test]
+    #[should_panic(expected = "chunk size must be non-zero")]
+    fn chunks_of_zero_panic() {
+        let ll: LinkedList<i32> = list![1];
+        ll.chunks(0);
+    }
+}
@@ -0,0 +1,251 @@
+use std::borrow::Borrow;
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::Hash;
+use std::iter::FusedIterator;
+
+use crate::doubly_linked_list::{DoublyLinkedList, NodeHandle};
+
+// A least recently used cache: it holds at most `capacity` entries, and when it's full adding a
+// new one evicts whichever entry was used longest ago
+//
+// The two halves each do what they're good at:
+//  - the HashMap finds an entry's node by key in O(1)
+//  - the DoublyLinkedList keeps entries in recency order, most recent at the front. Since the map
+//    hands us the node directly, moving it to the front (on every get) and unlinking the back
+//    (on eviction) are O(1) relinks too
+// The list holds (key, value) so that when we evict from the back we know which key to remove
+// from the map, which is why keys need to be Clone
+pub struct LruCache<K, V> {
+    map: HashMap<K, NodeHandle<(K, V)>>,
+    order: DoublyLinkedList<(K, V)>,
+    capacity: usize,
+}
+
+impl<K: Hash + Eq + Clone, V> LruCache<K, V> {
+    // A cache with capacity 0 accepts puts but never keeps anything
+    pub fn new(capacity: usize) -> Self {
+        LruCache {
+            map: HashMap::with_capacity(capacity),
+            order: DoublyLinkedList::new(),
+            capacity,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    // Looks up key and marks it as the most recently used entry
+    // This is why get takes &mut self: reading an entry changes the order
+    pub fn get<Q>(&mut self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let handle = *self.map.get(key)?;
+        // SAFETY: every handle in the map belongs to a node still in order (see put/remove/evict)
+        unsafe {
+            self.order.move_to_front(handle);
+            Some(&self.order.get_by_handle(handle).1)
+        }
+    }
+
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let handle = *self.map.get(key)?;
+        // SAFETY: as in get()
+        unsafe {
+            self.order.move_to_front(handle);
+            Some(&mut self.order.get_mut_by_handle(handle).1)
+        }
+    }
+
+    // Looks up key without counting it as a use
+    pub fn peek<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let handle = *self.map.get(key)?;
+        // SAFETY: as in get()
+        Some(unsafe { &self.order.get_by_handle(handle).1 })
+    }
+
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.map.contains_key(key)
+    }
+
+    // Inserts or updates key, making it the most recently used entry
+    // Returns the old value if key was already cached. If adding key makes the cache too big the
+    // least recently used entry is evicted
+    pub fn put(&mut self, key: K, value: V) -> Option<V> {
+        if let Some(&handle) = self.map.get(&key) {
+            // SAFETY: as in get()
+            unsafe {
+                self.order.move_to_front(handle);
+                let entry = self.order.get_mut_by_handle(handle);
+                return Some(std::mem::replace(&mut entry.1, value));
+            }
+        }
+
+        if self.capacity == 0 {
+            return None;
+        }
+        if self.map.len() == self.capacity {
+            self.pop_lru();
+        }
+        let handle = self.order.push_front_handle((key.clone(), value));
+        self.map.insert(key, handle);
+        None
+    }
+
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let handle = self.map.remove(key)?;
+        // SAFETY: the handle was in the map, so its node is still in order, and now that it's out
+        // of the map nothing else will use it
+        Some(unsafe { self.order.remove_by_handle(handle).1 })
+    }
+
+    // Evicts and returns the least recently used entry
+    pub fn pop_lru(&mut self) -> Option<(K, V)> {
+        let (key, value) = self.order.pop_back()?;
+        self.map.remove(&key);
+        Some((key, value))
+    }
+
+    // Entries from most to least recently used, without changing the order
+    pub fn iter(
+        &self,
+    ) -> impl DoubleEndedIterator<Item = (&K, &V)> + ExactSizeIterator + FusedIterator + '_ {
+        self.order.iter().map(|(key, value)| (key, value))
+    }
+
+    pub fn clear(&mut self) {
+        self.map.clear();
+        self.order = DoublyLinkedList::new();
+    }
+}
+
+impl<K: fmt::Debug + Hash + Eq + Clone, V: fmt::Debug> fmt::Debug for LruCache<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+#[cfg(test)]
+mod testing {
+    use super::*;
+
+    fn keys(cache: &LruCache<&'static str, i32>) -> Vec<&'static str> {
+        cache.iter().map(|(k, _)| *k).collect()
+    }
+
+    #[test]
+    fn evicts_the_least_recently_used() {
+        let mut cache = LruCache::new(2);
+        cache.put("a", 1);
+        cache.put("b", 2);
+        assert_eq!(Some(&1), cache.get("a"));
+        cache.put("c", 3);
+        // b was the least recently used, a was just read
+        assert!(!cache.contains_key("b"));
+        assert_eq!(vec!["c", "a"], keys(&cache));
+        assert_eq!(2, cache.len());
+    }
+
+    #[test]
+    fn peek_does_not_change_the_order() {
+        let mut cache = LruCache::new(2);
+        cache.put("a", 1);
+        cache.put("b", 2);
+        assert_eq!(Some(&1), cache.peek("a"));
+        cache.put("c", 3);
+        assert_eq!(None, cache.peek("a"));
+        assert_eq!(r#"{"c": 3, "b": 2}"#, format!("{cache:?}"));
+    }
+
+    #[test]
+    fn put_updates_and_refreshes_existing_keys() {
+        let mut cache = LruCache::new(2);
+        cache.put("a", 1);
+        cache.put("b", 2);
+        assert_eq!(Some(1), cache.put("a", 10));
+        cache.put("c", 3);
+        assert_eq!(vec!["c", "a"], keys(&cache));
+        *cache.get_mut("a").unwrap() += 1;
+        assert_eq!(Some(&11), cache.peek("a"));
+        assert_eq!(vec!["a", "c"], keys(&cache));
+    }
+
+    #[test]
+    fn can_remove_and_pop() {
+        let mut cache = LruCache::new(3);
+        for (k, v) in [("a", 1), ("b", 2), ("c", 3)] {
+            cache.put(k, v);
+        }
+        assert_eq!(Some(2), cache.remove("b"));
+        assert_eq!(None, cache.remove("b"));
+        assert_eq!(Some(("a", 1)), cache.pop_lru());
+        assert_eq!(vec!["c"], keys(&cache));
+        cache.clear();
+        assert!(cache.is_empty() && cache.pop_lru().is_none());
+    }
+
+    #[test]
+    fn zero_capacity_keeps_nothing() {
+        let mut cache = LruCache::new(0);
+        assert_eq!(None, cache.put("a", 1));
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn matches_a_naive_model() {
+        // the model is a Vec of (key, value) in recency order, most recent first
+        let mut cache = LruCache::new(8);
+        let mut model: Vec<(u32, u32)> = Vec::new();
+        let mut x: u32 = 12345;
+        for step in 0..2_000 {
+            x ^= x << 13;
+            x ^= x >> 17;
+            x ^= x << 5;
+            let key = x % 16;
+            if step % 2 == 0 {
+                let expected = model.iter().position(|(k, _)| *k == key).map(|at| {
+                    let entry = model.remove(at);
+                    model.insert(0, entry);
+                    &model[0].1
+                });
+                assert_eq!(expected, cache.get(&key));
+            } else {
+                let old = model
+                    .iter()
+                    .position(|(k, _)| *k == key)
+                    .map(|at| model.remove(at).1);
+                model.insert(0, (key, step));
+                model.truncate(8);
+                assert_eq!(old, cache.put(key, step));
+            }
+            assert!(model.iter().map(|(k, v)| (k, v)).eq(cache.iter()));
+        }
+    }
+}
@@ -0,0 +1,59 @@
+// list![...] is to LinkedList what vec![...] is to Vec
+//  list![1, 2, 3] => a list holding 1, 2 and 3 in that order
+//  list![x; n]    => a list holding n clones of x
+// #[macro_export] puts the macro at the root of the crate, so users write linked_list::list!
+// $crate always refers to this crate, so the macro works wherever it's expanded
+#[macro_export]
+macro_rules! list {
+    () => {
+        $crate::LinkedList::new()
+    };
+    // The values are pushed onto the front from last to first, so every push is O(1) and no
+    // walking is needed. Arrays iterate from both ends, so .rev() is free
+    ($($value:expr),+ $(,)?) => {{
+        let mut ll = $crate::LinkedList::new();
+        for value in ::core::iter::IntoIterator::into_iter([$($value),+]).rev() {
+            ll.push_front(value);
+        }
+        ll
+    }};
+    // Like vec![x; n] the value is cloned n - 1 times and moved in for the last slot
+    ($value:expr; $n:expr) => {
+        <$crate::LinkedList<_> as ::core::iter::FromIterator<_>>::from_iter(
+            ::core::iter::repeat_n($value, $n),
+        )
+    };
+}
+
+#[cfg(test)]
+mod testing {
+    use crate::LinkedList;
+
+    #[test]
+    fn can_build_lists_with_the_macro() {
+        let ll: LinkedList<i32> = list![];
+        assert!(ll.is_empty());
+
+        let ll = list![1, 2, 3];
+        assert_eq!(vec![1, 2, 3], Vec::from(ll));
+
+        let ll = list![String::from("trailing"), String::from("comma"),];
+        assert_eq!(2, ll.len());
+        assert_eq!(Some(&String::from("comma")), ll.back());
+    }
+
+    #[test]
+    fn can_build_repeated_lists_with_the_macro() {
+        let ll = list![vec![0u8]; 3];
+        assert_eq!(vec![vec![0], vec![0], vec![0]], Vec::from(ll));
+
+        let ll: LinkedList<i32> = list![7; 0];
+        assert!(ll.is_empty());
+    }
+
+    #[test]
+    fn macro_lists_nest() {
+        let ll = list![list![1], list![2, 3]];
+        assert_eq!(Some(&list![2, 3]), ll.back());
+    }
+}
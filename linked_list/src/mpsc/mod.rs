@@ -0,0 +1,342 @@
+// A multi-producer single-consumer queue built on a linked list and atomics, with no locks
+//
+// This is Dmitry Vyukov's MPSC queue, a close relative of the Michael-Scott queue. Values go in
+// at the head and come out at the tail, and there's always at least one node in the list (a
+// "stub") so neither end ever has to deal with an empty list:
+//
+//   tail (consumer)                          head (producers)
+//   [stub] -> [a] -> [b] -> [c] -> null       ^ points at [c]
+//
+// Sending swaps the new node into head with a single atomic swap, then links the old head to
+// it. Any number of threads can do that at once: the swap puts them in some order and each one
+// links up with whoever went before it.
+// Receiving is only ever done by one thread (the Receiver isn't Clone and recv takes &mut), so
+// the tail needs no synchronisation with other receivers: it reads the stub's next, and if
+// there's a node there that node's value is the next message and the node becomes the new stub.
+//
+// Note: between a sender's swap and its link there's a moment where the value is in the queue
+// but the receiver can't reach it yet. If that sender is descheduled right there, the receiver
+// sees the queue as empty until it resumes. So this is mutex-free (nobody ever waits on a lock)
+// but not strictly lock-free, which is the usual trade for how simple it is.
+
+mod sync;
+
+use std::fmt;
+use std::ptr;
+
+use sync::{thread, Arc, AtomicBool, AtomicPtr, AtomicUsize, Ordering};
+
+struct Node<T> {
+    next: AtomicPtr<Node<T>>,
+    // None only in the stub, every node a sender makes holds a value
+    value: Option<T>,
+}
+
+struct Queue<T> {
+    // where senders push, the most recently sent node
+    head: AtomicPtr<Node<T>>,
+    // the current stub, only the receiver touches it (it's atomic just so Queue can be shared)
+    tail: AtomicPtr<Node<T>>,
+    senders: AtomicUsize,
+    receiver_alive: AtomicBool,
+}
+
+pub struct Sender<T> {
+    queue: Arc<Queue<T>>,
+}
+
+pub struct Receiver<T> {
+    queue: Arc<Queue<T>>,
+}
+
+// Why try_recv didn't return a value
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TryRecvError {
+    // nothing to receive right now, but senders are still around
+    Empty,
+    // nothing to receive and every Sender is gone, so nothing ever will be
+    Disconnected,
+}
+
+// recv only fails one way: every Sender is gone and the queue is empty
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RecvError;
+
+// Creates a connected Sender and Receiver
+// Clone the Sender for as many producer threads as you like
+pub fn channel<T>() -> (Sender<T>, Receiver<T>) {
+    let stub = Node::alloc(None);
+    let queue = Arc::new(Queue {
+        head: AtomicPtr::new(stub),
+        tail: AtomicPtr::new(stub),
+        senders: AtomicUsize::new(1),
+        receiver_alive: AtomicBool::new(true),
+    });
+    (
+        Sender {
+            queue: Arc::clone(&queue),
+        },
+        Receiver { queue },
+    )
+}
+
+impl<T> Node<T> {
+    fn alloc(value: Option<T>) -> *mut Node<T> {
+        Box::into_raw(Box::new(Node {
+            next: AtomicPtr::new(ptr::null_mut()),
+            value,
+        }))
+    }
+}
+
+impl<T> Sender<T> {
+    // Queues value for the receiver, or hands it back if the Receiver has been dropped
+    pub fn send(&self, value: T) -> Result<(), T> {
+        if !self.queue.receiver_alive.load(Ordering::Acquire) {
+            return Err(value);
+        }
+
+        let node = Node::alloc(Some(value));
+        // AcqRel: Release publishes our node to the next sender to swap, Acquire makes sure we
+        // see the previous node fully initialised before we link to it
+        let prev = self.queue.head.swap(node, Ordering::AcqRel);
+        // SAFETY: prev is still alive: the receiver only frees a node once it has moved past it,
+        // and it can't move past prev until prev.next is set, which is what we're doing here.
+        // Release pairs with the receiver's Acquire load so it sees our node's value
+        unsafe { (*prev).next.store(node, Ordering::Release) };
+        Ok(())
+    }
+}
+
+impl<T> Receiver<T> {
+    // Takes the oldest value out of the queue without waiting
+    pub fn try_recv(&mut self) -> Result<T, TryRecvError> {
+        if let Some(value) = self.pop() {
+            return Ok(value);
+        }
+        if self.queue.senders.load(Ordering::Acquire) > 0 {
+            return Err(TryRecvError::Empty);
+        }
+        // Every sender is gone, but the last one might have sent something just before it left.
+        // Its send happens before its drop, so having seen the drop we'll see the send too
+        self.pop().ok_or(TryRecvError::Disconnected)
+    }
+
+    // Waits for a value, yielding to other threads between tries
+    // Fails once the queue is empty and every Sender is gone
+    pub fn recv(&mut self) -> Result<T, RecvError> {
+        loop {
+            match self.try_recv() {
+                Ok(value) => return Ok(value),
+                Err(TryRecvError::Disconnected) => return Err(RecvError),
+                Err(TryRecvError::Empty) => thread::yield_now(),
+            }
+        }
+    }
+
+    // Iterates over values until every Sender is gone
+    pub fn iter(&mut self) -> impl Iterator<Item = T> + '_ {
+        std::iter::from_fn(move || self.recv().ok())
+    }
+
+    fn pop(&mut self) -> Option<T> {
+        // Relaxed: the receiver is the only thread that ever reads or writes tail
+        let tail = self.queue.tail.load(Ordering::Relaxed);
+        // SAFETY: tail is the stub, which only the receiver ever frees
+        let next = unsafe { (*tail).next.load(Ordering::Acquire) };
+        if next.is_null() {
+            return None;
+        }
+
+        // next becomes the new stub, so we move its value out and leave None behind
+        // SAFETY: next was fully written before the sender's Release store that we Acquired, and
+        // no sender touches a node's value after linking it
+        let value = unsafe { (*next).value.take() };
+        self.queue.tail.store(next, Ordering::Relaxed);
+        // SAFETY: the old stub is unreachable now: head moved past it when next was sent and tail
+        // just moved past it, and no sender still needs it (its next is already set)
+        drop(unsafe { Box::from_raw(tail) });
+        value
+    }
+}
+
+impl<T> Clone for Sender<T> {
+    fn clone(&self) -> Self {
+        self.queue.senders.fetch_add(1, Ordering::Relaxed);
+        Sender {
+            queue: Arc::clone(&self.queue),
+        }
+    }
+}
+
+impl<T> Drop for Sender<T> {
+    fn drop(&mut self) {
+        // Release: everything this sender sent happens before the receiver sees the count drop
+        self.queue.senders.fetch_sub(1, Ordering::Release);
+    }
+}
+
+impl<T> Drop for Receiver<T> {
+    fn drop(&mut self) {
+        self.queue.receiver_alive.store(false, Ordering::Release);
+    }
+}
+
+// By the time the queue itself is dropped the Arc tells us nobody else can be using it, so we
+// can walk the list from the stub and free everything, values that were never received included
+impl<T> Drop for Queue<T> {
+    fn drop(&mut self) {
+        let mut node = self.tail.load(Ordering::Relaxed);
+        while !node.is_null() {
+            // SAFETY: every node from the stub onwards is owned by the queue and nobody else
+            let boxed = unsafe { Box::from_raw(node) };
+            node = boxed.next.load(Ordering::Relaxed);
+        }
+    }
+}
+
+// The raw pointers stop the compiler from working these out for itself
+// Values cross from sender threads to the receiver thread, so T: Send is all that's needed
+unsafe impl<T: Send> Send for Queue<T> {}
+unsafe impl<T: Send> Sync for Queue<T> {}
+
+impl<T> fmt::Debug for Sender<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Sender").finish_non_exhaustive()
+    }
+}
+
+impl<T> fmt::Debug for Receiver<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Receiver").finish_non_exhaustive()
+    }
+}
+
+#[cfg(all(test, not(loom)))]
+mod testing {
+    use super::*;
+
+    #[test]
+    fn can_send_and_receive_in_order() {
+        let (tx, mut rx) = channel();
+        assert_eq!(Err(TryRecvError::Empty), rx.try_recv());
+        for v in 0..5 {
+            tx.send(v).unwrap();
+        }
+        assert_eq!(Ok(0), rx.try_recv());
+        assert_eq!(
+            vec![1, 2, 3, 4],
+            (0..4).map(|_| rx.recv().unwrap()).collect::<Vec<_>>()
+        );
+        drop(tx);
+        assert_eq!(Err(TryRecvError::Disconnected), rx.try_recv());
+        assert_eq!(Err(RecvError), rx.recv());
+    }
+
+    #[test]
+    fn send_fails_once_the_receiver_is_gone() {
+        let (tx, rx) = channel();
+        drop(rx);
+        assert_eq!(Err(String::from("lost")), tx.send(String::from("lost")));
+    }
+
+    #[test]
+    fn unreceived_values_are_dropped_with_the_queue() {
+        let tracker = std::sync::Arc::new(());
+        let (tx, mut rx) = channel();
+        for _ in 0..3 {
+            tx.send(std::sync::Arc::clone(&tracker)).unwrap();
+        }
+        drop(rx.recv().unwrap());
+        assert_eq!(3, std::sync::Arc::strong_count(&tracker));
+        drop((tx, rx));
+        assert_eq!(1, std::sync::Arc::strong_count(&tracker));
+    }
+
+    #[test]
+    fn many_producers_one_consumer() {
+        let (tx, mut rx) = channel();
+        let producers: Vec<_> = (0..4)
+            .map(|p| {
+                let tx = tx.clone();
+                std::thread::spawn(move || {
+                    for i in 0..1_000 {
+                        tx.send((p, i)).unwrap();
+                    }
+                })
+            })
+            .collect();
+        drop(tx);
+
+        let mut last = [None; 4];
+        let mut count = 0;
+        for (p, i) in rx.iter() {
+            // values from one producer arrive in the order it sent them
+            assert!(last[p].is_none_or(|prev| prev < i));
+            last[p] = Some(i);
+            count += 1;
+        }
+        assert_eq!(4_000, count);
+        for producer in producers {
+            producer.join().unwrap();
+        }
+    }
+}
+
+// Model checked with loom, which runs the closure under every interleaving of the threads (and
+// every weak memory behaviour the orderings allow) to find races the normal tests would only hit
+// by luck:
+//  RUSTFLAGS="--cfg loom" cargo test --release --lib mpsc
+#[cfg(all(test, loom))]
+mod loom_testing {
+    use super::*;
+
+    #[test]
+    fn two_producers_are_both_received() {
+        loom::model(|| {
+            let (tx, mut rx) = channel();
+            let tx2 = tx.clone();
+            let a = loom::thread::spawn(move || tx.send(1).unwrap());
+            let b = loom::thread::spawn(move || tx2.send(2).unwrap());
+
+            let mut received = Vec::new();
+            while let Ok(value) = rx.recv() {
+                received.push(value);
+            }
+            received.sort();
+            assert_eq!(vec![1, 2], received);
+            a.join().unwrap();
+            b.join().unwrap();
+        });
+    }
+
+    #[test]
+    fn receiving_while_sending_keeps_order() {
+        loom::model(|| {
+            let (tx, mut rx) = channel();
+            let producer = loom::thread::spawn(move || {
+                tx.send(1).unwrap();
+                tx.send(2).unwrap();
+            });
+
+            let mut received = Vec::new();
+            while let Ok(value) = rx.recv() {
+                received.push(value);
+            }
+            assert_eq!(vec![1, 2], received);
+            producer.join().unwrap();
+        });
+    }
+
+    #[test]
+    fn dropping_the_receiver_while_sending_frees_everything() {
+        loom::model(|| {
+            let (tx, rx) = channel();
+            let producer = loom::thread::spawn(move || {
+                let _ = tx.send(String::from("maybe lost"));
+            });
+            drop(rx);
+            producer.join().unwrap();
+        });
+    }
+}
@@ -0,0 +1,16 @@
+// The queue's synchronisation primitives, swapped for loom's model-checked versions when
+// building with --cfg loom
+
+#[cfg(loom)]
+pub(super) use loom::{
+    sync::atomic::{AtomicBool, AtomicPtr, AtomicUsize, Ordering},
+    sync::Arc,
+    thread,
+};
+
+#[cfg(not(loom))]
+pub(super) use std::{
+    sync::atomic::{AtomicBool, AtomicPtr, AtomicUsize, Ordering},
+    sync::Arc,
+    thread,
+};
@@ -0,0 +1,224 @@
+use alloc::rc::Rc;
+use alloc::vec::Vec;
+use core::fmt;
+use core::iter::FusedIterator;
+
+// An immutable singly linked list (a "cons list", as in Lisp or Haskell)
+// Nothing in a PersistentList ever changes once it's built. push_front and tail don't modify the
+// list, they return a new one that shares every existing node with the old one:
+//
+//   let a = [2, 3]             a:     2 -> 3
+//   let b = a.push_front(1)    b: 1 ->^
+//   let c = a.push_front(9)    c: 9 ->^
+//
+// a, b and c all point at the same 2 and 3 nodes, and all three stay valid. Rc is what makes the
+// sharing possible: a node is freed once no list (and no other node) points at it anymore.
+// This is what makes snapshots cheap: keeping an old version of a list is just keeping an Rc.
+pub struct PersistentList<T> {
+    head: Link<T>,
+}
+
+type Link<T> = Option<Rc<Node<T>>>;
+
+struct Node<T> {
+    value: T,
+    next: Link<T>,
+    // how many nodes there are from here to the end, so len() is O(1) for every version
+    len: usize,
+}
+
+pub struct PersistentListIterRef<'a, T> {
+    next_node: Option<&'a Node<T>>,
+}
+
+impl<T> PersistentList<T> {
+    pub fn new() -> Self {
+        PersistentList { head: None }
+    }
+
+    pub fn len(&self) -> usize {
+        self.head.as_ref().map_or(0, |node| node.len)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.head.is_none()
+    }
+
+    // O(1): a new node pointing at our head, we're left exactly as we were
+    pub fn push_front(&self, value: T) -> Self {
+        PersistentList {
+            head: Some(Rc::new(Node {
+                value,
+                next: self.head.clone(),
+                len: self.len() + 1,
+            })),
+        }
+    }
+
+    // O(1): everything after the first value, as a list sharing our nodes
+    // The tail of an empty list is empty
+    pub fn tail(&self) -> Self {
+        PersistentList {
+            head: self.head.as_ref().and_then(|node| node.next.clone()),
+        }
+    }
+
+    pub fn front(&self) -> Option<&T> {
+        self.head.as_ref().map(|node| &node.value)
+    }
+
+    pub fn iter(&self) -> PersistentListIterRef<'_, T> {
+        PersistentListIterRef {
+            next_node: self.head.as_deref(),
+        }
+    }
+}
+
+impl<T> Default for PersistentList<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Cloning a persistent list is O(1): both copies share every node, and since neither can change
+// them there's nothing to copy
+// Note: this doesn't need T: Clone, which is why it isn't derived
+impl<T> Clone for PersistentList<T> {
+    fn clone(&self) -> Self {
+        PersistentList {
+            head: self.head.clone(),
+        }
+    }
+}
+
+// Dropping a long chain of Rcs would recurse once per node, so we unwind it in a loop instead
+// We can only take a node apart if we hold its last Rc: as soon as we reach a node someone else
+// still shares we stop, that part of the chain is theirs now
+impl<T> Drop for PersistentList<T> {
+    fn drop(&mut self) {
+        let mut head = self.head.take();
+        while let Some(node) = head {
+            match Rc::try_unwrap(node) {
+                Ok(mut node) => head = node.next.take(),
+                Err(_) => break,
+            }
+        }
+    }
+}
+
+// Values come out of the iterator front to back, but a cons list can only be built from the back
+// So we gather them up first and push them on in reverse
+impl<T> FromIterator<T> for PersistentList<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let values: Vec<T> = iter.into_iter().collect();
+        let mut list = PersistentList::new();
+        for value in values.into_iter().rev() {
+            list = list.push_front(value);
+        }
+        list
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for PersistentList<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<T: PartialEq> PartialEq for PersistentList<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len() && self.iter().eq(other.iter())
+    }
+}
+
+impl<T: Eq> Eq for PersistentList<T> {}
+
+impl<'a, T> IntoIterator for &'a PersistentList<T> {
+    type Item = &'a T;
+    type IntoIter = PersistentListIterRef<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, T> Iterator for PersistentListIterRef<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_node.map(|node| {
+            self.next_node = node.next.as_deref();
+            &node.value
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.next_node.map_or(0, |node| node.len);
+        (len, Some(len))
+    }
+}
+
+impl<T> ExactSizeIterator for PersistentListIterRef<'_, T> {}
+impl<T> FusedIterator for PersistentListIterRef<'_, T> {}
+
+#[cfg(test)]
+mod testing {
+    use super::*;
+
+    #[test]
+    fn versions_share_structure() {
+        let a: PersistentList<i32> = [2, 3].into_iter().collect();
+        let b = a.push_front(1);
+        let c = a.push_front(9);
+
+        assert_eq!(vec![2, 3], a.iter().copied().collect::<Vec<_>>());
+        assert_eq!(vec![1, 2, 3], b.iter().copied().collect::<Vec<_>>());
+        assert_eq!(vec![9, 2, 3], c.iter().copied().collect::<Vec<_>>());
+        assert_eq!((2, 3, 3), (a.len(), b.len(), c.len()));
+
+        // the tails of b and c are a itself, not a copy of it
+        let (b_tail, c_tail) = (b.tail(), c.tail());
+        assert!(Rc::ptr_eq(
+            b_tail.head.as_ref().unwrap(),
+            c_tail.head.as_ref().unwrap()
+        ));
+        assert_eq!(a, b_tail);
+    }
+
+    #[test]
+    fn can_walk_with_tail() {
+        let list: PersistentList<&str> = ["a", "b"].into_iter().collect();
+        assert_eq!(Some(&"a"), list.front());
+        assert_eq!(Some(&"b"), list.tail().front());
+        assert!(list.tail().tail().is_empty());
+        assert!(list.tail().tail().tail().is_empty());
+        assert_eq!(2, list.iter().len());
+        assert_eq!(r#"["a", "b"]"#, format!("{list:?}"));
+    }
+
+    #[test]
+    fn shared_nodes_outlive_the_list_that_made_them() {
+        let tracker = Rc::new(());
+        let base: PersistentList<Rc<()>> = (0..3).map(|_| Rc::clone(&tracker)).collect();
+        let longer = base.push_front(Rc::clone(&tracker));
+        assert_eq!(5, Rc::strong_count(&tracker));
+
+        // dropping base can't free anything, longer still needs all of its nodes
+        drop(base);
+        assert_eq!(5, Rc::strong_count(&tracker));
+        assert_eq!(4, longer.len());
+
+        drop(longer);
+        assert_eq!(1, Rc::strong_count(&tracker));
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn can_drop_long_lists() {
+        let list: PersistentList<usize> = (0..1_000_000).collect();
+        let snapshot = list.tail();
+        drop(list);
+        assert_eq!(999_999, snapshot.len());
+        drop(snapshot);
+    }
+}
@@ -0,0 +1,398 @@
+use alloc::rc::{Rc, Weak};
+use core::cell::{Ref, RefCell, RefMut};
+use core::fmt;
+use core::iter::FusedIterator;
+use core::marker::PhantomData;
+
+// A doubly linked list with no unsafe code at all, built from shared ownership instead
+//  Rc<T>      => a reference counted pointer, several Rcs can own the same value and it's freed
+//                when the last one goes away
+//  RefCell<T> => moves the borrow rules to runtime, so we can change a node through a shared Rc
+//                (borrow()/borrow_mut() panic instead of failing to compile if they'd overlap)
+//  Weak<T>    => a pointer that doesn't own anything, upgrade() gives us an Rc back if the value
+//                is still alive
+//
+// Why Weak for prev?
+// If both next and prev were Rcs, every pair of neighbours would own each other. Their counts
+// would never reach zero and dropping the list would leak every node (a reference cycle).
+// So next links own the node after them and prev links only point back: ownership runs front
+// to back, and the list holds an extra Rc to its last node so it can reach it in O(1).
+//
+// The cost compared to DoublyLinkedList is a reference count and a borrow flag in every node, and
+// the fact we can't hand out plain &T (every look at a value goes through a RefCell borrow)
+pub struct RcDoublyLinkedList<T> {
+    head: Link<T>,
+    tail: Link<T>,
+    len: usize,
+}
+
+type Link<T> = Option<Rc<RefCell<Node<T>>>>;
+
+struct Node<T> {
+    value: T,
+    next: Link<T>,
+    prev: Option<Weak<RefCell<Node<T>>>>,
+}
+
+// into_iter() just pops values off either end of a list it owns
+pub struct RcDoublyLinkedListIter<T> {
+    list: RcDoublyLinkedList<T>,
+}
+
+// values() walks the list handing out clones of the values
+// The front end follows next (an Rc, always valid) and the back end follows prev, which is Weak
+// and has to be upgrade()d at every step. The iterator holds Rcs to the nodes it's standing on,
+// so they can't be freed underneath it
+// It still borrows the list: popping a node while the iterator holds an Rc to it would leave
+// the node with two owners, and pop_* need to be the only owner to move the value out
+pub struct RcDoublyLinkedListValues<'a, T> {
+    front: Link<T>,
+    back: Link<T>,
+    remaining: usize,
+    _list: PhantomData<&'a RcDoublyLinkedList<T>>,
+}
+
+impl<T> RcDoublyLinkedList<T> {
+    pub fn new() -> Self {
+        RcDoublyLinkedList {
+            head: None,
+            tail: None,
+            len: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    // All four push/pop operations are O(1), just like DoublyLinkedList
+
+    pub fn push_front(&mut self, value: T) {
+        let node = Rc::new(RefCell::new(Node {
+            value,
+            next: self.head.take(),
+            prev: None,
+        }));
+        match &node.borrow().next {
+            // Rc::downgrade makes a Weak pointing at the same node without adding an owner
+            Some(old_head) => old_head.borrow_mut().prev = Some(Rc::downgrade(&node)),
+            None => self.tail = Some(Rc::clone(&node)),
+        }
+        self.head = Some(node);
+        self.len += 1;
+    }
+
+    pub fn push_back(&mut self, value: T) {
+        let node = Rc::new(RefCell::new(Node {
+            value,
+            next: None,
+            prev: self.tail.as_ref().map(Rc::downgrade),
+        }));
+        match self.tail.take() {
+            Some(old_tail) => old_tail.borrow_mut().next = Some(Rc::clone(&node)),
+            None => self.head = Some(Rc::clone(&node)),
+        }
+        self.tail = Some(node);
+        self.len += 1;
+    }
+
+    pub fn pop_front(&mut self) -> Option<T> {
+        self.head.take().map(|old_head| {
+            match old_head.borrow_mut().next.take() {
+                Some(new_head) => {
+                    new_head.borrow_mut().prev = None;
+                    self.head = Some(new_head);
+                }
+                // it was the only node, so the list's tail Rc points at it too
+                None => self.tail = None,
+            }
+            self.len -= 1;
+            Self::into_value(old_head)
+        })
+    }
+
+    pub fn pop_back(&mut self) -> Option<T> {
+        self.tail.take().map(|old_tail| {
+            // upgrade() can't fail here: the node before the tail is owned by its own prev (or
+            // the list's head), which we haven't touched
+            let prev = old_tail.borrow_mut().prev.take().and_then(|p| p.upgrade());
+            match prev {
+                Some(new_tail) => {
+                    // this drops the other Rc to the old tail, leaving ours as the only one
+                    new_tail.borrow_mut().next = None;
+                    self.tail = Some(new_tail);
+                }
+                None => self.head = None,
+            }
+            self.len -= 1;
+            Self::into_value(old_tail)
+        })
+    }
+
+    // Ref and RefMut are the guards RefCell hands out, the borrow lasts until they're dropped
+    // Ref::map narrows the guard from the whole node down to just its value
+
+    pub fn front(&self) -> Option<Ref<'_, T>> {
+        self.head
+            .as_ref()
+            .map(|node| Ref::map(node.borrow(), |node| &node.value))
+    }
+
+    pub fn front_mut(&mut self) -> Option<RefMut<'_, T>> {
+        self.head
+            .as_ref()
+            .map(|node| RefMut::map(node.borrow_mut(), |node| &mut node.value))
+    }
+
+    pub fn back(&self) -> Option<Ref<'_, T>> {
+        self.tail
+            .as_ref()
+            .map(|node| Ref::map(node.borrow(), |node| &node.value))
+    }
+
+    pub fn back_mut(&mut self) -> Option<RefMut<'_, T>> {
+        self.tail
+            .as_ref()
+            .map(|node| RefMut::map(node.borrow_mut(), |node| &mut node.value))
+    }
+
+    pub fn values(&self) -> RcDoublyLinkedListValues<'_, T>
+    where
+        T: Clone,
+    {
+        RcDoublyLinkedListValues {
+            front: self.head.clone(),
+            back: self.tail.clone(),
+            remaining: self.len,
+            _list: PhantomData,
+        }
+    }
+
+    // Calls f with a reference to every value from front to back
+    // This is how to look at values that aren't Clone: the borrow of each node only lasts for
+    // one call of f
+    pub fn for_each(&self, mut f: impl FnMut(&T)) {
+        let mut current = self.head.clone();
+        while let Some(node) = current {
+            let node = node.borrow();
+            f(&node.value);
+            current = node.next.clone();
+        }
+    }
+
+    // Once a node is unlinked our Rc is the only one left, so try_unwrap() hands us the RefCell
+    // and into_inner() the node inside it
+    fn into_value(node: Rc<RefCell<Node<T>>>) -> T {
+        match Rc::try_unwrap(node) {
+            Ok(node) => node.into_inner().value,
+            Err(_) => unreachable!("an unlinked node has no other owners"),
+        }
+    }
+}
+
+impl<T> Default for RcDoublyLinkedList<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Without this a long list would drop recursively: freeing the head drops its next Rc, which
+// frees the next node, which drops its next Rc... Popping frees one node at a time instead
+impl<T> Drop for RcDoublyLinkedList<T> {
+    fn drop(&mut self) {
+        while self.pop_front().is_some() {}
+    }
+}
+
+impl<T> Extend<T> for RcDoublyLinkedList<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for value in iter {
+            self.push_back(value);
+        }
+    }
+}
+
+impl<T> FromIterator<T> for RcDoublyLinkedList<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut list = RcDoublyLinkedList::new();
+        list.extend(iter);
+        list
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for RcDoublyLinkedList<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut list = f.debug_list();
+        self.for_each(|value| {
+            list.entry(value);
+        });
+        list.finish()
+    }
+}
+
+impl<T> IntoIterator for RcDoublyLinkedList<T> {
+    type Item = T;
+    type IntoIter = RcDoublyLinkedListIter<T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        RcDoublyLinkedListIter { list: self }
+    }
+}
+
+impl<T> Iterator for RcDoublyLinkedListIter<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.list.pop_front()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.list.len, Some(self.list.len))
+    }
+}
+
+impl<T> DoubleEndedIterator for RcDoublyLinkedListIter<T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.list.pop_back()
+    }
+}
+
+impl<T> ExactSizeIterator for RcDoublyLinkedListIter<T> {}
+impl<T> FusedIterator for RcDoublyLinkedListIter<T> {}
+
+impl<T: Clone> Iterator for RcDoublyLinkedListValues<'_, T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.front.take().map(|node| {
+            let node = node.borrow();
+            self.front = node.next.clone();
+            self.count_one();
+            node.value.clone()
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<T: Clone> DoubleEndedIterator for RcDoublyLinkedListValues<'_, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.back.take().map(|node| {
+            let node = node.borrow();
+            // This is the upgrade-based step: prev doesn't own the node before us, so we ask
+            // for an Rc to it. It's still alive because its own prev (or the list) owns it
+            self.back = node.prev.as_ref().and_then(Weak::upgrade);
+            self.count_one();
+            node.value.clone()
+        })
+    }
+}
+
+impl<T: Clone> ExactSizeIterator for RcDoublyLinkedListValues<'_, T> {}
+impl<T: Clone> FusedIterator for RcDoublyLinkedListValues<'_, T> {}
+
+impl<T> RcDoublyLinkedListValues<'_, T> {
+    // Once the two ends have met both of them are standing on nodes we've already handed out,
+    // so let go of them rather than keep those nodes alive for the rest of the iterator's life
+    fn count_one(&mut self) {
+        self.remaining -= 1;
+        if self.remaining == 0 {
+            self.front = None;
+            self.back = None;
+        }
+    }
+}
+
+#[cfg(test)]
+mod testing {
+    use super::*;
+
+    #[test]
+    fn can_push_and_pop_both_ends() {
+        let mut list = RcDoublyLinkedList::new();
+        list.push_back(1);
+        list.push_back(2);
+        list.push_front(0);
+        assert_eq!(3, list.len());
+        assert_eq!(Some(0), list.front().map(|v| *v));
+        assert_eq!(Some(2), list.back().map(|v| *v));
+
+        assert_eq!(Some(2), list.pop_back());
+        assert_eq!(Some(0), list.pop_front());
+        assert_eq!(Some(1), list.pop_back());
+        assert_eq!(None, list.pop_front());
+        assert_eq!(None, list.pop_back());
+        assert!(list.is_empty());
+        assert!(list.front().is_none() && list.back().is_none());
+    }
+
+    #[test]
+    fn can_change_values_through_ref_muts() {
+        let mut list: RcDoublyLinkedList<i32> = (0..3).collect();
+        *list.front_mut().unwrap() += 10;
+        *list.back_mut().unwrap() += 20;
+        assert_eq!("[10, 1, 22]", format!("{list:?}"));
+    }
+
+    #[test]
+    fn can_walk_both_ways() {
+        let list: RcDoublyLinkedList<String> =
+            ["a", "b", "c"].map(String::from).into_iter().collect();
+        assert_eq!(vec!["a", "b", "c"], list.values().collect::<Vec<_>>());
+        assert_eq!(vec!["c", "b", "a"], list.values().rev().collect::<Vec<_>>());
+
+        // the two ends meet in the middle without crossing
+        let mut values = list.values();
+        assert_eq!(Some(String::from("a")), values.next());
+        assert_eq!(Some(String::from("c")), values.next_back());
+        assert_eq!(1, values.len());
+        assert_eq!(Some(String::from("b")), values.next_back());
+        assert_eq!(None, values.next());
+        drop(values);
+
+        let mut lens = Vec::new();
+        list.for_each(|s| lens.push(s.len()));
+        assert_eq!(vec![1, 1, 1], lens);
+
+        assert_eq!(
+            vec!["c", "b", "a"],
+            list.into_iter().rev().collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn nodes_are_freed_without_cycles() {
+        let tracker = Rc::new(());
+        let mut list: RcDoublyLinkedList<Rc<()>> = (0..5).map(|_| Rc::clone(&tracker)).collect();
+        assert_eq!(6, Rc::strong_count(&tracker));
+
+        list.pop_back();
+        list.pop_front();
+        assert_eq!(4, Rc::strong_count(&tracker));
+
+        // if prev links were strong the nodes would keep each other alive here
+        drop(list);
+        assert_eq!(1, Rc::strong_count(&tracker));
+    }
+
+    #[test]
+    // a million nodes takes far too long under Miri, the soundness tests cover Drop there
+    #[cfg_attr(miri, ignore)]
+    fn can_drop_long_lists() {
+        let list: RcDoublyLinkedList<usize> = (0..1_000_000).collect();
+        assert_eq!(1_000_000, list.len());
+        drop(list);
+    }
+}
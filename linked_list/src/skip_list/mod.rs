@@ -0,0 +1,485 @@
+use alloc::vec::Vec;
+use core::borrow::Borrow;
+use core::fmt;
+use core::iter::FusedIterator;
+use core::mem;
+use core::ops::{Bound, RangeBounds};
+
+// A skip list: a sorted linked list with "express lanes" stacked on top of it
+//
+//   level 2: head ------------------------> 30 ------------------> None
+//   level 1: head ------> 10 -------------> 30 ------> 50 -------> None
+//   level 0: head -> 5 -> 10 -> 20 -> 25 -> 30 -> 40 -> 50 -> 60 -> None
+//
+// Every node is on level 0, and each node is also on the level above with probability 1/2. To
+// find a key we start on the highest level and move right while the next key is still smaller,
+// dropping down a level whenever we'd overshoot. Each level skips about half of the one below,
+// so that's O(log n) steps expected, like a balanced tree, but with only list relinking to do
+// on insert and remove (no rotations).
+//
+// Nodes live in a Vec and link to each other by index, like SlabList, so there's no unsafe code
+// and removed slots get reused
+pub struct SkipList<K, V> {
+    nodes: Vec<Option<SkipNode<K, V>>>,
+    // slots in nodes that are None and can be reused
+    free: Vec<usize>,
+    // head[level] is the first node on each level, head.len() is the tallest tower in the list
+    head: Vec<Option<usize>>,
+    len: usize,
+    rng: XorShift,
+}
+
+struct SkipNode<K, V> {
+    key: K,
+    value: V,
+    // next[level] for every level this node's tower reaches
+    next: Vec<Option<usize>>,
+}
+
+// More levels than this would only pay off past 2^32 values
+const MAX_LEVEL: usize = 32;
+
+// Where a search stopped on one level: None is the head, Some(i) is node i
+type Position = Option<usize>;
+
+// Seeded from the same per-process randomness HashMap uses, so tower heights (and so the shape of
+// the list) can't be predicted from the outside
+#[cfg(feature = "std")]
+fn default_seed() -> u64 {
+    use std::hash::BuildHasher;
+    std::collections::hash_map::RandomState::new().hash_one(0u8)
+}
+
+// Without std there's no randomness to ask for, so every list starts from the same seed
+#[cfg(not(feature = "std"))]
+fn default_seed() -> u64 {
+    0x9E37_79B9_7F4A_7C15
+}
+
+// A small fast random number generator for tower heights
+// The quality bar is low (we only need coin flips), so there's no need for a rand dependency
+struct XorShift(u64);
+
+impl XorShift {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+}
+
+pub struct SkipListIter<'a, K, V> {
+    list: &'a SkipList<K, V>,
+    next_node: Option<usize>,
+    // where a range stops, Unbounded for a plain iter()
+    end: Bound<&'a K>,
+    // how many entries are left at most; exact for a plain iter(), but a range can't know where
+    // it ends without walking there
+    remaining: usize,
+    exact: bool,
+}
+
+impl<K: Ord, V> SkipList<K, V> {
+    pub fn new() -> Self {
+        Self::with_seed(default_seed())
+    }
+
+    // Same keys inserted in the same order with the same seed always give the same list, which
+    // is handy for tests and benchmarks
+    pub fn with_seed(seed: u64) -> Self {
+        SkipList {
+            nodes: Vec::new(),
+            free: Vec::new(),
+            head: Vec::new(),
+            len: 0,
+            // xorshift gets stuck on 0 forever
+            rng: XorShift(seed | 1),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    // Inserts key -> value, returning the old value if key was already there
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        let preds = self.predecessors(&key);
+        if let Some(found) = self.next_of(preds[0], 0) {
+            let node = self.node_mut(found);
+            if node.key == key {
+                return Some(mem::replace(&mut node.value, value));
+            }
+        }
+
+        let height = self.random_height();
+        while self.head.len() < height {
+            self.head.push(None);
+        }
+        // levels the list didn't reach before this node start from the head, which is what
+        // predecessors() reports for them (None)
+        let next = (0..height)
+            .map(|level| self.next_of(preds[level], level))
+            .collect();
+        let index = self.alloc(SkipNode { key, value, next });
+        for (level, pred) in preds.iter().enumerate().take(height) {
+            self.set_next(*pred, level, Some(index));
+        }
+        self.len += 1;
+        None
+    }
+
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let preds = self.predecessors(key);
+        let found = self.next_of(preds[0], 0)?;
+        if self.node(found).key.borrow() != key {
+            return None;
+        }
+
+        let node = self.nodes[found].take().expect("linked nodes are occupied");
+        for (level, next) in node.next.into_iter().enumerate() {
+            self.set_next(preds[level], level, next);
+        }
+        self.free.push(found);
+        // drop levels nothing reaches anymore, so searches don't start on empty ones
+        while self.head.last() == Some(&None) {
+            self.head.pop();
+        }
+        self.len -= 1;
+        Some(node.value)
+    }
+
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.find(key).map(|index| &self.node(index).value)
+    }
+
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.find(key).map(|index| &mut self.node_mut(index).value)
+    }
+
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.find(key).is_some()
+    }
+
+    // Every (key, value) pair in key order
+    pub fn iter(&self) -> SkipListIter<'_, K, V> {
+        SkipListIter {
+            list: self,
+            next_node: self.head.first().copied().flatten(),
+            end: Bound::Unbounded,
+            remaining: self.len,
+            exact: true,
+        }
+    }
+
+    // The (key, value) pairs with keys inside range, in key order
+    // Finding the start is an O(log n) search, after that it's a walk along level 0
+    pub fn range<R: RangeBounds<K>>(&self, range: R) -> SkipListIter<'_, K, V> {
+        let next_node = match range.start_bound() {
+            Bound::Unbounded => self.head.first().copied().flatten(),
+            Bound::Included(start) => self.next_of(self.last_before(|k| k < start), 0),
+            Bound::Excluded(start) => self.next_of(self.last_before(|k| k <= start), 0),
+        };
+        // the end bound has to borrow from the list (the iterator outlives range), so we find
+        // the node it refers to and keep a reference to that node's key instead
+        let end = match range.end_bound() {
+            Bound::Unbounded => Bound::Unbounded,
+            Bound::Included(end) => match self.next_of(self.last_before(|k| k <= end), 0) {
+                Some(after) => Bound::Excluded(&self.node(after).key),
+                None => Bound::Unbounded,
+            },
+            Bound::Excluded(end) => match self.next_of(self.last_before(|k| k < end), 0) {
+                Some(after) => Bound::Excluded(&self.node(after).key),
+                None => Bound::Unbounded,
+            },
+        };
+        SkipListIter {
+            list: self,
+            next_node,
+            end,
+            remaining: self.len,
+            exact: false,
+        }
+    }
+
+    fn find<Q>(&self, key: &Q) -> Option<usize>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let found = self.next_of(self.last_before(|k| k.borrow() < key), 0)?;
+        (self.node(found).key.borrow() == key).then_some(found)
+    }
+
+    // The search: the last node on level 0 whose key is still `before` the target
+    fn last_before(&self, before: impl Fn(&K) -> bool) -> Position {
+        let mut at = None;
+        for level in (0..self.head.len()).rev() {
+            while let Some(next) = self.next_of(at, level) {
+                if !before(&self.node(next).key) {
+                    break;
+                }
+                at = Some(next);
+            }
+        }
+        at
+    }
+
+    // Like last_before, but remembers where the search stopped on every level: those are the
+    // nodes whose links change when key is inserted or removed
+    fn predecessors<Q>(&self, key: &Q) -> [Position; MAX_LEVEL]
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let mut preds = [None; MAX_LEVEL];
+        let mut at = None;
+        for level in (0..self.head.len()).rev() {
+            while let Some(next) = self.next_of(at, level) {
+                if self.node(next).key.borrow() >= key {
+                    break;
+                }
+                at = Some(next);
+            }
+            preds[level] = at;
+        }
+        preds
+    }
+
+    // Flip coins until one comes up tails, so height h has probability 1/2^h
+    fn random_height(&mut self) -> usize {
+        let height = self.rng.next().trailing_ones() as usize + 1;
+        height.min(MAX_LEVEL)
+    }
+
+    fn alloc(&mut self, node: SkipNode<K, V>) -> usize {
+        match self.free.pop() {
+            Some(index) => {
+                self.nodes[index] = Some(node);
+                index
+            }
+            None => {
+                self.nodes.push(Some(node));
+                self.nodes.len() - 1
+            }
+        }
+    }
+}
+
+impl<K, V> SkipList<K, V> {
+    fn node(&self, index: usize) -> &SkipNode<K, V> {
+        self.nodes[index]
+            .as_ref()
+            .expect("linked nodes are occupied")
+    }
+
+    fn node_mut(&mut self, index: usize) -> &mut SkipNode<K, V> {
+        self.nodes[index]
+            .as_mut()
+            .expect("linked nodes are occupied")
+    }
+
+    fn next_of(&self, at: Position, level: usize) -> Option<usize> {
+        match at {
+            None => self.head.get(level).copied().flatten(),
+            Some(index) => self.node(index).next[level],
+        }
+    }
+
+    fn set_next(&mut self, at: Position, level: usize, to: Option<usize>) {
+        match at {
+            None => self.head[level] = to,
+            Some(index) => self.node_mut(index).next[level] = to,
+        }
+    }
+}
+
+impl<K: Ord, V> Default for SkipList<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: Ord, V> Extend<(K, V)> for SkipList<K, V> {
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        for (key, value) in iter {
+            self.insert(key, value);
+        }
+    }
+}
+
+impl<K: Ord, V> FromIterator<(K, V)> for SkipList<K, V> {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut list = SkipList::new();
+        list.extend(iter);
+        list
+    }
+}
+
+impl<K: fmt::Debug, V: fmt::Debug> fmt::Debug for SkipList<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut map = f.debug_map();
+        let mut next = self.head.first().copied().flatten();
+        while let Some(index) = next {
+            let node = self.node(index);
+            map.entry(&node.key, &node.value);
+            next = node.next[0];
+        }
+        map.finish()
+    }
+}
+
+impl<'a, K: Ord, V> IntoIterator for &'a SkipList<K, V> {
+    type Item = (&'a K, &'a V);
+    type IntoIter = SkipListIter<'a, K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, K: Ord, V> Iterator for SkipListIter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.list.node(self.next_node?);
+        if let Bound::Excluded(end) = self.end {
+            if node.key >= *end {
+                self.next_node = None;
+                self.remaining = 0;
+                return None;
+            }
+        }
+        self.next_node = node.next[0];
+        self.remaining -= 1;
+        Some((&node.key, &node.value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match (self.next_node, self.exact) {
+            (None, _) => (0, Some(0)),
+            (Some(_), true) => (self.remaining, Some(self.remaining)),
+            (Some(_), false) => (0, Some(self.remaining)),
+        }
+    }
+}
+
+// Once next_node is None it stays None
+impl<K: Ord, V> FusedIterator for SkipListIter<'_, K, V> {}
+
+#[cfg(test)]
+mod testing {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn can_insert_get_and_remove() {
+        let mut list = SkipList::with_seed(7);
+        assert_eq!(None, list.insert("b", 2));
+        assert_eq!(None, list.insert("a", 1));
+        assert_eq!(None, list.insert("c", 3));
+        assert_eq!(Some(2), list.insert("b", 20));
+        assert_eq!(3, list.len());
+
+        assert_eq!(Some(&20), list.get("b"));
+        assert_eq!(None, list.get("d"));
+        *list.get_mut("a").unwrap() += 10;
+        assert_eq!(r#"{"a": 11, "b": 20, "c": 3}"#, format!("{list:?}"));
+
+        assert_eq!(Some(20), list.remove("b"));
+        assert_eq!(None, list.remove("b"));
+        assert!(!list.contains_key("b"));
+        assert_eq!(
+            vec![(&"a", &11), (&"c", &3)],
+            list.iter().collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn can_iterate_ranges() {
+        let list: SkipList<u32, u32> = (0..50).map(|k| (k * 2, k)).collect();
+        let keys = |r: SkipListIter<'_, u32, u32>| r.map(|(k, _)| *k).collect::<Vec<_>>();
+
+        assert_eq!(vec![10, 12, 14], keys(list.range(10..16)));
+        assert_eq!(vec![10, 12, 14, 16], keys(list.range(10..=16)));
+        assert_eq!(vec![12, 14, 16], keys(list.range(11..17)));
+        assert_eq!(vec![94, 96, 98], keys(list.range(93..)));
+        assert_eq!(vec![0, 2], keys(list.range(..=3)));
+        assert_eq!(
+            vec![12, 14],
+            keys(list.range((Bound::Excluded(10), Bound::Excluded(16))))
+        );
+        assert!(keys(list.range(200..)).is_empty());
+        assert!(keys(list.range(5..5)).is_empty());
+        assert_eq!(50, list.range(..).count());
+    }
+
+    #[test]
+    fn iterators_report_their_size() {
+        let list: SkipList<u32, u32> = (0..10).map(|k| (k, k)).collect();
+        let mut iter = list.iter();
+        iter.next();
+        assert_eq!((9, Some(9)), iter.size_hint());
+        // a range only knows an upper bound until it runs out
+        let mut range = list.range(2..4);
+        assert_eq!((0, Some(10)), range.size_hint());
+        assert_eq!(2, range.by_ref().count());
+        assert_eq!((0, Some(0)), range.size_hint());
+        assert_eq!(None, range.next());
+    }
+
+    #[test]
+    fn matches_a_btree_map() {
+        let mut list = SkipList::with_seed(42);
+        let mut model = BTreeMap::new();
+        let mut x: u64 = 1;
+        for step in 0..5_000 {
+            x = x.wrapping_mul(6364136223846793005).wrapping_add(1);
+            let key = (x >> 33) % 500;
+            if step % 3 == 0 {
+                assert_eq!(model.remove(&key), list.remove(&key));
+            } else {
+                assert_eq!(model.insert(key, step), list.insert(key, step));
+            }
+        }
+        assert_eq!(model.len(), list.len());
+        assert!(model.iter().eq(list.iter()));
+        assert!(model.range(100..200).eq(list.range(100..200)));
+        // removed slots get reused instead of growing the Vec forever
+        assert!(list.nodes.len() <= 500);
+    }
+
+    #[test]
+    fn towers_have_logarithmic_height() {
+        let list: SkipList<u32, ()> = (0..10_000).map(|k| (k, ())).collect();
+        // 10k values need ~13 levels, 32 would mean the coin flips are broken
+        assert!(
+            (8..=MAX_LEVEL).contains(&list.head.len()),
+            "{}",
+            list.head.len()
+        );
+        // and on average there are about two links per node
+        let links: usize = list.nodes.iter().flatten().map(|n| n.next.len()).sum();
+        assert!((15_000..25_000).contains(&links), "{links}");
+    }
+}
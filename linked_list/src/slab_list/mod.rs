@@ -0,0 +1,422 @@
+use alloc::vec::Vec;
+use core::fmt;
+use core::iter::FusedIterator;
+
+// A doubly linked list that keeps all of its nodes in one Vec and links them by index
+// Instead of a pointer each link is a position in `slots`, so:
+//  - nodes sit next to each other in memory, which is much kinder to the CPU cache than one heap
+//    allocation per node
+//  - no unsafe code is needed, an index can't dangle the way a pointer can (at worst it points at
+//    the wrong slot, which the generation check below catches)
+//  - removing a node just marks its slot free, and the free slots form their own linked list
+//    through the same Vec so the next push can reuse one in O(1)
+pub struct SlabList<T> {
+    slots: Vec<Slot<T>>,
+    head: Option<usize>,
+    tail: Option<usize>,
+    // first slot of the free list, None when every slot is in use
+    free: Option<usize>,
+    len: usize,
+}
+
+// Every slot counts how many times it has been reused
+// A key remembers the generation it was handed out with, so a key to a removed value can't
+// accidentally reach whatever value moved into the slot afterwards
+struct Slot<T> {
+    generation: u64,
+    entry: Entry<T>,
+}
+
+enum Entry<T> {
+    Occupied {
+        value: T,
+        prev: Option<usize>,
+        next: Option<usize>,
+    },
+    Free {
+        next_free: Option<usize>,
+    },
+}
+
+// A handle to one value in a SlabList, returned by the push methods
+// It's what makes O(1) removal from the middle possible: no walking to find the node
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct SlabKey {
+    index: usize,
+    generation: u64,
+}
+
+pub struct SlabListIterRef<'a, T> {
+    list: &'a SlabList<T>,
+    front: Option<usize>,
+    back: Option<usize>,
+    remaining: usize,
+}
+
+pub struct SlabListIter<T> {
+    list: SlabList<T>,
+}
+
+impl<T> SlabList<T> {
+    pub fn new() -> Self {
+        SlabList {
+            slots: Vec::new(),
+            head: None,
+            tail: None,
+            free: None,
+            len: 0,
+        }
+    }
+
+    // Room for capacity values before the Vec has to grow
+    pub fn with_capacity(capacity: usize) -> Self {
+        SlabList {
+            slots: Vec::with_capacity(capacity),
+            ..Self::new()
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn push_front(&mut self, value: T) -> SlabKey {
+        let key = self.alloc(value, None, self.head);
+        match self.head {
+            Some(old_head) => *self.links_mut(old_head).0 = Some(key.index),
+            None => self.tail = Some(key.index),
+        }
+        self.head = Some(key.index);
+        key
+    }
+
+    pub fn push_back(&mut self, value: T) -> SlabKey {
+        let key = self.alloc(value, self.tail, None);
+        match self.tail {
+            Some(old_tail) => *self.links_mut(old_tail).1 = Some(key.index),
+            None => self.head = Some(key.index),
+        }
+        self.tail = Some(key.index);
+        key
+    }
+
+    pub fn pop_front(&mut self) -> Option<T> {
+        self.head.map(|index| self.unlink(index))
+    }
+
+    pub fn pop_back(&mut self) -> Option<T> {
+        self.tail.map(|index| self.unlink(index))
+    }
+
+    // O(1): the key tells us exactly which slot to unlink
+    // None if the value was already removed
+    pub fn remove(&mut self, key: SlabKey) -> Option<T> {
+        self.get(key)?;
+        Some(self.unlink(key.index))
+    }
+
+    pub fn get(&self, key: SlabKey) -> Option<&T> {
+        match self.slots.get(key.index)? {
+            Slot {
+                generation,
+                entry: Entry::Occupied { value, .. },
+            } if *generation == key.generation => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn get_mut(&mut self, key: SlabKey) -> Option<&mut T> {
+        match self.slots.get_mut(key.index)? {
+            Slot {
+                generation,
+                entry: Entry::Occupied { value, .. },
+            } if *generation == key.generation => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn front(&self) -> Option<&T> {
+        self.head.map(|index| self.node(index).0)
+    }
+
+    pub fn back(&self) -> Option<&T> {
+        self.tail.map(|index| self.node(index).0)
+    }
+
+    pub fn iter(&self) -> SlabListIterRef<'_, T> {
+        SlabListIterRef {
+            list: self,
+            front: self.head,
+            back: self.tail,
+            remaining: self.len,
+        }
+    }
+
+    // Drops every value but keeps the Vec's memory around for reuse
+    pub fn clear(&mut self) {
+        self.slots.clear();
+        self.head = None;
+        self.tail = None;
+        self.free = None;
+        self.len = 0;
+    }
+
+    // Puts a node in a free slot if there is one, otherwise on the end of the Vec
+    fn alloc(&mut self, value: T, prev: Option<usize>, next: Option<usize>) -> SlabKey {
+        self.len += 1;
+        let entry = Entry::Occupied { value, prev, next };
+        match self.free {
+            Some(index) => {
+                let slot = &mut self.slots[index];
+                if let Entry::Free { next_free } = slot.entry {
+                    self.free = next_free;
+                }
+                slot.generation += 1;
+                slot.entry = entry;
+                SlabKey {
+                    index,
+                    generation: slot.generation,
+                }
+            }
+            None => {
+                self.slots.push(Slot {
+                    generation: 0,
+                    entry,
+                });
+                SlabKey {
+                    index: self.slots.len() - 1,
+                    generation: 0,
+                }
+            }
+        }
+    }
+
+    // Takes the node at index out of the chain, puts its slot on the free list and returns its
+    // value. index must be an occupied slot
+    fn unlink(&mut self, index: usize) -> T {
+        let entry = core::mem::replace(
+            &mut self.slots[index].entry,
+            Entry::Free {
+                next_free: self.free,
+            },
+        );
+        let Entry::Occupied { value, prev, next } = entry else {
+            unreachable!("only occupied slots are linked into the list");
+        };
+        self.free = Some(index);
+        self.len -= 1;
+
+        match prev {
+            Some(prev) => *self.links_mut(prev).1 = next,
+            None => self.head = next,
+        }
+        match next {
+            Some(next) => *self.links_mut(next).0 = prev,
+            None => self.tail = prev,
+        }
+        value
+    }
+
+    // (value, prev, next) of the node at index, all read with a single lookup since this is
+    // what iteration does at every step
+    fn node(&self, index: usize) -> (&T, Option<usize>, Option<usize>) {
+        match &self.slots[index].entry {
+            Entry::Occupied { value, prev, next } => (value, *prev, *next),
+            Entry::Free { .. } => unreachable!("only occupied slots are linked into the list"),
+        }
+    }
+
+    // (prev, next) of the node at index
+    fn links_mut(&mut self, index: usize) -> (&mut Option<usize>, &mut Option<usize>) {
+        match &mut self.slots[index].entry {
+            Entry::Occupied { prev, next, .. } => (prev, next),
+            Entry::Free { .. } => unreachable!("only occupied slots are linked into the list"),
+        }
+    }
+}
+
+impl<T> Default for SlabList<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Extend<T> for SlabList<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for value in iter {
+            self.push_back(value);
+        }
+    }
+}
+
+impl<T> FromIterator<T> for SlabList<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let iter = iter.into_iter();
+        let mut list = SlabList::with_capacity(iter.size_hint().0);
+        list.extend(iter);
+        list
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for SlabList<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<T> IntoIterator for SlabList<T> {
+    type Item = T;
+    type IntoIter = SlabListIter<T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        SlabListIter { list: self }
+    }
+}
+
+impl<'a, T> IntoIterator for &'a SlabList<T> {
+    type Item = &'a T;
+    type IntoIter = SlabListIterRef<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, T> Iterator for SlabListIterRef<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.front.map(|index| {
+            let (value, _, next) = self.list.node(index);
+            self.front = next;
+            self.remaining -= 1;
+            value
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<T> DoubleEndedIterator for SlabListIterRef<'_, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.back.map(|index| {
+            let (value, prev, _) = self.list.node(index);
+            self.back = prev;
+            self.remaining -= 1;
+            value
+        })
+    }
+}
+
+impl<T> ExactSizeIterator for SlabListIterRef<'_, T> {}
+impl<T> FusedIterator for SlabListIterRef<'_, T> {}
+
+impl<T> Iterator for SlabListIter<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.list.pop_front()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.list.len, Some(self.list.len))
+    }
+}
+
+impl<T> DoubleEndedIterator for SlabListIter<T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.list.pop_back()
+    }
+}
+
+impl<T> ExactSizeIterator for SlabListIter<T> {}
+impl<T> FusedIterator for SlabListIter<T> {}
+
+#[cfg(test)]
+mod testing {
+    use super::*;
+
+    #[test]
+    fn can_push_and_pop_both_ends() {
+        let mut list = SlabList::new();
+        list.push_back(1);
+        list.push_back(2);
+        list.push_front(0);
+        assert_eq!(vec![0, 1, 2], list.iter().copied().collect::<Vec<_>>());
+        assert_eq!(
+            vec![2, 1, 0],
+            list.iter().rev().copied().collect::<Vec<_>>()
+        );
+        assert_eq!((Some(&0), Some(&2)), (list.front(), list.back()));
+
+        assert_eq!(Some(2), list.pop_back());
+        assert_eq!(Some(0), list.pop_front());
+        assert_eq!(Some(1), list.pop_front());
+        assert_eq!(None, list.pop_back());
+        assert!(list.is_empty());
+    }
+
+    #[test]
+    fn can_remove_by_key() {
+        let mut list = SlabList::new();
+        let keys: Vec<SlabKey> = (0..5).map(|v| list.push_back(v)).collect();
+
+        assert_eq!(Some(2), list.remove(keys[2]));
+        assert_eq!(Some(0), list.remove(keys[0]));
+        assert_eq!(Some(4), list.remove(keys[4]));
+        assert_eq!(vec![1, 3], list.iter().copied().collect::<Vec<_>>());
+        assert_eq!((Some(&1), Some(&3)), (list.front(), list.back()));
+
+        // removing twice does nothing
+        assert_eq!(None, list.remove(keys[2]));
+        assert_eq!(2, list.len());
+
+        *list.get_mut(keys[3]).unwrap() *= 10;
+        assert_eq!(Some(&30), list.get(keys[3]));
+    }
+
+    #[test]
+    fn reuses_free_slots() {
+        let mut list = SlabList::new();
+        let old: Vec<SlabKey> = (0..4).map(|v| list.push_back(v)).collect();
+        for key in &old {
+            list.remove(*key);
+        }
+
+        let new: Vec<SlabKey> = (10..14).map(|v| list.push_front(v)).collect();
+        assert_eq!(4, list.slots.len());
+        assert_eq!(
+            vec![13, 12, 11, 10],
+            list.iter().copied().collect::<Vec<_>>()
+        );
+
+        // the old keys point at reused slots, but their generation is out of date
+        for key in &old {
+            assert_eq!(None, list.get(*key));
+            assert_eq!(None, list.remove(*key));
+        }
+        assert_eq!(Some(&13), list.get(new[3]));
+    }
+
+    #[test]
+    fn can_into_iter_both_ways() {
+        let list: SlabList<String> = ["a", "b", "c"].map(String::from).into_iter().collect();
+        assert_eq!(r#"["a", "b", "c"]"#, format!("{list:?}"));
+        let mut iter = list.into_iter();
+        assert_eq!(Some(String::from("c")), iter.next_back());
+        assert_eq!(2, iter.len());
+        assert_eq!(vec!["a", "b"], iter.collect::<Vec<_>>());
+    }
+}
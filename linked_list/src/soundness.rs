@@ -0,0 +1,326 @@
+// Tests aimed at the unsafe code in LinkedList and DoublyLinkedList rather than their features
+// Everything here is small on purpose so it runs under Miri, which interprets the program and
+// reports undefined behaviour (use after free, double free, leaks, aliasing violations):
+//  cargo +nightly miri test
+//
+// Values are Boxes so that every one of them is a separate allocation: a value that's dropped
+// twice or never dropped at all shows up as a double free or a leak
+
+use std::collections::VecDeque;
+
+use crate::{CircularList, DoublyLinkedList, LinkedList};
+
+// Variance
+// A LinkedList<&'static str> should be usable anywhere a LinkedList<&'a str> is expected, just
+// like a Vec. That only holds because PhantomData<Box<Node<T>>> makes the list covariant in T, a
+// bare *mut Node<T> would make it invariant and these functions wouldn't compile
+// (so "running" these tests is really just compiling them)
+#[allow(dead_code)]
+fn linked_list_is_covariant<'a>(ll: LinkedList<&'static str>) -> LinkedList<&'a str> {
+    ll
+}
+
+#[allow(dead_code)]
+fn linked_list_iters_are_covariant<'i, 'a>(
+    iter: crate::LinkedListIterRef<'i, &'static str>,
+    into_iter: crate::LinkedListIter<&'static str>,
+) -> (
+    crate::LinkedListIterRef<'i, &'a str>,
+    crate::LinkedListIter<&'a str>,
+) {
+    (iter, into_iter)
+}
+
+#[allow(dead_code)]
+fn doubly_linked_list_is_covariant<'a>(
+    list: DoublyLinkedList<&'static str>,
+) -> DoublyLinkedList<&'a str> {
+    list
+}
+
+#[allow(dead_code)]
+fn doubly_linked_list_iters_are_covariant<'i, 'a>(
+    iter: crate::DoublyLinkedListIterRef<'i, &'static str>,
+    into_iter: crate::DoublyLinkedListIter<&'static str>,
+) -> (
+    crate::DoublyLinkedListIterRef<'i, &'a str>,
+    crate::DoublyLinkedListIter<&'a str>,
+) {
+    (iter, into_iter)
+}
+
+// Note: the &mut iterators are (correctly) invariant in T, like std's IterMut, so there's no
+// check for them: if they were covariant you could write a &'short str into a list of &'static str
+
+// A tiny deterministic random number generator, so the op sequences below are the same on every
+// run (and under Miri, which has no access to real randomness by default)
+struct Lcg(u64);
+
+impl Lcg {
+    fn below(&mut self, n: usize) -> usize {
+        self.0 = self
+            .0
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        ((self.0 >> 33) as usize) % n.max(1)
+    }
+}
+
+fn values(ll: &LinkedList<Box<i32>>) -> Vec<i32> {
+    ll.iter().map(|v| **v).collect()
+}
+
+// Runs a random mix of every operation that touches raw links against a VecDeque doing the same
+// thing, checking after every step that the two agree (including both ends, which is where a
+// stale tail pointer would show up)
+#[test]
+fn linked_list_matches_a_model() {
+    let mut rng = Lcg(0x5eed);
+    let mut ll: LinkedList<Box<i32>> = LinkedList::new();
+    let mut model: VecDeque<i32> = VecDeque::new();
+
+    for value in 0..400 {
+        match rng.below(13) {
+            0 => {
+                ll.push_front(Box::new(value));
+                model.push_front(value);
+            }
+            1 | 2 => {
+                ll.push_back(Box::new(value));
+                model.push_back(value);
+            }
+            3 => assert_eq!(model.pop_front(), ll.pop_front().map(|v| *v)),
+            4 => assert_eq!(model.pop_back(), ll.pop_back().map(|v| *v)),
+            5 => {
+                let at = rng.below(model.len() + 1);
+                assert!(ll.insert(at, Box::new(value)).is_ok());
+                model.insert(at, value);
+            }
+            6 => {
+                let at = rng.below(model.len() + 1);
+                assert_eq!(model.remove(at), ll.remove(at).map(|v| *v));
+            }
+            7 => {
+                let mut other: LinkedList<Box<i32>> = (0..rng.below(3) as i32)
+                    .map(|i| Box::new(value + i))
+                    .collect();
+                model.extend(other.iter().map(|v| **v));
+                ll.append(&mut other);
+                assert!(other.is_empty());
+            }
+            8 => {
+                let at = rng.below(model.len() + 1);
+                let mut back = ll.split_off(at);
+                assert_eq!(
+                    model.iter().skip(at).copied().collect::<Vec<_>>(),
+                    values(&back)
+                );
+                back.push_back(Box::new(value));
+                model.push_back(value);
+                ll.append(&mut back);
+            }
+            9 => {
+                let n = rng.below(2 * model.len() + 1);
+                ll.rotate_left(n);
+                if !model.is_empty() {
+                    model.rotate_left(n % model.len());
+                }
+            }
+            10 => {
+                let keep = rng.below(3) as i32 + 2;
+                ll.retain(|v| **v % keep != 0);
+                model.retain(|v| v % keep != 0);
+            }
+            11 => {
+                // only take a couple of values and leave the iterator half way through
+                let (first, second) = {
+                    let mut taken = ll.extract_if(|v| **v % 2 == 0);
+                    (taken.next().map(|v| *v), taken.next().map(|v| *v))
+                };
+                for removed in [first, second].into_iter().flatten() {
+                    let at = model.iter().position(|v| *v == removed).unwrap();
+                    model.remove(at);
+                }
+            }
+            _ => {
+                ll.sort_by(|a, b| b.cmp(a));
+                model.make_contiguous().sort_by(|a, b| b.cmp(a));
+            }
+        }
+
+        assert_eq!(model.len(), ll.len());
+        assert_eq!(model.front(), ll.front().map(|v| &**v));
+        assert_eq!(model.back(), ll.back().map(|v| &**v));
+        assert_eq!(model.iter().copied().collect::<Vec<_>>(), values(&ll));
+    }
+
+    let (evens, odds) = ll.partition(|v| **v % 2 == 0);
+    let merged = LinkedList::merge_sorted_by(evens, odds, |a, b| b.cmp(a));
+    assert_eq!(model.len(), merged.len());
+}
+
+#[test]
+fn doubly_linked_list_matches_a_model() {
+    let mut rng = Lcg(0xd0b1e);
+    let mut list: DoublyLinkedList<Box<i32>> = DoublyLinkedList::new();
+    let mut model: VecDeque<i32> = VecDeque::new();
+
+    for value in 0..400 {
+        match rng.below(8) {
+            0 => {
+                list.push_front(Box::new(value));
+                model.push_front(value);
+            }
+            1 => {
+                list.push_back(Box::new(value));
+                model.push_back(value);
+            }
+            2 => assert_eq!(model.pop_front(), list.pop_front().map(|v| *v)),
+            3 => assert_eq!(model.pop_back(), list.pop_back().map(|v| *v)),
+            4 => {
+                // walk a cursor somewhere (possibly onto the ghost) and insert on both sides
+                let at = rng.below(model.len() + 1);
+                let mut cursor = list.cursor_front_mut();
+                for _ in 0..at {
+                    cursor.move_next();
+                }
+                cursor.insert_before(Box::new(value));
+                cursor.insert_after(Box::new(-value));
+                model.insert(at, value);
+                // on the ghost "after" means the front of the list
+                if at == model.len() - 1 {
+                    model.push_front(-value);
+                } else {
+                    model.insert(at + 2, -value);
+                }
+            }
+            5 => {
+                let at = rng.below(model.len() + 1);
+                let mut cursor = list.cursor_front_mut();
+                for _ in 0..at {
+                    cursor.move_next();
+                }
+                assert_eq!(model.remove(at), cursor.remove_current().map(|v| *v));
+            }
+            6 => {
+                let at = rng.below(model.len() + 1);
+                let mut cursor = list.cursor_front_mut();
+                for _ in 0..at {
+                    cursor.move_next();
+                }
+                let back = cursor.split_after();
+                let expected: Vec<i32> = if at == model.len() {
+                    model.drain(..).collect()
+                } else {
+                    model.drain(at + 1..).collect()
+                };
+                assert_eq!(expected, back.iter().map(|v| **v).collect::<Vec<_>>());
+            }
+            _ => {
+                for v in list.iter_mut() {
+                    **v += 1;
+                }
+                for v in model.iter_mut() {
+                    *v += 1;
+                }
+            }
+        }
+
+        assert_eq!(model.len(), list.len());
+        assert_eq!(model.front(), list.front().map(|v| &**v));
+        assert_eq!(model.back(), list.back().map(|v| &**v));
+        assert_eq!(
+            model.iter().rev().copied().collect::<Vec<_>>(),
+            list.iter().rev().map(|v| **v).collect::<Vec<_>>()
+        );
+    }
+
+    let mut into_iter = list.into_iter();
+    assert_eq!(model.pop_back(), into_iter.next_back().map(|v| *v));
+    // dropping a half used into_iter has to free what's left
+    drop(into_iter);
+}
+
+#[test]
+fn circular_list_matches_a_model() {
+    let mut rng = Lcg(0xc1c1e);
+    let mut list: CircularList<Box<i32>> = CircularList::new();
+    let mut model: VecDeque<i32> = VecDeque::new();
+
+    for value in 0..400 {
+        match rng.below(6) {
+            0 => {
+                list.push_front(Box::new(value));
+                model.push_front(value);
+            }
+            1 => {
+                list.push_back(Box::new(value));
+                model.push_back(value);
+            }
+            2 => assert_eq!(model.pop_front(), list.pop_front().map(|v| *v)),
+            3 => {
+                let n = rng.below(2 * model.len() + 1);
+                list.rotate_by(n);
+                if !model.is_empty() {
+                    model.rotate_left(n % model.len());
+                }
+            }
+            _ => {
+                // walk the cursor past the end at least once, then change the ring around it
+                let steps = rng.below(2 * model.len() + 1);
+                let mut cursor = list.cursor_mut();
+                for _ in 0..steps {
+                    cursor.move_next();
+                }
+                let at = if model.is_empty() {
+                    0
+                } else {
+                    steps % model.len()
+                };
+                match rng.below(3) {
+                    0 => assert_eq!(model.remove(at), cursor.remove_current().map(|v| *v)),
+                    1 => {
+                        cursor.insert_before(Box::new(value));
+                        model.insert(at, value);
+                    }
+                    _ => {
+                        cursor.insert_after(Box::new(value));
+                        model.insert((at + 1).min(model.len()), value);
+                    }
+                }
+            }
+        }
+
+        assert_eq!(model.len(), list.len());
+        assert_eq!(model.front(), list.front().map(|v| &**v));
+        assert_eq!(model.back(), list.back().map(|v| &**v));
+        assert_eq!(
+            model.iter().copied().collect::<Vec<_>>(),
+            list.iter().map(|v| **v).collect::<Vec<_>>()
+        );
+    }
+}
+
+// Iterators hand out references into nodes, and those references have to stay valid (and not
+// alias a &mut) while the iterator keeps walking. Miri checks that under Stacked Borrows
+#[test]
+fn references_from_iterators_stay_valid() {
+    let mut ll: LinkedList<Box<i32>> = (0..8).map(Box::new).collect();
+    let refs: Vec<&Box<i32>> = ll.iter().collect();
+    assert_eq!(28, refs.iter().map(|v| ***v).sum::<i32>());
+
+    let muts: Vec<&mut Box<i32>> = ll.iter_mut().collect();
+    for v in muts {
+        **v *= 2;
+    }
+    assert_eq!(Some(&Box::new(14)), ll.back());
+
+    let mut list: DoublyLinkedList<Box<i32>> = (0..8).map(Box::new).collect();
+    let mut iter = list.iter_mut();
+    let (first, last) = (iter.next().unwrap(), iter.next_back().unwrap());
+    std::mem::swap(first, last);
+    assert_eq!(
+        vec![7, 1, 2, 3, 4, 5, 6, 0],
+        list.iter().map(|v| **v).collect::<Vec<_>>()
+    );
+}
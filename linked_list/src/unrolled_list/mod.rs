@@ -0,0 +1,432 @@
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+use core::iter::FusedIterator;
+use core::slice;
+
+use crate::doubly_linked_list::{
+    CursorMut, DoublyLinkedList, DoublyLinkedListIter, DoublyLinkedListIterRef,
+};
+
+// An unrolled linked list: a linked list of small arrays ("chunks") instead of single values
+//
+//   [0 1 2 3] <-> [4 5] <-> [6 7 8]
+//
+// Walking it is mostly walking through arrays, which the CPU is very good at, and there's one
+// node (and one allocation) per N values instead of per value. Inserting and removing still only
+// touch one chunk, so it keeps the cheap middle edits that make linked lists worth having.
+//
+// The chunk list itself is just a DoublyLinkedList<Vec<T>>, its cursor does the relinking when
+// chunks are split or merged. Every chunk is a Vec with room for exactly N values:
+//  - a chunk that would grow past N is split in two halves
+//  - a chunk that shrinks below N / 2 is merged with a neighbour if the two fit in a single chunk
+//    (and empty chunks are always removed), so chunks don't thin out as values are removed
+pub struct UnrolledList<T, const N: usize = 16> {
+    chunks: DoublyLinkedList<Vec<T>>,
+    len: usize,
+}
+
+pub struct UnrolledListIterRef<'a, T> {
+    chunks: DoublyLinkedListIterRef<'a, Vec<T>>,
+    chunk: slice::Iter<'a, T>,
+    remaining: usize,
+}
+
+pub struct UnrolledListIter<T> {
+    chunks: DoublyLinkedListIter<Vec<T>>,
+    chunk: vec::IntoIter<T>,
+    remaining: usize,
+}
+
+impl<T, const N: usize> UnrolledList<T, N> {
+    // Checked when the list is first built rather than being a surprise on the first split
+    const HALF: usize = {
+        assert!(
+            N >= 2,
+            "chunks need room for at least two values to be split"
+        );
+        N / 2
+    };
+
+    pub fn new() -> Self {
+        let _ = Self::HALF;
+        UnrolledList {
+            chunks: DoublyLinkedList::new(),
+            len: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn chunk_count(&self) -> usize {
+        self.chunks.len()
+    }
+
+    pub fn push_back(&mut self, value: T) {
+        let mut cursor = self.chunks.cursor_back_mut();
+        match cursor.current() {
+            Some(chunk) if chunk.len() < N => chunk.push(value),
+            _ => self.chunks.push_back(Self::chunk_of(value)),
+        }
+        self.len += 1;
+    }
+
+    pub fn push_front(&mut self, value: T) {
+        let mut cursor = self.chunks.cursor_front_mut();
+        match cursor.current() {
+            Some(chunk) if chunk.len() < N => chunk.insert(0, value),
+            _ => self.chunks.push_front(Self::chunk_of(value)),
+        }
+        self.len += 1;
+    }
+
+    pub fn pop_front(&mut self) -> Option<T> {
+        let mut cursor = self.chunks.cursor_front_mut();
+        let chunk = cursor.current()?;
+        let value = chunk.remove(0);
+        if chunk.is_empty() {
+            cursor.remove_current();
+        }
+        self.len -= 1;
+        Some(value)
+    }
+
+    pub fn pop_back(&mut self) -> Option<T> {
+        let mut cursor = self.chunks.cursor_back_mut();
+        let chunk = cursor.current()?;
+        let value = chunk.pop();
+        if chunk.is_empty() {
+            cursor.remove_current();
+        }
+        self.len -= 1;
+        value
+    }
+
+    pub fn front(&self) -> Option<&T> {
+        self.chunks.front().and_then(|chunk| chunk.first())
+    }
+
+    pub fn back(&self) -> Option<&T> {
+        self.chunks.back().and_then(|chunk| chunk.last())
+    }
+
+    // O(index / N): we skip over whole chunks at a time
+    pub fn get(&self, mut index: usize) -> Option<&T> {
+        for chunk in self.chunks.iter() {
+            if index < chunk.len() {
+                return chunk.get(index);
+            }
+            index -= chunk.len();
+        }
+        None
+    }
+
+    pub fn get_mut(&mut self, mut index: usize) -> Option<&mut T> {
+        for chunk in self.chunks.iter_mut() {
+            if index < chunk.len() {
+                return chunk.get_mut(index);
+            }
+            index -= chunk.len();
+        }
+        None
+    }
+
+    // Inserts value at position index, index == len() appends
+    // Out of bounds we hand the value back, like LinkedList::insert
+    pub fn insert(&mut self, index: usize, value: T) -> Result<(), T> {
+        if index > self.len {
+            return Err(value);
+        }
+        if index == self.len {
+            self.push_back(value);
+            return Ok(());
+        }
+
+        let (mut cursor, offset) = Self::cursor_at(&mut self.chunks, index);
+        let chunk = cursor.current().expect("index is within bounds");
+        if chunk.len() < N {
+            chunk.insert(offset, value);
+        } else {
+            // full: move the back half into a new chunk right after this one, then insert into
+            // whichever half the position falls in
+            let mut back = Vec::with_capacity(N);
+            back.extend(chunk.drain(Self::HALF..));
+            if offset <= Self::HALF {
+                chunk.insert(offset, value);
+            } else {
+                back.insert(offset - Self::HALF, value);
+            }
+            cursor.insert_after(back);
+        }
+        self.len += 1;
+        Ok(())
+    }
+
+    // Removes and returns the value at position index, None if there isn't one
+    pub fn remove(&mut self, index: usize) -> Option<T> {
+        if index >= self.len {
+            return None;
+        }
+
+        let (mut cursor, offset) = Self::cursor_at(&mut self.chunks, index);
+        let chunk = cursor.current().expect("index is within bounds");
+        let value = chunk.remove(offset);
+        let chunk_len = chunk.len();
+        if chunk_len == 0 {
+            cursor.remove_current();
+        } else if chunk_len < Self::HALF {
+            // less than half full: fold the next chunk into this one if they fit together,
+            // otherwise try folding this one into the previous chunk
+            if cursor
+                .peek_next()
+                .is_some_and(|next| chunk_len + next.len() <= N)
+            {
+                cursor.move_next();
+                let next = cursor.remove_current().expect("peeked above");
+                cursor.move_prev();
+                cursor
+                    .current()
+                    .expect("moved back onto our chunk")
+                    .extend(next);
+            } else if cursor
+                .peek_prev()
+                .is_some_and(|prev| prev.len() + chunk_len <= N)
+            {
+                let chunk = cursor.remove_current().expect("cursor is on a chunk");
+                cursor.move_prev();
+                cursor.current().expect("peeked above").extend(chunk);
+            }
+        }
+        self.len -= 1;
+        Some(value)
+    }
+
+    pub fn iter(&self) -> UnrolledListIterRef<'_, T> {
+        UnrolledListIterRef {
+            chunks: self.chunks.iter(),
+            chunk: [].iter(),
+            remaining: self.len,
+        }
+    }
+
+    fn chunk_of(value: T) -> Vec<T> {
+        let mut chunk = Vec::with_capacity(N);
+        chunk.push(value);
+        chunk
+    }
+
+    // A cursor on the chunk holding position index, and where index is inside that chunk
+    // index must be < len
+    fn cursor_at(
+        chunks: &mut DoublyLinkedList<Vec<T>>,
+        mut index: usize,
+    ) -> (CursorMut<'_, Vec<T>>, usize) {
+        let mut cursor = chunks.cursor_front_mut();
+        while let Some(chunk) = cursor.current() {
+            if index < chunk.len() {
+                break;
+            }
+            index -= chunk.len();
+            cursor.move_next();
+        }
+        (cursor, index)
+    }
+}
+
+impl<T, const N: usize> Default for UnrolledList<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, const N: usize> Extend<T> for UnrolledList<T, N> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for value in iter {
+            self.push_back(value);
+        }
+    }
+}
+
+impl<T, const N: usize> FromIterator<T> for UnrolledList<T, N> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut list = UnrolledList::new();
+        list.extend(iter);
+        list
+    }
+}
+
+impl<T: fmt::Debug, const N: usize> fmt::Debug for UnrolledList<T, N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<T, const N: usize> IntoIterator for UnrolledList<T, N> {
+    type Item = T;
+    type IntoIter = UnrolledListIter<T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        UnrolledListIter {
+            remaining: self.len,
+            chunks: self.chunks.into_iter(),
+            chunk: Vec::new().into_iter(),
+        }
+    }
+}
+
+impl<'a, T, const N: usize> IntoIterator for &'a UnrolledList<T, N> {
+    type Item = &'a T;
+    type IntoIter = UnrolledListIterRef<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+// Both iterators work through one chunk at a time and only go back to the chunk list when the
+// current chunk runs out
+impl<'a, T> Iterator for UnrolledListIterRef<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(value) = self.chunk.next() {
+                self.remaining -= 1;
+                return Some(value);
+            }
+            self.chunk = self.chunks.next()?.iter();
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<T> ExactSizeIterator for UnrolledListIterRef<'_, T> {}
+impl<T> FusedIterator for UnrolledListIterRef<'_, T> {}
+
+impl<T> Iterator for UnrolledListIter<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(value) = self.chunk.next() {
+                self.remaining -= 1;
+                return Some(value);
+            }
+            self.chunk = self.chunks.next()?.into_iter();
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<T> ExactSizeIterator for UnrolledListIter<T> {}
+impl<T> FusedIterator for UnrolledListIter<T> {}
+
+#[cfg(test)]
+mod testing {
+    use super::*;
+
+    fn chunk_lens<T, const N: usize>(list: &UnrolledList<T, N>) -> Vec<usize> {
+        list.chunks.iter().map(Vec::len).collect()
+    }
+
+    #[test]
+    fn can_push_and_pop_both_ends() {
+        let mut list: UnrolledList<i32, 4> = UnrolledList::new();
+        for v in 0..6 {
+            list.push_back(v);
+        }
+        list.push_front(-1);
+        assert_eq!(vec![1, 4, 2], chunk_lens(&list));
+        assert_eq!(
+            vec![-1, 0, 1, 2, 3, 4, 5],
+            list.iter().copied().collect::<Vec<_>>()
+        );
+        assert_eq!((Some(&-1), Some(&5)), (list.front(), list.back()));
+
+        assert_eq!(Some(-1), list.pop_front());
+        assert_eq!(vec![4, 2], chunk_lens(&list));
+        assert_eq!(Some(5), list.pop_back());
+        assert_eq!(Some(4), list.pop_back());
+        assert_eq!(vec![4], chunk_lens(&list));
+        assert_eq!(4, list.len());
+        assert_eq!(vec![0, 1, 2, 3], list.into_iter().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn full_chunks_split_on_insert() {
+        let mut list: UnrolledList<i32, 4> = (0..4).collect();
+        assert_eq!(vec![4], chunk_lens(&list));
+        assert!(list.insert(1, 10).is_ok());
+        assert_eq!(vec![3, 2], chunk_lens(&list));
+        assert!(list.insert(4, 20).is_ok());
+        assert_eq!(vec![3, 3], chunk_lens(&list));
+        assert_eq!(
+            vec![0, 10, 1, 2, 20, 3],
+            list.iter().copied().collect::<Vec<_>>()
+        );
+        assert_eq!(Err(7), list.insert(8, 7));
+    }
+
+    #[test]
+    fn small_chunks_merge_on_remove() {
+        let mut list: UnrolledList<i32, 4> = (0..8).collect();
+        assert_eq!(vec![4, 4], chunk_lens(&list));
+        assert_eq!(Some(1), list.remove(1));
+        assert_eq!(Some(0), list.remove(0));
+        assert_eq!(vec![2, 4], chunk_lens(&list));
+        // dropping below half full, but 1 + 4 doesn't fit in one chunk
+        assert_eq!(Some(2), list.remove(0));
+        assert_eq!(vec![1, 4], chunk_lens(&list));
+        assert_eq!(Some(4), list.remove(1));
+        assert_eq!(Some(5), list.remove(1));
+        assert_eq!(vec![1, 2], chunk_lens(&list));
+        // the last chunk has no next one, but it fits into the one before it
+        assert_eq!(Some(6), list.remove(1));
+        assert_eq!(vec![2], chunk_lens(&list));
+        assert_eq!(vec![3, 7], list.iter().copied().collect::<Vec<_>>());
+        assert_eq!(None, list.remove(2));
+    }
+
+    #[test]
+    fn matches_a_vec() {
+        let mut list: UnrolledList<u32, 8> = UnrolledList::new();
+        let mut model = Vec::new();
+        let mut x: u32 = 7;
+        for step in 0..3_000 {
+            x ^= x << 13;
+            x ^= x >> 17;
+            x ^= x << 5;
+            let at = x as usize % (model.len() + 1);
+            if step % 3 == 0 && !model.is_empty() {
+                let at = at.min(model.len() - 1);
+                assert_eq!(Some(model.remove(at)), list.remove(at));
+            } else {
+                assert!(list.insert(at, step).is_ok());
+                model.insert(at, step);
+            }
+        }
+        assert_eq!(model.len(), list.len());
+        assert_eq!(model, list.iter().copied().collect::<Vec<_>>());
+        assert_eq!(model.get(100), list.get(100));
+        *list.get_mut(100).unwrap() = 0;
+        assert_eq!(Some(&0), list.get(100));
+        // chunks stay reasonably full, so there are far fewer of them than values
+        assert!(
+            list.chunk_count() * 2 < model.len(),
+            "{}",
+            list.chunk_count()
+        );
+    }
+}
@@ -0,0 +1,103 @@
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use bloom_filter::{BloomError, BloomFilter};
+use clap::Subcommand;
+use report::{Error, ErrorKind, Result, ResultExt};
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    #[command(about = "Build a filter from a file with one item per line")]
+    Build {
+        items: PathBuf,
+        filter: PathBuf,
+        #[arg(
+            long,
+            default_value_t = 0.01,
+            help = "false positive rate to size the filter for"
+        )]
+        fp_rate: f64,
+        #[arg(
+            long,
+            help = "items to size the filter for [default: the number of lines]"
+        )]
+        capacity: Option<usize>,
+    },
+    #[command(
+        about = "Check items against a filter, printing `item<TAB>maybe` or `item<TAB>no` for each",
+        long_about = "Check items against a filter, printing `item<TAB>maybe` or `item<TAB>no` for each. With no items on the command line they're read from stdin, one per line."
+    )]
+    Check { filter: PathBuf, items: Vec<String> },
+}
+
+pub fn run(command: Command) -> Result<()> {
+    match command {
+        Command::Build {
+            items,
+            filter,
+            fp_rate,
+            capacity,
+        } => build(&items, &filter, fp_rate, capacity),
+        Command::Check { filter, items } => check(&filter, items),
+    }
+}
+
+fn build(items: &Path, filter: &Path, fp_rate: f64, capacity: Option<usize>) -> Result<()> {
+    let lines = read_lines(BufReader::new(File::open(items).in_file(items)?)).in_file(items)?;
+    let capacity = capacity.unwrap_or(lines.len()).max(1);
+
+    let mut bloom = BloomFilter::new(fp_rate, capacity).map_err(bloom_error)?;
+    for line in &lines {
+        bloom.add_item(line);
+    }
+    log::info!(
+        "{} items into {} bits with {} hashes",
+        lines.len(),
+        bloom.size(),
+        bloom.hash_count()
+    );
+
+    fs::write(filter, bloom.to_bytes()).in_file(filter)
+}
+
+fn check(filter: &Path, items: Vec<String>) -> Result<()> {
+    let bytes = fs::read(filter).in_file(filter)?;
+    let bloom = BloomFilter::from_bytes(&bytes)
+        .map_err(bloom_error)
+        .context("not a bloom filter")
+        .in_file(filter)?;
+
+    let items = if items.is_empty() {
+        read_lines(io::stdin().lock())?
+    } else {
+        items
+    };
+    let mut out = BufWriter::new(io::stdout().lock());
+    for item in items {
+        let answer = if bloom.check(&item) { "maybe" } else { "no" };
+        writeln!(out, "{item}\t{answer}")?;
+    }
+    out.flush()?;
+    Ok(())
+}
+
+// Bad parameters can only have come from the command line
+fn bloom_error(e: BloomError) -> Error {
+    let kind = match e {
+        BloomError::InvalidParams(_) => ErrorKind::Usage,
+        BloomError::Corrupt(_) => ErrorKind::Data,
+        BloomError::Io(_) => ErrorKind::Io,
+        BloomError::Incompatible(_) | BloomError::Full | BloomError::UnknownPartition => {
+            ErrorKind::Internal
+        }
+    };
+    Error::new(kind, e.to_string())
+}
+
+// Blank lines aren't items
+fn read_lines(rdr: impl BufRead) -> io::Result<Vec<String>> {
+    rdr.lines()
+        .filter(|line| line.as_ref().map_or(true, |line| !line.is_empty()))
+        .collect()
+}
@@ -0,0 +1,93 @@
+// One binary for the command line side of the subprojects
+//
+//   lr dbsnp (index | map | rmap | lookup) ...
+//   lr bloom build words.txt words.bloom [--fp-rate 0.01]
+//   lr bloom check words.bloom [item]...
+//
+// Each subproject gets a clap subcommand and a run function. dbsnp's are mapdbsnp's own, from
+// mapdbsnp::cli, so it takes the same flags as the mapdbsnp binary (listed there). Logging goes to
+// stderr, -v turns on info and -vv debug (RUST_LOG still wins if it's set), and errors are printed
+// once here by report's Reporter, with where they happened and what caused them. The exit code
+// says what kind of error it was (see report::ErrorKind). Since nothing but results goes to
+// stdout, the dbsnp commands take - for their input and output to sit in a pipeline:
+//
+//   zcat input.tsv.gz | lr dbsnp map - map.idx - | sort -k1,1V
+
+mod bloom;
+
+use std::process::ExitCode;
+
+use clap::{ArgAction, Parser, Subcommand};
+use report::Reporter;
+
+#[derive(Debug, Parser)]
+#[command(name = "lr", version, about = "Command line tools from learning_rust")]
+struct Cli {
+    #[arg(short, long, global = true, action = ArgAction::Count, help = "-v for info, -vv for debug")]
+    verbose: u8,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    #[command(subcommand, about = "Map rsids to loci and back with a dbSNP index")]
+    Dbsnp(mapdbsnp::cli::Command),
+    #[command(subcommand, about = "Build and query Bloom filters")]
+    Bloom(bloom::Command),
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    init_logging(cli.verbose);
+
+    let result = match cli.command {
+        Command::Dbsnp(command) => mapdbsnp::cli::run(command),
+        Command::Bloom(command) => bloom::run(command),
+    };
+    Reporter::new("lr").exit(result)
+}
+
+fn init_logging(verbose: u8) {
+    let level = match verbose {
+        0 => "warn",
+        1 => "info",
+        _ => "debug",
+    };
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or(level))
+        .format_timestamp(None)
+        .init();
+}
+
+#[cfg(test)]
+mod testing {
+    use clap::CommandFactory;
+
+    use super::*;
+
+    #[test]
+    fn cli_is_well_formed() {
+        Cli::command().debug_assert();
+    }
+
+    #[test]
+    fn parses_subcommands() {
+        let cli = Cli::parse_from([
+            "lr", "-v", "dbsnp", "map", "in", "idx", "out", "--region", "1:5-9",
+        ]);
+        assert_eq!(cli.verbose, 1);
+        assert!(matches!(
+            cli.command,
+            Command::Dbsnp(mapdbsnp::cli::Command::Map { ref options, .. })
+                if options.regions == ["1:5-9"]
+        ));
+
+        let cli = Cli::parse_from(["lr", "bloom", "check", "f.bloom", "a", "b", "-vv"]);
+        assert_eq!(cli.verbose, 2);
+        assert!(matches!(
+            cli.command,
+            Command::Bloom(bloom::Command::Check { ref items, .. }) if items.len() == 2
+        ));
+    }
+}
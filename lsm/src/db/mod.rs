@@ -0,0 +1,551 @@
+use std::collections::HashSet;
+use std::fs::{self, File};
+use std::io::Write;
+use std::mem;
+use std::ops::{Bound, RangeBounds};
+use std::path::{Path, PathBuf};
+
+use crate::memtable::Memtable;
+use crate::merge::{Entry, MergeIter, Source};
+use crate::sstable::{bloom_path, Table, TableBuilder};
+use crate::wal::Wal;
+use crate::LsmError;
+
+// A log-structured merge tree: writes go to a log and a memtable, reads look newest to oldest
+//
+//   put/delete -> wal.log + memtable
+//                              | flush, once the memtable is big enough
+//                              v
+//   level 0     [t9] [t8] [t7]             whole memtables, keys overlap, newest first
+//                              | compact, once there are l0_tables of them
+//                              v
+//   level 1     [a..f] [g..m] [n..z]       no overlaps, level_size bytes at most
+//   level 2     [a..c] [d..e] ... [x..z]   no overlaps, level_ratio times bigger
+//   ...
+//
+// Nothing is ever changed in place. A newer version of a key (or a tombstone for it) just sits
+// higher up and hides the older ones, and compaction merges a level into the next one down,
+// keeping only the newest version of each key. That turns random writes into sequential ones at
+// the cost of a read maybe looking in several places, which is what the bloom filters are for.
+//
+// Which tables make up which level is in MANIFEST, a line of "level id" per table (level 0 newest
+// first). It's replaced whole by writing a new one and renaming it over the old, so a crash leaves
+// either the old set of tables or the new one. Tables a crash left out of it get deleted on open.
+const MANIFEST: &str = "MANIFEST";
+const WAL: &str = "wal.log";
+const MAX_LEVELS: usize = 7;
+
+#[derive(Debug, Clone)]
+pub struct Options {
+    // Flush the memtable once its keys and values take this many bytes
+    pub memtable_size: usize,
+    // Compact level 0 into level 1 once it has this many tables
+    pub l0_tables: usize,
+    // Most bytes of tables level 1 can hold, each level after holds level_ratio times more
+    pub level_size: u64,
+    pub level_ratio: u64,
+    // Compaction starts a new output table once one gets this big
+    pub table_size: u64,
+    // fsync the log on every write
+    pub sync: bool,
+    pub bloom_fp_rate: f64,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            memtable_size: 4 << 20,
+            l0_tables: 4,
+            level_size: 10 << 20,
+            level_ratio: 10,
+            table_size: 2 << 20,
+            sync: false,
+            bloom_fp_rate: 0.01,
+        }
+    }
+}
+
+// A table and the id its file is named by
+struct TableFile {
+    id: u64,
+    table: Table,
+}
+
+pub struct Db {
+    dir: PathBuf,
+    options: Options,
+    wal: Wal,
+    memtable: Memtable,
+    // levels[0] newest first, the rest in key order
+    levels: Vec<Vec<TableFile>>,
+    next_id: u64,
+    // per level, the last key of the table compacted from it last time, so the next compaction
+    // takes the table after it and every part of the key space gets its turn
+    compact_after: Vec<Vec<u8>>,
+}
+
+impl Db {
+    // Opens the database in dir, creating it if it isn't there
+    pub fn open(dir: impl AsRef<Path>, options: Options) -> Result<Self, LsmError> {
+        if options.memtable_size == 0
+            || options.l0_tables == 0
+            || options.level_size == 0
+            || options.level_ratio < 2
+            || options.table_size == 0
+        {
+            return Err(LsmError::InvalidParams(format!("{options:?}")));
+        }
+        let dir = dir.as_ref().to_path_buf();
+        fs::create_dir_all(&dir)?;
+
+        let mut levels: Vec<Vec<TableFile>> = (0..MAX_LEVELS).map(|_| Vec::new()).collect();
+        let mut live = HashSet::new();
+        for (level, id) in read_manifest(&dir.join(MANIFEST))? {
+            let table = Table::open(table_path(&dir, id), options.bloom_fp_rate)?;
+            levels[level].push(TableFile { id, table });
+            live.insert(id);
+        }
+        remove_orphans(&dir, &live)?;
+
+        let (wal, replayed) = Wal::open(dir.join(WAL), options.sync)?;
+        let mut memtable = Memtable::new();
+        for (key, value) in &replayed {
+            memtable.insert(key, value.as_deref());
+        }
+
+        let mut db = Db {
+            dir,
+            options,
+            wal,
+            memtable,
+            levels,
+            next_id: live.iter().max().map_or(1, |id| id + 1),
+            compact_after: vec![Vec::new(); MAX_LEVELS],
+        };
+        if db.memtable.size() >= db.options.memtable_size {
+            db.flush()?;
+        }
+        Ok(db)
+    }
+
+    pub fn put(&mut self, key: &[u8], value: &[u8]) -> Result<(), LsmError> {
+        self.write(key, Some(value))
+    }
+
+    pub fn delete(&mut self, key: &[u8]) -> Result<(), LsmError> {
+        self.write(key, None)
+    }
+
+    pub fn get(&self, key: &[u8]) -> Option<&[u8]> {
+        if let Some(value) = self.memtable.get(key) {
+            return value;
+        }
+        for file in &self.levels[0] {
+            if let Some(value) = file.table.get(key) {
+                return value;
+            }
+        }
+        // below level 0 at most one table per level can have the key
+        for level in &self.levels[1..] {
+            let idx = level.partition_point(|file| file.table.last_key() < key);
+            if let Some(value) = level.get(idx).and_then(|file| file.table.get(key)) {
+                return value;
+            }
+        }
+        None
+    }
+
+    // Keys and values in the range, in key order, merged from the memtable and every level
+    pub fn range<'k, R: RangeBounds<&'k [u8]>>(&self, range: R) -> Range<'_> {
+        let start = range.start_bound().map(|start| start.to_vec());
+        let end = range.end_bound().map(|end| end.to_vec());
+
+        let mut sources: Vec<Source> = vec![Box::new(
+            self.memtable.range(borrowed(&start), borrowed(&end)),
+        )];
+        for file in &self.levels[0] {
+            sources.push(Box::new(file.table.range(borrowed(&start), borrowed(&end))));
+        }
+        // the tables of a level don't overlap, so one after another they're one sorted source
+        for level in &self.levels[1..] {
+            let first = level.partition_point(|file| match borrowed(&start) {
+                Bound::Included(start) => file.table.last_key() < start,
+                Bound::Excluded(start) => file.table.last_key() <= start,
+                Bound::Unbounded => false,
+            });
+            let (start, end) = (start.clone(), end.clone());
+            sources.push(Box::new(level[first..].iter().flat_map(move |file| {
+                file.table.range(borrowed(&start), borrowed(&end))
+            })));
+        }
+        Range {
+            merged: MergeIter::new(sources),
+        }
+    }
+
+    // Writes the memtable out as a level 0 table and empties the log
+    // Happens by itself once the memtable is big enough, this is for doing it sooner.
+    pub fn flush(&mut self) -> Result<(), LsmError> {
+        if self.memtable.is_empty() {
+            return Ok(());
+        }
+        // with no tables at all there's nothing for a tombstone to hide
+        let keep_tombstones = self.levels.iter().any(|level| !level.is_empty());
+        let written = write_tables(
+            &self.dir,
+            &self.options,
+            &mut self.next_id,
+            self.memtable
+                .iter()
+                .filter(|entry| keep_tombstones || entry.1.is_some()),
+            self.memtable.len(),
+            u64::MAX,
+        )?;
+        self.levels[0].splice(0..0, written);
+        self.write_manifest()?;
+        self.wal.reset()?;
+        self.memtable.clear();
+
+        while let Some(level) = self.next_compaction() {
+            self.compact(level)?;
+        }
+        Ok(())
+    }
+
+    // How many tables each level has, level 0 first
+    pub fn level_tables(&self) -> Vec<usize> {
+        self.levels.iter().map(Vec::len).collect()
+    }
+
+    fn write(&mut self, key: &[u8], value: Option<&[u8]>) -> Result<(), LsmError> {
+        self.wal.append(key, value)?;
+        self.memtable.insert(key, value);
+        if self.memtable.size() >= self.options.memtable_size {
+            self.flush()?;
+        }
+        Ok(())
+    }
+
+    fn level_limit(&self, level: usize) -> u64 {
+        let growth = self.options.level_ratio.saturating_pow(level as u32 - 1);
+        self.options.level_size.saturating_mul(growth)
+    }
+
+    // The level that's over its limit, level 0 first, None when they're all fine
+    // The last level has no limit, there's nowhere further down for it to go.
+    fn next_compaction(&self) -> Option<usize> {
+        if self.levels[0].len() >= self.options.l0_tables {
+            return Some(0);
+        }
+        (1..MAX_LEVELS - 1).find(|&level| {
+            let bytes: u64 = self.levels[level]
+                .iter()
+                .map(|file| file.table.size())
+                .sum();
+            bytes > self.level_limit(level)
+        })
+    }
+
+    // Merges tables from level into the ones they overlap in the level below
+    // All of level 0 goes at once, its tables overlap each other. From any other level it's one
+    // table, the one after whichever went last time.
+    //
+    //   level 1     [a..f] [g..m] [n..z]       g..m is next
+    //   level 2   [a..d] [e..h] [i..k] [l..p]  e..h, i..k and l..p overlap it
+    //
+    //   level 1     [a..f]        [n..z]
+    //   level 2   [a..d] [e..i] [j..p]         g..m merged with them, split at table_size
+    fn compact(&mut self, level: usize) -> Result<(), LsmError> {
+        let upper: Vec<&TableFile> = if level == 0 {
+            self.levels[0].iter().collect()
+        } else {
+            let tables = &self.levels[level];
+            let after = &self.compact_after[level];
+            let next = tables.partition_point(|file| file.table.first_key() <= after.as_slice());
+            vec![tables.get(next).unwrap_or(&tables[0])]
+        };
+        let low = upper.iter().map(|file| file.table.first_key()).min();
+        let high = upper.iter().map(|file| file.table.last_key()).max();
+        let (Some(low), Some(high)) = (low, high) else {
+            return Ok(());
+        };
+        let lower: Vec<&TableFile> = self.levels[level + 1]
+            .iter()
+            .filter(|file| file.table.overlaps(low, high))
+            .collect();
+
+        // newest first: the upper tables, then the lower level as one source
+        let mut sources: Vec<Source> = upper
+            .iter()
+            .map(|file| Box::new(file.table.iter()) as Source)
+            .collect();
+        sources.push(Box::new(lower.iter().flat_map(|file| file.table.iter())));
+        // tombstones have to go down with everything else until there's nothing below to hide
+        let keep_tombstones = self.levels[level + 2..]
+            .iter()
+            .any(|level| !level.is_empty());
+
+        let inputs: Vec<&TableFile> = upper.iter().chain(&lower).copied().collect();
+        let input_len: u64 = inputs.iter().map(|file| file.table.len()).sum();
+        let input_size: u64 = inputs.iter().map(|file| file.table.size()).sum();
+        let expected_len = (input_len as u128 * self.options.table_size as u128
+            / input_size.max(1) as u128)
+            .min(input_len as u128) as usize
+            + 1;
+        let consumed: HashSet<u64> = inputs.iter().map(|file| file.id).collect();
+        self.compact_after[level] = high.to_vec();
+
+        let written = write_tables(
+            &self.dir,
+            &self.options,
+            &mut self.next_id,
+            MergeIter::new(sources).filter(|entry| keep_tombstones || entry.1.is_some()),
+            expected_len,
+            self.options.table_size,
+        )?;
+
+        let mut removed = Vec::new();
+        for idx in [level, level + 1] {
+            let (gone, kept) = mem::take(&mut self.levels[idx])
+                .into_iter()
+                .partition(|file| consumed.contains(&file.id));
+            self.levels[idx] = kept;
+            removed.extend::<Vec<TableFile>>(gone);
+        }
+        let below = &mut self.levels[level + 1];
+        below.extend(written);
+        below.sort_by(|a, b| a.table.first_key().cmp(b.table.first_key()));
+        self.write_manifest()?;
+
+        // the manifest no longer has them, if deleting fails they're orphans open cleans up
+        for file in removed {
+            let path = file.table.path().to_path_buf();
+            drop(file);
+            let _ = fs::remove_file(bloom_path(&path));
+            let _ = fs::remove_file(path);
+        }
+        Ok(())
+    }
+
+    fn write_manifest(&self) -> Result<(), LsmError> {
+        let mut text = String::new();
+        for (level, files) in self.levels.iter().enumerate() {
+            for file in files {
+                text.push_str(&format!("{level} {}\n", file.id));
+            }
+        }
+        let temp = self.dir.join(format!("{MANIFEST}.tmp"));
+        let mut file = File::create(&temp)?;
+        file.write_all(text.as_bytes())?;
+        file.sync_all()?;
+        fs::rename(temp, self.dir.join(MANIFEST))?;
+        Ok(())
+    }
+}
+
+// What a Db::range found, tombstones left out
+pub struct Range<'a> {
+    merged: MergeIter<'a>,
+}
+
+impl<'a> Iterator for Range<'a> {
+    type Item = (&'a [u8], &'a [u8]);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.merged.find_map(|(key, value)| Some((key, value?)))
+    }
+}
+
+fn table_path(dir: &Path, id: u64) -> PathBuf {
+    dir.join(format!("{id:06}.sst"))
+}
+
+fn borrowed(bound: &Bound<Vec<u8>>) -> Bound<&[u8]> {
+    bound.as_ref().map(Vec::as_slice)
+}
+
+// (level, id) per table, nothing if there's no manifest yet
+fn read_manifest(path: &Path) -> Result<Vec<(usize, u64)>, LsmError> {
+    let text = match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    };
+    text.lines()
+        .enumerate()
+        .map(|(n, line)| {
+            let parsed = line
+                .split_once(' ')
+                .and_then(|(level, id)| Some((level.parse().ok()?, id.parse().ok()?)));
+            match parsed {
+                Some((level, id)) if level < MAX_LEVELS => Ok((level, id)),
+                _ => Err(LsmError::Corrupt(format!(
+                    "{} line {}: {line:?}",
+                    path.display(),
+                    n + 1
+                ))),
+            }
+        })
+        .collect()
+}
+
+// Deletes tables (and their bloom filters) the manifest doesn't have
+// They're what a crash part way through a flush or compaction leaves behind.
+fn remove_orphans(dir: &Path, live: &HashSet<u64>) -> Result<(), LsmError> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        let is_table = matches!(
+            path.extension().and_then(|ext| ext.to_str()),
+            Some("sst" | "bloom")
+        );
+        let id = path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .and_then(|stem| stem.parse::<u64>().ok());
+        if let (true, Some(id)) = (is_table, id) {
+            if !live.contains(&id) {
+                fs::remove_file(&path)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+// Writes entries out as tables of about table_size bytes each, none if there are no entries
+fn write_tables<'a>(
+    dir: &Path,
+    options: &Options,
+    next_id: &mut u64,
+    entries: impl Iterator<Item = Entry<'a>>,
+    expected_len: usize,
+    table_size: u64,
+) -> Result<Vec<TableFile>, LsmError> {
+    let finish = |id: u64, builder: TableBuilder| -> Result<TableFile, LsmError> {
+        builder.finish()?;
+        let table = Table::open(table_path(dir, id), options.bloom_fp_rate)?;
+        Ok(TableFile { id, table })
+    };
+
+    let mut written = Vec::new();
+    let mut current: Option<(u64, TableBuilder)> = None;
+    for (key, value) in entries {
+        let (id, mut builder) = match current.take() {
+            Some(current) => current,
+            None => {
+                let id = *next_id;
+                *next_id += 1;
+                let path = table_path(dir, id);
+                (
+                    id,
+                    TableBuilder::create(path, expected_len, options.bloom_fp_rate)?,
+                )
+            }
+        };
+        builder.push(key, value)?;
+        if builder.size() >= table_size {
+            written.push(finish(id, builder)?);
+        } else {
+            current = Some((id, builder));
+        }
+    }
+    if let Some((id, builder)) = current {
+        written.push(finish(id, builder)?);
+    }
+    Ok(written)
+}
+
+#[cfg(test)]
+mod testing {
+    use std::collections::BTreeMap;
+
+    use super::*;
+
+    fn small_options() -> Options {
+        Options {
+            memtable_size: 2 << 10,
+            l0_tables: 2,
+            level_size: 8 << 10,
+            level_ratio: 2,
+            table_size: 2 << 10,
+            ..Options::default()
+        }
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn matches_a_btreemap_through_compactions_and_reopens() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut db = Db::open(dir.path(), small_options()).unwrap();
+        let mut model: BTreeMap<Vec<u8>, Vec<u8>> = BTreeMap::new();
+
+        let mut x: u64 = 1;
+        for step in 0..30_000 {
+            x = x.wrapping_mul(6364136223846793005).wrapping_add(1);
+            let key = format!("key{:05}", (x >> 33) % 3000).into_bytes();
+            if (x >> 20).is_multiple_of(4) {
+                db.delete(&key).unwrap();
+                model.remove(&key);
+            } else {
+                let value = format!("{step}-{}", "v".repeat(((x >> 40) % 20) as usize));
+                db.put(&key, value.as_bytes()).unwrap();
+                model.insert(key, value.into_bytes());
+            }
+
+            if step % 7_000 == 6_999 {
+                drop(db);
+                db = Db::open(dir.path(), small_options()).unwrap();
+            }
+        }
+
+        let tables = db.level_tables();
+        assert!(tables[2..].iter().any(|&n| n > 0), "{tables:?}");
+        for i in 0..3000 {
+            let key = format!("key{i:05}").into_bytes();
+            assert_eq!(db.get(&key), model.get(&key).map(Vec::as_slice));
+        }
+        assert!(db.range(..).eq(model.iter().map(|(k, v)| (&k[..], &v[..]))));
+
+        let (low, high) = (&b"key00500"[..], &b"key01500"[..]);
+        let found: Vec<_> = db
+            .range((Bound::Excluded(low), Bound::Included(high)))
+            .collect();
+        let expected: Vec<_> = model
+            .range::<[u8], _>((Bound::Excluded(low), Bound::Included(high)))
+            .map(|(k, v)| (&k[..], &v[..]))
+            .collect();
+        assert_eq!(found, expected);
+
+        // only the manifest's tables are left on disk
+        let files = fs::read_dir(dir.path()).unwrap().count();
+        assert_eq!(files, 2 + 2 * tables.iter().sum::<usize>());
+    }
+
+    #[test]
+    fn recovers_writes_from_the_log() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut db = Db::open(dir.path(), Options::default()).unwrap();
+        db.put(b"a", b"1").unwrap();
+        db.put(b"b", b"2").unwrap();
+        db.flush().unwrap();
+        db.delete(b"a").unwrap();
+        db.put(b"c", b"3").unwrap();
+        drop(db);
+
+        let db = Db::open(dir.path(), Options::default()).unwrap();
+        assert_eq!(db.level_tables()[0], 1);
+        assert_eq!(db.get(b"a"), None);
+        assert_eq!(db.get(b"b"), Some(&b"2"[..]));
+        assert_eq!(db.get(b"c"), Some(&b"3"[..]));
+        let all: Vec<_> = db.range(..).collect();
+        assert_eq!(all, [(&b"b"[..], &b"2"[..]), (&b"c"[..], &b"3"[..])]);
+
+        assert!(Db::open(
+            dir.path(),
+            Options {
+                level_ratio: 1,
+                ..Options::default()
+            }
+        )
+        .is_err());
+    }
+}
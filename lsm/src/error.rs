@@ -0,0 +1,43 @@
+use std::{error, fmt, io};
+
+use bloom_filter::BloomError;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LsmError {
+    // Options that can't work, or a key or value too big to store
+    InvalidParams(String),
+    // A table, log or manifest that doesn't parse
+    Corrupt(String),
+    // Reading or writing a file failed
+    Io(String),
+}
+
+impl fmt::Display for LsmError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LsmError::InvalidParams(msg) => write!(f, "invalid parameters: {msg}"),
+            LsmError::Corrupt(msg) => write!(f, "corrupt database: {msg}"),
+            LsmError::Io(msg) => write!(f, "database i/o failed: {msg}"),
+        }
+    }
+}
+
+impl error::Error for LsmError {}
+
+impl From<io::Error> for LsmError {
+    fn from(e: io::Error) -> Self {
+        LsmError::Io(e.to_string())
+    }
+}
+
+// Bloom sidecars are rebuilt from the table if they're bad, so the only errors that get this far
+// are sizing ones
+impl From<BloomError> for LsmError {
+    fn from(e: BloomError) -> Self {
+        match e {
+            BloomError::Io(msg) => LsmError::Io(msg),
+            BloomError::Corrupt(msg) => LsmError::Corrupt(msg),
+            e => LsmError::InvalidParams(e.to_string()),
+        }
+    }
+}
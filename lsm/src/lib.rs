@@ -0,0 +1,9 @@
+mod db;
+mod error;
+mod memtable;
+mod merge;
+mod sstable;
+mod wal;
+
+pub use db::{Db, Options, Range};
+pub use error::LsmError;
@@ -0,0 +1,70 @@
+use std::collections::BTreeMap;
+use std::ops::Bound;
+
+use crate::merge::Entry;
+
+// The newest writes, in memory and in key order, until there are enough to flush to a table
+// A delete is kept as a tombstone (None) rather than removing the key: older tables may still
+// have a value for it, and the tombstone is what hides that value until compaction drops both.
+#[derive(Debug, Default)]
+pub struct Memtable {
+    entries: BTreeMap<Vec<u8>, Option<Vec<u8>>>,
+    // roughly how many bytes the entries take, keys plus values, to decide when to flush
+    size: usize,
+}
+
+impl Memtable {
+    pub fn new() -> Self {
+        Memtable::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    pub fn insert(&mut self, key: &[u8], value: Option<&[u8]>) {
+        let added = key.len() + value.map_or(0, <[u8]>::len);
+        match self.entries.get_mut(key) {
+            Some(old) => {
+                self.size -= key.len() + old.as_ref().map_or(0, Vec::len);
+                *old = value.map(<[u8]>::to_vec);
+            }
+            None => {
+                self.entries.insert(key.to_vec(), value.map(<[u8]>::to_vec));
+            }
+        }
+        self.size += added;
+    }
+
+    // Some(None) for a tombstone, None if the key isn't here at all (so look in the tables)
+    pub fn get(&self, key: &[u8]) -> Option<Option<&[u8]>> {
+        self.entries.get(key).map(Option::as_deref)
+    }
+
+    pub fn range<'a>(
+        &'a self,
+        start: Bound<&[u8]>,
+        end: Bound<&[u8]>,
+    ) -> impl Iterator<Item = Entry<'a>> + 'a {
+        self.entries
+            .range::<[u8], _>((start, end))
+            .map(|(key, value)| (key.as_slice(), value.as_deref()))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = Entry<'_>> {
+        self.range(Bound::Unbounded, Bound::Unbounded)
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.size = 0;
+    }
+}
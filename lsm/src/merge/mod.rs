@@ -0,0 +1,98 @@
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+// A key and its value, or None for a tombstone, borrowed from a memtable or a mapped table
+pub type Entry<'a> = (&'a [u8], Option<&'a [u8]>);
+
+pub type Source<'a> = Box<dyn Iterator<Item = Entry<'a>> + 'a>;
+
+// Merges sorted sources into one sorted stream with one entry per key, the newest
+// Sources are given newest first (memtable, then L0 tables newest to oldest, then each level in
+// turn), so when several have the same key the one with the lowest index wins and the rest are
+// skipped. A heap holds each source's next key:
+//
+//   memtable  b d        heap: (a, 1) (b, 0) (d, 2)
+//   table 1   a b        pop (a, 1)             -> a from table 1
+//   table 2   d          pop (b, 0), skip (b, 1) -> b from the memtable
+//                        pop (d, 2)             -> d from table 2
+//
+// Tombstones come out like any other entry, reads skip them and compaction decides whether they
+// can go.
+pub struct MergeIter<'a> {
+    sources: Vec<Source<'a>>,
+    // the entry each source has ready, taken when its key is popped
+    heads: Vec<Option<Entry<'a>>>,
+    heap: BinaryHeap<Reverse<(&'a [u8], usize)>>,
+}
+
+impl<'a> MergeIter<'a> {
+    pub fn new(mut sources: Vec<Source<'a>>) -> Self {
+        let mut heads = Vec::with_capacity(sources.len());
+        let mut heap = BinaryHeap::with_capacity(sources.len());
+        for (idx, source) in sources.iter_mut().enumerate() {
+            let head = source.next();
+            if let Some((key, _)) = head {
+                heap.push(Reverse((key, idx)));
+            }
+            heads.push(head);
+        }
+        MergeIter {
+            sources,
+            heads,
+            heap,
+        }
+    }
+
+    // Takes source idx's ready entry and queues up its next one
+    fn advance(&mut self, idx: usize) -> Entry<'a> {
+        let next = self.sources[idx].next();
+        if let Some((key, _)) = next {
+            self.heap.push(Reverse((key, idx)));
+        }
+        std::mem::replace(&mut self.heads[idx], next).expect("a queued source has an entry ready")
+    }
+}
+
+impl<'a> Iterator for MergeIter<'a> {
+    type Item = Entry<'a>;
+
+    fn next(&mut self) -> Option<Entry<'a>> {
+        let Reverse((_, idx)) = self.heap.pop()?;
+        let entry = self.advance(idx);
+        // older versions of the same key, sources are sorted so they're all at the top now
+        while let Some(Reverse((key, older))) = self.heap.peek().copied() {
+            if key != entry.0 {
+                break;
+            }
+            self.heap.pop();
+            self.advance(older);
+        }
+        Some(entry)
+    }
+}
+
+#[cfg(test)]
+mod testing {
+    use super::*;
+
+    #[test]
+    fn newest_source_wins() {
+        let memtable: Vec<Entry> = vec![(b"b", Some(b"new")), (b"d", None)];
+        let table_1: Vec<Entry> = vec![(b"a", Some(b"1")), (b"b", Some(b"old")), (b"e", None)];
+        let table_2: Vec<Entry> = vec![(b"b", Some(b"older")), (b"d", Some(b"2"))];
+        let sources: Vec<Source> = vec![
+            Box::new(memtable.into_iter()),
+            Box::new(table_1.into_iter()),
+            Box::new(std::iter::empty()),
+            Box::new(table_2.into_iter()),
+        ];
+        let merged: Vec<Entry> = MergeIter::new(sources).collect();
+        let expected: Vec<Entry> = vec![
+            (b"a", Some(b"1")),
+            (b"b", Some(b"new")),
+            (b"d", None),
+            (b"e", None),
+        ];
+        assert_eq!(merged, expected);
+    }
+}
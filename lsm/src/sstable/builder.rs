@@ -0,0 +1,106 @@
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use binio::WriteBe;
+use bloom_filter::BloomFilter;
+
+use super::{bloom_path, BLOCK_SIZE, DELETE, MAGIC, PUT};
+use crate::wal::len_u32;
+use crate::LsmError;
+
+// Writes a table from entries in strictly increasing key order
+// Entries are packed into blocks of about BLOCK_SIZE bytes, and the first key of every block is
+// remembered for the index that goes after them. Every key also goes in a bloom filter, written
+// next to the table when it's finished.
+pub struct TableBuilder {
+    path: PathBuf,
+    file: BufWriter<File>,
+    bloom: BloomFilter,
+    // (first key, offset) per block
+    index: Vec<(Vec<u8>, u64)>,
+    last_key: Option<Vec<u8>>,
+    // bytes written so far, the offset the next entry goes at
+    offset: u64,
+    block_start: u64,
+    len: u64,
+}
+
+impl TableBuilder {
+    // expected_len sizes the bloom filter, going over it only raises the false positive rate
+    pub fn create(
+        path: impl AsRef<Path>,
+        expected_len: usize,
+        bloom_fp_rate: f64,
+    ) -> Result<Self, LsmError> {
+        let path = path.as_ref().to_path_buf();
+        Ok(TableBuilder {
+            file: BufWriter::new(File::create(&path)?),
+            path,
+            bloom: BloomFilter::new(bloom_fp_rate, expected_len.max(1))?,
+            index: Vec::new(),
+            last_key: None,
+            offset: 0,
+            block_start: 0,
+            len: 0,
+        })
+    }
+
+    // Bytes of entries written so far, for splitting compaction output into tables
+    pub fn size(&self) -> u64 {
+        self.offset
+    }
+
+    pub fn push(&mut self, key: &[u8], value: Option<&[u8]>) -> Result<(), LsmError> {
+        if self.last_key.as_deref().is_some_and(|last| last >= key) {
+            return Err(LsmError::InvalidParams(
+                "table keys must be strictly increasing".into(),
+            ));
+        }
+        if self.index.is_empty() || self.offset - self.block_start >= BLOCK_SIZE {
+            self.index.push((key.to_vec(), self.offset));
+            self.block_start = self.offset;
+        }
+
+        let value_bytes = value.unwrap_or_default();
+        self.file
+            .write_be_u8(if value.is_some() { PUT } else { DELETE })?;
+        self.file.write_be_u32(len_u32(key.len())?)?;
+        self.file.write_be_u32(len_u32(value_bytes.len())?)?;
+        self.file.write_all(key)?;
+        self.file.write_all(value_bytes)?;
+        self.offset += (1 + 4 + 4 + key.len() + value_bytes.len()) as u64;
+
+        self.bloom.add_item(key);
+        self.last_key = Some(key.to_vec());
+        self.len += 1;
+        Ok(())
+    }
+
+    // Writes the index, footer and bloom sidecar and syncs them
+    // A table has to have at least one entry, there's nothing to map otherwise.
+    pub fn finish(mut self) -> Result<(), LsmError> {
+        let Some(last_key) = self.last_key.take() else {
+            return Err(LsmError::InvalidParams("a table can't be empty".into()));
+        };
+
+        let index_offset = self.offset;
+        for (key, offset) in &self.index {
+            self.file.write_be_u32(len_u32(key.len())?)?;
+            self.file.write_all(key)?;
+            self.file.write_be_u64(*offset)?;
+        }
+        self.file.write_be_u32(len_u32(last_key.len())?)?;
+        self.file.write_all(&last_key)?;
+
+        self.file.write_be_u64(index_offset)?;
+        self.file.write_be_u64(self.index.len() as u64)?;
+        self.file.write_be_u64(self.len)?;
+        self.file.write_all(MAGIC)?;
+        self.file.flush()?;
+        self.file.get_ref().sync_all()?;
+
+        fs::write(bloom_path(&self.path), self.bloom.to_bytes())?;
+        Ok(())
+    }
+}
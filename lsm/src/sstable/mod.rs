@@ -0,0 +1,345 @@
+mod builder;
+
+pub use builder::TableBuilder;
+
+use std::fs::{self, File};
+use std::iter::FusedIterator;
+use std::ops::Bound;
+use std::path::{Path, PathBuf};
+
+use binio::{be_u32, be_u64};
+use bloom_filter::BloomFilter;
+use memmap2::Mmap;
+
+use crate::merge::Entry;
+use crate::LsmError;
+
+// A sorted string table, an immutable file of entries in key order, all integers big endian
+//
+//   blocks:  entries, kind u8 | key_len u32 | value_len u32 | key | value
+//   index:   per block, key_len u32 | first key | offset u64
+//            then key_len u32 | the table's last key
+//   footer:  index_offset u64 | block_count u64 | entry_count u64 | MAGIC
+//
+// kind is PUT or DELETE (a tombstone, with an empty value). The index is small enough to read
+// whole on open, so a lookup is a binary search over it and a scan of one ~4KB block.
+//
+// Next to every table is a bloom filter of its keys (same name, .bloom), which answers "definitely
+// not in this table" for most misses without touching the table at all. That's what keeps a read
+// of a missing key from costing a block per table.
+pub const MAGIC: &[u8; 8] = b"LSMSST01";
+const BLOCK_SIZE: u64 = 4096;
+const ENTRY_HEADER_SIZE: usize = 1 + 4 + 4;
+const FOOTER_SIZE: usize = 8 + 8 + 8 + 8;
+const PUT: u8 = 1;
+const DELETE: u8 = 0;
+
+pub fn bloom_path(table_path: &Path) -> PathBuf {
+    table_path.with_extension("bloom")
+}
+
+// A table opened for reading, memory mapped like BTreeFile so entries borrow from the map
+// The footer and index are checked on open, entries aren't: a table damaged in the middle of a
+// block can make a read panic.
+pub struct Table {
+    path: PathBuf,
+    map: Mmap,
+    // (where the first key starts, its length, block offset) per block
+    index: Vec<(usize, usize, u64)>,
+    // where the blocks end and the index starts
+    data_end: usize,
+    last_key: (usize, usize),
+    len: u64,
+    bloom: BloomFilter,
+}
+
+impl Table {
+    // A missing or unreadable bloom sidecar is rebuilt from the table
+    pub fn open(path: impl AsRef<Path>, bloom_fp_rate: f64) -> Result<Self, LsmError> {
+        let path = path.as_ref().to_path_buf();
+        let file = File::open(&path)?;
+        // SAFETY: tables are written once by a builder and never changed after, only deleted
+        // (which leaves an existing map alone on unix)
+        let map = unsafe { Mmap::map(&file)? };
+        let corrupt = |msg: &str| LsmError::Corrupt(format!("{}: {msg}", path.display()));
+
+        if map.len() < FOOTER_SIZE || &map[map.len() - 8..] != MAGIC {
+            return Err(corrupt("not a table"));
+        }
+        let footer = map.len() - FOOTER_SIZE;
+        let data_end = be_u64(&map, footer) as usize;
+        let block_count = be_u64(&map, footer + 8);
+        let len = be_u64(&map, footer + 16);
+        if data_end > footer || block_count == 0 || len == 0 {
+            return Err(corrupt("bad footer"));
+        }
+
+        // index entries, then the last key, and nothing left over before the footer
+        let mut index = Vec::new();
+        let mut at = data_end;
+        let mut key_at = |with_offset: bool| -> Option<(usize, usize, u64)> {
+            let key_len = be_u32(map.get(at..footer)?, 0) as usize;
+            let key_start = at + 4;
+            let key_end = key_start.checked_add(key_len)?;
+            let end = key_end + if with_offset { 8 } else { 0 };
+            if end > footer {
+                return None;
+            }
+            let offset = if with_offset {
+                be_u64(&map, key_end)
+            } else {
+                0
+            };
+            at = end;
+            Some((key_start, key_len, offset))
+        };
+        for _ in 0..block_count {
+            let entry = key_at(true).ok_or_else(|| corrupt("index runs past the footer"))?;
+            index.push(entry);
+        }
+        let (last_start, last_len, _) =
+            key_at(false).ok_or_else(|| corrupt("index runs past the footer"))?;
+        let offsets_ok = index[0].2 == 0
+            && index.windows(2).all(|pair| pair[0].2 < pair[1].2)
+            && index
+                .last()
+                .is_some_and(|last| (last.2 as usize) < data_end);
+        if at != footer || !offsets_ok {
+            return Err(corrupt("index doesn't match the blocks"));
+        }
+
+        let mut table = Table {
+            map,
+            index,
+            data_end,
+            last_key: (last_start, last_len),
+            len,
+            bloom: BloomFilter::with_size(1, 1)?,
+            path,
+        };
+        table.bloom = match fs::read(bloom_path(&table.path))
+            .ok()
+            .and_then(|bytes| BloomFilter::from_bytes(&bytes).ok())
+        {
+            Some(bloom) => bloom,
+            None => table.rebuild_bloom(bloom_fp_rate)?,
+        };
+        Ok(table)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    // Entries, tombstones included
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    // Size of the file in bytes
+    pub fn size(&self) -> u64 {
+        self.map.len() as u64
+    }
+
+    pub fn first_key(&self) -> &[u8] {
+        self.block_key(0)
+    }
+
+    pub fn last_key(&self) -> &[u8] {
+        let (start, len) = self.last_key;
+        &self.map[start..start + len]
+    }
+
+    // Some(None) for a tombstone, None if the key isn't in this table
+    pub fn get(&self, key: &[u8]) -> Option<Option<&[u8]>> {
+        if !self.bloom.check(key) || key > self.last_key() {
+            return None;
+        }
+        let block = self.block_for(key)?;
+        let mut at = self.index[block].2 as usize;
+        let end = self.block_end(block);
+        while at < end {
+            let (entry, next) = self.entry_at(at);
+            if entry.0 >= key {
+                return (entry.0 == key).then_some(entry.1);
+            }
+            at = next;
+        }
+        None
+    }
+
+    pub fn range(&self, start: Bound<&[u8]>, end: Bound<&[u8]>) -> TableIter<'_> {
+        // from the block the start key would be in, skipping what's before it
+        let at = match start {
+            Bound::Included(key) | Bound::Excluded(key) => self
+                .block_for(key)
+                .map_or(0, |block| self.index[block].2 as usize),
+            Bound::Unbounded => 0,
+        };
+        let mut iter = TableIter {
+            table: self,
+            at,
+            end: end.map(<[u8]>::to_vec),
+        };
+        while iter.at < self.data_end {
+            let ((key, _), next) = self.entry_at(iter.at);
+            let before = match start {
+                Bound::Included(start) => key < start,
+                Bound::Excluded(start) => key <= start,
+                Bound::Unbounded => false,
+            };
+            if !before {
+                break;
+            }
+            iter.at = next;
+        }
+        iter
+    }
+
+    pub fn iter(&self) -> TableIter<'_> {
+        self.range(Bound::Unbounded, Bound::Unbounded)
+    }
+
+    // Whether any key in the table could be in [start, end]
+    pub fn overlaps(&self, start: &[u8], end: &[u8]) -> bool {
+        self.first_key() <= end && start <= self.last_key()
+    }
+
+    fn block_key(&self, block: usize) -> &[u8] {
+        let (start, len, _) = self.index[block];
+        &self.map[start..start + len]
+    }
+
+    // The last block whose first key is <= key, None if key comes before the whole table
+    fn block_for(&self, key: &[u8]) -> Option<usize> {
+        let after = self
+            .index
+            .partition_point(|&(start, len, _)| &self.map[start..start + len] <= key);
+        after.checked_sub(1)
+    }
+
+    fn block_end(&self, block: usize) -> usize {
+        self.index
+            .get(block + 1)
+            .map_or(self.data_end, |next| next.2 as usize)
+    }
+
+    // The entry starting at byte at, and where the next one starts
+    fn entry_at(&self, at: usize) -> (Entry<'_>, usize) {
+        let key_len = be_u32(&self.map, at + 1) as usize;
+        let value_len = be_u32(&self.map, at + 5) as usize;
+        let key_start = at + ENTRY_HEADER_SIZE;
+        let value_start = key_start + key_len;
+        let next = value_start + value_len;
+        let key = &self.map[key_start..value_start];
+        let value = (self.map[at] == PUT).then(|| &self.map[value_start..next]);
+        ((key, value), next)
+    }
+
+    fn rebuild_bloom(&self, bloom_fp_rate: f64) -> Result<BloomFilter, LsmError> {
+        let mut bloom = BloomFilter::new(bloom_fp_rate, self.len as usize)?;
+        for (key, _) in self.iter() {
+            bloom.add_item(key);
+        }
+        // only an optimisation for next time, the table works without it
+        let _ = fs::write(bloom_path(&self.path), bloom.to_bytes());
+        Ok(bloom)
+    }
+}
+
+// A table's entries in key order, from Table::range
+pub struct TableIter<'a> {
+    table: &'a Table,
+    at: usize,
+    end: Bound<Vec<u8>>,
+}
+
+impl<'a> Iterator for TableIter<'a> {
+    type Item = Entry<'a>;
+
+    fn next(&mut self) -> Option<Entry<'a>> {
+        if self.at >= self.table.data_end {
+            return None;
+        }
+        let (entry, next) = self.table.entry_at(self.at);
+        let in_range = match &self.end {
+            Bound::Included(end) => entry.0 <= end.as_slice(),
+            Bound::Excluded(end) => entry.0 < end.as_slice(),
+            Bound::Unbounded => true,
+        };
+        if !in_range {
+            self.at = self.table.data_end;
+            return None;
+        }
+        self.at = next;
+        Some(entry)
+    }
+}
+
+impl FusedIterator for TableIter<'_> {}
+
+#[cfg(test)]
+mod testing {
+    use super::*;
+
+    fn key(i: u32) -> Vec<u8> {
+        format!("key{i:06}").into_bytes()
+    }
+
+    #[test]
+    fn builds_and_reads_a_table() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("1.sst");
+
+        // every third key, every fifth of those a tombstone, enough for a few hundred blocks
+        let keys: Vec<u32> = (0..60_000).step_by(3).collect();
+        let mut builder = TableBuilder::create(&path, keys.len(), 0.01).unwrap();
+        for &i in &keys {
+            let value = format!("value{i}");
+            let value = (i % 5 != 0).then_some(value.as_bytes());
+            builder.push(&key(i), value).unwrap();
+        }
+        assert!(builder.push(&key(0), None).is_err());
+        builder.finish().unwrap();
+
+        let table = Table::open(&path, 0.01).unwrap();
+        assert_eq!(table.len(), keys.len() as u64);
+        assert!(table.index.len() > 100);
+        assert_eq!(table.first_key(), key(0));
+        assert_eq!(table.last_key(), key(59_997));
+
+        assert_eq!(table.get(&key(3)), Some(Some(&b"value3"[..])));
+        assert_eq!(table.get(&key(15)), Some(None));
+        assert_eq!(table.get(&key(59_997)), Some(Some(&b"value59997"[..])));
+        for missing in [1, 2, 4, 59_998, 99_999] {
+            assert_eq!(table.get(&key(missing)), None);
+        }
+        assert_eq!(table.get(b"a"), None);
+
+        let found: Vec<Entry> = table
+            .range(Bound::Excluded(&key(30)), Bound::Included(&key(45)))
+            .collect();
+        let found_keys: Vec<&[u8]> = found.iter().map(|(key, _)| *key).collect();
+        assert_eq!(found_keys, [key(33), key(36), key(39), key(42), key(45)]);
+        assert_eq!(found[4].1, None);
+        assert_eq!(table.iter().count(), keys.len());
+        assert_eq!(
+            table
+                .range(Bound::Included(b"zzz"), Bound::Unbounded)
+                .count(),
+            0
+        );
+
+        // without its bloom filter the table builds a new one
+        fs::remove_file(bloom_path(&path)).unwrap();
+        let table = Table::open(&path, 0.01).unwrap();
+        assert_eq!(table.get(&key(9)), Some(Some(&b"value9"[..])));
+        assert!(bloom_path(&path).exists());
+
+        fs::write(&path, b"not a table at all").unwrap();
+        assert!(matches!(
+            Table::open(&path, 0.01),
+            Err(LsmError::Corrupt(_))
+        ));
+    }
+}
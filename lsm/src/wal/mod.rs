@@ -0,0 +1,203 @@
+use std::fs::{File, OpenOptions};
+use std::io::{BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+use binio::{be_u32, WriteBe};
+
+use crate::LsmError;
+
+// Every record is checksummed and length prefixed, all big endian
+//   crc32 u32 | len u32 | kind u8 | key_len u32 | key | value
+// len counts everything after itself, the crc covers the same bytes. kind is PUT or DELETE, a
+// delete has no value.
+const RECORD_HEADER_SIZE: usize = 4 + 4;
+const PUT: u8 = 1;
+const DELETE: u8 = 0;
+
+// The write-ahead log, every put and delete goes here before it goes in the memtable
+// The memtable is lost if the process dies, the log isn't, so opening the database replays it to
+// get back to where it was. Once the memtable is flushed to a table the log is emptied.
+//
+// A crash part way through an append leaves a torn record at the end: short, or with a checksum
+// that doesn't match. Replay stops at the first one and cuts the file there, which only ever
+// loses the write that was in progress. (A bad record with good ones after it would mean
+// something other than a crash happened, it's treated the same way.)
+pub struct Wal {
+    file: BufWriter<File>,
+    // fsync after every append, so an acknowledged write survives power loss and not just a crash
+    sync: bool,
+}
+
+// A key and its value, None for a delete
+pub type Record = (Vec<u8>, Option<Vec<u8>>);
+
+// What replay found, oldest first
+pub type Replayed = Vec<Record>;
+
+impl Wal {
+    // Opens or creates the log, returning it with the records already in it
+    pub fn open(path: impl AsRef<Path>, sync: bool) -> Result<(Self, Replayed), LsmError> {
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(path)?;
+        let mut bytes = Vec::new();
+        file.read_to_end(&mut bytes)?;
+
+        let mut records = Vec::new();
+        let mut at = 0;
+        while let Some((record, len)) = parse_record(&bytes[at..]) {
+            records.push(record);
+            at += len;
+        }
+        if at < bytes.len() {
+            file.set_len(at as u64)?;
+        }
+        file.seek(SeekFrom::Start(at as u64))?;
+
+        let wal = Wal {
+            file: BufWriter::new(file),
+            sync,
+        };
+        Ok((wal, records))
+    }
+
+    pub fn append(&mut self, key: &[u8], value: Option<&[u8]>) -> Result<(), LsmError> {
+        let mut payload = Vec::with_capacity(1 + 4 + key.len() + value.map_or(0, <[u8]>::len));
+        payload.write_be_u8(if value.is_some() { PUT } else { DELETE })?;
+        payload.write_be_u32(len_u32(key.len())?)?;
+        payload.extend_from_slice(key);
+        payload.extend_from_slice(value.unwrap_or_default());
+
+        self.file.write_be_u32(crc32(&payload))?;
+        self.file.write_be_u32(len_u32(payload.len())?)?;
+        self.file.write_all(&payload)?;
+        self.file.flush()?;
+        if self.sync {
+            self.file.get_ref().sync_data()?;
+        }
+        Ok(())
+    }
+
+    // Empties the log, after everything in it has made it into a table
+    pub fn reset(&mut self) -> Result<(), LsmError> {
+        self.file.flush()?;
+        let file = self.file.get_mut();
+        file.set_len(0)?;
+        file.seek(SeekFrom::Start(0))?;
+        file.sync_data()?;
+        Ok(())
+    }
+}
+
+// One record and how many bytes it took, None if what's left isn't a whole good record
+fn parse_record(bytes: &[u8]) -> Option<(Record, usize)> {
+    if bytes.len() < RECORD_HEADER_SIZE {
+        return None;
+    }
+    let crc = be_u32(bytes, 0);
+    let len = be_u32(bytes, 4) as usize;
+    let payload = bytes.get(RECORD_HEADER_SIZE..RECORD_HEADER_SIZE + len)?;
+    if crc32(payload) != crc || payload.len() < 5 {
+        return None;
+    }
+    let key_len = be_u32(payload, 1) as usize;
+    let key = payload.get(5..5 + key_len)?.to_vec();
+    let value = &payload[5 + key_len..];
+    let value = match payload[0] {
+        PUT => Some(value.to_vec()),
+        DELETE if value.is_empty() => None,
+        _ => return None,
+    };
+    Some(((key, value), RECORD_HEADER_SIZE + len))
+}
+
+pub(crate) fn len_u32(len: usize) -> Result<u32, LsmError> {
+    u32::try_from(len).map_err(|_| LsmError::InvalidParams(format!("{len} bytes is too big")))
+}
+
+// CRC-32 as in zip and ethernet (reflected, polynomial 0xEDB88320), a byte at a time from a table
+const CRC_TABLE: [u32; 256] = crc_table();
+
+const fn crc_table() -> [u32; 256] {
+    let mut table = [0; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 == 1 {
+                crc >> 1 ^ 0xEDB8_8320
+            } else {
+                crc >> 1
+            };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+}
+
+fn crc32(bytes: &[u8]) -> u32 {
+    !bytes.iter().fold(!0, |crc, &byte| {
+        CRC_TABLE[((crc ^ byte as u32) & 0xFF) as usize] ^ crc >> 8
+    })
+}
+
+#[cfg(test)]
+mod testing {
+    use std::fs;
+
+    use super::*;
+
+    #[test]
+    fn checksum_matches_the_usual_one() {
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+        assert_eq!(crc32(b""), 0);
+    }
+
+    #[test]
+    fn replays_up_to_a_torn_record() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("wal.log");
+
+        let (mut wal, replayed) = Wal::open(&path, false).unwrap();
+        assert!(replayed.is_empty());
+        wal.append(b"a", Some(b"1")).unwrap();
+        wal.append(b"b", None).unwrap();
+        wal.append(b"", Some(b"")).unwrap();
+        wal.append(b"c", Some(b"3")).unwrap();
+        drop(wal);
+
+        // the last record loses its final byte, as if the process died writing it
+        let len = fs::metadata(&path).unwrap().len();
+        File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_len(len - 1)
+            .unwrap();
+
+        let (mut wal, replayed) = Wal::open(&path, false).unwrap();
+        let expected: Replayed = vec![
+            (b"a".to_vec(), Some(b"1".to_vec())),
+            (b"b".to_vec(), None),
+            (b"".to_vec(), Some(b"".to_vec())),
+        ];
+        assert_eq!(replayed, expected);
+
+        // appends go after the good records, not after the torn one
+        wal.append(b"d", Some(b"4")).unwrap();
+        drop(wal);
+        let (mut wal, replayed) = Wal::open(&path, false).unwrap();
+        assert_eq!(replayed.len(), 4);
+        assert_eq!(replayed[3], (b"d".to_vec(), Some(b"4".to_vec())));
+
+        wal.reset().unwrap();
+        drop(wal);
+        assert!(Wal::open(&path, false).unwrap().1.is_empty());
+    }
+}
@@ -0,0 +1,183 @@
+// Chromosome names and the one byte codes an index keeps in their place
+//
+// A human assembly's 25 have fixed codes: 1 to 22, then X 23, Y 24 and MT 25. Any other contig a
+// map has (an unplaced scaffold, an alt haplotype, some other species' chromosome) gets the next
+// free code the first time it's seen, up to 254, since 255 is NO_CHROM. The names are written into
+// the index's metadata, so what goes in comes back out:
+//
+//   chrom   1    1
+//   ...
+//   chrom   25   MT
+//   chrom   26   GL000194.1
+//
+// An index from before there was a dictionary has no metadata, and only the fixed 25.
+//
+// Names are kept Ensembl style. UCSC's "chr1" and "chrM" and RefSeq's "NC_000001.11" are names for
+// the fixed chromosomes, so they're "1" and "MT" here; styled puts the chr back for output. Any
+// other name is kept as it's spelled.
+use std::{borrow::Cow, collections::HashMap};
+
+use report::{Error, Result};
+
+const FIXED: [&str; 25] = [
+    "1", "2", "3", "4", "5", "6", "7", "8", "9", "10", "11", "12", "13", "14", "15", "16", "17",
+    "18", "19", "20", "21", "22", "X", "Y", "MT",
+];
+const MAX_CODE: u8 = u8::MAX - 1;
+
+// names[i] has code i + 1
+#[derive(Debug, Clone)]
+pub struct Chroms {
+    names: Vec<String>,
+    codes: HashMap<String, u8>,
+}
+
+impl Chroms {
+    // Just the fixed chromosomes
+    pub fn new() -> Self {
+        let mut chroms = Chroms {
+            names: Vec::new(),
+            codes: HashMap::new(),
+        };
+        for name in FIXED {
+            chroms.add(name.into());
+        }
+        chroms
+    }
+
+    pub fn code(&self, name: &str) -> Option<u8> {
+        self.codes.get(canonical(name).as_ref()).copied()
+    }
+
+    // A name's code, given one if it doesn't have one yet
+    pub fn code_or_add(&mut self, name: &str) -> Result<u8> {
+        if let Some(code) = self.code(name) {
+            return Ok(code);
+        }
+        if self.names.len() == usize::from(MAX_CODE) {
+            return Err(Error::data(format!(
+                "no room for contig {name:?}, an index holds {MAX_CODE} contigs"
+            )));
+        }
+        Ok(self.add(canonical(name).into_owned()))
+    }
+
+    pub fn name(&self, code: u8) -> Result<&str> {
+        code.checked_sub(1)
+            .and_then(|i| self.names.get(usize::from(i)))
+            .map(String::as_str)
+            .ok_or_else(|| Error::data(format!("no chromosome has code {code} in this index")))
+    }
+
+    pub fn to_metadata(&self) -> Vec<u8> {
+        let mut metadata = String::new();
+        for (i, name) in self.names.iter().enumerate() {
+            metadata.push_str(&format!("chrom\t{}\t{name}\n", i + 1));
+        }
+        metadata.into_bytes()
+    }
+
+    // Empty metadata is an index from before the dictionary
+    pub fn from_metadata(metadata: &[u8]) -> Result<Self> {
+        if metadata.is_empty() {
+            return Ok(Chroms::new());
+        }
+        let metadata = std::str::from_utf8(metadata)
+            .map_err(|e| Error::data("the index's metadata isn't text").caused_by(e))?;
+        let mut chroms = Chroms {
+            names: Vec::new(),
+            codes: HashMap::new(),
+        };
+        for line in metadata.lines() {
+            let fields: Vec<&str> = line.split('\t').collect();
+            let ["chrom", code, name] = fields[..] else {
+                return Err(Error::data(format!(
+                    "unknown line {line:?} in the index's metadata"
+                )));
+            };
+            let expected = chroms.names.len() + 1;
+            if code != expected.to_string() || expected > usize::from(MAX_CODE) {
+                return Err(Error::data(format!(
+                    "expected chromosome code {expected} in the index's metadata, found {code}"
+                )));
+            }
+            chroms.add(name.into());
+        }
+        Ok(chroms)
+    }
+
+    fn add(&mut self, name: String) -> u8 {
+        let code = self.names.len() as u8 + 1;
+        self.codes.insert(name.clone(), code);
+        self.names.push(name);
+        code
+    }
+}
+
+impl Default for Chroms {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// The name a contig is kept under
+// RefSeq numbers X and Y as 23 and 24 like the codes do, and the mitochondrion is NC_012920.
+pub fn canonical(name: &str) -> Cow<'_, str> {
+    if let Some(accession) = name.strip_prefix("NC_") {
+        let number = accession.split('.').next().unwrap_or_default();
+        match number.parse::<usize>() {
+            Ok(n @ 1..=24) => return FIXED[n - 1].into(),
+            Ok(12920) => return "MT".into(),
+            _ => {}
+        }
+    }
+    match name.strip_prefix("chr") {
+        Some("M") => "MT".into(),
+        Some(fixed) if FIXED.contains(&fixed) => fixed.into(),
+        _ => name.into(),
+    }
+}
+
+// A kept name the way it's written out, "chr1" and "chrM" for the fixed ones with chr_prefix
+pub fn styled(name: &str, chr_prefix: bool) -> Cow<'_, str> {
+    match name {
+        "MT" if chr_prefix => "chrM".into(),
+        _ if chr_prefix && FIXED.contains(&name) => format!("chr{name}").into(),
+        _ => name.into(),
+    }
+}
+
+#[cfg(test)]
+mod testing {
+    use super::*;
+
+    #[test]
+    fn contig_names() {
+        assert_eq!(canonical("1"), "1");
+        assert_eq!(canonical("chrX"), "X");
+        assert_eq!(canonical("NC_000001.11"), "1");
+        assert_eq!(canonical("NC_000024.10"), "Y");
+        assert_eq!(canonical("NC_012920.1"), "MT");
+        assert_eq!(canonical("chrM"), "MT");
+        assert_eq!(canonical("NT_187361.1"), "NT_187361.1");
+        assert_eq!(canonical("chrUn_gl000220"), "chrUn_gl000220");
+        assert_eq!(styled("MT", true), "chrM");
+        assert_eq!(styled("7", true), "chr7");
+        assert_eq!(styled("GL000194.1", true), "GL000194.1");
+
+        let mut chroms = Chroms::new();
+        assert_eq!(chroms.code("chr2"), Some(2));
+        assert_eq!(chroms.code_or_add("GL000194.1").unwrap(), 26);
+        assert_eq!(chroms.code_or_add("chrY").unwrap(), 24);
+        assert!(chroms.name(0).is_err() && chroms.name(27).is_err());
+        let back = Chroms::from_metadata(&chroms.to_metadata()).unwrap();
+        assert_eq!(back.name(26).unwrap(), "GL000194.1");
+        assert_eq!(back.code("MT"), Some(25));
+
+        for contig in 27..=MAX_CODE {
+            chroms.code_or_add(&format!("contig{contig}")).unwrap();
+        }
+        assert!(chroms.code_or_add("one_too_many").is_err());
+        assert_eq!(chroms.code_or_add("contig254").unwrap(), MAX_CODE);
+    }
+}
@@ -0,0 +1,294 @@
+// The command line, as a clap subcommand so it can be a binary of its own and part of lr too
+//
+//   index map.tsv map.idx [--btree | --loci] [--assume-sorted] [--pos-width (32 | 64)] [--quiet]
+//   map input.tsv map.idx out.tsv [--region 1:1000-2000]... [--sort-by-locus]
+//       [--rsid-column N] [--output-column N] [--has-header | --no-header]
+//       [--target-build hg19ToHg38.over.chain] [--format vcf] [--compress] [--chr-prefix]
+//       [--search interpolation] [--on-missing (fail | skip | warn | emit)] [--threads N]
+//       [--quiet]
+//   rmap input.tsv loci.idx out.tsv [--compress]
+//   lookup map.idx [rsid]... [--file rsids.txt] [--chr-prefix]
+//
+// mapdbsnp parses Command on its own and `lr dbsnp` embeds it, and either calls run with it.
+// Any path but an index can be - for stdin or stdout. index and map show a progress bar on stderr
+// when it's a terminal.
+use std::{
+    io::{self, BufWriter},
+    path::{Path, PathBuf},
+};
+
+use clap::{Args, Subcommand};
+use report::Result;
+
+use crate::{
+    create_btree_map, create_locus_map, create_map, lookup_rsids, map_to_loci, map_to_rsids,
+    open_liftover, parse_column, parse_regions, parse_search, read_rsid_list, Format, MapOptions,
+    MapSummary, OnMissing, PosWidth, Progress, Search,
+};
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    #[command(
+        about = "Index an `rsid<TAB>chrom:pos` map, or a dbSNP VCF, either can be gzipped",
+        long_about = "Index an `rsid<TAB>chrom:pos` map, or a dbSNP VCF, either can be gzipped. The map is sorted by rsid on the way in, through temporary files in the system's temp directory, unless --assume-sorted says it already is."
+    )]
+    Index {
+        map: PathBuf,
+        index: PathBuf,
+        #[arg(long, help = "write a B-tree index instead of the flat sorted one")]
+        btree: bool,
+        #[arg(
+            long,
+            conflicts_with_all = ["btree", "assume_sorted"],
+            help = "write a locus index for rmap, the map is always sorted by locus for it"
+        )]
+        loci: bool,
+        #[arg(
+            long,
+            help = "skip sorting a tsv map that's already in rsid order, it's still checked as it's read (a VCF is always sorted)"
+        )]
+        assume_sorted: bool,
+        #[arg(
+            long,
+            value_name = "BITS",
+            default_value = "32",
+            help = "store positions in 32 bits, or 64 for chromosomes longer than 4Gb (a bigger index)"
+        )]
+        pos_width: PosWidth,
+        #[arg(short, long, help = QUIET)]
+        quiet: bool,
+    },
+    #[command(
+        about = "Replace the rsid in the first column of a tsv with its chrom:pos",
+        long_about = "Replace the rsid in the first column of a tsv with its chrom:pos, or another column with --rsid-column. Either kind of index works, it's detected from the file."
+    )]
+    Map {
+        input: PathBuf,
+        index: PathBuf,
+        output: PathBuf,
+        #[command(flatten)]
+        options: MapArgs,
+    },
+    #[command(
+        about = "Replace the chrom:pos in the first column of a tsv with its rsids",
+        long_about = "Replace the chrom:pos in the first column of a tsv with its rsids, `;`-separated if there are several and `.` if there are none. Needs an index made with --loci."
+    )]
+    Rmap {
+        input: PathBuf,
+        index: PathBuf,
+        output: PathBuf,
+        #[arg(long, help = "gzip the output")]
+        compress: bool,
+    },
+    #[command(
+        about = "Print `rsid<TAB>chrom:pos` for each rsid, with . for ones that aren't in the map",
+        long_about = "Print `rsid<TAB>chrom:pos` for each rsid, with . for ones that aren't in the map. With no rsids on the command line they're read one per line from --file, or stdin."
+    )]
+    Lookup {
+        index: PathBuf,
+        rsids: Vec<String>,
+        #[arg(
+            long,
+            value_name = "LIST",
+            conflicts_with = "rsids",
+            help = "read rsids from this file, one per line"
+        )]
+        file: Option<PathBuf>,
+        #[arg(long, help = CHR_PREFIX)]
+        chr_prefix: bool,
+    },
+}
+
+// Everything map takes besides its paths, as it comes from the command line
+#[derive(Debug, Args)]
+pub struct MapArgs {
+    #[arg(
+        long = "region",
+        value_name = "REGION",
+        help = "only keep loci in this 1-based inclusive region, like 1:1000-2000 (repeatable)"
+    )]
+    pub regions: Vec<String>,
+    #[arg(
+        long,
+        value_name = "N",
+        default_value = "1",
+        value_parser = parse_column,
+        help = "the column the rsid is in, counting from 1"
+    )]
+    pub rsid_column: usize,
+    #[arg(
+        long,
+        value_name = "N",
+        value_parser = parse_column,
+        help = "put the locus in this column of the output instead of where the rsid was, the other columns keep their order"
+    )]
+    pub output_column: Option<usize>,
+    #[arg(
+        long,
+        help = "the first line is a header, it's written out with `locus` as the rsid column's name (without this or --no-header, a first line without an rsid is taken to be one)"
+    )]
+    pub has_header: bool,
+    #[arg(
+        long,
+        conflicts_with = "has_header",
+        help = "the first line isn't a header, even if it doesn't have an rsid"
+    )]
+    pub no_header: bool,
+    #[arg(long, help = "write output sorted by chromosome and position")]
+    pub sort_by_locus: bool,
+    #[arg(
+        long,
+        value_name = "CHAIN",
+        help = "lift loci to another build with an unzipped UCSC chain file, dropping any that don't lift (regions are in the new build)"
+    )]
+    pub target_build: Option<PathBuf>,
+    #[arg(
+        long,
+        default_value = "tsv",
+        help = "tsv, or vcf for a sites-only VCF with the rsid in ID (the rest of the line is dropped)"
+    )]
+    pub format: Format,
+    #[arg(long, help = "gzip the output")]
+    pub compress: bool,
+    #[arg(long, help = CHR_PREFIX)]
+    pub chr_prefix: bool,
+    #[arg(
+        long,
+        default_value = "binary",
+        value_parser = parse_search,
+        help = "how to search a flat index, binary or interpolation (faster on big maps)"
+    )]
+    pub search: Search,
+    #[arg(
+        long,
+        default_value = "fail",
+        help = "what to do with an rsid that isn't in the map: fail, skip it, warn and skip it, or emit it with . for its locus (tsv output only)"
+    )]
+    pub on_missing: OnMissing,
+    #[arg(
+        long,
+        value_name = "N",
+        help = "look rsids up on a pool of N threads, 0 for one per core (output stays in input order)"
+    )]
+    pub threads: Option<usize>,
+    #[arg(short, long, help = QUIET)]
+    pub quiet: bool,
+}
+
+const CHR_PREFIX: &str = "write chromosomes UCSC style, chr1 and chrM instead of 1 and MT \
+    (other contigs are written as they were indexed)";
+const QUIET: &str = "don't show a progress bar (there's none anyway when stderr isn't a terminal)";
+
+impl MapArgs {
+    // Regions parsed and the chain file read, which is where bad arguments show up
+    fn options(self) -> Result<MapOptions> {
+        Ok(MapOptions {
+            regions: parse_regions(&self.regions)?,
+            sort_by_locus: self.sort_by_locus,
+            liftover: self.target_build.as_ref().map(open_liftover).transpose()?,
+            format: self.format,
+            compress: self.compress,
+            search: self.search,
+            on_missing: self.on_missing,
+            threads: self.threads,
+            progress: Progress::new(!self.quiet),
+            rsid_column: self.rsid_column,
+            locus_column: self.output_column,
+            has_header: header_flags(self.has_header, self.no_header),
+            chr_prefix: self.chr_prefix,
+        })
+    }
+}
+
+// --has-header or --no-header, or neither to tell from the first line
+fn header_flags(has_header: bool, no_header: bool) -> Option<bool> {
+    (has_header || no_header).then_some(has_header)
+}
+
+pub fn run(command: Command) -> Result<()> {
+    match command {
+        Command::Index {
+            map,
+            index,
+            btree,
+            loci,
+            assume_sorted,
+            pos_width,
+            quiet,
+        } => {
+            log::info!(
+                "indexing {} into {} ({})",
+                map.display(),
+                index.display(),
+                match (btree, loci) {
+                    (true, _) => "btree",
+                    (_, true) => "loci",
+                    _ => "flat",
+                }
+            );
+            let progress = Progress::new(!quiet);
+            if loci {
+                create_locus_map(&map, &index, pos_width, &progress)
+            } else if btree {
+                create_btree_map(&map, &index, !assume_sorted, pos_width, &progress)
+            } else {
+                create_map(&map, &index, !assume_sorted, pos_width, &progress)
+            }
+        }
+        Command::Map {
+            input,
+            index,
+            output,
+            options,
+        } => {
+            log::info!(
+                "mapping {} with {} into {}",
+                input.display(),
+                index.display(),
+                output.display()
+            );
+            let summary = map_to_loci(&input, &index, &output, &options.options()?)?;
+            report_missing(summary);
+            Ok(())
+        }
+        Command::Rmap {
+            input,
+            index,
+            output,
+            compress,
+        } => {
+            log::info!(
+                "mapping {} back to rsids with {} into {}",
+                input.display(),
+                index.display(),
+                output.display()
+            );
+            map_to_rsids(&input, &index, &output, compress)
+        }
+        Command::Lookup {
+            index,
+            rsids,
+            file,
+            chr_prefix,
+        } => {
+            let rsids = if rsids.is_empty() {
+                read_rsid_list(&file.as_deref().unwrap_or(Path::new("-")))?
+            } else {
+                rsids
+            };
+            log::info!("looking up {} rsids in {}", rsids.len(), index.display());
+            let mut out = BufWriter::new(io::stdout().lock());
+            lookup_rsids(&index, &rsids, chr_prefix, &mut out)
+        }
+    }
+}
+
+fn report_missing(summary: MapSummary) {
+    if summary.missing > 0 {
+        log::warn!(
+            "{} of {} records had an rsid that isn't in the map",
+            summary.missing,
+            summary.records
+        );
+    }
+}
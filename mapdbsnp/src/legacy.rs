@@ -0,0 +1,73 @@
+// The mapfile the first mapdbsnp wrote, from before indexes had a header
+// A big endian record count, then the records in rsid order, 9 bytes each and big endian too:
+//
+//   count u64 | rsid u32 | chrom u8 | pos u32 | rsid u32 | chrom u8 | pos u32 | ...
+//
+// It has no magic bytes, so a file is taken to be one when it's exactly as long as its count
+// says. Otherwise it reads like a flat index with narrow positions and no metadata, so its
+// chromosomes are the fixed 25 codes (see chroms). It's only ever binary searched.
+use std::{fs::File, io, path::Path};
+
+use memmap2::Mmap;
+
+const COUNT_SIZE: usize = 8;
+const RECORD_SIZE: usize = 4 + 1 + 4;
+
+pub struct LegacyMap {
+    map: Mmap,
+    len: usize,
+}
+
+impl LegacyMap {
+    // None if the file isn't laid out like one
+    pub fn open(path: &impl AsRef<Path>) -> io::Result<Option<Self>> {
+        let file = File::open(path)?;
+        // SAFETY: the same as MapFile's, the map is read only and nothing writes an old mapfile
+        // any more
+        let map = unsafe { Mmap::map(&file)? };
+        if map.len() < COUNT_SIZE {
+            return Ok(None);
+        }
+        let len = usize::try_from(binio::be_u64(&map, 0)).ok();
+        let expected = len
+            .and_then(|len| len.checked_mul(RECORD_SIZE))
+            .and_then(|size| size.checked_add(COUNT_SIZE));
+        Ok(len
+            .filter(|_| expected == Some(map.len()))
+            .map(|len| LegacyMap { map, len }))
+    }
+
+    // What a flat index's header would say: an rsid key, a narrow locus value and no metadata
+    pub fn key_size(&self) -> usize {
+        4
+    }
+
+    pub fn value_size(&self) -> usize {
+        1 + 4
+    }
+
+    pub fn metadata(&self) -> &[u8] {
+        &[]
+    }
+
+    // The chrom + pos of the first record for a big endian rsid, like MapFile::get
+    pub fn get(&self, key: &[u8; 4]) -> Option<&[u8]> {
+        let rsid = u32::from_be_bytes(*key);
+        let rsid_at = |i: usize| binio::be_u32(&self.map, COUNT_SIZE + i * RECORD_SIZE);
+        // the first record that isn't before rsid
+        let (mut lo, mut hi) = (0, self.len);
+        while lo < hi {
+            let mid = lo + (hi - lo) / 2;
+            if rsid_at(mid) < rsid {
+                lo = mid + 1;
+            } else {
+                hi = mid;
+            }
+        }
+        if lo == self.len || rsid_at(lo) != rsid {
+            return None;
+        }
+        let at = COUNT_SIZE + lo * RECORD_SIZE + 4;
+        Some(&self.map[at..at + 1 + 4])
+    }
+}
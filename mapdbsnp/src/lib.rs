@@ -0,0 +1,1672 @@
+use std::{
+    fmt,
+    fs::File,
+    io::{BufRead, Read, Write},
+    iter,
+    path::Path,
+    str::FromStr,
+};
+
+mod chroms;
+pub mod cli;
+mod legacy;
+mod progress;
+mod sort;
+mod stream;
+mod vcf;
+
+use btree_file::{BTreeBuilder, BTreeError, BTreeFile};
+use chroms::Chroms;
+use csv::{Position, Reader, ReaderBuilder, StringRecord, Writer, WriterBuilder};
+use interval_tree::{GenomeIntervals, Region};
+use legacy::LegacyMap;
+use liftover::{ChainError, LiftOver};
+use rayon::prelude::*;
+use report::{Error, ErrorKind, Location, Result, ResultExt};
+use sort::{LocusRecord, LocusRecordCodec, MapRecord, Sorter};
+use stream::Output;
+use zerocopy_map::{MapBuilder, MapError, MapFile};
+
+pub use progress::Progress;
+pub use zerocopy_map::Search;
+
+// Both index formats store the same record, an rsid key and a chrom + pos value, big endian, and
+// the locus index for rmap turns it around, a chrom + pos key and an rsid value. How wide the pos
+// is goes by PosWidth.
+const RSID_SIZE: usize = 4;
+const WIDE_LOCUS_SIZE: usize = 1 + 8;
+// The chromosome code for an output record that has no locus, after every real one when sorted
+const NO_CHROM: u8 = u8::MAX;
+// Input records read at a time by map_to_loci, whose lookups are shared out between threads
+const CHUNK_RECORDS: usize = 16 * 1024;
+
+// Regions to keep output for, from `chrom:start-end` strings
+// Positions are 1-based and inclusive like samtools, and chromosomes can be named any way the map
+// understands them, "chr1" or "1". No regions means keep everything.
+pub fn parse_regions<S: AsRef<str>>(
+    specs: impl IntoIterator<Item = S>,
+) -> Result<GenomeIntervals<()>> {
+    let mut regions = GenomeIntervals::new();
+    for spec in specs {
+        let spec = spec.as_ref();
+        let mut region: Region = spec
+            .parse()
+            .map_err(|e| Error::usage(format!("bad region {spec:?}")).caused_by(e))?;
+        region.chrom = chroms::canonical(&region.chrom).into_owned();
+        regions.insert_region(&region, ());
+    }
+    Ok(regions)
+}
+
+// A chain file for lifting loci to another build, unzipped (UCSC serves them gzipped)
+pub fn open_liftover<P: AsRef<Path>>(chain_path: &P) -> Result<LiftOver> {
+    LiftOver::open(chain_path).map_err(|e| match e {
+        ChainError::Io(e) => Error::from(e).in_file(chain_path),
+        e => {
+            let location = Location::file(chain_path).line(e.line().unwrap_or_default());
+            Error::data(e.to_string()).at(location)
+        }
+    })
+}
+
+// What map_to_loci writes
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Format {
+    // the input line with its rsid swapped for `chrom:pos`
+    #[default]
+    Tsv,
+    // a sites-only VCF with the rsid in ID, for tools that only take VCFs (bcftools, VEP)
+    // The rest of the input line has no column to go in and is dropped. The map has no alleles,
+    // so REF is N and ALT is missing. Most tools also want it sorted, see sort_by_locus.
+    Vcf,
+}
+
+impl FromStr for Format {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "tsv" => Ok(Format::Tsv),
+            "vcf" => Ok(Format::Vcf),
+            _ => Err(Error::usage(format!(
+                "unknown format {s:?}, expected tsv or vcf"
+            ))),
+        }
+    }
+}
+
+// How many bytes an index gives a position
+// Positions used to be u32 only, which is plenty for a human genome (chromosome 1 is 249Mb) but
+// not for some plants' and amphibians'. A wide index has u64 ones, at 4 more bytes a record.
+// There's no version number to tell them apart, the value size in the file's header does it: a
+// 5 byte chrom + pos is narrow and a 9 byte one wide. Every index from before there was a choice
+// reads as a narrow one, a headerless mapfile from the first mapdbsnp included (see legacy).
+//
+//   narrow   chrom u8 | pos u32
+//   wide     chrom u8 | pos u64
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PosWidth {
+    #[default]
+    U32,
+    U64,
+}
+
+impl PosWidth {
+    // How big a chrom + pos is at this width
+    fn locus_size(self) -> usize {
+        match self {
+            PosWidth::U32 => 1 + 4,
+            PosWidth::U64 => 1 + 8,
+        }
+    }
+
+    fn from_locus_size(size: usize) -> Option<Self> {
+        [PosWidth::U32, PosWidth::U64]
+            .into_iter()
+            .find(|width| width.locus_size() == size)
+    }
+
+    // A chrom + pos as an index stores it, in the first locus_size bytes of buf
+    // None if the position's too big for the width.
+    fn write_locus(self, chrom: u8, pos: u64, buf: &mut [u8; WIDE_LOCUS_SIZE]) -> Option<&[u8]> {
+        buf[0] = chrom;
+        match self {
+            PosWidth::U32 => buf[1..5].copy_from_slice(&u32::try_from(pos).ok()?.to_be_bytes()),
+            PosWidth::U64 => buf[1..].copy_from_slice(&pos.to_be_bytes()),
+        }
+        Some(&buf[..self.locus_size()])
+    }
+
+    fn read_locus(self, locus: &[u8]) -> (u8, u64) {
+        let pos = match self {
+            PosWidth::U32 => u64::from(binio::be_u32(locus, 1)),
+            PosWidth::U64 => binio::be_u64(locus, 1),
+        };
+        (locus[0], pos)
+    }
+}
+
+impl FromStr for PosWidth {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "32" => Ok(PosWidth::U32),
+            "64" => Ok(PosWidth::U64),
+            _ => Err(Error::usage(format!(
+                "unknown position width {s:?}, expected 32 or 64"
+            ))),
+        }
+    }
+}
+
+// What an index's header says its records are, an rsid index or a locus one and the width of
+// the loci in it, None if it's neither
+enum Layout {
+    Rsids(PosWidth),
+    Loci(PosWidth),
+}
+
+fn layout(key_size: usize, value_size: usize) -> Option<Layout> {
+    match (key_size, value_size) {
+        (RSID_SIZE, locus_size) => PosWidth::from_locus_size(locus_size).map(Layout::Rsids),
+        (locus_size, RSID_SIZE) => PosWidth::from_locus_size(locus_size).map(Layout::Loci),
+        _ => None,
+    }
+}
+
+// What map_to_loci keeps and how it writes it, the default is every record as tsv in input order
+// With a liftover, loci are lifted before anything else sees them, so regions and the sort are in
+// the target build. Loci that don't lift are dropped.
+//
+// Columns count from 0. The rsid's column is taken out and the locus put in at locus_column of
+// what's left, which is where the rsid was unless it's set, and every other column goes through
+// as it was:
+//
+//   a  rs5  b      rsid_column 1                      a  1:100  b
+//                  rsid_column 1, locus_column 0      1:100  a  b
+//
+// A header line goes through the same way, with `locus` for the rsid column's name.
+#[derive(Default)]
+pub struct MapOptions {
+    // only keep loci in these, or everything if there are none
+    pub regions: GenomeIntervals<()>,
+    pub sort_by_locus: bool,
+    pub liftover: Option<LiftOver>,
+    pub format: Format,
+    // gzip the output
+    pub compress: bool,
+    // how a flat index is searched, see MapIndex::with_search
+    pub search: Search,
+    pub on_missing: OnMissing,
+    // a pool of threads to look rsids up on, Some(0) for one per core, None for only this thread
+    pub threads: Option<usize>,
+    pub progress: Progress,
+    pub rsid_column: usize,
+    pub locus_column: Option<usize>,
+    // whether the first line is a header, None to tell from whether it has an rsid
+    pub has_header: Option<bool>,
+    // write the fixed chromosomes UCSC style, chr1 and chrM
+    pub chr_prefix: bool,
+}
+
+impl MapOptions {
+    fn locus_column(&self) -> usize {
+        self.locus_column.unwrap_or(self.rsid_column)
+    }
+}
+
+// What map_to_loci does with a record whose rsid isn't in the map
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OnMissing {
+    // stop with an error at the first one
+    #[default]
+    Fail,
+    // drop it, it's still counted in the summary
+    Skip,
+    // drop it and log a warning with its rsid and line
+    Warn,
+    // keep it, with `.` for its locus
+    // Only in a tsv: a VCF line can't be written without a locus, so map_to_loci turns down
+    // Emit with VCF output rather than quietly dropping them. If there are regions it's in none
+    // of them. Sorted output puts these last.
+    Emit,
+}
+
+impl FromStr for OnMissing {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "fail" => Ok(OnMissing::Fail),
+            "skip" => Ok(OnMissing::Skip),
+            "warn" => Ok(OnMissing::Warn),
+            "emit" => Ok(OnMissing::Emit),
+            _ => Err(Error::usage(format!(
+                "unknown policy {s:?}, expected fail, skip, warn or emit"
+            ))),
+        }
+    }
+}
+
+// What a map_to_loci run did, for the caller to report
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MapSummary {
+    // records read from the input
+    pub records: u64,
+    // of those, the ones with an rsid that isn't in the map
+    pub missing: u64,
+}
+
+// `binary` or `interpolation`, for a command line
+pub fn parse_search(name: &str) -> Result<Search> {
+    match name {
+        "binary" => Ok(Search::Binary),
+        "interpolation" => Ok(Search::Interpolation),
+        _ => Err(Error::usage(format!(
+            "unknown search {name:?}, expected binary or interpolation"
+        ))),
+    }
+}
+
+// A column for a command line, counted from 1 like cut's and made 0-based
+pub fn parse_column(column: &str) -> Result<usize> {
+    match column.parse::<usize>() {
+        Ok(column) if column > 0 => Ok(column - 1),
+        _ => Err(Error::usage(format!(
+            "bad column {column:?}, columns are counted from 1"
+        ))),
+    }
+}
+
+// The source tsv can be gzipped, it's told from its first bytes, and either path can be `-` for
+// stdin or stdout
+//
+// With threads, the input is read a chunk at a time and the chunk's lookups are shared out across
+// a pool, each thread with its own slice of records, then the results are written in input
+// order. The index is memory mapped and read only, so the threads share it as it is. Reading,
+// lifting, and writing stay on this thread; on a map that's bigger than memory it's the
+// lookups' page faults that take the time.
+//
+//   read chunk ──> [ lookups | lookups | lookups ] ──> write in order ──> read next chunk
+pub fn map_to_loci<P: AsRef<Path>>(
+    src_tsv: &P,
+    mapfile_path: &P,
+    out_path: &P,
+    options: &MapOptions,
+) -> Result<MapSummary> {
+    if options.format == Format::Vcf && options.on_missing == OnMissing::Emit {
+        return Err(Error::usage(
+            "--on-missing emit only works with tsv output, a VCF line has to have a locus",
+        ));
+    }
+    let index = MapIndex::open(mapfile_path)?.with_search(options.search);
+    let pool = options
+        .threads
+        .map(|threads| rayon::ThreadPoolBuilder::new().num_threads(threads).build())
+        .transpose()
+        .map_err(|e| Error::internal("couldn't start the lookup threads").caused_by(e))?;
+    let mut output = LociOutput::new(out_path, options, index.chroms.clone())?;
+    let mut tsv_rdr = tsv_reader(stream::open_with_progress(src_tsv, &options.progress)?);
+    let mut summary = MapSummary::default();
+
+    // paths as &Path, a P needn't be Sync to be shared with the pool
+    let (src, mapfile) = (src_tsv.as_ref(), mapfile_path.as_ref());
+    let rsid_column = options.rsid_column;
+    let lookup = |record: &StringRecord| -> Result<(u32, Option<(u8, u64)>)> {
+        let at = || record_location(&src, record.position());
+        let Some(rsid) = record.get(rsid_column) else {
+            return Err(no_column(rsid_column, "rsid").at(at()));
+        };
+        let rsid = rsid_to_u32(rsid).at(at())?;
+        let Some((chrom, pos)) = index.get(rsid) else {
+            return Ok((rsid, None));
+        };
+        index.chroms.name(chrom).in_file(mapfile)?;
+        Ok((rsid, Some((chrom, pos))))
+    };
+    // records are reused from chunk to chunk, reading into one keeps its allocations
+    let mut chunk = vec![StringRecord::new(); CHUNK_RECORDS];
+    let mut len = 0;
+    if tsv_rdr
+        .read_record(&mut chunk[0])
+        .map_err(|e| csv_error(e, src_tsv))?
+    {
+        if is_header(&chunk[0], options) {
+            output.write_header(without_column(&chunk[0], rsid_column))?;
+        } else {
+            len = 1;
+        }
+    }
+    loop {
+        while len < chunk.len()
+            && tsv_rdr
+                .read_record(&mut chunk[len])
+                .map_err(|e| csv_error(e, src_tsv))?
+        {
+            len += 1;
+        }
+        if len == 0 {
+            break;
+        }
+        let records = &chunk[..len];
+        let found: Vec<_> = match &pool {
+            Some(pool) => pool.install(|| records.par_iter().map(lookup).collect()),
+            None => records.iter().map(lookup).collect(),
+        };
+
+        for (record, found) in records.iter().zip(found) {
+            let (rsid, locus) = found?;
+            map_record(record, rsid, locus, src, &mut output, &mut summary)?;
+        }
+        len = 0;
+    }
+
+    output.finish()?;
+    options.progress.finish();
+    Ok(summary)
+}
+
+// Told from the first line when has_header isn't set: a header has a name where the rsid would be
+fn is_header(first: &StringRecord, options: &MapOptions) -> bool {
+    options.has_header.unwrap_or_else(|| {
+        let header = first
+            .get(options.rsid_column)
+            .is_some_and(|field| rsid_to_u32(field).is_err());
+        if header {
+            log::info!("the first line doesn't have an rsid, it's taken to be a header");
+        }
+        header
+    })
+}
+
+// One input record and what its lookup found, on its way to the output
+fn map_record<P: AsRef<Path>>(
+    record: &StringRecord,
+    rsid: u32,
+    locus: Option<(u8, u64)>,
+    src: &Path,
+    output: &mut LociOutput<P>,
+    summary: &mut MapSummary,
+) -> Result<()> {
+    let options = output.options;
+    let rest = without_column(record, options.rsid_column);
+    summary.records += 1;
+    options.progress.record();
+    let at = || record_location(&src, record.position());
+    if options.format == Format::Tsv && options.locus_column() >= record.len() {
+        return Err(no_column(options.locus_column(), "locus").at(at()));
+    }
+
+    let Some((chrom, pos)) = locus else {
+        summary.missing += 1;
+        let at = at();
+        match output.options.on_missing {
+            OnMissing::Fail => return Err(Error::data(format!("rs{rsid} isn't in the map")).at(at)),
+            OnMissing::Skip => {}
+            OnMissing::Warn => log::warn!("{at}: rs{rsid} isn't in the map"),
+            OnMissing::Emit => output.push_missing(rest)?,
+        }
+        return Ok(());
+    };
+    output.push(chrom, pos, rsid, rest)
+}
+
+// The other way around from map_to_loci, with an index from create_locus_map
+// The first column of each line is a chrom:pos ("chr1:12345" works too), and it's replaced by the
+// rsids at that locus, `;`-separated like a VCF's ID column when there are several. Loci without
+// one get a `.`, files keyed by locus are full of variants dbSNP hasn't seen.
+pub fn map_to_rsids<P: AsRef<Path>>(
+    src_tsv: &P,
+    mapfile_path: &P,
+    out_path: &P,
+    compress: bool,
+) -> Result<()> {
+    let index = LocusIndex::open(mapfile_path)?;
+    let mut tsv_rdr = tsv_reader(stream::open(src_tsv)?);
+    let mut wtr = tsv_writer(out_path, compress)?;
+
+    for record in tsv_rdr.records() {
+        let record = record.map_err(|e| csv_error(e, src_tsv))?;
+        let at = record_location(src_tsv, record.position());
+        let mut record_iter = record.iter();
+        let (chrom, pos) = parse_locus(record_iter.next().unwrap_or_default()).at(at)?;
+
+        // a contig the map has no code for can't have an rsid in it
+        let rsids = match index.chroms.code(chrom) {
+            Some(chrom) => index.get_all(chrom, pos),
+            None => Vec::new(),
+        };
+        let mut new_record = StringRecord::new();
+        if rsids.is_empty() {
+            new_record.push_field(".");
+        } else {
+            let rsids: Vec<String> = rsids.iter().map(|rsid| format!("rs{rsid}")).collect();
+            new_record.push_field(&rsids.join(";"));
+        }
+        for field in record_iter {
+            new_record.push_field(field);
+        }
+        wtr.write_record(&new_record)
+            .map_err(|e| csv_error(e, out_path))?;
+    }
+
+    finish_tsv(wtr, out_path)
+}
+
+// `rsid<TAB>chrom:pos` for each of a few rsids, for looking them up by hand
+// The "rs" is optional on the way in and always there on the way out. An rsid the map doesn't have
+// gets a `.` for its locus, and one with several loci gets its first.
+pub fn lookup_rsids<P: AsRef<Path>, S: AsRef<str>>(
+    mapfile_path: &P,
+    rsids: impl IntoIterator<Item = S>,
+    chr_prefix: bool,
+    out: &mut impl Write,
+) -> Result<()> {
+    let index = MapIndex::open(mapfile_path)?;
+    for rsid in rsids {
+        let rsid = rsid_to_u32(rsid.as_ref())?;
+        match index.lookup(rsid).in_file(mapfile_path)? {
+            Some(Locus { chrom, pos }) => writeln!(
+                out,
+                "rs{rsid}\t{}:{pos}",
+                chroms::styled(&chrom, chr_prefix)
+            )?,
+            None => writeln!(out, "rs{rsid}\t.")?,
+        }
+    }
+    out.flush()?;
+    Ok(())
+}
+
+// The lines of a list of rsids, a file or `-` for stdin, gzipped or not, skipping blank ones
+pub fn read_rsid_list<P: AsRef<Path>>(path: &P) -> Result<Vec<String>> {
+    let mut rsids = Vec::new();
+    for line in stream::open(path)?.lines() {
+        let line = line.in_file(path)?;
+        let line = line.trim();
+        if !line.is_empty() {
+            rsids.push(line.to_string());
+        }
+    }
+    Ok(rsids)
+}
+
+// Where an rsid is, as map writes it: `1:12345`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Locus {
+    pub chrom: String,
+    // 1-based
+    pub pos: u64,
+}
+
+impl fmt::Display for Locus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.chrom, self.pos)
+    }
+}
+
+// An rsid index made by create_map or create_btree_map, for looking rsids up one at a time
+//
+//   let index = MapIndex::open(&"map.idx")?;
+//   let locus = index.lookup(123)?;    // Some(Locus { chrom: "1", pos: 12345 }) for rs123
+//
+// Either format works, they're told apart by their magic bytes, and so does a mapfile from the
+// first mapdbsnp, which has none (see legacy). Opening is cheap, they're all memory mapped and
+// only their headers are read, so lookups are page faults rather than reads.
+pub struct MapIndex {
+    file: IndexFile,
+    chroms: Chroms,
+    width: PosWidth,
+}
+
+enum IndexFile {
+    // a handful of page reads from the root down per lookup
+    BTree(BTreeFile),
+    // ~log2(n) scattered reads per lookup, but a smaller file
+    Flat(MapFile),
+    // the same as Flat, with u32 positions and the fixed chromosomes
+    Legacy(LegacyMap),
+}
+
+impl MapIndex {
+    pub fn open<P: AsRef<Path>>(path: &P) -> Result<Self> {
+        check_index_path(path)?;
+        let Some(magic) = read_magic(path)? else {
+            return Err(not_a_map(path));
+        };
+
+        let file = if &magic == btree_file::MAGIC {
+            IndexFile::BTree(BTreeFile::open(path).map_err(|e| btree_error(e, path))?)
+        } else if &magic == zerocopy_map::MAGIC {
+            IndexFile::Flat(MapFile::open(path).map_err(|e| map_error(e, path))?)
+        } else if let Some(map) = LegacyMap::open(path).in_file(path)? {
+            IndexFile::Legacy(map)
+        } else {
+            return Err(not_a_map(path));
+        };
+        let (key_size, value_size, metadata) = match &file {
+            IndexFile::BTree(tree) => (tree.key_size(), tree.value_size(), tree.metadata()),
+            IndexFile::Flat(map) => (map.key_size(), map.value_size(), map.metadata()),
+            IndexFile::Legacy(map) => (map.key_size(), map.value_size(), map.metadata()),
+        };
+        match layout(key_size, value_size) {
+            Some(Layout::Rsids(width)) => {
+                let chroms = Chroms::from_metadata(metadata).in_file(path)?;
+                Ok(MapIndex {
+                    file,
+                    chroms,
+                    width,
+                })
+            }
+            Some(Layout::Loci(_)) => {
+                Err(Error::usage("this is a locus index, it's for mapdbsnp rmap").in_file(path))
+            }
+            None => Err(not_a_map(path)),
+        }
+    }
+
+    // How a flat index finds an rsid, binary search unless this says otherwise
+    // rsids are close to evenly spread over their range, so interpolation guesses land near
+    // them and a lookup takes about log2(log2(n)) probes instead of log2(n), which on a
+    // billion-record map is 5 page touches rather than 30. It falls back to halving if the
+    // guesses go badly. A B-tree has nothing to change, its root to leaf path is already short.
+    pub fn with_search(mut self, search: Search) -> Self {
+        if let IndexFile::Flat(map) = self.file {
+            self.file = IndexFile::Flat(map.with_search(search));
+        }
+        self
+    }
+
+    // Where an rsid is, None if the map doesn't have it
+    // rsids that map to several loci get their first one from either format. It's only an error
+    // if the index has a chromosome code that isn't one.
+    pub fn lookup(&self, rsid: u32) -> Result<Option<Locus>> {
+        let Some((chrom, pos)) = self.get(rsid) else {
+            return Ok(None);
+        };
+        Ok(Some(Locus {
+            chrom: self.chroms.name(chrom)?.into(),
+            pos,
+        }))
+    }
+
+    // The chrom code and pos for an rsid, without making a name for the chromosome
+    fn get(&self, rsid: u32) -> Option<(u8, u64)> {
+        let key = rsid.to_be_bytes();
+        let value = match &self.file {
+            IndexFile::BTree(tree) => tree.get(&key),
+            IndexFile::Flat(map) => map.get(&key),
+            IndexFile::Legacy(map) => map.get(&key),
+        }?;
+        Some(self.width.read_locus(value))
+    }
+}
+
+fn not_a_map(path: &impl AsRef<Path>) -> Error {
+    Error::data("not an rsid map, make one with mapdbsnp index").in_file(path)
+}
+
+// A flat index sorted by locus instead of rsid, so the same binary search finds a locus
+// There's no B-tree flavour, a lookup wants every rsid at a locus and the tree keeps one per key.
+struct LocusIndex {
+    map: MapFile,
+    chroms: Chroms,
+    width: PosWidth,
+}
+
+impl LocusIndex {
+    fn open<P: AsRef<Path>>(path: &P) -> Result<Self> {
+        check_index_path(path)?;
+        if read_magic(path)?.as_ref() != Some(zerocopy_map::MAGIC) {
+            return Err(not_a_locus_map(path));
+        }
+        let map = MapFile::open(path).map_err(|e| map_error(e, path))?;
+        match layout(map.key_size(), map.value_size()) {
+            Some(Layout::Loci(width)) => Ok(LocusIndex {
+                chroms: Chroms::from_metadata(map.metadata()).in_file(path)?,
+                map,
+                width,
+            }),
+            Some(Layout::Rsids(_)) => {
+                Err(Error::usage("this is an rsid index, it's for mapdbsnp map").in_file(path))
+            }
+            None => Err(not_a_locus_map(path)),
+        }
+    }
+
+    // Every rsid at a locus, smallest first
+    // A position too big for the index's width can't have any.
+    fn get_all(&self, chrom: u8, pos: u64) -> Vec<u32> {
+        let mut buf = [0u8; WIDE_LOCUS_SIZE];
+        let Some(key) = self.width.write_locus(chrom, pos, &mut buf) else {
+            return Vec::new();
+        };
+        self.map
+            .get_all(key)
+            .map(|value| binio::be_u32(value, 0))
+            .collect()
+    }
+}
+
+fn not_a_locus_map(path: &impl AsRef<Path>) -> Error {
+    Error::data("not a locus map, make one with mapdbsnp index-loci").in_file(path)
+}
+
+// Indexes are memory mapped, and written with a seek back to the header, so they can't be pipes
+fn check_index_path(path: &impl AsRef<Path>) -> Result<()> {
+    if stream::is_stdio(path) {
+        return Err(Error::usage(
+            "an index has to be a file, not stdin or stdout",
+        ));
+    }
+    Ok(())
+}
+
+// The first 8 bytes of a file, None if it's shorter than that
+fn read_magic(path: &impl AsRef<Path>) -> Result<Option<[u8; 8]>> {
+    let mut magic = [0u8; 8];
+    let mut file = File::open(path).in_file(path)?;
+    Ok(file.read_exact(&mut magic).ok().map(|()| magic))
+}
+
+// Where mapped records go, straight to the output file or through an external sort by locus
+// Records outside every region are dropped, each check is a walk down one chromosome's interval
+// tree rather than a scan of all the regions. chroms starts as the index's, a liftover can add
+// the target build's contigs to it.
+struct LociOutput<'a, P> {
+    sink: Sink,
+    out_path: &'a P,
+    options: &'a MapOptions,
+    chroms: Chroms,
+    sorter: Option<Sorter<LocusRecord, LocusRecordCodec>>,
+}
+
+impl<'a, P: AsRef<Path>> LociOutput<'a, P> {
+    fn new(out_path: &'a P, options: &'a MapOptions, chroms: Chroms) -> Result<Self> {
+        let sink = match options.format {
+            Format::Tsv => Sink::Tsv(Box::new(tsv_writer(out_path, options.compress)?)),
+            Format::Vcf => {
+                let out = stream::create(out_path, options.compress)?;
+                let mut header = vcf_lite::Header::new();
+                header.push_meta("source=mapdbsnp");
+                Sink::Vcf(vcf_lite::Writer::new(out, &header).in_file(out_path)?)
+            }
+        };
+        Ok(LociOutput {
+            sink,
+            out_path,
+            options,
+            chroms,
+            sorter: options.sort_by_locus.then(sort::by_locus),
+        })
+    }
+
+    // chrom has already been checked to have a name
+    fn push<'r>(
+        &mut self,
+        chrom: u8,
+        pos: u64,
+        rsid: u32,
+        rest: impl Iterator<Item = &'r str>,
+    ) -> Result<()> {
+        let (chrom, pos) = match &self.options.liftover {
+            Some(liftover) => match lift_locus(liftover, &mut self.chroms, chrom, pos)? {
+                Some(locus) => locus,
+                None => return Ok(()),
+            },
+            None => (chrom, pos),
+        };
+        let name = self.chroms.name(chrom)?.to_string();
+        // map positions are 1-based, the tree's are 0-based
+        let regions = &self.options.regions;
+        if !regions.is_empty() && !regions.contains(&name, pos.saturating_sub(1)) {
+            return Ok(());
+        }
+        // a VCF has nowhere to put the rest of the line, the one field it keeps is the ID
+        let id = matches!(self.sink, Sink::Vcf(_)).then(|| format!("rs{rsid}"));
+        match (&mut self.sorter, id) {
+            (Some(sorter), id) => {
+                let fields = match id {
+                    Some(id) => vec![id],
+                    None => rest.map(String::from).collect(),
+                };
+                sorter
+                    .push(LocusRecord { chrom, pos, fields })
+                    .context("couldn't sort the output")
+            }
+            (None, Some(id)) => self.write(Some((&name, pos)), iter::once(id.as_str())),
+            (None, None) => self.write(Some((&name, pos)), rest),
+        }
+    }
+
+    // A record that has no locus, for OnMissing::Emit
+    fn push_missing<'r>(&mut self, rest: impl Iterator<Item = &'r str>) -> Result<()> {
+        if !self.options.regions.is_empty() {
+            return Ok(());
+        }
+        match &mut self.sorter {
+            Some(sorter) => {
+                let fields = rest.map(String::from).collect();
+                let record = LocusRecord {
+                    chrom: NO_CHROM,
+                    pos: 0,
+                    fields,
+                };
+                sorter.push(record).context("couldn't sort the output")
+            }
+            None => self.write(None, rest),
+        }
+    }
+
+    fn finish(mut self) -> Result<()> {
+        if let Some(sorter) = self.sorter.take() {
+            for record in sorter.finish().context("couldn't sort the output")? {
+                let record = record.context("couldn't sort the output")?;
+                let fields = record.fields.iter().map(String::as_str);
+                if record.chrom == NO_CHROM {
+                    self.write(None, fields)?;
+                } else {
+                    let name = self.chroms.name(record.chrom)?.to_string();
+                    self.write(Some((&name, record.pos)), fields)?;
+                }
+            }
+        }
+        match self.sink {
+            Sink::Tsv(wtr) => finish_tsv(*wtr, self.out_path),
+            Sink::Vcf(wtr) => wtr.finish().and_then(Output::finish).in_file(self.out_path),
+        }
+    }
+
+    // A tsv's header line with `locus` for the rsid's column name, a VCF has its own header
+    fn write_header<'r>(&mut self, rest: impl Iterator<Item = &'r str>) -> Result<()> {
+        match &mut self.sink {
+            Sink::Tsv(wtr) => {
+                let header = tsv_row(self.options.locus_column(), "locus", rest);
+                wtr.write_record(&header)
+                    .map_err(|e| csv_error(e, self.out_path))
+            }
+            Sink::Vcf(_) => Ok(()),
+        }
+    }
+
+    // A record without a locus gets a `.` in a tsv, a VCF can't have one
+    // map_record has checked there are enough fields to put the locus in its column.
+    fn write<'f>(
+        &mut self,
+        locus: Option<(&str, u64)>,
+        fields: impl Iterator<Item = &'f str>,
+    ) -> Result<()> {
+        let chr_prefix = self.options.chr_prefix;
+        let locus = locus.map(|(chrom, pos)| (chroms::styled(chrom, chr_prefix), pos));
+        match &mut self.sink {
+            Sink::Tsv(wtr) => {
+                let loci = match locus {
+                    Some((chrom, pos)) => format!("{}:{}", chrom, pos),
+                    None => ".".into(),
+                };
+                let new_record = tsv_row(self.options.locus_column(), &loci, fields);
+                wtr.write_record(&new_record)
+                    .map_err(|e| csv_error(e, self.out_path))
+            }
+            Sink::Vcf(wtr) => {
+                // map_to_loci doesn't let OnMissing::Emit write a VCF
+                let Some((chrom, pos)) = locus else {
+                    return Err(Error::internal("a VCF record needs a locus"));
+                };
+                let mut record = vcf_lite::Record::new(chrom, pos);
+                record.ids = fields.map(String::from).collect();
+                wtr.write_record(&record).in_file(self.out_path)
+            }
+        }
+    }
+}
+
+// The fields with the locus put in at column, or last if that's one past the end
+fn tsv_row<'f>(column: usize, locus: &str, fields: impl Iterator<Item = &'f str>) -> StringRecord {
+    let mut row = StringRecord::new();
+    for field in fields {
+        if row.len() == column {
+            row.push_field(locus);
+        }
+        row.push_field(field);
+    }
+    if row.len() == column {
+        row.push_field(locus);
+    }
+    row
+}
+
+// csv's writer carries its buffer inline, so it's the one boxed
+enum Sink {
+    Tsv(Box<Writer<Output>>),
+    Vcf(vcf_lite::Writer<Output>),
+}
+
+// A map locus in the liftover's target build, None if it doesn't lift or lands on a new contig
+// when there are no codes left to give it
+// UCSC chains name chromosomes "chr1" and "chrM", Ensembl's "1" and "MT", either kind works.
+fn lift_locus(
+    liftover: &LiftOver,
+    chroms: &mut Chroms,
+    chrom: u8,
+    pos: u64,
+) -> Result<Option<(u8, u64)>> {
+    let name = chroms.name(chrom)?;
+    let ucsc = chroms::styled(name, true);
+    let source = if liftover.has_chrom(&ucsc) {
+        &ucsc
+    } else {
+        name
+    };
+    // map positions are 1-based, chains are 0-based
+    let Some((target, lifted)) = liftover.lift(source, pos.saturating_sub(1)) else {
+        return Ok(None);
+    };
+    Ok(chroms
+        .code_or_add(target)
+        .ok()
+        .map(|chrom| (chrom, lifted + 1)))
+}
+
+// Builds a flat index, a tsv map has to be sorted by rsid unless sort is set
+// The index is a zerocopy-map file, rsids that map to several loci keep every one of them.
+// Positions are stored at width, one that doesn't fit is an error.
+pub fn create_map<P: AsRef<Path>>(
+    src_tsv: &P,
+    dst: &P,
+    sort: bool,
+    width: PosWidth,
+    progress: &Progress,
+) -> Result<()> {
+    check_index_path(dst)?;
+    let mut builder =
+        MapBuilder::create(dst, RSID_SIZE, width.locus_size()).map_err(|e| map_error(e, dst))?;
+    let mut buf = [0u8; WIDE_LOCUS_SIZE];
+
+    let chroms = for_each_map_record(src_tsv, sort, progress, |record| {
+        builder
+            .push(
+                &record.rsid.to_be_bytes(),
+                locus_value(&record, width, &mut buf)?,
+            )
+            .map_err(|e| map_error(e, dst))
+    })?;
+    builder
+        .finish_with_metadata(&chroms.to_metadata())
+        .map_err(|e| map_error(e, dst))?;
+    progress.finish();
+
+    Ok(())
+}
+
+pub fn create_btree_map<P: AsRef<Path>>(
+    src_tsv: &P,
+    dst: &P,
+    sort: bool,
+    width: PosWidth,
+    progress: &Progress,
+) -> Result<()> {
+    check_index_path(dst)?;
+    let mut builder = BTreeBuilder::create(dst, RSID_SIZE, width.locus_size())
+        .map_err(|e| btree_error(e, dst))?;
+    let mut last_rsid = None;
+    let mut buf = [0u8; WIDE_LOCUS_SIZE];
+
+    let chroms = for_each_map_record(src_tsv, sort, progress, |record| {
+        // rsids that map to several loci keep their first one, the tree holds one value per key
+        // (the flat index keeps them all, but lookups there find the first one too)
+        if last_rsid == Some(record.rsid) {
+            return Ok(());
+        }
+        last_rsid = Some(record.rsid);
+
+        builder
+            .push(
+                &record.rsid.to_be_bytes(),
+                locus_value(&record, width, &mut buf)?,
+            )
+            .map_err(|e| btree_error(e, dst))
+    })?;
+    builder
+        .finish_with_metadata(&chroms.to_metadata())
+        .map_err(|e| btree_error(e, dst))?;
+    progress.finish();
+
+    Ok(())
+}
+
+// Builds a locus index for rmap, a flat index with the keys and values swapped
+// The map can be in any order, it always goes through the sorter to get it in locus order. A
+// locus with several rsids keeps them all, smallest first, but a pair seen twice is kept once.
+pub fn create_locus_map<P: AsRef<Path>>(
+    src: &P,
+    dst: &P,
+    width: PosWidth,
+    progress: &Progress,
+) -> Result<()> {
+    check_index_path(dst)?;
+    let mut builder =
+        MapBuilder::create(dst, width.locus_size(), RSID_SIZE).map_err(|e| map_error(e, dst))?;
+    let mut buf = [0u8; WIDE_LOCUS_SIZE];
+    let mut sorter = sort::map_by_locus();
+    let chroms = MapSource::open(src, progress)?
+        .read(|record, _| sorter.push(record).context("couldn't sort the map"))?;
+    progress.stage("sorting");
+
+    let mut last = None;
+    for record in sorter.finish().context("couldn't sort the map")? {
+        let record = record.context("couldn't sort the map")?;
+        if last == Some(record) {
+            continue;
+        }
+        last = Some(record);
+        builder
+            .push(
+                locus_value(&record, width, &mut buf)?,
+                &record.rsid.to_be_bytes(),
+            )
+            .map_err(|e| map_error(e, dst))?;
+    }
+    builder
+        .finish_with_metadata(&chroms.to_metadata())
+        .map_err(|e| map_error(e, dst))?;
+    progress.finish();
+
+    Ok(())
+}
+
+// Every record of the map in rsid order, then the chromosome names their codes stand for
+// Without sort the map is checked to be in order as it's read. With it, records go through an
+// external sort first, which is stable, so rsids on several lines keep their order either way.
+// A dbSNP VCF always needs sorting.
+fn for_each_map_record<P: AsRef<Path>>(
+    src: &P,
+    sort: bool,
+    progress: &Progress,
+    mut f: impl FnMut(MapRecord) -> Result<()>,
+) -> Result<Chroms> {
+    let source = MapSource::open(src, progress)?;
+    let mut sorter = (sort || source.is_vcf).then(sort::by_rsid);
+    let mut last_rsid = 0;
+    let chroms = source.read(|record, at| match &mut sorter {
+        Some(sorter) => sorter.push(record).context("couldn't sort the map"),
+        None => {
+            if last_rsid > record.rsid {
+                return Err(unsorted(record.rsid, last_rsid).at(at));
+            }
+            last_rsid = record.rsid;
+            f(record)
+        }
+    })?;
+
+    if let Some(sorter) = sorter {
+        progress.stage("sorting");
+        for record in sorter.finish().context("couldn't sort the map")? {
+            f(record.context("couldn't sort the map")?)?;
+        }
+    }
+    Ok(chroms)
+}
+
+// A map to read, either `rsid<TAB>chrom:pos` lines or a dbSNP VCF
+// Which one it is comes from peeking at the start of the input, since stdin can only be read
+// once.
+struct MapSource<'a, P> {
+    path: &'a P,
+    input: Box<dyn BufRead>,
+    is_vcf: bool,
+    progress: &'a Progress,
+}
+
+impl<'a, P: AsRef<Path>> MapSource<'a, P> {
+    fn open(path: &'a P, progress: &'a Progress) -> Result<Self> {
+        let mut input = stream::open_with_progress(path, progress)?;
+        let is_vcf = vcf::is_vcf(&mut input).in_file(path)?;
+        Ok(MapSource {
+            path,
+            input,
+            is_vcf,
+            progress,
+        })
+    }
+
+    // Every record of the map in the order it's in, with where it came from
+    // Chromosomes get codes as they're first seen, and the names for them are what's returned.
+    fn read(self, mut f: impl FnMut(MapRecord, Location) -> Result<()>) -> Result<Chroms> {
+        let src = self.path;
+        let progress = self.progress;
+        let mut chroms = Chroms::new();
+        if self.is_vcf {
+            vcf::for_each_record(self.input, src, &mut chroms, |record, at| {
+                progress.record();
+                f(record, at)
+            })?;
+            return Ok(chroms);
+        }
+        let mut rdr = tsv_reader(self.input);
+        for r in rdr.records() {
+            let r = r.map_err(|e| csv_error(e, src))?;
+            let at = record_location(src, r.position());
+            let record = parse_map_record(&r, &mut chroms).at(at.clone())?;
+            progress.record();
+            f(record, at)?;
+        }
+        Ok(chroms)
+    }
+}
+
+// Lines look like `rs123<TAB>1:12345`, or `rs123<TAB>chr1:12345`, or with any other contig
+fn parse_map_record(r: &StringRecord, chroms: &mut Chroms) -> Result<MapRecord> {
+    let rsid = rsid_to_u32(r.get(0).unwrap_or_default())?;
+    let (chrom, pos) = parse_locus(r.get(1).unwrap_or_default())?;
+    let chrom = chroms.code_or_add(chrom)?;
+    Ok(MapRecord { rsid, chrom, pos })
+}
+
+// `1:12345` as its chromosome name and position
+fn parse_locus(locus: &str) -> Result<(&str, u64)> {
+    let Some((chrom, pos)) = locus.split_once(':') else {
+        return Err(Error::data(format!("expected chrom:pos, found {locus:?}")));
+    };
+    let pos = pos
+        .parse::<u64>()
+        .map_err(|e| Error::data(format!("bad position {pos:?}")).caused_by(e))?;
+    Ok((chrom, pos))
+}
+
+// Every field of a record but the one in column
+fn without_column(record: &StringRecord, column: usize) -> impl Iterator<Item = &str> {
+    record
+        .iter()
+        .enumerate()
+        .filter(move |&(i, _)| i != column)
+        .map(|(_, field)| field)
+}
+
+fn no_column(column: usize, what: &str) -> Error {
+    Error::data(format!(
+        "there's no column {} for the {what}, the line isn't that long",
+        column + 1
+    ))
+}
+
+fn unsorted(rsid: u32, last: u32) -> Error {
+    Error::data(format!(
+        "rs{rsid} comes after rs{last}, make sure source map is sorted (or index without --assume-sorted)"
+    ))
+}
+
+// A record's chrom + pos as an index of width stores it, written into buf
+fn locus_value<'b>(
+    record: &MapRecord,
+    width: PosWidth,
+    buf: &'b mut [u8; WIDE_LOCUS_SIZE],
+) -> Result<&'b [u8]> {
+    let MapRecord { rsid, chrom, pos } = *record;
+    width.write_locus(chrom, pos, buf).ok_or_else(|| {
+        Error::data(format!(
+            "rs{rsid} is at position {pos}, too big for 32 bits (index with --pos-width 64)"
+        ))
+    })
+}
+
+fn rsid_to_u32(rsid: &str) -> Result<u32> {
+    rsid.replace("rs", "")
+        .parse::<u32>()
+        .map_err(|e| Error::data(format!("{rsid:?} isn't an rsid")).caused_by(e))
+}
+
+fn tsv_reader(input: Box<dyn BufRead>) -> Reader<Box<dyn BufRead>> {
+    ReaderBuilder::new()
+        .delimiter(b'\t')
+        .has_headers(false)
+        .from_reader(input)
+}
+
+fn tsv_writer(path: &impl AsRef<Path>, compress: bool) -> Result<Writer<Output>> {
+    Ok(WriterBuilder::new()
+        .delimiter(b'\t')
+        .has_headers(false)
+        .from_writer(stream::create(path, compress)?))
+}
+
+fn finish_tsv(wtr: Writer<Output>, path: &impl AsRef<Path>) -> Result<()> {
+    let out = wtr
+        .into_inner()
+        .map_err(|e| Error::new(e.error().kind().into(), e.to_string()).in_file(path))?;
+    out.finish().in_file(path)
+}
+
+// csv positions count lines from 1, same as Location
+fn record_location(path: &impl AsRef<Path>, position: Option<&Position>) -> Location {
+    let location = Location::file(path);
+    match position {
+        Some(position) => location.line(position.line()).offset(position.byte()),
+        None => location,
+    }
+}
+
+fn csv_error(e: csv::Error, path: &impl AsRef<Path>) -> Error {
+    let kind = match e.kind() {
+        csv::ErrorKind::Io(e) => e.kind().into(),
+        _ => ErrorKind::Data,
+    };
+    let location = record_location(path, e.position());
+    Error::new(kind, e.to_string()).at(location)
+}
+
+fn btree_error(e: BTreeError, path: &impl AsRef<Path>) -> Error {
+    let kind = match e {
+        BTreeError::InvalidParams(_) => ErrorKind::Internal,
+        BTreeError::Unsorted(_) | BTreeError::Corrupt(_) => ErrorKind::Data,
+        BTreeError::Io(_) => ErrorKind::Io,
+    };
+    Error::new(kind, e.to_string()).in_file(path)
+}
+
+fn map_error(e: MapError, path: &impl AsRef<Path>) -> Error {
+    let kind = match e {
+        MapError::InvalidParams(_) => ErrorKind::Internal,
+        MapError::Unsorted(_) | MapError::Corrupt(_) => ErrorKind::Data,
+        MapError::Io(_) => ErrorKind::Io,
+    };
+    Error::new(kind, e.to_string()).in_file(path)
+}
+
+#[cfg(test)]
+mod testing {
+    use std::{fs, path::PathBuf};
+
+    use tempfile::TempDir;
+
+    use super::*;
+
+    // A temp dir for a test's files, deleted when it's dropped
+    // Most tests start from indexed, a small map made into the usual flat narrow index.
+    struct Fixture {
+        dir: TempDir,
+        index: PathBuf,
+    }
+
+    impl Fixture {
+        fn new() -> Self {
+            let dir = tempfile::tempdir().unwrap();
+            let index = dir.path().join("rsids.idx");
+            Fixture { dir, index }
+        }
+
+        // map written to map.tsv and indexed into rsids.idx
+        fn indexed(map: &str) -> Self {
+            let fixture = Fixture::new();
+            fixture.write("map.tsv", map);
+            create_map(
+                &fixture.path("map.tsv"),
+                &fixture.index,
+                false,
+                PosWidth::U32,
+                &Progress::default(),
+            )
+            .unwrap();
+            fixture
+        }
+
+        fn path(&self, name: &str) -> PathBuf {
+            self.dir.path().join(name)
+        }
+
+        fn write(&self, name: &str, contents: impl AsRef<[u8]>) {
+            fs::write(self.path(name), contents).unwrap();
+        }
+
+        fn read(&self, name: &str) -> String {
+            fs::read_to_string(self.path(name)).unwrap()
+        }
+
+        // in.tsv through map_to_loci into out, and what came out
+        fn map(&self, index: &Path, options: &MapOptions) -> Result<String> {
+            map_to_loci(
+                &self.path("in.tsv"),
+                &index.to_path_buf(),
+                &self.path("out"),
+                options,
+            )?;
+            Ok(self.read("out"))
+        }
+    }
+
+    #[test]
+    fn rmap_finds_every_rsid_at_a_locus() {
+        let fixture = Fixture::new();
+        // out of order, two rsids at 1:100 and one pair twice
+        fixture.write(
+            "map.tsv",
+            "rs30\t2:5\nrs7\t1:100\nrs3\t1:100\nrs9\tX:42\nrs7\t1:100\n",
+        );
+        fixture.write("in.tsv", "1:100\ta\nchrX:42\tb\n2:6\tc\nchrUn_x:1\td\n");
+        let loci = fixture.path("loci.idx");
+
+        create_locus_map(
+            &fixture.path("map.tsv"),
+            &loci,
+            PosWidth::U32,
+            &Progress::default(),
+        )
+        .unwrap();
+        map_to_rsids(&fixture.path("in.tsv"), &loci, &fixture.path("out"), false).unwrap();
+        assert_eq!(fixture.read("out"), "rs3;rs7\ta\nrs9\tb\n.\tc\n.\td\n");
+
+        // and each kind of index is turned away by the other command
+        let wrong = fixture.map(&loci, &MapOptions::default());
+        assert_eq!(wrong.unwrap_err().kind(), ErrorKind::Usage);
+        create_map(
+            &fixture.path("map.tsv"),
+            &fixture.index,
+            true,
+            PosWidth::U32,
+            &Progress::default(),
+        )
+        .unwrap();
+        let wrong = map_to_rsids(
+            &fixture.path("in.tsv"),
+            &fixture.index,
+            &fixture.path("out"),
+            false,
+        );
+        assert_eq!(wrong.unwrap_err().kind(), ErrorKind::Usage);
+    }
+
+    #[test]
+    fn vcf_output_keeps_the_rsid_in_id() {
+        let fixture = Fixture::indexed("rs1\t2:5\nrs2\t1:100\nrs3\tMT:7\n");
+        fixture.write("in.tsv", "rs3\ta\nrs1\tb\nrs2\tc\n");
+
+        let options = MapOptions {
+            sort_by_locus: true,
+            format: Format::Vcf,
+            ..MapOptions::default()
+        };
+        assert_eq!(
+            fixture.map(&fixture.index, &options).unwrap(),
+            "##fileformat=VCFv4.2\n##source=mapdbsnp\n\
+             #CHROM\tPOS\tID\tREF\tALT\tQUAL\tFILTER\tINFO\n\
+             1\t100\trs2\tN\t.\t.\t.\t.\n\
+             2\t5\trs1\tN\t.\t.\t.\t.\n\
+             MT\t7\trs3\tN\t.\t.\t.\t.\n"
+        );
+    }
+
+    #[test]
+    fn gzip_in_and_out() {
+        use flate2::{read::MultiGzDecoder, write::GzEncoder, Compression};
+        use std::io::Write;
+
+        let fixture = Fixture::new();
+        // two gzip members back to back, the way bgzip writes them
+        let mut map = Vec::new();
+        for part in ["rs1\t2:5\n", "rs2\t1:100\n"] {
+            let mut gz = GzEncoder::new(Vec::new(), Compression::default());
+            gz.write_all(part.as_bytes()).unwrap();
+            map.extend(gz.finish().unwrap());
+        }
+        fixture.write("map.tsv.gz", map);
+        fixture.write("in.tsv", "rs2\ta\nrs1\tb\n");
+
+        create_map(
+            &fixture.path("map.tsv.gz"),
+            &fixture.index,
+            false,
+            PosWidth::U32,
+            &Progress::default(),
+        )
+        .unwrap();
+        let options = MapOptions {
+            compress: true,
+            ..MapOptions::default()
+        };
+        map_to_loci(
+            &fixture.path("in.tsv"),
+            &fixture.index,
+            &fixture.path("out.gz"),
+            &options,
+        )
+        .unwrap();
+        let mut out = String::new();
+        MultiGzDecoder::new(fs::File::open(fixture.path("out.gz")).unwrap())
+            .read_to_string(&mut out)
+            .unwrap();
+        assert_eq!(out, "1:100\ta\n2:5\tb\n");
+    }
+
+    #[test]
+    fn lookups() {
+        let fixture = Fixture::indexed("rs1\t2:5\nrs2\tX:100\nrs2\t3:9\n");
+        let tree = fixture.path("tree.idx");
+        create_btree_map(
+            &fixture.path("map.tsv"),
+            &tree,
+            false,
+            PosWidth::U32,
+            &Progress::default(),
+        )
+        .unwrap();
+
+        for path in [&fixture.index, &tree] {
+            let index = MapIndex::open(path).unwrap();
+            let locus = index.lookup(2).unwrap().unwrap();
+            assert_eq!(locus.to_string(), "X:100");
+            assert_eq!(index.lookup(1).unwrap().unwrap().pos, 5);
+            assert_eq!(index.lookup(3).unwrap(), None);
+        }
+    }
+
+    // count u64 | rsid u32 | chrom u8 | pos u32 | ..., all big endian, see legacy
+    fn legacy_mapfile(records: &[(u32, u8, u32)]) -> Vec<u8> {
+        let mut bytes = (records.len() as u64).to_be_bytes().to_vec();
+        for &(rsid, chrom, pos) in records {
+            bytes.extend(rsid.to_be_bytes());
+            bytes.push(chrom);
+            bytes.extend(pos.to_be_bytes());
+        }
+        bytes
+    }
+
+    #[test]
+    fn reads_mapfiles_from_the_first_version() {
+        let fixture = Fixture::new();
+        let records = [(1, 2, 5), (2, 23, 100), (2, 3, 9), (7, 25, 16_569)];
+        fixture.write("old.idx", legacy_mapfile(&records));
+
+        let index = MapIndex::open(&fixture.path("old.idx")).unwrap();
+        assert_eq!(index.lookup(2).unwrap().unwrap().to_string(), "X:100");
+        assert_eq!(index.lookup(7).unwrap().unwrap().to_string(), "MT:16569");
+        assert_eq!(index.lookup(1).unwrap().unwrap().pos, 5);
+        assert_eq!(index.lookup(0).unwrap(), None);
+        assert_eq!(index.lookup(3).unwrap(), None);
+        assert_eq!(index.lookup(8).unwrap(), None);
+
+        // a count that doesn't match the file's size isn't one
+        let mut short = legacy_mapfile(&records);
+        short.pop();
+        fixture.write("short.idx", short);
+        let wrong = MapIndex::open(&fixture.path("short.idx"));
+        assert_eq!(wrong.err().unwrap().kind(), ErrorKind::Data);
+    }
+
+    #[test]
+    fn maps_with_mapfiles_from_the_first_version() {
+        let fixture = Fixture::new();
+        // positions use all 32 bits, they're read as narrow ones
+        let records = [(3, 1, 4_000_000_000), (5, 24, 12)];
+        fixture.write("old.idx", legacy_mapfile(&records));
+        fixture.write("in.tsv", "rs5\ta\nrs3\tb\n");
+
+        let options = MapOptions {
+            chr_prefix: true,
+            ..MapOptions::default()
+        };
+        assert_eq!(
+            fixture.map(&fixture.path("old.idx"), &options).unwrap(),
+            "chrY:12\ta\nchr1:4000000000\tb\n"
+        );
+    }
+
+    #[test]
+    fn interpolation_finds_the_same_loci() {
+        let mut map = String::new();
+        let mut x = 7_u64;
+        let mut rsid = 0;
+        for _ in 0..2000 {
+            x = x.wrapping_mul(6364136223846793005).wrapping_add(1);
+            rsid += 1 + (x >> 60) as u32;
+            map.push_str(&format!("rs{rsid}\t{}:{}\n", 1 + (x >> 59) % 22, x >> 40));
+        }
+        let fixture = Fixture::indexed(&map);
+
+        let binary = MapIndex::open(&fixture.index).unwrap();
+        let interpolation = MapIndex::open(&fixture.index)
+            .unwrap()
+            .with_search(Search::Interpolation);
+        for rsid in 0..rsid + 2 {
+            assert_eq!(
+                binary.lookup(rsid).unwrap(),
+                interpolation.lookup(rsid).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn threads_keep_input_order() {
+        let map: String = (1..=30_000)
+            .map(|rsid| format!("rs{rsid}\t{}:{}\n", rsid % 22 + 1, rsid * 7))
+            .collect();
+        let fixture = Fixture::indexed(&map);
+        // more than a chunk, out of order, and with rsids past the end of the map
+        let mut x = 3_u64;
+        let input: String = (0..2 * CHUNK_RECORDS + 100)
+            .map(|i| {
+                x = x.wrapping_mul(6364136223846793005).wrapping_add(1);
+                format!("rs{}\t{i}\n", 1 + (x >> 40) % 32_000)
+            })
+            .collect();
+        fixture.write("in.tsv", input);
+
+        let run = |threads| {
+            let options = MapOptions {
+                on_missing: OnMissing::Emit,
+                threads,
+                ..MapOptions::default()
+            };
+            let summary = map_to_loci(
+                &fixture.path("in.tsv"),
+                &fixture.index,
+                &fixture.path("out"),
+                &options,
+            );
+            (summary.unwrap(), fixture.read("out"))
+        };
+        let (summary, out) = run(None);
+        assert!(summary.missing > 0);
+        assert_eq!(run(Some(4)), (summary, out));
+    }
+
+    #[test]
+    fn rsids_can_be_in_any_column() {
+        let fixture = Fixture::indexed("rs1\t2:5\nrs2\t1:100\n");
+        fixture.write("in.tsv", "a\trs2\tb\nc\trs1\td\n");
+
+        let run = |locus_column| {
+            let options = MapOptions {
+                rsid_column: 1,
+                locus_column,
+                ..MapOptions::default()
+            };
+            fixture.map(&fixture.index, &options)
+        };
+        assert_eq!(run(None).unwrap(), "a\t1:100\tb\nc\t2:5\td\n");
+        assert_eq!(run(Some(0)).unwrap(), "1:100\ta\tb\n2:5\tc\td\n");
+        assert_eq!(run(Some(2)).unwrap(), "a\tb\t1:100\nc\td\t2:5\n");
+        assert!(run(Some(3)).is_err());
+    }
+
+    #[test]
+    fn headers_go_through_with_locus_for_rsid() {
+        let fixture = Fixture::indexed("rs1\t2:5\nrs2\t1:100\n");
+        fixture.write("in.tsv", "name\tsnp\nb\trs1\na\trs2\n");
+
+        let run = |has_header| {
+            let options = MapOptions {
+                rsid_column: 1,
+                locus_column: Some(0),
+                sort_by_locus: true,
+                has_header,
+                ..MapOptions::default()
+            };
+            fixture.map(&fixture.index, &options)
+        };
+        let out = "locus\tname\n1:100\ta\n2:5\tb\n";
+        assert_eq!(run(None).unwrap(), out);
+        assert_eq!(run(Some(true)).unwrap(), out);
+        assert!(run(Some(false)).is_err());
+    }
+
+    #[test]
+    fn missing_rsids_follow_the_policy() {
+        let fixture = Fixture::indexed("rs1\t2:5\nrs2\t1:100\n");
+        fixture.write("in.tsv", "rs9\ta\nrs1\tb\nrs8\tc\nrs2\td\n");
+
+        let run = |on_missing, sort_by_locus| {
+            let options = MapOptions {
+                on_missing,
+                sort_by_locus,
+                ..MapOptions::default()
+            };
+            let summary = map_to_loci(
+                &fixture.path("in.tsv"),
+                &fixture.index,
+                &fixture.path("out"),
+                &options,
+            );
+            (summary, fixture.read("out"))
+        };
+
+        let (summary, _) = run(OnMissing::Fail, false);
+        assert_eq!(summary.unwrap_err().to_string(), "rs9 isn't in the map");
+        let (summary, out) = run(OnMissing::Skip, false);
+        assert_eq!(
+            summary.unwrap(),
+            MapSummary {
+                records: 4,
+                missing: 2
+            }
+        );
+        assert_eq!(out, "2:5\tb\n1:100\td\n");
+        let (_, out) = run(OnMissing::Emit, false);
+        assert_eq!(out, ".\ta\n2:5\tb\n.\tc\n1:100\td\n");
+        let (_, out) = run(OnMissing::Emit, true);
+        assert_eq!(out, "1:100\td\n2:5\tb\n.\ta\n.\tc\n");
+
+        // a VCF has nowhere to emit them, which is an error up front rather than lost lines
+        let options = MapOptions {
+            on_missing: OnMissing::Emit,
+            format: Format::Vcf,
+            ..MapOptions::default()
+        };
+        let vcf = map_to_loci(
+            &fixture.path("in.tsv"),
+            &fixture.index,
+            &fixture.path("out.vcf"),
+            &options,
+        );
+        assert_eq!(vcf.unwrap_err().kind(), ErrorKind::Usage);
+        assert!(!fixture.path("out.vcf").exists());
+    }
+
+    #[test]
+    fn any_contig_round_trips() {
+        let map = "rs1\tchr2:5\nrs2\tGL000194.1:7\nrs3\tchrM:9\nrs4\tchrUn_gl000220:3\n";
+        let fixture = Fixture::indexed(map);
+        fixture.write("in.tsv", "rs4\nrs3\nrs2\nrs1\n");
+        let (tree, loci) = (fixture.path("tree.idx"), fixture.path("loci.idx"));
+        let progress = Progress::default();
+        create_btree_map(
+            &fixture.path("map.tsv"),
+            &tree,
+            false,
+            PosWidth::U32,
+            &progress,
+        )
+        .unwrap();
+        create_locus_map(&fixture.path("map.tsv"), &loci, PosWidth::U32, &progress).unwrap();
+
+        for index in [&fixture.index, &tree] {
+            let run = |chr_prefix| {
+                let options = MapOptions {
+                    sort_by_locus: true,
+                    chr_prefix,
+                    ..MapOptions::default()
+                };
+                fixture.map(index, &options).unwrap()
+            };
+            // the fixed chromosomes first, then the rest in the order they were seen
+            assert_eq!(run(false), "2:5\nMT:9\nGL000194.1:7\nchrUn_gl000220:3\n");
+            assert_eq!(
+                run(true),
+                "chr2:5\nchrM:9\nGL000194.1:7\nchrUn_gl000220:3\n"
+            );
+        }
+
+        fixture.write(
+            "loci.tsv",
+            "GL000194.1:7\nMT:9\n2:5\nchr2:5\nGL000195.1:7\n",
+        );
+        map_to_rsids(
+            &fixture.path("loci.tsv"),
+            &loci,
+            &fixture.path("out"),
+            false,
+        )
+        .unwrap();
+        assert_eq!(fixture.read("out"), "rs2\nrs3\nrs1\nrs1\n.\n");
+    }
+
+    #[test]
+    fn wide_positions_need_a_wide_index() {
+        let fixture = Fixture::new();
+        fixture.write("map.tsv", "rs1\t2:5\nrs2\tchr9_big:5000000000\n");
+        fixture.write("in.tsv", "rs2\nrs1\n");
+        let map = fixture.path("map.tsv");
+        let (tree, loci) = (fixture.path("tree.idx"), fixture.path("loci.idx"));
+        let progress = Progress::default();
+        let narrow = create_map(&map, &fixture.index, false, PosWidth::U32, &progress);
+        assert_eq!(narrow.unwrap_err().kind(), ErrorKind::Data);
+
+        create_map(&map, &fixture.index, false, PosWidth::U64, &progress).unwrap();
+        create_btree_map(&map, &tree, false, PosWidth::U64, &progress).unwrap();
+        create_locus_map(&map, &loci, PosWidth::U64, &progress).unwrap();
+        for index in [&fixture.index, &tree] {
+            assert_eq!(
+                fixture.map(index, &MapOptions::default()).unwrap(),
+                "chr9_big:5000000000\n2:5\n"
+            );
+        }
+        fixture.write("loci.tsv", "chr9_big:5000000000\n2:5\n");
+        map_to_rsids(
+            &fixture.path("loci.tsv"),
+            &loci,
+            &fixture.path("out"),
+            false,
+        )
+        .unwrap();
+        assert_eq!(fixture.read("out"), "rs2\nrs1\n");
+
+        // and a narrow locus index has nothing past 32 bits rather than wrapping around
+        fixture.write("map.tsv", "rs1\t2:5\n");
+        create_locus_map(&map, &loci, PosWidth::U32, &progress).unwrap();
+        fixture.write("loci.tsv", "2:4294967301\n2:5\n");
+        map_to_rsids(
+            &fixture.path("loci.tsv"),
+            &loci,
+            &fixture.path("out"),
+            false,
+        )
+        .unwrap();
+        assert_eq!(fixture.read("out"), ".\nrs1\n");
+    }
+
+    #[test]
+    fn lookup_prints_a_line_per_rsid() {
+        let fixture = Fixture::indexed("rs1\t2:5\nrs2\tX:100\n");
+        fixture.write("list", "rs2\n\n7\n 1 \n");
+
+        let mut out = Vec::new();
+        let rsids = read_rsid_list(&fixture.path("list")).unwrap();
+        lookup_rsids(&fixture.index, &rsids, false, &mut out).unwrap();
+        assert_eq!(out, b"rs2\tX:100\nrs7\t.\nrs1\t2:5\n");
+        out.clear();
+        lookup_rsids(&fixture.index, ["2"], true, &mut out).unwrap();
+        assert_eq!(out, b"rs2\tchrX:100\n");
+        let bad = lookup_rsids(&fixture.index, ["rsx"], false, &mut out);
+        assert_eq!(bad.unwrap_err().kind(), ErrorKind::Data);
+    }
+}
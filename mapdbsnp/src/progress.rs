@@ -0,0 +1,100 @@
+// A progress bar on stderr for the long runs, indexing a whole dbSNP build takes a while
+//
+// The bar counts the input's bytes as they come off the disk, before any gzip decoding, so its
+// total is the file's size and the ETA holds for a gzipped file too. Records read and how many a
+// second go in its message. stdin has no size and gets a spinner with a byte count instead.
+//
+// Nothing's drawn unless stderr is a terminal, so a log file or a pipeline doesn't fill up with
+// escape codes, and Progress::default() is never drawn at all, which is what the library uses
+// when it isn't given one.
+use std::{
+    io::{self, IsTerminal, Read},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use indicatif::{HumanCount, ProgressBar, ProgressDrawTarget, ProgressStyle};
+
+// The message is redrawn every this many records, formatting it every record would cost
+const EVERY: u64 = 1 << 14;
+const SPINNER: &str = "{spinner} [{elapsed_precise}] {binary_bytes} {msg}";
+const BAR: &str =
+    "{spinner} [{elapsed_precise}] {wide_bar} {binary_bytes}/{binary_total_bytes} eta {eta} {msg}";
+
+// Clones share the bar and the count
+#[derive(Debug, Clone)]
+pub struct Progress {
+    bar: ProgressBar,
+    records: Arc<AtomicU64>,
+}
+
+impl Progress {
+    // A bar if show is set and stderr is a terminal, otherwise one that's never drawn
+    pub fn new(show: bool) -> Self {
+        if !show || !io::stderr().is_terminal() {
+            return Progress::default();
+        }
+        let bar = ProgressBar::with_draw_target(None, ProgressDrawTarget::stderr());
+        bar.set_style(style(SPINNER));
+        bar.enable_steady_tick(Duration::from_millis(200));
+        Progress {
+            bar,
+            records: Arc::default(),
+        }
+    }
+
+    // Called with the size of the input once it's known to be a file
+    pub(crate) fn set_len(&self, len: u64) {
+        self.bar.set_length(len);
+        self.bar.set_style(style(BAR));
+    }
+
+    pub(crate) fn wrap_read<R: Read>(&self, read: R) -> impl Read {
+        self.bar.wrap_read(read)
+    }
+
+    pub(crate) fn record(&self) {
+        let records = self.records.fetch_add(1, Ordering::Relaxed) + 1;
+        if records.is_multiple_of(EVERY) {
+            self.bar.set_message(self.message());
+        }
+    }
+
+    // What's happening once the input's all read, like a sort
+    pub(crate) fn stage(&self, stage: &str) {
+        self.bar.set_message(format!("{}, {stage}", self.message()));
+    }
+
+    pub(crate) fn finish(&self) {
+        self.bar.finish_with_message(self.message());
+    }
+
+    fn message(&self) -> String {
+        let records = self.records.load(Ordering::Relaxed);
+        let per_sec = records as f64 / self.bar.elapsed().as_secs_f64().max(1e-3);
+        format!(
+            "{} records, {}/s",
+            HumanCount(records),
+            HumanCount(per_sec as u64)
+        )
+    }
+}
+
+impl Default for Progress {
+    fn default() -> Self {
+        Progress {
+            bar: ProgressBar::hidden(),
+            records: Arc::default(),
+        }
+    }
+}
+
+fn style(template: &str) -> ProgressStyle {
+    // the templates are constants, an error here is a typo in one of them
+    ProgressStyle::with_template(template)
+        .expect("bad progress template")
+        .progress_chars("=> ")
+}
@@ -0,0 +1,101 @@
+// Records as extsort sees them, for sorting maps that aren't in rsid order, maps into locus order
+// for the locus index, and output by locus
+use std::cmp::Ordering;
+use std::io::{self, BufRead, Read, Write};
+
+use binio::{ReadBe, WriteBe};
+use extsort::{Codec, ExternalSorter};
+
+// One line of the source map, rs123<TAB>1:12345
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MapRecord {
+    pub rsid: u32,
+    pub chrom: u8,
+    pub pos: u64,
+}
+
+// One line of output before it's written, the locus and the rest of the input line
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LocusRecord {
+    pub chrom: u8,
+    pub pos: u64,
+    pub fields: Vec<String>,
+}
+
+pub type Sorter<T, C> = ExternalSorter<T, C, fn(&T, &T) -> Ordering>;
+
+pub fn by_rsid() -> Sorter<MapRecord, MapRecordCodec> {
+    ExternalSorter::new(MapRecordCodec, |a, b| a.rsid.cmp(&b.rsid))
+}
+
+// Map records the way the locus index wants them, rsids at the same locus smallest first
+pub fn map_by_locus() -> Sorter<MapRecord, MapRecordCodec> {
+    ExternalSorter::new(MapRecordCodec, |a, b| {
+        (a.chrom, a.pos, a.rsid).cmp(&(b.chrom, b.pos, b.rsid))
+    })
+}
+
+// Chromosomes in the order of their codes, 1..22, X, Y, MT
+pub fn by_locus() -> Sorter<LocusRecord, LocusRecordCodec> {
+    ExternalSorter::new(LocusRecordCodec, |a, b| {
+        (a.chrom, a.pos).cmp(&(b.chrom, b.pos))
+    })
+}
+
+// rsid u32 | chrom u8 | pos u64, a flat index record with the position always wide
+pub struct MapRecordCodec;
+
+impl Codec<MapRecord> for MapRecordCodec {
+    fn write<W: Write>(&self, item: &MapRecord, out: &mut W) -> io::Result<()> {
+        out.write_be_u32(item.rsid)?;
+        out.write_be_u8(item.chrom)?;
+        out.write_be_u64(item.pos)
+    }
+
+    fn read<R: BufRead>(&self, input: &mut R) -> io::Result<Option<MapRecord>> {
+        if input.fill_buf()?.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(MapRecord {
+            rsid: input.read_be_u32()?,
+            chrom: input.read_be_u8()?,
+            pos: input.read_be_u64()?,
+        }))
+    }
+}
+
+// chrom u8 | pos u64 | field count u32 | count * (length u32 | utf-8 bytes)
+pub struct LocusRecordCodec;
+
+impl Codec<LocusRecord> for LocusRecordCodec {
+    fn write<W: Write>(&self, item: &LocusRecord, out: &mut W) -> io::Result<()> {
+        out.write_be_u8(item.chrom)?;
+        out.write_be_u64(item.pos)?;
+        out.write_be_u32(item.fields.len() as u32)?;
+        for field in &item.fields {
+            out.write_be_u32(field.len() as u32)?;
+            out.write_all(field.as_bytes())?;
+        }
+        Ok(())
+    }
+
+    fn read<R: BufRead>(&self, input: &mut R) -> io::Result<Option<LocusRecord>> {
+        if input.fill_buf()?.is_empty() {
+            return Ok(None);
+        }
+        let chrom = input.read_be_u8()?;
+        let pos = input.read_be_u64()?;
+        let count = input.read_be_u32()?;
+        let mut fields = Vec::with_capacity(count as usize);
+        for _ in 0..count {
+            let len = input.read_be_u32()?;
+            let mut field = String::new();
+            input.take(u64::from(len)).read_to_string(&mut field)?;
+            if field.len() != len as usize {
+                return Err(io::ErrorKind::UnexpectedEof.into());
+            }
+            fields.push(field);
+        }
+        Ok(Some(LocusRecord { chrom, pos, fields }))
+    }
+}
@@ -0,0 +1,95 @@
+// Text files in and out, gzipped or not, and `-` for stdin or stdout
+//
+// Inputs are sniffed rather than trusted by name: a gzip stream starts 1f 8b whatever the file is
+// called. dbSNP's VCFs are bgzipped, which is a series of gzip members one after the other (so
+// tabix can seek to any of them), and MultiGzDecoder reads on through all of them where a plain
+// GzDecoder would stop after the first 64KB block.
+//
+// Output to stdout is buffered like a file is, stdout on its own flushes every line. Diagnostics
+// all go to stderr so they stay out of a pipeline.
+use std::{
+    fs::File,
+    io::{self, BufRead, BufReader, BufWriter, Read, Write},
+    path::Path,
+};
+
+use flate2::{read::MultiGzDecoder, write::GzEncoder, Compression};
+use report::{Result, ResultExt};
+
+use crate::Progress;
+
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+pub fn is_stdio(path: &impl AsRef<Path>) -> bool {
+    path.as_ref() == Path::new("-")
+}
+
+pub fn open(path: &impl AsRef<Path>) -> Result<Box<dyn BufRead>> {
+    open_with_progress(path, &Progress::default())
+}
+
+// The same with progress counted on the raw bytes, under the gzip decoder
+pub fn open_with_progress(
+    path: &impl AsRef<Path>,
+    progress: &Progress,
+) -> Result<Box<dyn BufRead>> {
+    let raw: Box<dyn Read> = if is_stdio(path) {
+        Box::new(io::stdin().lock())
+    } else {
+        let file = File::open(path).in_file(path)?;
+        progress.set_len(file.metadata().in_file(path)?.len());
+        Box::new(file)
+    };
+    let mut input: Box<dyn BufRead> = Box::new(BufReader::new(progress.wrap_read(raw)));
+    let gzipped = input.fill_buf().in_file(path)?.starts_with(&GZIP_MAGIC);
+    Ok(if gzipped {
+        Box::new(BufReader::new(MultiGzDecoder::new(input)))
+    } else {
+        input
+    })
+}
+
+pub fn create(path: &impl AsRef<Path>, compress: bool) -> Result<Output> {
+    let out: Box<dyn Write> = if is_stdio(path) {
+        Box::new(BufWriter::new(io::stdout().lock()))
+    } else {
+        Box::new(BufWriter::new(File::create(path).in_file(path)?))
+    };
+    Ok(if compress {
+        Output::Gz(GzEncoder::new(out, Compression::default()))
+    } else {
+        Output::Plain(out)
+    })
+}
+
+// Dropping a GzEncoder writes its trailer too, but swallows any error doing it, so output is
+// finished by hand to hear about a full disk
+pub enum Output {
+    Plain(Box<dyn Write>),
+    Gz(GzEncoder<Box<dyn Write>>),
+}
+
+impl Output {
+    pub fn finish(self) -> io::Result<()> {
+        match self {
+            Output::Plain(mut out) => out.flush(),
+            Output::Gz(gz) => gz.finish()?.flush(),
+        }
+    }
+}
+
+impl Write for Output {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Output::Plain(out) => out.write(buf),
+            Output::Gz(gz) => gz.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Output::Plain(out) => out.flush(),
+            Output::Gz(gz) => gz.flush(),
+        }
+    }
+}
@@ -0,0 +1,73 @@
+// dbSNP's own releases are VCFs with the rsid in the ID column
+//
+//   NC_000001.11  10001  rs1570391677  T  A,C  .  .  RS=1570391677;dbSNPBuildID=154;...
+//
+// so they can be indexed as they come, still bgzipped, instead of being cut down to an
+// `rsid<TAB>chrom:pos` map first. They're in locus order, not rsid order, so they always go
+// through the sorter.
+use std::{
+    collections::HashSet,
+    io::{self, BufRead},
+    path::Path,
+};
+
+use report::{Error, Location, Result, ResultExt};
+use vcf_lite::{Reader, VcfError};
+
+use crate::{chroms::Chroms, rsid_to_u32, sort::MapRecord};
+
+// Whether an (unzipped) input starts like a VCF, anything else is read as a tsv map
+// It only peeks, the input is left where it was.
+pub fn is_vcf(input: &mut impl BufRead) -> io::Result<bool> {
+    Ok(input.fill_buf()?.starts_with(b"##fileformat=VCF"))
+}
+
+// Every rsid in the file as a map record, with the line it came from
+// A record with several rsids gives one map record each, and ids that aren't rsids are skipped.
+// Contigs get codes from chroms as they come. A whole dbSNP build has more contigs than an index
+// has codes (there are hundreds of patches), so once they run out the rest are skipped with a
+// warning each rather than failing the index.
+pub fn for_each_record<P: AsRef<Path>>(
+    input: impl BufRead,
+    path: &P,
+    chroms: &mut Chroms,
+    mut f: impl FnMut(MapRecord, Location) -> Result<()>,
+) -> Result<()> {
+    let mut reader = Reader::new(input).map_err(|e| vcf_error(e, path))?;
+    let mut skipped = HashSet::new();
+
+    while let Some(record) = reader.read_record().map_err(|e| vcf_error(e, path))? {
+        let at = Location::file(path).line(reader.line());
+        let chrom = match chroms.code_or_add(&record.chrom) {
+            Ok(chrom) => chrom,
+            Err(e) => {
+                if skipped.insert(record.chrom.clone()) {
+                    log::warn!("{at}: {e}, its records are left out");
+                }
+                continue;
+            }
+        };
+        for id in record.ids.iter().filter(|id| id.starts_with("rs")) {
+            let rsid = rsid_to_u32(id).at(at.clone())?;
+            f(
+                MapRecord {
+                    rsid,
+                    chrom,
+                    pos: record.pos,
+                },
+                at.clone(),
+            )?;
+        }
+    }
+    Ok(())
+}
+
+fn vcf_error(e: VcfError, path: &impl AsRef<Path>) -> Error {
+    match e {
+        VcfError::Io(e) => Error::from(e).in_file(path),
+        e => {
+            let location = Location::file(path).line(e.line().unwrap_or_default());
+            Error::data(e.to_string()).at(location)
+        }
+    }
+}
@@ -0,0 +1,253 @@
+use std::error;
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+// What went wrong, broadly, which decides the exit code
+// The codes are the BSD sysexits ones, except usage which matches clap's 2, so a script can tell
+// "you called it wrong" from "your file is bad" from "the disk is unhappy" without parsing stderr.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorKind {
+    // bad arguments or options
+    Usage,
+    // an input file doesn't exist or can't be opened
+    NoInput,
+    // an input file is there but its contents are wrong (unsorted, unparseable, corrupt)
+    Data,
+    // reading or writing failed part way
+    Io,
+    // a bug, something that should have been impossible
+    Internal,
+}
+
+impl ErrorKind {
+    pub fn exit_code(self) -> u8 {
+        match self {
+            ErrorKind::Usage => 2,
+            ErrorKind::NoInput => 66,
+            ErrorKind::Data => 65,
+            ErrorKind::Io => 74,
+            ErrorKind::Internal => 70,
+        }
+    }
+}
+
+impl fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ErrorKind::Usage => "usage error",
+            ErrorKind::NoInput => "missing input",
+            ErrorKind::Data => "bad input",
+            ErrorKind::Io => "i/o error",
+            ErrorKind::Internal => "internal error",
+        })
+    }
+}
+
+// Where in which file an error was found, as much of it as is known
+//   map.tsv:12 (byte 345)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Location {
+    pub path: PathBuf,
+    // 1-based, like editors and compilers count them
+    pub line: Option<u64>,
+    // from the start of the file
+    pub offset: Option<u64>,
+}
+
+impl Location {
+    pub fn file(path: impl AsRef<Path>) -> Self {
+        Location {
+            path: path.as_ref().to_path_buf(),
+            line: None,
+            offset: None,
+        }
+    }
+
+    pub fn line(mut self, line: u64) -> Self {
+        self.line = Some(line);
+        self
+    }
+
+    pub fn offset(mut self, offset: u64) -> Self {
+        self.offset = Some(offset);
+        self
+    }
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.path.display())?;
+        if let Some(line) = self.line {
+            write!(f, ":{line}")?;
+        }
+        if let Some(offset) = self.offset {
+            write!(f, " (byte {offset})")?;
+        }
+        Ok(())
+    }
+}
+
+// An error for a command line tool to show a person: what happened, where, and why
+// Display is just the message, Reporter is what puts the location and the causes around it.
+#[derive(Debug)]
+pub struct Error {
+    kind: ErrorKind,
+    message: String,
+    location: Option<Location>,
+    source: Option<Box<dyn error::Error + Send + Sync>>,
+}
+
+impl Error {
+    pub fn new(kind: ErrorKind, message: impl Into<String>) -> Self {
+        Error {
+            kind,
+            message: message.into(),
+            location: None,
+            source: None,
+        }
+    }
+
+    pub fn usage(message: impl Into<String>) -> Self {
+        Error::new(ErrorKind::Usage, message)
+    }
+
+    pub fn data(message: impl Into<String>) -> Self {
+        Error::new(ErrorKind::Data, message)
+    }
+
+    pub fn internal(message: impl Into<String>) -> Self {
+        Error::new(ErrorKind::Internal, message)
+    }
+
+    // Replaces the location, later context usually knows more than earlier context
+    pub fn at(mut self, location: Location) -> Self {
+        self.location = Some(location);
+        self
+    }
+
+    // Sets the file, keeping any line or offset that's already known
+    pub fn in_file(mut self, path: impl AsRef<Path>) -> Self {
+        match &mut self.location {
+            Some(location) => location.path = path.as_ref().to_path_buf(),
+            None => self.location = Some(Location::file(path)),
+        }
+        self
+    }
+
+    pub fn caused_by(mut self, source: impl Into<Box<dyn error::Error + Send + Sync>>) -> Self {
+        self.source = Some(source.into());
+        self
+    }
+
+    pub fn kind(&self) -> ErrorKind {
+        self.kind
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    pub fn location(&self) -> Option<&Location> {
+        self.location.as_ref()
+    }
+
+    pub fn exit_code(&self) -> u8 {
+        self.kind.exit_code()
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        self.source
+            .as_deref()
+            .map(|e| e as &(dyn error::Error + 'static))
+    }
+}
+
+// A file that isn't there is the caller's problem, one that ends early is a bad file, anything
+// else is the system's
+impl From<io::ErrorKind> for ErrorKind {
+    fn from(kind: io::ErrorKind) -> Self {
+        match kind {
+            io::ErrorKind::NotFound | io::ErrorKind::PermissionDenied => ErrorKind::NoInput,
+            io::ErrorKind::InvalidData | io::ErrorKind::UnexpectedEof => ErrorKind::Data,
+            _ => ErrorKind::Io,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Self {
+        Error::new(e.kind().into(), e.to_string())
+    }
+}
+
+// Context for errors on their way up
+//   File::open(path).in_file(path)?
+//   read_header(file).context("not an index")?
+pub trait ResultExt<T> {
+    fn in_file(self, path: impl AsRef<Path>) -> Result<T>;
+    fn at(self, location: Location) -> Result<T>;
+    // Wraps the error as the cause of a new one with the same kind and location
+    fn context(self, message: impl Into<String>) -> Result<T>;
+}
+
+impl<T, E: Into<Error>> ResultExt<T> for Result<T, E> {
+    fn in_file(self, path: impl AsRef<Path>) -> Result<T> {
+        self.map_err(|e| e.into().in_file(path))
+    }
+
+    fn at(self, location: Location) -> Result<T> {
+        self.map_err(|e| e.into().at(location))
+    }
+
+    fn context(self, message: impl Into<String>) -> Result<T> {
+        self.map_err(|e| {
+            let e = e.into();
+            let mut wrapped = Error::new(e.kind, message);
+            wrapped.location = e.location.clone();
+            wrapped.caused_by(e)
+        })
+    }
+}
+
+#[cfg(test)]
+mod testing {
+    use super::*;
+
+    #[test]
+    fn locations_and_kinds() {
+        let e = Error::data("rs5 is out of order")
+            .at(Location::file("map.tsv").line(12).offset(345))
+            .in_file("other.tsv");
+        assert_eq!(e.kind(), ErrorKind::Data);
+        assert_eq!(e.exit_code(), 65);
+        assert_eq!(e.to_string(), "rs5 is out of order");
+        assert_eq!(e.location().unwrap().to_string(), "other.tsv:12 (byte 345)");
+        assert_eq!(Location::file("a").offset(8).to_string(), "a (byte 8)");
+
+        let missing: Error = io::Error::from(io::ErrorKind::NotFound).into();
+        assert_eq!(missing.kind(), ErrorKind::NoInput);
+        let eof: Error = io::Error::from(io::ErrorKind::UnexpectedEof).into();
+        assert_eq!(eof.kind(), ErrorKind::Data);
+    }
+
+    #[test]
+    fn context_keeps_the_cause_and_location() {
+        let result: Result<()> = Err(Error::data("bad byte").at(Location::file("x").offset(3)));
+        let e = result.context("x isn't an index").unwrap_err();
+        assert_eq!(e.to_string(), "x isn't an index");
+        assert_eq!(e.kind(), ErrorKind::Data);
+        assert_eq!(e.location().unwrap().offset, Some(3));
+        assert_eq!(error::Error::source(&e).unwrap().to_string(), "bad byte");
+    }
+}
@@ -0,0 +1,5 @@
+mod error;
+mod reporter;
+
+pub use error::{Error, ErrorKind, Location, Result, ResultExt};
+pub use reporter::Reporter;
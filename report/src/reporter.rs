@@ -0,0 +1,79 @@
+use std::error;
+use std::io::{self, Write};
+use std::process::ExitCode;
+
+use crate::Error;
+
+// Prints errors for people and turns them into exit codes, the same way for every tool
+//
+//   mapdbsnp: error: rs1234 isn't in the map
+//     --> input.tsv:17 (byte 2048)
+//     caused by: ...
+pub struct Reporter {
+    program: String,
+}
+
+impl Reporter {
+    pub fn new(program: impl Into<String>) -> Self {
+        Reporter {
+            program: program.into(),
+        }
+    }
+
+    pub fn write(&self, e: &Error, out: &mut impl Write) -> io::Result<()> {
+        writeln!(out, "{}: error: {e}", self.program)?;
+        if let Some(location) = e.location() {
+            writeln!(out, "  --> {location}")?;
+        }
+        let mut source = error::Error::source(e);
+        while let Some(cause) = source {
+            writeln!(out, "  caused by: {cause}")?;
+            source = cause.source();
+        }
+        Ok(())
+    }
+
+    // Writes the error to stderr and returns the code to exit with
+    pub fn report(&self, e: &Error) -> ExitCode {
+        // if stderr is gone there's nowhere left to complain to
+        let _ = self.write(e, &mut io::stderr().lock());
+        ExitCode::from(e.exit_code())
+    }
+
+    // For main: Ok is success, Err gets reported
+    pub fn exit(&self, result: crate::Result<()>) -> ExitCode {
+        match result {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(e) => self.report(&e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod testing {
+    use super::*;
+    use crate::{ErrorKind, Location, ResultExt};
+
+    #[test]
+    fn writes_location_and_causes() {
+        let result: crate::Result<()> = Err(io::Error::other("disk on fire").into());
+        let e = result
+            .context("couldn't write out.tsv")
+            .unwrap_err()
+            .at(Location::file("out.tsv").line(3));
+
+        assert_eq!(e.kind(), ErrorKind::Io);
+        let mut out = Vec::new();
+        Reporter::new("tool").write(&e, &mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "tool: error: couldn't write out.tsv\n  --> out.tsv:3\n  caused by: disk on fire\n"
+        );
+
+        let mut out = Vec::new();
+        Reporter::new("tool")
+            .write(&Error::usage("no input"), &mut out)
+            .unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "tool: error: no input\n");
+    }
+}
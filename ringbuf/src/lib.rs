@@ -0,0 +1,4 @@
+mod ring;
+pub mod spsc;
+
+pub use ring::{RingBuffer, RingBufferIter};
@@ -0,0 +1,313 @@
+use std::fmt;
+use std::iter::FusedIterator;
+use std::mem::MaybeUninit;
+
+// A queue that holds at most N values, in an array with no allocation at all
+// The values live in a window of the array that starts at head and wraps round the end:
+//
+//   buf:  [ d  e  .  .  .  a  b  c ]       head = 5, len = 5
+//                          ^ head           front to back: a b c d e
+//
+// Pushing writes just past the end of the window and popping moves head along, so neither ever
+// moves a value that's already there. When the buffer is full there are two things a push can
+// do, and they're two methods:
+//  - push fails and hands the value back (for when dropping data is a bug)
+//  - force_push overwrites the oldest value (for logs, audio, sensor readings, anywhere the newest
+//    N values are the ones that matter)
+//
+// Slots outside the window hold nothing, which is what MaybeUninit is for: it lets the array
+// exist without N values to fill it with, in exchange for us keeping track of which slots are
+// initialised (the window) and dropping only those.
+pub struct RingBuffer<T, const N: usize> {
+    buf: [MaybeUninit<T>; N],
+    head: usize,
+    len: usize,
+}
+
+impl<T, const N: usize> RingBuffer<T, N> {
+    pub const fn new() -> Self {
+        const { assert!(N > 0, "a ring buffer needs room for at least one value") };
+        RingBuffer {
+            buf: [const { MaybeUninit::uninit() }; N],
+            head: 0,
+            len: 0,
+        }
+    }
+
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.len == N
+    }
+
+    // Adds value at the back, or hands it back if the buffer is full
+    pub fn push(&mut self, value: T) -> Result<(), T> {
+        if self.is_full() {
+            return Err(value);
+        }
+        let slot = self.slot(self.len);
+        self.buf[slot].write(value);
+        self.len += 1;
+        Ok(())
+    }
+
+    // Adds value at the back, pushing the oldest value out (and returning it) if the buffer is full
+    pub fn force_push(&mut self, value: T) -> Option<T> {
+        if !self.is_full() {
+            let _ = self.push(value);
+            return None;
+        }
+        // the front slot becomes the back one: swap the value in and move head past it
+        // SAFETY: the buffer is full so every slot, head's included, is initialised
+        let oldest = unsafe { self.buf[self.head].assume_init_read() };
+        self.buf[self.head].write(value);
+        self.head = self.slot(1);
+        Some(oldest)
+    }
+
+    // Takes out the oldest value
+    pub fn pop(&mut self) -> Option<T> {
+        if self.is_empty() {
+            return None;
+        }
+        // SAFETY: head is in the window, and moving head past it means it won't be read again
+        let value = unsafe { self.buf[self.head].assume_init_read() };
+        self.head = self.slot(1);
+        self.len -= 1;
+        Some(value)
+    }
+
+    // Takes out the newest value
+    pub fn pop_back(&mut self) -> Option<T> {
+        if self.is_empty() {
+            return None;
+        }
+        self.len -= 1;
+        // SAFETY: this was the last slot in the window, shrinking len just took it out
+        Some(unsafe { self.buf[self.slot(self.len)].assume_init_read() })
+    }
+
+    // i counts from the front, 0 is the oldest value
+    pub fn get(&self, i: usize) -> Option<&T> {
+        // SAFETY: slots 0..len from head are the window
+        (i < self.len).then(|| unsafe { self.buf[self.slot(i)].assume_init_ref() })
+    }
+
+    pub fn get_mut(&mut self, i: usize) -> Option<&mut T> {
+        if i >= self.len {
+            return None;
+        }
+        let slot = self.slot(i);
+        // SAFETY: slots 0..len from head are the window
+        Some(unsafe { self.buf[slot].assume_init_mut() })
+    }
+
+    pub fn front(&self) -> Option<&T> {
+        self.get(0)
+    }
+
+    pub fn back(&self) -> Option<&T> {
+        self.get(self.len.wrapping_sub(1))
+    }
+
+    pub fn clear(&mut self) {
+        while self.pop().is_some() {}
+        self.head = 0;
+    }
+
+    // Oldest to newest
+    pub fn iter(&self) -> RingBufferIter<'_, T, N> {
+        RingBufferIter {
+            ring: self,
+            front: 0,
+            back: self.len,
+        }
+    }
+
+    // The array index of the i-th value from the front
+    fn slot(&self, i: usize) -> usize {
+        (self.head + i) % N
+    }
+}
+
+impl<T, const N: usize> Default for RingBuffer<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Clone, const N: usize> Clone for RingBuffer<T, N> {
+    fn clone(&self) -> Self {
+        let mut ring = Self::new();
+        for value in self.iter() {
+            let _ = ring.push(value.clone());
+        }
+        ring
+    }
+}
+
+impl<T, const N: usize> Drop for RingBuffer<T, N> {
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
+
+impl<T: fmt::Debug, const N: usize> fmt::Debug for RingBuffer<T, N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<T: PartialEq, const N: usize> PartialEq for RingBuffer<T, N> {
+    fn eq(&self, other: &Self) -> bool {
+        self.iter().eq(other.iter())
+    }
+}
+
+impl<T: Eq, const N: usize> Eq for RingBuffer<T, N> {}
+
+// front..back are the positions (from head) still to be yielded
+pub struct RingBufferIter<'a, T, const N: usize> {
+    ring: &'a RingBuffer<T, N>,
+    front: usize,
+    back: usize,
+}
+
+impl<'a, T, const N: usize> Iterator for RingBufferIter<'a, T, N> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        if self.front == self.back {
+            return None;
+        }
+        self.front += 1;
+        self.ring.get(self.front - 1)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.back - self.front;
+        (len, Some(len))
+    }
+}
+
+impl<T, const N: usize> DoubleEndedIterator for RingBufferIter<'_, T, N> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.front == self.back {
+            return None;
+        }
+        self.back -= 1;
+        self.ring.get(self.back)
+    }
+}
+
+impl<T, const N: usize> ExactSizeIterator for RingBufferIter<'_, T, N> {}
+
+// Once front meets back they stay met
+impl<T, const N: usize> FusedIterator for RingBufferIter<'_, T, N> {}
+
+impl<'a, T, const N: usize> IntoIterator for &'a RingBuffer<T, N> {
+    type Item = &'a T;
+    type IntoIter = RingBufferIter<'a, T, N>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+#[cfg(test)]
+mod testing {
+    use std::collections::VecDeque;
+    use std::rc::Rc;
+
+    use super::*;
+
+    #[test]
+    fn agrees_with_vecdeque() {
+        let mut ring = RingBuffer::<u32, 7>::new();
+        let mut model = VecDeque::new();
+        let mut x: u64 = 5;
+        for _ in 0..10_000 {
+            x = x.wrapping_mul(6364136223846793005).wrapping_add(1);
+            let value = (x >> 33) as u32;
+            match x % 5 {
+                0 => {
+                    let pushed = ring.push(value);
+                    if model.len() < 7 {
+                        model.push_back(value);
+                        assert_eq!(pushed, Ok(()));
+                    } else {
+                        assert_eq!(pushed, Err(value));
+                    }
+                }
+                1 => {
+                    let evicted = (model.len() == 7).then(|| model.pop_front()).flatten();
+                    model.push_back(value);
+                    assert_eq!(ring.force_push(value), evicted);
+                }
+                2 => assert_eq!(ring.pop(), model.pop_front()),
+                3 => assert_eq!(ring.pop_back(), model.pop_back()),
+                _ => {
+                    if let Some(back) = ring.get_mut(model.len().wrapping_sub(1)) {
+                        *back = value;
+                        *model.back_mut().unwrap() = value;
+                    }
+                }
+            }
+
+            assert_eq!(ring.len(), model.len());
+            assert_eq!(ring.is_full(), model.len() == 7);
+            assert_eq!((ring.front(), ring.back()), (model.front(), model.back()));
+            assert!(ring.iter().eq(model.iter()));
+            assert!(ring.iter().rev().eq(model.iter().rev()));
+        }
+    }
+
+    #[test]
+    fn keeps_the_newest_values() {
+        let mut ring = RingBuffer::<_, 3>::default();
+        for i in 0..10 {
+            ring.force_push(i);
+        }
+        assert_eq!(format!("{ring:?}"), "[7, 8, 9]");
+        assert_eq!(ring.capacity(), 3);
+        assert_eq!(ring.push(10), Err(10));
+        assert_eq!(ring.clone(), ring);
+
+        let mut iter = ring.iter();
+        assert_eq!(iter.len(), 3);
+        assert_eq!((iter.next(), iter.next_back()), (Some(&7), Some(&9)));
+        assert_eq!((iter.next(), iter.next()), (Some(&8), None));
+        assert_eq!(iter.next_back(), None);
+
+        ring.clear();
+        assert!(ring.is_empty());
+        assert_eq!(
+            (ring.pop(), ring.pop_back(), ring.back()),
+            (None, None, None)
+        );
+    }
+
+    #[test]
+    fn drops_every_value_once() {
+        let counter = Rc::new(());
+        {
+            let mut ring = RingBuffer::<_, 4>::new();
+            for _ in 0..10 {
+                drop(ring.force_push(Rc::clone(&counter)));
+            }
+            drop(ring.pop());
+            assert_eq!(Rc::strong_count(&counter), 1 + 3);
+        }
+        assert_eq!(Rc::strong_count(&counter), 1);
+    }
+}
@@ -0,0 +1,323 @@
+// A ring buffer split into a Producer and a Consumer for two threads, with no locks
+//
+// It's a Lamport queue: head and tail are counters that only ever go forward, the producer is
+// the only one who moves tail and the consumer the only one who moves head. They count round
+// 0..2N rather than all of usize, so a full buffer (tail N ahead of head) and an empty one (tail
+// == head) still look different, and the slot for a count is count % N.
+//
+//   buf (N = 4):  [ c  .  a  b ]      head = 6, tail = 1
+//                         ^ 6 % 4       the values are counts 6, 7, 0: slots 2, 3, 0: a b c
+//
+// tail - head (mod 2N) is how many values are in the buffer. Letting them wrap round usize::MAX
+// instead only works when N divides 2^64: with N = 3, usize::MAX % 3 is 0 and so is the 0 it
+// wraps to, and the same slot gets written twice.
+// Each side reads the other's counter to see how much room or how many values there are, and
+// publishes its own with a Release store after it's done with the slot, so:
+//  - the consumer only reads a slot after the producer's write to it is visible
+//  - the producer only reuses a slot after the consumer has finished moving the value out
+// Neither side ever waits on the other, push fails when full and pop returns None when empty.
+
+mod sync;
+
+use std::fmt;
+use std::mem::MaybeUninit;
+
+use sync::{Arc, AtomicUsize, Ordering, UnsafeCell};
+
+struct Shared<T, const N: usize> {
+    buf: [UnsafeCell<MaybeUninit<T>>; N],
+    // the next slot to pop, only the consumer moves it
+    head: AtomicUsize,
+    // the next slot to push to, only the producer moves it
+    tail: AtomicUsize,
+}
+
+pub struct Producer<T, const N: usize> {
+    shared: Arc<Shared<T, N>>,
+}
+
+pub struct Consumer<T, const N: usize> {
+    shared: Arc<Shared<T, N>>,
+}
+
+// Creates an empty queue with room for N values, split into its two ends
+pub fn channel<T, const N: usize>() -> (Producer<T, N>, Consumer<T, N>) {
+    const { assert!(N > 0, "a ring buffer needs room for at least one value") };
+    const { assert!(N <= usize::MAX / 2, "the counters count to 2N") };
+    let shared = Arc::new(Shared {
+        buf: std::array::from_fn(|_| UnsafeCell::new(MaybeUninit::uninit())),
+        head: AtomicUsize::new(0),
+        tail: AtomicUsize::new(0),
+    });
+    (
+        Producer {
+            shared: Arc::clone(&shared),
+        },
+        Consumer { shared },
+    )
+}
+
+impl<T, const N: usize> Shared<T, N> {
+    fn len(&self) -> usize {
+        let tail = self.tail.load(Ordering::Acquire);
+        let head = self.head.load(Ordering::Acquire);
+        distance::<N>(head, tail)
+    }
+}
+
+// How far count b is ahead of a, counting round 0..2N
+fn distance<const N: usize>(a: usize, b: usize) -> usize {
+    if b >= a {
+        b - a
+    } else {
+        b + 2 * N - a
+    }
+}
+
+fn next<const N: usize>(count: usize) -> usize {
+    if count + 1 == 2 * N {
+        0
+    } else {
+        count + 1
+    }
+}
+
+impl<T, const N: usize> Producer<T, N> {
+    // Adds value at the back, or hands it back if the queue is full
+    pub fn push(&mut self, value: T) -> Result<(), T> {
+        // Relaxed: nobody else writes tail
+        let tail = self.shared.tail.load(Ordering::Relaxed);
+        // Acquire pairs with pop's Release, the consumer is done with every slot before head
+        let head = self.shared.head.load(Ordering::Acquire);
+        if distance::<N>(head, tail) == N {
+            return Err(value);
+        }
+
+        // SAFETY: the slot is outside head..tail, so the consumer won't touch it until we move
+        // tail past it below
+        self.shared.buf[tail % N].with_mut(|slot| unsafe { (*slot).write(value) });
+        // Release: the write above is visible to anyone who sees the new tail
+        self.shared.tail.store(next::<N>(tail), Ordering::Release);
+        Ok(())
+    }
+
+    // Values waiting to be popped, the consumer may be taking them as you look
+    pub fn len(&self) -> usize {
+        self.shared.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.len() == N
+    }
+}
+
+impl<T, const N: usize> Consumer<T, N> {
+    // Takes out the oldest value, None if there's nothing there yet
+    pub fn pop(&mut self) -> Option<T> {
+        // Relaxed: nobody else writes head
+        let head = self.shared.head.load(Ordering::Relaxed);
+        // Acquire pairs with push's Release, every slot before tail has been written
+        let tail = self.shared.tail.load(Ordering::Acquire);
+        if head == tail {
+            return None;
+        }
+
+        // SAFETY: the slot is inside head..tail so it holds a value, and the producer won't touch
+        // it until we move head past it below
+        let value =
+            self.shared.buf[head % N].with_mut(|slot| unsafe { (*slot).assume_init_read() });
+        // Release: we've finished reading the slot before the producer can see it's free
+        self.shared.head.store(next::<N>(head), Ordering::Release);
+        Some(value)
+    }
+
+    // Values waiting to be popped, the producer may be adding more as you look
+    pub fn len(&self) -> usize {
+        self.shared.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+// Once both ends are gone nobody else can be using the buffer, so whatever was never popped
+// gets dropped here
+impl<T, const N: usize> Drop for Shared<T, N> {
+    fn drop(&mut self) {
+        let head = self.head.load(Ordering::Relaxed);
+        let tail = self.tail.load(Ordering::Relaxed);
+        let mut i = head;
+        while i != tail {
+            // SAFETY: head..tail are the slots holding values
+            self.buf[i % N].with_mut(|slot| unsafe { (*slot).assume_init_drop() });
+            i = next::<N>(i);
+        }
+    }
+}
+
+// The UnsafeCells stop the compiler from working this out for itself
+// Values cross from the producer's thread to the consumer's, so T: Send is all that's needed
+unsafe impl<T: Send, const N: usize> Sync for Shared<T, N> {}
+
+impl<T, const N: usize> fmt::Debug for Producer<T, N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Producer").finish_non_exhaustive()
+    }
+}
+
+impl<T, const N: usize> fmt::Debug for Consumer<T, N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Consumer").finish_non_exhaustive()
+    }
+}
+
+#[cfg(all(test, not(loom)))]
+mod testing {
+    use std::rc::Rc;
+    use std::thread;
+
+    use super::*;
+
+    #[test]
+    fn fills_and_empties() {
+        let (mut tx, mut rx) = channel::<_, 3>();
+        assert_eq!(rx.pop(), None);
+        for i in 0..3 {
+            tx.push(i).unwrap();
+        }
+        assert!(tx.is_full());
+        assert_eq!(tx.push(3), Err(3));
+        assert_eq!(rx.pop(), Some(0));
+        tx.push(3).unwrap();
+        assert_eq!(rx.len(), 3);
+        assert_eq!(
+            std::iter::from_fn(|| rx.pop()).collect::<Vec<_>>(),
+            [1, 2, 3]
+        );
+        assert!(tx.is_empty() && rx.is_empty());
+    }
+
+    fn wraps<const N: usize>() {
+        let (mut tx, mut rx) = channel::<_, N>();
+        // start both counters just short of where they wrap
+        let start = 2 * N - 1;
+        tx.shared.head.store(start, Ordering::Relaxed);
+        tx.shared.tail.store(start, Ordering::Relaxed);
+        for round in 0..10 {
+            for i in 0..N {
+                tx.push(Box::new(round * N + i)).unwrap();
+            }
+            assert!(tx.is_full());
+            for i in 0..N {
+                assert_eq!(rx.pop().as_deref(), Some(&(round * N + i)));
+            }
+            assert!(rx.is_empty());
+        }
+        // and whatever's left at the end is dropped once
+        tx.push(Box::new(0)).unwrap();
+    }
+
+    #[test]
+    fn counters_can_wrap() {
+        wraps::<1>();
+        wraps::<3>();
+        wraps::<4>();
+    }
+
+    #[test]
+    fn unpopped_values_are_dropped() {
+        let counter = Rc::new(());
+        let (mut tx, mut rx) = channel::<_, 8>();
+        for _ in 0..5 {
+            tx.push(Rc::clone(&counter)).unwrap();
+        }
+        drop(rx.pop());
+        drop(tx);
+        assert_eq!(Rc::strong_count(&counter), 1 + 4);
+        drop(rx);
+        assert_eq!(Rc::strong_count(&counter), 1);
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn across_threads() {
+        const COUNT: u64 = 200_000;
+        let (mut tx, mut rx) = channel::<u64, 64>();
+        let producer = thread::spawn(move || {
+            for i in 0..COUNT {
+                let mut value = i;
+                while let Err(back) = tx.push(value) {
+                    value = back;
+                    thread::yield_now();
+                }
+            }
+        });
+
+        // everything arrives, once, in order
+        let mut expected = 0;
+        while expected < COUNT {
+            match rx.pop() {
+                Some(value) => {
+                    assert_eq!(value, expected);
+                    expected += 1;
+                }
+                None => thread::yield_now(),
+            }
+        }
+        producer.join().unwrap();
+        assert_eq!(rx.pop(), None);
+    }
+}
+
+// Model checked with loom, which runs the closure under every interleaving of the threads (and
+// every weak memory behaviour the orderings allow):
+//  RUSTFLAGS="--cfg loom" cargo test --release --lib spsc
+#[cfg(all(test, loom))]
+mod loom_testing {
+    use super::*;
+
+    #[test]
+    fn values_arrive_in_order() {
+        loom::model(|| {
+            let (mut tx, mut rx) = channel::<_, 2>();
+            let producer = loom::thread::spawn(move || {
+                for i in 0..3 {
+                    let mut value = i;
+                    while let Err(back) = tx.push(value) {
+                        value = back;
+                        loom::thread::yield_now();
+                    }
+                }
+            });
+
+            let mut received = Vec::new();
+            while received.len() < 3 {
+                match rx.pop() {
+                    Some(value) => received.push(value),
+                    None => loom::thread::yield_now(),
+                }
+            }
+            assert_eq!(received, [0, 1, 2]);
+            producer.join().unwrap();
+        });
+    }
+
+    #[test]
+    fn dropping_both_ends_frees_everything() {
+        loom::model(|| {
+            let (mut tx, mut rx) = channel::<_, 2>();
+            let producer = loom::thread::spawn(move || {
+                let _ = tx.push(String::from("a"));
+                let _ = tx.push(String::from("b"));
+            });
+            drop(rx.pop());
+            drop(rx);
+            producer.join().unwrap();
+        });
+    }
+}
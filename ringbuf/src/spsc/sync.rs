@@ -0,0 +1,31 @@
+// The queue's synchronisation primitives, swapped for loom's model-checked versions when
+// building with --cfg loom
+
+#[cfg(loom)]
+pub(super) use loom::{
+    cell::UnsafeCell,
+    sync::atomic::{AtomicUsize, Ordering},
+    sync::Arc,
+};
+
+#[cfg(not(loom))]
+pub(super) use std::{
+    sync::atomic::{AtomicUsize, Ordering},
+    sync::Arc,
+};
+
+// std's UnsafeCell with loom's interface, where every access goes through a closure so loom can
+// check it doesn't race
+#[cfg(not(loom))]
+pub(super) struct UnsafeCell<T>(std::cell::UnsafeCell<T>);
+
+#[cfg(not(loom))]
+impl<T> UnsafeCell<T> {
+    pub(super) fn new(value: T) -> Self {
+        UnsafeCell(std::cell::UnsafeCell::new(value))
+    }
+
+    pub(super) fn with_mut<R>(&self, f: impl FnOnce(*mut T) -> R) -> R {
+        f(self.0.get())
+    }
+}
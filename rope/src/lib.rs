@@ -0,0 +1,3 @@
+mod rope;
+
+pub use rope::{Chunks, Rope};
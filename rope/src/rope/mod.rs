@@ -0,0 +1,352 @@
+mod node;
+
+use std::fmt;
+use std::ops::{Bound, RangeBounds};
+use std::sync::Arc;
+
+use node::Node;
+
+// A string for big texts that get edited a lot, like an editor's buffer
+//
+// A String keeps its bytes in one block, so inserting or deleting anywhere but the end moves
+// everything after it: O(n) per edit, which on a few megabytes is a lot per keystroke. A rope
+// keeps the text in small pieces at the leaves of a balanced tree:
+//
+//                 (11 bytes)
+//              /             \
+//        (6 bytes)          "world"
+//        /       \
+//     "Hel"     "lo "
+//
+// and each branch remembers how many bytes (and chars) are below it, so finding position i is a
+// walk down from the root, and inserting or deleting is cutting the tree there and gluing it
+// back together, O(log n) whatever the size of the text.
+//
+// Positions are byte offsets, like String's, and have to fall on char boundaries. char_to_byte
+// and byte_to_char convert to and from char positions, also in O(log n).
+#[derive(Clone)]
+pub struct Rope {
+    root: Arc<Node>,
+}
+
+impl Rope {
+    pub fn new() -> Self {
+        Rope {
+            root: node::empty(),
+        }
+    }
+
+    pub fn len_bytes(&self) -> usize {
+        self.root.bytes()
+    }
+
+    pub fn len_chars(&self) -> usize {
+        self.root.chars()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len_bytes() == 0
+    }
+
+    // Panics if at is past the end or inside a char, like String::insert_str
+    pub fn insert(&mut self, at: usize, text: &str) {
+        self.assert_char_boundary(at);
+        if text.is_empty() {
+            return;
+        }
+        if node::insert_in_leaf(&mut self.root, at, text) {
+            return;
+        }
+        let (before, after) = node::split(&self.root, at);
+        self.root = node::join(node::join(before, node::build(text)), after);
+    }
+
+    // Panics if the range is out of bounds or cuts through a char
+    pub fn remove(&mut self, range: impl RangeBounds<usize>) {
+        let (start, end) = self.bounds(range);
+        let (before, rest) = node::split(&self.root, start);
+        let (_, after) = node::split(&rest, end - start);
+        self.root = node::join(before, after);
+    }
+
+    // A copy of part of the text, which shares everything but the cut edges with this one
+    pub fn slice(&self, range: impl RangeBounds<usize>) -> Rope {
+        let (start, end) = self.bounds(range);
+        let (_, rest) = node::split(&self.root, start);
+        let (middle, _) = node::split(&rest, end - start);
+        Rope { root: middle }
+    }
+
+    // Moves everything from at on into a new rope
+    pub fn split_off(&mut self, at: usize) -> Rope {
+        self.assert_char_boundary(at);
+        let (before, after) = node::split(&self.root, at);
+        self.root = before;
+        Rope { root: after }
+    }
+
+    pub fn append(&mut self, other: Rope) {
+        self.root = node::join(Arc::clone(&self.root), other.root);
+    }
+
+    pub fn byte(&self, at: usize) -> u8 {
+        assert!(at < self.len_bytes(), "byte {at} is past the end");
+        let (text, offset) = node::leaf_at(&self.root, at);
+        text.as_bytes()[offset]
+    }
+
+    // The char at char position `at`
+    pub fn char(&self, at: usize) -> char {
+        assert!(at < self.len_chars(), "char {at} is past the end");
+        let (text, offset) = node::leaf_at(&self.root, self.char_to_byte(at));
+        text[offset..].chars().next().expect("a char starts here")
+    }
+
+    // Panics if at is past the end (at == len_chars is the end, len_bytes)
+    pub fn char_to_byte(&self, at: usize) -> usize {
+        assert!(at <= self.len_chars(), "char {at} is past the end");
+        node::char_to_byte(&self.root, at)
+    }
+
+    // Panics if at is past the end or inside a char
+    pub fn byte_to_char(&self, at: usize) -> usize {
+        self.assert_char_boundary(at);
+        node::byte_to_char(&self.root, at)
+    }
+
+    pub fn is_char_boundary(&self, at: usize) -> bool {
+        if at > self.len_bytes() {
+            return false;
+        }
+        // leaves are cut between chars, so only a leaf's own text can tell
+        let (text, offset) = node::leaf_at(&self.root, at);
+        text.is_char_boundary(offset)
+    }
+
+    // The text in pieces, in order, without copying it
+    pub fn chunks(&self) -> Chunks<'_> {
+        Chunks {
+            stack: vec![&self.root],
+        }
+    }
+
+    pub fn chars(&self) -> impl Iterator<Item = char> + '_ {
+        self.chunks().flat_map(str::chars)
+    }
+
+    pub fn bytes(&self) -> impl Iterator<Item = u8> + '_ {
+        self.chunks().flat_map(str::bytes)
+    }
+
+    fn bounds(&self, range: impl RangeBounds<usize>) -> (usize, usize) {
+        let start = match range.start_bound() {
+            Bound::Included(&start) => start,
+            Bound::Excluded(&start) => start + 1,
+            Bound::Unbounded => 0,
+        };
+        let end = match range.end_bound() {
+            Bound::Included(&end) => end + 1,
+            Bound::Excluded(&end) => end,
+            Bound::Unbounded => self.len_bytes(),
+        };
+        assert!(start <= end, "range starts at {start} but ends at {end}");
+        self.assert_char_boundary(start);
+        self.assert_char_boundary(end);
+        (start, end)
+    }
+
+    fn assert_char_boundary(&self, at: usize) {
+        assert!(
+            at <= self.len_bytes(),
+            "byte {at} is past the end ({})",
+            self.len_bytes()
+        );
+        assert!(self.is_char_boundary(at), "byte {at} is inside a char");
+    }
+}
+
+impl Default for Rope {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl From<&str> for Rope {
+    fn from(text: &str) -> Self {
+        Rope {
+            root: node::build(text),
+        }
+    }
+}
+
+impl From<String> for Rope {
+    fn from(text: String) -> Self {
+        Rope::from(text.as_str())
+    }
+}
+
+impl fmt::Display for Rope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.chunks().try_for_each(|chunk| f.write_str(chunk))
+    }
+}
+
+impl fmt::Debug for Rope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.chunks()).finish()
+    }
+}
+
+// Equal text, however it happens to be cut up
+impl PartialEq for Rope {
+    fn eq(&self, other: &Rope) -> bool {
+        self.len_bytes() == other.len_bytes() && self.bytes().eq(other.bytes())
+    }
+}
+
+impl Eq for Rope {}
+
+impl PartialEq<str> for Rope {
+    fn eq(&self, other: &str) -> bool {
+        self.len_bytes() == other.len() && self.bytes().eq(other.bytes())
+    }
+}
+
+impl PartialEq<&str> for Rope {
+    fn eq(&self, other: &&str) -> bool {
+        self == *other
+    }
+}
+
+// The leaves left to right: a stack of the subtrees still to visit, right sides pushed first
+pub struct Chunks<'a> {
+    stack: Vec<&'a Node>,
+}
+
+impl<'a> Iterator for Chunks<'a> {
+    type Item = &'a str;
+
+    fn next(&mut self) -> Option<&'a str> {
+        while let Some(node) = self.stack.pop() {
+            match node {
+                Node::Leaf { text, .. } if text.is_empty() => {}
+                Node::Leaf { text, .. } => return Some(text),
+                Node::Branch { left, right, .. } => {
+                    self.stack.push(right);
+                    self.stack.push(left);
+                }
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod testing {
+    use super::*;
+
+    // Checks every branch's sums and balance, and returns the height
+    fn check(node: &Node) -> u8 {
+        match node {
+            Node::Leaf { text, chars } => {
+                assert!(text.len() <= node::MAX_LEAF);
+                assert_eq!(*chars, text.chars().count());
+                0
+            }
+            Node::Branch {
+                left,
+                right,
+                bytes,
+                chars,
+                height,
+            } => {
+                let (l, r) = (check(left), check(right));
+                assert!(l.abs_diff(r) <= 1, "heights {l} and {r} under one branch");
+                assert_eq!(*height, l.max(r) + 1);
+                assert_eq!(*bytes, left.bytes() + right.bytes());
+                assert_eq!(*chars, left.chars() + right.chars());
+                *height
+            }
+        }
+    }
+
+    // Text with one, two, three and four byte chars in it
+    const SAMPLE: &str = "a é € 😀 ";
+
+    #[test]
+    fn agrees_with_string() {
+        let mut rope = Rope::new();
+        let mut model = String::new();
+        let mut x: u64 = 11;
+        for _ in 0..3000 {
+            x = x.wrapping_mul(6364136223846793005).wrapping_add(1);
+            let chars = model.chars().count();
+            let at = model.char_indices().nth((x >> 33) as usize % (chars + 1));
+            let at = at.map_or(model.len(), |(i, _)| i);
+            if (x >> 20).is_multiple_of(3) && at < model.len() {
+                let end = model[at..].char_indices().nth((x >> 40) as usize % 20);
+                let end = end.map_or(model.len(), |(i, _)| at + i);
+                rope.remove(at..end);
+                model.replace_range(at..end, "");
+            } else {
+                let len = (x >> 24) as usize % 30;
+                let text: String = SAMPLE.chars().cycle().skip(len % 8).take(len).collect();
+                rope.insert(at, &text);
+                model.insert_str(at, &text);
+            }
+            assert_eq!(rope.len_bytes(), model.len());
+            assert_eq!(rope.len_chars(), model.chars().count());
+        }
+
+        check(&rope.root);
+        assert_eq!(rope.to_string(), model);
+        assert!(rope.chars().eq(model.chars()));
+        for (c, (b, ch)) in model.char_indices().enumerate() {
+            assert_eq!(rope.char_to_byte(c), b);
+            assert_eq!(rope.byte_to_char(b), c);
+            assert_eq!(rope.char(c), ch);
+        }
+        assert_eq!(rope.char_to_byte(rope.len_chars()), model.len());
+    }
+
+    #[test]
+    fn slices_share_and_dont_disturb() {
+        let text: String = SAMPLE.repeat(40);
+        let mut rope = Rope::from(text.as_str());
+        check(&rope.root);
+
+        let start = rope.char_to_byte(13);
+        let end = rope.char_to_byte(101);
+        let slice = rope.slice(start..end);
+        check(&slice.root);
+        assert_eq!(slice, &text[start..end]);
+
+        // editing either one leaves the other alone
+        rope.insert(0, "front ");
+        rope.remove(6 + start..);
+        assert_eq!(slice, &text[start..end]);
+        assert_eq!(rope, format!("front {}", &text[..start]).as_str());
+
+        let mut tail = rope.split_off(6);
+        assert_eq!(rope, "front ");
+        tail.append(slice);
+        check(&tail.root);
+        assert_eq!(tail, &text[..end]);
+    }
+
+    #[test]
+    fn chunks_cover_the_text_in_order() {
+        let text = "the quick brown fox jumps over the lazy dog".repeat(10);
+        let rope = Rope::from(text.as_str());
+        assert!(rope.chunks().all(|chunk| !chunk.is_empty()));
+        assert_eq!(rope.chunks().collect::<String>(), text);
+        assert_eq!(Rope::new().chunks().count(), 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "inside a char")]
+    fn positions_must_be_char_boundaries() {
+        let mut rope = Rope::from("naïve");
+        rope.insert(3, "x");
+    }
+}
@@ -0,0 +1,319 @@
+// The tree under a Rope: an AVL tree with the text in its leaves, in order, and every branch
+// knowing how many bytes and chars are under it
+//
+// Everything is built out of two operations, both O(log n):
+//  - join(l, r) glues two trees together. If one is much taller, walk down the taller one's
+//    near edge until the heights match, make a branch there, and rotate on the way back up
+//    wherever that left a node more than one level lopsided.
+//  - split(t, at) cuts a tree in two. Walk down to the leaf holding `at`, cut the leaf, and on
+//    the way back up join each half with the subtrees that were hanging off the path.
+// The joins while splitting are between trees whose heights go up step by step, so their costs
+// telescope to O(log n) overall rather than O(log^2 n).
+//
+// Nodes are shared (Arc) and never changed once another tree can see them, so cloning a rope is
+// one reference count and a slice only copies the nodes along the cuts.
+
+use std::sync::Arc;
+
+// Bytes per leaf. Smaller leaves make small edits cheaper, bigger ones make walking the text
+// cheaper; tests use tiny leaves so a few hundred bytes of text make a deep tree.
+pub(super) const MAX_LEAF: usize = if cfg!(test) { 8 } else { 1024 };
+
+#[derive(Debug, Clone)]
+pub(super) enum Node {
+    Leaf {
+        text: String,
+        chars: usize,
+    },
+    Branch {
+        left: Arc<Node>,
+        right: Arc<Node>,
+        bytes: usize,
+        chars: usize,
+        height: u8,
+    },
+}
+
+impl Node {
+    pub(super) fn bytes(&self) -> usize {
+        match self {
+            Node::Leaf { text, .. } => text.len(),
+            Node::Branch { bytes, .. } => *bytes,
+        }
+    }
+
+    pub(super) fn chars(&self) -> usize {
+        match self {
+            Node::Leaf { chars, .. } | Node::Branch { chars, .. } => *chars,
+        }
+    }
+
+    pub(super) fn height(&self) -> u8 {
+        match self {
+            Node::Leaf { .. } => 0,
+            Node::Branch { height, .. } => *height,
+        }
+    }
+}
+
+pub(super) fn leaf(text: &str) -> Arc<Node> {
+    Arc::new(Node::Leaf {
+        text: text.to_string(),
+        chars: text.chars().count(),
+    })
+}
+
+pub(super) fn empty() -> Arc<Node> {
+    leaf("")
+}
+
+fn branch(left: Arc<Node>, right: Arc<Node>) -> Arc<Node> {
+    Arc::new(Node::Branch {
+        bytes: left.bytes() + right.bytes(),
+        chars: left.chars() + right.chars(),
+        height: left.height().max(right.height()) + 1,
+        left,
+        right,
+    })
+}
+
+// A balanced tree over text, cut into leaves at char boundaries
+pub(super) fn build(text: &str) -> Arc<Node> {
+    let mut leaves = Vec::with_capacity(text.len() / MAX_LEAF + 1);
+    let mut start = 0;
+    while start < text.len() {
+        let mut end = (start + MAX_LEAF).min(text.len());
+        while !text.is_char_boundary(end) {
+            end -= 1;
+        }
+        leaves.push(leaf(&text[start..end]));
+        start = end;
+    }
+    match leaves.is_empty() {
+        true => empty(),
+        false => halves(&leaves),
+    }
+}
+
+// Halving keeps the two sides of every branch within one level of each other
+fn halves(leaves: &[Arc<Node>]) -> Arc<Node> {
+    match leaves {
+        [only] => Arc::clone(only),
+        _ => {
+            let (left, right) = leaves.split_at(leaves.len() / 2);
+            branch(halves(left), halves(right))
+        }
+    }
+}
+
+pub(super) fn join(left: Arc<Node>, right: Arc<Node>) -> Arc<Node> {
+    if left.bytes() == 0 {
+        return right;
+    }
+    if right.bytes() == 0 {
+        return left;
+    }
+    // two small leaves become one, so lots of little edits don't leave lots of little leaves
+    if let (Node::Leaf { text: l, .. }, Node::Leaf { text: r, .. }) = (&*left, &*right) {
+        if l.len() + r.len() <= MAX_LEAF {
+            return leaf(&[l.as_str(), r].concat());
+        }
+    }
+
+    match (&*left, &*right) {
+        (
+            Node::Branch {
+                left: ll,
+                right: lr,
+                ..
+            },
+            _,
+        ) if left.height() > right.height() + 1 => {
+            balance(Arc::clone(ll), join(Arc::clone(lr), right))
+        }
+        (
+            _,
+            Node::Branch {
+                left: rl,
+                right: rr,
+                ..
+            },
+        ) if right.height() > left.height() + 1 => {
+            balance(join(left, Arc::clone(rl)), Arc::clone(rr))
+        }
+        _ => branch(left, right),
+    }
+}
+
+// A branch over two trees up to two levels apart, rotated back into shape if they are
+//
+//   too tall on the left, and the left's left is the taller side: one rotation
+//
+//         .                  ll
+//       ll   r     ->      x    .
+//      x  lr                  lr  r
+//
+//   the left's right is the taller side: two, its middle comes up to the top
+//
+//         .                    lr
+//       ll   r     ->       .      .
+//      x  lr              x  lrl  lrr  r
+//        lrl lrr
+fn balance(left: Arc<Node>, right: Arc<Node>) -> Arc<Node> {
+    if left.height() > right.height() + 1 {
+        let Node::Branch {
+            left: ll,
+            right: lr,
+            ..
+        } = &*left
+        else {
+            unreachable!("a taller tree is a branch")
+        };
+        if ll.height() >= lr.height() {
+            return branch(Arc::clone(ll), branch(Arc::clone(lr), right));
+        }
+        let Node::Branch {
+            left: lrl,
+            right: lrr,
+            ..
+        } = &**lr
+        else {
+            unreachable!("a taller tree is a branch")
+        };
+        return branch(
+            branch(Arc::clone(ll), Arc::clone(lrl)),
+            branch(Arc::clone(lrr), right),
+        );
+    }
+    if right.height() > left.height() + 1 {
+        let Node::Branch {
+            left: rl,
+            right: rr,
+            ..
+        } = &*right
+        else {
+            unreachable!("a taller tree is a branch")
+        };
+        if rr.height() >= rl.height() {
+            return branch(branch(left, Arc::clone(rl)), Arc::clone(rr));
+        }
+        let Node::Branch {
+            left: rll,
+            right: rlr,
+            ..
+        } = &**rl
+        else {
+            unreachable!("a taller tree is a branch")
+        };
+        return branch(
+            branch(left, Arc::clone(rll)),
+            branch(Arc::clone(rlr), Arc::clone(rr)),
+        );
+    }
+    branch(left, right)
+}
+
+// The bytes before `at` and the bytes from it on, at must be a char boundary
+pub(super) fn split(node: &Arc<Node>, at: usize) -> (Arc<Node>, Arc<Node>) {
+    if at == 0 {
+        return (empty(), Arc::clone(node));
+    }
+    if at == node.bytes() {
+        return (Arc::clone(node), empty());
+    }
+    match &**node {
+        Node::Leaf { text, .. } => (leaf(&text[..at]), leaf(&text[at..])),
+        Node::Branch { left, right, .. } if at <= left.bytes() => {
+            let (before, after) = split(left, at);
+            (before, join(after, Arc::clone(right)))
+        }
+        Node::Branch { left, right, .. } => {
+            let (before, after) = split(right, at - left.bytes());
+            (join(Arc::clone(left), before), after)
+        }
+    }
+}
+
+// Puts a little text straight into the leaf it lands in, if there's room there
+// That's the common case when typing, and it leaves the shape of the tree alone. Nodes nobody
+// else can see are changed in place, shared ones are copied first.
+pub(super) fn insert_in_leaf(node: &mut Arc<Node>, at: usize, text: &str) -> bool {
+    if let Node::Leaf { text: existing, .. } = &**node {
+        if existing.len() + text.len() > MAX_LEAF {
+            return false;
+        }
+    }
+    match Arc::make_mut(node) {
+        Node::Leaf {
+            text: existing,
+            chars,
+        } => {
+            existing.insert_str(at, text);
+            *chars += text.chars().count();
+            true
+        }
+        Node::Branch {
+            left,
+            right,
+            bytes,
+            chars,
+            ..
+        } => {
+            let fits = match at <= left.bytes() {
+                true => insert_in_leaf(left, at, text),
+                false => insert_in_leaf(right, at - left.bytes(), text),
+            };
+            if fits {
+                *bytes += text.len();
+                *chars += text.chars().count();
+            }
+            fits
+        }
+    }
+}
+
+// The leaf holding byte `at` and where in it, the last leaf for at == len
+pub(super) fn leaf_at(mut node: &Node, mut at: usize) -> (&str, usize) {
+    loop {
+        match node {
+            Node::Leaf { text, .. } => return (text, at),
+            Node::Branch { left, .. } if at < left.bytes() => node = left,
+            Node::Branch { left, right, .. } => {
+                at -= left.bytes();
+                node = right;
+            }
+        }
+    }
+}
+
+pub(super) fn char_to_byte(mut node: &Node, mut at: usize) -> usize {
+    let mut before = 0;
+    loop {
+        match node {
+            Node::Leaf { text, .. } => {
+                return before + text.char_indices().nth(at).map_or(text.len(), |(i, _)| i);
+            }
+            Node::Branch { left, .. } if at < left.chars() => node = left,
+            Node::Branch { left, right, .. } => {
+                at -= left.chars();
+                before += left.bytes();
+                node = right;
+            }
+        }
+    }
+}
+
+pub(super) fn byte_to_char(mut node: &Node, mut at: usize) -> usize {
+    let mut before = 0;
+    loop {
+        match node {
+            Node::Leaf { text, .. } => return before + text[..at].chars().count(),
+            Node::Branch { left, .. } if at < left.bytes() => node = left,
+            Node::Branch { left, right, .. } => {
+                at -= left.bytes();
+                before += left.chars();
+                node = right;
+            }
+        }
+    }
+}
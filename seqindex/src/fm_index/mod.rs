@@ -0,0 +1,265 @@
+use succinct::{BitVec, RankSelect};
+
+use crate::suffix_array::build;
+
+// Rows between occurrence checkpoints
+const OCC_STEP: usize = 64;
+const DEFAULT_SAMPLE_STEP: usize = 32;
+
+// An FM-index: a compressed stand-in for a suffix array, built on the Burrows-Wheeler transform
+//
+// Add a sentinel ($, smaller than everything) to the end of the text and sort all its rotations.
+// The last column is the BWT, and the rows are in suffix array order:
+//
+//   row  rotation   SA         F = first column, L = last column (the BWT)
+//   0    $banana    6
+//   1    a$banan    5          L = annb$aa
+//   2    ana$ban    3
+//   3    anana$b    1          The k-th "a" in F and the k-th "a" in L are the same character of
+//   4    banana$    0          the text. So from any row, the row whose rotation starts one
+//   5    na$bana    4          character earlier is C[c] + occ(c, row), where c = L[row], C[c]
+//   6    nana$ba    2          counts the characters smaller than c and occ(c, row) the c's in
+//                              L before row. That's the LF mapping.
+//
+// count uses it backwards over the pattern: start with every row, and for each character c,
+// from the last, narrow the range to the rows that start with c followed by what's matched so
+// far. That's O(m) for a pattern of length m, with no binary search and no text kept at all.
+//
+// occ is a table of counts every OCC_STEP rows plus a short scan of L. locate needs text
+// positions, which the BWT doesn't have, so the suffix array is kept for every row whose position
+// is a multiple of the sample step and other rows LF their way back to one of those.
+pub struct FmIndex {
+    len: usize,
+    // byte -> symbol, 0 for bytes that aren't in the text (symbol 0 is the sentinel)
+    // A text can have all 256 bytes in it, so with the sentinel there are up to 257 symbols and
+    // they don't fit in a u8.
+    codes: [u16; 256],
+    symbols: usize,
+    // the BWT as symbols
+    bwt: Vec<u16>,
+    // c[s] = how many symbols in the text (sentinel included) are smaller than s
+    c: Vec<usize>,
+    // occ[block * symbols + s] = how many times s appears in bwt[..block * OCC_STEP]
+    occ: Vec<u32>,
+    sample_step: usize,
+    // a bit per row, set if its position was sampled, so a row's sample is samples[rank1(row)]
+    sampled: RankSelect,
+    samples: Vec<u32>,
+}
+
+impl FmIndex {
+    pub fn new(text: &[u8]) -> Self {
+        Self::with_sample_step(text, DEFAULT_SAMPLE_STEP)
+    }
+
+    // A bigger sample step keeps fewer positions (n / step of them) but locate walks further
+    // (up to step - 1 LF steps per hit)
+    pub fn with_sample_step(text: &[u8], sample_step: usize) -> Self {
+        assert!(sample_step > 0, "sample step must be positive");
+
+        let mut present = [false; 256];
+        for &byte in text {
+            present[byte as usize] = true;
+        }
+        let mut codes = [0u16; 256];
+        let mut symbols = 1;
+        for byte in 0..256 {
+            if present[byte] {
+                codes[byte] = symbols;
+                symbols += 1;
+            }
+        }
+        let symbols = usize::from(symbols);
+
+        let mut coded: Vec<u16> = text.iter().map(|&byte| codes[byte as usize]).collect();
+        coded.push(0);
+        let sa = build(&coded);
+        let rows = sa.len();
+
+        let bwt: Vec<u16> = sa
+            .iter()
+            .map(|&pos| match pos {
+                0 => 0,
+                pos => coded[pos as usize - 1],
+            })
+            .collect();
+
+        let mut c = vec![0; symbols + 1];
+        for &s in &coded {
+            c[s as usize + 1] += 1;
+        }
+        for s in 1..=symbols {
+            c[s] += c[s - 1];
+        }
+
+        let mut occ = Vec::with_capacity((rows / OCC_STEP + 1) * symbols);
+        let mut counts = vec![0u32; symbols];
+        for (row, &s) in bwt.iter().enumerate() {
+            if row.is_multiple_of(OCC_STEP) {
+                occ.extend_from_slice(&counts);
+            }
+            counts[s as usize] += 1;
+        }
+        if rows.is_multiple_of(OCC_STEP) {
+            occ.extend_from_slice(&counts);
+        }
+
+        let mut sampled = BitVec::zeros(rows);
+        let mut samples = Vec::with_capacity(rows / sample_step + 1);
+        for (row, &pos) in sa.iter().enumerate() {
+            if (pos as usize).is_multiple_of(sample_step) {
+                sampled.set(row, true);
+                samples.push(pos);
+            }
+        }
+
+        FmIndex {
+            len: text.len(),
+            codes,
+            symbols,
+            bwt,
+            c,
+            occ,
+            sample_step,
+            sampled: RankSelect::new(sampled),
+            samples,
+        }
+    }
+
+    // Length of the indexed text
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn sample_step(&self) -> usize {
+        self.sample_step
+    }
+
+    pub fn count(&self, pattern: &[u8]) -> usize {
+        let (lo, hi) = self.rows(pattern);
+        hi - lo
+    }
+
+    // Every position pattern starts at, in text order
+    pub fn locate(&self, pattern: &[u8]) -> Vec<usize> {
+        let (lo, hi) = self.rows(pattern);
+        let mut positions: Vec<usize> = (lo..hi).map(|row| self.position(row)).collect();
+        positions.sort_unstable();
+        positions
+    }
+
+    // Backward search, the rows whose rotations start with pattern
+    fn rows(&self, pattern: &[u8]) -> (usize, usize) {
+        // row 0 is the sentinel's own rotation, the only row an empty pattern shouldn't match
+        if pattern.is_empty() {
+            return (1, self.bwt.len());
+        }
+        let (mut lo, mut hi) = (0, self.bwt.len());
+        for &byte in pattern.iter().rev() {
+            let s = self.codes[byte as usize];
+            if s == 0 {
+                return (0, 0);
+            }
+            lo = self.c[s as usize] + self.occ(s, lo);
+            hi = self.c[s as usize] + self.occ(s, hi);
+            if lo >= hi {
+                return (0, 0);
+            }
+        }
+        (lo, hi)
+    }
+
+    // How many times s appears in bwt[..row]
+    fn occ(&self, s: u16, row: usize) -> usize {
+        let block = row / OCC_STEP;
+        let before = self.occ[block * self.symbols + s as usize] as usize;
+        before
+            + self.bwt[block * OCC_STEP..row]
+                .iter()
+                .filter(|&&x| x == s)
+                .count()
+    }
+
+    // The text position of a row: LF back to a sampled row, then add the steps taken
+    fn position(&self, mut row: usize) -> usize {
+        let mut steps = 0;
+        while !self.sampled.get(row) {
+            let s = self.bwt[row];
+            row = self.c[s as usize] + self.occ(s, row);
+            steps += 1;
+        }
+        self.samples[self.sampled.rank1(row)] as usize + steps
+    }
+}
+
+#[cfg(test)]
+mod testing {
+    use super::*;
+    use crate::suffix_array::{random_text, SuffixArray};
+
+    #[test]
+    fn banana() {
+        let fm = FmIndex::new(b"banana");
+        assert_eq!(fm.bwt, [1, 3, 3, 2, 0, 1, 1]);
+        assert_eq!(fm.count(b"ana"), 2);
+        assert_eq!(fm.locate(b"ana"), [1, 3]);
+        assert_eq!(fm.locate(b"a"), [1, 3, 5]);
+        assert_eq!(fm.count(b"nab"), 0);
+        assert_eq!(fm.count(b"x"), 0);
+        assert_eq!(fm.count(b""), 6);
+        assert_eq!(FmIndex::new(b"").count(b"a"), 0);
+    }
+
+    #[test]
+    fn every_byte_value() {
+        let text: Vec<u8> = (0..=255).chain([255, 255, 0]).collect();
+        let fm = FmIndex::new(&text);
+        assert_eq!(fm.symbols, 257);
+        assert_eq!(fm.count(&[255]), 3);
+        assert_eq!(fm.locate(&[255]), [255, 256, 257]);
+        assert_eq!(fm.locate(&[0]), [0, 258]);
+        assert_eq!(fm.locate(&[254, 255, 255]), [254]);
+        assert_eq!(fm.count(&[]), text.len());
+    }
+
+    #[test]
+    fn agrees_with_suffix_array() {
+        for (seed, step, alphabet) in [
+            (1, 1, &b"ACGT"[..]),
+            (2, 7, b"ACGTN"),
+            (3, 32, b"ACGT"),
+            (4, 5, b"ab"),
+        ] {
+            let text = random_text(seed, 20_000, alphabet);
+            let sa = SuffixArray::new(&text);
+            let fm = FmIndex::with_sample_step(&text, step);
+            for len in 1..12 {
+                for start in (0..text.len() - len).step_by(1009) {
+                    let pattern = &text[start..start + len];
+                    assert_eq!(fm.count(pattern), sa.count(pattern));
+                    assert_eq!(fm.locate(pattern), sa.locate(pattern));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn indexes_word_like_text() {
+        // lowercase lines, a bigger alphabet than DNA's
+        let text = random_text(6, 50_000, b"abcdefghijklmnopqrstuvwxyz\n");
+        let sa = SuffixArray::new(&text);
+        let fm = FmIndex::new(&text);
+        for pattern in ["the", "ing\n", "\nz", "qu", "xyzzy", "a"] {
+            assert_eq!(fm.count(pattern.as_bytes()), sa.count(pattern.as_bytes()));
+            assert_eq!(
+                fm.locate(pattern.as_bytes()),
+                sa.locate(pattern.as_bytes()),
+                "{pattern:?}"
+            );
+        }
+    }
+}
@@ -0,0 +1,5 @@
+mod fm_index;
+mod suffix_array;
+
+pub use fm_index::FmIndex;
+pub use suffix_array::SuffixArray;
@@ -0,0 +1,198 @@
+// Every suffix of a text, sorted, stored as the positions they start at
+//
+//   text: banana             sorted suffixes    position
+//                            a                  5
+//                            ana                3
+//                            anana              1
+//                            banana             0
+//                            na                 4
+//                            nana               2
+//
+// Every occurrence of a pattern is the start of a suffix that begins with it, and sorting puts
+// all of those next to each other, so finding a pattern is two binary searches for where that
+// run of suffixes starts and ends: O(m log n) for a pattern of length m, however many hits.
+//
+// Positions are u32, which is plenty for one human chromosome (the longest is ~250M bases) and
+// half the size of usize.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SuffixArray {
+    text: Vec<u8>,
+    sa: Vec<u32>,
+}
+
+impl SuffixArray {
+    pub fn new(text: &[u8]) -> Self {
+        SuffixArray {
+            text: text.to_vec(),
+            sa: build(text),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.sa.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sa.is_empty()
+    }
+
+    pub fn text(&self) -> &[u8] {
+        &self.text
+    }
+
+    // Suffix start positions, in sorted suffix order
+    pub fn as_slice(&self) -> &[u32] {
+        &self.sa
+    }
+
+    // The run of the array whose suffixes start with pattern
+    // The positions in it are in suffix order, not text order.
+    pub fn find(&self, pattern: &[u8]) -> &[u32] {
+        // a suffix shorter than the pattern that matches as far as it goes sorts before it
+        let prefix = |pos: u32| {
+            let suffix = &self.text[pos as usize..];
+            &suffix[..suffix.len().min(pattern.len())]
+        };
+        let start = self.sa.partition_point(|&pos| prefix(pos) < pattern);
+        let end = start + self.sa[start..].partition_point(|&pos| prefix(pos) == pattern);
+        &self.sa[start..end]
+    }
+
+    pub fn count(&self, pattern: &[u8]) -> usize {
+        self.find(pattern).len()
+    }
+
+    // Every position pattern starts at, in text order
+    pub fn locate(&self, pattern: &[u8]) -> Vec<usize> {
+        let mut positions: Vec<usize> = self.find(pattern).iter().map(|&p| p as usize).collect();
+        positions.sort_unstable();
+        positions
+    }
+}
+
+// Prefix doubling: sort the suffixes by their first 1, 2, 4, 8, ... characters
+// rank[i] is where suffix i's first k characters place it among everyone's first k characters
+// (ties share a rank). The first 2k characters of suffix i are its first k followed by the first
+// k of suffix i + k, so ordering by the pair (rank[i], rank[i + k]) sorts by 2k characters
+// without looking at the text again. Once every rank is different the order is final. Each round
+// is a sort, so it's O(n log^2 n), slower than the linear time algorithms (SA-IS) but short.
+// The text is bytes for SuffixArray, and the FM-index's u16 symbols.
+pub(crate) fn build<T: Copy + Into<u32>>(text: &[T]) -> Vec<u32> {
+    assert!(
+        text.len() < u32::MAX as usize,
+        "text too long for u32 positions"
+    );
+    let n = text.len();
+    let mut sa: Vec<u32> = (0..n as u32).collect();
+    // ranks start at 1 so 0 can mean "past the end", which sorts first like a shorter suffix does
+    let mut rank: Vec<u32> = text.iter().map(|&c| c.into() + 1).collect();
+    let mut next_rank = vec![0; n];
+
+    let mut k = 1;
+    loop {
+        let key = |i: u32| {
+            let i = i as usize;
+            (rank[i], rank.get(i + k).copied().unwrap_or(0))
+        };
+        sa.sort_unstable_by_key(|&i| key(i));
+
+        let mut r = 1;
+        for w in 0..n {
+            if w > 0 && key(sa[w - 1]) != key(sa[w]) {
+                r += 1;
+            }
+            next_rank[sa[w] as usize] = r;
+        }
+        std::mem::swap(&mut rank, &mut next_rank);
+        if r as usize == n || k >= n {
+            break;
+        }
+        k *= 2;
+    }
+    sa
+}
+
+// Compares suffixes the slow way, for checking
+#[cfg(test)]
+pub(crate) fn naive(text: &[u8]) -> Vec<u32> {
+    let mut sa: Vec<u32> = (0..text.len() as u32).collect();
+    sa.sort_by(|&a, &b| text[a as usize..].cmp(&text[b as usize..]));
+    sa
+}
+
+// Used by the tests of both indexes, 0..n random picks from an alphabet
+#[cfg(test)]
+pub(crate) fn random_text(seed: u64, n: usize, alphabet: &[u8]) -> Vec<u8> {
+    let mut x = seed;
+    (0..n)
+        .map(|_| {
+            x = x.wrapping_mul(6364136223846793005).wrapping_add(1);
+            alphabet[(x >> 33) as usize % alphabet.len()]
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod testing {
+    use std::cmp::Ordering;
+
+    use super::*;
+
+    fn naive_locate(text: &[u8], pattern: &[u8]) -> Vec<usize> {
+        (0..=text.len().saturating_sub(pattern.len()))
+            .filter(|&i| text[i..].starts_with(pattern))
+            .collect()
+    }
+
+    #[test]
+    fn banana() {
+        let sa = SuffixArray::new(b"banana");
+        assert_eq!(sa.as_slice(), [5, 3, 1, 0, 4, 2]);
+        assert_eq!(sa.count(b"ana"), 2);
+        assert_eq!(sa.locate(b"ana"), [1, 3]);
+        assert_eq!(sa.locate(b"nab"), Vec::<usize>::new());
+        assert_eq!(sa.count(b""), 6);
+        assert_eq!(sa.count(b"bananas"), 0);
+        assert!(SuffixArray::new(b"").is_empty());
+    }
+
+    #[test]
+    fn agrees_with_naive_sort() {
+        for (seed, alphabet) in [
+            (1, &b"ACGT"[..]),
+            (2, b"ab"),
+            (3, b"a"),
+            (4, b"acegikmoqsuwy"),
+        ] {
+            for n in [1, 2, 3, 10, 100, 1000] {
+                let text = random_text(seed, n, alphabet);
+                assert_eq!(build(&text), naive(&text));
+            }
+        }
+    }
+
+    #[test]
+    fn finds_every_occurrence() {
+        let text = random_text(5, 5000, b"ACGT");
+        let sa = SuffixArray::new(&text);
+        for len in 1..8 {
+            for start in (0..text.len() - len).step_by(397) {
+                let pattern = &text[start..start + len];
+                assert_eq!(sa.locate(pattern), naive_locate(&text, pattern));
+            }
+        }
+        assert_eq!(sa.locate(b"ACGTN"), Vec::<usize>::new());
+    }
+
+    #[test]
+    fn sorts_by_full_suffix() {
+        // equal prefixes that only differ far in
+        let text = b"aaaaaaaaaaaaaaaaab";
+        let sa = SuffixArray::new(text);
+        assert_eq!(sa.as_slice(), naive(text).as_slice());
+        assert!(sa
+            .as_slice()
+            .windows(2)
+            .all(|w| { text[w[0] as usize..].cmp(&text[w[1] as usize..]) == Ordering::Less }));
+    }
+}
@@ -0,0 +1,105 @@
+// Rank and select throughput on a 16M bit vector at a few densities, and Elias-Fano lookups
+//  cargo bench --bench rank_select
+// Queries are random positions, so past L2 most of the time goes to cache misses: rank is one miss
+// into the block counts and one into the bits, select adds a binary search over blocks.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use succinct::{BitVec, EliasFano, RankSelect};
+
+const BITS: usize = 1 << 24;
+const QUERIES: usize = 10_000;
+// ones per thousand bits
+const DENSITIES: [u64; 3] = [10, 500, 990];
+
+fn lcg(seed: u64) -> impl FnMut() -> u64 {
+    let mut x = seed;
+    move || {
+        x = x.wrapping_mul(6364136223846793005).wrapping_add(1);
+        x >> 16
+    }
+}
+
+fn bits(per_mille: u64) -> RankSelect {
+    let mut next = lcg(per_mille);
+    (0..BITS)
+        .map(|_| next() % 1000 < per_mille)
+        .collect::<BitVec>()
+        .into()
+}
+
+fn queries(n: usize, below: usize) -> Vec<usize> {
+    let mut next = lcg(99);
+    (0..n).map(|_| next() as usize % below.max(1)).collect()
+}
+
+fn rank(c: &mut Criterion) {
+    let mut group = c.benchmark_group("rank1");
+    group.throughput(Throughput::Elements(QUERIES as u64));
+    for per_mille in DENSITIES {
+        let rs = bits(per_mille);
+        let qs = queries(QUERIES, rs.len());
+        group.bench_with_input(BenchmarkId::from_parameter(per_mille), &qs, |b, qs| {
+            b.iter(|| qs.iter().map(|&i| rs.rank1(i)).sum::<usize>())
+        });
+    }
+    group.finish();
+}
+
+fn select(c: &mut Criterion) {
+    let mut group = c.benchmark_group("select");
+    group.throughput(Throughput::Elements(QUERIES as u64));
+    for per_mille in DENSITIES {
+        let rs = bits(per_mille);
+        let ones = queries(QUERIES, rs.count_ones());
+        group.bench_with_input(BenchmarkId::new("select1", per_mille), &ones, |b, qs| {
+            b.iter(|| qs.iter().map(|&k| rs.select1(k).unwrap()).sum::<usize>())
+        });
+        let zeros = queries(QUERIES, rs.count_zeros());
+        group.bench_with_input(BenchmarkId::new("select0", per_mille), &zeros, |b, qs| {
+            b.iter(|| qs.iter().map(|&k| rs.select0(k).unwrap()).sum::<usize>())
+        });
+    }
+    group.finish();
+}
+
+fn elias_fano(c: &mut Criterion) {
+    let mut group = c.benchmark_group("elias_fano");
+    group.throughput(Throughput::Elements(QUERIES as u64));
+    let mut next = lcg(5);
+    let mut value = 0;
+    let values: Vec<u64> = (0..1 << 20)
+        .map(|_| {
+            value += next() % 64;
+            value
+        })
+        .collect();
+    let ef = EliasFano::new(&values);
+    let indexes = queries(QUERIES, values.len());
+    group.bench_function("get", |b| {
+        b.iter(|| indexes.iter().map(|&i| ef.get(i).unwrap()).sum::<u64>())
+    });
+    let targets: Vec<u64> = queries(QUERIES, value as usize)
+        .into_iter()
+        .map(|x| x as u64)
+        .collect();
+    group.bench_function("next_geq", |b| {
+        b.iter(|| {
+            targets
+                .iter()
+                .map(|&x| ef.next_geq(x).map_or(0, |(i, _)| i))
+                .sum::<usize>()
+        })
+    });
+    group.bench_function("slice partition_point", |b| {
+        b.iter(|| {
+            targets
+                .iter()
+                .map(|&x| values.partition_point(|&v| v < x))
+                .sum::<usize>()
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, rank, select, elias_fano);
+criterion_main!(benches);
@@ -0,0 +1,254 @@
+// A non-decreasing sequence of integers in about 2 + log2(u / n) bits each (Elias-Fano)
+//
+// For n values below u, each value is split into its low l = floor(log2(u / n)) bits and the
+// rest, its high part. Low parts are stored as they are, packed l bits each. High parts are
+// non-decreasing, so they're stored as gaps in unary: value i sets bit high(i) + i in a bit
+// vector, and each zero before it is one step up in high part.
+//
+//   values 3 4 7 13 14 15 21 43, u = 44, n = 8 -> l = 2
+//   high:  0 1 1 3 3 3 5 10         low: 3 0 3 1 2 3 1 3
+//   upper: 1 0 1 1 0 0 1 1 1 0 0 1 0 0 0 0 0 1 0 0
+//          ^   ^ ^     ^ ^ ^     ^           ^ one per value, at high + i
+//
+// The upper bits hold n ones and u >> l zeros, which is fewer than 2n since 2^l > u / 2n, so the
+// whole thing is under 3 + l bits per value.
+// get(i) is a select1 in the upper bits, next_geq(x) a select0 to jump to x's high part and a
+// short scan from there.
+
+use std::iter::FusedIterator;
+
+use crate::{BitVec, RankSelect};
+
+#[derive(Debug, Clone)]
+pub struct EliasFano {
+    len: usize,
+    low_bits: u32,
+    // len * low_bits bits, value i's low part at bit i * low_bits
+    lows: Vec<u64>,
+    upper: RankSelect,
+}
+
+impl EliasFano {
+    // Panics if values isn't sorted
+    pub fn new(values: &[u64]) -> Self {
+        assert!(
+            values.windows(2).all(|pair| pair[0] <= pair[1]),
+            "Elias-Fano needs non-decreasing values"
+        );
+        let len = values.len();
+        let universe = values.last().map_or(0, |&max| max + 1);
+        let low_bits = if len == 0 || universe <= len as u64 {
+            0
+        } else {
+            (universe / len as u64).ilog2()
+        };
+
+        let mut lows = vec![0u64; (len * low_bits as usize).div_ceil(64)];
+        let mut upper = BitVec::zeros(len + (universe >> low_bits) as usize + 1);
+        for (i, &value) in values.iter().enumerate() {
+            write_bits(&mut lows, i * low_bits as usize, low_bits, value);
+            upper.set((value >> low_bits) as usize + i, true);
+        }
+
+        EliasFano {
+            len,
+            low_bits,
+            lows,
+            upper: RankSelect::new(upper),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn get(&self, i: usize) -> Option<u64> {
+        let one = self.upper.select1(i)?;
+        Some(self.value(i, one))
+    }
+
+    // The first value >= x and its index
+    pub fn next_geq(&self, x: u64) -> Option<(usize, u64)> {
+        let high = (x >> self.low_bits) as usize;
+        // the upper bits for high part `high` start after the high-th zero
+        let start = match high {
+            0 => 0,
+            _ => self.upper.select0(high - 1)? + 1,
+        };
+        // everything before start has a smaller high part, so smaller value
+        let mut i = start - high;
+        let mut one = start;
+        while i < self.len {
+            while !self.upper.get(one) {
+                one += 1;
+            }
+            let value = self.value(i, one);
+            if value >= x {
+                return Some((i, value));
+            }
+            i += 1;
+            one += 1;
+        }
+        None
+    }
+
+    // How many values are < x
+    pub fn rank(&self, x: u64) -> usize {
+        self.next_geq(x).map_or(self.len, |(i, _)| i)
+    }
+
+    pub fn iter(&self) -> EliasFanoIter<'_> {
+        EliasFanoIter {
+            ef: self,
+            index: 0,
+            one: 0,
+        }
+    }
+
+    pub fn size_in_bytes(&self) -> usize {
+        self.lows.len() * 8 + self.upper.size_in_bytes()
+    }
+
+    // value i, whose one in the upper bits is at position one
+    fn value(&self, i: usize, one: usize) -> u64 {
+        let high = (one - i) as u64;
+        high << self.low_bits | read_bits(&self.lows, i * self.low_bits as usize, self.low_bits)
+    }
+}
+
+// The low `width` bits of value at bit offset `at`, possibly straddling two words
+fn write_bits(words: &mut [u64], at: usize, width: u32, value: u64) {
+    if width == 0 {
+        return;
+    }
+    let value = value & (u64::MAX >> (64 - width));
+    let (word, shift) = (at / 64, at % 64);
+    words[word] |= value << shift;
+    if shift + width as usize > 64 {
+        words[word + 1] |= value >> (64 - shift);
+    }
+}
+
+fn read_bits(words: &[u64], at: usize, width: u32) -> u64 {
+    if width == 0 {
+        return 0;
+    }
+    let (word, shift) = (at / 64, at % 64);
+    let mut value = words[word] >> shift;
+    if shift + width as usize > 64 {
+        value |= words[word + 1] << (64 - shift);
+    }
+    value & (u64::MAX >> (64 - width))
+}
+
+// Walks the upper bits once instead of a select per value
+pub struct EliasFanoIter<'a> {
+    ef: &'a EliasFano,
+    index: usize,
+    one: usize,
+}
+
+impl Iterator for EliasFanoIter<'_> {
+    type Item = u64;
+
+    fn next(&mut self) -> Option<u64> {
+        if self.index == self.ef.len {
+            return None;
+        }
+        while !self.ef.upper.get(self.one) {
+            self.one += 1;
+        }
+        let value = self.ef.value(self.index, self.one);
+        self.index += 1;
+        self.one += 1;
+        Some(value)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let left = self.ef.len - self.index;
+        (left, Some(left))
+    }
+}
+
+impl ExactSizeIterator for EliasFanoIter<'_> {}
+
+// Once every value is read index stays at len
+impl FusedIterator for EliasFanoIter<'_> {}
+
+#[cfg(test)]
+mod testing {
+    use super::*;
+
+    fn random_sorted(seed: u64, len: usize, max_gap: u64) -> Vec<u64> {
+        let mut x = seed;
+        let mut value = 0;
+        (0..len)
+            .map(|_| {
+                x = x.wrapping_mul(6364136223846793005).wrapping_add(1);
+                value += (x >> 33) % (max_gap + 1);
+                value
+            })
+            .collect()
+    }
+
+    #[test]
+    fn example_from_the_comment() {
+        let values = [3, 4, 7, 13, 14, 15, 21, 43];
+        let ef = EliasFano::new(&values);
+        assert_eq!(ef.low_bits, 2);
+        let upper: String = ef
+            .upper
+            .bits()
+            .iter()
+            .map(|b| if b { '1' } else { '0' })
+            .collect();
+        assert_eq!(upper, "10110011100100000100");
+        assert_eq!(ef.iter().collect::<Vec<_>>(), values);
+        assert_eq!(ef.next_geq(16), Some((6, 21)));
+        assert_eq!(ef.next_geq(44), None);
+        assert_eq!(ef.rank(14), 4);
+    }
+
+    #[test]
+    fn agrees_with_a_slice() {
+        for (len, max_gap) in [
+            (0, 1),
+            (1, 0),
+            (1, 1000),
+            (100, 0),
+            (500, 3),
+            (1000, 1 << 20),
+        ] {
+            let values = random_sorted(len as u64 + max_gap, len, max_gap);
+            let ef = EliasFano::new(&values);
+            assert_eq!(ef.len(), len);
+            assert_eq!(ef.iter().collect::<Vec<_>>(), values);
+            for (i, &value) in values.iter().enumerate() {
+                assert_eq!(ef.get(i), Some(value));
+            }
+            assert_eq!(ef.get(len), None);
+
+            let max = values.last().copied().unwrap_or(0);
+            let mut x = 0;
+            while x <= max + 2 {
+                let expected = values.partition_point(|&v| v < x);
+                assert_eq!(
+                    ef.next_geq(x),
+                    values.get(expected).map(|&v| (expected, v)),
+                    "next_geq({x})"
+                );
+                x += 1 + max / 500;
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "non-decreasing")]
+    fn rejects_unsorted_values() {
+        EliasFano::new(&[1, 3, 2]);
+    }
+}
@@ -0,0 +1,7 @@
+mod bitvec;
+mod elias_fano;
+mod rank_select;
+
+pub use bitvec::BitVec;
+pub use elias_fano::{EliasFano, EliasFanoIter};
+pub use rank_select::RankSelect;
@@ -0,0 +1,295 @@
+// A bit vector that answers rank and select in constant (or close to it) time
+//
+//   rank1(i):   how many ones come before position i
+//   select1(k): the position of the k-th one (counting from 0)
+//
+// and the same for zeros. They're inverses: rank1(select1(k)) == k. Together they turn a bit
+// vector into an index: "which sampled row is this" is a rank, "where does bucket h start" is a
+// select. Plain popcounts would make each one a scan, this spends a little extra space to skip it.
+//
+// Rank is Vigna's rank9. Bits are cut into 512 bit blocks (8 words), and each block gets two words:
+// the number of ones before the block, and seven 9 bit counts of the ones before each word
+// within the block (word 0's is always 0, so it isn't stored):
+//
+//   block:     | w0 | w1 | w2 | w3 | w4 | w5 | w6 | w7 |
+//   absolute:  ones before w0
+//   relative:  ones from w0 to before w1, w2, ... w7    (each <= 448, fits in 9 bits)
+//
+// rank1(i) = absolute + relative[word] + popcount(the bits of i's word below i), two lookups and
+// a popcount. That's 128 bits per 512, 25% on top of the bits themselves.
+//
+// Select has no such neat trick. Every SELECT_SAMPLE-th one remembers which block it's in, which
+// narrows the search for the k-th one to the blocks between two samples, binary searched on their
+// absolute counts. Then the relative counts pick the word, and the word is scanned.
+// Zeros get their own samples, found the same way with "zeros before" = bits before - ones before.
+
+use crate::BitVec;
+
+const BLOCK_WORDS: usize = 8;
+const BLOCK_BITS: usize = BLOCK_WORDS * 64;
+const SELECT_SAMPLE: usize = 1 << 10;
+
+#[derive(Debug, Clone)]
+pub struct RankSelect {
+    bits: BitVec,
+    // (absolute, relative) per block, plus one more block past the end so rank1(len) works
+    // without a special case
+    blocks: Vec<(u64, u64)>,
+    ones: usize,
+    // the block holding one number k * SELECT_SAMPLE, and the same for zeros
+    select1_samples: Vec<u32>,
+    select0_samples: Vec<u32>,
+}
+
+impl RankSelect {
+    pub fn new(bits: BitVec) -> Self {
+        let words = bits.words();
+        let block_count = words.len().div_ceil(BLOCK_WORDS) + 1;
+        let mut blocks = Vec::with_capacity(block_count);
+        let mut ones = 0u64;
+        for b in 0..block_count {
+            let mut relative = 0u64;
+            let mut within = 0u64;
+            for w in 0..BLOCK_WORDS {
+                if w > 0 {
+                    relative |= within << (9 * (w - 1));
+                }
+                within += words
+                    .get(b * BLOCK_WORDS + w)
+                    .map_or(0, |word| u64::from(word.count_ones()));
+            }
+            blocks.push((ones, relative));
+            ones += within;
+        }
+
+        let mut rank_select = RankSelect {
+            bits,
+            blocks,
+            ones: ones as usize,
+            select1_samples: Vec::new(),
+            select0_samples: Vec::new(),
+        };
+        rank_select.select1_samples =
+            rank_select.sample(|rs, b| rs.blocks[b].0 as usize, rank_select.ones);
+        rank_select.select0_samples = rank_select.sample(
+            |rs, b| b * BLOCK_BITS - rs.blocks[b].0 as usize,
+            rank_select.count_zeros(),
+        );
+        rank_select
+    }
+
+    // For k = 0, SELECT_SAMPLE, 2 * SELECT_SAMPLE, ... the block the k-th one (or zero) is in,
+    // the last block whose count before it is <= k
+    fn sample(&self, before: impl Fn(&Self, usize) -> usize, total: usize) -> Vec<u32> {
+        let mut samples = Vec::with_capacity(total / SELECT_SAMPLE + 1);
+        let mut block = 0;
+        for k in (0..total).step_by(SELECT_SAMPLE) {
+            while before(self, block + 1) <= k {
+                block += 1;
+            }
+            samples.push(block as u32);
+        }
+        samples
+    }
+
+    pub fn len(&self) -> usize {
+        self.bits.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bits.is_empty()
+    }
+
+    pub fn count_ones(&self) -> usize {
+        self.ones
+    }
+
+    pub fn count_zeros(&self) -> usize {
+        self.len() - self.ones
+    }
+
+    pub fn get(&self, i: usize) -> bool {
+        self.bits.get(i)
+    }
+
+    pub fn bits(&self) -> &BitVec {
+        &self.bits
+    }
+
+    // Ones in [0, i), i can be len
+    pub fn rank1(&self, i: usize) -> usize {
+        assert!(
+            i <= self.len(),
+            "rank of {i} out of range for {} bits",
+            self.len()
+        );
+        let (absolute, relative) = self.blocks[i / BLOCK_BITS];
+        let word = i / 64 % BLOCK_WORDS;
+        let mut rank = absolute as usize;
+        if word > 0 {
+            rank += (relative >> (9 * (word - 1)) & 0x1FF) as usize;
+        }
+        let below = i % 64;
+        if below > 0 {
+            rank += (self.bits.words()[i / 64] & ((1 << below) - 1)).count_ones() as usize;
+        }
+        rank
+    }
+
+    // Zeros in [0, i)
+    pub fn rank0(&self, i: usize) -> usize {
+        i - self.rank1(i)
+    }
+
+    // Position of the k-th one, None if there are k or fewer
+    pub fn select1(&self, k: usize) -> Option<usize> {
+        if k >= self.ones {
+            return None;
+        }
+        let block = self.find_block(k, &self.select1_samples, |b| self.blocks[b].0 as usize);
+        let (absolute, relative) = self.blocks[block];
+        let mut k = k - absolute as usize;
+        // the last word in the block whose count before it is <= k
+        let mut word = 0;
+        while word + 1 < BLOCK_WORDS && (relative >> (9 * word) & 0x1FF) as usize <= k {
+            word += 1;
+        }
+        if word > 0 {
+            k -= (relative >> (9 * (word - 1)) & 0x1FF) as usize;
+        }
+        let w = block * BLOCK_WORDS + word;
+        Some(w * 64 + select_in_word(self.bits.words()[w], k))
+    }
+
+    // Position of the k-th zero
+    pub fn select0(&self, k: usize) -> Option<usize> {
+        if k >= self.count_zeros() {
+            return None;
+        }
+        let zeros_before = |b: usize| b * BLOCK_BITS - self.blocks[b].0 as usize;
+        let block = self.find_block(k, &self.select0_samples, zeros_before);
+        let (absolute, relative) = self.blocks[block];
+        let mut k = k - (block * BLOCK_BITS - absolute as usize);
+        let ones_within = |word: usize| {
+            if word == 0 {
+                0
+            } else {
+                (relative >> (9 * (word - 1)) & 0x1FF) as usize
+            }
+        };
+        let mut word = 0;
+        while word + 1 < BLOCK_WORDS && (word + 1) * 64 - ones_within(word + 1) <= k {
+            word += 1;
+        }
+        k -= word * 64 - ones_within(word);
+        let w = block * BLOCK_WORDS + word;
+        // bits past len are zero in the word but aren't really there, k < count_zeros keeps
+        // the answer before them
+        Some(w * 64 + select_in_word(!self.bits.words()[w], k))
+    }
+
+    // The last block with before(block) <= k, between the samples either side of k
+    fn find_block(&self, k: usize, samples: &[u32], before: impl Fn(usize) -> usize) -> usize {
+        let sample = k / SELECT_SAMPLE;
+        let mut lo = samples[sample] as usize;
+        let mut hi = samples
+            .get(sample + 1)
+            .map_or(self.blocks.len() - 1, |&b| b as usize + 1);
+        // invariant: before(lo) <= k < before(hi)
+        while hi - lo > 1 {
+            let mid = lo + (hi - lo) / 2;
+            if before(mid) <= k {
+                lo = mid;
+            } else {
+                hi = mid;
+            }
+        }
+        lo
+    }
+
+    // Heap bytes used, bits included
+    pub fn size_in_bytes(&self) -> usize {
+        self.bits.words().len() * 8
+            + self.blocks.len() * 16
+            + (self.select1_samples.len() + self.select0_samples.len()) * 4
+    }
+}
+
+// Position of the k-th set bit of word, which has more than k
+// Clearing the lowest set bit k times leaves the one we want lowest.
+fn select_in_word(mut word: u64, k: usize) -> usize {
+    for _ in 0..k {
+        word &= word - 1;
+    }
+    word.trailing_zeros() as usize
+}
+
+impl From<BitVec> for RankSelect {
+    fn from(bits: BitVec) -> Self {
+        RankSelect::new(bits)
+    }
+}
+
+impl FromIterator<bool> for RankSelect {
+    fn from_iter<I: IntoIterator<Item = bool>>(iter: I) -> Self {
+        RankSelect::new(iter.into_iter().collect())
+    }
+}
+
+#[cfg(test)]
+mod testing {
+    use super::*;
+
+    fn random_bits(seed: u64, len: usize, per_mille: u64) -> BitVec {
+        let mut x = seed;
+        (0..len)
+            .map(|_| {
+                x = x.wrapping_mul(6364136223846793005).wrapping_add(1);
+                (x >> 33) % 1000 < per_mille
+            })
+            .collect()
+    }
+
+    fn check(bits: BitVec) {
+        let naive: Vec<bool> = bits.iter().collect();
+        let rs = RankSelect::new(bits);
+        let mut ones = 0;
+        let mut zeros = 0;
+        for (i, &bit) in naive.iter().enumerate() {
+            assert_eq!(rs.rank1(i), ones, "rank1({i})");
+            assert_eq!(rs.rank0(i), zeros, "rank0({i})");
+            if bit {
+                assert_eq!(rs.select1(ones), Some(i), "select1({ones})");
+                ones += 1;
+            } else {
+                assert_eq!(rs.select0(zeros), Some(i), "select0({zeros})");
+                zeros += 1;
+            }
+        }
+        assert_eq!(rs.rank1(naive.len()), ones);
+        assert_eq!((rs.count_ones(), rs.count_zeros()), (ones, zeros));
+        assert_eq!(rs.select1(ones), None);
+        assert_eq!(rs.select0(zeros), None);
+    }
+
+    #[test]
+    fn agrees_with_counting() {
+        for len in [0, 1, 63, 64, 65, 511, 512, 513, 1000, 4096] {
+            for per_mille in [0, 10, 500, 990, 1000] {
+                check(random_bits(len as u64 + per_mille, len, per_mille));
+            }
+        }
+    }
+
+    // long enough to need several select samples, with runs where the blocks they point at are
+    // far apart
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn agrees_with_counting_across_samples() {
+        let mut bits = random_bits(7, 100_000, 500);
+        bits.extend(std::iter::repeat_n(false, 50_000));
+        bits.extend(random_bits(8, 50_000, 3).iter());
+        bits.extend(std::iter::repeat_n(true, 30_000));
+        check(bits);
+    }
+}
@@ -0,0 +1,5 @@
+mod radix;
+mod trie;
+
+pub use radix::{RadixMap, RadixMapIter};
+pub use trie::{ByteTrie, CharTrie, CharTriePrefixIter, Trie, TriePrefixIter};
@@ -0,0 +1,359 @@
+use std::iter::FusedIterator;
+use std::mem;
+
+// A radix tree (compressed trie, PATRICIA tree) mapping byte string keys to values
+// A plain trie spends a node on every byte, even along stretches where there's nothing to choose
+// between. A radix tree collapses every chain of single-child nodes into one edge labelled with
+// all of their bytes:
+//
+//   trie for "romane", "romanus", "rubens"      radix tree for the same keys
+//
+//             r                                          root
+//            / \                                  "rom" /    \ "ubens"
+//           o   u                                  "an"  o     o (rubens)
+//           |   |                                       |
+//           m   b ...                           "e" /  \ "us"
+//           |                                      o    o
+//           a ...                            (romane)   (romanus)
+//
+// Every node has either a value or at least two children (the root is the one exception), so the
+// tree has at most 2n nodes for n keys, however long the keys are. Lookups still take one step
+// per byte, but they compare whole labels at a time instead of hopping node to node.
+//
+// Since the edges along a key's path spell out its prefixes, longest_prefix finds the longest
+// stored key that a query starts with in one walk down: that's routing table style lookup
+pub struct RadixMap<V> {
+    root: Node<V>,
+    len: usize,
+}
+
+struct Node<V> {
+    value: Option<V>,
+    // sorted by the first byte of their label, no two children share a first byte
+    children: Vec<Edge<V>>,
+}
+
+struct Edge<V> {
+    // never empty
+    label: Vec<u8>,
+    node: Node<V>,
+}
+
+// Every (key, value), in key order
+// Like TriePrefixIter: path is the key of the node we're at, stack has the edges left to visit
+// on each level of it along with how long path was before that level
+pub struct RadixMapIter<'a, V> {
+    path: Vec<u8>,
+    stack: Vec<(usize, std::slice::Iter<'a, Edge<V>>)>,
+    root_value: Option<&'a V>,
+}
+
+impl<V> Node<V> {
+    fn new(value: Option<V>) -> Self {
+        Node {
+            value,
+            children: Vec::new(),
+        }
+    }
+
+    // Where the child starting with byte is, or where it would go
+    fn child_index(&self, byte: u8) -> Result<usize, usize> {
+        self.children
+            .binary_search_by_key(&byte, |edge| edge.label[0])
+    }
+
+    // The child whose label key starts with, and the rest of key after it
+    fn step<'k>(&self, key: &'k [u8]) -> Option<(&Node<V>, &'k [u8])> {
+        let index = self.child_index(*key.first()?).ok()?;
+        let edge = &self.children[index];
+        let rest = key.strip_prefix(edge.label.as_slice())?;
+        Some((&edge.node, rest))
+    }
+}
+
+impl<V> RadixMap<V> {
+    pub fn new() -> Self {
+        RadixMap {
+            root: Node::new(None),
+            len: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    // Returns the old value if key was already in the map
+    pub fn insert(&mut self, key: impl AsRef<[u8]>, value: V) -> Option<V> {
+        let old = insert(&mut self.root, key.as_ref(), value);
+        self.len += usize::from(old.is_none());
+        old
+    }
+
+    pub fn get(&self, key: impl AsRef<[u8]>) -> Option<&V> {
+        let mut node = &self.root;
+        let mut key = key.as_ref();
+        while !key.is_empty() {
+            (node, key) = node.step(key)?;
+        }
+        node.value.as_ref()
+    }
+
+    pub fn contains_key(&self, key: impl AsRef<[u8]>) -> bool {
+        self.get(key).is_some()
+    }
+
+    // The longest stored key that key starts with (key itself counts), along with its value
+    // The matched part is returned as a slice of key
+    pub fn longest_prefix<'k>(&self, key: &'k [u8]) -> Option<(&'k [u8], &V)> {
+        let mut node = &self.root;
+        let mut rest = key;
+        let mut best = None;
+        loop {
+            if let Some(value) = &node.value {
+                best = Some((&key[..key.len() - rest.len()], value));
+            }
+            match node.step(rest) {
+                Some(next) => (node, rest) = next,
+                None => return best,
+            }
+        }
+    }
+
+    pub fn remove(&mut self, key: impl AsRef<[u8]>) -> Option<V> {
+        let removed = remove(&mut self.root, key.as_ref())?;
+        self.len -= 1;
+        Some(removed)
+    }
+
+    pub fn iter(&self) -> RadixMapIter<'_, V> {
+        RadixMapIter {
+            path: Vec::new(),
+            stack: vec![(0, self.root.children.iter())],
+            root_value: self.root.value.as_ref(),
+        }
+    }
+}
+
+fn insert<V>(node: &mut Node<V>, key: &[u8], value: V) -> Option<V> {
+    let Some(&first) = key.first() else {
+        return node.value.replace(value);
+    };
+    let index = match node.child_index(first) {
+        Ok(index) => index,
+        // nothing shares even the first byte: the whole rest of the key becomes one new edge
+        Err(index) => {
+            node.children.insert(
+                index,
+                Edge {
+                    label: key.to_vec(),
+                    node: Node::new(Some(value)),
+                },
+            );
+            return None;
+        }
+    };
+
+    let edge = &mut node.children[index];
+    let common = edge
+        .label
+        .iter()
+        .zip(key)
+        .take_while(|(a, b)| a == b)
+        .count();
+    if common < edge.label.len() {
+        // key leaves the edge partway along, so split it where they part:
+        //   "roman" + insert "rome"  =>  "rom" -> { "an", "e" }
+        // The lower half keeps the old node, the upper half gets a new node in between
+        let lower = Edge {
+            label: edge.label.split_off(common),
+            node: mem::replace(&mut edge.node, Node::new(None)),
+        };
+        edge.node.children.push(lower);
+    }
+    insert(&mut edge.node, &key[common..], value)
+}
+
+// On the way back up, any node left with no value is tidied up: with no children it's dropped,
+// with one child it's merged into that child so every chain stays compressed
+fn remove<V>(node: &mut Node<V>, key: &[u8]) -> Option<V> {
+    let Some(&first) = key.first() else {
+        return node.value.take();
+    };
+    let index = node.child_index(first).ok()?;
+    let edge = &mut node.children[index];
+    let rest = key.strip_prefix(edge.label.as_slice())?;
+    let removed = remove(&mut edge.node, rest)?;
+
+    if edge.node.value.is_none() {
+        match edge.node.children.len() {
+            0 => {
+                node.children.remove(index);
+            }
+            1 => {
+                let child = edge.node.children.pop().expect("checked the length");
+                edge.label.extend(child.label);
+                edge.node = child.node;
+            }
+            _ => {}
+        }
+    }
+    Some(removed)
+}
+
+impl<V> Default for RadixMap<V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: AsRef<[u8]>, V> FromIterator<(K, V)> for RadixMap<V> {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut map = RadixMap::new();
+        for (key, value) in iter {
+            map.insert(key, value);
+        }
+        map
+    }
+}
+
+impl<'a, V> Iterator for RadixMapIter<'a, V> {
+    type Item = (Vec<u8>, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(value) = self.root_value.take() {
+            return Some((Vec::new(), value));
+        }
+        while let Some((len, edges)) = self.stack.last_mut() {
+            let len = *len;
+            match edges.next() {
+                Some(edge) => {
+                    self.path.truncate(len);
+                    self.path.extend_from_slice(&edge.label);
+                    self.stack
+                        .push((self.path.len(), edge.node.children.iter()));
+                    if let Some(value) = &edge.node.value {
+                        return Some((self.path.clone(), value));
+                    }
+                }
+                None => {
+                    self.stack.pop();
+                }
+            }
+        }
+        None
+    }
+}
+
+// Once the stack is empty it stays empty
+impl<V> FusedIterator for RadixMapIter<'_, V> {}
+
+#[cfg(test)]
+mod testing {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    fn node_count<V>(node: &Node<V>) -> usize {
+        1 + node
+            .children
+            .iter()
+            .map(|edge| node_count(&edge.node))
+            .sum::<usize>()
+    }
+
+    #[test]
+    fn can_insert_get_and_remove() {
+        let mut map = RadixMap::new();
+        assert_eq!(None, map.insert("romane", 1));
+        assert_eq!(None, map.insert("romanus", 2));
+        assert_eq!(None, map.insert("rubens", 3));
+        assert_eq!(None, map.insert("roman", 4));
+        assert_eq!(Some(4), map.insert("roman", 40));
+        assert_eq!(4, map.len());
+
+        assert_eq!(Some(&1), map.get("romane"));
+        assert_eq!(Some(&40), map.get("roman"));
+        assert_eq!(None, map.get("rom"));
+        assert_eq!(None, map.get("romanes"));
+
+        assert_eq!(None, map.remove("rom"));
+        assert_eq!(Some(40), map.remove("roman"));
+        assert_eq!(None, map.get("roman"));
+        assert_eq!(Some(&2), map.get("romanus"));
+        assert_eq!(3, map.len());
+    }
+
+    #[test]
+    fn chains_stay_compressed() {
+        let mut map: RadixMap<()> = ["romane", "romanus", "rubens"]
+            .into_iter()
+            .map(|k| (k, ()))
+            .collect();
+        // root, "r", "oman", "e", "us", "ubens"
+        assert_eq!(6, node_count(&map.root));
+
+        // removing romanus leaves "oman" with one child, which merges into "omane"
+        map.remove("romanus");
+        assert_eq!(4, node_count(&map.root));
+        map.remove("romane");
+        map.remove("rubens");
+        assert_eq!(1, node_count(&map.root));
+        assert!(map.is_empty());
+    }
+
+    #[test]
+    fn finds_the_longest_prefix() {
+        // a tiny routing table keyed by IPv4 octets
+        let routes: RadixMap<&str> = [
+            (vec![], "default"),
+            (vec![10], "10/8"),
+            (vec![10, 1], "10.1/16"),
+            (vec![10, 1, 2], "10.1.2/24"),
+            (vec![192, 168], "192.168/16"),
+        ]
+        .into_iter()
+        .collect();
+
+        let route = |ip: [u8; 4]| routes.longest_prefix(&ip).map(|(_, name)| *name);
+        assert_eq!(Some("10.1.2/24"), route([10, 1, 2, 3]));
+        assert_eq!(Some("10.1/16"), route([10, 1, 3, 3]));
+        assert_eq!(Some("10/8"), route([10, 2, 0, 0]));
+        assert_eq!(Some("192.168/16"), route([192, 168, 0, 1]));
+        assert_eq!(Some("default"), route([8, 8, 8, 8]));
+
+        let names: RadixMap<()> = [("foo", ()), ("foobar", ())].into_iter().collect();
+        assert_eq!(
+            Some(&b"foo"[..]),
+            names.longest_prefix(b"foobaz").map(|(k, _)| k)
+        );
+        assert_eq!(None, names.longest_prefix(b"fo"));
+    }
+
+    #[test]
+    fn matches_a_btree_map() {
+        let mut map = RadixMap::new();
+        let mut model = BTreeMap::new();
+        let mut x: u64 = 1;
+        for step in 0..5_000 {
+            x = x.wrapping_mul(6364136223846793005).wrapping_add(1);
+            // short keys over a small alphabet, so they share lots of prefixes
+            let len = (x >> 60) as usize % 6;
+            let key: Vec<u8> = (0..len)
+                .map(|i| b'a' + ((x >> (20 + 3 * i)) % 3) as u8)
+                .collect();
+            if step % 3 == 0 {
+                assert_eq!(model.remove(&key), map.remove(&key));
+            } else {
+                assert_eq!(model.insert(key.clone(), step), map.insert(&key, step));
+            }
+        }
+        assert_eq!(model.len(), map.len());
+        assert!(model.iter().map(|(k, v)| (k.clone(), v)).eq(map.iter()));
+        // every node but the root holds a value or is a real branch
+        assert!(node_count(&map.root) <= 2 * map.len() + 1);
+    }
+}
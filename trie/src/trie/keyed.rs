@@ -0,0 +1,161 @@
+use super::{Trie, TriePrefixIter};
+
+// A trie over the bytes of its keys: 256 possible children per node, and keys can be any bytes
+// (not just UTF-8). A multi-byte character takes one level per byte
+pub type ByteTrie = Trie<u8>;
+
+impl ByteTrie {
+    pub fn insert_bytes(&mut self, key: impl AsRef<[u8]>) -> bool {
+        self.insert(key.as_ref().iter().copied())
+    }
+
+    pub fn contains_bytes(&self, key: impl AsRef<[u8]>) -> bool {
+        self.contains(key.as_ref().iter().copied())
+    }
+
+    pub fn remove_bytes(&mut self, key: impl AsRef<[u8]>) -> bool {
+        self.remove(key.as_ref().iter().copied())
+    }
+
+    pub fn iter_prefix_bytes(&self, prefix: impl AsRef<[u8]>) -> TriePrefixIter<'_, u8> {
+        self.iter_prefix(prefix.as_ref().iter().copied())
+    }
+}
+
+// A trie over the chars of string keys: one level per character, however many bytes it takes,
+// and what comes back out are Strings again
+#[derive(Default)]
+pub struct CharTrie {
+    trie: Trie<char>,
+}
+
+pub struct CharTriePrefixIter<'a> {
+    iter: TriePrefixIter<'a, char>,
+}
+
+impl CharTrie {
+    pub fn new() -> Self {
+        CharTrie { trie: Trie::new() }
+    }
+
+    pub fn len(&self) -> usize {
+        self.trie.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.trie.is_empty()
+    }
+
+    pub fn insert(&mut self, key: &str) -> bool {
+        self.trie.insert(key.chars())
+    }
+
+    pub fn contains(&self, key: &str) -> bool {
+        self.trie.contains(key.chars())
+    }
+
+    pub fn contains_prefix(&self, prefix: &str) -> bool {
+        self.trie.contains_prefix(prefix.chars())
+    }
+
+    pub fn remove(&mut self, key: &str) -> bool {
+        self.trie.remove(key.chars())
+    }
+
+    pub fn iter_prefix(&self, prefix: &str) -> CharTriePrefixIter<'_> {
+        CharTriePrefixIter {
+            iter: self.trie.iter_prefix(prefix.chars()),
+        }
+    }
+
+    pub fn iter(&self) -> CharTriePrefixIter<'_> {
+        self.iter_prefix("")
+    }
+}
+
+impl<'a> Extend<&'a str> for CharTrie {
+    fn extend<I: IntoIterator<Item = &'a str>>(&mut self, keys: I) {
+        for key in keys {
+            self.insert(key);
+        }
+    }
+}
+
+impl<'a> FromIterator<&'a str> for CharTrie {
+    fn from_iter<I: IntoIterator<Item = &'a str>>(keys: I) -> Self {
+        let mut trie = CharTrie::new();
+        trie.extend(keys);
+        trie
+    }
+}
+
+impl Iterator for CharTriePrefixIter<'_> {
+    type Item = String;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter.next().map(String::from_iter)
+    }
+}
+
+#[cfg(test)]
+mod testing {
+    use super::*;
+
+    #[test]
+    fn byte_trie_takes_any_bytes() {
+        let mut trie = ByteTrie::new();
+        assert!(trie.insert_bytes([0xff, 0x00]));
+        assert!(trie.insert_bytes("hé"));
+        assert!(trie.contains_bytes([0xff, 0x00]));
+        // é is two bytes, so "h" plus its first byte is a prefix but not a key
+        assert!(!trie.contains_bytes(&"hé".as_bytes()[..2]));
+        assert_eq!(
+            vec!["hé".as_bytes().to_vec()],
+            trie.iter_prefix_bytes("h").collect::<Vec<_>>()
+        );
+        assert!(trie.remove_bytes("hé"));
+        assert_eq!(1, trie.len());
+    }
+
+    #[test]
+    fn char_trie_works_with_strings() {
+        let mut trie: CharTrie = ["héllo", "hélium", "help"].into_iter().collect();
+        assert_eq!(
+            vec!["hélium", "héllo"],
+            trie.iter_prefix("hé").collect::<Vec<_>>()
+        );
+        assert!(trie.contains_prefix("hel"));
+        assert!(trie.remove("help"));
+        assert!(!trie.contains_prefix("hel"));
+        assert_eq!(2, trie.len());
+        assert_eq!(2, trie.iter().count());
+    }
+
+    #[test]
+    fn matches_a_word_list() {
+        // made up words over a few letters, so plenty of them share prefixes (and some repeat)
+        let mut x = 11_u64;
+        let words: Vec<String> = (0..5000)
+            .map(|_| {
+                x = x.wrapping_mul(6364136223846793005).wrapping_add(1);
+                let len = 1 + (x >> 61) as usize;
+                (0..len)
+                    .map(|i| b"aeiprst"[(x >> (8 * i + 3)) as usize % 7] as char)
+                    .collect()
+            })
+            .collect();
+        let trie: CharTrie = words.iter().map(String::as_str).collect();
+        let mut distinct = words.clone();
+        distinct.sort();
+        distinct.dedup();
+        assert_eq!(distinct.len(), trie.len());
+
+        let expected: Vec<&str> = distinct
+            .iter()
+            .map(String::as_str)
+            .filter(|w| w.starts_with("pre"))
+            .collect();
+        assert!(!expected.is_empty());
+        assert_eq!(expected, trie.iter_prefix("pre").collect::<Vec<_>>());
+    }
+}
@@ -0,0 +1,256 @@
+mod keyed;
+
+use std::collections::{btree_map, BTreeMap};
+use std::iter::FusedIterator;
+
+pub use keyed::{ByteTrie, CharTrie, CharTriePrefixIter};
+
+// A trie (prefix tree) stores a set of keys by sharing their common prefixes
+// Every edge is one symbol of a key, so a key is a path from the root, and a node marks whether
+// the path leading to it is a whole key (terminal) or just a prefix of longer ones:
+//
+//   insert "car", "cart", "cat"      root
+//                                     | c
+//                                     o
+//                                     | a
+//                                     o
+//                                  r / \ t
+//                    (car)  terminal o   o terminal (cat)
+//                                    | t
+//                                    o terminal (cart)
+//
+// Looking a key up takes one step per symbol no matter how many keys are stored, and every key
+// under a prefix sits in one subtree, which is what makes prefix queries cheap.
+// Trie is generic over the symbol type: ByteTrie and CharTrie wrap it for the common cases
+pub struct Trie<S> {
+    root: Node<S>,
+    len: usize,
+}
+
+// A BTreeMap keeps the children sorted by symbol, so walking the tree depth first visits keys in
+// lexicographic order. A sparse level (most of them) only pays for the children it has
+struct Node<S> {
+    terminal: bool,
+    children: BTreeMap<S, Node<S>>,
+}
+
+// Every key under a prefix, in order, as the full key (prefix included)
+// A depth first walk with an explicit stack: path is the key of the node we're at, and stack has
+// the children left to visit on each level of it
+pub struct TriePrefixIter<'a, S> {
+    path: Vec<S>,
+    stack: Vec<btree_map::Iter<'a, S, Node<S>>>,
+    // the prefix itself is a key and hasn't been returned yet
+    prefix_is_key: bool,
+}
+
+impl<S> Node<S> {
+    fn new() -> Self {
+        Node {
+            terminal: false,
+            children: BTreeMap::new(),
+        }
+    }
+}
+
+impl<S: Ord + Clone> Trie<S> {
+    pub fn new() -> Self {
+        Trie {
+            root: Node::new(),
+            len: 0,
+        }
+    }
+
+    // The number of keys stored, not the number of nodes
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    // Returns false if key was already stored
+    pub fn insert(&mut self, key: impl IntoIterator<Item = S>) -> bool {
+        let mut node = &mut self.root;
+        for symbol in key {
+            node = node.children.entry(symbol).or_insert_with(Node::new);
+        }
+        let inserted = !node.terminal;
+        node.terminal = true;
+        self.len += usize::from(inserted);
+        inserted
+    }
+
+    pub fn contains(&self, key: impl IntoIterator<Item = S>) -> bool {
+        self.find(key).is_some_and(|node| node.terminal)
+    }
+
+    // Whether any stored key starts with prefix (a key counts as its own prefix)
+    pub fn contains_prefix(&self, prefix: impl IntoIterator<Item = S>) -> bool {
+        self.find(prefix).is_some()
+    }
+
+    // Returns false if key wasn't stored
+    // Nodes that no longer lead to any key are removed too, so the trie doesn't fill up with
+    // dead branches
+    pub fn remove(&mut self, key: impl IntoIterator<Item = S>) -> bool {
+        let key: Vec<S> = key.into_iter().collect();
+        let removed = remove(&mut self.root, &key);
+        self.len -= usize::from(removed);
+        removed
+    }
+
+    pub fn iter_prefix(&self, prefix: impl IntoIterator<Item = S>) -> TriePrefixIter<'_, S> {
+        let path: Vec<S> = prefix.into_iter().collect();
+        match self.find(path.iter().cloned()) {
+            Some(node) => TriePrefixIter {
+                prefix_is_key: node.terminal,
+                stack: vec![node.children.iter()],
+                path,
+            },
+            None => TriePrefixIter {
+                path,
+                stack: Vec::new(),
+                prefix_is_key: false,
+            },
+        }
+    }
+
+    // Every key, in order
+    pub fn iter(&self) -> TriePrefixIter<'_, S> {
+        self.iter_prefix([])
+    }
+
+    // The node at the end of key's path, if the path exists
+    fn find(&self, key: impl IntoIterator<Item = S>) -> Option<&Node<S>> {
+        key.into_iter()
+            .try_fold(&self.root, |node, symbol| node.children.get(&symbol))
+    }
+}
+
+// Returns whether key was found. On the way back up every node that's neither a key nor on the
+// way to one is dropped from its parent
+// Recursion is one call per symbol of key, so it's only as deep as the key is long
+fn remove<S: Ord>(node: &mut Node<S>, key: &[S]) -> bool {
+    let Some((first, rest)) = key.split_first() else {
+        return std::mem::replace(&mut node.terminal, false);
+    };
+    let Some(child) = node.children.get_mut(first) else {
+        return false;
+    };
+    let removed = remove(child, rest);
+    if removed && !child.terminal && child.children.is_empty() {
+        node.children.remove(first);
+    }
+    removed
+}
+
+impl<S: Ord + Clone> Default for Trie<S> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<S: Ord + Clone, K: IntoIterator<Item = S>> Extend<K> for Trie<S> {
+    fn extend<I: IntoIterator<Item = K>>(&mut self, keys: I) {
+        for key in keys {
+            self.insert(key);
+        }
+    }
+}
+
+impl<S: Ord + Clone, K: IntoIterator<Item = S>> FromIterator<K> for Trie<S> {
+    fn from_iter<I: IntoIterator<Item = K>>(keys: I) -> Self {
+        let mut trie = Trie::new();
+        trie.extend(keys);
+        trie
+    }
+}
+
+impl<S: Clone> Iterator for TriePrefixIter<'_, S> {
+    type Item = Vec<S>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if std::mem::take(&mut self.prefix_is_key) {
+            return Some(self.path.clone());
+        }
+        while let Some(children) = self.stack.last_mut() {
+            match children.next() {
+                // step down into the next child
+                Some((symbol, child)) => {
+                    self.path.push(symbol.clone());
+                    self.stack.push(child.children.iter());
+                    if child.terminal {
+                        return Some(self.path.clone());
+                    }
+                }
+                // every child on this level is done, back up one level
+                None => {
+                    self.stack.pop();
+                    self.path.pop();
+                }
+            }
+        }
+        None
+    }
+}
+
+// Once the stack is empty it stays empty
+impl<S: Clone> FusedIterator for TriePrefixIter<'_, S> {}
+
+#[cfg(test)]
+mod testing {
+    use super::*;
+
+    fn keys(iter: TriePrefixIter<'_, u8>) -> Vec<String> {
+        iter.map(|key| String::from_utf8(key).unwrap()).collect()
+    }
+
+    #[test]
+    fn can_insert_and_look_up() {
+        let mut trie: Trie<u8> = Trie::new();
+        assert!(trie.insert("cart".bytes()));
+        assert!(trie.insert("car".bytes()));
+        assert!(!trie.insert("car".bytes()));
+        assert_eq!(2, trie.len());
+
+        assert!(trie.contains("car".bytes()));
+        assert!(!trie.contains("ca".bytes()));
+        assert!(trie.contains_prefix("ca".bytes()));
+        assert!(!trie.contains_prefix("cat".bytes()));
+        // the empty key is a key like any other
+        assert!(!trie.contains([]));
+        trie.insert([]);
+        assert!(trie.contains([]));
+    }
+
+    #[test]
+    fn can_iterate_a_prefix_in_order() {
+        let trie: Trie<u8> = ["cat", "car", "cart", "dog", "ca"]
+            .iter()
+            .map(|key| key.bytes())
+            .collect();
+        assert_eq!(
+            vec!["ca", "car", "cart", "cat"],
+            keys(trie.iter_prefix("ca".bytes()))
+        );
+        assert_eq!(vec!["car", "cart"], keys(trie.iter_prefix("car".bytes())));
+        assert!(keys(trie.iter_prefix("x".bytes())).is_empty());
+        assert_eq!(5, trie.iter().count());
+    }
+
+    #[test]
+    fn remove_prunes_dead_branches() {
+        let mut trie: Trie<u8> = ["car", "cart"].iter().map(|key| key.bytes()).collect();
+        assert!(!trie.remove("ca".bytes()));
+        assert!(trie.remove("cart".bytes()));
+        assert!(!trie.remove("cart".bytes()));
+        assert!(!trie.contains_prefix("cart".bytes()));
+        assert!(trie.contains("car".bytes()));
+
+        assert!(trie.remove("car".bytes()));
+        assert!(trie.is_empty());
+        assert!(trie.root.children.is_empty());
+    }
+}
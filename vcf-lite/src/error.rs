@@ -0,0 +1,46 @@
+use std::{error, fmt, io};
+
+#[derive(Debug)]
+pub enum VcfError {
+    // Reading the underlying file failed
+    Io(io::Error),
+    // The meta lines or the #CHROM line are missing or malformed, at a 1-based line
+    Header { line: u64, message: String },
+    // A record line is malformed, at a 1-based line
+    Record { line: u64, message: String },
+}
+
+impl VcfError {
+    // The line the problem is on, if it's about the contents rather than the reading
+    pub fn line(&self) -> Option<u64> {
+        match self {
+            VcfError::Io(_) => None,
+            VcfError::Header { line, .. } | VcfError::Record { line, .. } => Some(*line),
+        }
+    }
+}
+
+impl fmt::Display for VcfError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VcfError::Io(e) => write!(f, "{e}"),
+            VcfError::Header { line, message } => write!(f, "bad header on line {line}: {message}"),
+            VcfError::Record { line, message } => write!(f, "bad record on line {line}: {message}"),
+        }
+    }
+}
+
+impl error::Error for VcfError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            VcfError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for VcfError {
+    fn from(e: io::Error) -> Self {
+        VcfError::Io(e)
+    }
+}
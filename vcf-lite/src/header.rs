@@ -0,0 +1,70 @@
+use std::io::{self, Write};
+
+// The eight columns every VCF has, in order, FORMAT and the samples can follow them
+pub(crate) const FIXED_COLUMNS: [&str; 8] =
+    ["CHROM", "POS", "ID", "REF", "ALT", "QUAL", "FILTER", "INFO"];
+
+// Everything before the first record
+//
+//   ##fileformat=VCFv4.2                      meta lines, fileformat always first
+//   ##INFO=<ID=DP,Number=1,Type=Integer,...>
+//   #CHROM POS ID REF ALT QUAL FILTER INFO    the column line, tab separated
+//
+// Meta lines are kept as text without their "##", nothing here needs to understand an INFO
+// definition, only carry it through to the output. If the file has samples the column line goes
+// on with FORMAT and their names.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Header {
+    meta: Vec<String>,
+    samples: Vec<String>,
+}
+
+impl Header {
+    // A header with only a fileformat line and no samples, for writing a VCF from scratch
+    pub fn new() -> Self {
+        Header {
+            meta: vec!["fileformat=VCFv4.2".into()],
+            samples: Vec::new(),
+        }
+    }
+
+    pub(crate) fn from_parts(meta: Vec<String>, samples: Vec<String>) -> Self {
+        Header { meta, samples }
+    }
+
+    // The version after "fileformat=", like "VCFv4.2"
+    pub fn fileformat(&self) -> &str {
+        self.meta[0].strip_prefix("fileformat=").unwrap_or_default()
+    }
+
+    // The meta lines without their "##"
+    pub fn meta(&self) -> &[String] {
+        &self.meta
+    }
+
+    // Adds a meta line, like `INFO=<ID=RS,...>` for an annotation that's going to be written
+    pub fn push_meta(&mut self, line: impl Into<String>) {
+        self.meta.push(line.into());
+    }
+
+    pub fn samples(&self) -> &[String] {
+        &self.samples
+    }
+
+    pub fn write_to(&self, mut w: impl Write) -> io::Result<()> {
+        for line in &self.meta {
+            writeln!(w, "##{line}")?;
+        }
+        write!(w, "#{}", FIXED_COLUMNS.join("\t"))?;
+        if !self.samples.is_empty() {
+            write!(w, "\tFORMAT\t{}", self.samples.join("\t"))?;
+        }
+        writeln!(w)
+    }
+}
+
+impl Default for Header {
+    fn default() -> Self {
+        Header::new()
+    }
+}
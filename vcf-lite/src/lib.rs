@@ -0,0 +1,11 @@
+mod error;
+mod header;
+mod reader;
+mod record;
+mod writer;
+
+pub use error::VcfError;
+pub use header::Header;
+pub use reader::{Reader, Records};
+pub use record::Record;
+pub use writer::Writer;
@@ -0,0 +1,183 @@
+use std::io::BufRead;
+
+use crate::header::FIXED_COLUMNS;
+use crate::{Header, Record, VcfError};
+
+// Reads the header up front, then records one line at a time
+// Nothing is held but the current line, so a whole dbSNP release streams through in constant
+// memory. Blank lines are skipped and "\r\n" endings are fine.
+pub struct Reader<R> {
+    inner: R,
+    header: Header,
+    // lines read so far, so the last one read is this one
+    line: u64,
+    buf: String,
+}
+
+impl<R: BufRead> Reader<R> {
+    pub fn new(inner: R) -> Result<Self, VcfError> {
+        let mut reader = Reader {
+            inner,
+            header: Header::new(),
+            line: 0,
+            buf: String::new(),
+        };
+        reader.header = reader.read_header()?;
+        Ok(reader)
+    }
+
+    pub fn header(&self) -> &Header {
+        &self.header
+    }
+
+    // The 1-based number of the last line read, for pointing at a record that's wrong in some
+    // way only the caller knows about
+    pub fn line(&self) -> u64 {
+        self.line
+    }
+
+    // The next record, None at the end of the file
+    pub fn read_record(&mut self) -> Result<Option<Record>, VcfError> {
+        loop {
+            if !self.next_line()? {
+                return Ok(None);
+            }
+            if self.buf.is_empty() {
+                continue;
+            }
+            return Record::parse(&self.buf)
+                .map(Some)
+                .map_err(|message| VcfError::Record {
+                    line: self.line,
+                    message,
+                });
+        }
+    }
+
+    pub fn records(&mut self) -> Records<'_, R> {
+        Records {
+            reader: self,
+            done: false,
+        }
+    }
+
+    fn read_header(&mut self) -> Result<Header, VcfError> {
+        if !self.next_line()? || !self.buf.starts_with("##fileformat=VCF") {
+            return Err(self.header_error("doesn't start with ##fileformat=VCF"));
+        }
+        let mut meta = vec![self.buf[2..].to_string()];
+        loop {
+            if !self.next_line()? {
+                return Err(self.header_error("ends before the #CHROM line"));
+            }
+            if let Some(line) = self.buf.strip_prefix("##") {
+                meta.push(line.to_string());
+                continue;
+            }
+            let Some(columns) = self.buf.strip_prefix('#') else {
+                return Err(self.header_error("expected the #CHROM line"));
+            };
+            let columns: Vec<&str> = columns.split('\t').collect();
+            if columns.len() < FIXED_COLUMNS.len() || columns[..8] != FIXED_COLUMNS {
+                return Err(
+                    self.header_error(&format!("expected the columns {}", FIXED_COLUMNS.join(" ")))
+                );
+            }
+            let samples = match &columns[8..] {
+                [] => Vec::new(),
+                ["FORMAT", samples @ ..] => samples.iter().map(|s| s.to_string()).collect(),
+                _ => return Err(self.header_error("samples without a FORMAT column")),
+            };
+            return Ok(Header::from_parts(meta, samples));
+        }
+    }
+
+    // Reads a line into buf without its line ending, false at the end of the file
+    fn next_line(&mut self) -> Result<bool, VcfError> {
+        self.buf.clear();
+        if self.inner.read_line(&mut self.buf)? == 0 {
+            return Ok(false);
+        }
+        self.line += 1;
+        let len = self.buf.trim_end_matches(['\n', '\r']).len();
+        self.buf.truncate(len);
+        Ok(true)
+    }
+
+    fn header_error(&self, message: &str) -> VcfError {
+        VcfError::Header {
+            line: self.line.max(1),
+            message: message.into(),
+        }
+    }
+}
+
+// Stops after the first error, a file that's broken once is likely broken on every line after
+pub struct Records<'a, R> {
+    reader: &'a mut Reader<R>,
+    done: bool,
+}
+
+impl<R: BufRead> Iterator for Records<'_, R> {
+    type Item = Result<Record, VcfError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let next = self.reader.read_record().transpose();
+        self.done = !matches!(next, Some(Ok(_)));
+        next
+    }
+}
+
+#[cfg(test)]
+mod testing {
+    use super::*;
+
+    const VCF: &str = "##fileformat=VCFv4.1\n\
+        ##INFO=<ID=DB,Number=0,Type=Flag,Description=\"dbSNP membership\">\n\
+        #CHROM\tPOS\tID\tREF\tALT\tQUAL\tFILTER\tINFO\tFORMAT\tNA12878\n\
+        1\t10177\trs367896724\tA\tAC\t.\tPASS\tDB\tGT\t0|1\r\n\
+        \n\
+        X\t2700157\trs6\tG\tA\t.\t.\t.\tGT\t1|1\n";
+
+    #[test]
+    fn reads_header_and_records() {
+        let mut reader = Reader::new(VCF.as_bytes()).unwrap();
+        let header = reader.header();
+        assert_eq!(header.fileformat(), "VCFv4.1");
+        assert_eq!(header.meta().len(), 2);
+        assert_eq!(header.samples(), ["NA12878"]);
+
+        let records: Vec<_> = reader.records().collect::<Result<_, _>>().unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].ids, ["rs367896724"]);
+        assert_eq!(records[0].info_value("DB"), Some(""));
+        assert_eq!(records[1].chrom, "X");
+        assert_eq!(records[1].pos, 2700157);
+        assert_eq!(records[1].genotypes, ["GT", "1|1"]);
+        assert_eq!(reader.line(), 6);
+    }
+
+    #[test]
+    fn errors_say_where() {
+        let err = Reader::new("rs1\t1:100\n".as_bytes()).err().unwrap();
+        assert!(matches!(err, VcfError::Header { line: 1, .. }));
+
+        let err = Reader::new("##fileformat=VCFv4.2\n##source=x\n".as_bytes())
+            .err()
+            .unwrap();
+        assert!(matches!(err, VcfError::Header { line: 2, .. }));
+
+        let bad_columns = "##fileformat=VCFv4.2\n#CHROM\tPOS\tID\n";
+        let err = Reader::new(bad_columns.as_bytes()).err().unwrap();
+        assert!(matches!(err, VcfError::Header { line: 2, .. }));
+
+        let bad_record = VCF.replace("2700157", "27OO157");
+        let mut reader = Reader::new(bad_record.as_bytes()).unwrap();
+        let results: Vec<_> = reader.records().collect();
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[1].as_ref().unwrap_err().line(), Some(6));
+    }
+}
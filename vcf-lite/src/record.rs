@@ -0,0 +1,180 @@
+use std::fmt;
+
+// One data line
+//
+//   1  10177  rs367896724  A  AC  100  PASS  AC=2130;AF=0.425;DB
+//
+// CHROM, POS, ID, REF and ALT are split out since they're what callers look at. QUAL, FILTER,
+// INFO and anything after (FORMAT and the genotypes) are kept as they were read and written back
+// untouched, which is most of the point of a lite parser. A "." in ID or ALT is an empty list.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Record {
+    pub chrom: String,
+    // 1-based
+    pub pos: u64,
+    pub ids: Vec<String>,
+    pub reference: String,
+    pub alts: Vec<String>,
+    pub qual: String,
+    pub filter: String,
+    pub info: String,
+    // FORMAT then one column per sample, empty for a sites-only file
+    pub genotypes: Vec<String>,
+}
+
+impl Record {
+    // A record with only a locus, the rest missing ("."), to fill in before writing
+    pub fn new(chrom: impl Into<String>, pos: u64) -> Self {
+        Record {
+            chrom: chrom.into(),
+            pos,
+            ids: Vec::new(),
+            reference: "N".into(),
+            alts: Vec::new(),
+            qual: ".".into(),
+            filter: ".".into(),
+            info: ".".into(),
+            genotypes: Vec::new(),
+        }
+    }
+
+    // Splits a line without its newline, the error is a message for VcfError::Record
+    pub(crate) fn parse(line: &str) -> Result<Self, String> {
+        let mut fields = line.split('\t');
+        let mut next = |name: &str| {
+            fields
+                .next()
+                .ok_or_else(|| format!("no {name} column, expected at least 8 tab separated"))
+        };
+        let chrom = next("CHROM")?;
+        let pos = next("POS")?;
+        let ids = next("ID")?;
+        let reference = next("REF")?;
+        let alts = next("ALT")?;
+        let qual = next("QUAL")?;
+        let filter = next("FILTER")?;
+        let info = next("INFO")?;
+
+        if chrom.is_empty() {
+            return Err("empty CHROM".into());
+        }
+        let pos = pos
+            .parse()
+            .map_err(|_| format!("POS {pos:?} isn't a position"))?;
+        Ok(Record {
+            chrom: chrom.into(),
+            pos,
+            ids: split_list(ids, ';'),
+            reference: reference.into(),
+            alts: split_list(alts, ','),
+            qual: qual.into(),
+            filter: filter.into(),
+            info: info.into(),
+            genotypes: fields.map(String::from).collect(),
+        })
+    }
+
+    // The value of an INFO key: Some("") for a flag like DB, None when it isn't there
+    pub fn info_value(&self, key: &str) -> Option<&str> {
+        if self.info == "." {
+            return None;
+        }
+        self.info
+            .split(';')
+            .find_map(|entry| match entry.split_once('=') {
+                Some((k, value)) if k == key => Some(value),
+                None if entry == key => Some(""),
+                _ => None,
+            })
+    }
+
+    // Appends `key=value` to INFO, or just `key` for a flag, replacing a "." if there was one
+    // An existing entry for the key is left alone, so check info_value first to avoid doubles.
+    pub fn push_info(&mut self, key: &str, value: Option<&str>) {
+        if self.info == "." {
+            self.info.clear();
+        } else {
+            self.info.push(';');
+        }
+        self.info.push_str(key);
+        if let Some(value) = value {
+            self.info.push('=');
+            self.info.push_str(value);
+        }
+    }
+}
+
+// The line as it goes in a file, without a newline
+impl fmt::Display for Record {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}",
+            self.chrom,
+            self.pos,
+            join_list(&self.ids, ";"),
+            self.reference,
+            join_list(&self.alts, ","),
+            self.qual,
+            self.filter,
+            self.info
+        )?;
+        for column in &self.genotypes {
+            write!(f, "\t{column}")?;
+        }
+        Ok(())
+    }
+}
+
+fn split_list(field: &str, separator: char) -> Vec<String> {
+    if field == "." {
+        return Vec::new();
+    }
+    field.split(separator).map(String::from).collect()
+}
+
+fn join_list(list: &[String], separator: &str) -> String {
+    if list.is_empty() {
+        return ".".into();
+    }
+    list.join(separator)
+}
+
+#[cfg(test)]
+mod testing {
+    use super::*;
+
+    #[test]
+    fn parses_and_prints_the_same_line() {
+        let line = "1\t10177\trs367896724;rs1\tA\tAC,AT\t100\tPASS\tAC=2130;DB\tGT\t0|1";
+        let record = Record::parse(line).unwrap();
+        assert_eq!(record.chrom, "1");
+        assert_eq!(record.pos, 10177);
+        assert_eq!(record.ids, ["rs367896724", "rs1"]);
+        assert_eq!(record.alts, ["AC", "AT"]);
+        assert_eq!(record.genotypes, ["GT", "0|1"]);
+        assert_eq!(record.to_string(), line);
+
+        let missing = "X\t5\t.\tG\t.\t.\t.\t.";
+        let record = Record::parse(missing).unwrap();
+        assert!(record.ids.is_empty() && record.alts.is_empty());
+        assert_eq!(record.to_string(), missing);
+
+        assert!(Record::parse("1\t5\t.\tG").is_err());
+        assert!(Record::parse("1\tfive\t.\tG\t.\t.\t.\t.").is_err());
+    }
+
+    #[test]
+    fn info_entries() {
+        let mut record = Record::new("2", 100);
+        assert_eq!(record.to_string(), "2\t100\t.\tN\t.\t.\t.\t.");
+        assert_eq!(record.info_value("DB"), None);
+
+        record.push_info("DB", None);
+        record.push_info("RS", Some("12"));
+        assert_eq!(record.info, "DB;RS=12");
+        assert_eq!(record.info_value("DB"), Some(""));
+        assert_eq!(record.info_value("RS"), Some("12"));
+        assert_eq!(record.info_value("R"), None);
+    }
+}
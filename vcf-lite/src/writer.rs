@@ -0,0 +1,55 @@
+use std::io::{self, Write};
+
+use crate::{Header, Record};
+
+// Writes the header straight away, then one line per record
+// Wrap a file in a BufWriter first, every record is a handful of small writes.
+pub struct Writer<W: Write> {
+    inner: W,
+}
+
+impl<W: Write> Writer<W> {
+    pub fn new(mut inner: W, header: &Header) -> io::Result<Self> {
+        header.write_to(&mut inner)?;
+        Ok(Writer { inner })
+    }
+
+    pub fn write_record(&mut self, record: &Record) -> io::Result<()> {
+        writeln!(self.inner, "{record}")
+    }
+
+    // Flushes and hands back the inner writer
+    pub fn finish(mut self) -> io::Result<W> {
+        self.inner.flush()?;
+        Ok(self.inner)
+    }
+}
+
+#[cfg(test)]
+mod testing {
+    use super::*;
+    use crate::Reader;
+
+    #[test]
+    fn round_trips_through_the_reader() {
+        let mut header = Header::new();
+        header.push_meta("INFO=<ID=RS,Number=1,Type=Integer,Description=\"dbSNP id\">");
+
+        let mut writer = Writer::new(Vec::new(), &header).unwrap();
+        let mut records = Vec::new();
+        for (i, chrom) in ["1", "2", "MT"].into_iter().enumerate() {
+            let mut record = Record::new(chrom, 100 * i as u64 + 1);
+            record.ids.push(format!("rs{i}"));
+            record.push_info("RS", Some(&i.to_string()));
+            writer.write_record(&record).unwrap();
+            records.push(record);
+        }
+        let bytes = writer.finish().unwrap();
+        assert!(bytes.starts_with(b"##fileformat=VCFv4.2\n##INFO=<ID=RS"));
+
+        let mut reader = Reader::new(bytes.as_slice()).unwrap();
+        assert_eq!(reader.header(), &header);
+        let read: Vec<_> = reader.records().collect::<Result<_, _>>().unwrap();
+        assert_eq!(read, records);
+    }
+}
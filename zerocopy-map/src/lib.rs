@@ -0,0 +1,3 @@
+mod map;
+
+pub use map::{MapBuilder, MapError, MapFile, MapIter, Search, MAGIC};
@@ -0,0 +1,117 @@
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use binio::HeaderWriter;
+
+use super::header::{Checksum, Header, HEADER_SIZE};
+use super::MapError;
+
+// Writes a map from records that arrive in key order
+// Records stream straight to the file, the only state is the last key (to check the order) and
+// the running checksum. The header is reserved as zeros and filled in by finish, so a builder
+// dropped without finishing leaves a file MapFile::open rejects.
+//
+// Unlike a BTreeBuilder the same key can come more than once, MapFile::get_all finds them all.
+pub struct MapBuilder {
+    file: HeaderWriter<BufWriter<File>>,
+    key_size: usize,
+    value_size: usize,
+    len: u64,
+    last_key: Vec<u8>,
+    checksum: Checksum,
+}
+
+impl MapBuilder {
+    // Creates (or truncates) the file
+    pub fn create(
+        path: impl AsRef<Path>,
+        key_size: usize,
+        value_size: usize,
+    ) -> Result<Self, MapError> {
+        if key_size == 0 {
+            return Err(MapError::InvalidParams("keys can't be empty".into()));
+        }
+        let file = HeaderWriter::new(BufWriter::new(File::create(path)?), HEADER_SIZE)?;
+        Ok(MapBuilder {
+            file,
+            key_size,
+            value_size,
+            len: 0,
+            last_key: Vec::with_capacity(key_size),
+            checksum: Checksum::new(),
+        })
+    }
+
+    // Builds a whole map from an iterator of sorted records
+    pub fn bulk_load<K, V>(
+        path: impl AsRef<Path>,
+        key_size: usize,
+        value_size: usize,
+        records: impl IntoIterator<Item = (K, V)>,
+    ) -> Result<u64, MapError>
+    where
+        K: AsRef<[u8]>,
+        V: AsRef<[u8]>,
+    {
+        let mut builder = Self::create(path, key_size, value_size)?;
+        for (key, value) in records {
+            builder.push(key.as_ref(), value.as_ref())?;
+        }
+        builder.finish()
+    }
+
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    // Keys must not decrease (compared as bytes), so big endian integers sort numerically
+    pub fn push(&mut self, key: &[u8], value: &[u8]) -> Result<(), MapError> {
+        if key.len() != self.key_size || value.len() != self.value_size {
+            return Err(MapError::InvalidParams(format!(
+                "expected a {} byte key and {} byte value, got {} and {}",
+                self.key_size,
+                self.value_size,
+                key.len(),
+                value.len()
+            )));
+        }
+        if self.len > 0 && key < &self.last_key[..] {
+            return Err(MapError::Unsorted(self.len));
+        }
+
+        self.file.write_all(key)?;
+        self.file.write_all(value)?;
+        self.checksum.update(key);
+        self.checksum.update(value);
+        self.len += 1;
+        self.last_key.clear();
+        self.last_key.extend_from_slice(key);
+        Ok(())
+    }
+
+    // Fills in the header, returns the number of records
+    pub fn finish(self) -> Result<u64, MapError> {
+        self.finish_with_metadata(&[])
+    }
+
+    // The same with metadata for MapFile::metadata, which can be anything and any length
+    pub fn finish_with_metadata(mut self, metadata: &[u8]) -> Result<u64, MapError> {
+        self.file.write_all(metadata)?;
+        self.checksum.update(metadata);
+        let header = Header {
+            key_size: self.key_size,
+            value_size: self.value_size,
+            len: self.len,
+            checksum: self.checksum.value(),
+            metadata_len: metadata.len() as u64,
+        };
+        let file = self.file.finish(&header.to_bytes())?;
+        file.get_ref().sync_all()?;
+        Ok(self.len)
+    }
+}
@@ -0,0 +1,32 @@
+use std::{error, fmt, io};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MapError {
+    // A zero size key, or a record of the wrong size
+    InvalidParams(String),
+    // Keys have to arrive in order, this is the index of the first one that didn't
+    Unsorted(u64),
+    // A file that isn't a map, is from another version, or doesn't match its checksum
+    Corrupt(String),
+    // Reading or writing the file failed
+    Io(String),
+}
+
+impl fmt::Display for MapError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MapError::InvalidParams(msg) => write!(f, "invalid map parameters: {msg}"),
+            MapError::Unsorted(n) => write!(f, "record {n} is less than the one before it"),
+            MapError::Corrupt(msg) => write!(f, "corrupt map file: {msg}"),
+            MapError::Io(msg) => write!(f, "map i/o failed: {msg}"),
+        }
+    }
+}
+
+impl error::Error for MapError {}
+
+impl From<io::Error> for MapError {
+    fn from(e: io::Error) -> Self {
+        MapError::Io(e.to_string())
+    }
+}
@@ -0,0 +1,87 @@
+use binio::{be_u32, be_u64};
+
+use super::MapError;
+
+// A map file is a header and then every record back to back, all integers big endian
+//
+//   header:   magic | version u32 | key_size u32 | value_size u32 | len u64 | checksum u64
+//             | metadata_len u64 (zeros up to HEADER_SIZE)
+//   records:  len * (key | value), in key order
+//   metadata: metadata_len bytes
+//
+// Records are all the same size, so record i starts at HEADER_SIZE + i * (key_size + value_size)
+// and finding one is arithmetic, not a walk. The header gets a whole HEADER_SIZE bytes, most of
+// them zeros, so later versions have room to add fields without moving the records.
+//
+// Metadata is whatever the caller wants kept with the map. It goes after the records because it's
+// only known once they're all written, and version 2 added it: a version 1 map has zeros where
+// its length is, which reads as none.
+//
+// The checksum is 64 bit FNV-1a over the records and metadata. Checking it means reading the
+// whole file, so open doesn't, MapFile::verify does.
+pub const MAGIC: &[u8; 8] = b"ZCOPYMAP";
+pub const VERSION: u32 = 2;
+pub const HEADER_SIZE: usize = 64;
+const FIELDS_SIZE: usize = 8 + 4 + 4 + 4 + 8 + 8 + 8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Header {
+    pub key_size: usize,
+    pub value_size: usize,
+    pub len: u64,
+    pub checksum: u64,
+    pub metadata_len: u64,
+}
+
+impl Header {
+    pub fn to_bytes(self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(FIELDS_SIZE);
+        bytes.extend_from_slice(MAGIC);
+        bytes.extend_from_slice(&VERSION.to_be_bytes());
+        bytes.extend_from_slice(&(self.key_size as u32).to_be_bytes());
+        bytes.extend_from_slice(&(self.value_size as u32).to_be_bytes());
+        bytes.extend_from_slice(&self.len.to_be_bytes());
+        bytes.extend_from_slice(&self.checksum.to_be_bytes());
+        bytes.extend_from_slice(&self.metadata_len.to_be_bytes());
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, MapError> {
+        if bytes.len() < HEADER_SIZE || &bytes[..8] != MAGIC {
+            return Err(MapError::Corrupt("missing header".into()));
+        }
+        let version = be_u32(bytes, 8);
+        if !(1..=VERSION).contains(&version) {
+            return Err(MapError::Corrupt(format!(
+                "version {version} map, this reads up to version {VERSION}"
+            )));
+        }
+        Ok(Header {
+            key_size: be_u32(bytes, 12) as usize,
+            value_size: be_u32(bytes, 16) as usize,
+            len: be_u64(bytes, 20),
+            checksum: be_u64(bytes, 28),
+            metadata_len: be_u64(bytes, 36),
+        })
+    }
+}
+
+// FNV-1a, a byte at a time, fed as records are written and again by verify
+#[derive(Debug, Clone, Copy)]
+pub struct Checksum(u64);
+
+impl Checksum {
+    pub fn new() -> Self {
+        Checksum(0xcbf2_9ce4_8422_2325)
+    }
+
+    pub fn update(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 = (self.0 ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+
+    pub fn value(self) -> u64 {
+        self.0
+    }
+}
@@ -0,0 +1,382 @@
+mod builder;
+mod error;
+mod header;
+
+pub use builder::MapBuilder;
+pub use error::MapError;
+pub use header::MAGIC;
+
+use std::fs::File;
+use std::iter::FusedIterator;
+use std::path::Path;
+
+use memmap2::Mmap;
+
+use header::{Checksum, Header, HEADER_SIZE};
+
+// How MapFile finds a key
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Search {
+    // Halve the records left every probe, ~log2(n) probes whatever the keys are
+    #[default]
+    Binary,
+    // Guess where the key is from how far it is between the first and last keys left, like
+    // opening a dictionary near the back for "w". Keys spread evenly (rsids, say) take
+    // ~log2(log2(n)) probes, which on a file too big to cache is that many fewer page faults.
+    // Badly spread keys fall back to halving, so the worst case is about twice binary's.
+    Interpolation,
+}
+
+// A read only sorted array of fixed size records, written by MapBuilder
+// The file is memory mapped and keys and values are slices of the map, nothing is parsed or
+// copied on the way out. Only the header is checked on open; verify reads every record.
+pub struct MapFile {
+    map: Mmap,
+    header: Header,
+    len: usize,
+    record_size: usize,
+    search: Search,
+}
+
+impl MapFile {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, MapError> {
+        let file = File::open(path)?;
+        // SAFETY: the map is read only, but another process truncating or rewriting the file while
+        // it's mapped is still undefined behaviour. Maps are written once by a builder and only
+        // read after.
+        let map = unsafe { Mmap::map(&file)? };
+        let header = Header::from_bytes(&map)?;
+
+        let record_size = header.key_size + header.value_size;
+        let len = usize::try_from(header.len).ok();
+        let metadata_len = usize::try_from(header.metadata_len).ok();
+        let expected = len
+            .and_then(|len| len.checked_mul(record_size))
+            .and_then(|size| size.checked_add(HEADER_SIZE))
+            .zip(metadata_len)
+            .and_then(|(size, metadata_len)| size.checked_add(metadata_len));
+        if header.key_size == 0 || expected != Some(map.len()) {
+            return Err(MapError::Corrupt(format!(
+                "{} bytes doesn't fit the header: {header:?}",
+                map.len()
+            )));
+        }
+
+        Ok(MapFile {
+            len: header.len as usize,
+            map,
+            header,
+            record_size,
+            search: Search::default(),
+        })
+    }
+
+    pub fn with_search(mut self, search: Search) -> Self {
+        self.search = search;
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn key_size(&self) -> usize {
+        self.header.key_size
+    }
+
+    pub fn value_size(&self) -> usize {
+        self.header.value_size
+    }
+
+    // What the builder was finished with, empty if it wasn't given any
+    pub fn metadata(&self) -> &[u8] {
+        &self.map[self.map.len() - self.header.metadata_len as usize..]
+    }
+
+    // The value of the first record with this key
+    pub fn get(&self, key: &[u8]) -> Option<&[u8]> {
+        self.get_all(key).next()
+    }
+
+    // The values of every record with this key, in the order they were written
+    pub fn get_all(&self, key: &[u8]) -> impl Iterator<Item = &[u8]> + '_ {
+        let start = if key.len() == self.header.key_size {
+            self.lower_bound(key)
+        } else {
+            self.len
+        };
+        let end = (start..self.len)
+            .find(|&i| self.key(i) != key)
+            .unwrap_or(self.len);
+        (start..end).map(|i| self.record(i).1)
+    }
+
+    // Index of the first record whose key isn't less than key, len if there isn't one
+    pub fn lower_bound(&self, key: &[u8]) -> usize {
+        match self.search {
+            Search::Binary => self.partition(key, |lo, hi| lo + (hi - lo) / 2),
+            Search::Interpolation => {
+                let target = prefix(key);
+                // enough guesses for any evenly spread keys, then it's halving to the end
+                let mut guesses = 2 * (usize::BITS - self.len.leading_zeros());
+                self.partition(key, |lo, hi| {
+                    if guesses == 0 || hi - lo < 3 {
+                        return lo + (hi - lo) / 2;
+                    }
+                    guesses -= 1;
+                    let first = prefix(self.key(lo));
+                    let last = prefix(self.key(hi - 1));
+                    if target <= first {
+                        lo
+                    } else if target >= last {
+                        hi - 1
+                    } else {
+                        let span = (hi - 1 - lo) as u128;
+                        let ahead = u128::from(target - first) * span / u128::from(last - first);
+                        lo + ahead as usize
+                    }
+                })
+            }
+        }
+    }
+
+    // Record i, as (key, value)
+    pub fn record(&self, i: usize) -> (&[u8], &[u8]) {
+        let start = HEADER_SIZE + i * self.record_size;
+        let record = &self.map[start..start + self.record_size];
+        record.split_at(self.header.key_size)
+    }
+
+    pub fn iter(&self) -> MapIter<'_> {
+        MapIter { map: self, next: 0 }
+    }
+
+    // Reads every record to check the checksum and the key order
+    pub fn verify(&self) -> Result<(), MapError> {
+        let mut checksum = Checksum::new();
+        checksum.update(&self.map[HEADER_SIZE..]);
+        if checksum.value() != self.header.checksum {
+            return Err(MapError::Corrupt(format!(
+                "checksum is {:#x}, the header says {:#x}",
+                checksum.value(),
+                self.header.checksum
+            )));
+        }
+        match (1..self.len).find(|&i| self.key(i) < self.key(i - 1)) {
+            Some(i) => Err(MapError::Corrupt(format!("record {i} is out of order"))),
+            None => Ok(()),
+        }
+    }
+
+    fn key(&self, i: usize) -> &[u8] {
+        self.record(i).0
+    }
+
+    // The lower bound, narrowing [lo, hi) by comparing against the record probe picks in it
+    // Everything before lo is less than key and everything from hi on isn't, whichever record
+    // gets probed, so the search strategies only differ in how fast they get there.
+    fn partition(&self, key: &[u8], mut probe: impl FnMut(usize, usize) -> usize) -> usize {
+        let (mut lo, mut hi) = (0, self.len);
+        while lo < hi {
+            let i = probe(lo, hi);
+            if self.key(i) < key {
+                lo = i + 1;
+            } else {
+                hi = i;
+            }
+        }
+        lo
+    }
+}
+
+// The first 8 bytes of a key as a number, zero padded, for interpolating between keys
+// Byte order and number order agree, and keys that share these 8 bytes are just not told apart
+// (the guess is worse, the answer isn't).
+fn prefix(key: &[u8]) -> u64 {
+    let mut bytes = [0; 8];
+    let n = key.len().min(8);
+    bytes[..n].copy_from_slice(&key[..n]);
+    u64::from_be_bytes(bytes)
+}
+
+// (key, value) pairs borrowed from the map, in order
+pub struct MapIter<'a> {
+    map: &'a MapFile,
+    next: usize,
+}
+
+impl<'a> Iterator for MapIter<'a> {
+    type Item = (&'a [u8], &'a [u8]);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.next == self.map.len {
+            return None;
+        }
+        self.next += 1;
+        Some(self.map.record(self.next - 1))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let left = self.map.len - self.next;
+        (left, Some(left))
+    }
+}
+
+impl ExactSizeIterator for MapIter<'_> {}
+
+impl FusedIterator for MapIter<'_> {}
+
+#[cfg(test)]
+mod testing {
+    use std::fs::{self, OpenOptions};
+    use std::os::unix::fs::FileExt;
+
+    use super::*;
+
+    #[test]
+    fn searches_agree_with_a_model() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("model.map");
+
+        // sorted random keys with runs of duplicates and gaps, values counting up
+        let mut x: u64 = 7;
+        let mut keys: Vec<u32> = (0..20_000)
+            .map(|_| {
+                x = x.wrapping_mul(6364136223846793005).wrapping_add(1);
+                (x >> 40) as u32
+            })
+            .collect();
+        keys.sort_unstable();
+        let records = keys
+            .iter()
+            .enumerate()
+            .map(|(i, key)| (key.to_be_bytes(), (i as u64).to_be_bytes()));
+        assert_eq!(MapBuilder::bulk_load(&path, 4, 8, records).unwrap(), 20_000);
+
+        let binary = MapFile::open(&path).unwrap();
+        let interpolation = MapFile::open(&path)
+            .unwrap()
+            .with_search(Search::Interpolation);
+        binary.verify().unwrap();
+        assert_eq!(
+            (binary.len(), binary.key_size(), binary.value_size()),
+            (20_000, 4, 8)
+        );
+        assert!(binary
+            .iter()
+            .map(|(key, _)| key)
+            .eq(keys.iter().map(|k| k.to_be_bytes().to_vec())));
+
+        for _ in 0..5000 {
+            x = x.wrapping_mul(6364136223846793005).wrapping_add(1);
+            // half of them keys that are in the map, half anything
+            let key = if x & 1 == 0 {
+                keys[(x >> 33) as usize % keys.len()]
+            } else {
+                (x >> 40) as u32
+            };
+            let bytes = key.to_be_bytes();
+            let first = keys.partition_point(|&k| k < key);
+            let count = keys[first..].iter().take_while(|&&k| k == key).count();
+            for map in [&binary, &interpolation] {
+                assert_eq!(map.lower_bound(&bytes), first);
+                let values: Vec<u64> = map
+                    .get_all(&bytes)
+                    .map(|v| u64::from_be_bytes(v.try_into().unwrap()))
+                    .collect();
+                assert_eq!(
+                    values,
+                    (first as u64..(first + count) as u64).collect::<Vec<_>>()
+                );
+            }
+        }
+        assert_eq!(binary.get(&[0; 3]), None);
+        assert_eq!(interpolation.lower_bound(&[0xFF; 4]), binary.len());
+    }
+
+    #[test]
+    fn empty_map() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("empty.map");
+        let records: [([u8; 2], [u8; 0]); 0] = [];
+        MapBuilder::bulk_load(&path, 2, 0, records).unwrap();
+
+        let map = MapFile::open(&path)
+            .unwrap()
+            .with_search(Search::Interpolation);
+        assert!(map.is_empty());
+        assert_eq!(map.get(&[1, 2]), None);
+        assert_eq!(map.iter().next(), None);
+        map.verify().unwrap();
+    }
+
+    #[test]
+    fn keeps_metadata_after_the_records() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("meta.map");
+        let mut builder = MapBuilder::create(&path, 2, 1).unwrap();
+        for key in 0..10u16 {
+            builder.push(&key.to_be_bytes(), &[key as u8]).unwrap();
+        }
+        builder.finish_with_metadata(b"chrom\t1\t1\n").unwrap();
+
+        let map = MapFile::open(&path).unwrap();
+        assert_eq!(map.metadata(), b"chrom\t1\t1\n");
+        assert_eq!((map.len(), map.get(&[0, 9])), (10, Some(&[9][..])));
+        map.verify().unwrap();
+
+        // a version 1 map is the same with no metadata, its length is in the padding
+        MapBuilder::bulk_load(&path, 2, 1, [([0, 1], [2])]).unwrap();
+        let file = OpenOptions::new().write(true).open(&path).unwrap();
+        file.write_all_at(&1u32.to_be_bytes(), 8).unwrap();
+        let map = MapFile::open(&path).unwrap();
+        assert_eq!(
+            (map.metadata(), map.get(&[0, 1])),
+            (&[][..], Some(&[2][..]))
+        );
+    }
+
+    #[test]
+    fn rejects_bad_records_and_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("bad.map");
+
+        assert!(matches!(MapFile::open(&path), Err(MapError::Io(_))));
+        assert!(MapBuilder::create(&path, 0, 4).is_err());
+
+        let mut builder = MapBuilder::create(&path, 2, 1).unwrap();
+        builder.push(&[0, 2], &[1]).unwrap();
+        builder.push(&[0, 2], &[2]).unwrap();
+        assert_eq!(builder.push(&[0, 1], &[3]), Err(MapError::Unsorted(2)));
+        assert!(matches!(
+            builder.push(&[0, 3], &[]),
+            Err(MapError::InvalidParams(_))
+        ));
+        // never finished, so the header is still zeros
+        drop(builder);
+        assert!(matches!(MapFile::open(&path), Err(MapError::Corrupt(_))));
+
+        let mut builder = MapBuilder::create(&path, 2, 1).unwrap();
+        for key in 0..100u16 {
+            builder.push(&key.to_be_bytes(), &[7]).unwrap();
+        }
+        builder.finish().unwrap();
+        MapFile::open(&path).unwrap().verify().unwrap();
+
+        // a changed value still opens, verify notices
+        let file = OpenOptions::new().write(true).open(&path).unwrap();
+        file.write_all_at(&[8], HEADER_SIZE as u64 + 2).unwrap();
+        let map = MapFile::open(&path).unwrap();
+        assert_eq!(map.get(&[0, 0]), Some(&[8][..]));
+        assert!(matches!(map.verify(), Err(MapError::Corrupt(_))));
+
+        // a lost record doesn't open at all
+        let len = fs::metadata(&path).unwrap().len();
+        file.set_len(len - 3).unwrap();
+        assert!(matches!(MapFile::open(&path), Err(MapError::Corrupt(_))));
+    }
+}